@@ -0,0 +1,344 @@
+//! Helpers for the modules instrumented by Binaryen's Asyncify pass
+//! (`wasm-opt --asyncify`), which can unwind their stack to give the
+//! control back to the host, and rewind it later to resume where they
+//! stopped.
+use crate::env::{HostEnvInitError, WasmerEnv};
+use crate::externals::{Function, Memory};
+use crate::instance::Instance;
+use crate::module::Module;
+use crate::native::NativeFunc;
+use crate::types::Val;
+use crate::ExternType;
+use std::any::Any;
+use std::convert::TryInto;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use wasmer_engine::RuntimeError;
+
+/// The functions exported by the modules instrumented by Asyncify.
+const ASYNCIFY_EXPORTS: [&str; 5] = [
+    "asyncify_start_unwind",
+    "asyncify_stop_unwind",
+    "asyncify_start_rewind",
+    "asyncify_stop_rewind",
+    "asyncify_get_state",
+];
+
+/// The size of the header of the Asyncify data: the current and the end
+/// addresses of the saved stack.
+const DATA_HEADER_SIZE: u32 = 8;
+
+/// The state of an instance instrumented by Asyncify.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AsyncifyState {
+    /// The instance runs normally.
+    Normal,
+    /// The instance is unwinding its stack, or has unwound it until
+    /// [`Asyncify::stop_unwind`] is called.
+    Unwinding,
+    /// The instance is rewinding its stack, until
+    /// [`Asyncify::stop_rewind`] is called.
+    Rewinding,
+}
+
+type PendingFuture = Pin<Box<dyn Future<Output = Box<dyn Any + Send>> + Send>>;
+
+struct AsyncifyExports {
+    start_unwind: NativeFunc<i32>,
+    stop_unwind: NativeFunc,
+    start_rewind: NativeFunc<i32>,
+    stop_rewind: NativeFunc,
+    get_state: NativeFunc<(), i32>,
+    memory: Memory,
+}
+
+#[derive(Default)]
+struct AsyncifyInner {
+    exports: Option<AsyncifyExports>,
+    /// The buffer where the stack is saved, as its address and length
+    data: Option<(u32, u32)>,
+    /// The future the instance is suspended on
+    pending: Option<PendingFuture>,
+    /// The output of the future, returned to the instance when it rewinds
+    resumed: Option<Box<dyn Any + Send>>,
+}
+
+/// The host side of the Asyncify protocol for an instance.
+///
+/// It's the environment of the host functions which suspend the instance,
+/// with [`Asyncify::handle_async`], and is initialized with the exports of
+/// the instance when it's instantiated. The stack of the instance is saved
+/// in a buffer of its memory given to [`Asyncify::set_data`].
+///
+/// ```
+/// # use wasmer::{Asyncify, Function, Store};
+/// # let store = Store::default();
+/// fn sleep(asyncify: &Asyncify, ms: i32) -> i32 {
+///     // the first time it's called, it suspends the instance and returns a
+///     // value which is ignored; when the instance resumes, it's called again
+///     // and returns the output of the future
+///     match asyncify.handle_async(move || async move { ms * 2 }) {
+///         Ok(Some(result)) => result,
+///         _ => 0,
+///     }
+/// }
+///
+/// let asyncify = Asyncify::new();
+/// let sleep = Function::new_native_with_env(&store, asyncify.clone(), sleep);
+/// ```
+#[derive(Clone, Default)]
+pub struct Asyncify {
+    inner: Arc<Mutex<AsyncifyInner>>,
+}
+
+impl fmt::Debug for Asyncify {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inner = self.inner.lock().unwrap();
+        f.debug_struct("Asyncify")
+            .field("initialized", &inner.exports.is_some())
+            .field("data", &inner.data)
+            .field("suspended", &inner.pending.is_some())
+            .finish()
+    }
+}
+
+impl WasmerEnv for Asyncify {
+    fn init_with_instance(&mut self, instance: &Instance) -> Result<(), HostEnvInitError> {
+        let exports = &instance.exports;
+        let memory = exports
+            .iter()
+            .memories()
+            .map(|(_, memory)| memory.clone())
+            .next()
+            .ok_or_else(|| {
+                RuntimeError::new("the instance doesn't export its memory for Asyncify")
+            })?;
+        self.inner.lock().unwrap().exports = Some(AsyncifyExports {
+            start_unwind: exports.get_native_function("asyncify_start_unwind")?,
+            stop_unwind: exports.get_native_function("asyncify_stop_unwind")?,
+            start_rewind: exports.get_native_function("asyncify_start_rewind")?,
+            stop_rewind: exports.get_native_function("asyncify_stop_rewind")?,
+            get_state: exports.get_native_function("asyncify_get_state")?,
+            memory,
+        });
+        Ok(())
+    }
+}
+
+impl Asyncify {
+    /// Create the host side of the Asyncify protocol, initialized when the
+    /// host functions using it are instantiated.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether `module` is instrumented by Asyncify.
+    pub fn is_asyncified(module: &Module) -> bool {
+        ASYNCIFY_EXPORTS.iter().all(|name| {
+            module.exports().any(|export| {
+                export.name() == *name && matches!(export.ty(), ExternType::Function(_))
+            })
+        })
+    }
+
+    /// Save the stack of the instance in `len` bytes at `ptr` in its memory
+    /// when it unwinds.
+    ///
+    /// The buffer must be large enough for the locals of all the functions
+    /// being unwound, and not be used by the instance.
+    pub fn set_data(&self, ptr: u32, len: u32) -> Result<(), RuntimeError> {
+        if len <= DATA_HEADER_SIZE {
+            return Err(RuntimeError::new(format!(
+                "the Asyncify data needs more than {} bytes",
+                DATA_HEADER_SIZE
+            )));
+        }
+        self.inner.lock().unwrap().data = Some((ptr, len));
+        Ok(())
+    }
+
+    fn with_exports<T>(
+        &self,
+        f: impl FnOnce(&AsyncifyExports, (u32, u32)) -> Result<T, RuntimeError>,
+    ) -> Result<T, RuntimeError> {
+        let inner = self.inner.lock().unwrap();
+        let exports = inner
+            .exports
+            .as_ref()
+            .ok_or_else(|| RuntimeError::new("Asyncify isn't initialized with an instance"))?;
+        let data = inner
+            .data
+            .ok_or_else(|| RuntimeError::new("Asyncify has no data buffer"))?;
+        f(exports, data)
+    }
+
+    /// The state of the instance.
+    pub fn state(&self) -> Result<AsyncifyState, RuntimeError> {
+        self.with_exports(|exports, _| match exports.get_state.call()? {
+            0 => Ok(AsyncifyState::Normal),
+            1 => Ok(AsyncifyState::Unwinding),
+            2 => Ok(AsyncifyState::Rewinding),
+            state => Err(RuntimeError::new(format!(
+                "invalid Asyncify state {}",
+                state
+            ))),
+        })
+    }
+
+    /// Make the instance unwind its stack when the current host function
+    /// returns, into an empty data buffer.
+    pub fn start_unwind(&self) -> Result<(), RuntimeError> {
+        self.with_exports(|exports, (ptr, len)| {
+            write_u32(&exports.memory, ptr, ptr + DATA_HEADER_SIZE)?;
+            write_u32(&exports.memory, ptr + 4, ptr + len)?;
+            exports.start_unwind.call(ptr as i32)
+        })
+    }
+
+    /// Go back to the normal state once the export called by the host
+    /// returned after unwinding.
+    pub fn stop_unwind(&self) -> Result<(), RuntimeError> {
+        self.with_exports(|exports, _| exports.stop_unwind.call())
+    }
+
+    /// Make the instance rewind its saved stack when the export which was
+    /// unwound is called again.
+    pub fn start_rewind(&self) -> Result<(), RuntimeError> {
+        self.with_exports(|exports, (ptr, _)| exports.start_rewind.call(ptr as i32))
+    }
+
+    /// Go back to the normal state once the host function which unwound the
+    /// stack is called again by the rewinding instance.
+    pub fn stop_rewind(&self) -> Result<(), RuntimeError> {
+        self.with_exports(|exports, _| exports.stop_rewind.call())
+    }
+
+    /// A copy of the stack saved by the last unwind, to
+    /// [`restore_stack`](Self::restore_stack) it later.
+    pub fn saved_stack(&self) -> Result<Vec<u8>, RuntimeError> {
+        self.with_exports(|exports, (ptr, _)| {
+            let start = ptr + DATA_HEADER_SIZE;
+            let end = read_u32(&exports.memory, ptr)?;
+            let view = exports.memory.view::<u8>();
+            let stack = view
+                .get(start as usize..end as usize)
+                .ok_or_else(|| RuntimeError::new("the Asyncify data is out of bounds"))?;
+            Ok(stack.iter().map(|cell| cell.get()).collect())
+        })
+    }
+
+    /// Put back a stack returned by [`saved_stack`](Self::saved_stack), for
+    /// the instance to rewind it after [`start_rewind`](Self::start_rewind).
+    pub fn restore_stack(&self, stack: &[u8]) -> Result<(), RuntimeError> {
+        self.with_exports(|exports, (ptr, len)| {
+            let start = ptr + DATA_HEADER_SIZE;
+            let size: u32 = stack.len().try_into().unwrap_or(u32::MAX);
+            if size > len - DATA_HEADER_SIZE {
+                return Err(RuntimeError::new(
+                    "the stack is larger than the Asyncify data",
+                ));
+            }
+            let view = exports.memory.view::<u8>();
+            let cells = view
+                .get(start as usize..(start + size) as usize)
+                .ok_or_else(|| RuntimeError::new("the Asyncify data is out of bounds"))?;
+            for (cell, byte) in cells.iter().zip(stack) {
+                cell.set(*byte);
+            }
+            write_u32(&exports.memory, ptr, start + size)?;
+            write_u32(&exports.memory, ptr + 4, ptr + len)
+        })
+    }
+
+    /// Suspend the instance on the future created by `start`, from a host
+    /// function called by it.
+    ///
+    /// When the instance runs normally, it starts unwinding and `None` is
+    /// returned: the host function must then return, with any value. The
+    /// future is awaited by [`AsyncifiedFunction::call_async`], which
+    /// rewinds the instance, calling the host function again: `Some` of the
+    /// output of the future is then returned.
+    pub fn handle_async<T, F>(&self, start: impl FnOnce() -> F) -> Result<Option<T>, RuntimeError>
+    where
+        T: Send + 'static,
+        F: Future<Output = T> + Send + 'static,
+    {
+        match self.state()? {
+            AsyncifyState::Normal => {
+                let future = start();
+                self.inner.lock().unwrap().pending = Some(Box::pin(async move {
+                    Box::new(future.await) as Box<dyn Any + Send>
+                }));
+                self.start_unwind()?;
+                Ok(None)
+            }
+            AsyncifyState::Rewinding => {
+                self.stop_rewind()?;
+                let resumed = self.inner.lock().unwrap().resumed.take();
+                match resumed.map(|resumed| resumed.downcast::<T>()) {
+                    Some(Ok(output)) => Ok(Some(*output)),
+                    _ => Err(RuntimeError::new(
+                        "the instance resumed without the output of its future",
+                    )),
+                }
+            }
+            AsyncifyState::Unwinding => Err(RuntimeError::new(
+                "a host function was called while the instance is unwinding",
+            )),
+        }
+    }
+}
+
+fn read_u32(memory: &Memory, ptr: u32) -> Result<u32, RuntimeError> {
+    let view = memory.view::<u32>();
+    view.get(ptr as usize / 4)
+        .filter(|_| ptr % 4 == 0)
+        .map(|cell| u32::from_le(cell.get()))
+        .ok_or_else(|| RuntimeError::new("the Asyncify data is out of bounds"))
+}
+
+fn write_u32(memory: &Memory, ptr: u32, value: u32) -> Result<(), RuntimeError> {
+    let view = memory.view::<u32>();
+    view.get(ptr as usize / 4)
+        .filter(|_| ptr % 4 == 0)
+        .map(|cell| cell.set(value.to_le()))
+        .ok_or_else(|| RuntimeError::new("the Asyncify data is out of bounds"))
+}
+
+/// An exported function of an instance instrumented by Asyncify, which can
+/// be suspended by the host functions using [`Asyncify::handle_async`].
+#[derive(Clone, Debug)]
+pub struct AsyncifiedFunction {
+    function: Function,
+    asyncify: Asyncify,
+}
+
+impl AsyncifiedFunction {
+    /// Call `function` with the Asyncify protocol of `asyncify`.
+    pub fn new(function: Function, asyncify: Asyncify) -> Self {
+        Self { function, asyncify }
+    }
+
+    /// Call the function, awaiting the futures the instance is suspended
+    /// on, until it returns.
+    pub async fn call_async(&self, params: &[Val]) -> Result<Box<[Val]>, RuntimeError> {
+        loop {
+            let results = self.function.call(params)?;
+            if self.asyncify.state()? != AsyncifyState::Unwinding {
+                return Ok(results);
+            }
+            self.asyncify.stop_unwind()?;
+
+            let pending = self.asyncify.inner.lock().unwrap().pending.take();
+            let pending = pending.ok_or_else(|| {
+                RuntimeError::new("the instance unwound without a future to await")
+            })?;
+            let output = pending.await;
+            self.asyncify.inner.lock().unwrap().resumed = Some(output);
+
+            self.asyncify.start_rewind()?;
+        }
+    }
+}
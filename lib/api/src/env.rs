@@ -1,4 +1,4 @@
-use crate::{ExportError, Instance};
+use crate::{ExportError, Instance, RuntimeError};
 use thiserror::Error;
 
 /// An error while initializing the user supplied host env with the `WasmerEnv` trait.
@@ -7,6 +7,9 @@ use thiserror::Error;
 pub enum HostEnvInitError {
     /// An error occurred when accessing an export
     Export(ExportError),
+    /// An error occurred when calling into the instance, for example in
+    /// `WasmerEnv::finish`
+    Runtime(RuntimeError),
 }
 
 impl From<ExportError> for HostEnvInitError {
@@ -15,6 +18,12 @@ impl From<ExportError> for HostEnvInitError {
     }
 }
 
+impl From<RuntimeError> for HostEnvInitError {
+    fn from(other: RuntimeError) -> Self {
+        Self::Runtime(other)
+    }
+}
+
 /// Trait for initializing the environments passed to host functions after
 /// instantiation but before execution.
 ///
@@ -28,7 +37,7 @@ impl From<ExportError> for HostEnvInitError {
 /// This trait can be derived like so:
 ///
 /// ```
-/// use wasmer::{WasmerEnv, LazyInit, Memory, NativeFunc};
+/// use wasmer::{WasmerEnv, Global, LazyInit, Memory, NativeFunc};
 ///
 /// #[derive(WasmerEnv, Clone)]
 /// pub struct MyEnvWithNoInstanceData {
@@ -44,6 +53,8 @@ impl From<ExportError> for HostEnvInitError {
 ///     func: LazyInit<NativeFunc<(i32, i32), i32>>,
 ///     #[wasmer(export(optional = true, alias = "memory2", alias = "_memory2"))]
 ///     optional_memory: LazyInit<Memory>,
+///     #[wasmer(export(name = "__heap_base", optional))]
+///     heap_base: LazyInit<Option<Global>>,
 /// }
 ///
 /// ```
@@ -57,9 +68,36 @@ impl From<ExportError> for HostEnvInitError {
 /// - `name = "string"`: specify the name of this item in the Wasm module. If this is not specified, it will default to the name of the field.
 /// - `optional = true`: specify whether this export is optional. Defaults to
 /// `false`. Being optional means that if the export can't be found, the
-/// [`LazyInit`] will be left uninitialized.
+/// [`LazyInit`] will be left uninitialized. `optional` alone is the same as
+/// `optional = true`. If the field is a `LazyInit<Option<T>>`, the export is
+/// optional and the [`LazyInit`] is always initialized, with `None` when the
+/// export can't be found.
 /// - `alias = "string"`: specify additional names to look for in the Wasm module.
 /// `alias` may be specified multiple times to search for multiple aliases.
+///
+/// The struct itself accepts `#[wasmer(finish = "path")]`, where `path`
+/// names a function called as [`WasmerEnv::finish`], with the signature
+/// `fn(&mut Self, &Instance) -> Result<(), HostEnvInitError>`:
+///
+/// ```
+/// use std::sync::{Arc, Mutex};
+/// use wasmer::{HostEnvInitError, Instance, LazyInit, Memory, Pages, WasmerEnv};
+///
+/// #[derive(WasmerEnv, Clone)]
+/// #[wasmer(finish = "MyEnvWithDerivedState::finish_env")]
+/// pub struct MyEnvWithDerivedState {
+///     #[wasmer(export)]
+///     memory: LazyInit<Memory>,
+///     initial_size: Arc<Mutex<Pages>>,
+/// }
+///
+/// impl MyEnvWithDerivedState {
+///     fn finish_env(&mut self, _instance: &Instance) -> Result<(), HostEnvInitError> {
+///         *self.initial_size.lock().unwrap() = self.memory_ref().unwrap().size();
+///         Ok(())
+///     }
+/// }
+/// ```
 /// -------
 ///
 /// This trait may also be implemented manually:
@@ -87,6 +125,21 @@ pub trait WasmerEnv: Clone + Send + Sync {
     fn init_with_instance(&mut self, _instance: &Instance) -> Result<(), HostEnvInitError> {
         Ok(())
     }
+
+    /// The function that Wasmer will call on your type once
+    /// `init_with_instance` has filled the [`LazyInit`] fields of the envs
+    /// of all the imports, to let it compute the state derived from them.
+    ///
+    /// It is called once per `Instance`, on the env of the first import
+    /// with this type of env. Every import has its own clone of the env,
+    /// so the state computed here must be shared, e.g. behind an `Arc`, to
+    /// be seen by the other imports.
+    ///
+    /// This is the place to, for example, cache the size of a memory or
+    /// call an `init` function exported by the instance.
+    fn finish(&mut self, _instance: &Instance) -> Result<(), HostEnvInitError> {
+        Ok(())
+    }
 }
 
 impl WasmerEnv for u8 {}
@@ -120,6 +173,10 @@ impl<T: WasmerEnv> WasmerEnv for Box<T> {
     fn init_with_instance(&mut self, instance: &Instance) -> Result<(), HostEnvInitError> {
         (&mut **self).init_with_instance(instance)
     }
+
+    fn finish(&mut self, instance: &Instance) -> Result<(), HostEnvInitError> {
+        (**self).finish(instance)
+    }
 }
 
 impl<T: WasmerEnv> WasmerEnv for ::std::sync::Arc<::std::sync::Mutex<T>> {
@@ -127,6 +184,11 @@ impl<T: WasmerEnv> WasmerEnv for ::std::sync::Arc<::std::sync::Mutex<T>> {
         let mut guard = self.lock().unwrap();
         guard.init_with_instance(instance)
     }
+
+    fn finish(&mut self, instance: &Instance) -> Result<(), HostEnvInitError> {
+        let mut guard = self.lock().unwrap();
+        guard.finish(instance)
+    }
 }
 
 /// Lazily init an item
@@ -1,7 +1,7 @@
 use crate::externals::{Extern, Function, Global, Memory, Table};
 use crate::import_object::LikeNamespace;
 use crate::native::NativeFunc;
-use crate::WasmTypeList;
+use crate::{ExternType, WasmTypeList};
 use indexmap::IndexMap;
 use loupe::MemoryUsage;
 use std::fmt;
@@ -171,6 +171,14 @@ impl Exports {
         self.map.get(name)
     }
 
+    /// Get an export as an `Extern`, together with its type.
+    pub fn get_extern_with_type(&self, name: &str) -> Result<(&Extern, ExternType), ExportError> {
+        match self.map.get(name) {
+            None => Err(ExportError::Missing(name.to_string())),
+            Some(extern_) => Ok((extern_, extern_.ty())),
+        }
+    }
+
     /// Returns true if the `Exports` contains the given export name.
     pub fn contains<S>(&self, name: S) -> bool
     where
@@ -185,6 +193,26 @@ impl Exports {
             iter: self.map.iter(),
         }
     }
+
+    /// Get an iterator over the exported functions.
+    pub fn iter_functions(&self) -> impl Iterator<Item = (&String, &Function)> {
+        self.iter().functions()
+    }
+
+    /// Get an iterator over the exported memories.
+    pub fn iter_memories(&self) -> impl Iterator<Item = (&String, &Memory)> {
+        self.iter().memories()
+    }
+
+    /// Get an iterator over the exported globals.
+    pub fn iter_globals(&self) -> impl Iterator<Item = (&String, &Global)> {
+        self.iter().globals()
+    }
+
+    /// Get an iterator over the exported tables.
+    pub fn iter_tables(&self) -> impl Iterator<Item = (&String, &Table)> {
+        self.iter().tables()
+    }
 }
 
 impl fmt::Debug for Exports {
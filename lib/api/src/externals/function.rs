@@ -11,6 +11,7 @@ pub use inner::{FromToNativeWasmType, HostFunction, WasmTypeList, WithEnv, Witho
 pub use inner::{UnsafeMutableEnv, WithUnsafeMutableEnv};
 
 use loupe::MemoryUsage;
+use std::any::TypeId;
 use std::cmp::max;
 use std::ffi::c_void;
 use std::fmt;
@@ -70,19 +71,28 @@ pub struct Function {
     pub(crate) exported: ExportFunction,
 }
 
-fn build_export_function_metadata<Env>(
+/// The type of the functions calling `WasmerEnv::init_with_instance` and
+/// `WasmerEnv::finish` on a host env.
+type HostEnvInitFn<Env> =
+    for<'a> fn(&'a mut Env, &'a crate::Instance) -> Result<(), crate::HostEnvInitError>;
+
+/// Build the metadata of a host function whose env is `env`, wrapping the
+/// user-supplied env of type `UserEnv`.
+fn build_export_function_metadata<Env, UserEnv>(
     env: Env,
-    import_init_function_ptr: for<'a> fn(
-        &'a mut Env,
-        &'a crate::Instance,
-    ) -> Result<(), crate::HostEnvInitError>,
+    import_init_function_ptr: HostEnvInitFn<Env>,
+    import_finish_function_ptr: HostEnvInitFn<Env>,
 ) -> (*mut c_void, ExportFunctionMetadata)
 where
     Env: Clone + Sized + 'static + Send + Sync,
+    UserEnv: 'static,
 {
     let import_init_function_ptr = Some(unsafe {
         std::mem::transmute::<_, ImportInitializerFuncPtr>(import_init_function_ptr)
     });
+    let import_finish_function_ptr = Some((TypeId::of::<UserEnv>(), unsafe {
+        std::mem::transmute::<_, ImportInitializerFuncPtr>(import_finish_function_ptr)
+    }));
     let host_env_clone_fn = |ptr: *mut c_void| -> *mut c_void {
         let env_ref: &Env = unsafe {
             ptr.cast::<Env>()
@@ -103,6 +113,7 @@ where
         ExportFunctionMetadata::new(
             env,
             import_init_function_ptr,
+            import_finish_function_ptr,
             host_env_clone_fn,
             host_env_drop_fn,
         )
@@ -188,6 +199,7 @@ impl Function {
                         ExportFunctionMetadata::new(
                             host_env,
                             None,
+                            None,
                             host_env_clone_fn,
                             host_env_drop_fn,
                         )
@@ -264,15 +276,23 @@ impl Function {
                 function_type: ty.clone(),
             });
 
-        let import_init_function_ptr: for<'a> fn(&'a mut _, &'a _) -> Result<(), _> =
+        let import_init_function_ptr: HostEnvInitFn<_> =
             |env: &mut VMDynamicFunctionContext<DynamicFunctionWithEnv<Env>>,
              instance: &crate::Instance| {
                 Env::init_with_instance(&mut *env.ctx.env, instance)
             };
+        let import_finish_function_ptr: HostEnvInitFn<_> =
+            |env: &mut VMDynamicFunctionContext<DynamicFunctionWithEnv<Env>>,
+             instance: &crate::Instance| Env::finish(&mut *env.ctx.env, instance);
 
         let (host_env, metadata) = build_export_function_metadata::<
             VMDynamicFunctionContext<DynamicFunctionWithEnv<Env>>,
-        >(dynamic_ctx, import_init_function_ptr);
+            Env,
+        >(
+            dynamic_ctx,
+            import_init_function_ptr,
+            import_finish_function_ptr,
+        );
 
         // We don't yet have the address with the Wasm ABI signature.
         // The engine linker will replace the address with one pointing to a
@@ -388,7 +408,7 @@ impl Function {
         let address = function.address();
 
         let (host_env, metadata) =
-            build_export_function_metadata::<Env>(env, Env::init_with_instance);
+            build_export_function_metadata::<Env, Env>(env, Env::init_with_instance, Env::finish);
 
         let vmctx = VMFunctionEnvironment { host_env };
         let signature = function.ty();
@@ -436,7 +456,7 @@ impl Function {
         let address = function.address();
 
         let (host_env, metadata) =
-            build_export_function_metadata::<Env>(env, Env::init_with_instance);
+            build_export_function_metadata::<Env, Env>(env, Env::init_with_instance, Env::finish);
 
         let vmctx = VMFunctionEnvironment { host_env };
         let signature = function.ty();
@@ -654,12 +674,19 @@ impl Function {
     }
 
     pub(crate) fn checked_anyfunc(&self) -> VMCallerCheckedAnyfunc {
-        let vmsignature = self
-            .store
-            .engine()
-            .register_signature(&self.exported.vm_function.signature);
+        let engine = self.store.engine();
+        let vmsignature = engine.register_signature(&self.exported.vm_function.signature);
+        let mut func_ptr = self.exported.vm_function.address;
+        // The dynamic host functions don't have an address with the Wasm
+        // ABI until they're imported, so they're called through the
+        // dynamic function trampoline of their signature instead.
+        if func_ptr.is_null() && self.exported.vm_function.kind == VMFunctionKind::Dynamic {
+            if let Some(trampoline) = engine.dynamic_function_trampoline(vmsignature) {
+                func_ptr = *trampoline;
+            }
+        }
         VMCallerCheckedAnyfunc {
-            func_ptr: self.exported.vm_function.address,
+            func_ptr,
             type_index: vmsignature,
             vmctx: self.exported.vm_function.vmctx,
         }
@@ -668,6 +695,9 @@ impl Function {
     /// Transform this WebAssembly function into a function with the
     /// native ABI. See [`NativeFunc`] to learn more.
     ///
+    /// This works as well for the functions received as a
+    /// `Value::FuncRef`, for example when reading a table.
+    ///
     /// # Examples
     ///
     /// ```
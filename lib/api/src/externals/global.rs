@@ -1,5 +1,5 @@
 use crate::exports::{ExportError, Exportable};
-use crate::externals::Extern;
+use crate::externals::{Extern, FromToNativeWasmType};
 use crate::store::{Store, StoreObject};
 use crate::types::Val;
 use crate::GlobalType;
@@ -9,6 +9,7 @@ use loupe::MemoryUsage;
 use std::fmt;
 use std::sync::Arc;
 use wasmer_engine::{Export, ExportGlobal};
+use wasmer_types::NativeWasmType;
 use wasmer_vm::{Global as RuntimeGlobal, VMExportGlobal};
 
 /// A WebAssembly `global` instance.
@@ -182,6 +183,60 @@ impl Global {
         Ok(())
     }
 
+    /// Retrieves the current value of the Global as a `T`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Global, Store, Value};
+    /// # let store = Store::default();
+    /// #
+    /// let g = Global::new(&store, Value::I32(1));
+    ///
+    /// assert_eq!(g.get_typed::<i32>().unwrap(), 1);
+    /// assert!(g.get_typed::<i64>().is_err());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the Global doesn't have the type of `T`.
+    pub fn get_typed<T: FromToNativeWasmType>(&self) -> Result<T, RuntimeError> {
+        let expected = T::Native::WASM_TYPE;
+        if self.ty().ty != expected {
+            return Err(RuntimeError::new(format!(
+                "global has type {}, not {}",
+                self.ty().ty,
+                expected
+            )));
+        }
+        let mut binary = 0i128;
+        unsafe { self.get().write_value_to(&mut binary) };
+        Ok(T::from_native(T::Native::from_binary(binary)))
+    }
+
+    /// Sets the value of the Global from a `T`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Global, Store, Value};
+    /// # let store = Store::default();
+    /// #
+    /// let g = Global::new_mut(&store, Value::F64(1.0));
+    ///
+    /// g.set_typed(2.5f64).unwrap();
+    ///
+    /// assert_eq!(g.get(), Value::F64(2.5));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the Global is immutable or doesn't have the
+    /// type of `T`.
+    pub fn set_typed<T: FromToNativeWasmType>(&self, val: T) -> Result<(), RuntimeError> {
+        self.set(val.to_native().to_value())
+    }
+
     pub(crate) fn from_vm_export(store: &Store, wasmer_export: ExportGlobal) -> Self {
         Self {
             store: store.clone(),
@@ -4,11 +4,35 @@ use crate::store::Store;
 use crate::{MemoryType, MemoryView};
 use loupe::MemoryUsage;
 use std::convert::TryInto;
+use std::ptr;
 use std::slice;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
 use wasmer_engine::{Export, ExportMemory};
 use wasmer_types::{Pages, ValueType};
-use wasmer_vm::{Memory as RuntimeMemory, MemoryError, VMExportMemory};
+use wasmer_vm::{
+    HostBuffer, Memory as RuntimeMemory, MemoryError, MemoryStyle, VMExportMemory, WaitResult,
+};
+
+/// An error that can occur when reading from or writing to a [`Memory`]
+/// with the bulk access methods.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryAccessError {
+    /// The access would read or write past the end of the memory.
+    #[error("out of bounds memory access")]
+    HeapOutOfBounds,
+    /// The offset or the length of the access overflows the address space.
+    #[error("address calculation overflow")]
+    Overflow,
+    /// The address is not properly aligned for the accessed type.
+    #[error("unaligned pointer")]
+    Unaligned,
+    /// Waiting is only allowed on shared memories which support it.
+    #[error("atomic wait is not supported on this memory")]
+    WaitUnsupported,
+}
 
 /// A WebAssembly `memory` instance.
 ///
@@ -55,6 +79,82 @@ impl Memory {
         })
     }
 
+    /// Creates a new host `Memory` from the provided [`MemoryType`],
+    /// backed by a buffer owned by the host.
+    ///
+    /// The memory grows in place and can never grow beyond the number of
+    /// pages that fit in `buffer`. It is created by the store
+    /// [`Tunables`][crate::Tunables], so that a
+    /// [`ResourceLimiter`][crate::ResourceLimiter] installed in the
+    /// store still applies to it.
+    ///
+    /// Modules importing this memory must have been compiled with
+    /// tunables that choose a dynamic memory style without guard pages
+    /// for it, for example [`BaseTunables::tiny`][crate::BaseTunables::tiny].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Memory, MemoryType, Pages, Store, Type, Value, WASM_PAGE_SIZE};
+    /// # let store = Store::default();
+    /// #
+    /// let buffer = vec![0u8; 2 * WASM_PAGE_SIZE];
+    /// let m = Memory::new_with_buffer(&store, MemoryType::new(1, None, false), buffer).unwrap();
+    ///
+    /// assert_eq!(m.grow(1).unwrap(), Pages(1));
+    /// assert!(m.grow(1).is_err());
+    /// ```
+    pub fn new_with_buffer(
+        store: &Store,
+        ty: MemoryType,
+        buffer: impl HostBuffer + 'static,
+    ) -> Result<Self, MemoryError> {
+        let memory = store
+            .tunables()
+            .create_host_memory_with_buffer(&ty, Box::new(buffer))?;
+
+        Ok(Self {
+            store: store.clone(),
+            memory,
+        })
+    }
+
+    /// Creates a new host `Memory` from the provided [`MemoryType`],
+    /// backed by a memory-mapped `file`.
+    ///
+    /// The contents of the memory live in the file: a non-empty file
+    /// provides the initial contents of the memory (which then starts
+    /// with enough pages to hold them), and the file is extended as the
+    /// memory grows. Use [`Memory::flush`] to synchronously write the
+    /// changes back to the file.
+    ///
+    /// The memory is created by the store [`Tunables`][crate::Tunables],
+    /// which choose its style, like for [`Memory::new`].
+    #[cfg(not(target_os = "windows"))]
+    pub fn new_file_backed(
+        store: &Store,
+        ty: MemoryType,
+        file: std::fs::File,
+    ) -> Result<Self, MemoryError> {
+        let tunables = store.tunables();
+        let style = tunables.memory_style(&ty);
+        let memory = tunables.create_host_memory_file_backed(&ty, &style, file)?;
+
+        Ok(Self {
+            store: store.clone(),
+            memory,
+        })
+    }
+
+    /// Synchronously writes the contents of the memory back to its
+    /// backing storage, such as the file of a memory created with
+    /// [`Memory::new_file_backed`].
+    ///
+    /// This does nothing for memories without backing storage.
+    pub fn flush(&self) -> Result<(), MemoryError> {
+        self.memory.flush()
+    }
+
     /// Returns the [`MemoryType`] of the `Memory`.
     ///
     /// # Example
@@ -88,6 +188,54 @@ impl Memory {
         &self.store
     }
 
+    /// Returns a handle to this same memory that belongs to `store`, so
+    /// that it can be imported by instances created from `store`.
+    ///
+    /// Both handles refer to the same underlying memory: no data is
+    /// copied, and growing the memory through one handle is visible
+    /// through the other.
+    ///
+    /// Within a single `Store`, this is the same as cloning the `Memory`.
+    /// To share a memory with another `Store` (possibly used on another
+    /// thread), the memory must be a shared memory with a static style,
+    /// so that concurrent accesses are allowed and growing it never moves
+    /// it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Memory, MemoryType, Pages, Store, Type, Value};
+    /// # let store = Store::default();
+    /// #
+    /// let m = Memory::new(&store, MemoryType::new(1, Some(2), true)).unwrap();
+    /// let other_store = Store::default();
+    /// let other_m = m.share_in_store(&other_store).unwrap();
+    ///
+    /// m.grow(1).unwrap();
+    /// assert!(other_m.same(&m));
+    /// assert_eq!(other_m.size(), Pages(2));
+    /// ```
+    pub fn share_in_store(&self, store: &Store) -> Result<Self, MemoryError> {
+        if !Store::same(&self.store, store) {
+            if !self.ty().shared {
+                return Err(MemoryError::InvalidMemory {
+                    reason: "only shared memories can be shared across stores".to_string(),
+                });
+            }
+            if let MemoryStyle::Dynamic { .. } = self.memory.style() {
+                return Err(MemoryError::InvalidMemory {
+                    reason: "memories that can move when growing can't be shared across stores"
+                        .to_string(),
+                });
+            }
+        }
+
+        Ok(Self {
+            store: store.clone(),
+            memory: self.memory.clone(),
+        })
+    }
+
     /// Retrieve a slice of the memory contents.
     ///
     /// # Safety
@@ -182,6 +330,37 @@ impl Memory {
         self.memory.grow(delta.into())
     }
 
+    /// Register a callback invoked after every successful growth of the
+    /// memory, whether it was grown by the host or by a `memory.grow`
+    /// instruction. The callback receives the previous and the new size.
+    ///
+    /// The base pointer of the memory may move when it grows, so this is
+    /// the place to invalidate anything derived from [`Memory::data_ptr`].
+    /// The callback must not grow the memory itself.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Memory, MemoryType, Pages, Store, Type, Value};
+    /// # use std::sync::{Arc, Mutex};
+    /// # let store = Store::default();
+    /// #
+    /// let m = Memory::new(&store, MemoryType::new(1, None, false)).unwrap();
+    /// let sizes = Arc::new(Mutex::new(Vec::new()));
+    /// let sizes2 = sizes.clone();
+    /// m.subscribe_grow(move |old, new| sizes2.lock().unwrap().push((old, new)))
+    ///     .unwrap();
+    ///
+    /// m.grow(2).unwrap();
+    /// assert_eq!(*sizes.lock().unwrap(), vec![(Pages(1), Pages(3))]);
+    /// ```
+    pub fn subscribe_grow<F>(&self, callback: F) -> Result<(), MemoryError>
+    where
+        F: Fn(Pages, Pages) + Send + Sync + 'static,
+    {
+        self.memory.subscribe_grow(Box::new(callback))
+    }
+
     /// Return a "view" of the currently accessible memory. By
     /// default, the view is unsynchronized, using regular memory
     /// accesses. You can force a memory view to use atomic accesses
@@ -221,6 +400,174 @@ impl Memory {
         unsafe { MemoryView::new(base as _, length as u32) }
     }
 
+    /// Copies the bytes of the memory starting at `offset` into `buf`.
+    ///
+    /// The whole `buf` is filled, or nothing is read and an error is
+    /// returned if the range is not within the memory.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Memory, MemoryType, Store};
+    /// # let store = Store::default();
+    /// #
+    /// let m = Memory::new(&store, MemoryType::new(1, None, false)).unwrap();
+    /// m.write(0x10, b"hello").unwrap();
+    ///
+    /// let mut buf = [0u8; 5];
+    /// m.read(0x10, &mut buf).unwrap();
+    /// assert_eq!(&buf, b"hello");
+    /// ```
+    pub fn read(&self, offset: u64, buf: &mut [u8]) -> Result<(), MemoryAccessError> {
+        let src = self.checked_ptr(offset, buf.len())?;
+        // The memory may be shared with wasm code running concurrently, so
+        // it must never be exposed as a Rust slice: copy through raw pointers.
+        unsafe { ptr::copy(src, buf.as_mut_ptr(), buf.len()) };
+        Ok(())
+    }
+
+    /// Copies the `len` bytes of the memory starting at `offset` into a
+    /// new vector.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Memory, MemoryType, Store};
+    /// # let store = Store::default();
+    /// #
+    /// let m = Memory::new(&store, MemoryType::new(1, None, false)).unwrap();
+    /// m.write(0x10, b"hello").unwrap();
+    ///
+    /// assert_eq!(m.read_vec(0x10, 5).unwrap(), b"hello");
+    /// assert!(m.read_vec(0xffff, 2).is_err());
+    /// ```
+    pub fn read_vec(&self, offset: u64, len: usize) -> Result<Vec<u8>, MemoryAccessError> {
+        let src = self.checked_ptr(offset, len)?;
+        let mut vec = Vec::with_capacity(len);
+        unsafe {
+            ptr::copy(src, vec.as_mut_ptr(), len);
+            vec.set_len(len);
+        }
+        Ok(vec)
+    }
+
+    /// Copies `buf` into the memory, starting at `offset`.
+    ///
+    /// The whole `buf` is written, or nothing is written and an error is
+    /// returned if the range is not within the memory.
+    pub fn write(&self, offset: u64, buf: &[u8]) -> Result<(), MemoryAccessError> {
+        let dst = self.checked_ptr(offset, buf.len())?;
+        unsafe { ptr::copy(buf.as_ptr(), dst, buf.len()) };
+        Ok(())
+    }
+
+    /// Wake up to `count` threads blocked in [`Memory::atomic_wait32`] or
+    /// [`Memory::atomic_wait64`] on `offset`, and return the number of
+    /// threads woken.
+    ///
+    /// `offset` must be aligned to 4 bytes. Notifying a memory which is
+    /// not shared always wakes 0 threads.
+    pub fn atomic_notify(&self, offset: u64, count: u32) -> Result<u32, MemoryAccessError> {
+        self.checked_atomic_ptr::<AtomicU32>(offset)?;
+        match self.memory.parking_spot() {
+            Some(spot) if self.ty().shared => Ok(spot.notify(offset, count)),
+            _ => Ok(0),
+        }
+    }
+
+    /// Block the current thread until another thread notifies `offset`,
+    /// provided that the 32-bit value at `offset` is `expected`.
+    ///
+    /// If `timeout` is `None`, the thread waits indefinitely. `offset`
+    /// must be aligned to 4 bytes, and the memory must be shared.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Memory, MemoryType, Store, WaitResult};
+    /// # use std::time::Duration;
+    /// # let store = Store::default();
+    /// #
+    /// let m = Memory::new(&store, MemoryType::new(1, Some(1), true)).unwrap();
+    /// let waiter = m.clone();
+    /// let thread = std::thread::spawn(move || waiter.atomic_wait32(0x10, 0, None));
+    ///
+    /// while m.atomic_notify(0x10, 1).unwrap() == 0 {
+    ///     std::thread::yield_now();
+    /// }
+    /// assert_eq!(thread.join().unwrap(), Ok(WaitResult::Woken));
+    ///
+    /// // The value is not the expected one, so there is no wait.
+    /// let timeout = Some(Duration::from_secs(1));
+    /// assert_eq!(m.atomic_wait32(0x10, 1, timeout), Ok(WaitResult::Mismatch));
+    /// ```
+    pub fn atomic_wait32(
+        &self,
+        offset: u64,
+        expected: u32,
+        timeout: Option<Duration>,
+    ) -> Result<WaitResult, MemoryAccessError> {
+        let value = self.checked_atomic_ptr::<AtomicU32>(offset)?;
+        let spot = self.waitable_parking_spot()?;
+        Ok(spot.wait(
+            offset,
+            || unsafe { (*value).load(Ordering::SeqCst) } == expected,
+            timeout,
+        ))
+    }
+
+    /// Block the current thread until another thread notifies `offset`,
+    /// provided that the 64-bit value at `offset` is `expected`.
+    ///
+    /// If `timeout` is `None`, the thread waits indefinitely. `offset`
+    /// must be aligned to 8 bytes, and the memory must be shared.
+    pub fn atomic_wait64(
+        &self,
+        offset: u64,
+        expected: u64,
+        timeout: Option<Duration>,
+    ) -> Result<WaitResult, MemoryAccessError> {
+        let value = self.checked_atomic_ptr::<AtomicU64>(offset)?;
+        let spot = self.waitable_parking_spot()?;
+        Ok(spot.wait(
+            offset,
+            || unsafe { (*value).load(Ordering::SeqCst) } == expected,
+            timeout,
+        ))
+    }
+
+    fn waitable_parking_spot(&self) -> Result<&wasmer_vm::ParkingSpot, MemoryAccessError> {
+        if !self.ty().shared {
+            return Err(MemoryAccessError::WaitUnsupported);
+        }
+        self.memory
+            .parking_spot()
+            .ok_or(MemoryAccessError::WaitUnsupported)
+    }
+
+    /// Returns a pointer to the atomic at `offset`, after checking that it
+    /// is aligned and within the memory.
+    fn checked_atomic_ptr<A>(&self, offset: u64) -> Result<*const A, MemoryAccessError> {
+        if offset % std::mem::size_of::<A>() as u64 != 0 {
+            return Err(MemoryAccessError::Unaligned);
+        }
+        Ok(self.checked_ptr(offset, std::mem::size_of::<A>())? as *const A)
+    }
+
+    /// Returns a pointer to the `len` bytes starting at `offset`, after
+    /// checking that they are within the memory.
+    fn checked_ptr(&self, offset: u64, len: usize) -> Result<*mut u8, MemoryAccessError> {
+        let definition = self.memory.vmmemory();
+        let def = unsafe { definition.as_ref() };
+        let end = offset
+            .checked_add(len as u64)
+            .ok_or(MemoryAccessError::Overflow)?;
+        if end > def.current_length as u64 {
+            return Err(MemoryAccessError::HeapOutOfBounds);
+        }
+        Ok(unsafe { def.base.add(offset as usize) })
+    }
+
     pub(crate) fn from_vm_export(store: &Store, wasmer_export: ExportMemory) -> Self {
         Self {
             store: store.clone(),
@@ -80,6 +80,25 @@ impl Table {
         set_table_item(self.table.as_ref(), index, item)
     }
 
+    /// Returns an iterator over the current elements of the `Table`.
+    ///
+    /// Null references are returned as `Val::ExternRef(ExternRef::Null)`.
+    /// The iteration stops at the end of the table, even if the table is
+    /// grown while iterating.
+    pub fn iter(&self) -> impl Iterator<Item = Val> + '_ {
+        let mut index = 0;
+        std::iter::from_fn(move || {
+            let val = self.get(index)?;
+            index += 1;
+            Some(val)
+        })
+    }
+
+    /// Returns a snapshot of the current elements of the `Table`.
+    pub fn to_vec(&self) -> Vec<Val> {
+        self.iter().collect()
+    }
+
     /// Retrieves the size of the `Table` (in elements)
     pub fn size(&self) -> u32 {
         self.table.size()
@@ -140,6 +159,35 @@ impl Table {
         Ok(())
     }
 
+    /// Sets the `len` elements of the table starting at `index` to `val`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error, without modifying the table, if the range is out
+    /// of bounds of the table.
+    pub fn fill(&self, index: u32, val: Val, len: u32) -> Result<(), RuntimeError> {
+        let item = val.into_checked_anyfunc(&self.store)?;
+        self.table
+            .fill(index, item, len)
+            .map_err(RuntimeError::from_trap)
+    }
+
+    /// Sets the elements of the table starting at `index` to `vals`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error, without modifying the table, if the range is out
+    /// of bounds of the table or if any value can't be stored in it.
+    pub fn init_from(&self, index: u32, vals: &[Val]) -> Result<(), RuntimeError> {
+        let items = vals
+            .iter()
+            .map(|val| val.into_checked_anyfunc(&self.store))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.table
+            .init(index, &items)
+            .map_err(RuntimeError::from_trap)
+    }
+
     pub(crate) fn from_vm_export(store: &Store, wasmer_export: ExportTable) -> Self {
         Self {
             store: store.clone(),
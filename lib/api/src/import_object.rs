@@ -1,12 +1,16 @@
 //! The import module contains the implementation data structures and helper functions used to
 //! manipulate and access a wasm module's imports including memories, tables, globals, and
 //! functions.
+use crate::exports::Exportable;
+use crate::externals::Extern;
+use crate::module::Module;
+use crate::types::{ExternType, ImportType};
 use std::borrow::{Borrow, BorrowMut};
 use std::collections::VecDeque;
 use std::collections::{hash_map::Entry, HashMap};
 use std::fmt;
 use std::sync::{Arc, Mutex};
-use wasmer_engine::{Export, NamedResolver};
+use wasmer_engine::{Export, NamedResolver, Resolver};
 
 /// The `LikeNamespace` trait represents objects that act as a namespace for imports.
 /// For example, an `Instance` or `Namespace` could be
@@ -178,70 +182,93 @@ impl fmt::Debug for ImportObject {
     }
 }
 
-// The import! macro for ImportObject
-
-/// Generate an [`ImportObject`] easily with the `imports!` macro.
+/// A [`Resolver`] that first looks up the imports of a module in a base
+/// resolver, and calls a closure for the imports the base resolver
+/// doesn't provide.
+///
+/// The closure receives the module name, the field name and the type of
+/// the import, so it can synthesize the missing items on demand.
 ///
-/// [`ImportObject`]: struct.ImportObject.html
+/// The types of the imports are looked up by module and field name, so
+/// the resolver may be used for another module with the same imports in
+/// a different order. The index of the import is only used to tell apart
+/// several imports with the same names.
 ///
 /// # Usage
 ///
 /// ```
-/// # use wasmer::{Function, Store};
+/// # use wasmer::{imports, Extern, ExternType, FallbackResolver, Function, Instance, Module, Store};
+/// # fn main() -> anyhow::Result<()> {
 /// # let store = Store::default();
-/// use wasmer::imports;
-///
-/// let import_object = imports! {
-///     "env" => {
-///         "foo" => Function::new_native(&store, foo)
-///     },
-/// };
-///
-/// fn foo(n: i32) -> i32 {
-///     n
-/// }
+/// let module = Module::new(&store, r#"
+///     (module
+///       (import "log" "enter" (func))
+///       (import "log" "leave" (func)))
+/// "#)?;
+/// let resolver = FallbackResolver::new(&module, imports! {}, |_module, _field, ty| match ty {
+///     ExternType::Function(ty) => Some(Extern::Function(Function::new(&store, ty, |_| Ok(vec![])))),
+///     _ => None,
+/// });
+/// let instance = Instance::new(&module, &resolver)?;
+/// # Ok(())
+/// # }
 /// ```
-#[macro_export]
-macro_rules! imports {
-    ( $( $ns_name:expr => $ns:tt ),* $(,)? ) => {
-        {
-            let mut import_object = $crate::ImportObject::new();
-
-            $({
-                let namespace = $crate::import_namespace!($ns);
-
-                import_object.register($ns_name, namespace);
-            })*
+pub struct FallbackResolver<R, F> {
+    base: R,
+    imports: Vec<ImportType>,
+    callback: F,
+}
 
-            import_object
+impl<R, F> FallbackResolver<R, F>
+where
+    R: Resolver,
+    F: Fn(&str, &str, &ExternType) -> Option<Extern>,
+{
+    /// Create a resolver for the imports of `module`, falling back to
+    /// `callback` when `base` can't resolve an import.
+    pub fn new(module: &Module, base: R, callback: F) -> Self {
+        Self {
+            base,
+            imports: module.imports().collect(),
+            callback,
         }
-    };
-}
+    }
 
-#[macro_export]
-#[doc(hidden)]
-macro_rules! namespace {
-    ($( $import_name:expr => $import_item:expr ),* $(,)? ) => {
-        $crate::import_namespace!( { $( $import_name => $import_item, )* } )
-    };
+    /// Get the type of the import named `module`.`field`, preferring the
+    /// import at `index` if several have these names.
+    fn import_type(&self, index: u32, module: &str, field: &str) -> Option<&ExternType> {
+        let is_named = |import: &&ImportType| import.module() == module && import.name() == field;
+        self.imports
+            .get(index as usize)
+            .filter(is_named)
+            .or_else(|| self.imports.iter().find(is_named))
+            .map(ImportType::ty)
+    }
 }
 
-#[macro_export]
-#[doc(hidden)]
-macro_rules! import_namespace {
-    ( { $( $import_name:expr => $import_item:expr ),* $(,)? } ) => {{
-        let mut namespace = $crate::Exports::new();
-
-        $(
-            namespace.insert($import_name, $import_item);
-        )*
-
-        namespace
-    }};
+impl<R, F> Resolver for FallbackResolver<R, F>
+where
+    R: Resolver,
+    F: Fn(&str, &str, &ExternType) -> Option<Extern>,
+{
+    fn resolve(&self, index: u32, module: &str, field: &str) -> Option<Export> {
+        self.base.resolve(index, module, field).or_else(|| {
+            let ty = self.import_type(index, module, field)?;
+            (self.callback)(module, field, ty).map(|item| item.to_export())
+        })
+    }
+}
 
-    ( $namespace:ident ) => {
-        $namespace
-    };
+impl<R, F> fmt::Debug for FallbackResolver<R, F>
+where
+    R: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FallbackResolver")
+            .field("base", &self.base)
+            .field("imports", &self.imports)
+            .finish()
+    }
 }
 
 #[cfg(test)]
@@ -1,13 +1,15 @@
 use crate::exports::Exports;
-use crate::externals::Extern;
+use crate::externals::{Extern, Global, Memory, Table};
 use crate::module::Module;
 use crate::store::Store;
+use crate::types::Val;
 use crate::{HostEnvInitError, LinkError, RuntimeError};
 use loupe::MemoryUsage;
 use std::fmt;
 use std::sync::{Arc, Mutex};
 use thiserror::Error;
 use wasmer_engine::Resolver;
+use wasmer_types::{ExportIndex, Mutability};
 use wasmer_vm::{InstanceHandle, VMContext};
 
 /// A WebAssembly Instance is a stateful, executable
@@ -164,6 +166,184 @@ impl Instance {
     pub fn vmctx_ptr(&self) -> *mut VMContext {
         self.handle.lock().unwrap().vmctx_ptr()
     }
+
+    /// Capture the state of the memories, globals and tables defined by
+    /// this instance.
+    ///
+    /// Only the items defined by this instance are captured: the imported
+    /// items are not part of the snapshot, as they are owned by the host or
+    /// by other instances.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{imports, Instance, Module, Store, Value};
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let module = Module::new(&store, r#"
+    ///     (module
+    ///       (global $counter (export "counter") (mut i32) (i32.const 0))
+    ///       (func (export "increment")
+    ///         (global.set $counter (i32.add (global.get $counter) (i32.const 1)))))
+    /// "#)?;
+    /// let instance = Instance::new(&module, &imports! {})?;
+    /// let increment = instance.exports.get_native_function::<(), ()>("increment")?;
+    /// let counter = instance.exports.get_global("counter")?;
+    ///
+    /// let snapshot = instance.snapshot();
+    /// increment.call()?;
+    /// assert_eq!(counter.get(), Value::I32(1));
+    ///
+    /// instance.restore(&snapshot)?;
+    /// assert_eq!(counter.get(), Value::I32(0));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn snapshot(&self) -> InstanceSnapshot {
+        let (memories, globals, tables) = self.local_items();
+        InstanceSnapshot {
+            memories: memories
+                .iter()
+                .map(|memory| unsafe { memory.data_unchecked() }.to_vec())
+                .collect(),
+            globals: globals.iter().map(Global::get).collect(),
+            tables: tables.iter().map(Table::to_vec).collect(),
+        }
+    }
+
+    /// Restore the state captured by [`Instance::snapshot`].
+    ///
+    /// Like the snapshot, this only touches the memories, globals and
+    /// tables defined by this instance, not the imported ones.
+    ///
+    /// Memories and tables that are smaller than in the snapshot are grown
+    /// back to their size in the snapshot. Memories and tables can't
+    /// shrink though, so the ones that grew since the snapshot keep their
+    /// size: their contents are restored, and the bytes and elements past
+    /// the end of the snapshot are reset to zero and null.
+    ///
+    /// Everything is grown before anything is written, so if growing
+    /// fails, the contents of the instance are left untouched.
+    pub fn restore(&self, snapshot: &InstanceSnapshot) -> Result<(), RuntimeError> {
+        let (memories, globals, tables) = self.local_items();
+        if memories.len() != snapshot.memories.len()
+            || globals.len() != snapshot.globals.len()
+            || tables.len() != snapshot.tables.len()
+        {
+            return Err(RuntimeError::new(
+                "the snapshot was not taken from an instance of this module",
+            ));
+        }
+
+        for (memory, data) in memories.iter().zip(&snapshot.memories) {
+            let size = memory.data_size() as usize;
+            if size < data.len() {
+                let delta = ((data.len() - size) / wasmer_types::WASM_PAGE_SIZE) as u32;
+                memory
+                    .grow(delta)
+                    .map_err(|e| RuntimeError::new(e.to_string()))?;
+            }
+        }
+        for (table, elements) in tables.iter().zip(&snapshot.tables) {
+            let size = table.size() as usize;
+            if size < elements.len() {
+                table.grow((elements.len() - size) as u32, Val::null())?;
+            }
+        }
+
+        for (memory, data) in memories.iter().zip(&snapshot.memories) {
+            let (restored, grown) = unsafe { memory.data_unchecked_mut() }.split_at_mut(data.len());
+            restored.copy_from_slice(data);
+            for byte in grown {
+                *byte = 0;
+            }
+        }
+
+        for (global, value) in globals.iter().zip(&snapshot.globals) {
+            if global.ty().mutability == Mutability::Var {
+                global.set(value.clone())?;
+            }
+        }
+
+        for (table, elements) in tables.iter().zip(&snapshot.tables) {
+            table.init_from(0, elements)?;
+            let grown = table.size() - elements.len() as u32;
+            if grown > 0 {
+                table.fill(elements.len() as u32, Val::null(), grown)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the memories, globals and tables defined by this instance.
+    fn local_items(&self) -> (Vec<Memory>, Vec<Global>, Vec<Table>) {
+        let handle = self.handle.lock().unwrap();
+        let info = self.module.info();
+        let store = self.store();
+        let lookup =
+            |index| Extern::from_vm_export(store, handle.lookup_by_declaration(&index).into());
+
+        let memories = info
+            .memories
+            .keys()
+            .filter(|index| info.local_memory_index(*index).is_some())
+            .filter_map(|index| match lookup(ExportIndex::Memory(index)) {
+                Extern::Memory(memory) => Some(memory),
+                _ => None,
+            })
+            .collect();
+        let globals = info
+            .globals
+            .keys()
+            .filter(|index| info.local_global_index(*index).is_some())
+            .filter_map(|index| match lookup(ExportIndex::Global(index)) {
+                Extern::Global(global) => Some(global),
+                _ => None,
+            })
+            .collect();
+        let tables = info
+            .tables
+            .keys()
+            .filter(|index| info.local_table_index(*index).is_some())
+            .filter_map(|index| match lookup(ExportIndex::Table(index)) {
+                Extern::Table(table) => Some(table),
+                _ => None,
+            })
+            .collect();
+        (memories, globals, tables)
+    }
+}
+
+/// The state of the memories, globals and tables defined by an
+/// [`Instance`], as captured by [`Instance::snapshot`].
+///
+/// The imported memories, globals and tables are not part of it.
+#[derive(Clone, Debug)]
+pub struct InstanceSnapshot {
+    memories: Vec<Vec<u8>>,
+    globals: Vec<Val>,
+    tables: Vec<Vec<Val>>,
+}
+
+impl InstanceSnapshot {
+    /// Returns the contents of the memories, in the order they are
+    /// defined in the module.
+    pub fn memories(&self) -> &[Vec<u8>] {
+        &self.memories
+    }
+
+    /// Returns the values of the globals, in the order they are defined
+    /// in the module.
+    pub fn globals(&self) -> &[Val] {
+        &self.globals
+    }
+
+    /// Returns the elements of the tables, in the order they are defined
+    /// in the module.
+    pub fn tables(&self) -> &[Vec<Val>] {
+        &self.tables
+    }
 }
 
 impl fmt::Debug for Instance {
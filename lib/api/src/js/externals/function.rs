@@ -0,0 +1,841 @@
+use crate::env::{HostEnvInitError, WasmerEnv};
+use crate::js::exports::{ExportError, Exportable};
+use crate::js::externals::Extern;
+use crate::js::instance::Instance;
+use crate::js::native::NativeFunc;
+use crate::js::store::Store;
+use crate::js::trap::RuntimeError;
+use crate::js::types::{val_from_js, val_to_js, Val};
+use js_sys::{Array, Function as JsFunction};
+use std::any::TypeId;
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasmer_types::FunctionType;
+
+pub(crate) use inner::{array_from_vals, vals_from_array};
+pub use inner::{FromToNativeWasmType, HostFunction, WasmTypeList, WithEnv, WithoutEnv};
+
+/// A WebAssembly `function` instance.
+///
+/// A function instance is the runtime representation of a function.
+/// It effectively is a closure of the original function (defined in either
+/// the host or the WebAssembly module) over the runtime `Instance` of its
+/// originating `Module`.
+///
+/// With the `js` backend, it wraps a JavaScript function.
+///
+/// Spec: <https://webassembly.github.io/spec/core/exec/runtime.html#function-instances>
+#[derive(Clone)]
+pub struct Function {
+    store: Store,
+    ty: FunctionType,
+    function: JsFunction,
+    /// The environment of a host function, initialized with the
+    /// instance importing it.
+    environment: Option<Rc<dyn HostEnvironment>>,
+}
+
+impl Function {
+    /// Creates a new host `Function` (dynamic) with the provided signature.
+    ///
+    /// The function is called by the JavaScript host with the values
+    /// of the parameters, and its error is thrown as a JavaScript
+    /// exception.
+    pub fn new<FT, F>(store: &Store, ty: FT, func: F) -> Self
+    where
+        FT: Into<FunctionType>,
+        F: Fn(&[Val]) -> Result<Vec<Val>, RuntimeError> + 'static + Send + Sync,
+    {
+        Self::from_closure(store, ty.into(), func, None)
+    }
+
+    /// Creates a new host `Function` (dynamic) with the provided
+    /// signature and environment.
+    ///
+    /// The environment is initialized with [`WasmerEnv::init_with_instance`]
+    /// by the first instance importing the function.
+    pub fn new_with_env<FT, F, Env>(store: &Store, ty: FT, env: Env, func: F) -> Self
+    where
+        FT: Into<FunctionType>,
+        F: Fn(&Env, &[Val]) -> Result<Vec<Val>, RuntimeError> + 'static + Send + Sync,
+        Env: Sized + WasmerEnv + 'static,
+    {
+        let environment = Rc::new(Environment::new(env));
+        let env = environment.env.clone();
+        Self::from_closure(
+            store,
+            ty.into(),
+            move |params| func(&*env.borrow(), params),
+            Some(environment),
+        )
+    }
+
+    /// Creates a new host `Function` from a native function.
+    ///
+    /// The function signature is automatically retrieved using the
+    /// Rust typing system.
+    pub fn new_native<F, Args, Rets, Env>(store: &Store, func: F) -> Self
+    where
+        F: HostFunction<Args, Rets, WithoutEnv, Env>,
+        Args: WasmTypeList,
+        Rets: WasmTypeList,
+        Env: Sized + 'static,
+    {
+        let ty = FunctionType::new(Args::wasm_types(), Rets::wasm_types());
+        Self::from_closure(
+            store,
+            ty,
+            move |params| func.call_with_vals(None, params),
+            None,
+        )
+    }
+
+    /// Creates a new host `Function` from a native function and a
+    /// provided environment.
+    ///
+    /// The environment is initialized with [`WasmerEnv::init_with_instance`]
+    /// by the first instance importing the function.
+    pub fn new_native_with_env<F, Args, Rets, Env>(store: &Store, env: Env, func: F) -> Self
+    where
+        F: HostFunction<Args, Rets, WithEnv, Env>,
+        Args: WasmTypeList,
+        Rets: WasmTypeList,
+        Env: Sized + WasmerEnv + 'static,
+    {
+        let ty = FunctionType::new(Args::wasm_types(), Rets::wasm_types());
+        let environment = Rc::new(Environment::new(env));
+        let env = environment.env.clone();
+        Self::from_closure(
+            store,
+            ty,
+            move |params| func.call_with_vals(Some(&*env.borrow()), params),
+            Some(environment),
+        )
+    }
+
+    /// Creates a host `Function` calling `func` with the values of the
+    /// parameters.
+    fn from_closure<F>(
+        store: &Store,
+        ty: FunctionType,
+        func: F,
+        environment: Option<Rc<dyn HostEnvironment>>,
+    ) -> Self
+    where
+        F: Fn(&[Val]) -> Result<Vec<Val>, RuntimeError> + 'static,
+    {
+        let function_type = ty.clone();
+        let closure = Closure::wrap(Box::new(move |args: Array| -> Result<JsValue, JsValue> {
+            let params = function_type
+                .params()
+                .iter()
+                .zip(args.iter())
+                .map(|(ty, arg)| val_from_js(*ty, &arg))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|error| JsValue::from(js_sys::Error::new(&error.message())))?;
+            let results = func(&params)
+                .map_err(|error| JsValue::from(js_sys::Error::new(&error.message())))?;
+
+            results_to_js(&results).map_err(|error| js_sys::Error::new(&error.message()).into())
+        })
+            as Box<dyn FnMut(Array) -> Result<JsValue, JsValue>>);
+
+        // The imported functions receive their parameters as separate
+        // arguments, which are gathered in an array for the closure.
+        let wrapper =
+            JsFunction::new_with_args("f", "return function(...args) { return f(args); }");
+        let function = wrapper
+            .call1(&JsValue::NULL, &closure.into_js_value())
+            .expect("the function wrapper can't fail")
+            .unchecked_into();
+
+        Self {
+            store: store.clone(),
+            ty,
+            function,
+            environment,
+        }
+    }
+
+    pub(crate) fn from_js(store: &Store, function: JsFunction, ty: FunctionType) -> Self {
+        Self {
+            store: store.clone(),
+            ty,
+            function,
+            environment: None,
+        }
+    }
+
+    /// Returns the [`FunctionType`] of the `Function`.
+    pub fn ty(&self) -> &FunctionType {
+        &self.ty
+    }
+
+    /// Returns the [`Store`] where the `Function` belongs.
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    /// Returns the number of parameters that this function takes.
+    pub fn param_arity(&self) -> usize {
+        self.ty.params().len()
+    }
+
+    /// Returns the number of results this function produces.
+    pub fn result_arity(&self) -> usize {
+        self.ty.results().len()
+    }
+
+    /// Call the `Function` function.
+    ///
+    /// Depending on where the Function is defined, it will call it.
+    /// 1. If the function is defined inside a WebAssembly, it will call the trampoline
+    ///    for the function signature.
+    /// 2. If the function is defined in the host (in a native way), it will
+    ///    call the trampoline.
+    pub fn call(&self, params: &[Val]) -> Result<Box<[Val]>, RuntimeError> {
+        if params.len() != self.param_arity() {
+            return Err(RuntimeError::new(format!(
+                "expected {} arguments, got {}",
+                self.param_arity(),
+                params.len()
+            )));
+        }
+
+        let arguments = params.iter().map(val_to_js).collect::<Result<Array, _>>()?;
+        let result = self.function.apply(&JsValue::NULL, &arguments)?;
+
+        match self.ty.results() {
+            [] => Ok(Box::new([])),
+            [ty] => Ok(Box::new([val_from_js(*ty, &result)?])),
+            types => {
+                let results: Array = result
+                    .dyn_into()
+                    .map_err(|_| RuntimeError::new("expected an array of results"))?;
+                types
+                    .iter()
+                    .zip(results.iter())
+                    .map(|(ty, result)| val_from_js(*ty, &result))
+                    .collect()
+            }
+        }
+    }
+
+    /// Transform this WebAssembly function into a function with the
+    /// native ABI. See [`NativeFunc`] to learn more.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `Args` or the `Rets` generic parameters
+    /// don't match the types of the function.
+    pub fn native<Args, Rets>(&self) -> Result<NativeFunc<Args, Rets>, RuntimeError>
+    where
+        Args: WasmTypeList,
+        Rets: WasmTypeList,
+    {
+        // type check
+        {
+            let expected = self.ty.params();
+            let given = Args::wasm_types();
+
+            if expected != given {
+                return Err(RuntimeError::new(format!(
+                    "given types (`{:?}`) for the function arguments don't match the actual types (`{:?}`)",
+                    given,
+                    expected,
+                )));
+            }
+        }
+
+        {
+            let expected = self.ty.results();
+            let given = Rets::wasm_types();
+
+            if expected != given {
+                return Err(RuntimeError::new(format!(
+                    "given types (`{:?}`) for the function results don't match the actual types (`{:?}`)",
+                    given,
+                    expected,
+                )));
+            }
+        }
+
+        Ok(NativeFunc::new(self.clone()))
+    }
+
+    pub(crate) fn as_js(&self) -> &JsValue {
+        self.function.as_ref()
+    }
+
+    pub(crate) fn environment(&self) -> Option<&Rc<dyn HostEnvironment>> {
+        self.environment.as_ref()
+    }
+}
+
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        self.store == other.store && self.ty == other.ty && self.function == other.function
+    }
+}
+
+/// The environment of a host function, as seen by the instance
+/// importing the function.
+pub(crate) trait HostEnvironment {
+    /// Marks the environment as initialized, returning whether it
+    /// wasn't: like with the native backend, only the first instance
+    /// importing the function initializes its environment.
+    fn claim(&self) -> bool;
+
+    /// Calls [`WasmerEnv::init_with_instance`].
+    fn init_with_instance(&self, instance: &Instance) -> Result<(), HostEnvInitError>;
+
+    /// Calls [`WasmerEnv::finish`].
+    fn finish(&self, instance: &Instance) -> Result<(), HostEnvInitError>;
+
+    /// The type of the environment, as [`WasmerEnv::finish`] is called
+    /// once per type.
+    fn env_type(&self) -> TypeId;
+}
+
+/// The environment of a host function, shared by the function and its
+/// initializer.
+struct Environment<Env> {
+    env: Rc<RefCell<Env>>,
+    initialized: Cell<bool>,
+}
+
+impl<Env> Environment<Env> {
+    fn new(env: Env) -> Self {
+        Self {
+            env: Rc::new(RefCell::new(env)),
+            initialized: Cell::new(false),
+        }
+    }
+}
+
+impl<Env: WasmerEnv + 'static> HostEnvironment for Environment<Env> {
+    fn claim(&self) -> bool {
+        !self.initialized.replace(true)
+    }
+
+    fn init_with_instance(&self, instance: &Instance) -> Result<(), HostEnvInitError> {
+        self.env.borrow_mut().init_with_instance(instance)
+    }
+
+    fn finish(&self, instance: &Instance) -> Result<(), HostEnvInitError> {
+        self.env.borrow_mut().finish(instance)
+    }
+
+    fn env_type(&self) -> TypeId {
+        TypeId::of::<Env>()
+    }
+}
+
+/// Converts the results of a host function to the value it returns
+/// to JavaScript: nothing, the only result, or an array of results.
+fn results_to_js(results: &[Val]) -> Result<JsValue, RuntimeError> {
+    Ok(match results {
+        [] => JsValue::UNDEFINED,
+        [result] => val_to_js(result)?,
+        results => results
+            .iter()
+            .map(val_to_js)
+            .collect::<Result<Array, _>>()?
+            .into(),
+    })
+}
+
+impl<'a> Exportable<'a> for Function {
+    fn get_self_from_extern(_extern: &'a Extern) -> Result<&'a Self, ExportError> {
+        match _extern {
+            Extern::Function(func) => Ok(func),
+            _ => Err(ExportError::IncompatibleType),
+        }
+    }
+}
+
+impl fmt::Debug for Function {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("Function")
+            .field("ty", &self.ty)
+            .finish()
+    }
+}
+
+/// This private inner module contains the low-level implementation
+/// for `Function` and its siblings.
+mod inner {
+    use crate::js::trap::RuntimeError;
+    use crate::js::types::Val;
+    use std::array::TryFromSliceError;
+    use std::convert::{Infallible, TryInto};
+    use std::error::Error;
+    use wasmer_types::{NativeWasmType, Type};
+
+    /// A trait to convert a Rust value to a `WasmNativeType` value,
+    /// or to convert `WasmNativeType` value to a Rust value.
+    ///
+    /// This trait should ideally be split into two traits:
+    /// `FromNativeWasmType` and `ToNativeWasmType` but it creates a
+    /// non-negligible complexity in the `WasmTypeList`
+    /// implementation.
+    pub unsafe trait FromToNativeWasmType: Copy
+    where
+        Self: Sized,
+    {
+        /// Native Wasm type.
+        type Native: NativeWasmType;
+
+        /// Convert a value of kind `Self::Native` to `Self`.
+        ///
+        /// # Panics
+        ///
+        /// This method panics if `native` cannot fit in the `Self`
+        /// type`.
+        fn from_native(native: Self::Native) -> Self;
+
+        /// Convert self to `Self::Native`.
+        ///
+        /// # Panics
+        ///
+        /// This method panics if `self` cannot fit in the
+        /// `Self::Native` type.
+        fn to_native(self) -> Self::Native;
+    }
+
+    macro_rules! from_to_native_wasm_type {
+        ( $( $type:ty => $native_type:ty ),* ) => {
+            $(
+                #[allow(clippy::use_self)]
+                unsafe impl FromToNativeWasmType for $type {
+                    type Native = $native_type;
+
+                    #[inline]
+                    fn from_native(native: Self::Native) -> Self {
+                        native as Self
+                    }
+
+                    #[inline]
+                    fn to_native(self) -> Self::Native {
+                        self as Self::Native
+                    }
+                }
+            )*
+        };
+    }
+
+    macro_rules! from_to_native_wasm_type_same_size {
+        ( $( $type:ty => $native_type:ty ),* ) => {
+            $(
+                #[allow(clippy::use_self)]
+                unsafe impl FromToNativeWasmType for $type {
+                    type Native = $native_type;
+
+                    #[inline]
+                    fn from_native(native: Self::Native) -> Self {
+                        Self::from_ne_bytes(Self::Native::to_ne_bytes(native))
+                    }
+
+                    #[inline]
+                    fn to_native(self) -> Self::Native {
+                        Self::Native::from_ne_bytes(Self::to_ne_bytes(self))
+                    }
+                }
+            )*
+        };
+    }
+
+    from_to_native_wasm_type!(
+        i8 => i32,
+        u8 => i32,
+        i16 => i32,
+        u16 => i32
+    );
+
+    from_to_native_wasm_type_same_size!(
+        i32 => i32,
+        u32 => i32,
+        i64 => i64,
+        u64 => i64,
+        f32 => f32,
+        f64 => f64
+    );
+
+    /// The `WasmTypeList` trait represents a tuple (list) of Wasm
+    /// typed values. It is used to get low-level representation of
+    /// such a tuple.
+    ///
+    /// With the `js` backend, there's no C representation of the
+    /// tuple: the values cross the JavaScript boundary one by one.
+    pub trait WasmTypeList
+    where
+        Self: Sized,
+    {
+        /// The array type that can hold all the represented values.
+        ///
+        /// Note that all values are stored in their binary form.
+        type Array: AsMut<[i128]>;
+
+        /// Constructs `Self` based on an array of values.
+        fn from_array(array: Self::Array) -> Self;
+
+        /// Constructs `Self` based on a slice of values.
+        ///
+        /// `from_slice` returns a `Result` because it is possible
+        /// that the slice doesn't have the same size than
+        /// `Self::Array`, in which circumstance an error of kind
+        /// `TryFromSliceError` will be returned.
+        fn from_slice(slice: &[i128]) -> Result<Self, TryFromSliceError>;
+
+        /// Builds and returns an array of type `Array` from a tuple
+        /// (list) of values.
+        fn into_array(self) -> Self::Array;
+
+        /// Allocates and return an empty array of type `Array` that
+        /// will hold a tuple (list) of values, usually to hold the
+        /// returned values of a WebAssembly function call.
+        fn empty_array() -> Self::Array;
+
+        /// Get the Wasm types for the tuple (list) of currently
+        /// represented values.
+        fn wasm_types() -> &'static [Type];
+    }
+
+    /// Converts the binary form of values of types `types` to values.
+    pub(crate) fn vals_from_array(array: &[i128], types: &[Type]) -> Vec<Val> {
+        array
+            .iter()
+            .zip(types)
+            .map(|(binary, ty)| match ty {
+                Type::I32 => Val::I32(i32::from_binary(*binary)),
+                Type::I64 => Val::I64(i64::from_binary(*binary)),
+                Type::F32 => Val::F32(f32::from_binary(*binary)),
+                Type::F64 => Val::F64(f64::from_binary(*binary)),
+                ty => unreachable!("`{:?}` isn't a native type", ty),
+            })
+            .collect()
+    }
+
+    /// Converts values to their binary form, checking that they are of
+    /// types `types`.
+    pub(crate) fn array_from_vals(vals: &[Val], types: &[Type]) -> Result<Vec<i128>, RuntimeError> {
+        if vals.len() != types.len() {
+            return Err(RuntimeError::new(format!(
+                "expected {} values, got {}",
+                types.len(),
+                vals.len()
+            )));
+        }
+        vals.iter()
+            .zip(types)
+            .map(|(val, ty)| match (val, ty) {
+                (Val::I32(value), Type::I32) => Ok(value.to_binary()),
+                (Val::I64(value), Type::I64) => Ok(value.to_binary()),
+                (Val::F32(value), Type::F32) => Ok(value.to_binary()),
+                (Val::F64(value), Type::F64) => Ok(value.to_binary()),
+                (val, ty) => Err(RuntimeError::new(format!(
+                    "expected a value of type `{:?}`, got `{:?}`",
+                    ty,
+                    val.ty()
+                ))),
+            })
+            .collect()
+    }
+
+    /// The `IntoResult` trait turns a `WasmTypeList` into a
+    /// `Result<WasmTypeList, Self::Error>`.
+    ///
+    /// It is mostly used to turn result values of a Wasm function
+    /// call into a `Result`.
+    pub trait IntoResult<T>
+    where
+        T: WasmTypeList,
+    {
+        /// The error type for this trait.
+        type Error: Error + Sync + Send + 'static;
+
+        /// Transforms `Self` into a `Result`.
+        fn into_result(self) -> Result<T, Self::Error>;
+    }
+
+    impl<T> IntoResult<T> for T
+    where
+        T: WasmTypeList,
+    {
+        // `T` is not a `Result`, it's already a value, so no error
+        // can be built.
+        type Error = Infallible;
+
+        fn into_result(self) -> Result<Self, Infallible> {
+            Ok(self)
+        }
+    }
+
+    impl<T, E> IntoResult<T> for Result<T, E>
+    where
+        T: WasmTypeList,
+        E: Error + Sync + Send + 'static,
+    {
+        type Error = E;
+
+        fn into_result(self) -> Self {
+            self
+        }
+    }
+
+    /// Converts the error of a host function to the exception thrown
+    /// to JavaScript.
+    fn into_runtime_error<E: Error + Sync + Send + 'static>(error: E) -> RuntimeError {
+        let error: Box<dyn Error + Sync + Send> = Box::new(error);
+        match error.downcast::<RuntimeError>() {
+            Ok(error) => *error,
+            Err(error) => RuntimeError::new(error.to_string()),
+        }
+    }
+
+    /// The `HostFunction` trait represents the set of functions that
+    /// can be used as host function.
+    ///
+    /// With the `js` backend, the function is called by the JavaScript
+    /// host with the values of its parameters.
+    pub trait HostFunction<Args, Rets, Kind, T>: 'static
+    where
+        Args: WasmTypeList,
+        Rets: WasmTypeList,
+        Kind: HostFunctionKind,
+        T: Sized,
+        Self: Sized,
+    {
+        /// Call the function with its environment, if it has one, and the
+        /// values of its parameters.
+        #[doc(hidden)]
+        fn call_with_vals(&self, env: Option<&T>, params: &[Val])
+            -> Result<Vec<Val>, RuntimeError>;
+    }
+
+    /// Empty trait to specify the kind of `HostFunction`: With or
+    /// without an environment.
+    ///
+    /// This trait is never aimed to be used by a user. It is used by
+    /// the trait system to automatically generate the appropriate
+    /// host functions.
+    #[doc(hidden)]
+    pub trait HostFunctionKind {}
+
+    /// An empty struct to help Rust typing to determine
+    /// when a `HostFunction` does have an environment.
+    pub struct WithEnv;
+
+    impl HostFunctionKind for WithEnv {}
+
+    /// An empty struct to help Rust typing to determine
+    /// when a `HostFunction` does not have an environment.
+    pub struct WithoutEnv;
+
+    impl HostFunctionKind for WithoutEnv {}
+
+    macro_rules! impl_host_function {
+        ( $( $x:ident ),* ) => {
+            // Implement `WasmTypeList` for a specific tuple.
+            #[allow(unused_parens, dead_code)]
+            impl< $( $x ),* >
+                WasmTypeList
+            for
+                ( $( $x ),* )
+            where
+                $( $x: FromToNativeWasmType ),*
+            {
+                type Array = [i128; count_idents!( $( $x ),* )];
+
+                fn from_array(array: Self::Array) -> Self {
+                    // Unpack items of the array.
+                    #[allow(non_snake_case)]
+                    let [ $( $x ),* ] = array;
+
+                    // Build the tuple.
+                    (
+                        $(
+                            FromToNativeWasmType::from_native(NativeWasmType::from_binary($x))
+                        ),*
+                    )
+                }
+
+                fn from_slice(slice: &[i128]) -> Result<Self, TryFromSliceError> {
+                    Ok(Self::from_array(slice.try_into()?))
+                }
+
+                fn into_array(self) -> Self::Array {
+                    // Unpack items of the tuple.
+                    #[allow(non_snake_case)]
+                    let ( $( $x ),* ) = self;
+
+                    // Build the array.
+                    [
+                        $(
+                            FromToNativeWasmType::to_native($x).to_binary()
+                        ),*
+                    ]
+                }
+
+                fn empty_array() -> Self::Array {
+                    // Build an array initialized with `0`.
+                    [0; count_idents!( $( $x ),* )]
+                }
+
+                fn wasm_types() -> &'static [Type] {
+                    &[
+                        $(
+                            $x::Native::WASM_TYPE
+                        ),*
+                    ]
+                }
+            }
+
+            // Implement `HostFunction` for a function that has the same arity than the tuple.
+            // This specific function has no environment.
+            #[allow(unused_parens)]
+            impl< $( $x, )* Rets, RetsAsResult, Func >
+                HostFunction<( $( $x ),* ), Rets, WithoutEnv, ()>
+            for
+                Func
+            where
+                $( $x: FromToNativeWasmType, )*
+                Rets: WasmTypeList,
+                RetsAsResult: IntoResult<Rets>,
+                Func: Fn($( $x , )*) -> RetsAsResult + 'static + Send,
+            {
+                #[allow(non_snake_case)]
+                fn call_with_vals(&self, _env: Option<&()>, params: &[Val]) -> Result<Vec<Val>, RuntimeError> {
+                    let args = array_from_vals(params, <( $( $x ),* )>::wasm_types())?;
+                    let ( $( $x ),* ) = <( $( $x ),* )>::from_slice(&args)
+                        .expect("the number of arguments was checked");
+                    let results = self( $( $x ),* ).into_result().map_err(into_runtime_error)?;
+                    Ok(vals_from_array(results.into_array().as_mut(), Rets::wasm_types()))
+                }
+            }
+
+            // Implement `HostFunction` for a function that has the same arity than the tuple.
+            // This specific function has an environment.
+            #[allow(unused_parens)]
+            impl< $( $x, )* Rets, RetsAsResult, Env, Func >
+                HostFunction<( $( $x ),* ), Rets, WithEnv, Env>
+            for
+                Func
+            where
+                $( $x: FromToNativeWasmType, )*
+                Rets: WasmTypeList,
+                RetsAsResult: IntoResult<Rets>,
+                Env: Sized,
+                Func: Fn(&Env, $( $x , )*) -> RetsAsResult + Send + 'static,
+            {
+                #[allow(non_snake_case)]
+                fn call_with_vals(&self, env: Option<&Env>, params: &[Val]) -> Result<Vec<Val>, RuntimeError> {
+                    let env = env.expect("the function is called with its environment");
+                    let args = array_from_vals(params, <( $( $x ),* )>::wasm_types())?;
+                    let ( $( $x ),* ) = <( $( $x ),* )>::from_slice(&args)
+                        .expect("the number of arguments was checked");
+                    let results = self(env, $( $x ),* ).into_result().map_err(into_runtime_error)?;
+                    Ok(vals_from_array(results.into_array().as_mut(), Rets::wasm_types()))
+                }
+            }
+        };
+    }
+
+    // Black-magic to count the number of identifiers at compile-time.
+    macro_rules! count_idents {
+        ( $($idents:ident),* ) => {
+            {
+                #[allow(dead_code, non_camel_case_types)]
+                enum Idents { $( $idents, )* __CountIdentsLast }
+                const COUNT: usize = Idents::__CountIdentsLast as usize;
+                COUNT
+            }
+        };
+    }
+
+    // Here we go! Let's generate all the `WasmTypeList` and
+    // `HostFunction` implementations.
+    impl_host_function!();
+    impl_host_function!(A1);
+    impl_host_function!(A1, A2);
+    impl_host_function!(A1, A2, A3);
+    impl_host_function!(A1, A2, A3, A4);
+    impl_host_function!(A1, A2, A3, A4, A5);
+    impl_host_function!(A1, A2, A3, A4, A5, A6);
+    impl_host_function!(A1, A2, A3, A4, A5, A6, A7);
+    impl_host_function!(A1, A2, A3, A4, A5, A6, A7, A8);
+    impl_host_function!(A1, A2, A3, A4, A5, A6, A7, A8, A9);
+    impl_host_function!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10);
+    impl_host_function!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11);
+    impl_host_function!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12);
+    impl_host_function!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13);
+    impl_host_function!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14);
+    impl_host_function!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15);
+    impl_host_function!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16);
+    impl_host_function!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16, A17);
+    impl_host_function!(
+        A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16, A17, A18
+    );
+    impl_host_function!(
+        A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16, A17, A18, A19
+    );
+    impl_host_function!(
+        A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16, A17, A18, A19, A20
+    );
+    impl_host_function!(
+        A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16, A17, A18, A19, A20,
+        A21
+    );
+    impl_host_function!(
+        A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16, A17, A18, A19, A20,
+        A21, A22
+    );
+    impl_host_function!(
+        A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16, A17, A18, A19, A20,
+        A21, A22, A23
+    );
+    impl_host_function!(
+        A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16, A17, A18, A19, A20,
+        A21, A22, A23, A24
+    );
+    impl_host_function!(
+        A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16, A17, A18, A19, A20,
+        A21, A22, A23, A24, A25
+    );
+    impl_host_function!(
+        A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16, A17, A18, A19, A20,
+        A21, A22, A23, A24, A25, A26
+    );
+
+    // Implement `WasmTypeList` on `Infallible`, which means that
+    // `Infallible` can be used as a returned type of a host function
+    // to express that it doesn't return, or to express that it cannot
+    // fail (with `Result<_, Infallible>`).
+    impl WasmTypeList for Infallible {
+        type Array = [i128; 0];
+
+        fn from_array(_: Self::Array) -> Self {
+            unreachable!()
+        }
+
+        fn from_slice(_: &[i128]) -> Result<Self, TryFromSliceError> {
+            unreachable!()
+        }
+
+        fn into_array(self) -> Self::Array {
+            []
+        }
+
+        fn empty_array() -> Self::Array {
+            unreachable!()
+        }
+
+        fn wasm_types() -> &'static [Type] {
+            &[]
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use crate::js::exports::{ExportError, Exportable};
+use crate::js::externals::Extern;
+use crate::js::store::Store;
+use crate::js::trap::RuntimeError;
+use crate::js::types::{type_to_js_name, val_from_js, val_to_js, Val};
+use js_sys::{Object, Reflect};
+use std::fmt;
+use wasm_bindgen::prelude::*;
+use wasmer_types::{GlobalType, Mutability};
+
+#[wasm_bindgen]
+extern "C" {
+    /// `js-sys` doesn't bind `WebAssembly.Global` yet.
+    #[wasm_bindgen(js_namespace = WebAssembly, js_name = Global, extends = Object)]
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub(crate) type JsGlobal;
+
+    #[wasm_bindgen(constructor, js_namespace = WebAssembly, js_class = "Global", catch)]
+    fn new(descriptor: &Object, value: &JsValue) -> Result<JsGlobal, JsValue>;
+
+    #[wasm_bindgen(method, getter, js_namespace = WebAssembly, js_class = "Global")]
+    fn value(this: &JsGlobal) -> JsValue;
+
+    #[wasm_bindgen(method, setter, js_namespace = WebAssembly, js_class = "Global", catch)]
+    fn set_value(this: &JsGlobal, value: &JsValue) -> Result<(), JsValue>;
+}
+
+/// A WebAssembly `global` instance.
+///
+/// A global instance is the runtime representation of a global variable.
+/// It consists of an individual value and a flag indicating whether it is mutable.
+///
+/// With the `js` backend, it wraps a `WebAssembly.Global`.
+///
+/// Spec: <https://webassembly.github.io/spec/core/exec/runtime.html#global-instances>
+#[derive(Clone, PartialEq)]
+pub struct Global {
+    store: Store,
+    ty: GlobalType,
+    global: JsGlobal,
+}
+
+impl Global {
+    /// Create a new `Global` with the initial value [`Val`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value can't be passed to the JavaScript host,
+    /// e.g. an `externref`.
+    pub fn new(store: &Store, val: Val) -> Self {
+        Self::from_value(store, val, Mutability::Const).unwrap()
+    }
+
+    /// Create a mutable `Global` with the initial value [`Val`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value can't be passed to the JavaScript host,
+    /// e.g. an `externref`.
+    pub fn new_mut(store: &Store, val: Val) -> Self {
+        Self::from_value(store, val, Mutability::Var).unwrap()
+    }
+
+    /// Create a `Global` with the initial value [`Val`] and the provided [`Mutability`].
+    fn from_value(store: &Store, val: Val, mutability: Mutability) -> Result<Self, RuntimeError> {
+        let ty = GlobalType::new(val.ty(), mutability);
+        let descriptor = Object::new();
+        Reflect::set(&descriptor, &"value".into(), &type_to_js_name(ty.ty).into())?;
+        Reflect::set(
+            &descriptor,
+            &"mutable".into(),
+            &mutability.is_mutable().into(),
+        )?;
+        let global = JsGlobal::new(&descriptor, &val_to_js(&val)?)?;
+
+        Ok(Self {
+            store: store.clone(),
+            ty,
+            global,
+        })
+    }
+
+    pub(crate) fn from_js(store: &Store, global: JsGlobal, ty: GlobalType) -> Self {
+        Self {
+            store: store.clone(),
+            ty,
+            global,
+        }
+    }
+
+    /// Returns the [`GlobalType`] of the `Global`.
+    pub fn ty(&self) -> &GlobalType {
+        &self.ty
+    }
+
+    /// Returns the [`Store`] where the `Global` belongs.
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    /// Retrieves the current value [`Val`] that the Global has.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value can't be received from the JavaScript host,
+    /// e.g. an `externref`.
+    pub fn get(&self) -> Val {
+        val_from_js(self.ty.ty, &self.global.value()).unwrap()
+    }
+
+    /// Sets a custom value [`Val`] to the runtime Global.
+    ///
+    /// # Errors
+    ///
+    /// Trying to mutate a immutable global will raise an error, as
+    /// trying to set a value of a different type.
+    pub fn set(&self, val: Val) -> Result<(), RuntimeError> {
+        if !self.ty().mutability.is_mutable() {
+            return Err(RuntimeError::new(
+                "Attempted to set an immutable global".to_string(),
+            ));
+        }
+        if val.ty() != self.ty().ty {
+            return Err(RuntimeError::new(format!(
+                "Attempted to operate on a global of type {expected} as a global of type {found}",
+                expected = self.ty().ty,
+                found = val.ty(),
+            )));
+        }
+        self.global.set_value(&val_to_js(&val)?)?;
+        Ok(())
+    }
+
+    /// Returns whether or not these two globals refer to the same data.
+    pub fn same(&self, other: &Self) -> bool {
+        self.global == other.global
+    }
+
+    pub(crate) fn as_js(&self) -> &JsValue {
+        self.global.as_ref()
+    }
+}
+
+impl fmt::Debug for Global {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("Global")
+            .field("ty", &self.ty())
+            .field("value", &self.get())
+            .finish()
+    }
+}
+
+impl<'a> Exportable<'a> for Global {
+    fn get_self_from_extern(_extern: &'a Extern) -> Result<&'a Self, ExportError> {
+        match _extern {
+            Extern::Global(global) => Ok(global),
+            _ => Err(ExportError::IncompatibleType),
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use crate::js::exports::{ExportError, Exportable};
+use crate::js::externals::Extern;
+use crate::js::store::Store;
+use crate::js::trap::RuntimeError;
+use js_sys::{Object, Reflect, Uint8Array, WebAssembly};
+use std::fmt;
+use thiserror::Error;
+use wasm_bindgen::prelude::*;
+use wasmer_types::{MemoryType, Pages, WASM_MAX_PAGES, WASM_PAGE_SIZE};
+
+/// Error type describing things that can go wrong when operating on Wasm Memories.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum MemoryError {
+    /// The operation would cause the size of the memory to exceed the maximum or would cause
+    /// an overflow leading to unindexable memory.
+    #[error("The memory could not grow: current size {} pages, requested increase: {} pages", current.0, attempted_delta.0)]
+    CouldNotGrow {
+        /// The current size in pages.
+        current: Pages,
+        /// The attempted amount to grow by in pages.
+        attempted_delta: Pages,
+    },
+    /// A user defined error value, used for error cases not listed above.
+    #[error("A user-defined error occurred: {0}")]
+    Generic(String),
+}
+
+/// An error that can occur when reading from or writing to a [`Memory`]
+/// with the bulk access methods.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryAccessError {
+    /// The access would read or write past the end of the memory.
+    #[error("out of bounds memory access")]
+    HeapOutOfBounds,
+    /// The offset or the length of the access overflows the address space.
+    #[error("address calculation overflow")]
+    Overflow,
+}
+
+/// A WebAssembly `memory` instance.
+///
+/// A memory instance is the runtime representation of a linear memory.
+/// It consists of a vector of bytes and an optional maximum size.
+///
+/// With the `js` backend, it wraps a `WebAssembly.Memory`. Its bytes
+/// are only accessed by copy, with [`Memory::read`] and
+/// [`Memory::write`], as the buffer is detached when the memory grows.
+///
+/// Spec: <https://webassembly.github.io/spec/core/exec/runtime.html#memory-instances>
+#[derive(Clone, PartialEq)]
+pub struct Memory {
+    store: Store,
+    ty: MemoryType,
+    memory: WebAssembly::Memory,
+}
+
+impl Memory {
+    /// Creates a new host `Memory` from the provided [`MemoryType`].
+    pub fn new(store: &Store, ty: MemoryType) -> Result<Self, MemoryError> {
+        let descriptor = Object::new();
+        let set = |key: &str, value: JsValue| {
+            Reflect::set(&descriptor, &key.into(), &value)
+                .map(|_| ())
+                .map_err(|error| MemoryError::Generic(RuntimeError::from(error).message()))
+        };
+        set("initial", ty.minimum.0.into())?;
+        if let Some(maximum) = ty.maximum {
+            set("maximum", maximum.0.into())?;
+        }
+        if ty.shared {
+            set("shared", true.into())?;
+        }
+        let memory = WebAssembly::Memory::new(&descriptor)
+            .map_err(|error| MemoryError::Generic(RuntimeError::from(error).message()))?;
+
+        Ok(Self {
+            store: store.clone(),
+            ty,
+            memory,
+        })
+    }
+
+    pub(crate) fn from_js(store: &Store, memory: WebAssembly::Memory, ty: MemoryType) -> Self {
+        Self {
+            store: store.clone(),
+            ty,
+            memory,
+        }
+    }
+
+    /// Returns the [`MemoryType`] of the `Memory`.
+    pub fn ty(&self) -> &MemoryType {
+        &self.ty
+    }
+
+    /// Returns the [`Store`] where the `Memory` belongs.
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    /// Returns the size (in bytes) of the `Memory`.
+    pub fn data_size(&self) -> u64 {
+        self.view().length().into()
+    }
+
+    /// Returns the size (in [`Pages`]) of the `Memory`.
+    pub fn size(&self) -> Pages {
+        Pages((self.data_size() / WASM_PAGE_SIZE as u64) as u32)
+    }
+
+    /// Grow memory by the specified amount of WebAssembly [`Pages`] and return
+    /// the previous memory size.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if memory can't be grown by the specified amount
+    /// of pages.
+    pub fn grow<IntoPages>(&self, delta: IntoPages) -> Result<Pages, MemoryError>
+    where
+        IntoPages: Into<Pages>,
+    {
+        let delta = delta.into();
+        let current = self.size();
+        let maximum = self.ty.maximum.unwrap_or(Pages(WASM_MAX_PAGES));
+        // `WebAssembly.Memory.prototype.grow` throws a `RangeError` past
+        // the maximum, which isn't caught by `js-sys`.
+        match current.0.checked_add(delta.0) {
+            Some(new_size) if new_size <= maximum.0 => Ok(Pages(self.memory.grow(delta.0))),
+            _ => Err(MemoryError::CouldNotGrow {
+                current,
+                attempted_delta: delta,
+            }),
+        }
+    }
+
+    /// Copies the memory into `buf`, starting at `offset`.
+    ///
+    /// The whole `buf` is filled, or nothing is read and an error is
+    /// returned if the range is not within the memory.
+    pub fn read(&self, offset: u64, buf: &mut [u8]) -> Result<(), MemoryAccessError> {
+        self.checked_subarray(offset, buf.len())?.copy_to(buf);
+        Ok(())
+    }
+
+    /// Copies the `len` bytes of the memory starting at `offset` into a
+    /// new vector.
+    pub fn read_vec(&self, offset: u64, len: usize) -> Result<Vec<u8>, MemoryAccessError> {
+        Ok(self.checked_subarray(offset, len)?.to_vec())
+    }
+
+    /// Copies `buf` into the memory, starting at `offset`.
+    ///
+    /// The whole `buf` is written, or nothing is written and an error is
+    /// returned if the range is not within the memory.
+    pub fn write(&self, offset: u64, buf: &[u8]) -> Result<(), MemoryAccessError> {
+        self.checked_subarray(offset, buf.len())?.copy_from(buf);
+        Ok(())
+    }
+
+    /// Returns whether or not these two memories refer to the same data.
+    pub fn same(&self, other: &Self) -> bool {
+        self.memory == other.memory
+    }
+
+    /// Returns a view of the current buffer of the memory, which is
+    /// detached when the memory grows.
+    fn view(&self) -> Uint8Array {
+        Uint8Array::new(&self.memory.buffer())
+    }
+
+    /// Returns a view of the `len` bytes starting at `offset`, after
+    /// checking that they are within the memory.
+    fn checked_subarray(&self, offset: u64, len: usize) -> Result<Uint8Array, MemoryAccessError> {
+        let view = self.view();
+        let end = offset
+            .checked_add(len as u64)
+            .ok_or(MemoryAccessError::Overflow)?;
+        if end > view.length().into() {
+            return Err(MemoryAccessError::HeapOutOfBounds);
+        }
+        Ok(view.subarray(offset as u32, end as u32))
+    }
+
+    pub(crate) fn as_js(&self) -> &JsValue {
+        self.memory.as_ref()
+    }
+}
+
+impl fmt::Debug for Memory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Memory")
+            .field("ty", &self.ty)
+            .field("size", &self.size())
+            .finish()
+    }
+}
+
+impl<'a> Exportable<'a> for Memory {
+    fn get_self_from_extern(_extern: &'a Extern) -> Result<&'a Self, ExportError> {
+        match _extern {
+            Extern::Memory(memory) => Ok(memory),
+            _ => Err(ExportError::IncompatibleType),
+        }
+    }
+}
@@ -0,0 +1,133 @@
+mod function;
+mod global;
+mod memory;
+mod table;
+
+pub(crate) use self::function::{array_from_vals, vals_from_array};
+pub use self::function::{
+    FromToNativeWasmType, Function, HostFunction, WasmTypeList, WithEnv, WithoutEnv,
+};
+pub use self::global::Global;
+pub use self::memory::{Memory, MemoryAccessError, MemoryError};
+pub use self::table::Table;
+
+use crate::js::exports::{ExportError, Exportable};
+use crate::js::store::Store;
+use crate::js::trap::RuntimeError;
+use std::fmt;
+use wasm_bindgen::{JsCast, JsValue};
+use wasmer_types::ExternType;
+
+/// An `Extern` is the runtime representation of an entity that
+/// can be imported or exported.
+///
+/// Spec: <https://webassembly.github.io/spec/core/exec/runtime.html#external-values>
+#[derive(Clone)]
+pub enum Extern {
+    /// A external [`Function`].
+    Function(Function),
+    /// A external [`Global`].
+    Global(Global),
+    /// A external [`Table`].
+    Table(Table),
+    /// A external [`Memory`].
+    Memory(Memory),
+}
+
+impl Extern {
+    /// Return the underlying type of the inner `Extern`.
+    pub fn ty(&self) -> ExternType {
+        match self {
+            Self::Function(ft) => ExternType::Function(ft.ty().clone()),
+            Self::Memory(ft) => ExternType::Memory(*ft.ty()),
+            Self::Table(tt) => ExternType::Table(*tt.ty()),
+            Self::Global(gt) => ExternType::Global(*gt.ty()),
+        }
+    }
+
+    /// Create an `Extern` from a JavaScript export of type `ty`.
+    pub(crate) fn from_js(
+        store: &Store,
+        value: JsValue,
+        ty: &ExternType,
+    ) -> Result<Self, RuntimeError> {
+        let unexpected = || RuntimeError::new(format!("expected {:?}, got {:?}", ty, value));
+
+        Ok(match ty {
+            ExternType::Function(ty) => Self::Function(Function::from_js(
+                store,
+                value.clone().dyn_into().map_err(|_| unexpected())?,
+                ty.clone(),
+            )),
+            ExternType::Global(ty) => {
+                Self::Global(Global::from_js(store, value.clone().unchecked_into(), *ty))
+            }
+            ExternType::Memory(ty) => Self::Memory(Memory::from_js(
+                store,
+                value.clone().dyn_into().map_err(|_| unexpected())?,
+                *ty,
+            )),
+            ExternType::Table(ty) => Self::Table(Table::from_js(
+                store,
+                value.clone().dyn_into().map_err(|_| unexpected())?,
+                *ty,
+            )),
+        })
+    }
+
+    /// The JavaScript value of the `Extern`, e.g. to import it.
+    pub(crate) fn as_js(&self) -> &JsValue {
+        match self {
+            Self::Function(function) => function.as_js(),
+            Self::Global(global) => global.as_js(),
+            Self::Memory(memory) => memory.as_js(),
+            Self::Table(table) => table.as_js(),
+        }
+    }
+}
+
+impl<'a> Exportable<'a> for Extern {
+    fn get_self_from_extern(_extern: &'a Self) -> Result<&'a Self, ExportError> {
+        // Since this is already an extern, we can just return it.
+        Ok(_extern)
+    }
+}
+
+impl fmt::Debug for Extern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Function(_) => "Function(...)",
+                Self::Global(_) => "Global(...)",
+                Self::Memory(_) => "Memory(...)",
+                Self::Table(_) => "Table(...)",
+            }
+        )
+    }
+}
+
+impl From<Function> for Extern {
+    fn from(r: Function) -> Self {
+        Self::Function(r)
+    }
+}
+
+impl From<Global> for Extern {
+    fn from(r: Global) -> Self {
+        Self::Global(r)
+    }
+}
+
+impl From<Memory> for Extern {
+    fn from(r: Memory) -> Self {
+        Self::Memory(r)
+    }
+}
+
+impl From<Table> for Extern {
+    fn from(r: Table) -> Self {
+        Self::Table(r)
+    }
+}
@@ -0,0 +1,156 @@
+use crate::js::exports::{ExportError, Exportable};
+use crate::js::externals::Extern;
+use crate::js::store::Store;
+use crate::js::trap::RuntimeError;
+use crate::js::types::{type_to_js_name, Val};
+use js_sys::{Function as JsFunction, Object, Reflect, WebAssembly};
+use std::fmt;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasmer_types::{ExternRef, TableType, Type};
+
+/// A WebAssembly `table` instance.
+///
+/// The `Table` struct is an array-like structure representing a WebAssembly Table,
+/// which stores function references.
+///
+/// With the `js` backend, it wraps a `WebAssembly.Table`. The elements
+/// can be set but not read back, as the type of a function stored by
+/// JavaScript is unknown.
+///
+/// Spec: <https://webassembly.github.io/spec/core/exec/runtime.html#table-instances>
+#[derive(Clone, PartialEq)]
+pub struct Table {
+    store: Store,
+    ty: TableType,
+    table: WebAssembly::Table,
+}
+
+/// Converts a table element to the value stored by JavaScript: a
+/// function or `null`.
+fn element_to_js(val: &Val) -> Result<JsValue, RuntimeError> {
+    match val {
+        Val::FuncRef(function) => Ok(function.as_js().clone()),
+        Val::ExternRef(ExternRef::Null) => Ok(JsValue::NULL),
+        _ => Err(RuntimeError::new(format!(
+            "expected a function reference as a table element, got {:?}",
+            val.ty()
+        ))),
+    }
+}
+
+impl Table {
+    /// Creates a new `Table` with the provided [`TableType`] definition.
+    ///
+    /// All the elements in the table will be set to the `init` value.
+    pub fn new(store: &Store, ty: TableType, init: Val) -> Result<Self, RuntimeError> {
+        if ty.ty != Type::FuncRef {
+            return Err(RuntimeError::new(
+                "only tables of function references are supported",
+            ));
+        }
+
+        let descriptor = Object::new();
+        Reflect::set(
+            &descriptor,
+            &"element".into(),
+            &type_to_js_name(ty.ty).into(),
+        )?;
+        Reflect::set(&descriptor, &"initial".into(), &ty.minimum.into())?;
+        if let Some(maximum) = ty.maximum {
+            Reflect::set(&descriptor, &"maximum".into(), &maximum.into())?;
+        }
+        let table = WebAssembly::Table::new(&descriptor)?;
+        let table = Self {
+            store: store.clone(),
+            ty,
+            table,
+        };
+
+        for index in 0..table.size() {
+            table.set(index, init.clone())?;
+        }
+
+        Ok(table)
+    }
+
+    pub(crate) fn from_js(store: &Store, table: WebAssembly::Table, ty: TableType) -> Self {
+        Self {
+            store: store.clone(),
+            ty,
+            table,
+        }
+    }
+
+    /// Returns the [`TableType`] of the `Table`.
+    pub fn ty(&self) -> &TableType {
+        &self.ty
+    }
+
+    /// Returns the [`Store`] where the `Table` belongs.
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    /// Sets an element `val` in the Table at the provided `index`.
+    pub fn set(&self, index: u32, val: Val) -> Result<(), RuntimeError> {
+        let element = element_to_js(&val)?;
+        self.table
+            .set(index, element.unchecked_ref::<JsFunction>())?;
+        Ok(())
+    }
+
+    /// Retrieves the size of the `Table` (in elements)
+    pub fn size(&self) -> u32 {
+        self.table.length()
+    }
+
+    /// Grows the size of the `Table` by `delta`, initializating
+    /// the elements with the provided `init` value.
+    ///
+    /// It returns the previous size of the `Table` in case is able
+    /// to grow the Table successfully.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `delta` is out of bounds for the table.
+    pub fn grow(&self, delta: u32, init: Val) -> Result<u32, RuntimeError> {
+        let element = element_to_js(&init)?;
+        let len = self
+            .table
+            .grow(delta)
+            .map_err(|_| RuntimeError::new(format!("failed to grow table by `{}`", delta)))?;
+        for index in len..len + delta {
+            self.table
+                .set(index, element.unchecked_ref::<JsFunction>())?;
+        }
+        Ok(len)
+    }
+
+    /// Returns whether or not these two tables refer to the same data.
+    pub fn same(&self, other: &Self) -> bool {
+        self.table == other.table
+    }
+
+    pub(crate) fn as_js(&self) -> &JsValue {
+        self.table.as_ref()
+    }
+}
+
+impl fmt::Debug for Table {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Table")
+            .field("ty", &self.ty)
+            .field("size", &self.size())
+            .finish()
+    }
+}
+
+impl<'a> Exportable<'a> for Table {
+    fn get_self_from_extern(_extern: &'a Extern) -> Result<&'a Self, ExportError> {
+        match _extern {
+            Extern::Table(table) => Ok(table),
+            _ => Err(ExportError::IncompatibleType),
+        }
+    }
+}
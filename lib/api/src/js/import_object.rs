@@ -0,0 +1,60 @@
+use crate::js::exports::Exports;
+use crate::js::externals::Extern;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fmt;
+
+/// All of the import data used when instantiating.
+///
+/// It's suggested that you use the [`imports!`] macro
+/// instead of creating an `ImportObject` by hand.
+///
+/// [`imports!`]: macro.imports.html
+///
+/// With the `js` backend, the namespaces are [`Exports`], which
+/// are turned into the imports object of the JavaScript host on
+/// instantiation.
+#[derive(Clone, Default)]
+pub struct ImportObject {
+    map: HashMap<String, Exports>,
+}
+
+impl ImportObject {
+    /// Create a new `ImportObject`.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Gets an export given a module and a name
+    pub fn get_export(&self, module: &str, name: &str) -> Option<Extern> {
+        self.map.get(module)?.get_extern(name).cloned()
+    }
+
+    /// Returns true if the ImportObject contains namespace with the provided name.
+    pub fn contains_namespace(&self, name: &str) -> bool {
+        self.map.contains_key(name)
+    }
+
+    /// Register an [`Exports`] as a namespace, and return the namespace
+    /// previously registered with this name, if any.
+    pub fn register<S>(&mut self, name: S, namespace: Exports) -> Option<Exports>
+    where
+        S: Into<String>,
+    {
+        match self.map.entry(name.into()) {
+            Entry::Vacant(empty) => {
+                empty.insert(namespace);
+                None
+            }
+            Entry::Occupied(mut occupied) => Some(occupied.insert(namespace)),
+        }
+    }
+}
+
+impl fmt::Debug for ImportObject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ImportObject")
+            .field("map", &self.map)
+            .finish()
+    }
+}
@@ -0,0 +1,179 @@
+use crate::env::HostEnvInitError;
+use crate::js::exports::Exports;
+use crate::js::externals::Extern;
+use crate::js::import_object::ImportObject;
+use crate::js::module::Module;
+use crate::js::store::Store;
+use crate::js::trap::RuntimeError;
+use js_sys::{Object, Reflect, WebAssembly};
+use std::collections::HashSet;
+use std::fmt;
+use thiserror::Error;
+use wasm_bindgen::{JsCast, JsValue};
+
+/// A WebAssembly Instance is a stateful, executable
+/// instance of a WebAssembly [`Module`].
+///
+/// Instance objects contain all the exported WebAssembly
+/// functions, memories, tables and globals that allow
+/// interacting with WebAssembly.
+///
+/// With the `js` backend, it is instantiated by the JavaScript host
+/// with `WebAssembly.Instance`.
+///
+/// Spec: <https://webassembly.github.io/spec/core/exec/runtime.html#module-instances>
+#[derive(Clone)]
+pub struct Instance {
+    module: Module,
+    /// The exports for an instance.
+    pub exports: Exports,
+}
+
+/// An error while instantiating a module.
+///
+/// This is not a common WebAssembly error, however
+/// we need to differentiate from a link error (an error
+/// that happens while linking, on instantiation) and a
+/// Trap that occurs when calling the WebAssembly module
+/// start function.
+#[derive(Error, Debug)]
+pub enum InstantiationError {
+    /// A linking ocurred during instantiation.
+    #[error("Link error: {0}")]
+    Link(String),
+
+    /// A runtime error occured while invoking the start function
+    #[error(transparent)]
+    Start(RuntimeError),
+
+    /// Error occurred when initializing the host environment.
+    #[error(transparent)]
+    HostEnvInitialization(HostEnvInitError),
+}
+
+impl From<HostEnvInitError> for InstantiationError {
+    fn from(other: HostEnvInitError) -> Self {
+        Self::HostEnvInitialization(other)
+    }
+}
+
+impl Instance {
+    /// Creates a new `Instance` from a WebAssembly [`Module`] and a
+    /// set of imports from the [`ImportObject`].
+    ///
+    /// ## Errors
+    ///
+    /// The function can return [`InstantiationError`]s.
+    ///
+    /// Those are, as defined by the spec:
+    ///  * Link errors that happen when plugging the imports into the instance
+    ///  * Runtime errors that happen when running the module `start` function.
+    pub fn new(module: &Module, imports: &ImportObject) -> Result<Self, InstantiationError> {
+        let store = module.store();
+        let js_imports = Self::js_imports(module, imports)?;
+        let instance =
+            WebAssembly::Instance::new(module.as_js(), &js_imports).map_err(|error| {
+                if error.is_instance_of::<WebAssembly::LinkError>() {
+                    InstantiationError::Link(RuntimeError::from(error).message())
+                } else {
+                    InstantiationError::Start(RuntimeError::from(error))
+                }
+            })?;
+
+        let js_exports = instance.exports();
+        let exports = module
+            .exports()
+            .map(|export| {
+                let name = export.name().to_string();
+                let value = Reflect::get(&js_exports, &name.as_str().into()).map_err(|error| {
+                    InstantiationError::Link(RuntimeError::from(error).message())
+                })?;
+                let extern_ = Extern::from_js(store, value, export.ty())
+                    .map_err(|error| InstantiationError::Link(error.message()))?;
+                Ok((name, extern_))
+            })
+            .collect::<Result<Exports, InstantiationError>>()?;
+
+        let instance = Self {
+            module: module.clone(),
+            exports,
+        };
+
+        // Like with the native backend, the environments of the imported
+        // host functions are initialized by the first instance importing
+        // them, and `WasmerEnv::finish` is called once per type of env.
+        let environments = module
+            .imports()
+            .filter_map(
+                |import| match imports.get_export(import.module(), import.name()) {
+                    Some(Extern::Function(function)) => function.environment().cloned(),
+                    _ => None,
+                },
+            )
+            .filter(|environment| environment.claim())
+            .collect::<Vec<_>>();
+        for environment in environments.iter() {
+            environment.init_with_instance(&instance)?;
+        }
+        let mut finished = HashSet::new();
+        for environment in environments.iter() {
+            if finished.insert(environment.env_type()) {
+                environment.finish(&instance)?;
+            }
+        }
+
+        Ok(instance)
+    }
+
+    /// Builds the imports object of the JavaScript host, with the
+    /// imports of the module looked up in the [`ImportObject`].
+    fn js_imports(module: &Module, imports: &ImportObject) -> Result<Object, InstantiationError> {
+        let js_imports = Object::new();
+        let link_error =
+            |error: JsValue| InstantiationError::Link(RuntimeError::from(error).message());
+
+        for import in module.imports() {
+            let extern_ = imports
+                .get_export(import.module(), import.name())
+                .ok_or_else(|| {
+                    InstantiationError::Link(format!(
+                        "Error while importing {0:?}.{1:?}: unknown import. Expected {2:?}",
+                        import.module(),
+                        import.name(),
+                        import.ty()
+                    ))
+                })?;
+
+            let namespace_name = JsValue::from(import.module());
+            let namespace = match Reflect::get(&js_imports, &namespace_name).map_err(link_error)? {
+                namespace if namespace.is_undefined() => {
+                    let namespace = Object::new();
+                    Reflect::set(&js_imports, &namespace_name, &namespace).map_err(link_error)?;
+                    namespace
+                }
+                namespace => namespace.unchecked_into(),
+            };
+            Reflect::set(&namespace, &import.name().into(), extern_.as_js()).map_err(link_error)?;
+        }
+
+        Ok(js_imports)
+    }
+
+    /// Gets the [`Module`] associated with this instance.
+    pub fn module(&self) -> &Module {
+        &self.module
+    }
+
+    /// Returns the [`Store`] where the `Instance` belongs.
+    pub fn store(&self) -> &Store {
+        self.module.store()
+    }
+}
+
+impl fmt::Debug for Instance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Instance")
+            .field("exports", &self.exports)
+            .finish()
+    }
+}
@@ -0,0 +1,99 @@
+//! The `js` backend of the API, for `wasm32-unknown-unknown`.
+//!
+//! The modules are compiled and run by the JavaScript WebAssembly API
+//! of the host, e.g. a browser or Node.js, through `wasm-bindgen`. The
+//! backend is enabled with the `js` feature, without the default
+//! features:
+//!
+//! ```toml
+//! [dependencies]
+//! wasmer = { version = "1.0", default-features = false, features = ["js", "wat"] }
+//! ```
+//!
+//! It provides the same API as the native backend for stores, modules,
+//! instances, exports, memories, host functions with an environment
+//! ([`WasmerEnv`] and [`LazyInit`]) and typed functions ([`NativeFunc`]),
+//! so that the embedder code can be shared.
+//!
+//! [`WasmerEnv`]: crate::WasmerEnv
+//! [`LazyInit`]: crate::LazyInit
+//!
+//! # Differences with the native backend
+//!
+//! The following items of the native backend aren't available, as the
+//! JavaScript API gives no way to implement them:
+//!
+//! * `MemoryView`, `WasmPtr`, `Array` and `Item`: the memory isn't in the
+//!   address space of the Rust code, and its buffer is detached when it
+//!   grows. Use [`Memory::read`] and [`Memory::write`], which copy the
+//!   bytes.
+//! * `Engine`, `Tunables`, `BaseTunables`, the compilers, the `JIT` and
+//!   `Native` engines, the artifacts and `Module::serialize`: the host
+//!   compiles the modules, with its own limits. `Store::default()` is the
+//!   only store.
+//! * `Linker`, `Asyncify`, `InstanceSnapshot`, `WeakModule` and
+//!   `ResourceLimiter`: they need access to the instance internals.
+//! * `raise_user_trap` and the `vm` module: the traps are JavaScript
+//!   exceptions. Return a [`RuntimeError`] from the host function instead.
+//! * `Triple`, `CpuFeature` and the other target types: the target is the
+//!   host.
+//!
+//! The values of `externref` and `v128` can't cross the JavaScript
+//! boundary either.
+
+mod exports;
+mod externals;
+mod import_object;
+mod instance;
+mod module;
+mod module_types;
+mod native;
+mod store;
+mod trap;
+mod types;
+
+pub use crate::js::exports::{
+    ExportError, Exportable, ExportableWithGenerics, Exports, ExportsIterator,
+};
+pub use crate::js::externals::{
+    Extern, FromToNativeWasmType, Function, Global, HostFunction, Memory, MemoryAccessError,
+    MemoryError, Table, WasmTypeList,
+};
+pub use crate::js::import_object::ImportObject;
+pub use crate::js::instance::{Instance, InstantiationError};
+pub use crate::js::module::{CompileError, Module};
+pub use crate::js::native::NativeFunc;
+pub use crate::js::store::Store;
+pub use crate::js::trap::RuntimeError;
+pub use crate::js::types::{Val, Val as Value};
+pub use wasmer_types::{
+    Bytes, ExportType, ExternRef, ExternType, FunctionType, GlobalType, ImportType, MemoryType,
+    Mutability, Pages, TableType, Type as ValType, Type, WASM_MAX_PAGES, WASM_MIN_PAGES,
+    WASM_PAGE_SIZE,
+};
+pub use wasmer_types::{NativeWasmType, ValueType};
+
+#[doc(hidden)]
+pub mod internals {
+    //! We use the internals module for exporting types that are only
+    //! intended to use in internal crates. Please don't use any of this
+    //! types directly, as they might change frequently or be removed in
+    //! the future.
+
+    pub use crate::js::externals::{WithEnv, WithoutEnv};
+}
+
+// The JavaScript objects can only be used from the thread of the
+// JavaScript host, which is the only thread of `wasm32-unknown-unknown`,
+// so the externs can be stored in the environments of the host functions,
+// which are `Send + Sync` like with the native backend.
+unsafe impl Send for Function {}
+unsafe impl Sync for Function {}
+unsafe impl Send for Global {}
+unsafe impl Sync for Global {}
+unsafe impl Send for Memory {}
+unsafe impl Sync for Memory {}
+unsafe impl Send for Table {}
+unsafe impl Sync for Table {}
+unsafe impl Send for Module {}
+unsafe impl Sync for Module {}
@@ -0,0 +1,124 @@
+use crate::js::module_types::{read_module_types, ModuleTypes};
+use crate::js::store::Store;
+use crate::js::trap::RuntimeError;
+use js_sys::{Uint8Array, WebAssembly};
+use std::fmt;
+use std::sync::Arc;
+use thiserror::Error;
+use wasmer_types::{ExportType, ImportType};
+
+/// The error that can happen while compiling a module.
+#[derive(Error, Debug)]
+pub enum CompileError {
+    /// The binary of the module can't be parsed, e.g. because the
+    /// text format of the module is malformed.
+    #[error("WebAssembly translation error: {0}")]
+    Wasm(String),
+
+    /// The module is rejected by the JavaScript host.
+    #[error("Validation error: {0}")]
+    Validate(String),
+}
+
+/// A WebAssembly Module contains stateless WebAssembly
+/// code that has already been compiled and can be instantiated
+/// multiple times.
+///
+/// With the `js` backend, it wraps a `WebAssembly.Module`.
+#[derive(Clone)]
+pub struct Module {
+    store: Store,
+    module: WebAssembly::Module,
+    name: Option<String>,
+    types: Arc<ModuleTypes>,
+}
+
+impl Module {
+    /// Creates a new WebAssembly Module given the configuration
+    /// in the store.
+    ///
+    /// If the provided bytes are not WebAssembly-like (start with `b"\0asm"`),
+    /// and the "wat" feature is enabled for this crate, this function will try to
+    /// to convert the bytes assuming they correspond to the WebAssembly text
+    /// format.
+    #[allow(unreachable_code)]
+    pub fn new(store: &Store, bytes: impl AsRef<[u8]>) -> Result<Self, CompileError> {
+        #[cfg(feature = "wat")]
+        let bytes = wat::parse_bytes(bytes.as_ref())
+            .map_err(|e| CompileError::Wasm(format!("Error when converting wat: {}", e)))?;
+
+        Self::from_binary(store, bytes.as_ref())
+    }
+
+    /// Creates a new WebAssembly module from a binary.
+    ///
+    /// Opposed to [`Module::new`], this function is not compatible with
+    /// the WebAssembly text format (if the "wat" feature is enabled for
+    /// this crate).
+    pub fn from_binary(store: &Store, binary: &[u8]) -> Result<Self, CompileError> {
+        let types = read_module_types(binary).map_err(CompileError::Wasm)?;
+        let module = WebAssembly::Module::new(&Uint8Array::from(binary).into())
+            .map_err(|error| CompileError::Validate(RuntimeError::from(error).message()))?;
+
+        Ok(Self {
+            store: store.clone(),
+            module,
+            name: None,
+            types: Arc::new(types),
+        })
+    }
+
+    /// Validates a new WebAssembly Module with the JavaScript host.
+    pub fn validate(_store: &Store, binary: &[u8]) -> Result<(), CompileError> {
+        match WebAssembly::validate(&Uint8Array::from(binary).into()) {
+            Ok(true) => Ok(()),
+            _ => Err(CompileError::Validate(
+                "the module is rejected by the JavaScript host".to_string(),
+            )),
+        }
+    }
+
+    /// Returns the name of the current module.
+    ///
+    /// The name is only set with [`Module::set_name`] with the `js`
+    /// backend, as the name section isn't read.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Sets the name of the current module.
+    ///
+    /// Returns `true` as the module is never shared with the `js`
+    /// backend.
+    pub fn set_name(&mut self, name: &str) -> bool {
+        self.name = Some(name.to_string());
+        true
+    }
+
+    /// Returns an iterator over the imported types in the Module.
+    pub fn imports<'a>(&'a self) -> impl ExactSizeIterator<Item = ImportType> + 'a {
+        self.types.imports.iter().cloned()
+    }
+
+    /// Returns an iterator over the exported types in the Module.
+    pub fn exports<'a>(&'a self) -> impl ExactSizeIterator<Item = ExportType> + 'a {
+        self.types.exports.iter().cloned()
+    }
+
+    /// Returns the [`Store`] where the `Module` belongs.
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    pub(crate) fn as_js(&self) -> &WebAssembly::Module {
+        &self.module
+    }
+}
+
+impl fmt::Debug for Module {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Module")
+            .field("name", &self.name())
+            .finish()
+    }
+}
@@ -0,0 +1,203 @@
+//! The types of the imports and the exports of a module.
+//!
+//! The JavaScript API only gives the kind of the imports and the
+//! exports, so their types are read from the binary of the module.
+
+use wasmer_types::{
+    ExportType, ExternType, FunctionType, GlobalType, ImportType, MemoryType, Mutability,
+    TableType, Type,
+};
+use wasmparser::{
+    BinaryReaderError, ExternalKind, ImportSectionEntryType, Parser, Payload, TypeDef,
+};
+
+/// The types of the imports and the exports of a module.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ModuleTypes {
+    pub(crate) imports: Vec<ImportType>,
+    pub(crate) exports: Vec<ExportType>,
+}
+
+/// The index spaces of a module, filled while reading its sections.
+#[derive(Default)]
+struct IndexSpaces {
+    types: Vec<Option<FunctionType>>,
+    functions: Vec<Option<FunctionType>>,
+    tables: Vec<TableType>,
+    memories: Vec<MemoryType>,
+    globals: Vec<GlobalType>,
+}
+
+impl IndexSpaces {
+    fn function_type(&self, index: u32) -> Result<FunctionType, String> {
+        self.types
+            .get(index as usize)
+            .cloned()
+            .flatten()
+            .ok_or_else(|| format!("unknown function type {}", index))
+    }
+
+    fn push_function(&mut self, type_index: u32) -> Result<ExternType, String> {
+        let ty = self.function_type(type_index)?;
+        self.functions.push(Some(ty.clone()));
+        Ok(ExternType::Function(ty))
+    }
+
+    fn export_type(&self, kind: ExternalKind, index: u32) -> Option<ExternType> {
+        let index = index as usize;
+        match kind {
+            ExternalKind::Function => self
+                .functions
+                .get(index)
+                .cloned()
+                .flatten()
+                .map(ExternType::Function),
+            ExternalKind::Table => self.tables.get(index).cloned().map(ExternType::Table),
+            ExternalKind::Memory => self.memories.get(index).cloned().map(ExternType::Memory),
+            ExternalKind::Global => self.globals.get(index).cloned().map(ExternType::Global),
+            _ => None,
+        }
+    }
+}
+
+fn value_type(ty: wasmparser::Type) -> Result<Type, String> {
+    Ok(match ty {
+        wasmparser::Type::I32 => Type::I32,
+        wasmparser::Type::I64 => Type::I64,
+        wasmparser::Type::F32 => Type::F32,
+        wasmparser::Type::F64 => Type::F64,
+        wasmparser::Type::V128 => Type::V128,
+        wasmparser::Type::FuncRef => Type::FuncRef,
+        wasmparser::Type::ExternRef => Type::ExternRef,
+        ty => return Err(format!("unsupported value type {:?}", ty)),
+    })
+}
+
+fn table_type(ty: wasmparser::TableType) -> Result<TableType, String> {
+    Ok(TableType::new(
+        value_type(ty.element_type)?,
+        ty.limits.initial,
+        ty.limits.maximum,
+    ))
+}
+
+fn memory_type(ty: wasmparser::MemoryType) -> Result<MemoryType, String> {
+    match ty {
+        wasmparser::MemoryType::M32 { limits, shared } => {
+            Ok(MemoryType::new(limits.initial, limits.maximum, shared))
+        }
+        wasmparser::MemoryType::M64 { .. } => Err("64-bit memories aren't supported".to_string()),
+    }
+}
+
+fn global_type(ty: wasmparser::GlobalType) -> Result<GlobalType, String> {
+    Ok(GlobalType::new(
+        value_type(ty.content_type)?,
+        if ty.mutable {
+            Mutability::Var
+        } else {
+            Mutability::Const
+        },
+    ))
+}
+
+fn binary_reader_error(error: BinaryReaderError) -> String {
+    error.message().to_string()
+}
+
+/// Reads the types of the imports and the exports of a module.
+pub(crate) fn read_module_types(binary: &[u8]) -> Result<ModuleTypes, String> {
+    let mut spaces = IndexSpaces::default();
+    let mut module_types = ModuleTypes::default();
+
+    for payload in Parser::new(0).parse_all(binary) {
+        match payload.map_err(binary_reader_error)? {
+            Payload::TypeSection(types) => {
+                for ty in types {
+                    spaces.types.push(match ty.map_err(binary_reader_error)? {
+                        TypeDef::Func(ty) => Some(FunctionType::new(
+                            ty.params
+                                .iter()
+                                .cloned()
+                                .map(value_type)
+                                .collect::<Result<Vec<_>, _>>()?,
+                            ty.returns
+                                .iter()
+                                .cloned()
+                                .map(value_type)
+                                .collect::<Result<Vec<_>, _>>()?,
+                        )),
+                        _ => None,
+                    });
+                }
+            }
+            Payload::ImportSection(imports) => {
+                for import in imports {
+                    let import = import.map_err(binary_reader_error)?;
+                    let ty = match import.ty {
+                        ImportSectionEntryType::Function(index) => spaces.push_function(index)?,
+                        ImportSectionEntryType::Table(ty) => {
+                            let ty = table_type(ty)?;
+                            spaces.tables.push(ty);
+                            ExternType::Table(ty)
+                        }
+                        ImportSectionEntryType::Memory(ty) => {
+                            let ty = memory_type(ty)?;
+                            spaces.memories.push(ty);
+                            ExternType::Memory(ty)
+                        }
+                        ImportSectionEntryType::Global(ty) => {
+                            let ty = global_type(ty)?;
+                            spaces.globals.push(ty);
+                            ExternType::Global(ty)
+                        }
+                        ty => return Err(format!("unsupported import {:?}", ty)),
+                    };
+                    module_types.imports.push(ImportType::new(
+                        import.module,
+                        import.field.unwrap_or_default(),
+                        ty,
+                    ));
+                }
+            }
+            Payload::FunctionSection(functions) => {
+                for type_index in functions {
+                    spaces.push_function(type_index.map_err(binary_reader_error)?)?;
+                }
+            }
+            Payload::TableSection(tables) => {
+                for ty in tables {
+                    spaces
+                        .tables
+                        .push(table_type(ty.map_err(binary_reader_error)?)?);
+                }
+            }
+            Payload::MemorySection(memories) => {
+                for ty in memories {
+                    spaces
+                        .memories
+                        .push(memory_type(ty.map_err(binary_reader_error)?)?);
+                }
+            }
+            Payload::GlobalSection(globals) => {
+                for global in globals {
+                    spaces
+                        .globals
+                        .push(global_type(global.map_err(binary_reader_error)?.ty)?);
+                }
+            }
+            Payload::ExportSection(exports) => {
+                for export in exports {
+                    let export = export.map_err(binary_reader_error)?;
+                    let ty = spaces
+                        .export_type(export.kind, export.index)
+                        .ok_or_else(|| format!("unsupported export `{}`", export.field))?;
+                    module_types.exports.push(ExportType::new(export.field, ty));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(module_types)
+}
@@ -0,0 +1,149 @@
+//! Native Functions.
+//!
+//! This module creates the helper `NativeFunc` that let us call WebAssembly
+//! functions with the native ABI, that is:
+//!
+//! ```ignore
+//! let add_one = instance.exports.get_function("function_name")?;
+//! let add_one_native: NativeFunc<i32, i32> = add_one.native().unwrap();
+//! ```
+//!
+//! With the `js` backend, the arguments and the results still cross the
+//! JavaScript boundary as values, but their types are checked once,
+//! when the `NativeFunc` is created.
+use std::marker::PhantomData;
+
+use crate::js::externals::{array_from_vals, vals_from_array};
+use crate::js::{FromToNativeWasmType, Function, RuntimeError, Store, Val, WasmTypeList};
+use wasmer_types::NativeWasmType;
+
+/// A WebAssembly function that can be called natively
+/// (using the Native ABI).
+#[derive(Clone)]
+pub struct NativeFunc<Args = (), Rets = ()> {
+    function: Function,
+    _phantom: PhantomData<(Args, Rets)>,
+}
+
+impl<Args, Rets> NativeFunc<Args, Rets>
+where
+    Args: WasmTypeList,
+    Rets: WasmTypeList,
+{
+    pub(crate) fn new(function: Function) -> Self {
+        Self {
+            function,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns the [`Store`] where the `NativeFunc` belongs.
+    pub fn store(&self) -> &Store {
+        self.function.store()
+    }
+
+    /// Returns the untyped [`Function`] this typed function was created
+    /// from, pointing to the same function.
+    pub fn to_function(&self) -> Function {
+        self.function.clone()
+    }
+
+    /// Call the function with dynamically typed values, like
+    /// [`Function::call`] does.
+    pub fn call_dyn(&self, params: &[Val]) -> Result<Box<[Val]>, RuntimeError> {
+        self.function.call(params)
+    }
+}
+
+impl<Args, Rets> From<NativeFunc<Args, Rets>> for Function
+where
+    Args: WasmTypeList,
+    Rets: WasmTypeList,
+{
+    fn from(other: NativeFunc<Args, Rets>) -> Self {
+        other.function
+    }
+}
+
+macro_rules! impl_native_traits {
+    (  $( $x:ident ),* ) => {
+        #[allow(unused_parens, non_snake_case)]
+        impl<$( $x , )* Rets> NativeFunc<( $( $x ),* ), Rets>
+        where
+            $( $x: FromToNativeWasmType, )*
+            Rets: WasmTypeList,
+        {
+            /// Call the typed func and return results.
+            pub fn call(&self, $( $x: $x, )* ) -> Result<Rets, RuntimeError> {
+                let params_list = [ $( $x.to_native().to_binary() ),* ];
+                let params = vals_from_array(&params_list, <( $( $x ),* )>::wasm_types());
+                let results = self.function.call(&params)?;
+                let rets_list = array_from_vals(&results, Rets::wasm_types())?;
+                Ok(Rets::from_slice(&rets_list).expect("the number of results was checked"))
+            }
+        }
+
+        #[allow(unused_parens)]
+        impl<'a, $( $x, )* Rets> crate::js::exports::ExportableWithGenerics<'a, ($( $x ),*), Rets> for NativeFunc<( $( $x ),* ), Rets>
+        where
+            $( $x: FromToNativeWasmType, )*
+            Rets: WasmTypeList,
+        {
+            fn get_self_from_extern_with_generics(_extern: &'a crate::js::externals::Extern) -> Result<Self, crate::js::exports::ExportError> {
+                use crate::js::exports::Exportable;
+                crate::js::Function::get_self_from_extern(_extern)?.native().map_err(|_| crate::js::exports::ExportError::IncompatibleType)
+            }
+        }
+    };
+}
+
+impl_native_traits!();
+impl_native_traits!(A1);
+impl_native_traits!(A1, A2);
+impl_native_traits!(A1, A2, A3);
+impl_native_traits!(A1, A2, A3, A4);
+impl_native_traits!(A1, A2, A3, A4, A5);
+impl_native_traits!(A1, A2, A3, A4, A5, A6);
+impl_native_traits!(A1, A2, A3, A4, A5, A6, A7);
+impl_native_traits!(A1, A2, A3, A4, A5, A6, A7, A8);
+impl_native_traits!(A1, A2, A3, A4, A5, A6, A7, A8, A9);
+impl_native_traits!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10);
+impl_native_traits!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11);
+impl_native_traits!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12);
+impl_native_traits!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13);
+impl_native_traits!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14);
+impl_native_traits!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15);
+impl_native_traits!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16);
+impl_native_traits!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16, A17);
+impl_native_traits!(
+    A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16, A17, A18
+);
+impl_native_traits!(
+    A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16, A17, A18, A19
+);
+impl_native_traits!(
+    A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16, A17, A18, A19, A20
+);
+impl_native_traits!(
+    A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16, A17, A18, A19, A20, A21
+);
+impl_native_traits!(
+    A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16, A17, A18, A19, A20, A21,
+    A22
+);
+impl_native_traits!(
+    A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16, A17, A18, A19, A20, A21,
+    A22, A23
+);
+impl_native_traits!(
+    A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16, A17, A18, A19, A20, A21,
+    A22, A23, A24
+);
+impl_native_traits!(
+    A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16, A17, A18, A19, A20, A21,
+    A22, A23, A24, A25
+);
+impl_native_traits!(
+    A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16, A17, A18, A19, A20, A21,
+    A22, A23, A24, A25, A26
+);
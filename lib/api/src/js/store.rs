@@ -0,0 +1,38 @@
+use std::fmt;
+use std::sync::Arc;
+
+/// The store represents all global state that can be manipulated by
+/// WebAssembly programs.
+///
+/// With the `js` backend, the state is owned by the JavaScript host,
+/// so the store only tells apart the objects created in different
+/// stores.
+#[derive(Clone, Default)]
+pub struct Store {
+    id: Arc<()>,
+}
+
+impl Store {
+    /// Creates a new `Store`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks whether two stores are identical. A store is considered
+    /// equal to another store if both are clones of the same store.
+    pub fn same(a: &Self, b: &Self) -> bool {
+        Arc::ptr_eq(&a.id, &b.id)
+    }
+}
+
+impl PartialEq for Store {
+    fn eq(&self, other: &Self) -> bool {
+        Self::same(self, other)
+    }
+}
+
+impl fmt::Debug for Store {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Store").finish()
+    }
+}
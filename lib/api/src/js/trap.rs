@@ -0,0 +1,58 @@
+use std::error::Error;
+use std::fmt;
+use wasm_bindgen::{JsCast, JsValue};
+
+/// A struct representing an aborted instruction execution, with a
+/// message indicating the cause.
+///
+/// With the `js` backend, it's built from the exception thrown by the
+/// JavaScript host, e.g. a `WebAssembly.RuntimeError` for a trap.
+#[derive(Clone, PartialEq, Eq)]
+pub struct RuntimeError {
+    message: String,
+}
+
+impl RuntimeError {
+    /// Creates a new generic `RuntimeError` with the given `message`.
+    ///
+    /// # Example
+    /// ```
+    /// let trap = wasmer::RuntimeError::new("unexpected error");
+    /// assert_eq!("unexpected error", trap.message());
+    /// ```
+    pub fn new<I: Into<String>>(message: I) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+
+    /// Returns a reference the `message` stored in `Trap`.
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+}
+
+impl From<JsValue> for RuntimeError {
+    fn from(value: JsValue) -> Self {
+        match value.dyn_ref::<js_sys::Error>() {
+            Some(error) => Self::new(String::from(error.message())),
+            None => Self::new(value.as_string().unwrap_or_else(|| format!("{:?}", value))),
+        }
+    }
+}
+
+impl fmt::Debug for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RuntimeError")
+            .field("message", &self.message)
+            .finish()
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RuntimeError: {}", self.message)
+    }
+}
+
+impl Error for RuntimeError {}
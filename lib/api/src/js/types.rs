@@ -0,0 +1,114 @@
+use crate::js::externals::Function;
+use crate::js::trap::RuntimeError;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasmer_types::{ExternRef, NullableFuncRef, Type, Value};
+
+/// WebAssembly computations manipulate values of basic value types:
+/// * Integers (32 or 64 bit width)
+/// * Floating-point (32 or 64 bit width)
+/// * Vectors (128 bits, with 32 or 64 bit lanes)
+///
+/// Spec: <https://webassembly.github.io/spec/core/exec/runtime.html#values>
+pub type Val = Value<Function>;
+
+/// Null function references are represented as null `externref`s, so a
+/// `Function` is never null.
+impl NullableFuncRef for Function {
+    fn is_null(&self) -> bool {
+        false
+    }
+
+    fn null() -> Option<Self> {
+        None
+    }
+}
+
+#[wasm_bindgen]
+extern "C" {
+    /// The `i64` values are `BigInt`s in JavaScript. They are converted
+    /// from and to strings, as `js-sys` doesn't bind `BigInt` yet.
+    #[wasm_bindgen(js_name = BigInt)]
+    fn big_int(value: &str) -> JsValue;
+
+    #[wasm_bindgen(js_name = String)]
+    fn to_string(value: &JsValue) -> String;
+}
+
+/// Converts a value to its JavaScript representation.
+pub(crate) fn val_to_js(val: &Val) -> Result<JsValue, RuntimeError> {
+    Ok(match val {
+        Val::I32(value) => JsValue::from_f64(*value as f64),
+        Val::I64(value) => big_int(&value.to_string()),
+        Val::F32(value) => JsValue::from_f64(*value as f64),
+        Val::F64(value) => JsValue::from_f64(*value),
+        Val::FuncRef(function) => function.as_js().clone(),
+        Val::ExternRef(ExternRef::Null) => JsValue::NULL,
+        Val::ExternRef(_) => {
+            return Err(RuntimeError::new(
+                "`externref` values can't be passed to JavaScript",
+            ))
+        }
+        Val::V128(_) => {
+            return Err(RuntimeError::new(
+                "`v128` values can't be passed to JavaScript",
+            ))
+        }
+    })
+}
+
+/// Converts a JavaScript value to a value of type `ty`.
+pub(crate) fn val_from_js(ty: Type, value: &JsValue) -> Result<Val, RuntimeError> {
+    let number = || {
+        value
+            .as_f64()
+            .ok_or_else(|| RuntimeError::new(format!("expected a number, got {:?}", value)))
+    };
+
+    Ok(match ty {
+        Type::I32 => Val::I32(number()? as i32),
+        Type::I64 => Val::I64(
+            to_string(value)
+                .parse()
+                .map_err(|_| RuntimeError::new(format!("expected a BigInt, got {:?}", value)))?,
+        ),
+        Type::F32 => Val::F32(number()? as f32),
+        Type::F64 => Val::F64(number()?),
+        Type::FuncRef | Type::ExternRef if value.is_null() => Val::ExternRef(ExternRef::Null),
+        Type::FuncRef if value.is_instance_of::<js_sys::Function>() => {
+            return Err(RuntimeError::new(
+                "function references from JavaScript aren't supported, as their type is unknown",
+            ))
+        }
+        Type::FuncRef => {
+            return Err(RuntimeError::new(format!(
+                "expected a function, got {:?}",
+                value
+            )))
+        }
+        Type::ExternRef => {
+            return Err(RuntimeError::new(
+                "`externref` values can't be received from JavaScript",
+            ))
+        }
+        Type::V128 => {
+            return Err(RuntimeError::new(
+                "`v128` values can't be received from JavaScript",
+            ))
+        }
+    })
+}
+
+/// The name of a value type in the JavaScript API, e.g. for the
+/// descriptor of a global or a table.
+pub(crate) fn type_to_js_name(ty: Type) -> &'static str {
+    match ty {
+        Type::I32 => "i32",
+        Type::I64 => "i64",
+        Type::F32 => "f32",
+        Type::F64 => "f64",
+        Type::V128 => "v128",
+        Type::FuncRef => "anyfunc",
+        Type::ExternRef => "externref",
+    }
+}
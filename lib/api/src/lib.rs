@@ -253,80 +253,119 @@
 //! [wasmer-llvm]: https://docs.rs/wasmer-llvm/*/wasmer_llvm/
 //! [wasmer-wasi]: https://docs.rs/wasmer-wasi/*/wasmer_wasi/
 
+#[macro_use]
+mod macros;
 mod env;
-mod exports;
-mod externals;
-mod import_object;
-mod instance;
-mod module;
-mod native;
-mod ptr;
-mod store;
-mod tunables;
-mod types;
-mod utils;
 
 /// Implement [`WasmerEnv`] for your type with `#[derive(WasmerEnv)]`.
 ///
 /// See the [`WasmerEnv`] trait for more information.
 pub use wasmer_derive::WasmerEnv;
 
-#[doc(hidden)]
-pub mod internals {
-    //! We use the internals module for exporting types that are only
-    //! intended to use in internal crates such as the compatibility crate
-    //! `wasmer-vm`. Please don't use any of this types directly, as
-    //! they might change frequently or be removed in the future.
+pub use crate::env::{HostEnvInitError, LazyInit, WasmerEnv};
 
-    #[cfg(feature = "deprecated")]
-    pub use crate::externals::{UnsafeMutableEnv, WithUnsafeMutableEnv};
-    pub use crate::externals::{WithEnv, WithoutEnv};
-}
+cfg_if::cfg_if! {
+    if #[cfg(all(feature = "js", target_arch = "wasm32"))] {
+        mod js;
 
-pub use crate::env::{HostEnvInitError, LazyInit, WasmerEnv};
-pub use crate::exports::{ExportError, Exportable, Exports, ExportsIterator};
-pub use crate::externals::{
-    Extern, FromToNativeWasmType, Function, Global, HostFunction, Memory, Table, WasmTypeList,
-};
-pub use crate::import_object::{ImportObject, ImportObjectIterator, LikeNamespace};
-pub use crate::instance::{Instance, InstantiationError};
-pub use crate::module::Module;
-pub use crate::native::NativeFunc;
-pub use crate::ptr::{Array, Item, WasmPtr};
-pub use crate::store::{Store, StoreObject};
-pub use crate::tunables::BaseTunables;
-pub use crate::types::{
-    ExportType, ExternRef, ExternType, FunctionType, GlobalType, HostInfo, HostRef, ImportType,
-    MemoryType, Mutability, TableType, Val, ValType,
-};
-pub use crate::types::{Val as Value, ValType as Type};
-pub use crate::utils::is_wasm;
-pub use target_lexicon::{Architecture, CallingConvention, OperatingSystem, Triple, HOST};
-#[cfg(feature = "compiler")]
-pub use wasmer_compiler::{
-    wasmparser, CompilerConfig, FunctionMiddleware, MiddlewareError, MiddlewareReaderState,
-    ModuleMiddleware,
-};
-pub use wasmer_compiler::{
-    CompileError, CpuFeature, Features, ParseCpuFeatureError, Target, WasmError, WasmResult,
-};
-pub use wasmer_engine::{
-    ChainableNamedResolver, DeserializeError, Engine, Export, FrameInfo, LinkError, NamedResolver,
-    NamedResolverChain, Resolver, RuntimeError, SerializeError, Tunables,
-};
-pub use wasmer_types::{
-    Atomically, Bytes, ExportIndex, GlobalInit, LocalFunctionIndex, MemoryView, Pages, ValueType,
-    WASM_MAX_PAGES, WASM_MIN_PAGES, WASM_PAGE_SIZE,
-};
+        pub use crate::js::*;
+    } else if #[cfg(target_arch = "wasm32")] {
+        compile_error!("The `js` feature is required on `wasm32` targets.");
+    } else {
+        mod asyncify;
+        mod exports;
+        mod externals;
+        mod import_object;
+        mod instance;
+        mod linker;
+        mod module;
+        mod native;
+        mod ptr;
+        mod store;
+        mod tunables;
+        mod types;
+        mod utils;
+
+        #[doc(hidden)]
+        pub mod internals {
+            //! We use the internals module for exporting types that are only
+            //! intended to use in internal crates such as the compatibility crate
+            //! `wasmer-vm`. Please don't use any of this types directly, as
+            //! they might change frequently or be removed in the future.
+
+            #[cfg(feature = "deprecated")]
+            pub use crate::externals::{UnsafeMutableEnv, WithUnsafeMutableEnv};
+            pub use crate::externals::{WithEnv, WithoutEnv};
+        }
+
+        pub use crate::asyncify::{AsyncifiedFunction, Asyncify, AsyncifyState};
+        pub use crate::exports::{ExportError, Exportable, Exports, ExportsIterator};
+        pub use crate::externals::{
+            Extern, FromToNativeWasmType, Function, Global, HostFunction, Memory, MemoryAccessError,
+            Table, WasmTypeList,
+        };
+        pub use crate::import_object::{
+            FallbackResolver, ImportObject, ImportObjectIterator, LikeNamespace,
+        };
+        pub use crate::instance::{Instance, InstanceSnapshot, InstantiationError};
+        pub use crate::linker::{Linker, LinkerError};
+        pub use crate::module::{Module, WeakModule};
+        pub use crate::native::NativeFunc;
+        pub use crate::ptr::{Array, Item, WasmPtr};
+        pub use crate::store::{Store, StoreObject};
+        pub use crate::tunables::BaseTunables;
+        pub use crate::types::{
+            ExportType, ExternRef, ExternRefScope, ExternType, FunctionType, GlobalType, HostInfo,
+            HostRef, ImportType, MemoryType, Mutability, TableType, Val, ValType,
+        };
+        pub use crate::types::{Val as Value, ValType as Type};
+        pub use crate::utils::is_wasm;
+        pub use target_lexicon::{Architecture, CallingConvention, OperatingSystem, Triple, HOST};
+        #[cfg(feature = "compiler")]
+        pub use wasmer_compiler::{
+            wasmparser, CompilerConfig, FunctionMiddleware, MiddlewareError, MiddlewareReaderState,
+            ModuleMiddleware,
+        };
+        pub use wasmer_compiler::{
+            CompileError, CpuFeature, Features, ParseCpuFeatureError, Target, WasmError, WasmResult,
+        };
+        pub use wasmer_engine::{
+            current_backtrace, ChainableNamedResolver, DeserializeError, Engine, Export, FrameInfo,
+            ImportError, LinkError, NamedResolver, NamedResolverChain, Resolver, RuntimeError,
+            SerializeError, SourceLocation, Tunables,
+        };
+        pub use wasmer_types::{
+            Atomically, Bytes, ExportIndex, GlobalInit, LocalFunctionIndex, MemoryView, Pages,
+            ValueType, WASM_MAX_PAGES, WASM_MIN_PAGES, WASM_PAGE_SIZE,
+        };
+
+        // TODO: should those be moved into wasmer::vm as well?
+        pub use wasmer_vm::{raise_user_trap, MemoryError, ResourceLimiter, VMExport, WaitResult};
+        pub mod vm {
+            //! The vm module re-exports wasmer-vm types.
+
+            pub use wasmer_vm::{
+                platform, set_platform, HostBuffer, HostBufferMemory, Memory, MemoryError,
+                MemoryGrowCallback, MemoryStyle, OsPlatform, Platform, StaticPlatform, Table,
+                TableStyle, VMMemoryDefinition, VMTableDefinition,
+            };
+        }
 
-// TODO: should those be moved into wasmer::vm as well?
-pub use wasmer_vm::{raise_user_trap, MemoryError, VMExport};
-pub mod vm {
-    //! The vm module re-exports wasmer-vm types.
+        #[cfg(feature = "singlepass")]
+        pub use wasmer_compiler_singlepass::Singlepass;
 
-    pub use wasmer_vm::{
-        Memory, MemoryError, MemoryStyle, Table, TableStyle, VMMemoryDefinition, VMTableDefinition,
-    };
+        #[cfg(feature = "cranelift")]
+        pub use wasmer_compiler_cranelift::{Cranelift, CraneliftOptLevel};
+
+        #[cfg(feature = "llvm")]
+        pub use wasmer_compiler_llvm::{LLVMOptLevel, LLVM};
+
+        #[cfg(feature = "jit")]
+        pub use wasmer_engine_jit::{JITArtifact, JITEngine, JIT};
+
+        #[cfg(feature = "native")]
+        pub use wasmer_engine_native::{Native, NativeArtifact, NativeEngine};
+    }
 }
 
 #[cfg(feature = "wat")]
@@ -352,20 +391,5 @@ let store = Store::new(&engine);
 ```"#
 );
 
-#[cfg(feature = "singlepass")]
-pub use wasmer_compiler_singlepass::Singlepass;
-
-#[cfg(feature = "cranelift")]
-pub use wasmer_compiler_cranelift::{Cranelift, CraneliftOptLevel};
-
-#[cfg(feature = "llvm")]
-pub use wasmer_compiler_llvm::{LLVMOptLevel, LLVM};
-
-#[cfg(feature = "jit")]
-pub use wasmer_engine_jit::{JITArtifact, JITEngine, JIT};
-
-#[cfg(feature = "native")]
-pub use wasmer_engine_native::{Native, NativeArtifact, NativeEngine};
-
 /// Version number of this crate.
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -0,0 +1,192 @@
+//! The linker module contains [`Linker`], a resolver that is built
+//! incrementally by defining imports one at a time.
+use crate::exports::Exportable;
+use crate::externals::Extern;
+use crate::instance::{Instance, InstantiationError};
+use crate::module::Module;
+use crate::store::{Store, StoreObject};
+use indexmap::map::{Entry, IndexMap};
+use thiserror::Error;
+use wasmer_engine::{Export, NamedResolver};
+
+/// An error while defining items in a [`Linker`].
+#[derive(Error, Debug)]
+pub enum LinkerError {
+    /// An item is already defined with the same module and name, and
+    /// shadowing is not allowed.
+    #[error("import `{module}`.`{name}` is already defined")]
+    Duplicate {
+        /// The module name of the item.
+        module: String,
+        /// The field name of the item.
+        name: String,
+    },
+
+    /// The item comes from a different store than the linker.
+    #[error("import `{module}`.`{name}` comes from a different store")]
+    DifferentStores {
+        /// The module name of the item.
+        module: String,
+        /// The field name of the item.
+        name: String,
+    },
+
+    /// A module registered with [`Linker::module`] failed to instantiate.
+    #[error(transparent)]
+    Instantiation(Box<InstantiationError>),
+}
+
+impl From<InstantiationError> for LinkerError {
+    fn from(error: InstantiationError) -> Self {
+        Self::Instantiation(Box::new(error))
+    }
+}
+
+/// A resolver where imports are defined one by one, by module and
+/// field name.
+///
+/// Unlike an [`ImportObject`](crate::ImportObject), a `Linker` detects
+/// duplicate definitions, can register whole instances and modules, and
+/// can alias the items of a module under another module name.
+///
+/// # Example
+///
+/// ```
+/// # use wasmer::{Function, Linker, Module, Store};
+/// # fn main() -> anyhow::Result<()> {
+/// # let store = Store::default();
+/// let mut linker = Linker::new(&store);
+/// linker.define("env", "double", Function::new_native(&store, |x: i32| x * 2))?;
+///
+/// let module = Module::new(&store, r#"
+///     (module
+///       (import "env" "double" (func $double (param i32) (result i32)))
+///       (func (export "run") (result i32) (call $double (i32.const 21))))
+/// "#)?;
+/// let instance = linker.instantiate(&module)?;
+/// let run = instance.exports.get_native_function::<(), i32>("run")?;
+/// assert_eq!(run.call()?, 42);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Linker {
+    store: Store,
+    items: IndexMap<(String, String), Extern>,
+    allow_shadowing: bool,
+}
+
+impl Linker {
+    /// Create an empty `Linker` for items of `store`.
+    pub fn new(store: &Store) -> Self {
+        Self {
+            store: store.clone(),
+            items: IndexMap::new(),
+            allow_shadowing: false,
+        }
+    }
+
+    /// Returns the [`Store`] of this linker.
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    /// Allow definitions to replace the items already defined with the
+    /// same module and name, instead of failing with
+    /// [`LinkerError::Duplicate`].
+    pub fn allow_shadowing(&mut self, allow: bool) -> &mut Self {
+        self.allow_shadowing = allow;
+        self
+    }
+
+    /// Define an item as the import `module`.`name`.
+    pub fn define(
+        &mut self,
+        module: &str,
+        name: &str,
+        item: impl Into<Extern>,
+    ) -> Result<&mut Self, LinkerError> {
+        let item = item.into();
+        if !item.comes_from_same_store(&self.store) {
+            return Err(LinkerError::DifferentStores {
+                module: module.to_string(),
+                name: name.to_string(),
+            });
+        }
+        match self.items.entry((module.to_string(), name.to_string())) {
+            Entry::Occupied(mut entry) => {
+                if !self.allow_shadowing {
+                    return Err(LinkerError::Duplicate {
+                        module: module.to_string(),
+                        name: name.to_string(),
+                    });
+                }
+                entry.insert(item);
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(item);
+            }
+        }
+        Ok(self)
+    }
+
+    /// Define all the exports of `instance` as imports of `module`.
+    pub fn instance(
+        &mut self,
+        module: &str,
+        instance: &Instance,
+    ) -> Result<&mut Self, LinkerError> {
+        for (name, item) in instance.exports.iter() {
+            self.define(module, name, item.clone())?;
+        }
+        Ok(self)
+    }
+
+    /// Instantiate `wasm_module` with the items defined so far, and
+    /// define its exports as imports of `module`.
+    ///
+    /// Returns the new instance.
+    pub fn module(&mut self, module: &str, wasm_module: &Module) -> Result<Instance, LinkerError> {
+        let instance = self.instantiate(wasm_module)?;
+        self.instance(module, &instance)?;
+        Ok(instance)
+    }
+
+    /// Define all the items of `module` as items of `as_module` too.
+    pub fn alias(&mut self, module: &str, as_module: &str) -> Result<&mut Self, LinkerError> {
+        let items = self
+            .items
+            .iter()
+            .filter(|((item_module, _), _)| item_module == module)
+            .map(|((_, name), item)| (name.clone(), item.clone()))
+            .collect::<Vec<_>>();
+        for (name, item) in items {
+            self.define(as_module, &name, item)?;
+        }
+        Ok(self)
+    }
+
+    /// Get the item defined as `module`.`name`, if any.
+    pub fn get(&self, module: &str, name: &str) -> Option<&Extern> {
+        self.items.get(&(module.to_string(), name.to_string()))
+    }
+
+    /// Iterate over the defined items, as `(module, name, item)`, in the
+    /// order they were first defined.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str, &Extern)> + '_ {
+        self.items
+            .iter()
+            .map(|((module, name), item)| (module.as_str(), name.as_str(), item))
+    }
+
+    /// Instantiate `module` with the items defined in this linker.
+    pub fn instantiate(&self, module: &Module) -> Result<Instance, InstantiationError> {
+        Instance::new(module, self)
+    }
+}
+
+impl NamedResolver for Linker {
+    fn resolve_by_name(&self, module: &str, name: &str) -> Option<Export> {
+        self.get(module, name).map(Exportable::to_export)
+    }
+}
@@ -0,0 +1,70 @@
+//! The `imports!` macro and its helpers, shared by the backends.
+//!
+//! They used to live in `import_object.rs`, but that module is only
+//! compiled by the native backend, and `imports!` is exported by both:
+//! the macros only expand to calls to the `ImportObject` and `Exports` of
+//! the backend in use.
+
+/// Generate an [`ImportObject`] easily with the `imports!` macro.
+///
+/// [`ImportObject`]: struct.ImportObject.html
+///
+/// # Usage
+///
+/// ```
+/// # use wasmer::{Function, Store};
+/// # let store = Store::default();
+/// use wasmer::imports;
+///
+/// let import_object = imports! {
+///     "env" => {
+///         "foo" => Function::new_native(&store, foo)
+///     },
+/// };
+///
+/// fn foo(n: i32) -> i32 {
+///     n
+/// }
+/// ```
+#[macro_export]
+macro_rules! imports {
+    ( $( $ns_name:expr => $ns:tt ),* $(,)? ) => {
+        {
+            let mut import_object = $crate::ImportObject::new();
+
+            $({
+                let namespace = $crate::import_namespace!($ns);
+
+                import_object.register($ns_name, namespace);
+            })*
+
+            import_object
+        }
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! namespace {
+    ($( $import_name:expr => $import_item:expr ),* $(,)? ) => {
+        $crate::import_namespace!( { $( $import_name => $import_item, )* } )
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! import_namespace {
+    ( { $( $import_name:expr => $import_item:expr ),* $(,)? } ) => {{
+        let mut namespace = $crate::Exports::new();
+
+        $(
+            namespace.insert($import_name, $import_item);
+        )*
+
+        namespace
+    }};
+
+    ( $namespace:ident ) => {
+        $namespace
+    };
+}
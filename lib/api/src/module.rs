@@ -1,11 +1,13 @@
+use crate::externals::{Extern, Function};
+use crate::import_object::FallbackResolver;
 use crate::store::Store;
-use crate::types::{ExportType, ImportType};
-use crate::InstantiationError;
+use crate::types::{ExportType, ExternType, ImportType};
+use crate::{Instance, InstantiationError, RuntimeError};
 use loupe::MemoryUsage;
 use std::fmt;
 use std::io;
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 use thiserror::Error;
 use wasmer_compiler::CompileError;
 #[cfg(feature = "wat")]
@@ -279,6 +281,47 @@ impl Module {
         }
     }
 
+    /// Instantiate this module, filling the function imports that
+    /// `resolver` can't resolve with stubs.
+    ///
+    /// Calling a stub traps with a message naming the missing import.
+    /// Missing imports that are not functions still make the
+    /// instantiation fail.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let wat = r#"(module
+    ///     (import "env" "optional" (func $optional))
+    ///     (func (export "run") (call $optional)))"#;
+    /// let module = Module::new(&store, wat)?;
+    /// let instance = module.instantiate_with_stubs(&imports! {})?;
+    /// let error = instance.exports.get_function("run")?.call(&[]).unwrap_err();
+    /// assert_eq!(error.message(), "called the missing import `env`.`optional`");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn instantiate_with_stubs<R: Resolver>(
+        &self,
+        resolver: R,
+    ) -> Result<Instance, InstantiationError> {
+        let resolver = FallbackResolver::new(self, resolver, |module, field, ty| match ty {
+            ExternType::Function(ty) => {
+                let message = format!("called the missing import `{}`.`{}`", module, field);
+                Some(Extern::Function(Function::new(
+                    &self.store,
+                    ty,
+                    move |_| Err(RuntimeError::new(message.clone())),
+                )))
+            }
+            _ => None,
+        });
+        Instance::new(self, &resolver)
+    }
+
     /// Returns the name of the current module.
     ///
     /// This name is normally set in the WebAssembly bytecode by some
@@ -397,6 +440,72 @@ impl Module {
         self.artifact.module_ref().custom_sections(name)
     }
 
+    /// Returns a new `Module` with the same code as this one and an
+    /// additional custom section named `name`, replacing any custom
+    /// section with the same name.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let module = Module::new(&store, "(module)")?;
+    /// let module = module.with_custom_section("build-id", b"1234")?;
+    ///
+    /// let section = module.custom_sections("build-id").next().unwrap();
+    /// assert_eq!(&*section, b"1234");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_custom_section(&self, name: &str, data: &[u8]) -> Result<Self, SerializeError> {
+        self.with_module_info(|module_info| module_info.add_custom_section(name, data.into()))
+    }
+
+    /// Returns a new `Module` with the same code as this one, without the
+    /// custom sections for which `predicate` returns `true`.
+    ///
+    /// This is useful to remove large debug sections before caching a
+    /// module. The predicate receives the name and the data of each
+    /// custom section.
+    pub fn strip_custom_sections(
+        &self,
+        mut predicate: impl FnMut(&str, &[u8]) -> bool,
+    ) -> Result<Self, SerializeError> {
+        self.with_module_info(|module_info| {
+            module_info.retain_custom_sections(|name, data| !predicate(name, data))
+        })
+    }
+
+    /// Returns a new `Module` with the same code as this one, without the
+    /// module and function names read from its `name` section.
+    ///
+    /// The functions are then shown by their index in the traces.
+    pub fn strip_names(&self) -> Result<Self, SerializeError> {
+        self.with_module_info(|module_info| {
+            module_info.name = None;
+            module_info.function_names.clear();
+        })
+    }
+
+    /// Returns a copy of this `Module` with a modified [`ModuleInfo`].
+    ///
+    /// The compiled artifact is shared with other modules and instances,
+    /// so a private copy is made by going through its serialized form.
+    fn with_module_info(&self, f: impl FnOnce(&mut ModuleInfo)) -> Result<Self, SerializeError> {
+        let bytes = self.serialize()?;
+        // The bytes were just serialized by the same engine.
+        let mut module = unsafe { Self::deserialize(&self.store, &bytes) }
+            .map_err(|e| SerializeError::Generic(e.to_string()))?;
+        let module_info = Arc::get_mut(&mut module.artifact)
+            .and_then(|artifact| artifact.module_mut())
+            .ok_or_else(|| {
+                SerializeError::Generic("the module information can't be modified".to_string())
+            })?;
+        f(module_info);
+        Ok(module)
+    }
+
     /// Returns the [`Store`] where the `Instance` belongs.
     pub fn store(&self) -> &Store {
         &self.store
@@ -421,6 +530,41 @@ impl Module {
     pub fn artifact(&self) -> &Arc<dyn Artifact> {
         &self.artifact
     }
+
+    /// Creates a [`WeakModule`] handle, which doesn't keep the compiled
+    /// code alive.
+    pub fn downgrade(&self) -> WeakModule {
+        WeakModule {
+            store: self.store.clone(),
+            artifact: Arc::downgrade(&self.artifact),
+        }
+    }
+}
+
+/// A weak handle to a [`Module`], obtained with [`Module::downgrade`].
+///
+/// The compiled code is freed once all the [`Module`]s and the instances
+/// using it are dropped, even though some weak handles remain.
+#[derive(Clone)]
+pub struct WeakModule {
+    store: Store,
+    artifact: Weak<dyn Artifact>,
+}
+
+impl WeakModule {
+    /// Returns the [`Module`] if its compiled code is still alive.
+    pub fn upgrade(&self) -> Option<Module> {
+        Some(Module {
+            store: self.store.clone(),
+            artifact: self.artifact.upgrade()?,
+        })
+    }
+}
+
+impl fmt::Debug for WeakModule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WeakModule").finish()
+    }
 }
 
 impl fmt::Debug for Module {
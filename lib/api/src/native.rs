@@ -13,7 +13,7 @@ use crate::externals::function::{
     DynamicFunctionWithEnv, DynamicFunctionWithoutEnv, FunctionDefinition, HostFunctionDefinition,
     VMDynamicFunction, WasmFunctionDefinition,
 };
-use crate::{FromToNativeWasmType, Function, RuntimeError, Store, WasmTypeList};
+use crate::{FromToNativeWasmType, Function, RuntimeError, Store, Val, WasmTypeList};
 use std::panic::{catch_unwind, AssertUnwindSafe};
 use wasmer_engine::ExportFunction;
 use wasmer_types::NativeWasmType;
@@ -60,6 +60,47 @@ where
     pub(crate) fn arg_kind(&self) -> VMFunctionKind {
         self.exported.vm_function.kind
     }
+
+    /// Returns the untyped [`Function`] this typed function was created
+    /// from, pointing to the same function.
+    pub fn to_function(&self) -> Function {
+        Function {
+            store: self.store.clone(),
+            definition: self.definition.clone(),
+            exported: self.exported.clone(),
+        }
+    }
+
+    /// Call the function with dynamically typed values, like
+    /// [`Function::call`] does.
+    ///
+    /// This lets a caller holding a typed `NativeFunc` perform the
+    /// occasional call whose arguments are only known at runtime,
+    /// without looking the function up again.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{imports, wat2wasm, Instance, Module, NativeFunc, Store, Value};
+    /// # let store = Store::default();
+    /// # let wasm_bytes = wat2wasm(r#"
+    /// # (module
+    /// #   (func (export "sum") (param $x i32) (param $y i32) (result i32)
+    /// #     local.get $x
+    /// #     local.get $y
+    /// #     i32.add
+    /// #   ))
+    /// # "#.as_bytes()).unwrap();
+    /// # let module = Module::new(&store, wasm_bytes).unwrap();
+    /// # let instance = Instance::new(&module, &imports! {}).unwrap();
+    /// let sum: NativeFunc<(i32, i32), i32> = instance.exports.get_native_function("sum").unwrap();
+    ///
+    /// assert_eq!(sum.call(1, 2).unwrap(), 3);
+    /// assert_eq!(sum.call_dyn(&[Value::I32(1), Value::I32(2)]).unwrap().to_vec(), vec![Value::I32(3)]);
+    /// ```
+    pub fn call_dyn(&self, params: &[Val]) -> Result<Box<[Val]>, RuntimeError> {
+        self.to_function().call(params)
+    }
 }
 
 /*
@@ -253,3 +294,26 @@ impl_native_traits!(
 impl_native_traits!(
     A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16, A17, A18, A19, A20
 );
+impl_native_traits!(
+    A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16, A17, A18, A19, A20, A21
+);
+impl_native_traits!(
+    A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16, A17, A18, A19, A20, A21,
+    A22
+);
+impl_native_traits!(
+    A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16, A17, A18, A19, A20, A21,
+    A22, A23
+);
+impl_native_traits!(
+    A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16, A17, A18, A19, A20, A21,
+    A22, A23, A24
+);
+impl_native_traits!(
+    A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16, A17, A18, A19, A20, A21,
+    A22, A23, A24, A25
+);
+impl_native_traits!(
+    A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16, A17, A18, A19, A20, A21,
+    A22, A23, A24, A25, A26
+);
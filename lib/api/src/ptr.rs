@@ -6,8 +6,8 @@
 //! Therefore, you should use this abstraction whenever possible to avoid memory
 //! related bugs when implementing an ABI.
 
-use crate::{externals::Memory, FromToNativeWasmType};
-use std::{cell::Cell, fmt, marker::PhantomData, mem};
+use crate::{externals::Memory, FromToNativeWasmType, MemoryAccessError};
+use std::{cell::Cell, ffi::CStr, ffi::CString, fmt, marker::PhantomData, mem};
 use wasmer_types::ValueType;
 
 /// The `Array` marker type. This type can be used like `WasmPtr<T, Array>`
@@ -54,6 +54,20 @@ impl<T: Copy, Ty> WasmPtr<T, Ty> {
     pub fn offset(self) -> u32 {
         self.offset
     }
+
+    /// Returns the `WasmPtr` `count` elements of type `T` after this one.
+    ///
+    /// Returns an error instead of wrapping around if the resulting
+    /// offset doesn't fit in the 32-bit address space.
+    #[inline]
+    #[allow(clippy::should_implement_trait)]
+    pub fn add(self, count: u32) -> Result<Self, MemoryAccessError> {
+        let offset = (mem::size_of::<T>() as u32)
+            .checked_mul(count)
+            .and_then(|bytes| self.offset.checked_add(bytes))
+            .ok_or(MemoryAccessError::Overflow)?;
+        Ok(Self::new(offset))
+    }
 }
 
 #[inline(always)]
@@ -183,6 +197,40 @@ impl<T: Copy + ValueType> WasmPtr<T, Array> {
         Some(cell_ptrs)
     }
 
+    /// Returns a pointer to the element at `index` of this array.
+    ///
+    /// Returns an error instead of wrapping around if the resulting
+    /// offset doesn't fit in the 32-bit address space.
+    #[inline]
+    pub fn index(self, index: u32) -> Result<WasmPtr<T, Item>, MemoryAccessError> {
+        Ok(WasmPtr::new(self.add(index)?.offset))
+    }
+
+    /// Dereference the `len` elements of this array, checking that they
+    /// are all within the memory and that the array is aligned for `T`.
+    ///
+    /// Unlike [`WasmPtr::deref`], this never silently realigns the
+    /// pointer, and reports why the access is invalid.
+    ///
+    /// This method is unsound if used with unsynchronized shared memory.
+    /// If you're unsure what that means, it likely does not apply to you.
+    pub fn slice(self, memory: &Memory, len: u32) -> Result<&[Cell<T>], MemoryAccessError> {
+        if mem::size_of::<T>() == 0 {
+            return Err(MemoryAccessError::HeapOutOfBounds);
+        }
+        if self.offset as usize % mem::align_of::<T>() != 0 {
+            return Err(MemoryAccessError::Unaligned);
+        }
+        let end = self.add(len)?.offset;
+        if end as usize > memory.size().bytes().0 {
+            return Err(MemoryAccessError::HeapOutOfBounds);
+        }
+        unsafe {
+            let cell_ptr = memory.view::<u8>().as_ptr().add(self.offset as usize) as *const Cell<T>;
+            Ok(std::slice::from_raw_parts(cell_ptr, len as usize))
+        }
+    }
+
     /// Get a UTF-8 string from the `WasmPtr` with the given length.
     ///
     /// Note that . The
@@ -263,6 +311,81 @@ impl<T: Copy + ValueType> WasmPtr<T, Array> {
     }
 }
 
+/// Methods for `WasmPtr`s to nul-terminated byte strings, as used by C.
+impl WasmPtr<u8, Array> {
+    /// Get a C string from the `WasmPtr`, scanning at most `max_len` bytes
+    /// (including the nul terminator) for the nul terminator.
+    ///
+    /// Returns `None` if no nul byte is found within `max_len` bytes or
+    /// before the end of the memory.
+    ///
+    /// # Safety
+    /// This method behaves similarly to [`WasmPtr::get_utf8_str`], all safety invariants on
+    /// that method must also be upheld here.
+    pub unsafe fn get_cstr(self, memory: &Memory, max_len: u32) -> Option<&CStr> {
+        let memory_size = memory.size().bytes().0;
+        let start = self.offset as usize;
+        if start >= memory_size {
+            return None;
+        }
+        let end = memory_size.min(start + max_len as usize);
+        let ptr = memory.view::<u8>().as_ptr().add(start) as *const u8;
+        let slice: &[u8] = std::slice::from_raw_parts(ptr, end - start);
+        let length = slice.iter().position(|&byte| byte == 0)?;
+        Some(CStr::from_bytes_with_nul_unchecked(&slice[..=length]))
+    }
+
+    /// Get an owned C string from the `WasmPtr`, scanning at most `max_len`
+    /// bytes (including the nul terminator) for the nul terminator.
+    pub fn get_cstring(self, memory: &Memory, max_len: u32) -> Option<CString> {
+        unsafe { self.get_cstr(memory, max_len) }.map(|s| s.to_owned())
+    }
+
+    /// Write `string` and its nul terminator to the memory at this `WasmPtr`.
+    ///
+    /// Returns an error, and writes nothing, if it doesn't fit in the memory.
+    pub fn write_cstr(self, memory: &Memory, string: &CStr) -> Result<(), MemoryAccessError> {
+        memory.write(self.offset as u64, string.to_bytes_with_nul())
+    }
+
+    /// Write the UTF-8 `string` to the memory at this `WasmPtr`, without
+    /// any terminator.
+    ///
+    /// Returns an error, and writes nothing, if it doesn't fit in the memory.
+    pub fn write_utf8_str(self, memory: &Memory, string: &str) -> Result<(), MemoryAccessError> {
+        memory.write(self.offset as u64, string.as_bytes())
+    }
+}
+
+/// Methods for `WasmPtr`s to UTF-16 strings, as used by AssemblyScript or
+/// code targeting Windows. The code units are little-endian, like all of
+/// the Wasm linear memory.
+impl WasmPtr<u16, Array> {
+    /// Get a `String` from the `WasmPtr` to `str_len` UTF-16 code units.
+    ///
+    /// Returns `None` if the string is out of bounds or is not valid UTF-16.
+    pub fn get_utf16_str(self, memory: &Memory, str_len: u32) -> Option<String> {
+        let bytes = memory
+            .read_vec(self.offset as u64, str_len as usize * 2)
+            .ok()?;
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+            .collect();
+        String::from_utf16(&units).ok()
+    }
+
+    /// Write `string` as UTF-16 to the memory at this `WasmPtr`, without any
+    /// terminator, and return the number of code units written.
+    ///
+    /// Returns an error, and writes nothing, if it doesn't fit in the memory.
+    pub fn write_utf16_str(self, memory: &Memory, string: &str) -> Result<u32, MemoryAccessError> {
+        let bytes: Vec<u8> = string.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        memory.write(self.offset as u64, &bytes)?;
+        Ok((bytes.len() / 2) as u32)
+    }
+}
+
 unsafe impl<T: Copy, Ty> FromToNativeWasmType for WasmPtr<T, Ty> {
     type Native = i32;
 
@@ -394,4 +517,83 @@ mod test {
             assert!(unsafe { oob_end_array_ptr.deref_mut(&memory, 1, 0).is_none() });
         }
     }
+
+    #[test]
+    fn wasm_ptr_strings() {
+        let store = Store::default();
+        let memory = Memory::new(&store, MemoryType::new(1, Some(1), false)).unwrap();
+        let last_address = (memory.size().bytes().0 - 1) as u32;
+
+        let cstr_ptr: WasmPtr<u8, Array> = WasmPtr::new(16);
+        let hello = CString::new("hello").unwrap();
+        cstr_ptr.write_cstr(&memory, &hello).unwrap();
+        assert_eq!(cstr_ptr.get_cstring(&memory, 64), Some(hello.clone()));
+        assert_eq!(cstr_ptr.get_cstring(&memory, 6), Some(hello.clone()));
+        // The nul terminator must be within the scanned bytes.
+        assert_eq!(cstr_ptr.get_cstring(&memory, 5), None);
+
+        let end_ptr: WasmPtr<u8, Array> = WasmPtr::new(last_address - 2);
+        assert_eq!(
+            end_ptr.write_cstr(&memory, &hello),
+            Err(MemoryAccessError::HeapOutOfBounds)
+        );
+        end_ptr.write_utf8_str(&memory, "abc").unwrap();
+        assert_eq!(end_ptr.get_cstring(&memory, 64), None);
+        assert_eq!(
+            WasmPtr::<u8, Array>::new(last_address + 1).get_cstring(&memory, 1),
+            None
+        );
+
+        let utf16_ptr: WasmPtr<u16, Array> = WasmPtr::new(33);
+        assert_eq!(
+            utf16_ptr.write_utf16_str(&memory, "h\u{e9}\u{1f600}"),
+            Ok(4)
+        );
+        assert_eq!(
+            utf16_ptr.get_utf16_str(&memory, 4).as_deref(),
+            Some("h\u{e9}\u{1f600}")
+        );
+        // Half of a surrogate pair is not valid UTF-16.
+        assert_eq!(utf16_ptr.get_utf16_str(&memory, 3), None);
+
+        let end_ptr: WasmPtr<u16, Array> = WasmPtr::new(last_address - 3);
+        assert_eq!(end_ptr.write_utf16_str(&memory, "ab"), Ok(2));
+        assert_eq!(
+            end_ptr.write_utf16_str(&memory, "abc"),
+            Err(MemoryAccessError::HeapOutOfBounds)
+        );
+        assert_eq!(end_ptr.get_utf16_str(&memory, 3), None);
+    }
+
+    #[test]
+    fn wasm_ptr_checked_arithmetic() {
+        let store = Store::default();
+        let memory = Memory::new(&store, MemoryType::new(1, Some(1), false)).unwrap();
+        let memory_size = memory.size().bytes().0 as u32;
+
+        let array: WasmPtr<u32, Array> = WasmPtr::new(8);
+        assert_eq!(array.add(2).unwrap().offset(), 16);
+        assert_eq!(array.index(3).unwrap(), WasmPtr::new(20));
+        assert_eq!(array.add(u32::MAX), Err(MemoryAccessError::Overflow));
+        assert_eq!(
+            WasmPtr::<u8>::new(u32::MAX).add(1),
+            Err(MemoryAccessError::Overflow)
+        );
+
+        let cells = array.slice(&memory, 4).unwrap();
+        assert_eq!(cells.len(), 4);
+        cells[1].set(42);
+        assert_eq!(array.index(1).unwrap().deref(&memory).unwrap().get(), 42);
+
+        let last: WasmPtr<u32, Array> = WasmPtr::new(memory_size - 8);
+        assert_eq!(last.slice(&memory, 2).map(|cells| cells.len()), Ok(2));
+        assert_eq!(
+            last.slice(&memory, 3).map(|cells| cells.len()),
+            Err(MemoryAccessError::HeapOutOfBounds)
+        );
+        assert_eq!(
+            WasmPtr::<u32, Array>::new(2).slice(&memory, 1).map(|_| ()),
+            Err(MemoryAccessError::Unaligned)
+        );
+    }
 }
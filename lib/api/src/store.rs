@@ -1,10 +1,13 @@
-use crate::tunables::BaseTunables;
+use crate::tunables::{BaseTunables, LimitingTunables};
 use loupe::MemoryUsage;
 use std::fmt;
 use std::sync::Arc;
 #[cfg(all(feature = "compiler", feature = "engine"))]
 use wasmer_compiler::CompilerConfig;
 use wasmer_engine::{Engine, Tunables};
+#[cfg(all(feature = "default-compiler", feature = "default-engine"))]
+use wasmer_types::Features;
+use wasmer_vm::ResourceLimiter;
 
 /// The store represents all global state that can be manipulated by
 /// WebAssembly programs. It consists of the runtime representation
@@ -20,6 +23,7 @@ use wasmer_engine::{Engine, Tunables};
 pub struct Store {
     engine: Arc<dyn Engine + Send + Sync>,
     tunables: Arc<dyn Tunables + Send + Sync>,
+    deterministic: bool,
 }
 
 impl Store {
@@ -31,6 +35,7 @@ impl Store {
         Self {
             engine: engine.cloned(),
             tunables: Arc::new(BaseTunables::for_target(engine.target())),
+            deterministic: false,
         }
     }
 
@@ -42,14 +47,51 @@ impl Store {
         Self {
             engine: engine.cloned(),
             tunables: Arc::new(tunables),
+            deterministic: false,
+        }
+    }
+
+    /// Creates a new deterministic `Store` with a specific [`Engine`], like
+    /// [`Store::deterministic`] but with any compiler configuration, for
+    /// example one carrying the metering middleware.
+    ///
+    /// The engine must compile deterministically: its compiler must
+    /// canonicalize the NaNs (see
+    /// `CompilerConfig::enable_nan_canonicalization`), and the `threads`
+    /// feature must be disabled.
+    pub fn new_deterministic<E>(engine: &E) -> Self
+    where
+        E: Engine + ?Sized,
+    {
+        Self {
+            deterministic: true,
+            ..Self::new(engine)
         }
     }
 
+    /// Installs a [`ResourceLimiter`] that will be consulted every time a
+    /// memory or a table created from this `Store` tries to grow.
+    ///
+    /// Only the memories and tables created after this call (including
+    /// the ones created when instantiating a module) are affected.
+    pub fn set_resource_limiter(&mut self, limiter: impl ResourceLimiter + 'static) {
+        self.tunables = Arc::new(LimitingTunables::new(
+            self.tunables.clone(),
+            Arc::new(limiter),
+        ));
+    }
+
     /// Returns the [`Tunables`].
     pub fn tunables(&self) -> &dyn Tunables {
         self.tunables.as_ref()
     }
 
+    /// Returns true if this `Store` was created with
+    /// [`Store::deterministic`] or [`Store::new_deterministic`].
+    pub fn is_deterministic(&self) -> bool {
+        self.deterministic
+    }
+
     /// Returns the [`Engine`].
     pub fn engine(&self) -> &Arc<dyn Engine + Send + Sync> {
         &self.engine
@@ -69,49 +111,92 @@ impl PartialEq for Store {
     }
 }
 
-// We only implement default if we have assigned a default compiler and engine
+// We store them on a function that returns to make
+// sure this function doesn't emit a compile error even if
+// more than one compiler is enabled.
 #[cfg(all(feature = "default-compiler", feature = "default-engine"))]
-impl Default for Store {
-    fn default() -> Self {
-        // We store them on a function that returns to make
-        // sure this function doesn't emit a compile error even if
-        // more than one compiler is enabled.
-        #[allow(unreachable_code)]
-        fn get_config() -> impl CompilerConfig + 'static {
-            cfg_if::cfg_if! {
-                if #[cfg(feature = "default-cranelift")] {
-                    wasmer_compiler_cranelift::Cranelift::default()
-                } else if #[cfg(feature = "default-llvm")] {
-                    wasmer_compiler_llvm::LLVM::default()
-                } else if #[cfg(feature = "default-singlepass")] {
-                    wasmer_compiler_singlepass::Singlepass::default()
-                } else {
-                    compile_error!("No default compiler chosen")
-                }
-            }
+#[allow(unreachable_code)]
+fn default_compiler_config() -> impl CompilerConfig + 'static {
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "default-cranelift")] {
+            wasmer_compiler_cranelift::Cranelift::default()
+        } else if #[cfg(feature = "default-llvm")] {
+            wasmer_compiler_llvm::LLVM::default()
+        } else if #[cfg(feature = "default-singlepass")] {
+            wasmer_compiler_singlepass::Singlepass::default()
+        } else {
+            compile_error!("No default compiler chosen")
         }
+    }
+}
 
-        #[allow(unreachable_code, unused_mut)]
-        fn get_engine(mut config: impl CompilerConfig + 'static) -> impl Engine + Send + Sync {
-            cfg_if::cfg_if! {
-                if #[cfg(feature = "default-jit")] {
-                    wasmer_engine_jit::JIT::new(config)
-                        .engine()
-                } else if #[cfg(feature = "default-native")] {
-                    wasmer_engine_native::Native::new(config)
-                        .engine()
-                } else {
-                    compile_error!("No default engine chosen")
-                }
+#[cfg(all(feature = "default-compiler", feature = "default-engine"))]
+#[allow(unreachable_code, unused_mut)]
+fn default_engine(
+    mut config: impl CompilerConfig + 'static,
+    features: Option<Features>,
+) -> impl Engine + Send + Sync {
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "default-jit")] {
+            let mut builder = wasmer_engine_jit::JIT::new(config);
+            if let Some(features) = features {
+                builder = builder.features(features);
+            }
+            builder.engine()
+        } else if #[cfg(feature = "default-native")] {
+            let mut builder = wasmer_engine_native::Native::new(config);
+            if let Some(features) = features {
+                builder = builder.features(features);
             }
+            builder.engine()
+        } else {
+            compile_error!("No default engine chosen")
         }
+    }
+}
 
-        let config = get_config();
-        let engine = get_engine(config);
+#[cfg(all(feature = "default-compiler", feature = "default-engine"))]
+impl Store {
+    /// Creates a new `Store` with the default compiler and engine,
+    /// configured to execute WebAssembly deterministically.
+    ///
+    /// In this mode:
+    /// * the compiler canonicalizes the NaNs produced by floating-point
+    ///   operations, whose bits otherwise depend on the architecture;
+    /// * the `threads` feature, whose shared memories can be raced, is
+    ///   disabled;
+    /// * [`Store::is_deterministic`] returns `true`, so that the host
+    ///   integrations relying on the environment virtualize it. For
+    ///   example, `wasmer-wasi` freezes the clocks and seeds the random
+    ///   source of the modules it provides imports to.
+    ///
+    /// The metering middleware charges costs that only depend on the
+    /// operators of the module, so the points consumed by an execution are
+    /// the same on every run and on every host. Use
+    /// [`Store::new_deterministic`] with an engine whose compiler
+    /// configuration carries the middleware to meter a deterministic
+    /// execution.
+    pub fn deterministic() -> Self {
+        let mut config = default_compiler_config();
+        config.enable_nan_canonicalization();
+        let mut features = config.default_features_for_target(&Default::default());
+        features.threads(false);
+        let engine = default_engine(config, Some(features));
+        Self::new_deterministic(&engine)
+    }
+}
+
+// We only implement default if we have assigned a default compiler and engine
+#[cfg(all(feature = "default-compiler", feature = "default-engine"))]
+impl Default for Store {
+    fn default() -> Self {
+        let config = default_compiler_config();
+        let engine = default_engine(config, None);
         let tunables = BaseTunables::for_target(engine.target());
         Store {
             engine: Arc::new(engine),
             tunables: Arc::new(tunables),
+            deterministic: false,
         }
     }
 }
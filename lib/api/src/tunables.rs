@@ -8,8 +8,8 @@ use wasmer_compiler::Target;
 use wasmer_engine::Tunables;
 use wasmer_vm::MemoryError;
 use wasmer_vm::{
-    LinearMemory, LinearTable, Memory, MemoryStyle, Table, TableStyle, VMMemoryDefinition,
-    VMTableDefinition,
+    HostBuffer, LimitedMemory, LimitedTable, LinearMemory, LinearTable, Memory, MemoryStyle,
+    ResourceLimiter, Table, TableStyle, VMMemoryDefinition, VMTableDefinition,
 };
 
 /// Tunable parameters for WebAssembly compilation.
@@ -67,6 +67,49 @@ impl BaseTunables {
             dynamic_memory_offset_guard_size,
         }
     }
+
+    /// Get the `BaseTunables` for memory-constrained targets, such as
+    /// devices with a few dozen MiB of RAM.
+    ///
+    /// Memories declaring a maximum of at most 16 MiB are allocated up
+    /// front, and no guard pages are reserved at all: the generated code
+    /// bounds-checks every memory access instead.
+    pub fn tiny() -> Self {
+        Self {
+            static_memory_bound: 0x100.into(),
+            static_memory_offset_guard_size: 0,
+            dynamic_memory_offset_guard_size: 0,
+        }
+    }
+
+    /// Get the `BaseTunables` for hosts without virtual memory, that
+    /// allocate the memories from a static buffer with a
+    /// [`StaticPlatform`][crate::vm::StaticPlatform].
+    ///
+    /// No address space is reserved beyond the current size of the
+    /// memories, and there are no guard pages: the generated code
+    /// bounds-checks every memory access.
+    pub fn without_virtual_memory() -> Self {
+        Self {
+            static_memory_bound: 0.into(),
+            static_memory_offset_guard_size: 0,
+            dynamic_memory_offset_guard_size: 0,
+        }
+    }
+
+    /// Get the `BaseTunables` for 64-bit hosts with plenty of address
+    /// space.
+    ///
+    /// Every memory reserves the full 4 GiB of addressable space followed
+    /// by a 6 GiB offset guard, so that no explicit bounds checks are
+    /// needed even for accesses with large constant offsets.
+    pub fn server() -> Self {
+        Self {
+            static_memory_bound: 0x1_0000.into(),
+            static_memory_offset_guard_size: 0x1_8000_0000,
+            dynamic_memory_offset_guard_size: 0x1_0000,
+        }
+    }
 }
 
 impl Tunables for BaseTunables {
@@ -150,6 +193,103 @@ impl Tunables for BaseTunables {
     }
 }
 
+/// Tunables that delegate to other tunables, wrapping every memory and
+/// table they create so that growth is checked by a [`ResourceLimiter`].
+///
+/// This is what [`Store::set_resource_limiter`][crate::Store::set_resource_limiter]
+/// installs.
+#[derive(MemoryUsage)]
+pub(crate) struct LimitingTunables {
+    base: Arc<dyn Tunables + Send + Sync>,
+    #[loupe(skip)]
+    limiter: Arc<dyn ResourceLimiter>,
+}
+
+impl LimitingTunables {
+    pub(crate) fn new(
+        base: Arc<dyn Tunables + Send + Sync>,
+        limiter: Arc<dyn ResourceLimiter>,
+    ) -> Self {
+        Self { base, limiter }
+    }
+
+    fn limit_memory(&self, memory: Arc<dyn Memory>) -> Arc<dyn Memory> {
+        Arc::new(LimitedMemory::new(memory, self.limiter.clone()))
+    }
+
+    fn limit_table(&self, table: Arc<dyn Table>) -> Arc<dyn Table> {
+        Arc::new(LimitedTable::new(table, self.limiter.clone()))
+    }
+}
+
+impl Tunables for LimitingTunables {
+    fn memory_style(&self, memory: &MemoryType) -> MemoryStyle {
+        self.base.memory_style(memory)
+    }
+
+    fn table_style(&self, table: &TableType) -> TableStyle {
+        self.base.table_style(table)
+    }
+
+    fn create_host_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+    ) -> Result<Arc<dyn Memory>, MemoryError> {
+        Ok(self.limit_memory(self.base.create_host_memory(ty, style)?))
+    }
+
+    fn create_host_memory_with_buffer(
+        &self,
+        ty: &MemoryType,
+        buffer: Box<dyn HostBuffer>,
+    ) -> Result<Arc<dyn Memory>, MemoryError> {
+        Ok(self.limit_memory(self.base.create_host_memory_with_buffer(ty, buffer)?))
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    fn create_host_memory_file_backed(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+        file: std::fs::File,
+    ) -> Result<Arc<dyn Memory>, MemoryError> {
+        Ok(self.limit_memory(self.base.create_host_memory_file_backed(ty, style, file)?))
+    }
+
+    unsafe fn create_vm_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+        vm_definition_location: NonNull<VMMemoryDefinition>,
+    ) -> Result<Arc<dyn Memory>, MemoryError> {
+        Ok(self.limit_memory(
+            self.base
+                .create_vm_memory(ty, style, vm_definition_location)?,
+        ))
+    }
+
+    fn create_host_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+    ) -> Result<Arc<dyn Table>, String> {
+        Ok(self.limit_table(self.base.create_host_table(ty, style)?))
+    }
+
+    unsafe fn create_vm_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+        vm_definition_location: NonNull<VMTableDefinition>,
+    ) -> Result<Arc<dyn Table>, String> {
+        Ok(self.limit_table(
+            self.base
+                .create_vm_table(ty, style, vm_definition_location)?,
+        ))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -192,4 +332,46 @@ mod tests {
             s => panic!("Unexpected memory style: {:?}", s),
         }
     }
+
+    #[test]
+    fn preset_memory_styles() {
+        let tiny = BaseTunables::tiny();
+        let style = tiny.memory_style(&MemoryType::new(1, Some(16), false));
+        assert_eq!(
+            style,
+            MemoryStyle::Static {
+                bound: Pages(0x100),
+                offset_guard_size: 0,
+            }
+        );
+        assert!(style.needs_bounds_checks());
+        let style = tiny.memory_style(&MemoryType::new(1, None, false));
+        assert_eq!(
+            style,
+            MemoryStyle::Dynamic {
+                offset_guard_size: 0
+            }
+        );
+
+        let without_virtual_memory = BaseTunables::without_virtual_memory();
+        let style = without_virtual_memory.memory_style(&MemoryType::new(1, Some(16), false));
+        assert_eq!(
+            style,
+            MemoryStyle::Dynamic {
+                offset_guard_size: 0
+            }
+        );
+        assert!(style.needs_bounds_checks());
+
+        let server = BaseTunables::server();
+        let style = server.memory_style(&MemoryType::new(1, None, false));
+        assert_eq!(
+            style,
+            MemoryStyle::Static {
+                bound: Pages::max_value(),
+                offset_guard_size: 0x1_8000_0000,
+            }
+        );
+        assert!(!style.needs_bounds_checks());
+    }
 }
@@ -2,11 +2,11 @@ use crate::externals::Function;
 use crate::store::{Store, StoreObject};
 use crate::RuntimeError;
 use std::ptr;
-use wasmer_types::Value;
 pub use wasmer_types::{
-    ExportType, ExternRef, ExternType, FunctionType, GlobalType, HostInfo, HostRef, ImportType,
-    MemoryType, Mutability, TableType, Type as ValType,
+    ExportType, ExternRef, ExternRefScope, ExternType, FunctionType, GlobalType, HostInfo, HostRef,
+    ImportType, MemoryType, Mutability, TableType, Type as ValType,
 };
+use wasmer_types::{NullableFuncRef, Value};
 
 /// WebAssembly computations manipulate values of basic value types:
 /// * Integers (32 or 64 bit width)
@@ -27,6 +27,18 @@ impl StoreObject for Val {
     }
 }
 
+/// Null function references are represented as null `externref`s, so a
+/// `Function` is never null.
+impl NullableFuncRef for Function {
+    fn is_null(&self) -> bool {
+        false
+    }
+
+    fn null() -> Option<Self> {
+        None
+    }
+}
+
 impl From<Function> for Val {
     fn from(val: Function) -> Self {
         Self::FuncRef(val)
@@ -73,6 +85,9 @@ impl ValFuncRef for Val {
             .engine()
             .lookup_signature(item.type_index)
             .expect("Signature not found in store");
+        // All the functions with the same signature can be called with the
+        // same trampoline, which catches the traps of the callee.
+        let call_trampoline = store.engine().lookup_trampoline(item.type_index);
         let export = wasmer_engine::ExportFunction {
             // TODO:
             // figure out if we ever need a value here: need testing with complicated import patterns
@@ -84,7 +99,7 @@ impl ValFuncRef for Val {
                 // are converted to use the trampolines with static signatures).
                 kind: wasmer_vm::VMFunctionKind::Static,
                 vmctx: item.vmctx,
-                call_trampoline: None,
+                call_trampoline,
                 instance_ref: None,
             },
         };
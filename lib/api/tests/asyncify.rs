@@ -0,0 +1,124 @@
+use anyhow::Result;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+use wasmer::*;
+
+/// A module instrumented by hand like Asyncify does: `run` saves its local
+/// `x` when `sleep` unwinds, and restores it when rewinding.
+const ASYNCIFIED_MODULE: &str = r#"
+(module
+  (import "env" "sleep" (func $sleep (param i32) (result i32)))
+  (memory (export "memory") 1)
+  (global $state (mut i32) (i32.const 0))
+  (global $data (mut i32) (i32.const 0))
+  (func (export "asyncify_start_unwind") (param i32)
+    (global.set $state (i32.const 1))
+    (global.set $data (local.get 0)))
+  (func (export "asyncify_stop_unwind")
+    (global.set $state (i32.const 0)))
+  (func (export "asyncify_start_rewind") (param i32)
+    (global.set $state (i32.const 2))
+    (global.set $data (local.get 0)))
+  (func (export "asyncify_stop_rewind")
+    (global.set $state (i32.const 0)))
+  (func (export "asyncify_get_state") (result i32)
+    (global.get $state))
+  (func (export "run") (param $x i32) (result i32)
+    (local $result i32)
+    (if (i32.eq (global.get $state) (i32.const 2))
+      (then
+        ;; pop x
+        (i32.store (global.get $data) (i32.sub (i32.load (global.get $data)) (i32.const 4)))
+        (local.set $x (i32.load (i32.load (global.get $data))))))
+    (local.set $result (call $sleep (local.get $x)))
+    (if (i32.eq (global.get $state) (i32.const 1))
+      (then
+        ;; push x
+        (i32.store (i32.load (global.get $data)) (local.get $x))
+        (i32.store (global.get $data) (i32.add (i32.load (global.get $data)) (i32.const 4)))
+        (return (i32.const 0))))
+    (i32.add (local.get $result) (local.get $x))))
+"#;
+
+/// A future which is pending once before returning its value.
+struct YieldOnce(Option<i32>, bool);
+
+impl Future for YieldOnce {
+    type Output = i32;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<i32> {
+        if self.1 {
+            Poll::Ready(self.0.take().unwrap())
+        } else {
+            self.1 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    fn raw_waker() -> RawWaker {
+        fn clone(_: *const ()) -> RawWaker {
+            raw_waker()
+        }
+        fn noop(_: *const ()) {}
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+        RawWaker::new(std::ptr::null(), &VTABLE)
+    }
+    let waker = unsafe { Waker::from_raw(raw_waker()) };
+    let mut cx = Context::from_waker(&waker);
+    let mut future = Box::pin(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+#[test]
+fn asyncify_suspends_and_resumes() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(&store, ASYNCIFIED_MODULE)?;
+    assert!(Asyncify::is_asyncified(&module));
+    assert!(!Asyncify::is_asyncified(&Module::new(&store, "(module)")?));
+
+    fn sleep(asyncify: &Asyncify, x: i32) -> Result<i32, RuntimeError> {
+        let result = asyncify.handle_async(move || YieldOnce(Some(x * 10), false))?;
+        Ok(result.unwrap_or(-1))
+    }
+    let asyncify = Asyncify::new();
+    let import_object = imports! {
+        "env" => {
+            "sleep" => Function::new_native_with_env(&store, asyncify.clone(), sleep),
+        },
+    };
+    let instance = Instance::new(&module, &import_object)?;
+    asyncify.set_data(1024, 1024)?;
+    assert_eq!(asyncify.state()?, AsyncifyState::Normal);
+
+    let run = AsyncifiedFunction::new(
+        instance.exports.get_function("run")?.clone(),
+        asyncify.clone(),
+    );
+    let results = block_on(run.call_async(&[Val::I32(4)]))?;
+    assert_eq!(results.to_vec(), vec![Val::I32(44)]);
+    assert_eq!(asyncify.state()?, AsyncifyState::Normal);
+
+    // unwind by hand, and resume from a copy of the stack
+    let run = instance.exports.get_native_function::<i32, i32>("run")?;
+    assert_eq!(run.call(7)?, 0);
+    assert_eq!(asyncify.state()?, AsyncifyState::Unwinding);
+    asyncify.stop_unwind()?;
+    let stack = asyncify.saved_stack()?;
+    assert_eq!(stack, 7i32.to_le_bytes().to_vec());
+
+    asyncify.restore_stack(&2i32.to_le_bytes())?;
+    assert_eq!(asyncify.saved_stack()?, 2i32.to_le_bytes().to_vec());
+    asyncify.start_rewind()?;
+    // the future of the unwound call isn't awaited, so there's no output
+    assert!(run.call(7).is_err());
+
+    Ok(())
+}
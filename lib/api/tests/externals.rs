@@ -58,6 +58,22 @@ fn global_set() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn global_typed() -> Result<()> {
+    let store = Store::default();
+    let counter = Global::new_mut(&store, Value::I64(41));
+    counter.set_typed(counter.get_typed::<i64>()? + 1)?;
+    assert_eq!(counter.get(), Value::I64(42));
+    assert!(counter.get_typed::<i32>().is_err());
+    assert!(counter.set_typed(1.0f32).is_err());
+
+    let flag = Global::new(&store, Value::I32(-1));
+    assert_eq!(flag.get_typed::<u32>()?, u32::MAX);
+    assert!(flag.set_typed(0u32).is_err());
+
+    Ok(())
+}
+
 #[test]
 fn table_new() -> Result<()> {
     let store = Store::default();
@@ -127,6 +143,28 @@ fn table_grow() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn table_iter() -> Result<()> {
+    let store = Store::default();
+    let table_type = TableType {
+        ty: Type::FuncRef,
+        minimum: 3,
+        maximum: None,
+    };
+    let table = Table::new(&store, table_type, Value::ExternRef(ExternRef::Null))?;
+    let f = Function::new_native(&store, |num: i32| num + 1);
+    table.set(1, Value::FuncRef(f))?;
+
+    let elements = table.to_vec();
+    assert_eq!(elements.len(), 3);
+    assert!(matches!(elements[0], Value::ExternRef(ExternRef::Null)));
+    assert!(matches!(&elements[1], Value::FuncRef(f) if f.ty().params() == [Type::I32]));
+    assert!(matches!(elements[2], Value::ExternRef(ExternRef::Null)));
+    assert_eq!(table.iter().count(), 3);
+
+    Ok(())
+}
+
 #[test]
 #[ignore]
 fn table_copy() -> Result<()> {
@@ -134,6 +172,71 @@ fn table_copy() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn table_bulk_operations() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"(module
+             (import "env" "table" (table 4 funcref))
+             (type $t (func (result i32)))
+             (func (export "call") (param i32) (result i32)
+               (call_indirect (type $t) (local.get 0))))"#,
+    )?;
+    let table_type = TableType {
+        ty: Type::FuncRef,
+        minimum: 4,
+        maximum: None,
+    };
+    let table = Table::new(&store, table_type, Value::ExternRef(ExternRef::Null))?;
+    let one = Value::FuncRef(Function::new_native(&store, || 1));
+    let two = Value::FuncRef(Function::new_native(&store, || 2));
+
+    table.fill(0, one.clone(), 4)?;
+    table.init_from(2, &[two.clone()])?;
+    Table::copy(&table, 0, &table, 2, 1)?;
+
+    assert!(table.fill(3, two.clone(), 2).is_err());
+    assert!(table.init_from(3, &[two.clone(), two.clone()]).is_err());
+    assert!(table.init_from(0, &[two, Value::I32(0)]).is_err());
+
+    let instance = Instance::new(&module, &imports! { "env" => { "table" => table.clone() } })?;
+    let call: NativeFunc<i32, i32> = instance.exports.get_native_function("call")?;
+    let elements = (0..4)
+        .map(|i| call.call(i))
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(elements, vec![2, 1, 2, 1]);
+
+    Ok(())
+}
+
+#[test]
+fn table_call_indirect_dynamic_function() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"(module
+             (table (export "table") 1 funcref)
+             (type $t (func (param i32) (result i32)))
+             (func (export "call") (param i32) (result i32)
+               (call_indirect (type $t) (local.get 0) (i32.const 0))))"#,
+    )?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let table = instance.exports.get_table("table")?;
+    let increment = Function::new(
+        &store,
+        FunctionType::new(vec![Type::I32], vec![Type::I32]),
+        |args| Ok(vec![Value::I32(args[0].unwrap_i32() + 1)]),
+    );
+
+    table.set(0, Value::FuncRef(increment.clone()))?;
+
+    let call: NativeFunc<i32, i32> = instance.exports.get_native_function("call")?;
+    assert_eq!(call.call(41)?, 42);
+
+    Ok(())
+}
+
 #[test]
 fn memory_new() -> Result<()> {
     let store = Store::default();
@@ -177,6 +280,412 @@ fn memory_grow() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn memory_read_write() -> Result<()> {
+    let store = Store::default();
+    let memory = Memory::new(&store, MemoryType::new(1, None, false))?;
+    let end = WASM_PAGE_SIZE as u64;
+
+    memory.write(end - 4, &[1, 2, 3, 4])?;
+    let mut buf = [0u8; 4];
+    memory.read(end - 4, &mut buf)?;
+    assert_eq!(buf, [1, 2, 3, 4]);
+    assert_eq!(memory.read_vec(end - 2, 2)?, vec![3, 4]);
+    assert_eq!(memory.read_vec(end, 0)?, Vec::<u8>::new());
+
+    assert_eq!(
+        memory.write(end - 3, &[0; 4]),
+        Err(MemoryAccessError::HeapOutOfBounds)
+    );
+    assert_eq!(
+        memory.read(u64::MAX, &mut buf),
+        Err(MemoryAccessError::Overflow)
+    );
+    assert_eq!(memory.read_vec(end - 4, 4)?, vec![1, 2, 3, 4]);
+
+    // The new pages become accessible after a growth.
+    memory.grow(1)?;
+    memory.write(end, &[5])?;
+    assert_eq!(memory.read_vec(end - 1, 2)?, vec![4, 5]);
+
+    Ok(())
+}
+
+#[test]
+fn memory_atomic_wait_notify() -> Result<()> {
+    use std::time::Duration;
+
+    let store = Store::default();
+    let memory = Memory::new(&store, MemoryType::new(1, Some(1), true))?;
+    memory.write(8, &42u64.to_le_bytes())?;
+
+    assert_eq!(memory.atomic_notify(8, 1)?, 0);
+    assert_eq!(memory.atomic_wait64(8, 41, None)?, WaitResult::Mismatch);
+    assert_eq!(
+        memory.atomic_wait64(8, 42, Some(Duration::from_millis(10)))?,
+        WaitResult::TimedOut
+    );
+    assert_eq!(
+        memory.atomic_wait64(4, 0, None),
+        Err(MemoryAccessError::Unaligned)
+    );
+    assert_eq!(
+        memory.atomic_wait32(WASM_PAGE_SIZE as u64, 0, None),
+        Err(MemoryAccessError::HeapOutOfBounds)
+    );
+
+    let waiters = (0..2)
+        .map(|_| {
+            let memory = memory.clone();
+            std::thread::spawn(move || memory.atomic_wait64(8, 42, None))
+        })
+        .collect::<Vec<_>>();
+    let mut woken = 0;
+    while woken < 2 {
+        woken += memory.atomic_notify(8, 2)?;
+        std::thread::yield_now();
+    }
+    for waiter in waiters {
+        assert_eq!(waiter.join().unwrap(), Ok(WaitResult::Woken));
+    }
+
+    let unshared = Memory::new(&store, MemoryType::new(1, None, false))?;
+    assert_eq!(unshared.atomic_notify(0, 1)?, 0);
+    assert_eq!(
+        unshared.atomic_wait32(0, 0, None),
+        Err(MemoryAccessError::WaitUnsupported)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn memory_atomic_wait_notify_from_wasm() -> Result<()> {
+    const WAT: &str = r#"(module
+        (import "env" "memory" (memory 1 1 shared))
+        (func (export "wait32") (param i32 i32 i64) (result i32)
+          (memory.atomic.wait32 (local.get 0) (local.get 1) (local.get 2)))
+        (func (export "wait64") (param i32 i64 i64) (result i32)
+          (memory.atomic.wait64 offset=8 (local.get 0) (local.get 1) (local.get 2)))
+        (func (export "notify") (param i32 i32) (result i32)
+          (memory.atomic.notify (local.get 0) (local.get 1))))"#;
+    const UNSHARED_WAT: &str = r#"(module
+        (memory 1)
+        (func (export "wait32") (param i32 i32 i64) (result i32)
+          (memory.atomic.wait32 (local.get 0) (local.get 1) (local.get 2)))
+        (func (export "notify") (param i32 i32) (result i32)
+          (memory.atomic.notify (local.get 0) (local.get 1))))"#;
+
+    let mut features = Features::new();
+    features.threads(true);
+    let store = Store::new(&JIT::new(Cranelift::default()).features(features).engine());
+    let memory = Memory::new(&store, MemoryType::new(1, Some(1), true))?;
+    let module = Module::new(&store, WAT)?;
+    let instance = Instance::new(
+        &module,
+        &imports! { "env" => { "memory" => memory.clone() } },
+    )?;
+    let wait32: NativeFunc<(i32, i32, i64), i32> =
+        instance.exports.get_native_function("wait32")?;
+    let wait64: NativeFunc<(i32, i64, i64), i32> =
+        instance.exports.get_native_function("wait64")?;
+    let notify: NativeFunc<(i32, i32), i32> = instance.exports.get_native_function("notify")?;
+
+    assert_eq!(wait32.call(0, 1, -1)?, WaitResult::Mismatch as i32);
+    assert_eq!(wait32.call(0, 0, 1_000_000)?, WaitResult::TimedOut as i32);
+    assert_eq!(wait64.call(8, 0, 0)?, WaitResult::TimedOut as i32);
+    assert_eq!(notify.call(0, 1)?, 0);
+    let trap = wait32.call(2, 0, -1).unwrap_err();
+    assert_eq!(trap.message(), "unaligned atomic access");
+    let trap = wait32.call(WASM_PAGE_SIZE as i32, 0, -1).unwrap_err();
+    assert_eq!(trap.message(), "out of bounds memory access");
+    let trap = notify.call(WASM_PAGE_SIZE as i32, 1).unwrap_err();
+    assert_eq!(trap.message(), "out of bounds memory access");
+
+    // The guest waits and the host notifies.
+    let waiter = std::thread::spawn(move || wait32.call(0, 0, -1));
+    while memory.atomic_notify(0, 1)? == 0 {
+        std::thread::yield_now();
+    }
+    assert_eq!(waiter.join().unwrap()?, WaitResult::Woken as i32);
+
+    // The host waits and the guest notifies, on the effective address of
+    // `wait64`.
+    let waiter = {
+        let memory = memory.clone();
+        std::thread::spawn(move || memory.atomic_wait64(16, 0, None))
+    };
+    while notify.call(16, 1)? == 0 {
+        std::thread::yield_now();
+    }
+    assert_eq!(waiter.join().unwrap(), Ok(WaitResult::Woken));
+
+    // Nothing can wait on a locally defined memory that is not shared.
+    let instance = Instance::new(&Module::new(&store, UNSHARED_WAT)?, &imports! {})?;
+    let wait32: NativeFunc<(i32, i32, i64), i32> =
+        instance.exports.get_native_function("wait32")?;
+    let notify: NativeFunc<(i32, i32), i32> = instance.exports.get_native_function("notify")?;
+    let trap = wait32.call(0, 0, -1).unwrap_err();
+    assert_eq!(trap.message(), "atomic wait on non-shared memory");
+    assert_eq!(notify.call(0, 1)?, 0);
+
+    Ok(())
+}
+
+#[test]
+fn memory_subscribe_grow() -> Result<()> {
+    use std::sync::{Arc, Mutex};
+
+    let store = Store::default();
+    let memory = Memory::new(&store, MemoryType::new(1, Some(4), false))?;
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let events2 = events.clone();
+    memory.subscribe_grow(move |old, new| events2.lock().unwrap().push((old, new)))?;
+
+    memory.grow(1)?;
+    // Neither empty nor failed growths are reported.
+    memory.grow(0)?;
+    assert!(memory.grow(10).is_err());
+
+    let module = Module::new(
+        &store,
+        r#"(module
+             (import "env" "memory" (memory 1))
+             (func (export "grow") (param i32) (result i32)
+               (memory.grow (local.get 0))))"#,
+    )?;
+    let instance = Instance::new(
+        &module,
+        &imports! { "env" => { "memory" => memory.clone() } },
+    )?;
+    let grow: NativeFunc<i32, i32> = instance.exports.get_native_function("grow")?;
+    assert_eq!(grow.call(2)?, 2);
+
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec![(Pages(1), Pages(2)), (Pages(2), Pages(4))]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn memory_new_with_buffer() -> Result<()> {
+    let engine = Store::default().engine().clone();
+    let store = Store::new_with_tunables(&*engine, BaseTunables::tiny());
+
+    let mut buffer = vec![0u8; 2 * WASM_PAGE_SIZE];
+    buffer[42] = 7;
+    let memory = Memory::new_with_buffer(&store, MemoryType::new(1, None, false), buffer)?;
+    assert_eq!(memory.size(), Pages(1));
+
+    let module = Module::new(
+        &store,
+        r#"(module
+             (import "env" "memory" (memory 1))
+             (func (export "load") (param i32) (result i32)
+               (i32.load8_u (local.get 0)))
+             (func (export "grow") (param i32) (result i32)
+               (memory.grow (local.get 0))))"#,
+    )?;
+    let import_object = imports! {
+        "env" => {
+            "memory" => memory.clone(),
+        },
+    };
+    let instance = Instance::new(&module, &import_object)?;
+    let load: NativeFunc<i32, i32> = instance.exports.get_native_function("load")?;
+    let grow: NativeFunc<i32, i32> = instance.exports.get_native_function("grow")?;
+    assert_eq!(load.call(42)?, 7);
+    assert!(load.call(WASM_PAGE_SIZE as i32).is_err());
+    assert_eq!(grow.call(1)?, 1);
+    assert_eq!(load.call(WASM_PAGE_SIZE as i32)?, 0);
+    assert_eq!(grow.call(1)?, -1);
+
+    // The buffer has no guard pages, unlike the memories the default
+    // tunables expect for the same memory type.
+    let store = Store::default();
+    let memory = Memory::new_with_buffer(
+        &store,
+        MemoryType::new(1, None, false),
+        vec![0u8; WASM_PAGE_SIZE],
+    )?;
+    let module = Module::new(&store, r#"(module (import "env" "memory" (memory 1)))"#)?;
+    let import_object = imports! {
+        "env" => {
+            "memory" => memory,
+        },
+    };
+    assert!(matches!(
+        Instance::new(&module, &import_object),
+        Err(InstantiationError::Link(LinkError::Import(
+            _,
+            _,
+            ImportError::IncompatibleMemoryStyle(..)
+        )))
+    ));
+
+    Ok(())
+}
+
+#[cfg(not(target_os = "windows"))]
+#[test]
+fn memory_new_file_backed() -> Result<()> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let store = Store::default();
+    let mut file = tempfile::tempfile()?;
+
+    let memory =
+        Memory::new_file_backed(&store, MemoryType::new(1, None, false), file.try_clone()?)?;
+    assert_eq!(memory.size(), Pages(1));
+    memory.grow(1)?;
+    unsafe {
+        memory.data_unchecked_mut()[WASM_PAGE_SIZE + 1] = 42;
+    }
+    memory.flush()?;
+    drop(memory);
+
+    let mut contents = Vec::new();
+    file.seek(SeekFrom::Start(0))?;
+    file.read_to_end(&mut contents)?;
+    assert_eq!(contents.len(), 2 * WASM_PAGE_SIZE);
+    assert_eq!(contents[WASM_PAGE_SIZE + 1], 42);
+
+    // Reopening the file restores the previous size and contents.
+    let memory =
+        Memory::new_file_backed(&store, MemoryType::new(1, None, false), file.try_clone()?)?;
+    assert_eq!(memory.size(), Pages(2));
+    assert_eq!(unsafe { memory.data_unchecked()[WASM_PAGE_SIZE + 1] }, 42);
+    drop(memory);
+
+    // Without guard pages, the whole file must still be mapped when it is
+    // larger than the minimum of the memory.
+    let engine = store.engine().clone();
+    let tiny_store = Store::new_with_tunables(&*engine, BaseTunables::tiny());
+    let memory = Memory::new_file_backed(&tiny_store, MemoryType::new(1, None, false), file)?;
+    assert_eq!(memory.size(), Pages(2));
+    assert_eq!(unsafe { memory.data_unchecked()[WASM_PAGE_SIZE + 1] }, 42);
+    memory.grow(1)?;
+    assert_eq!(unsafe { memory.data_unchecked()[WASM_PAGE_SIZE + 1] }, 42);
+
+    Ok(())
+}
+
+#[test]
+fn memory_share_in_store() -> Result<()> {
+    const WAT: &str = r#"(module
+        (import "env" "memory" (memory 1 2 shared))
+        (func (export "load") (param i32) (result i32)
+          (i32.load8_u (local.get 0)))
+        (func (export "store") (param i32 i32)
+          (i32.store8 (local.get 0) (local.get 1))))"#;
+
+    fn store_with_threads() -> Store {
+        let mut features = Features::new();
+        features.threads(true);
+        let engine = JIT::new(Cranelift::default()).features(features).engine();
+        Store::new(&engine)
+    }
+
+    let store = store_with_threads();
+    let memory = Memory::new(&store, MemoryType::new(1, Some(2), true))?;
+    let module = Module::new(&store, WAT)?;
+    let instance = Instance::new(
+        &module,
+        &imports! { "env" => { "memory" => memory.clone() } },
+    )?;
+    let store_byte: NativeFunc<(i32, i32), ()> = instance.exports.get_native_function("store")?;
+    store_byte.call(7, 42)?;
+
+    let other_store = store_with_threads();
+    let shared = memory.share_in_store(&other_store)?;
+    let loaded = std::thread::spawn(move || -> Result<i32> {
+        let module = Module::new(&other_store, WAT)?;
+        let instance = Instance::new(&module, &imports! { "env" => { "memory" => shared } })?;
+        let load: NativeFunc<i32, i32> = instance.exports.get_native_function("load")?;
+        Ok(load.call(7)?)
+    })
+    .join()
+    .unwrap()?;
+    assert_eq!(loaded, 42);
+
+    let unshared = Memory::new(&store, MemoryType::new(1, Some(2), false))?;
+    assert!(unshared.share_in_store(&store).is_ok());
+    assert!(unshared.share_in_store(&Store::default()).is_err());
+
+    Ok(())
+}
+
+#[derive(Debug)]
+struct MaxPagesLimiter(u32);
+
+impl ResourceLimiter for MaxPagesLimiter {
+    fn memory_growing(&self, _current: Pages, desired: Pages, _maximum: Option<Pages>) -> bool {
+        desired.0 <= self.0
+    }
+
+    fn table_growing(&self, _current: u32, desired: u32, _maximum: Option<u32>) -> bool {
+        desired <= self.0
+    }
+}
+
+#[test]
+fn resource_limiter() -> Result<()> {
+    let mut store = Store::default();
+    store.set_resource_limiter(MaxPagesLimiter(4));
+
+    let memory = Memory::new(&store, MemoryType::new(Pages(1), None, false))?;
+    assert_eq!(memory.grow(Pages(3))?, Pages(1));
+    assert_eq!(
+        memory.grow(Pages(1)),
+        Err(MemoryError::CouldNotGrow {
+            current: 4.into(),
+            attempted_delta: 1.into(),
+        })
+    );
+    assert_eq!(memory.size(), Pages(4));
+
+    let table_type = TableType::new(Type::FuncRef, 0, None);
+    let f = Function::new_native(&store, |num: i32| num + 1);
+    let table = Table::new(&store, table_type, Value::FuncRef(f.clone()))?;
+    assert_eq!(table.grow(4, Value::FuncRef(f.clone()))?, 0);
+    assert!(table.grow(1, Value::FuncRef(f)).is_err());
+
+    let module = Module::new(
+        &store,
+        r#"(module
+             (memory 1)
+             (func (export "grow") (param i32) (result i32)
+               (memory.grow (local.get 0))))"#,
+    )?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let grow: NativeFunc<i32, i32> = instance.exports.get_native_function("grow")?;
+    assert_eq!(grow.call(3)?, 1);
+    assert_eq!(grow.call(1)?, -1);
+
+    let memory = Memory::new_with_buffer(
+        &store,
+        MemoryType::new(Pages(1), None, false),
+        vec![0u8; 8 * WASM_PAGE_SIZE],
+    )?;
+    assert_eq!(memory.grow(Pages(3))?, Pages(1));
+    assert!(memory.grow(Pages(1)).is_err());
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        let memory = Memory::new_file_backed(
+            &store,
+            MemoryType::new(Pages(1), None, false),
+            tempfile::tempfile()?,
+        )?;
+        assert_eq!(memory.grow(Pages(3))?, Pages(1));
+        assert!(memory.grow(Pages(1)).is_err());
+    }
+
+    Ok(())
+}
+
 #[test]
 fn function_new() -> Result<()> {
     let store = Store::default();
@@ -374,6 +883,37 @@ fn native_function_works() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn native_function_call_dyn() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+        (module
+          (func (export "sub") (param i32 i64) (result i64)
+            (i64.sub (i64.extend_i32_s (local.get 0)) (local.get 1))))
+        "#,
+    )?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let sub: NativeFunc<(i32, i64), i64> = instance.exports.get_native_function("sub")?;
+
+    assert_eq!(sub.call(10, 3)?, 7);
+    assert_eq!(
+        sub.call_dyn(&[Value::I32(10), Value::I64(3)])?.to_vec(),
+        vec![Value::I64(7)]
+    );
+    assert!(sub.call_dyn(&[Value::I32(10)]).is_err());
+
+    let function = sub.to_function();
+    assert_eq!(function.ty().params(), &[Type::I32, Type::I64]);
+    assert_eq!(
+        function.call(&[Value::I32(1), Value::I64(3)])?.to_vec(),
+        vec![Value::I64(-2)]
+    );
+
+    Ok(())
+}
+
 #[test]
 fn function_outlives_instance() -> Result<()> {
     let store = Store::default();
@@ -429,3 +969,134 @@ fn manually_generate_wasmer_env() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn wasmer_env_finish() -> Result<()> {
+    let store = Store::default();
+    #[derive(WasmerEnv, Clone)]
+    #[wasmer(finish = "MyEnv::finish_env")]
+    struct MyEnv {
+        #[wasmer(export)]
+        memory: LazyInit<Memory>,
+        pages: u32,
+    }
+
+    impl MyEnv {
+        fn finish_env(&mut self, instance: &Instance) -> Result<(), HostEnvInitError> {
+            self.pages = self.memory_ref().unwrap().size().0;
+            instance
+                .exports
+                .get_native_function::<(), ()>("init")?
+                .call()?;
+            Ok(())
+        }
+    }
+
+    fn pages(env: &MyEnv) -> u32 {
+        env.pages
+    }
+
+    let module = Module::new(
+        &store,
+        r#"
+        (module
+          (import "env" "pages" (func $pages (result i32)))
+          (memory (export "memory") 2)
+          (global (export "initialized") (mut i32) (i32.const 0))
+          (func (export "init")
+            (global.set 0 (i32.const 1)))
+          (func (export "run") (result i32)
+            (call $pages)))
+        "#,
+    )?;
+    let env = MyEnv {
+        memory: LazyInit::new(),
+        pages: 0,
+    };
+    let import_object = imports! {
+        "env" => {
+            "pages" => Function::new_native_with_env(&store, env, pages),
+        },
+    };
+    let instance = Instance::new(&module, &import_object)?;
+
+    assert_eq!(
+        instance.exports.get_global("initialized")?.get(),
+        Value::I32(1)
+    );
+    let run = instance.exports.get_native_function::<(), u32>("run")?;
+    assert_eq!(run.call()?, 2);
+
+    Ok(())
+}
+
+#[test]
+fn wasmer_env_finish_once_per_instance() -> Result<()> {
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    let store = Store::default();
+    #[derive(WasmerEnv, Clone)]
+    #[wasmer(finish = "MyEnv::finish_env")]
+    struct MyEnv {
+        #[wasmer(export)]
+        memory: LazyInit<Memory>,
+        finish_calls: Arc<AtomicU32>,
+    }
+
+    impl MyEnv {
+        fn finish_env(&mut self, instance: &Instance) -> Result<(), HostEnvInitError> {
+            self.finish_calls.fetch_add(1, Ordering::SeqCst);
+            // `init` calls `$pages`, whose env must already be initialized.
+            instance
+                .exports
+                .get_native_function::<(), ()>("init")?
+                .call()?;
+            Ok(())
+        }
+    }
+
+    fn pages(env: &MyEnv) -> u32 {
+        env.memory_ref().unwrap().size().0
+    }
+
+    fn calls(env: &MyEnv) -> u32 {
+        env.finish_calls.load(Ordering::SeqCst)
+    }
+
+    let module = Module::new(
+        &store,
+        r#"
+        (module
+          (import "env" "calls" (func $calls (result i32)))
+          (import "env" "pages" (func $pages (result i32)))
+          (memory (export "memory") 2)
+          (global (export "pages") (mut i32) (i32.const 0))
+          (func (export "init")
+            (global.set 0 (call $pages)))
+          (func (export "calls") (result i32)
+            (call $calls)))
+        "#,
+    )?;
+    let env = MyEnv {
+        memory: LazyInit::new(),
+        finish_calls: Arc::new(AtomicU32::new(0)),
+    };
+    let import_object = imports! {
+        "env" => {
+            "calls" => Function::new_native_with_env(&store, env.clone(), calls),
+            "pages" => Function::new_native_with_env(&store, env.clone(), pages),
+        },
+    };
+
+    let instance = Instance::new(&module, &import_object)?;
+    assert_eq!(instance.exports.get_global("pages")?.get(), Value::I32(2));
+    let calls = instance.exports.get_native_function::<(), u32>("calls")?;
+    assert_eq!(calls.call()?, 1);
+
+    // Each instance gets its own call to `finish`.
+    Instance::new(&module, &import_object)?;
+    assert_eq!(calls.call()?, 2);
+
+    Ok(())
+}
@@ -37,3 +37,215 @@ fn exports_work_after_multiple_instances_have_been_freed() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn exports_iterate_by_kind() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"(module
+             (func (export "f1"))
+             (func (export "f2") (param i32) (result i32) local.get 0)
+             (memory (export "mem") 1)
+             (global (export "g") i32 (i32.const 7)))"#,
+    )?;
+    let instance = Instance::new(&module, &ImportObject::new())?;
+    let exports = &instance.exports;
+
+    let functions = exports
+        .iter_functions()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(functions, vec!["f1", "f2"]);
+    assert_eq!(exports.iter_memories().count(), 1);
+    assert_eq!(exports.iter_globals().count(), 1);
+    assert_eq!(exports.iter_tables().count(), 0);
+
+    let (extern_, ty) = exports.get_extern_with_type("f2")?;
+    assert!(matches!(extern_, Extern::Function(_)));
+    assert_eq!(
+        ty,
+        ExternType::Function(FunctionType::new(vec![Type::I32], vec![Type::I32]))
+    );
+    assert!(matches!(
+        exports.get_extern_with_type("missing"),
+        Err(ExportError::Missing(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn fallback_resolver_synthesizes_missing_imports() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+        (module
+          (import "env" "answer" (func $answer (result i32)))
+          (import "log" "enter" (func $enter (param i32)))
+          (func (export "run") (result i32)
+            (call $enter (i32.const 1))
+            (call $answer)))
+        "#,
+    )?;
+
+    let import_object = imports! {
+        "env" => {
+            "answer" => Function::new_native(&store, || 42),
+        }
+    };
+    let requested = std::sync::Mutex::new(Vec::new());
+    let resolver = FallbackResolver::new(&module, import_object, |module, field, ty| {
+        requested
+            .lock()
+            .unwrap()
+            .push(format!("{}.{}", module, field));
+        match ty {
+            ExternType::Function(ty) => {
+                Some(Extern::Function(Function::new(&store, ty, |_| Ok(vec![]))))
+            }
+            _ => None,
+        }
+    });
+
+    let instance = Instance::new(&module, &resolver)?;
+    let run = instance.exports.get_native_function::<(), i32>("run")?;
+    assert_eq!(run.call()?, 42);
+    assert_eq!(*requested.lock().unwrap(), vec!["log.enter".to_string()]);
+
+    Ok(())
+}
+
+#[test]
+fn instance_snapshot_and_restore() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+        (module
+          (import "env" "imported" (global $imported (mut i32)))
+          (memory (export "memory") 1)
+          (global $counter (export "counter") (mut i64) (i64.const 5))
+          (global (export "constant") i32 (i32.const 1))
+          (table (export "table") 2 funcref)
+          (func $one (result i32) (i32.const 1))
+          (elem (i32.const 0) $one)
+          (func (export "mutate")
+            (i32.store (i32.const 16) (i32.const 0xdeadbeef))
+            (global.set $counter (i64.const 42))
+            (global.set $imported (i32.const 7))))
+        "#,
+    )?;
+    let imported = Global::new_mut(&store, Value::I32(0));
+    let import_object = imports! { "env" => { "imported" => imported.clone() } };
+    let instance = Instance::new(&module, &import_object)?;
+    let memory = instance.exports.get_memory("memory")?;
+    let counter = instance.exports.get_global("counter")?;
+    let table = instance.exports.get_table("table")?;
+
+    let snapshot = instance.snapshot();
+    assert_eq!(snapshot.memories().len(), 1);
+    assert_eq!(snapshot.memories()[0].len(), 0x10000);
+    assert_eq!(snapshot.globals(), &[Value::I64(5), Value::I32(1)]);
+    assert_eq!(snapshot.tables()[0].len(), 2);
+
+    instance
+        .exports
+        .get_native_function::<(), ()>("mutate")?
+        .call()?;
+    table.set(0, Value::ExternRef(ExternRef::Null))?;
+    assert_eq!(memory.read_vec(16, 4)?, vec![0xef, 0xbe, 0xad, 0xde]);
+    assert_eq!(counter.get(), Value::I64(42));
+
+    instance.restore(&snapshot)?;
+    assert_eq!(memory.read_vec(16, 4)?, vec![0; 4]);
+    assert_eq!(counter.get(), Value::I64(5));
+    assert!(matches!(table.get(0), Some(Value::FuncRef(_))));
+    // Imported globals are not part of the snapshot.
+    assert_eq!(imported.get(), Value::I32(7));
+
+    // Memories and tables can't shrink back to their size in the snapshot,
+    // but what grew is reset.
+    instance
+        .exports
+        .get_native_function::<(), ()>("mutate")?
+        .call()?;
+    memory.grow(1)?;
+    memory.write(0x10000, &[1, 2, 3, 4])?;
+    table.grow(1, Value::FuncRef(Function::new_native(&store, || 2)))?;
+    instance.restore(&snapshot)?;
+    assert_eq!(memory.size(), Pages(2));
+    assert_eq!(memory.read_vec(16, 4)?, vec![0; 4]);
+    assert_eq!(memory.read_vec(0x10000, 4)?, vec![0; 4]);
+    assert_eq!(counter.get(), Value::I64(5));
+    assert_eq!(table.size(), 3);
+    assert!(matches!(
+        table.get(2),
+        Some(Value::ExternRef(ExternRef::Null))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn deterministic_store_canonicalizes_nans() -> Result<()> {
+    let wat = r#"
+        (module
+          (func (export "nan") (param f32) (result i32)
+            (i32.reinterpret_f32 (f32.div (local.get 0) (local.get 0)))))
+    "#;
+
+    assert!(!Store::default().is_deterministic());
+    let store = Store::deterministic();
+    assert!(store.is_deterministic());
+
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let nan = instance.exports.get_native_function::<f32, u32>("nan")?;
+    assert_eq!(nan.call(0.0)?, 0x7fc0_0000);
+
+    Ok(())
+}
+
+#[test]
+fn fallback_resolver_looks_up_imports_by_name() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+        (module
+          (import "env" "answer" (func (result i32)))
+          (import "log" "enter" (func (param i32))))
+        "#,
+    )?;
+    let reordered = Module::new(
+        &store,
+        r#"
+        (module
+          (import "log" "enter" (func $enter (param i32)))
+          (import "env" "answer" (func $answer (result i32)))
+          (func (export "run") (result i32)
+            (call $enter (i32.const 1))
+            (call $answer)))
+        "#,
+    )?;
+
+    // The resolver is built for `module`, but the types of the imports of
+    // `reordered` must still be found by their names.
+    let resolver = FallbackResolver::new(&module, imports! {}, |_module, field, ty| match ty {
+        ExternType::Function(ty) => {
+            let answer = if field == "answer" { Some(42) } else { None };
+            Some(Extern::Function(Function::new(&store, ty, move |_| {
+                Ok(answer.into_iter().map(Value::I32).collect())
+            })))
+        }
+        _ => None,
+    });
+
+    let instance = Instance::new(&reordered, &resolver)?;
+    let run = instance.exports.get_native_function::<(), i32>("run")?;
+    assert_eq!(run.call()?, 42);
+
+    Ok(())
+}
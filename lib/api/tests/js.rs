@@ -0,0 +1,234 @@
+#![cfg(all(feature = "js", target_arch = "wasm32"))]
+
+use anyhow::Result;
+use std::sync::{Arc, Mutex};
+use wasm_bindgen_test::*;
+use wasmer::*;
+
+const SUM_WAT: &str = r#"
+(module
+  (func (export "sum") (param i32 i32) (result i32)
+    local.get 0
+    local.get 1
+    i32.add))
+"#;
+
+#[wasm_bindgen_test]
+fn instantiate_and_call() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(&store, SUM_WAT)?;
+    let instance = Instance::new(&module, &imports! {})?;
+
+    let sum = instance.exports.get_function("sum")?;
+    assert_eq!(
+        sum.call(&[Value::I32(1), Value::I32(2)])?.into_vec(),
+        vec![Value::I32(3)],
+    );
+
+    Ok(())
+}
+
+#[wasm_bindgen_test]
+fn get_native_function() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(&store, SUM_WAT)?;
+    let instance = Instance::new(&module, &imports! {})?;
+
+    let sum: NativeFunc<(i32, i32), i32> = instance.exports.get_native_function("sum")?;
+    assert_eq!(sum.call(40, 2)?, 42);
+
+    let sum: NativeFunc<(i32, i32), i32> = instance.exports.get_function("sum")?.native()?;
+    assert_eq!(sum.call(-1, 1)?, 0);
+
+    assert!(matches!(
+        instance
+            .exports
+            .get_native_function::<(i64, i64), i64>("sum"),
+        Err(ExportError::IncompatibleType)
+    ));
+    let error = match instance.exports.get_function("sum")?.native::<i32, i32>() {
+        Ok(_) => panic!("the signature of the function doesn't match"),
+        Err(error) => error,
+    };
+    assert_eq!(
+        error.message(),
+        "given types (`[I32]`) for the function arguments don't match the actual types (`[I32, I32]`)"
+    );
+
+    Ok(())
+}
+
+#[wasm_bindgen_test]
+fn native_host_function() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+(module
+  (import "env" "multiply" (func $multiply (param i32 i64) (result i64)))
+  (func (export "call_multiply") (param i32 i64) (result i64)
+    local.get 0
+    local.get 1
+    call $multiply))
+"#,
+    )?;
+
+    fn multiply(a: i32, b: i64) -> i64 {
+        a as i64 * b
+    }
+
+    let import_object = imports! {
+        "env" => {
+            "multiply" => Function::new_native(&store, multiply),
+        },
+    };
+    let instance = Instance::new(&module, &import_object)?;
+
+    let call_multiply: NativeFunc<(i32, i64), i64> =
+        instance.exports.get_native_function("call_multiply")?;
+    assert_eq!(call_multiply.call(3, 1 << 40)?, 3 << 40);
+
+    Ok(())
+}
+
+#[wasm_bindgen_test]
+fn native_host_function_error() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+(module
+  (import "env" "fail" (func $fail))
+  (func (export "run") call $fail))
+"#,
+    )?;
+
+    fn fail() -> Result<(), RuntimeError> {
+        Err(RuntimeError::new("host function failed"))
+    }
+
+    let import_object = imports! {
+        "env" => {
+            "fail" => Function::new_native(&store, fail),
+        },
+    };
+    let instance = Instance::new(&module, &import_object)?;
+
+    let run: NativeFunc = instance.exports.get_native_function("run")?;
+    let error = run.call().unwrap_err();
+    assert!(error.message().contains("host function failed"));
+
+    Ok(())
+}
+
+#[derive(WasmerEnv, Clone)]
+struct Env {
+    #[wasmer(export)]
+    memory: LazyInit<Memory>,
+    #[wasmer(export(name = "double"))]
+    double: LazyInit<NativeFunc<i32, i32>>,
+    seen: Arc<Mutex<Vec<u8>>>,
+}
+
+#[wasm_bindgen_test]
+fn host_function_with_env() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+(module
+  (import "env" "record" (func $record (param i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 16) "hello")
+  (func (export "double") (param i32) (result i32)
+    local.get 0
+    i32.const 2
+    i32.mul)
+  (func (export "run") (result i32)
+    i32.const 16
+    i32.const 5
+    call $record))
+"#,
+    )?;
+
+    fn record(env: &Env, ptr: i32, len: i32) -> i32 {
+        let memory = env.memory_ref().unwrap();
+        let bytes = memory.read_vec(ptr as u64, len as usize).unwrap();
+        env.seen.lock().unwrap().extend_from_slice(&bytes);
+        env.double_ref().unwrap().call(len).unwrap()
+    }
+
+    let env = Env {
+        memory: LazyInit::new(),
+        double: LazyInit::new(),
+        seen: Arc::new(Mutex::new(Vec::new())),
+    };
+    let seen = env.seen.clone();
+    let import_object = imports! {
+        "env" => {
+            "record" => Function::new_native_with_env(&store, env, record),
+        },
+    };
+    let instance = Instance::new(&module, &import_object)?;
+
+    let run: NativeFunc<(), i32> = instance.exports.get_native_function("run")?;
+    assert_eq!(run.call()?, 10);
+    assert_eq!(&*seen.lock().unwrap(), b"hello");
+
+    Ok(())
+}
+
+#[wasm_bindgen_test]
+fn dynamic_host_function_with_env() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+(module
+  (import "env" "count" (func $count (param i32)))
+  (func (export "run")
+    i32.const 1
+    call $count
+    i32.const 2
+    call $count))
+"#,
+    )?;
+
+    let total = Arc::new(Mutex::new(0));
+    let import_object = imports! {
+        "env" => {
+            "count" => Function::new_with_env(
+                &store,
+                FunctionType::new(vec![Type::I32], vec![]),
+                total.clone(),
+                |total: &Arc<Mutex<i32>>, params: &[Val]| {
+                    *total.lock().unwrap() += params[0].unwrap_i32();
+                    Ok(vec![])
+                },
+            ),
+        },
+    };
+    let instance = Instance::new(&module, &import_object)?;
+
+    instance.exports.get_function("run")?.call(&[])?;
+    assert_eq!(*total.lock().unwrap(), 3);
+
+    Ok(())
+}
+
+#[wasm_bindgen_test]
+fn memory_read_write() -> Result<()> {
+    let store = Store::default();
+    let memory = Memory::new(&store, MemoryType::new(1, Some(2), false))?;
+
+    memory.write(8, b"wasmer")?;
+    assert_eq!(memory.read_vec(8, 6)?, b"wasmer");
+
+    memory.grow(1)?;
+    assert_eq!(memory.size(), Pages(2));
+    // The bytes are kept when the buffer is detached by `grow`.
+    assert_eq!(memory.read_vec(8, 6)?, b"wasmer");
+    assert!(memory.write(2 * WASM_PAGE_SIZE as u64 - 2, b"abc").is_err());
+
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use anyhow::Result;
+use wasmer::*;
+
+#[test]
+fn linker_define_and_instantiate() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+        (module
+          (import "env" "add" (func $add (param i32 i32) (result i32)))
+          (import "env" "base" (global $base i32))
+          (func (export "run") (result i32)
+            (call $add (global.get $base) (i32.const 2))))
+        "#,
+    )?;
+
+    let mut linker = Linker::new(&store);
+    linker
+        .define(
+            "env",
+            "add",
+            Function::new_native(&store, |a: i32, b: i32| a + b),
+        )?
+        .define("env", "base", Global::new(&store, Value::I32(40)))?;
+    assert!(linker.get("env", "add").is_some());
+    assert!(linker.get("env", "missing").is_none());
+
+    let instance = linker.instantiate(&module)?;
+    let run = instance.exports.get_native_function::<(), i32>("run")?;
+    assert_eq!(run.call()?, 42);
+
+    Ok(())
+}
+
+#[test]
+fn linker_duplicates_and_shadowing() -> Result<()> {
+    let store = Store::default();
+    let mut linker = Linker::new(&store);
+    linker.define("env", "x", Global::new(&store, Value::I32(1)))?;
+
+    match linker.define("env", "x", Global::new(&store, Value::I32(2))) {
+        Err(LinkerError::Duplicate { module, name }) => {
+            assert_eq!(module, "env");
+            assert_eq!(name, "x");
+        }
+        _ => panic!("expected a duplicate definition error"),
+    }
+
+    linker.allow_shadowing(true);
+    linker.define("env", "x", Global::new(&store, Value::I32(2)))?;
+    match linker.get("env", "x") {
+        Some(Extern::Global(global)) => assert_eq!(global.get(), Value::I32(2)),
+        _ => panic!("expected a global"),
+    }
+    assert_eq!(linker.iter().count(), 1);
+
+    let other_store = Store::default();
+    assert!(matches!(
+        linker.define("env", "y", Global::new(&other_store, Value::I32(3))),
+        Err(LinkerError::DifferentStores { .. })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn linker_modules_and_aliases() -> Result<()> {
+    let store = Store::default();
+    let provider = Module::new(
+        &store,
+        r#"
+        (module
+          (func (export "answer") (result i32) (i32.const 42)))
+        "#,
+    )?;
+    let consumer = Module::new(
+        &store,
+        r#"
+        (module
+          (import "lib" "answer" (func $answer (result i32)))
+          (func (export "run") (result i32) (call $answer)))
+        "#,
+    )?;
+
+    let mut linker = Linker::new(&store);
+    linker.module("provider", &provider)?;
+    linker.alias("provider", "lib")?;
+
+    let instance = linker.instantiate(&consumer)?;
+    let run = instance.exports.get_native_function::<(), i32>("run")?;
+    assert_eq!(run.call()?, 42);
+
+    Ok(())
+}
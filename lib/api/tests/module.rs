@@ -24,6 +24,58 @@ fn module_set_name() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn module_weak_handles() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(&store, "(module $name)")?;
+    let weak = module.downgrade();
+    assert_eq!(weak.upgrade().unwrap().name(), Some("name"));
+
+    let instance = Instance::new(&module, &imports! {})?;
+    drop(module);
+    assert!(weak.upgrade().is_some());
+
+    drop(instance);
+    assert!(weak.upgrade().is_none());
+
+    Ok(())
+}
+
+#[test]
+fn module_custom_sections() -> Result<()> {
+    let store = Store::default();
+    let mut wasm = wat2wasm(b"(module $name (func (export \"f\")))")?.into_owned();
+    for (name, data) in &[(".debug_info", &b"dwarf"[..]), ("policy", b"strict")] {
+        wasm.push(0);
+        wasm.push((1 + name.len() + data.len()) as u8);
+        wasm.push(name.len() as u8);
+        wasm.extend_from_slice(name.as_bytes());
+        wasm.extend_from_slice(data);
+    }
+    let module = Module::new(&store, wasm)?;
+    assert_eq!(
+        &*module.custom_sections("policy").next().unwrap(),
+        b"strict"
+    );
+
+    let stripped = module.strip_custom_sections(|name, _| name.starts_with(".debug"))?;
+    assert_eq!(stripped.custom_sections(".debug_info").count(), 0);
+    assert_eq!(stripped.custom_sections("policy").count(), 1);
+    assert_eq!(stripped.name(), Some("name"));
+    // The original module is left untouched.
+    assert_eq!(module.custom_sections(".debug_info").count(), 1);
+
+    let tagged = stripped.with_custom_section("policy", b"lenient")?;
+    let policies = tagged.custom_sections("policy").collect::<Vec<_>>();
+    assert_eq!(policies.len(), 1);
+    assert_eq!(&*policies[0], b"lenient");
+
+    let instance = Instance::new(&tagged, &imports! {})?;
+    instance.exports.get_function("f")?.call(&[])?;
+
+    Ok(())
+}
+
 #[test]
 fn imports() -> Result<()> {
     let store = Store::default();
@@ -246,3 +298,40 @@ fn calling_host_functions_with_negative_values_works() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn module_instantiate_with_stubs() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+        (module
+          (import "env" "present" (func $present (result i32)))
+          (import "env" "missing" (func $missing))
+          (func (export "present") (result i32) (call $present))
+          (func (export "missing") (call $missing)))
+        "#,
+    )?;
+    let import_object = imports! {
+        "env" => {
+            "present" => Function::new_native(&store, || 1),
+        }
+    };
+
+    assert!(Instance::new(&module, &import_object).is_err());
+    let instance = module.instantiate_with_stubs(&import_object)?;
+    let present = instance.exports.get_native_function::<(), i32>("present")?;
+    assert_eq!(present.call()?, 1);
+    let error = instance
+        .exports
+        .get_function("missing")?
+        .call(&[])
+        .unwrap_err();
+    assert_eq!(error.message(), "called the missing import `env`.`missing`");
+
+    // Only functions are stubbed.
+    let module = Module::new(&store, r#"(module (import "env" "memory" (memory 1)))"#)?;
+    assert!(module.instantiate_with_stubs(&import_object).is_err());
+
+    Ok(())
+}
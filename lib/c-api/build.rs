@@ -50,6 +50,9 @@ const MIDDLEWARES_FEATURE_AS_C_DEFINE: &'static str = "WASMER_MIDDLEWARES_ENABLE
 #[allow(unused)]
 const EMSCRIPTEN_FEATURE_AS_C_DEFINE: &'static str = "WASMER_EMSCRIPTEN_ENABLED";
 
+#[allow(unused)]
+const ALLOCATOR_FEATURE_AS_C_DEFINE: &'static str = "WASMER_ALLOCATOR_ENABLED";
+
 macro_rules! map_feature_as_c_define {
     ($feature:expr, $c_define:ident, $accumulator:ident) => {
         #[cfg(feature = $feature)]
@@ -143,6 +146,7 @@ fn build_wasm_c_api_headers(crate_dir: &str, out_dir: &str) {
     map_feature_as_c_define!("wasi", WASI_FEATURE_AS_C_DEFINE, pre_header);
     map_feature_as_c_define!("middlewares", MIDDLEWARES_FEATURE_AS_C_DEFINE, pre_header);
     map_feature_as_c_define!("emscripten", EMSCRIPTEN_FEATURE_AS_C_DEFINE, pre_header);
+    map_feature_as_c_define!("allocator", ALLOCATOR_FEATURE_AS_C_DEFINE, pre_header);
 
     add_wasmer_version(&mut pre_header);
 
@@ -297,7 +301,8 @@ fn new_builder(language: Language, crate_dir: &str, include_guard: &str, header:
         .with_define("feature", "jit", JIT_FEATURE_AS_C_DEFINE)
         .with_define("feature", "compiler", COMPILER_FEATURE_AS_C_DEFINE)
         .with_define("feature", "wasi", WASI_FEATURE_AS_C_DEFINE)
-        .with_define("feature", "emscripten", EMSCRIPTEN_FEATURE_AS_C_DEFINE);
+        .with_define("feature", "emscripten", EMSCRIPTEN_FEATURE_AS_C_DEFINE)
+        .with_define("feature", "allocator", ALLOCATOR_FEATURE_AS_C_DEFINE);
 
     #[cfg(feature = "system-libffi")]
     let builder = builder.with_parse_expand_features(&["system-libffi"]);
@@ -469,6 +474,7 @@ fn exclude_items_from_wasm_c_api(builder: Builder) -> Builder {
         .exclude_item("wasi_env_set_instance")
         .exclude_item("wasi_env_set_memory")
         .exclude_item("wasi_env_t")
+        .exclude_item("wasi_env_write_stdin")
         .exclude_item("wasi_get_imports")
         .exclude_item("wasi_get_start_function")
         .exclude_item("wasi_get_unordered_imports")
@@ -485,8 +491,12 @@ fn exclude_items_from_wasm_c_api(builder: Builder) -> Builder {
         .exclude_item("wasmer_cpu_features_new")
         .exclude_item("wasmer_cpu_features_t")
         .exclude_item("wasmer_engine_t")
+        .exclude_item("wasmer_externref_data")
+        .exclude_item("wasmer_externref_finalizer_t")
+        .exclude_item("wasmer_externref_new")
         .exclude_item("wasmer_features_bulk_memory")
         .exclude_item("wasmer_features_delete")
+        .exclude_item("wasmer_features_exceptions")
         .exclude_item("wasmer_features_memory64")
         .exclude_item("wasmer_features_module_linking")
         .exclude_item("wasmer_features_multi_memory")
@@ -497,18 +507,39 @@ fn exclude_items_from_wasm_c_api(builder: Builder) -> Builder {
         .exclude_item("wasmer_features_t")
         .exclude_item("wasmer_features_tail_call")
         .exclude_item("wasmer_features_threads")
+        .exclude_item("wasmer_frame_function_name")
+        .exclude_item("wasmer_frame_module_name")
+        .exclude_item("wasmer_free_callback_t")
+        .exclude_item("wasmer_interrupt_as_middleware")
+        .exclude_item("wasmer_interrupt_delete")
+        .exclude_item("wasmer_interrupt_handle_delete")
+        .exclude_item("wasmer_interrupt_handle_interrupt")
+        .exclude_item("wasmer_interrupt_handle_is_interrupted")
+        .exclude_item("wasmer_interrupt_handle_reset")
+        .exclude_item("wasmer_interrupt_handle_t")
+        .exclude_item("wasmer_interrupt_new")
+        .exclude_item("wasmer_interrupt_t")
         .exclude_item("wasmer_is_compiler_available")
         .exclude_item("wasmer_is_engine_available")
         .exclude_item("wasmer_is_headless")
+        .exclude_item("wasmer_malloc_callback_t")
         .exclude_item("wasmer_metering_as_middleware")
         .exclude_item("wasmer_metering_delete")
         .exclude_item("wasmer_metering_get_remaining_points")
+        .exclude_item("wasmer_metering_handle_delete")
+        .exclude_item("wasmer_metering_handle_get_remaining_points")
+        .exclude_item("wasmer_metering_handle_new")
+        .exclude_item("wasmer_metering_handle_points_are_exhausted")
+        .exclude_item("wasmer_metering_handle_set_remaining_points")
+        .exclude_item("wasmer_metering_handle_t")
         .exclude_item("wasmer_metering_new")
         .exclude_item("wasmer_metering_points_are_exhausted")
         .exclude_item("wasmer_metering_set_remaining_points")
         .exclude_item("wasmer_metering_t")
         .exclude_item("wasmer_middleware_t")
+        .exclude_item("wasmer_module_deserialize_from_file")
         .exclude_item("wasmer_module_name")
+        .exclude_item("wasmer_module_serialize_to_file")
         .exclude_item("wasmer_module_set_name")
         .exclude_item("wasmer_named_extern_module")
         .exclude_item("wasmer_named_extern_name")
@@ -519,9 +550,13 @@ fn exclude_items_from_wasm_c_api(builder: Builder) -> Builder {
         .exclude_item("wasmer_named_extern_vec_new")
         .exclude_item("wasmer_named_extern_vec_new_empty")
         .exclude_item("wasmer_named_extern_vec_new_uninitialized")
+        .exclude_item("wasmer_realloc_callback_t")
+        .exclude_item("wasmer_set_allocator")
+        .exclude_item("wasmer_store_interrupt_handle")
         .exclude_item("wasmer_target_delete")
         .exclude_item("wasmer_target_new")
         .exclude_item("wasmer_target_t")
+        .exclude_item("wasmer_trap_message_with_trace")
         .exclude_item("wasmer_triple_delete")
         .exclude_item("wasmer_triple_new")
         .exclude_item("wasmer_triple_new_from_host")
@@ -23,7 +23,6 @@ use wasmer_engine_object_file::ObjectFile;
 ///
 /// This is a Wasmer-specific type with Wasmer-specific functions for
 /// manipulating it.
-#[cfg(feature = "compiler")]
 #[derive(Debug, Copy, Clone)]
 #[repr(C)]
 pub enum wasmer_compiler_t {
@@ -357,10 +356,21 @@ cfg_if! {
             let engine: Arc<dyn Engine + Send + Sync> = Arc::new(Native::headless().engine());
             Box::new(wasm_engine_t { inner: engine })
         }
-    }
-    // There are currently no uses of the object-file engine + compiler from the C API.
-    // So if we get here, we default to headless mode regardless of if `compiler` is enabled.
-    else if #[cfg(feature = "object-file")] {
+    } else if #[cfg(all(feature = "object-file", feature = "compiler"))] {
+        /// Creates a new object-file engine with the default compiler.
+        ///
+        /// # Example
+        ///
+        /// See [`wasm_engine_delete`].
+        ///
+        /// cbindgen:ignore
+        #[no_mangle]
+        pub extern "C" fn wasm_engine_new() -> Box<wasm_engine_t> {
+            let compiler_config: Box<dyn CompilerConfig> = get_default_compiler_config();
+            let engine: Arc<dyn Engine + Send + Sync> = Arc::new(ObjectFile::new(compiler_config).engine());
+            Box::new(wasm_engine_t { inner: engine })
+        }
+    } else if #[cfg(feature = "object-file")] {
         /// Creates a new headless object-file engine.
         ///
         /// # Example
@@ -523,10 +533,8 @@ pub extern "C" fn wasm_engine_new_with_config(
                 },
                 wasmer_engine_t::OBJECT_FILE => {
                     cfg_if! {
-                        // There are currently no uses of the object-file engine + compiler from the C API.
-                        // So we run in headless mode.
                         if #[cfg(feature = "object-file")] {
-                            let mut builder = ObjectFile::headless();
+                            let mut builder = ObjectFile::new(compiler_config);
 
                             if let Some(target) = config.target {
                                 builder = builder.target(target.inner);
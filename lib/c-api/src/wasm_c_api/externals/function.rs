@@ -1,6 +1,6 @@
 use super::super::store::wasm_store_t;
 use super::super::trap::wasm_trap_t;
-use super::super::types::{wasm_functype_t, wasm_valkind_enum};
+use super::super::types::{wasm_functype_t, wasm_ref_t, wasm_valkind_enum};
 use super::super::value::{wasm_val_inner, wasm_val_t, wasm_val_vec_t};
 use std::convert::TryInto;
 use std::ffi::c_void;
@@ -44,7 +44,7 @@ pub unsafe extern "C" fn wasm_func_new(
     let func_sig = &function_type.inner().function_type;
     let num_rets = func_sig.results().len();
     let inner_callback = move |args: &[Val]| -> Result<Vec<Val>, RuntimeError> {
-        let processed_args: wasm_val_vec_t = args
+        let mut processed_args: wasm_val_vec_t = args
             .into_iter()
             .map(TryInto::try_into)
             .collect::<Result<Vec<wasm_val_t>, _>>()
@@ -62,6 +62,13 @@ pub unsafe extern "C" fn wasm_func_new(
 
         let trap = callback(&processed_args, &mut results);
 
+        // The callback only borrows the references of the arguments.
+        processed_args
+            .into_slice_mut()
+            .into_iter()
+            .flatten()
+            .for_each(|value| value.delete_ref());
+
         if !trap.is_null() {
             let trap: Box<wasm_trap_t> = Box::from_raw(trap);
 
@@ -76,6 +83,13 @@ pub unsafe extern "C" fn wasm_func_new(
             .collect::<Result<Vec<Val>, _>>()
             .expect("Result conversion failed");
 
+        // The callback gives up the references of the results.
+        results
+            .into_slice_mut()
+            .into_iter()
+            .flatten()
+            .for_each(|value| value.delete_ref());
+
         Ok(processed_results)
     };
     let function = Function::new(&store.inner, func_sig, inner_callback);
@@ -126,7 +140,7 @@ pub unsafe extern "C" fn wasm_func_new_with_env(
     }
 
     let trampoline = move |env: &WrapperEnv, args: &[Val]| -> Result<Vec<Val>, RuntimeError> {
-        let processed_args: wasm_val_vec_t = args
+        let mut processed_args: wasm_val_vec_t = args
             .into_iter()
             .map(TryInto::try_into)
             .collect::<Result<Vec<wasm_val_t>, _>>()
@@ -144,6 +158,13 @@ pub unsafe extern "C" fn wasm_func_new_with_env(
 
         let trap = callback(env.env, &processed_args, &mut results);
 
+        // The callback only borrows the references of the arguments.
+        processed_args
+            .into_slice_mut()
+            .into_iter()
+            .flatten()
+            .for_each(|value| value.delete_ref());
+
         if !trap.is_null() {
             let trap: Box<wasm_trap_t> = Box::from_raw(trap);
 
@@ -158,6 +179,13 @@ pub unsafe extern "C" fn wasm_func_new_with_env(
             .collect::<Result<Vec<Val>, _>>()
             .expect("Result conversion failed");
 
+        // The callback gives up the references of the results.
+        results
+            .into_slice_mut()
+            .into_iter()
+            .flatten()
+            .for_each(|value| value.delete_ref());
+
         Ok(processed_results)
     };
 
@@ -247,3 +275,38 @@ pub extern "C" fn wasm_func_type(func: Option<&wasm_func_t>) -> Option<Box<wasm_
 
     Some(Box::new(wasm_functype_t::new(func.inner.ty().clone())))
 }
+
+#[no_mangle]
+pub extern "C" fn wasm_func_as_ref(func: Option<&wasm_func_t>) -> Option<Box<wasm_ref_t>> {
+    let func = func?;
+
+    Some(Box::new(wasm_ref_t {
+        inner: Val::FuncRef(func.inner.clone()),
+        instance: func.instance.clone(),
+    }))
+}
+
+#[no_mangle]
+pub extern "C" fn wasm_func_as_ref_const(func: Option<&wasm_func_t>) -> Option<Box<wasm_ref_t>> {
+    wasm_func_as_ref(func)
+}
+
+#[no_mangle]
+pub extern "C" fn wasm_ref_as_func(reference: Option<&wasm_ref_t>) -> Option<Box<wasm_func_t>> {
+    let reference = reference?;
+
+    match &reference.inner {
+        Val::FuncRef(function) => Some(Box::new(wasm_func_t {
+            inner: function.clone(),
+            instance: reference.instance.clone(),
+        })),
+        _ => None,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn wasm_ref_as_func_const(
+    reference: Option<&wasm_ref_t>,
+) -> Option<Box<wasm_func_t>> {
+    wasm_ref_as_func(reference)
+}
@@ -12,18 +12,16 @@ pub struct wasm_table_t {
 pub unsafe extern "C" fn wasm_table_new(
     store: Option<&wasm_store_t>,
     table_type: Option<&wasm_tabletype_t>,
-    init: *const wasm_ref_t,
+    init: Option<&wasm_ref_t>,
 ) -> Option<Box<wasm_table_t>> {
     let store = store?;
     let table_type = table_type?;
 
     let table_type = table_type.inner().table_type.clone();
-    let init_val = todo!("get val from init somehow");
-    /*
+    let init_val = wasm_ref_t::to_val(init);
     let table = c_try!(Table::new(&store.inner, table_type, init_val));
 
     Some(Box::new(wasm_table_t { inner: table }))
-    */
 }
 
 #[no_mangle]
@@ -47,13 +45,170 @@ pub unsafe extern "C" fn wasm_table_size(table: &wasm_table_t) -> usize {
     table.inner.size() as _
 }
 
+#[no_mangle]
+pub extern "C" fn wasm_table_get(
+    table: &wasm_table_t,
+    index: wasm_table_size_t,
+) -> Option<Box<wasm_ref_t>> {
+    wasm_ref_t::new(table.inner.get(index)?).map(Box::new)
+}
+
+#[no_mangle]
+pub extern "C" fn wasm_table_set(
+    table: &mut wasm_table_t,
+    index: wasm_table_size_t,
+    value: Option<&wasm_ref_t>,
+) -> bool {
+    // The table doesn't own the host functions: their `wasm_func_t`
+    // or `wasm_ref_t` must outlive the element.
+    c_try!(table.inner.set(index, wasm_ref_t::to_val(value)); otherwise false);
+
+    true
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn wasm_table_grow(
-    _table: &mut wasm_table_t,
-    _delta: wasm_table_size_t,
-    _init: *mut wasm_ref_t,
+    table: &mut wasm_table_t,
+    delta: wasm_table_size_t,
+    init: Option<&wasm_ref_t>,
 ) -> bool {
-    // TODO: maybe need to look at result to return `true`; also maybe report error here
-    //wasm_table.inner.grow(delta, init).is_ok()
-    todo!("Blocked on transforming ExternRef into a val type")
+    c_try!(table.inner.grow(delta, wasm_ref_t::to_val(init)); otherwise false);
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use inline_c::assert_c;
+
+    #[test]
+    fn test_table_call_indirect() {
+        (assert_c! {
+            #include "tests/wasmer_wasm.h"
+
+            wasm_trap_t* forty_two(const wasm_val_vec_t* arguments, wasm_val_vec_t* results) {
+                (void) arguments;
+                wasm_val_t result = WASM_I32_VAL(42);
+                results->data[0] = result;
+
+                return NULL;
+            }
+
+            int main() {
+                wasm_engine_t* engine = wasm_engine_new();
+                wasm_store_t* store = wasm_store_new(engine);
+
+                wasm_byte_vec_t wat;
+                wasmer_byte_vec_new_from_string(
+                    &wat,
+                    "(module\n"
+                    "  (type $t (func (result i32)))\n"
+                    "  (table (export \"table\") 2 funcref)\n"
+                    "  (func (export \"call\") (param i32) (result i32)\n"
+                    "    local.get 0\n"
+                    "    call_indirect (type $t)))"
+                );
+                wasm_byte_vec_t wasm;
+                wat2wasm(&wat, &wasm);
+
+                wasm_module_t* module = wasm_module_new(store, &wasm);
+                assert(module);
+
+                wasm_extern_vec_t imports = WASM_EMPTY_VEC;
+                wasm_instance_t* instance = wasm_instance_new(store, module, &imports, NULL);
+                assert(instance);
+
+                wasm_extern_vec_t exports;
+                wasm_instance_exports(instance, &exports);
+                assert(exports.size == 2);
+
+                wasm_table_t* table = wasm_extern_as_table(exports.data[0]);
+                const wasm_func_t* call = wasm_extern_as_func(exports.data[1]);
+                assert(table);
+                assert(call);
+
+                wasm_functype_t* forty_two_type = wasm_functype_new_0_1(wasm_valtype_new_i32());
+                wasm_func_t* forty_two_function = wasm_func_new(store, forty_two_type, forty_two);
+                wasm_ref_t* forty_two_ref = wasm_func_as_ref(forty_two_function);
+
+                // Put the host function in a new slot of the table.
+                assert(wasm_table_size(table) == 2);
+                assert(wasm_table_grow(table, 1, NULL));
+                assert(wasm_table_size(table) == 3);
+                assert(wasm_table_get(table, 2) == NULL);
+                assert(wasm_table_set(table, 2, forty_two_ref));
+                assert(!wasm_table_set(table, 3, forty_two_ref));
+
+                wasm_ref_t* element = wasm_table_get(table, 2);
+                assert(element);
+                wasm_ref_delete(element);
+
+                // And call it from WebAssembly.
+                {
+                    wasm_val_t arguments[1] = { WASM_I32_VAL(2) };
+                    wasm_val_t results[1] = { WASM_INIT_VAL };
+                    wasm_val_vec_t arguments_as_array = WASM_ARRAY_VEC(arguments);
+                    wasm_val_vec_t results_as_array = WASM_ARRAY_VEC(results);
+
+                    wasm_trap_t* trap = wasm_func_call(call, &arguments_as_array, &results_as_array);
+                    assert(trap == NULL);
+                    assert(results[0].of.i32 == 42);
+                }
+
+                // A null element traps.
+                {
+                    wasm_val_t arguments[1] = { WASM_I32_VAL(0) };
+                    wasm_val_t results[1] = { WASM_INIT_VAL };
+                    wasm_val_vec_t arguments_as_array = WASM_ARRAY_VEC(arguments);
+                    wasm_val_vec_t results_as_array = WASM_ARRAY_VEC(results);
+
+                    wasm_trap_t* trap = wasm_func_call(call, &arguments_as_array, &results_as_array);
+                    assert(trap);
+                    wasm_trap_delete(trap);
+                }
+
+                // Host tables work the same.
+                {
+                    wasm_limits_t limits = { 1, 1 };
+                    wasm_tabletype_t* table_type = wasm_tabletype_new(wasm_valtype_new(WASM_FUNCREF), &limits);
+                    wasm_table_t* host_table = wasm_table_new(store, table_type, forty_two_ref);
+                    assert(host_table);
+                    assert(wasm_table_size(host_table) == 1);
+                    assert(!wasm_table_grow(host_table, 1, NULL));
+
+                    wasm_ref_t* host_element = wasm_table_get(host_table, 0);
+                    assert(host_element);
+
+                    wasm_func_t* host_function = wasm_ref_as_func(host_element);
+                    wasm_val_t results[1] = { WASM_INIT_VAL };
+                    wasm_val_vec_t arguments_as_array = WASM_EMPTY_VEC;
+                    wasm_val_vec_t results_as_array = WASM_ARRAY_VEC(results);
+
+                    wasm_trap_t* trap = wasm_func_call(host_function, &arguments_as_array, &results_as_array);
+                    assert(trap == NULL);
+                    assert(results[0].of.i32 == 42);
+
+                    wasm_func_delete(host_function);
+                    wasm_ref_delete(host_element);
+                    wasm_table_delete(host_table);
+                    wasm_tabletype_delete(table_type);
+                }
+
+                wasm_ref_delete(forty_two_ref);
+                wasm_func_delete(forty_two_function);
+                wasm_functype_delete(forty_two_type);
+                wasm_table_delete(table);
+                wasm_extern_vec_delete(&exports);
+                wasm_instance_delete(instance);
+                wasm_module_delete(module);
+                wasm_byte_vec_delete(&wasm);
+                wasm_byte_vec_delete(&wat);
+                wasm_store_delete(store);
+                wasm_engine_delete(engine);
+
+                return 0;
+            }
+        })
+        .success();
+    }
 }
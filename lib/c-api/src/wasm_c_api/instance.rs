@@ -2,6 +2,8 @@ use super::externals::{wasm_extern_t, wasm_extern_vec_t};
 use super::module::wasm_module_t;
 use super::store::wasm_store_t;
 use super::trap::wasm_trap_t;
+#[cfg(feature = "middlewares")]
+use super::unstable::middlewares::interrupt::InterruptRegistration;
 use crate::ordered_resolver::OrderedResolver;
 use std::mem;
 use std::sync::Arc;
@@ -11,6 +13,10 @@ use wasmer::{Extern, Instance, InstantiationError};
 #[allow(non_camel_case_types)]
 pub struct wasm_instance_t {
     pub(crate) inner: Arc<Instance>,
+    /// The registration to the interrupt handle of the store, which
+    /// is removed when the instance is deleted.
+    #[cfg(feature = "middlewares")]
+    _interrupt_registration: Option<InterruptRegistration>,
 }
 
 /// Creates a new instance from a WebAssembly module and a
@@ -30,15 +36,16 @@ pub struct wasm_instance_t {
 ///
 /// # Notes
 ///
-/// The `store` argument is ignored. The store from the given module
-/// will be used.
+/// The store from the given module will be used. The `store`
+/// argument is only used to register the instance to the interrupt
+/// handle of the store, see `wasmer_store_interrupt_handle`.
 ///
 /// # Example
 ///
 /// See the module's documentation.
 #[no_mangle]
 pub unsafe extern "C" fn wasm_instance_new(
-    _store: Option<&wasm_store_t>,
+    #[allow(unused_variables)] store: Option<&wasm_store_t>,
     module: Option<&wasm_module_t>,
     imports: Option<&wasm_extern_vec_t>,
     traps: *mut *mut wasm_trap_t,
@@ -81,11 +88,18 @@ pub unsafe extern "C" fn wasm_instance_new(
         }
     };
 
-    Some(Box::new(wasm_instance_t { inner: instance }))
+    Some(Box::new(wasm_instance_t {
+        #[cfg(feature = "middlewares")]
+        _interrupt_registration: store.and_then(|store| store.interrupts.register(&instance)),
+        inner: instance,
+    }))
 }
 
 /// Deletes an instance.
 ///
+/// The instance is unregistered from the interrupt handle of its
+/// store, and is freed once its exports are deleted too.
+///
 /// # Example
 ///
 /// See `wasm_instance_new`.
@@ -1,10 +1,18 @@
 use super::engine::wasm_engine_t;
+#[cfg(feature = "middlewares")]
+use super::unstable::middlewares::interrupt::StoreInterrupts;
+#[cfg(feature = "middlewares")]
+use std::sync::Arc;
 use wasmer::Store;
 
 /// Opaque type representing a WebAssembly store.
 #[allow(non_camel_case_types)]
 pub struct wasm_store_t {
     pub(crate) inner: Store,
+    /// The interrupt handles of the instances created in this store,
+    /// see `wasmer_store_interrupt_handle`.
+    #[cfg(feature = "middlewares")]
+    pub(crate) interrupts: Arc<StoreInterrupts>,
 }
 
 /// Creates a new WebAssembly store given a specific [engine][super::engine].
@@ -19,7 +27,11 @@ pub unsafe extern "C" fn wasm_store_new(
     let engine = engine?;
     let store = Store::new(&*engine.inner);
 
-    Some(Box::new(wasm_store_t { inner: store }))
+    Some(Box::new(wasm_store_t {
+        inner: store,
+        #[cfg(feature = "middlewares")]
+        interrupts: Arc::default(),
+    }))
 }
 
 /// Deletes a WebAssembly store.
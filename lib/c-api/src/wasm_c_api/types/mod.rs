@@ -6,6 +6,7 @@ mod global;
 mod import;
 mod memory;
 mod mutability;
+mod reference;
 mod table;
 mod value;
 
@@ -17,6 +18,7 @@ pub use global::*;
 pub use import::*;
 pub use memory::*;
 pub use mutability::*;
+pub use reference::*;
 pub use table::*;
 pub use value::*;
 
@@ -88,9 +90,5 @@ impl From<String> for owned_wasm_name_t {
     }
 }
 
-// opaque type over `ExternRef`?
-#[allow(non_camel_case_types)]
-pub struct wasm_ref_t;
-
 #[allow(non_camel_case_types)]
 pub type wasm_message_t = wasm_byte_vec_t;
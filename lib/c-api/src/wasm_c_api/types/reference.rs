@@ -0,0 +1,49 @@
+use std::sync::Arc;
+use wasmer::{ExternRef, Instance, Val};
+
+/// A reference to a WebAssembly function, or to host data with an
+/// `externref`. `NULL` is the null reference.
+///
+/// See `wasm_func_as_ref`, `wasm_ref_as_func` and
+/// `wasmer_externref_new`.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone)]
+pub struct wasm_ref_t {
+    /// A `Val::FuncRef`, or a non-null `Val::ExternRef`.
+    pub(crate) inner: Val,
+    // this is how we ensure the instance stays alive
+    pub(crate) instance: Option<Arc<Instance>>,
+}
+
+impl wasm_ref_t {
+    /// Creates a reference from a value, or `None` for the null
+    /// reference and the values that aren't references.
+    pub(crate) fn new(value: Val) -> Option<Self> {
+        match value {
+            Val::FuncRef(_)
+            | Val::ExternRef(ExternRef::Ref(_))
+            | Val::ExternRef(ExternRef::Other(_)) => Some(Self {
+                inner: value,
+                instance: None,
+            }),
+            _ => None,
+        }
+    }
+
+    /// The value of a reference, or of the null reference.
+    pub(crate) fn to_val(reference: Option<&Self>) -> Val {
+        match reference {
+            Some(reference) => reference.inner.clone(),
+            None => Val::ExternRef(ExternRef::Null),
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn wasm_ref_delete(_reference: Option<Box<wasm_ref_t>>) {}
+
+#[no_mangle]
+pub extern "C" fn wasm_ref_copy(reference: &wasm_ref_t) -> Box<wasm_ref_t> {
+    // do shallow copy
+    Box::new(reference.clone())
+}
@@ -0,0 +1,264 @@
+//! Unstable non-standard Wasmer-specific API to allocate the memory
+//! of the library with the embedder's allocator, e.g. to use memory
+//! pools or to track allocations.
+//!
+//! The allocator covers every allocation of the library, including
+//! the byte vectors, the names and the traps returned to the
+//! embedder, but not the memories of the instances, which are mapped
+//! directly.
+//!
+//! This API is only available with the `allocator` feature, which is
+//! disabled by default, as it replaces the global allocator of the
+//! library: every allocation carries a small header, even when no
+//! callbacks are set.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use inline_c::assert_c;
+//! # fn main() {
+//! #    (assert_c! {
+//! # #include "tests/wasmer_wasm.h"
+//! # #include <stdlib.h>
+//! #
+//! static size_t allocated = 0;
+//!
+//! void* counting_malloc(size_t size, size_t align) {
+//!     allocated += size;
+//!     return aligned_alloc(align, (size + align - 1) / align * align);
+//! }
+//!
+//! void counting_free(void* ptr, size_t size, size_t align) {
+//!     (void) align;
+//!     allocated -= size;
+//!     free(ptr);
+//! }
+//!
+//! int main() {
+//!     // Set the allocator, preferably before calling any other
+//!     // function.
+//!     assert(wasmer_set_allocator(counting_malloc, counting_free, NULL));
+//!
+//!     wasm_engine_t* engine = wasm_engine_new();
+//!     wasm_store_t* store = wasm_store_new(engine);
+//!
+//!     assert(allocated > 0);
+//!
+//!     // The allocator can't be changed once it has been set.
+//!     assert(!wasmer_set_allocator(counting_malloc, counting_free, NULL));
+//!
+//!     wasm_store_delete(store);
+//!     wasm_engine_delete(engine);
+//!
+//!     return 0;
+//! }
+//! #    })
+//! #    .success();
+//! # }
+//! ```
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::UnsafeCell;
+use std::os::raw::c_void;
+use std::ptr;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Allocates `size` bytes aligned to `align`, a power of two, or
+/// returns `NULL` on failure.
+#[allow(non_camel_case_types)]
+pub type wasmer_malloc_callback_t = unsafe extern "C" fn(size: usize, align: usize) -> *mut c_void;
+
+/// Frees the memory at `ptr`, allocated by the
+/// [`wasmer_malloc_callback_t`] or [`wasmer_realloc_callback_t`]
+/// callbacks with the same `size` and `align`.
+#[allow(non_camel_case_types)]
+pub type wasmer_free_callback_t = unsafe extern "C" fn(ptr: *mut c_void, size: usize, align: usize);
+
+/// Resizes the memory at `ptr` from `old_size` to `new_size` bytes,
+/// keeping it aligned to `align`, or returns `NULL` on failure.
+#[allow(non_camel_case_types)]
+pub type wasmer_realloc_callback_t = Option<
+    unsafe extern "C" fn(
+        ptr: *mut c_void,
+        old_size: usize,
+        align: usize,
+        new_size: usize,
+    ) -> *mut c_void,
+>;
+
+/// The callbacks aren't set, the system allocator is used.
+const UNSET: u8 = 0;
+/// The callbacks are being set.
+const SETTING: u8 = 1;
+/// The callbacks are set, and used for the new allocations.
+const SET: u8 = 2;
+
+/// The allocation was made by the system allocator.
+const SYSTEM_TAG: u8 = 0;
+/// The allocation was made by the callbacks.
+const CALLBACKS_TAG: u8 = 1;
+
+/// The allocator of the library, using the callbacks set with
+/// [`wasmer_set_allocator`], or the system allocator.
+///
+/// Each allocation is preceded by a header, whose last byte tells
+/// which allocator made it, so that the memory allocated before the
+/// callbacks were set is still freed by the system allocator.
+struct Allocator {
+    state: AtomicU8,
+    callbacks: UnsafeCell<Option<Callbacks>>,
+}
+
+#[derive(Clone, Copy)]
+struct Callbacks {
+    malloc: wasmer_malloc_callback_t,
+    free: wasmer_free_callback_t,
+    realloc: wasmer_realloc_callback_t,
+}
+
+// The callbacks are only written once, before `state` is set to
+// `SET`, and only read after.
+unsafe impl Sync for Allocator {}
+
+/// The size of the header before an allocation aligned to `align`,
+/// keeping it aligned.
+fn header_size(align: usize) -> usize {
+    align.max(16)
+}
+
+impl Allocator {
+    /// The callbacks, once set.
+    fn callbacks(&self) -> Option<Callbacks> {
+        match self.state.load(Ordering::Acquire) {
+            SET => unsafe { *self.callbacks.get() },
+            _ => None,
+        }
+    }
+
+    /// The callbacks that made an allocation tagged with `tag`.
+    fn callbacks_for(&self, tag: u8) -> Option<Callbacks> {
+        match tag {
+            SYSTEM_TAG => None,
+            _ => self.callbacks(),
+        }
+    }
+}
+
+unsafe impl GlobalAlloc for Allocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let header_size = header_size(layout.align());
+        let size = layout.size() + header_size;
+        let (base, tag) = match self.callbacks() {
+            Some(callbacks) => (
+                (callbacks.malloc)(size, layout.align()) as *mut u8,
+                CALLBACKS_TAG,
+            ),
+            None => (
+                System.alloc(Layout::from_size_align_unchecked(size, layout.align())),
+                SYSTEM_TAG,
+            ),
+        };
+        if base.is_null() {
+            return base;
+        }
+        let ptr = base.add(header_size);
+        *ptr.sub(1) = tag;
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let header_size = header_size(layout.align());
+        let size = layout.size() + header_size;
+        let base = ptr.sub(header_size);
+        match self.callbacks_for(*ptr.sub(1)) {
+            Some(callbacks) => (callbacks.free)(base as *mut c_void, size, layout.align()),
+            None => System.dealloc(
+                base,
+                Layout::from_size_align_unchecked(size, layout.align()),
+            ),
+        }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let header_size = header_size(layout.align());
+        let size = layout.size() + header_size;
+        let base = ptr.sub(header_size);
+        // The header, and so the tag, is moved with the data.
+        let new_base = match self.callbacks_for(*ptr.sub(1)) {
+            Some(Callbacks {
+                realloc: Some(realloc),
+                ..
+            }) => realloc(
+                base as *mut c_void,
+                size,
+                layout.align(),
+                new_size + header_size,
+            ) as *mut u8,
+            Some(callbacks) => {
+                let new_base =
+                    (callbacks.malloc)(new_size + header_size, layout.align()) as *mut u8;
+                if !new_base.is_null() {
+                    ptr::copy_nonoverlapping(base, new_base, size.min(new_size + header_size));
+                    (callbacks.free)(base as *mut c_void, size, layout.align());
+                }
+                new_base
+            }
+            None => System.realloc(
+                base,
+                Layout::from_size_align_unchecked(size, layout.align()),
+                new_size + header_size,
+            ),
+        };
+        if new_base.is_null() {
+            return new_base;
+        }
+        new_base.add(header_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Allocator = Allocator {
+    state: AtomicU8::new(UNSET),
+    callbacks: UnsafeCell::new(None),
+};
+
+/// Unstable non-standard Wasmer-specific API to allocate the memory
+/// of the library with the given callbacks. `realloc_callback` can
+/// be `NULL`, in which case memory is resized with `malloc_callback`
+/// and `free_callback`.
+///
+/// The callbacks are used for the memory allocated from now on, the
+/// memory allocated before is still freed by the system allocator.
+/// They receive sizes including a small header used by the library.
+///
+/// The allocator can only be set once: the function returns `true`
+/// if it has been set, `false` if it already was.
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_set_allocator(
+    malloc_callback: wasmer_malloc_callback_t,
+    free_callback: wasmer_free_callback_t,
+    realloc_callback: wasmer_realloc_callback_t,
+) -> bool {
+    if ALLOCATOR
+        .state
+        .compare_exchange(UNSET, SETTING, Ordering::AcqRel, Ordering::Acquire)
+        .is_err()
+    {
+        return false;
+    }
+
+    unsafe {
+        *ALLOCATOR.callbacks.get() = Some(Callbacks {
+            malloc: malloc_callback,
+            free: free_callback,
+            realloc: realloc_callback,
+        });
+    }
+    ALLOCATOR.state.store(SET, Ordering::Release);
+
+    true
+}
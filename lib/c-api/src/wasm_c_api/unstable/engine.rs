@@ -8,6 +8,11 @@ use super::target_lexicon::wasmer_target_t;
 /// Unstable non-standard Wasmer-specific API to update the
 /// configuration to specify a particular target for the engine.
 ///
+/// The target can be another machine than the host, to cross-compile
+/// modules with the native or object-file engines. Such modules can't
+/// be instantiated, but they can be serialized with
+/// `wasm_module_serialize` and deserialized on the target machine.
+///
 /// # Example
 ///
 /// ```rust
@@ -64,7 +69,7 @@ pub extern "C" fn wasm_config_set_target(config: &mut wasm_config_t, target: Box
 ///     // Create the configuration.
 ///     wasm_config_t* config = wasm_config_new();
 ///
-///     // Set the target.
+///     // Set the features.
 ///     {
 ///         wasmer_features_t* features = wasmer_features_new();
 ///         wasmer_features_simd(features, true);
@@ -131,6 +136,96 @@ mod tests {
     use inline_c::assert_c;
     use std::env::{remove_var, set_var};
 
+    #[test]
+    fn test_wasm_config_set_target_cross_compile() {
+        // Another operating system, since the compilers may only
+        // support the host architecture.
+        set_var(
+            "TRIPLE",
+            if cfg!(target_os = "linux") {
+                format!("{}-apple-darwin", std::env::consts::ARCH)
+            } else {
+                format!("{}-unknown-linux-gnu", std::env::consts::ARCH)
+            },
+        );
+
+        (assert_c! {
+            #include "tests/wasmer_wasm.h"
+            #include <stdlib.h>
+
+            int main() {
+                if (!wasmer_is_engine_available(OBJECT_FILE) || wasmer_is_headless()) {
+                    return 0;
+                }
+
+                wasm_config_t* config = wasm_config_new();
+                wasm_config_set_engine(config, OBJECT_FILE);
+
+                wasmer_triple_t* triple;
+
+                {
+                    wasm_name_t triple_name;
+                    wasm_name_new_from_string(&triple_name, getenv("TRIPLE"));
+
+                    triple = wasmer_triple_new(&triple_name);
+
+                    wasm_name_delete(&triple_name);
+                }
+
+                assert(triple);
+
+                wasmer_cpu_features_t* cpu_features = wasmer_cpu_features_new();
+
+                {
+                    wasm_name_t cpu_feature_name;
+                    wasm_name_new_from_string(&cpu_feature_name, "sse2");
+
+                    wasmer_cpu_features_add(cpu_features, &cpu_feature_name);
+
+                    wasm_name_delete(&cpu_feature_name);
+                }
+
+                wasm_config_set_target(config, wasmer_target_new(triple, cpu_features));
+
+                wasm_engine_t* engine = wasm_engine_new_with_config(config);
+                assert(engine);
+
+                wasm_store_t* store = wasm_store_new(engine);
+
+                wasm_byte_vec_t wat;
+                wasmer_byte_vec_new_from_string(
+                    &wat,
+                    "(module\n"
+                    "  (func (export \"add\") (param i32 i32) (result i32)\n"
+                    "    local.get 0\n"
+                    "    local.get 1\n"
+                    "    i32.add))"
+                );
+                wasm_byte_vec_t wasm;
+                wat2wasm(&wat, &wasm);
+
+                wasm_module_t* module = wasm_module_new(store, &wasm);
+                assert(module);
+
+                wasm_byte_vec_t serialized;
+                wasm_module_serialize(module, &serialized);
+                assert(serialized.size > 0);
+
+                wasm_byte_vec_delete(&serialized);
+                wasm_module_delete(module);
+                wasm_byte_vec_delete(&wasm);
+                wasm_byte_vec_delete(&wat);
+                wasm_store_delete(store);
+                wasm_engine_delete(engine);
+
+                return 0;
+            }
+        })
+        .success();
+
+        remove_var("TRIPLE");
+    }
+
     #[test]
     fn test_wasmer_is_headless() {
         set_var(
@@ -0,0 +1,118 @@
+//! Unstable non-standard Wasmer-specific API to create external
+//! references (`externref`) to host data, and to pass them as
+//! `wasm_val_t` of kind `WASM_ANYREF`.
+//!
+//! An external reference owns a pointer to host data, with an
+//! optional finalizer that is called once the last reference to it,
+//! copies included, is deleted.
+//!
+//! Note that the compilers don't support the `externref` values in
+//! the WebAssembly functions yet, so they can't be passed to the
+//! modules at this time.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use inline_c::assert_c;
+//! # fn main() {
+//! #    (assert_c! {
+//! # #include "tests/wasmer_wasm.h"
+//! #
+//! static int finalized = 0;
+//!
+//! void finalize(void* data) {
+//!     assert(*(int*) data == 42);
+//!     finalized += 1;
+//! }
+//!
+//! int main() {
+//!     // Create an external reference to some host data.
+//!     int answer = 42;
+//!     wasm_ref_t* reference = wasmer_externref_new(&answer, finalize);
+//!     assert(wasmer_externref_data(reference) == &answer);
+//!
+//!     // Pass it as a value, which owns the reference.
+//!     wasm_val_t value = WASM_REF_VAL(reference);
+//!     wasm_val_t copy;
+//!     wasm_val_copy(&copy, &value);
+//!
+//!     assert(copy.kind == WASM_ANYREF);
+//!     assert(copy.of.ref != reference);
+//!     assert(wasmer_externref_data(copy.of.ref) == &answer);
+//!
+//!     // The data is finalized once the last reference is deleted.
+//!     wasm_val_delete(&value);
+//!     assert(finalized == 0);
+//!
+//!     wasm_val_delete(&copy);
+//!     assert(finalized == 1);
+//!
+//!     return 0;
+//! }
+//! #    })
+//! #    .success();
+//! # }
+//! ```
+
+use super::super::types::wasm_ref_t;
+use std::os::raw::c_void;
+use std::ptr;
+use wasmer::{ExternRef, Val};
+
+/// Finalizes the host data of an external reference, once the last
+/// reference to it has been deleted.
+#[allow(non_camel_case_types)]
+pub type wasmer_externref_finalizer_t = Option<unsafe extern "C" fn(data: *mut c_void)>;
+
+/// The host data of an external reference.
+struct HostData(*mut c_void);
+
+/// Unstable non-standard Wasmer-specific API to create an external
+/// reference to the host data `data`.
+///
+/// `finalizer` can be `NULL`, otherwise it's called with `data` once
+/// the last reference to it is deleted.
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_externref_new(
+    data: *mut c_void,
+    finalizer: wasmer_externref_finalizer_t,
+) -> Box<wasm_ref_t> {
+    let externref = match finalizer {
+        Some(finalizer) => {
+            ExternRef::new_with_finalizer(Box::new(HostData(data)), move |_| unsafe {
+                finalizer(data)
+            })
+        }
+        None => ExternRef::new(Box::new(HostData(data))),
+    };
+
+    Box::new(wasm_ref_t {
+        inner: Val::ExternRef(externref),
+        instance: None,
+    })
+}
+
+/// Unstable non-standard Wasmer-specific API to get the host data of
+/// an external reference, or `NULL` if `reference` isn't an external
+/// reference created by [`wasmer_externref_new`].
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_externref_data(reference: Option<&wasm_ref_t>) -> *mut c_void {
+    match reference {
+        Some(wasm_ref_t {
+            inner: Val::ExternRef(externref @ ExternRef::Other(_)),
+            ..
+        }) => externref
+            .data()
+            .downcast_ref::<HostData>()
+            .map_or(ptr::null_mut(), |data| data.0),
+        _ => ptr::null_mut(),
+    }
+}
@@ -362,3 +362,97 @@ pub extern "C" fn wasmer_features_memory64(
 
     true
 }
+
+/// Configures whether the WebAssembly exception handling proposal
+/// will be enabled.
+///
+/// The [WebAssembly exception handling proposal][proposal] is not
+/// currently fully standardized and is undergoing development.
+/// Support for this feature can be enabled through this method for
+/// appropriate WebAssembly modules.
+///
+/// This feature gates items such as the `try`, `catch` and `throw`
+/// instructions.
+///
+/// This is `false` by default.
+///
+/// [proposal]: https://github.com/WebAssembly/exception-handling
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_features_exceptions(
+    features: Option<&mut wasmer_features_t>,
+    enable: bool,
+) -> bool {
+    let features = match features {
+        Some(features) => features,
+        _ => return false,
+    };
+
+    features.inner.exceptions = enable;
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use inline_c::assert_c;
+
+    #[test]
+    fn test_wasm_config_set_features() {
+        (assert_c! {
+            #include "tests/wasmer_wasm.h"
+
+            int main() {
+                wasm_byte_vec_t wat;
+                wasmer_byte_vec_new_from_string(
+                    &wat,
+                    "(module\n"
+                    "  (func (export \"zero\") (result v128)\n"
+                    "    v128.const i64x2 0 0))"
+                );
+                wasm_byte_vec_t wasm;
+                wat2wasm(&wat, &wasm);
+
+                // SIMD is disabled by default.
+                {
+                    wasm_engine_t* engine = wasm_engine_new();
+                    wasm_store_t* store = wasm_store_new(engine);
+
+                    assert(!wasm_module_validate(store, &wasm));
+
+                    wasm_store_delete(store);
+                    wasm_engine_delete(engine);
+                }
+
+                // And enabled through the configuration.
+                {
+                    wasm_config_t* config = wasm_config_new();
+                    wasmer_features_t* features = wasmer_features_new();
+                    assert(wasmer_features_simd(features, true));
+                    wasm_config_set_features(config, features);
+
+                    wasm_engine_t* engine = wasm_engine_new_with_config(config);
+                    wasm_store_t* store = wasm_store_new(engine);
+
+                    assert(wasm_module_validate(store, &wasm));
+
+                    wasm_module_t* module = wasm_module_new(store, &wasm);
+                    assert(module);
+
+                    wasm_module_delete(module);
+                    wasm_store_delete(store);
+                    wasm_engine_delete(engine);
+                }
+
+                wasm_byte_vec_delete(&wasm);
+                wasm_byte_vec_delete(&wat);
+
+                return 0;
+            }
+        })
+        .success();
+    }
+}
@@ -0,0 +1,378 @@
+//! Unstable non-standard Wasmer-specific API that contains everything
+//! to create the middleware interrupt API.
+//!
+//! The interrupt middleware is used to stop the running instances of
+//! a store from another thread, for example to implement a timeout.
+//! The instances check an interrupt flag on entry of every function
+//! and on every loop iteration, and trap if it is set.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use inline_c::assert_c;
+//! # fn main() {
+//! #    (assert_c! {
+//! # #include "tests/wasmer_wasm.h"
+//! #
+//! int main() {
+//!     // Create a new interrupt middleware.
+//!     wasmer_interrupt_t* interrupt = wasmer_interrupt_new();
+//!
+//!     // Consume `interrupt` to produce a generic `wasmer_middleware_t` value.
+//!     wasmer_middleware_t* middleware = wasmer_interrupt_as_middleware(interrupt);
+//!
+//!     // Create a new configuration, and push the middleware in it.
+//!     wasm_config_t* config = wasm_config_new();
+//!     wasm_config_push_middleware(config, middleware);
+//!
+//!     // Create the engine and the store based on the configuration.
+//!     wasm_engine_t* engine = wasm_engine_new_with_config(config);
+//!     wasm_store_t* store = wasm_store_new(engine);
+//!
+//!     // Get the interrupt handle of the store. It can be used from
+//!     // any thread.
+//!     wasmer_interrupt_handle_t* handle = wasmer_store_interrupt_handle(store);
+//!     assert(handle);
+//!
+//!     // Create the new WebAssembly module.
+//!     wasm_byte_vec_t wat;
+//!     wasmer_byte_vec_new_from_string(
+//!         &wat,
+//!         "(module\n"
+//!         "  (func $add_one_f (param $value i32) (result i32)\n"
+//!         "    local.get $value\n"
+//!         "    i32.const 1\n"
+//!         "    i32.add)\n"
+//!         "  (export \"add_one\" (func $add_one_f)))"
+//!     );
+//!     wasm_byte_vec_t wasm;
+//!     wat2wasm(&wat, &wasm);
+//!
+//!     wasm_module_t* module = wasm_module_new(store, &wasm);
+//!     assert(module);
+//!
+//!     // Instantiate the module.
+//!     wasm_extern_vec_t imports = WASM_EMPTY_VEC;
+//!     wasm_trap_t* traps = NULL;
+//!     wasm_instance_t* instance = wasm_instance_new(store, module, &imports, &traps);
+//!     assert(instance);
+//!
+//!     wasm_extern_vec_t exports;
+//!     wasm_instance_exports(instance, &exports);
+//!     assert(exports.size >= 1);
+//!
+//!     const wasm_func_t* add_one = wasm_extern_as_func(exports.data[0]);
+//!     assert(add_one);
+//!
+//!     wasm_val_t arguments[1] = { WASM_I32_VAL(41) };
+//!     wasm_val_t results[1] = { WASM_INIT_VAL };
+//!
+//!     wasm_val_vec_t arguments_as_array = WASM_ARRAY_VEC(arguments);
+//!     wasm_val_vec_t results_as_array = WASM_ARRAY_VEC(results);
+//!
+//!     // The instance runs as usual.
+//!     {
+//!         wasm_trap_t* trap = wasm_func_call(add_one, &arguments_as_array, &results_as_array);
+//!         assert(trap == NULL);
+//!         assert(results[0].of.i32 == 42);
+//!         assert(!wasmer_interrupt_handle_is_interrupted(handle));
+//!     }
+//!
+//!     // Once interrupted, the instance traps.
+//!     {
+//!         wasmer_interrupt_handle_interrupt(handle);
+//!         assert(wasmer_interrupt_handle_is_interrupted(handle));
+//!
+//!         wasm_trap_t* trap = wasm_func_call(add_one, &arguments_as_array, &results_as_array);
+//!         assert(trap != NULL);
+//!         wasm_trap_delete(trap);
+//!     }
+//!
+//!     // Once reset, the instance runs again.
+//!     {
+//!         wasmer_interrupt_handle_reset(handle);
+//!         assert(!wasmer_interrupt_handle_is_interrupted(handle));
+//!
+//!         wasm_trap_t* trap = wasm_func_call(add_one, &arguments_as_array, &results_as_array);
+//!         assert(trap == NULL);
+//!         assert(results[0].of.i32 == 42);
+//!     }
+//!
+//!     wasm_extern_vec_delete(&exports);
+//!     wasm_instance_delete(instance);
+//!     wasm_module_delete(module);
+//!     wasmer_interrupt_handle_delete(handle);
+//!     wasm_store_delete(store);
+//!     wasm_engine_delete(engine);
+//!
+//!     return 0;
+//! }
+//! #    })
+//! #    .success();
+//! # }
+//! ```
+
+use super::super::super::store::wasm_store_t;
+use super::wasmer_middleware_t;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use wasmer::Instance;
+use wasmer_middlewares::{Interrupt, InterruptHandle};
+
+/// Opaque type representing an interrupt middleware.
+///
+/// To transform this specific middleware into a generic one, please
+/// see [`wasmer_interrupt_as_middleware`].
+///
+/// # Example
+///
+/// See module's documentation.
+#[allow(non_camel_case_types)]
+pub struct wasmer_interrupt_t {
+    pub(crate) inner: Arc<Interrupt>,
+}
+
+/// The interrupt handles of the live instances of a store.
+#[derive(Debug, Default)]
+pub(crate) struct StoreInterrupts {
+    handles: Mutex<InterruptHandles>,
+}
+
+#[derive(Debug, Default)]
+struct InterruptHandles {
+    next_key: usize,
+    by_key: HashMap<usize, InterruptHandle>,
+}
+
+impl StoreInterrupts {
+    /// Registers an instance, if its module was compiled with the
+    /// interrupt middleware.
+    ///
+    /// The handle keeps the instance alive, so it's unregistered when
+    /// the returned registration is dropped, with the instance.
+    pub(crate) fn register(self: &Arc<Self>, instance: &Instance) -> Option<InterruptRegistration> {
+        let handle = InterruptHandle::new(instance).ok()?;
+        let mut handles = self.handles.lock().unwrap();
+        let key = handles.next_key;
+        handles.next_key += 1;
+        handles.by_key.insert(key, handle);
+
+        Some(InterruptRegistration {
+            interrupts: Arc::clone(self),
+            key,
+        })
+    }
+
+    fn for_each(&self, f: impl FnMut(&InterruptHandle)) {
+        self.handles.lock().unwrap().by_key.values().for_each(f)
+    }
+
+    fn any(&self, f: impl FnMut(&InterruptHandle) -> bool) -> bool {
+        self.handles.lock().unwrap().by_key.values().any(f)
+    }
+}
+
+/// The registration of an instance to the interrupt handle of its
+/// store, removed on drop.
+#[derive(Debug)]
+pub(crate) struct InterruptRegistration {
+    interrupts: Arc<StoreInterrupts>,
+    key: usize,
+}
+
+impl Drop for InterruptRegistration {
+    fn drop(&mut self) {
+        self.interrupts
+            .handles
+            .lock()
+            .unwrap()
+            .by_key
+            .remove(&self.key);
+    }
+}
+
+/// Opaque type representing the interrupt handle of a store.
+///
+/// It's created with [`wasmer_store_interrupt_handle`], and can be
+/// used from any thread, even while the store is in use.
+///
+/// # Example
+///
+/// See module's documentation.
+#[allow(non_camel_case_types)]
+pub struct wasmer_interrupt_handle_t {
+    pub(crate) inner: Arc<StoreInterrupts>,
+}
+
+/// Creates a new interrupt middleware.
+///
+/// # Example
+///
+/// See module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_interrupt_new() -> Box<wasmer_interrupt_t> {
+    Box::new(wasmer_interrupt_t {
+        inner: Arc::new(Interrupt::new()),
+    })
+}
+
+/// Deletes a [`wasmer_interrupt_t`].
+///
+/// # Example
+///
+/// See module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_interrupt_delete(_interrupt: Option<Box<wasmer_interrupt_t>>) {}
+
+/// Transforms a [`wasmer_interrupt_t`] into a generic
+/// [`wasmer_middleware_t`], to then be pushed in the configuration with
+/// [`wasm_config_push_middleware`][super::wasm_config_push_middleware].
+///
+/// This function takes ownership of `interrupt`.
+///
+/// # Example
+///
+/// See module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_interrupt_as_middleware(
+    interrupt: Option<Box<wasmer_interrupt_t>>,
+) -> Option<Box<wasmer_middleware_t>> {
+    let interrupt = interrupt?;
+
+    Some(Box::new(wasmer_middleware_t {
+        inner: interrupt.inner,
+    }))
+}
+
+/// Gets the interrupt handle of a store.
+///
+/// The handle interrupts the instances created in the store with
+/// `wasm_instance_new`, if their module has been compiled with the
+/// interrupt middleware. The instances created after the handle are
+/// covered too, but aren't affected by the previous interruptions. An
+/// instance is registered once its `start` function has returned, so
+/// the `start` function itself can't be interrupted. An instance is
+/// unregistered by `wasm_instance_delete`.
+///
+/// # Example
+///
+/// See module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_store_interrupt_handle(
+    store: Option<&wasm_store_t>,
+) -> Option<Box<wasmer_interrupt_handle_t>> {
+    let store = store?;
+
+    Some(Box::new(wasmer_interrupt_handle_t {
+        inner: store.interrupts.clone(),
+    }))
+}
+
+/// Deletes a [`wasmer_interrupt_handle_t`].
+///
+/// # Example
+///
+/// See module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_interrupt_handle_delete(_handle: Option<Box<wasmer_interrupt_handle_t>>) {}
+
+/// Interrupts the instances of the store: they trap the next time
+/// they enter a function or loop. Host functions aren't interrupted,
+/// the instances stop once they return.
+///
+/// This function can be called from any thread.
+///
+/// # Example
+///
+/// See module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_interrupt_handle_interrupt(handle: &wasmer_interrupt_handle_t) {
+    handle.inner.for_each(InterruptHandle::interrupt);
+}
+
+/// Returns true if an instance of the store has been interrupted and
+/// not reset since, false otherwise.
+///
+/// This function can be called from any thread.
+///
+/// # Example
+///
+/// See module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_interrupt_handle_is_interrupted(
+    handle: &wasmer_interrupt_handle_t,
+) -> bool {
+    handle.inner.any(InterruptHandle::is_interrupted)
+}
+
+/// Clears the interrupt flag of the instances of the store, so that
+/// they can run again.
+///
+/// This function can be called from any thread.
+///
+/// # Example
+///
+/// See module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_interrupt_handle_reset(handle: &wasmer_interrupt_handle_t) {
+    handle.inner.for_each(InterruptHandle::reset);
+}
+
+#[cfg(test)]
+mod tests {
+    use inline_c::assert_c;
+
+    #[test]
+    fn test_deleted_instances_are_unregistered() {
+        (assert_c! {
+            #include "tests/wasmer_wasm.h"
+
+            int main() {
+                wasmer_interrupt_t* interrupt = wasmer_interrupt_new();
+                wasmer_middleware_t* middleware = wasmer_interrupt_as_middleware(interrupt);
+
+                wasm_config_t* config = wasm_config_new();
+                wasm_config_push_middleware(config, middleware);
+
+                wasm_engine_t* engine = wasm_engine_new_with_config(config);
+                wasm_store_t* store = wasm_store_new(engine);
+                wasmer_interrupt_handle_t* handle = wasmer_store_interrupt_handle(store);
+
+                wasm_byte_vec_t wat;
+                wasmer_byte_vec_new_from_string(&wat, "(module (func (export \"f\")))");
+                wasm_byte_vec_t wasm;
+                wat2wasm(&wat, &wasm);
+
+                wasm_module_t* module = wasm_module_new(store, &wasm);
+                assert(module);
+
+                wasm_extern_vec_t imports = WASM_EMPTY_VEC;
+                wasm_trap_t* traps = NULL;
+
+                wasm_instance_t* first = wasm_instance_new(store, module, &imports, &traps);
+                wasm_instance_t* second = wasm_instance_new(store, module, &imports, &traps);
+                assert(first && second);
+
+                // Both instances are interrupted.
+                wasmer_interrupt_handle_interrupt(handle);
+                assert(wasmer_interrupt_handle_is_interrupted(handle));
+
+                // Once deleted, an instance isn't held by the handle anymore.
+                wasm_instance_delete(first);
+                assert(wasmer_interrupt_handle_is_interrupted(handle));
+                wasm_instance_delete(second);
+                assert(!wasmer_interrupt_handle_is_interrupted(handle));
+
+                wasm_module_delete(module);
+                wasm_byte_vec_delete(&wasm);
+                wasm_byte_vec_delete(&wat);
+                wasmer_interrupt_handle_delete(handle);
+                wasm_store_delete(store);
+                wasm_engine_delete(engine);
+
+                return 0;
+            }
+        })
+        .success();
+    }
+}
@@ -138,7 +138,7 @@ use std::sync::Arc;
 use wasmer::wasmparser::Operator;
 use wasmer_middlewares::{
     metering::{get_remaining_points, set_remaining_points, MeteringPoints},
-    Metering,
+    Metering, MeteringHandle,
 };
 
 /// Opaque type representing a metering middleware.
@@ -154,6 +154,16 @@ pub struct wasmer_metering_t {
     pub(crate) inner: Arc<Metering<Box<dyn Fn(&Operator) -> u64 + Send + Sync>>>,
 }
 
+/// Opaque type representing the metering handle of an instance.
+///
+/// Unlike the instance, it can be used from any thread, for example
+/// to refill the points of a running instance. See
+/// [`wasmer_metering_handle_new`].
+#[allow(non_camel_case_types)]
+pub struct wasmer_metering_handle_t {
+    pub(crate) inner: MeteringHandle,
+}
+
 /// Function type to represent a user-defined cost function
 /// implemented in C.
 ///
@@ -298,6 +308,141 @@ pub extern "C" fn wasmer_metering_set_remaining_points(instance: &wasm_instance_
     set_remaining_points(&instance.inner, new_limit);
 }
 
+/// Creates the metering handle of an instance, or returns `NULL` if
+/// its module hasn't been compiled with the metering middleware.
+///
+/// The handle can be used from any thread, and outlives the instance.
+///
+/// # Example
+///
+/// ```rust
+/// # use inline_c::assert_c;
+/// # fn main() {
+/// #    (assert_c! {
+/// # #include "tests/wasmer_wasm.h"
+/// #
+/// // Define a dummy “cost function”.
+/// uint64_t cost_function(wasmer_parser_operator_t wasm_operator) {
+///     switch(wasm_operator) {
+///         default:
+///             return 0;
+///     }
+/// }
+///
+/// int main() {
+///     // Set the initial amount of points to 7.
+///     wasmer_metering_t* metering = wasmer_metering_new(7, cost_function);
+///     wasmer_middleware_t* middleware = wasmer_metering_as_middleware(metering);
+///
+///     wasm_config_t* config = wasm_config_new();
+///     wasm_config_push_middleware(config, middleware);
+///
+///     wasm_engine_t* engine = wasm_engine_new_with_config(config);
+///     wasm_store_t* store = wasm_store_new(engine);
+///
+///     // Create the module and instantiate it.
+///     wasm_byte_vec_t wat;
+///     wasmer_byte_vec_new_from_string(&wat, "(module)");
+///     wasm_byte_vec_t wasm;
+///     wat2wasm(&wat, &wasm);
+///
+///     wasm_module_t* module = wasm_module_new(store, &wasm);
+///     assert(module);
+///
+///     wasm_extern_vec_t imports = WASM_EMPTY_VEC;
+///     wasm_trap_t* traps = NULL;
+///     wasm_instance_t* instance = wasm_instance_new(store, module, &imports, &traps);
+///     assert(instance);
+///
+///     // Get the metering handle of the instance.
+///     wasmer_metering_handle_t* handle = wasmer_metering_handle_new(instance);
+///     assert(handle);
+///
+///     // Read and set the number of points through the handle.
+///     assert(wasmer_metering_handle_get_remaining_points(handle) == 7);
+///     assert(wasmer_metering_handle_points_are_exhausted(handle) == false);
+///
+///     wasmer_metering_handle_set_remaining_points(handle, 42);
+///     assert(wasmer_metering_handle_get_remaining_points(handle) == 42);
+///     assert(wasmer_metering_get_remaining_points(instance) == 42);
+///
+///     wasmer_metering_handle_delete(handle);
+///     wasm_instance_delete(instance);
+///     wasm_module_delete(module);
+///     wasm_store_delete(store);
+///     wasm_engine_delete(engine);
+///
+///     return 0;
+/// }
+/// #    })
+/// #    .success();
+/// # }
+/// ```
+#[no_mangle]
+pub extern "C" fn wasmer_metering_handle_new(
+    instance: Option<&wasm_instance_t>,
+) -> Option<Box<wasmer_metering_handle_t>> {
+    let instance = instance?;
+
+    Some(Box::new(wasmer_metering_handle_t {
+        inner: MeteringHandle::new(&instance.inner).ok()?,
+    }))
+}
+
+/// Deletes a [`wasmer_metering_handle_t`].
+///
+/// # Example
+///
+/// See [`wasmer_metering_handle_new`].
+#[no_mangle]
+pub extern "C" fn wasmer_metering_handle_delete(_handle: Option<Box<wasmer_metering_handle_t>>) {}
+
+/// Like [`wasmer_metering_get_remaining_points`], but callable from
+/// any thread.
+///
+/// # Example
+///
+/// See [`wasmer_metering_handle_new`].
+#[no_mangle]
+pub extern "C" fn wasmer_metering_handle_get_remaining_points(
+    handle: &wasmer_metering_handle_t,
+) -> u64 {
+    match handle.inner.get_remaining_points() {
+        MeteringPoints::Remaining(value) => value,
+        MeteringPoints::Exhausted => std::u64::MAX,
+    }
+}
+
+/// Like [`wasmer_metering_points_are_exhausted`], but callable from
+/// any thread.
+///
+/// # Example
+///
+/// See [`wasmer_metering_handle_new`].
+#[no_mangle]
+pub extern "C" fn wasmer_metering_handle_points_are_exhausted(
+    handle: &wasmer_metering_handle_t,
+) -> bool {
+    matches!(
+        handle.inner.get_remaining_points(),
+        MeteringPoints::Exhausted,
+    )
+}
+
+/// Like [`wasmer_metering_set_remaining_points`], but callable from
+/// any thread.
+///
+/// # Example
+///
+/// See [`wasmer_metering_handle_new`].
+#[no_mangle]
+pub extern "C" fn wasmer_metering_handle_set_remaining_points(
+    handle: &wasmer_metering_handle_t,
+    new_limit: u64,
+) {
+    handle.inner.set_remaining_points(new_limit);
+}
+
 /// Transforms a [`wasmer_metering_t`] into a generic
 /// [`wasmer_middleware_t`], to then be pushed in the configuration with
 /// [`wasm_config_push_middleware`][super::wasm_config_push_middleware].
@@ -1,6 +1,7 @@
 //! Unstable non-standard Wasmer-specific types to manipulate module
 //! middlewares.
 
+pub mod interrupt;
 pub mod metering;
 
 use super::super::engine::wasm_config_t;
@@ -15,7 +16,9 @@ compile_error!("The `middlewares` feature requires the `compiler` feature to be
 /// Used by `wasm_config_push_middleware`. A specific middleware is
 /// transformed into this type to get a generic middleware. See for
 /// example
-/// [`wasmer_metering_as_middleware`][metering::wasmer_metering_as_middleware].
+/// [`wasmer_metering_as_middleware`][metering::wasmer_metering_as_middleware]
+/// or
+/// [`wasmer_interrupt_as_middleware`][interrupt::wasmer_interrupt_as_middleware].
 #[derive(Debug)]
 #[allow(non_camel_case_types)]
 pub struct wasmer_middleware_t {
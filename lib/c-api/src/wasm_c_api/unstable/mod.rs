@@ -1,9 +1,14 @@
+#[cfg(feature = "allocator")]
+pub mod allocator;
 pub mod engine;
+pub mod externref;
 pub mod features;
 #[cfg(feature = "middlewares")]
 pub mod middlewares;
 pub mod module;
+#[cfg(feature = "middlewares")]
 pub mod parser;
 pub mod target_lexicon;
+pub mod trap;
 #[cfg(feature = "wasi")]
 pub mod wasi;
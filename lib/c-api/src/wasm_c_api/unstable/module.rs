@@ -1,10 +1,14 @@
 //! Unstable non-standard Wasmer-specific extensions to the Wasm C API.
 
 use super::super::module::wasm_module_t;
+use super::super::store::wasm_store_t;
 use super::super::types::wasm_name_t;
+use std::ffi::CStr;
+use std::os::raw::c_char;
 use std::ptr;
 use std::str;
 use std::sync::Arc;
+use wasmer::Module;
 
 /// Unstable non-standard Wasmer-specific API to get the module's
 /// name, otherwise `out->size` is set to `0` and `out->data` to
@@ -157,3 +161,121 @@ pub unsafe extern "C" fn wasmer_module_set_name(
         None => false,
     }
 }
+
+/// Unstable non-standard Wasmer-specific API to serialize a module
+/// into the file at `path`, like [`wasm_module_serialize`] but
+/// without going through a byte vector. The function returns `true`
+/// if the module has been written, `false` otherwise.
+///
+/// # Safety
+///
+/// `path` must be a null-terminated string.
+///
+/// # Example
+///
+/// See [`wasmer_module_deserialize_from_file`].
+///
+/// [`wasm_module_serialize`]: super::super::module::wasm_module_serialize
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_module_serialize_to_file(
+    module: &wasm_module_t,
+    path: *const c_char,
+) -> bool {
+    let path = c_try!(CStr::from_ptr(path).to_str(); otherwise false);
+
+    c_try!(module.inner.serialize_to_file(path); otherwise false);
+
+    true
+}
+
+/// Unstable non-standard Wasmer-specific API to deserialize a module
+/// from the file at `path`, like [`wasm_module_deserialize`] but
+/// mapping the file in memory instead of reading it into a byte
+/// vector.
+///
+/// Note: the module has to be serialized before with the
+/// `wasm_module_serialize` or [`wasmer_module_serialize_to_file`]
+/// functions.
+///
+/// # Safety
+///
+/// `path` must be a null-terminated string. This function is
+/// **unsafe** for the same reasons as [`wasm_module_deserialize`]:
+/// the file contains the function assembly bodies, which are loaded
+/// into executable memory.
+///
+/// # Example
+///
+/// ```rust
+/// # use inline_c::assert_c;
+/// # fn main() {
+/// #    (assert_c! {
+/// # #include "tests/wasmer_wasm.h"
+/// #
+/// int main() {
+///     // Create the engine and the store.
+///     wasm_engine_t* engine = wasm_engine_new();
+///     wasm_store_t* store = wasm_store_new(engine);
+///
+///     // Create a WebAssembly module from a WAT definition.
+///     wasm_byte_vec_t wat;
+///     wasmer_byte_vec_new_from_string(
+///         &wat,
+///         "(module\n"
+///         "  (func (export \"function\") (param i32 i64))\n"
+///         "  (memory (export \"memory\") 1))"
+///     );
+///     wasm_byte_vec_t wasm;
+///     wat2wasm(&wat, &wasm);
+///
+///     // Create the module.
+///     wasm_module_t* module = wasm_module_new(store, &wasm);
+///     assert(module);
+///
+///     // Serialize the module into a file.
+///     assert(wasmer_module_serialize_to_file(module, "module.wasmu"));
+///     wasm_module_delete(module);
+///
+///     // Deserialize the module from the file. Note that the store
+///     // must be the same as the one used to serialize.
+///     wasm_module_t* deserialized_module = wasmer_module_deserialize_from_file(
+///         store,
+///         "module.wasmu"
+///     );
+///     remove("module.wasmu");
+///     assert(deserialized_module);
+///
+///     // Check we have our 2 export types.
+///     wasm_exporttype_vec_t export_types;
+///     wasm_module_exports(deserialized_module, &export_types);
+///
+///     assert(export_types.size == 2);
+///
+///     // Free everything.
+///     wasm_exporttype_vec_delete(&export_types);
+///     wasm_module_delete(deserialized_module);
+///     wasm_byte_vec_delete(&wasm);
+///     wasm_byte_vec_delete(&wat);
+///     wasm_store_delete(store);
+///     wasm_engine_delete(engine);
+///
+///     return 0;
+/// }
+/// #    })
+/// #    .success();
+/// # }
+/// ```
+///
+/// [`wasm_module_deserialize`]: super::super::module::wasm_module_deserialize
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_module_deserialize_from_file(
+    store: &wasm_store_t,
+    path: *const c_char,
+) -> Option<Box<wasm_module_t>> {
+    let path = c_try!(CStr::from_ptr(path).to_str());
+    let module = c_try!(Module::deserialize_from_file(&store.inner, path));
+
+    Some(Box::new(wasm_module_t {
+        inner: Arc::new(module),
+    }))
+}
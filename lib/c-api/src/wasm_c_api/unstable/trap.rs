@@ -0,0 +1,166 @@
+//! Unstable non-standard Wasmer-specific API to inspect the
+//! backtrace of a trap, with the names from the `name` section.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use inline_c::assert_c;
+//! # fn main() {
+//! #    (assert_c! {
+//! # #include "tests/wasmer_wasm.h"
+//! #
+//! int main() {
+//!     // Create the engine and the store.
+//!     wasm_engine_t* engine = wasm_engine_new();
+//!     wasm_store_t* store = wasm_store_new(engine);
+//!
+//!     // Create a WebAssembly module from a WAT definition.
+//!     wasm_byte_vec_t wat;
+//!     wasmer_byte_vec_new_from_string(
+//!         &wat,
+//!         "(module $calculator\n"
+//!         "  (func $divide (export \"divide\") (param i32 i32) (result i32)\n"
+//!         "    local.get 0\n"
+//!         "    local.get 1\n"
+//!         "    i32.div_u))"
+//!     );
+//!     wasm_byte_vec_t wasm;
+//!     wat2wasm(&wat, &wasm);
+//!
+//!     // Create the module.
+//!     wasm_module_t* module = wasm_module_new(store, &wasm);
+//!     assert(module);
+//!
+//!     // Instantiate the module.
+//!     wasm_extern_vec_t imports = WASM_EMPTY_VEC;
+//!     wasm_instance_t* instance = wasm_instance_new(store, module, &imports, NULL);
+//!     assert(instance);
+//!
+//!     // Divide by zero.
+//!     wasm_extern_vec_t exports;
+//!     wasm_instance_exports(instance, &exports);
+//!     const wasm_func_t* divide = wasm_extern_as_func(exports.data[0]);
+//!
+//!     wasm_val_t arguments[2] = { WASM_I32_VAL(1), WASM_I32_VAL(0) };
+//!     wasm_val_t results[1] = { WASM_INIT_VAL };
+//!     wasm_val_vec_t arguments_as_array = WASM_ARRAY_VEC(arguments);
+//!     wasm_val_vec_t results_as_array = WASM_ARRAY_VEC(results);
+//!
+//!     wasm_trap_t* trap = wasm_func_call(divide, &arguments_as_array, &results_as_array);
+//!     assert(trap);
+//!
+//!     // The trap happened in the `divide` function of the
+//!     // `calculator` module.
+//!     wasm_frame_t* origin = wasm_trap_origin(trap);
+//!     assert(origin);
+//!     assert(wasm_frame_func_index(origin) == 0);
+//!
+//!     {
+//!         wasm_name_t module_name;
+//!         wasmer_frame_module_name(origin, &module_name);
+//!
+//!         wasmer_assert_name(&module_name, "calculator");
+//!
+//!         wasm_name_delete(&module_name);
+//!     }
+//!
+//!     {
+//!         wasm_name_t function_name;
+//!         wasmer_frame_function_name(origin, &function_name);
+//!
+//!         wasmer_assert_name(&function_name, "divide");
+//!
+//!         wasm_name_delete(&function_name);
+//!     }
+//!
+//!     // Or, all at once.
+//!     {
+//!         wasm_message_t message;
+//!         wasmer_trap_message_with_trace(trap, &message);
+//!
+//!         assert(strstr(message.data, "integer divide by zero"));
+//!         assert(strstr(message.data, "at calculator::divide (calculator[0]:0x"));
+//!
+//!         wasm_name_delete(&message);
+//!     }
+//!
+//!     // Free everything.
+//!     wasm_frame_delete(origin);
+//!     wasm_trap_delete(trap);
+//!     wasm_extern_vec_delete(&exports);
+//!     wasm_instance_delete(instance);
+//!     wasm_module_delete(module);
+//!     wasm_byte_vec_delete(&wasm);
+//!     wasm_byte_vec_delete(&wat);
+//!     wasm_store_delete(store);
+//!     wasm_engine_delete(engine);
+//!
+//!     return 0;
+//! }
+//! #    })
+//! #    .success();
+//! # }
+//! ```
+
+use super::super::trap::wasm_trap_t;
+use super::super::types::{wasm_frame_t, wasm_message_t, wasm_name_t};
+use std::ptr;
+
+/// Unstable non-standard Wasmer-specific API to get the name of the
+/// module of a frame, from the `name` section or inferred by Wasmer.
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_frame_module_name(
+    frame: &wasm_frame_t,
+    // own
+    out: &mut wasm_name_t,
+) {
+    *out = frame.info.module_name().as_bytes().to_vec().into();
+}
+
+/// Unstable non-standard Wasmer-specific API to get the name of the
+/// function of a frame, otherwise `out->size` is set to `0` and
+/// `out->data` to `NULL`.
+///
+/// The name comes from the `name` section, or is inferred by Wasmer,
+/// e.g. from the exports.
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_frame_function_name(
+    frame: &wasm_frame_t,
+    // own
+    out: &mut wasm_name_t,
+) {
+    match frame.info.function_name() {
+        Some(name) => *out = name.as_bytes().to_vec().into(),
+        None => {
+            out.data = ptr::null_mut();
+            out.size = 0;
+        }
+    }
+}
+
+/// Unstable non-standard Wasmer-specific API to get the message of a
+/// trap followed by its backtrace, a frame per line, as a
+/// null-terminated string.
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_trap_message_with_trace(
+    trap: &wasm_trap_t,
+    // own
+    out: &mut wasm_message_t,
+) {
+    let mut byte_vec = trap.inner.to_string().into_bytes();
+    byte_vec.push(0); // append NUL
+
+    *out = byte_vec.into();
+}
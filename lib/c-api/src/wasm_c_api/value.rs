@@ -1,7 +1,8 @@
 use super::types::{wasm_ref_t, wasm_valkind_enum};
 use crate::error::{update_last_error, CApiError};
 use std::convert::{TryFrom, TryInto};
-use wasmer::Val;
+use std::ptr;
+use wasmer::{ExternRef, Val};
 
 /// Represents the kind of values. The variants of this C enum is
 /// defined in `wasm.h` to list the following:
@@ -142,8 +143,12 @@ pub unsafe extern "C" fn wasm_val_copy(
             wasm_valkind_enum::WASM_F64 => wasm_val_inner {
                 float64_t: val.of.float64_t,
             },
-            wasm_valkind_enum::WASM_ANYREF => wasm_val_inner { wref: val.of.wref },
-            wasm_valkind_enum::WASM_FUNCREF => wasm_val_inner { wref: val.of.wref },
+            wasm_valkind_enum::WASM_ANYREF | wasm_valkind_enum::WASM_FUNCREF => wasm_val_inner {
+                wref: match val.of.wref.as_ref() {
+                    Some(reference) => Box::into_raw(Box::new(reference.clone())),
+                    None => ptr::null_mut(),
+                },
+            },
         },
 
         Err(e) => {
@@ -155,10 +160,24 @@ pub unsafe extern "C" fn wasm_val_copy(
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn wasm_val_delete(val: Option<Box<wasm_val_t>>) {
+pub unsafe extern "C" fn wasm_val_delete(val: Option<&mut wasm_val_t>) {
     if let Some(val) = val {
-        // TODO: figure out where wasm_val is allocated first...
-        let _ = val;
+        val.delete_ref();
+    }
+}
+
+impl wasm_val_t {
+    /// Deletes the reference owned by the value, if it holds one.
+    pub(crate) unsafe fn delete_ref(&mut self) {
+        match self.kind.try_into() {
+            Ok(wasm_valkind_enum::WASM_ANYREF) | Ok(wasm_valkind_enum::WASM_FUNCREF)
+                if !self.of.wref.is_null() =>
+            {
+                let _ = Box::from_raw(self.of.wref);
+                self.of.wref = ptr::null_mut();
+            }
+            _ => {}
+        }
     }
 }
 
@@ -195,8 +214,22 @@ impl TryFrom<&wasm_val_t> for Val {
             wasm_valkind_enum::WASM_I64 => Val::I64(unsafe { item.of.int64_t }),
             wasm_valkind_enum::WASM_F32 => Val::F32(unsafe { item.of.float32_t }),
             wasm_valkind_enum::WASM_F64 => Val::F64(unsafe { item.of.float64_t }),
-            wasm_valkind_enum::WASM_ANYREF => return Err("ANYREF not supported at this time"),
-            wasm_valkind_enum::WASM_FUNCREF => return Err("FUNCREF not supported at this time"),
+            wasm_valkind_enum::WASM_ANYREF => match unsafe { item.of.wref.as_ref() } {
+                Some(wasm_ref_t {
+                    inner: value @ Val::ExternRef(_),
+                    ..
+                }) => value.clone(),
+                Some(_) => return Err("ANYREF value holds a function reference"),
+                None => Val::ExternRef(ExternRef::Null),
+            },
+            wasm_valkind_enum::WASM_FUNCREF => match unsafe { item.of.wref.as_ref() } {
+                Some(wasm_ref_t {
+                    inner: value @ Val::FuncRef(_),
+                    ..
+                }) => value.clone(),
+                Some(_) => return Err("FUNCREF value holds an external reference"),
+                None => Val::ExternRef(ExternRef::Null),
+            },
         })
     }
 }
@@ -231,7 +264,22 @@ impl TryFrom<&Val> for wasm_val_t {
                 kind: wasm_valkind_enum::WASM_F64 as _,
             },
             Val::V128(_) => return Err("128bit SIMD types not yet supported in Wasm C API"),
-            _ => todo!("Handle these values in TryFrom<Val> for wasm_val_t"),
+            Val::FuncRef(_) => wasm_val_t {
+                of: wasm_val_inner {
+                    wref: wasm_ref_t::new(item.clone()).map_or_else(ptr::null_mut, |reference| {
+                        Box::into_raw(Box::new(reference))
+                    }),
+                },
+                kind: wasm_valkind_enum::WASM_FUNCREF as _,
+            },
+            Val::ExternRef(_) => wasm_val_t {
+                of: wasm_val_inner {
+                    wref: wasm_ref_t::new(item.clone()).map_or_else(ptr::null_mut, |reference| {
+                        Box::into_raw(Box::new(reference))
+                    }),
+                },
+                kind: wasm_valkind_enum::WASM_ANYREF as _,
+            },
         })
     }
 }
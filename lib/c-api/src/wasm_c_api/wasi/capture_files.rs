@@ -1,4 +1,4 @@
-//! Default implementations for capturing the stdout/stderr output of a WASI program.
+//! Default implementations for capturing the stdin/stdout/stderr of a WASI program.
 
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
@@ -93,3 +93,74 @@ impl Write for OutputCapturer {
         Ok(())
     }
 }
+
+/// For feeding stdin from the host. Stores the input not read yet.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InputCapturer {
+    pub(crate) buffer: VecDeque<u8>,
+}
+
+impl InputCapturer {
+    pub fn new() -> Self {
+        Self {
+            buffer: VecDeque::new(),
+        }
+    }
+}
+
+#[typetag::serde]
+impl WasiFile for InputCapturer {
+    fn last_accessed(&self) -> u64 {
+        0
+    }
+    fn last_modified(&self) -> u64 {
+        0
+    }
+    fn created_time(&self) -> u64 {
+        0
+    }
+    fn size(&self) -> u64 {
+        0
+    }
+    fn set_len(&mut self, _len: u64) -> Result<(), WasiFsError> {
+        Ok(())
+    }
+    fn unlink(&mut self) -> Result<(), WasiFsError> {
+        Ok(())
+    }
+    fn bytes_available(&self) -> Result<usize, WasiFsError> {
+        Ok(self.buffer.len())
+    }
+}
+
+// reading consumes the input, reaching the end of file once it's all read
+impl Read for InputCapturer {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let total_to_read = buf.len().min(self.buffer.len());
+        for (address, value) in buf.iter_mut().zip(self.buffer.drain(..total_to_read)) {
+            *address = value;
+        }
+        Ok(total_to_read)
+    }
+}
+
+// fail when writing or Seeking
+impl Seek for InputCapturer {
+    fn seek(&mut self, _pos: io::SeekFrom) -> io::Result<u64> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "can not seek capturing stdin",
+        ))
+    }
+}
+impl Write for InputCapturer {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "can not write to capturing stdin",
+        ))
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -133,6 +133,8 @@ pub unsafe extern "C" fn wasi_config_mapdir(
     true
 }
 
+/// Capture the stdout of the guest in a buffer, read with
+/// [`wasi_env_read_stdout`], instead of inheriting the one of the host.
 #[no_mangle]
 pub extern "C" fn wasi_config_capture_stdout(config: &mut wasi_config_t) {
     config.inherit_stdout = false;
@@ -143,6 +145,8 @@ pub extern "C" fn wasi_config_inherit_stdout(config: &mut wasi_config_t) {
     config.inherit_stdout = true;
 }
 
+/// Capture the stderr of the guest in a buffer, read with
+/// [`wasi_env_read_stderr`], instead of inheriting the one of the host.
 #[no_mangle]
 pub extern "C" fn wasi_config_capture_stderr(config: &mut wasi_config_t) {
     config.inherit_stderr = false;
@@ -153,10 +157,15 @@ pub extern "C" fn wasi_config_inherit_stderr(config: &mut wasi_config_t) {
     config.inherit_stderr = true;
 }
 
-//#[no_mangle]
-//pub extern "C" fn wasi_config_capture_stdin(config: &mut wasi_config_t) {
-//    config.inherit_stdin = false;
-//}
+/// Feed the stdin of the guest from a buffer, written with
+/// [`wasi_env_write_stdin`], instead of inheriting the one of the host.
+///
+/// The guest reaches the end of its stdin once it has read everything
+/// written so far.
+#[no_mangle]
+pub extern "C" fn wasi_config_capture_stdin(config: &mut wasi_config_t) {
+    config.inherit_stdin = false;
+}
 
 #[no_mangle]
 pub extern "C" fn wasi_config_inherit_stdin(config: &mut wasi_config_t) {
@@ -186,7 +195,11 @@ pub extern "C" fn wasi_env_new(mut config: Box<wasi_config_t>) -> Option<Box<was
             .stderr(Box::new(capture_files::OutputCapturer::new()));
     }
 
-    // TODO: impl capturer for stdin
+    if !config.inherit_stdin {
+        config
+            .state_builder
+            .stdin(Box::new(capture_files::InputCapturer::new()));
+    }
 
     let wasi_state = c_try!(config.state_builder.build());
 
@@ -218,6 +231,14 @@ pub extern "C" fn wasi_env_set_instance(
 #[no_mangle]
 pub extern "C" fn wasi_env_set_memory(_env: &mut wasi_env_t, _memory: &wasm_memory_t) {}
 
+/// Read the stdout captured since the last call, up to `buffer_len`
+/// bytes, returning the number of bytes read, or -1 on error.
+///
+/// It requires [`wasi_config_capture_stdout`].
+///
+/// # Safety
+///
+/// `buffer` must point to at least `buffer_len` writable bytes.
 #[no_mangle]
 pub unsafe extern "C" fn wasi_env_read_stdout(
     env: &mut wasi_env_t,
@@ -242,6 +263,14 @@ pub unsafe extern "C" fn wasi_env_read_stdout(
     read_inner(stdout, inner_buffer)
 }
 
+/// Read the stderr captured since the last call, up to `buffer_len`
+/// bytes, returning the number of bytes read, or -1 on error.
+///
+/// It requires [`wasi_config_capture_stderr`].
+///
+/// # Safety
+///
+/// `buffer` must point to at least `buffer_len` writable bytes.
 #[no_mangle]
 pub unsafe extern "C" fn wasi_env_read_stderr(
     env: &mut wasi_env_t,
@@ -268,6 +297,46 @@ pub unsafe extern "C" fn wasi_env_read_stderr(
     read_inner(stderr, inner_buffer)
 }
 
+/// Write `buffer_len` bytes to the stdin of the guest, after the ones
+/// it hasn't read yet, returning the number of bytes written, or -1 on
+/// error.
+///
+/// It requires [`wasi_config_capture_stdin`].
+///
+/// # Safety
+///
+/// `buffer` must point to at least `buffer_len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn wasi_env_write_stdin(
+    env: &mut wasi_env_t,
+    buffer: *const c_char,
+    buffer_len: usize,
+) -> isize {
+    let inner_buffer = slice::from_raw_parts(buffer as *const u8, buffer_len);
+    let mut state = env.inner.state();
+    let stdin = match state.fs.stdin_mut() {
+        Ok(Some(stdin)) => stdin,
+        _ => {
+            update_last_error(CApiError {
+                msg: "could not find a file handle for `stdin`".to_string(),
+            });
+            return -1;
+        }
+    };
+    match stdin.downcast_mut::<capture_files::InputCapturer>() {
+        Some(ic) => {
+            ic.buffer.extend(inner_buffer);
+            buffer_len as isize
+        }
+        None => {
+            update_last_error(CApiError {
+                msg: "`stdin` isn't captured, see `wasi_config_capture_stdin`".to_string(),
+            });
+            -1
+        }
+    }
+}
+
 fn read_inner(wasi_file: &mut Box<dyn WasiFile>, inner_buffer: &mut [u8]) -> isize {
     if let Some(oc) = wasi_file.downcast_mut::<capture_files::OutputCapturer>() {
         let total_to_read = min(inner_buffer.len(), oc.buffer.len());
@@ -479,6 +548,85 @@ mod tests {
         .success();
     }
 
+    #[test]
+    fn test_wasi_capture_stdio() {
+        (assert_c! {
+            #include "tests/wasmer_wasm.h"
+
+            int main() {
+                wasm_engine_t* engine = wasm_engine_new();
+                wasm_store_t* store = wasm_store_new(engine);
+
+                // A module copying its stdin to its stdout.
+                wasm_byte_vec_t wat;
+                wasmer_byte_vec_new_from_string(
+                    &wat,
+                    "(module\n"
+                    "  (import \"wasi_unstable\" \"fd_read\" (func $fd_read (param i32 i32 i32 i32) (result i32)))\n"
+                    "  (import \"wasi_unstable\" \"fd_write\" (func $fd_write (param i32 i32 i32 i32) (result i32)))\n"
+                    "  (memory (export \"memory\") 1)\n"
+                    "  (func (export \"_start\")\n"
+                    "    (i32.store (i32.const 0) (i32.const 16))\n"
+                    "    (i32.store (i32.const 4) (i32.const 64))\n"
+                    "    (drop (call $fd_read (i32.const 0) (i32.const 0) (i32.const 1) (i32.const 8)))\n"
+                    "    (i32.store (i32.const 4) (i32.load (i32.const 8)))\n"
+                    "    (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)))))"
+                );
+                wasm_byte_vec_t wasm;
+                wat2wasm(&wat, &wasm);
+
+                wasm_module_t* module = wasm_module_new(store, &wasm);
+                assert(module);
+
+                wasi_config_t* config = wasi_config_new("example_program");
+                wasi_config_capture_stdin(config);
+                wasi_config_capture_stdout(config);
+                wasi_env_t* wasi_env = wasi_env_new(config);
+                assert(wasi_env);
+
+                assert(wasi_env_write_stdin(wasi_env, "hello", 5) == 5);
+                char buffer[16] = { 0 };
+                assert(wasi_env_read_stdout(wasi_env, buffer, sizeof(buffer)) == 0);
+
+                wasm_importtype_vec_t import_types;
+                wasm_module_imports(module, &import_types);
+                wasm_extern_vec_t imports;
+                wasm_extern_vec_new_uninitialized(&imports, import_types.size);
+                wasm_importtype_vec_delete(&import_types);
+                assert(wasi_get_imports(store, module, wasi_env, &imports));
+
+                wasm_trap_t* traps = NULL;
+                wasm_instance_t* instance = wasm_instance_new(store, module, &imports, &traps);
+                assert(instance);
+
+                wasm_func_t* start = wasi_get_start_function(instance);
+                assert(start);
+                wasm_val_vec_t arguments = WASM_EMPTY_VEC;
+                wasm_val_vec_t results = WASM_EMPTY_VEC;
+                assert(wasm_func_call(start, &arguments, &results) == NULL);
+
+                // The output is read incrementally.
+                assert(wasi_env_read_stdout(wasi_env, buffer, 3) == 3);
+                assert(wasi_env_read_stdout(wasi_env, buffer + 3, sizeof(buffer) - 3) == 2);
+                assert(strcmp(buffer, "hello") == 0);
+                assert(wasi_env_read_stdout(wasi_env, buffer, sizeof(buffer)) == 0);
+
+                wasm_func_delete(start);
+                wasm_instance_delete(instance);
+                wasm_extern_vec_delete(&imports);
+                wasi_env_delete(wasi_env);
+                wasm_module_delete(module);
+                wasm_byte_vec_delete(&wasm);
+                wasm_byte_vec_delete(&wat);
+                wasm_store_delete(store);
+                wasm_engine_delete(engine);
+
+                return 0;
+            }
+        })
+        .success();
+    }
+
     #[test]
     fn test_wasi_get_wasi_version_invalid() {
         (assert_c! {
@@ -28,9 +28,6 @@
 #  define DEPRECATED(message) __declspec(deprecated(message))
 #endif
 
-// The `wasi` feature has been enabled for this build.
-#define WASMER_WASI_ENABLED
-
 // This file corresponds to the following Wasmer version.
 #define WASMER_VERSION "1.0.2"
 #define WASMER_VERSION_MAJOR 1
@@ -68,9 +68,6 @@
 // The `compiler` feature has been enabled for this build.
 #define WASMER_COMPILER_ENABLED
 
-// The `wasi` feature has been enabled for this build.
-#define WASMER_WASI_ENABLED
-
 // The `middlewares` feature has been enabled for this build.
 #define WASMER_MIDDLEWARES_ENABLED
 
@@ -115,13 +112,11 @@ typedef enum wasi_version_t {
 } wasi_version_t;
 #endif
 
-#if defined(WASMER_COMPILER_ENABLED)
 typedef enum wasmer_compiler_t {
   CRANELIFT = 0,
   LLVM = 1,
   SINGLEPASS = 2,
 } wasmer_compiler_t;
-#endif
 
 typedef enum wasmer_engine_t {
   JIT = 0,
@@ -645,6 +640,12 @@ typedef struct wasmer_cpu_features_t wasmer_cpu_features_t;
 
 typedef struct wasmer_features_t wasmer_features_t;
 
+typedef struct wasmer_interrupt_handle_t wasmer_interrupt_handle_t;
+
+typedef struct wasmer_interrupt_t wasmer_interrupt_t;
+
+typedef struct wasmer_metering_handle_t wasmer_metering_handle_t;
+
 typedef struct wasmer_metering_t wasmer_metering_t;
 
 typedef struct wasmer_middleware_t wasmer_middleware_t;
@@ -664,8 +665,22 @@ typedef struct wasmer_named_extern_vec_t {
 } wasmer_named_extern_vec_t;
 #endif
 
+typedef void (*wasmer_externref_finalizer_t)(void *data);
+
 typedef uint64_t (*wasmer_metering_cost_function_t)(enum wasmer_parser_operator_t wasm_operator);
 
+#if defined(WASMER_ALLOCATOR_ENABLED)
+typedef void *(*wasmer_malloc_callback_t)(uintptr_t size, uintptr_t align);
+#endif
+
+#if defined(WASMER_ALLOCATOR_ENABLED)
+typedef void (*wasmer_free_callback_t)(void *ptr, uintptr_t size, uintptr_t align);
+#endif
+
+#if defined(WASMER_ALLOCATOR_ENABLED)
+typedef void *(*wasmer_realloc_callback_t)(void *ptr, uintptr_t old_size, uintptr_t align, uintptr_t new_size);
+#endif
+
 #ifdef __cplusplus
 extern "C" {
 #endif // __cplusplus
@@ -678,6 +693,10 @@ void wasi_config_arg(struct wasi_config_t *config, const char *arg);
 void wasi_config_capture_stderr(struct wasi_config_t *config);
 #endif
 
+#if defined(WASMER_WASI_ENABLED)
+void wasi_config_capture_stdin(struct wasi_config_t *config);
+#endif
+
 #if defined(WASMER_WASI_ENABLED)
 void wasi_config_capture_stdout(struct wasi_config_t *config);
 #endif
@@ -738,6 +757,10 @@ void wasi_env_set_memory(struct wasi_env_t *_env,
                          const wasm_memory_t *_memory);
 #endif
 
+#if defined(WASMER_WASI_ENABLED)
+intptr_t wasi_env_write_stdin(struct wasi_env_t *env, const char *buffer, uintptr_t buffer_len);
+#endif
+
 #if defined(WASMER_WASI_ENABLED)
 bool wasi_get_imports(const wasm_store_t *store,
                       const wasm_module_t *module,
@@ -779,10 +802,16 @@ void wasmer_cpu_features_delete(struct wasmer_cpu_features_t *_cpu_features);
 
 struct wasmer_cpu_features_t *wasmer_cpu_features_new(void);
 
+void *wasmer_externref_data(const wasm_ref_t *reference);
+
+wasm_ref_t *wasmer_externref_new(void *data, wasmer_externref_finalizer_t finalizer);
+
 bool wasmer_features_bulk_memory(struct wasmer_features_t *features, bool enable);
 
 void wasmer_features_delete(struct wasmer_features_t *_features);
 
+bool wasmer_features_exceptions(struct wasmer_features_t *features, bool enable);
+
 bool wasmer_features_memory64(struct wasmer_features_t *features, bool enable);
 
 bool wasmer_features_module_linking(struct wasmer_features_t *features, bool enable);
@@ -801,6 +830,24 @@ bool wasmer_features_tail_call(struct wasmer_features_t *features, bool enable);
 
 bool wasmer_features_threads(struct wasmer_features_t *features, bool enable);
 
+void wasmer_frame_function_name(const wasm_frame_t *frame, wasm_name_t *out);
+
+void wasmer_frame_module_name(const wasm_frame_t *frame, wasm_name_t *out);
+
+struct wasmer_middleware_t *wasmer_interrupt_as_middleware(struct wasmer_interrupt_t *interrupt);
+
+void wasmer_interrupt_delete(struct wasmer_interrupt_t *_interrupt);
+
+void wasmer_interrupt_handle_delete(struct wasmer_interrupt_handle_t *_handle);
+
+void wasmer_interrupt_handle_interrupt(const struct wasmer_interrupt_handle_t *handle);
+
+bool wasmer_interrupt_handle_is_interrupted(const struct wasmer_interrupt_handle_t *handle);
+
+void wasmer_interrupt_handle_reset(const struct wasmer_interrupt_handle_t *handle);
+
+struct wasmer_interrupt_t *wasmer_interrupt_new(void);
+
 bool wasmer_is_compiler_available(enum wasmer_compiler_t compiler);
 
 bool wasmer_is_engine_available(enum wasmer_engine_t engine);
@@ -817,6 +864,17 @@ void wasmer_metering_delete(struct wasmer_metering_t *_metering);
 
 uint64_t wasmer_metering_get_remaining_points(const wasm_instance_t *instance);
 
+void wasmer_metering_handle_delete(struct wasmer_metering_handle_t *_handle);
+
+uint64_t wasmer_metering_handle_get_remaining_points(const struct wasmer_metering_handle_t *handle);
+
+struct wasmer_metering_handle_t *wasmer_metering_handle_new(const wasm_instance_t *instance);
+
+bool wasmer_metering_handle_points_are_exhausted(const struct wasmer_metering_handle_t *handle);
+
+void wasmer_metering_handle_set_remaining_points(const struct wasmer_metering_handle_t *handle,
+                                                 uint64_t new_limit);
+
 struct wasmer_metering_t *wasmer_metering_new(uint64_t initial_limit,
                                               wasmer_metering_cost_function_t cost_function);
 
@@ -824,8 +882,12 @@ bool wasmer_metering_points_are_exhausted(const wasm_instance_t *instance);
 
 void wasmer_metering_set_remaining_points(const wasm_instance_t *instance, uint64_t new_limit);
 
+wasm_module_t *wasmer_module_deserialize_from_file(const wasm_store_t *store, const char *path);
+
 void wasmer_module_name(const wasm_module_t *module, wasm_name_t *out);
 
+bool wasmer_module_serialize_to_file(const wasm_module_t *module, const char *path);
+
 bool wasmer_module_set_name(wasm_module_t *module, const wasm_name_t *name);
 
 #if defined(WASMER_WASI_ENABLED)
@@ -864,11 +926,21 @@ void wasmer_named_extern_vec_new_uninitialized(struct wasmer_named_extern_vec_t
                                                uintptr_t length);
 #endif
 
+#if defined(WASMER_ALLOCATOR_ENABLED)
+bool wasmer_set_allocator(wasmer_malloc_callback_t malloc_callback,
+                          wasmer_free_callback_t free_callback,
+                          wasmer_realloc_callback_t realloc_callback);
+#endif
+
+struct wasmer_interrupt_handle_t *wasmer_store_interrupt_handle(const wasm_store_t *store);
+
 void wasmer_target_delete(struct wasmer_target_t *_target);
 
 struct wasmer_target_t *wasmer_target_new(struct wasmer_triple_t *triple,
                                           struct wasmer_cpu_features_t *cpu_features);
 
+void wasmer_trap_message_with_trace(const wasm_trap_t *trap, wasm_message_t *out);
+
 void wasmer_triple_delete(struct wasmer_triple_t *_triple);
 
 struct wasmer_triple_t *wasmer_triple_new(const wasm_name_t *triple);
@@ -0,0 +1,176 @@
+//! An asynchronous variant of [`Cache`](crate::Cache), for the hosts
+//! running on an async executor, which shouldn't be blocked while loading or
+//! storing the artifacts.
+
+use crate::filesystem::{touch, FileSystemCache};
+use crate::hash::Hash;
+use std::error::Error;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use wasmer::{DeserializeError, Module, SerializeError, Store};
+
+/// A future returned by the methods of [`AsyncCache`].
+pub type CacheFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A generic cache for storing and loading compiled wasm modules without
+/// blocking the executor.
+pub trait AsyncCache {
+    /// The serialization error for the implementation
+    type SerializeError: Error + Send + Sync;
+    /// The deserialization error for the implementation
+    type DeserializeError: Error + Send + Sync;
+
+    /// Loads a module using the provided [`Store`] and [`Hash`].
+    ///
+    /// # Safety
+    /// This function is unsafe as the cache store could be tampered with.
+    unsafe fn load_async<'a>(
+        &'a self,
+        store: &'a Store,
+        key: Hash,
+    ) -> CacheFuture<'a, Result<Module, Self::DeserializeError>>;
+
+    /// Store a [`Module`] into the cache with the given [`Hash`].
+    fn store_async<'a>(
+        &'a mut self,
+        key: Hash,
+        module: &'a Module,
+    ) -> CacheFuture<'a, Result<(), Self::SerializeError>>;
+}
+
+impl AsyncCache for FileSystemCache {
+    type DeserializeError = DeserializeError;
+    type SerializeError = SerializeError;
+
+    /// Reads and deserializes the artifact on another thread.
+    unsafe fn load_async<'a>(
+        &'a self,
+        store: &'a Store,
+        key: Hash,
+    ) -> CacheFuture<'a, Result<Module, Self::DeserializeError>> {
+        let path = self.path_of(self.key_for(key, store));
+        let store = store.clone();
+        Box::pin(spawn_blocking(move || {
+            let module = Module::deserialize_from_file(&store, &path)?;
+            touch(&path);
+            Ok(module)
+        }))
+    }
+
+    /// Serializes and writes the artifact on another thread.
+    fn store_async<'a>(
+        &'a mut self,
+        key: Hash,
+        module: &'a Module,
+    ) -> CacheFuture<'a, Result<(), Self::SerializeError>> {
+        let path = self.path_of(self.key_for(key, module.store()));
+        let module = module.clone();
+        let cache = self.clone();
+        Box::pin(spawn_blocking(move || {
+            let buffer = module.serialize()?;
+            std::fs::write(path, buffer)?;
+            cache.evict()?;
+            Ok(())
+        }))
+    }
+}
+
+struct BlockingState<T> {
+    output: Option<T>,
+    waker: Option<Waker>,
+}
+
+/// A future of the output of a closure run on its own thread, which works
+/// with any executor.
+struct Blocking<T> {
+    state: Arc<Mutex<BlockingState<T>>>,
+}
+
+fn spawn_blocking<T, F>(f: F) -> Blocking<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let state = Arc::new(Mutex::new(BlockingState {
+        output: None,
+        waker: None,
+    }));
+    let thread_state = state.clone();
+    thread::spawn(move || {
+        let output = f();
+        let mut state = thread_state.lock().unwrap();
+        state.output = Some(output);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    });
+    Blocking { state }
+}
+
+impl<T> Future for Blocking<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let mut state = self.state.lock().unwrap();
+        match state.output.take() {
+            Some(output) => Poll::Ready(output),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::task::{RawWaker, RawWakerVTable};
+
+    /// A waker sending a message when woken.
+    fn channel_waker(sender: mpsc::Sender<()>) -> Waker {
+        unsafe fn clone(data: *const ()) -> RawWaker {
+            let sender = &*(data as *const mpsc::Sender<()>);
+            RawWaker::new(
+                Box::into_raw(Box::new(sender.clone())) as *const (),
+                &VTABLE,
+            )
+        }
+        unsafe fn wake(data: *const ()) {
+            wake_by_ref(data);
+            drop_waker(data);
+        }
+        unsafe fn wake_by_ref(data: *const ()) {
+            let _ = (*(data as *const mpsc::Sender<()>)).send(());
+        }
+        unsafe fn drop_waker(data: *const ()) {
+            drop(Box::from_raw(data as *mut mpsc::Sender<()>));
+        }
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake_by_ref, drop_waker);
+
+        let data = Box::into_raw(Box::new(sender)) as *const ();
+        unsafe { Waker::from_raw(RawWaker::new(data, &VTABLE)) }
+    }
+
+    #[test]
+    fn blocking_future_wakes_the_task() {
+        let (sender, receiver) = mpsc::channel();
+        let waker = channel_waker(sender);
+        let mut cx = Context::from_waker(&waker);
+
+        let (start, started) = mpsc::channel::<()>();
+        let mut future = Box::pin(spawn_blocking(move || {
+            started.recv().unwrap();
+            42
+        }));
+        assert_eq!(future.as_mut().poll(&mut cx), Poll::Pending);
+
+        start.send(()).unwrap();
+        receiver.recv().unwrap();
+        assert_eq!(future.as_mut().poll(&mut cx), Poll::Ready(42));
+    }
+}
@@ -1,8 +1,10 @@
 use crate::cache::Cache;
 use crate::hash::Hash;
-use std::fs::{create_dir_all, File};
+use std::fs::{self, create_dir_all, File};
 use std::io::{self, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
 use wasmer::{DeserializeError, Module, SerializeError, Store};
 
 /// Representation of a directory that contains compiled wasm artifacts.
@@ -30,9 +32,47 @@ use wasmer::{DeserializeError, Module, SerializeError, Store};
 ///     Ok(())
 /// }
 /// ```
+///
+/// # Eviction
+///
+/// By default the cache grows forever. A maximum size and a maximum age
+/// can be set with [`FileSystemCache::set_max_size`] and
+/// [`FileSystemCache::set_max_age`]: the least recently used artifacts are
+/// then removed when storing a new one, or when calling
+/// [`FileSystemCache::prune`].
+///
+/// The last use of an artifact is tracked with the modification time of
+/// its file, which is updated on every load.
+///
+/// # Keys
+///
+/// The keys are combined with the [`Engine::fingerprint`] of the store, so
+/// that an artifact built by another version of Wasmer, engine or for other
+/// CPU features misses the cache instead of failing to load. Artifacts
+/// built with another compiler configuration fail to load with
+/// [`DeserializeError::Incompatible`] in compiling engines.
+///
+/// [`DeserializeError::Incompatible`]: wasmer::DeserializeError::Incompatible
+///
+/// [`Engine::fingerprint`]: wasmer::Engine::fingerprint
+#[derive(Debug, Clone)]
 pub struct FileSystemCache {
     path: PathBuf,
     ext: Option<String>,
+    max_size: Option<u64>,
+    max_age: Option<Duration>,
+    fingerprint: bool,
+}
+
+/// The outcome of [`FileSystemCache::prune`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PruneStats {
+    /// The number of artifacts removed from the cache.
+    pub removed: usize,
+    /// The number of bytes freed.
+    pub freed: u64,
+    /// The size in bytes of the artifacts left in the cache.
+    pub remaining: u64,
 }
 
 impl FileSystemCache {
@@ -43,7 +83,13 @@ impl FileSystemCache {
             let metadata = path.metadata()?;
             if metadata.is_dir() {
                 if !metadata.permissions().readonly() {
-                    Ok(Self { path, ext: None })
+                    Ok(Self {
+                        path,
+                        ext: None,
+                        max_size: None,
+                        max_age: None,
+                        fingerprint: true,
+                    })
                 } else {
                     // This directory is readonly.
                     Err(io::Error::new(
@@ -64,7 +110,13 @@ impl FileSystemCache {
         } else {
             // Create the directory and any parent directories if they don't yet exist.
             create_dir_all(&path)?;
-            Ok(Self { path, ext: None })
+            Ok(Self {
+                path,
+                ext: None,
+                max_size: None,
+                max_age: None,
+                fingerprint: true,
+            })
         }
     }
 
@@ -75,34 +127,195 @@ impl FileSystemCache {
     pub fn set_cache_extension(&mut self, ext: Option<impl ToString>) {
         self.ext = ext.map(|ext| ext.to_string());
     }
-}
 
-impl Cache for FileSystemCache {
-    type DeserializeError = DeserializeError;
-    type SerializeError = SerializeError;
+    /// Set the maximum total size in bytes of the cached artifacts.
+    ///
+    /// When it is exceeded, the least recently used artifacts are removed.
+    pub fn set_max_size(&mut self, max_size: Option<u64>) {
+        self.max_size = max_size;
+    }
 
-    unsafe fn load(&self, store: &Store, key: Hash) -> Result<Module, Self::DeserializeError> {
-        let filename = if let Some(ref ext) = self.ext {
-            format!("{}.{}", key.to_string(), ext)
+    /// Set how long an artifact is kept in the cache after its last use.
+    pub fn set_max_age(&mut self, max_age: Option<Duration>) {
+        self.max_age = max_age;
+    }
+
+    /// Set whether the keys are combined with the [`Engine::fingerprint`] of
+    /// the store, which is the default.
+    ///
+    /// Disabling it allows a headless engine to load the artifacts stored
+    /// by the engine which compiled them.
+    ///
+    /// [`Engine::fingerprint`]: wasmer::Engine::fingerprint
+    pub fn set_engine_fingerprint(&mut self, enabled: bool) {
+        self.fingerprint = enabled;
+    }
+
+    /// Remove the artifacts older than the maximum age, then the least
+    /// recently used ones until the cache fits in the maximum size.
+    ///
+    /// Only the files named after a [`Hash`] are considered, so that other
+    /// files living in the cache directory are left untouched.
+    pub fn prune(&self) -> io::Result<PruneStats> {
+        let now = SystemTime::now();
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(&self.path)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if !metadata.is_file() || !is_cache_entry(&entry.path()) {
+                continue;
+            }
+            let last_used = metadata.modified().unwrap_or(now);
+            entries.push((last_used, metadata.len(), entry.path()));
+        }
+        // The most recently used artifacts come first.
+        entries.sort_by(|a, b| b.0.cmp(&a.0));
+
+        let mut stats = PruneStats::default();
+        for (last_used, size, path) in entries {
+            let expired = match self.max_age {
+                Some(max_age) => now
+                    .duration_since(last_used)
+                    .map_or(false, |age| age > max_age),
+                None => false,
+            };
+            let too_large = match self.max_size {
+                Some(max_size) => stats.remaining + size > max_size,
+                None => false,
+            };
+            if !expired && !too_large {
+                stats.remaining += size;
+                continue;
+            }
+            match fs::remove_file(&path) {
+                Ok(()) => {
+                    stats.removed += 1;
+                    stats.freed += size;
+                }
+                // Another process may have pruned it already.
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(stats)
+    }
+
+    /// The key of the artifact built by the engine of `store`.
+    pub(crate) fn key_for(&self, key: Hash, store: &Store) -> Hash {
+        if self.fingerprint {
+            key.with_fingerprint(&store.engine().fingerprint())
         } else {
-            key.to_string()
-        };
-        let path = self.path.join(filename);
-        Module::deserialize_from_file(&store, path)
+            key
+        }
     }
 
-    fn store(&mut self, key: Hash, module: &Module) -> Result<(), Self::SerializeError> {
+    /// The path of the file caching the module with the given key.
+    pub(crate) fn path_of(&self, key: Hash) -> PathBuf {
         let filename = if let Some(ref ext) = self.ext {
             format!("{}.{}", key.to_string(), ext)
         } else {
             key.to_string()
         };
-        let path = self.path.join(filename);
+        self.path.join(filename)
+    }
+
+    /// Prune the cache after storing an artifact, if it is bounded.
+    pub(crate) fn evict(&self) -> io::Result<()> {
+        if self.max_size.is_some() || self.max_age.is_some() {
+            self.prune()?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether the file is named after a [`Hash`], with any extension.
+fn is_cache_entry(path: &Path) -> bool {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .map_or(false, |stem| Hash::from_str(stem).is_ok())
+}
+
+/// Mark the artifact as recently used.
+pub(crate) fn touch(path: &Path) {
+    // Failing to do so only makes the eviction less accurate.
+    let _ = filetime::set_file_mtime(path, filetime::FileTime::now());
+}
+
+impl Cache for FileSystemCache {
+    type DeserializeError = DeserializeError;
+    type SerializeError = SerializeError;
+
+    unsafe fn load(&self, store: &Store, key: Hash) -> Result<Module, Self::DeserializeError> {
+        let path = self.path_of(self.key_for(key, store));
+        let module = Module::deserialize_from_file(store, &path)?;
+        touch(&path);
+        Ok(module)
+    }
+
+    fn store(&mut self, key: Hash, module: &Module) -> Result<(), Self::SerializeError> {
+        let path = self.path_of(self.key_for(key, module.store()));
         let mut file = File::create(path)?;
 
         let buffer = module.serialize()?;
         file.write_all(&buffer)?;
+        drop(file);
 
+        self.evict()?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_cache(name: &str) -> FileSystemCache {
+        let path =
+            std::env::temp_dir().join(format!("wasmer-cache-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        FileSystemCache::new(path).unwrap()
+    }
+
+    fn write_entry(cache: &FileSystemCache, byte: u8, size: usize, age: u64) -> PathBuf {
+        let path = cache.path_of(Hash::new([byte; 32]));
+        fs::write(&path, vec![0; size]).unwrap();
+        let mtime = SystemTime::now() - Duration::from_secs(age);
+        filetime::set_file_mtime(&path, filetime::FileTime::from_system_time(mtime)).unwrap();
+        path
+    }
+
+    #[test]
+    fn prune_removes_least_recently_used() {
+        let mut cache = temp_cache("lru");
+        cache.set_cache_extension(Some("bin"));
+        let oldest = write_entry(&cache, 1, 100, 30);
+        let middle = write_entry(&cache, 2, 100, 20);
+        let newest = write_entry(&cache, 3, 100, 10);
+        let other = cache.path.join("README");
+        fs::write(&other, vec![0; 1000]).unwrap();
+
+        cache.set_max_size(Some(250));
+        let stats = cache.prune().unwrap();
+        assert_eq!(
+            stats,
+            PruneStats {
+                removed: 1,
+                freed: 100,
+                remaining: 200
+            }
+        );
+        assert!(!oldest.exists());
+        assert!(middle.exists());
+        assert!(newest.exists());
+        assert!(other.exists());
+
+        cache.set_max_size(None);
+        cache.set_max_age(Some(Duration::from_secs(15)));
+        let stats = cache.prune().unwrap();
+        assert_eq!(stats.removed, 1);
+        assert!(!middle.exists());
+        assert!(newest.exists());
+
+        fs::remove_dir_all(&cache.path).unwrap();
+    }
+}
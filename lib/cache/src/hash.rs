@@ -22,6 +22,18 @@ impl Hash {
         Self::new(hash.into())
     }
 
+    /// Combines the hash with a fingerprint of the engine, like
+    /// [`Engine::fingerprint`], so that the artifacts of different engines
+    /// get different keys.
+    ///
+    /// [`Engine::fingerprint`]: wasmer::Engine::fingerprint
+    pub fn with_fingerprint(&self, fingerprint: &str) -> Self {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&self.0);
+        hasher.update(fingerprint.as_bytes());
+        Self::new(hasher.finalize().into())
+    }
+
     pub(crate) fn to_array(&self) -> [u8; 32] {
         self.0
     }
@@ -71,4 +83,12 @@ mod tests {
         let hash = Hash::new(original);
         assert_eq!(hash.to_array(), original);
     }
+
+    #[test]
+    fn hash_with_fingerprint() {
+        let hash = Hash::generate(b"module");
+        assert_eq!(hash.with_fingerprint("a"), hash.with_fingerprint("a"));
+        assert_ne!(hash.with_fingerprint("a"), hash.with_fingerprint("b"));
+        assert_ne!(hash.with_fingerprint("a"), hash);
+    }
 }
@@ -19,13 +19,21 @@
     )
 )]
 
+#[cfg(feature = "async")]
+mod async_cache;
 mod cache;
 mod filesystem;
 mod hash;
+mod memory;
+mod remote;
 
+#[cfg(feature = "async")]
+pub use crate::async_cache::{AsyncCache, CacheFuture};
 pub use crate::cache::Cache;
-pub use crate::filesystem::FileSystemCache;
+pub use crate::filesystem::{FileSystemCache, PruneStats};
 pub use crate::hash::Hash;
+pub use crate::memory::MemoryCache;
+pub use crate::remote::{RemoteCache, TieredCache};
 
 // We re-export those for convinience of users
 pub use wasmer::{DeserializeError, SerializeError};
@@ -0,0 +1,145 @@
+use crate::cache::Cache;
+use crate::hash::Hash;
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::io;
+use std::sync::Mutex;
+use wasmer::{DeserializeError, Module, Store, WeakModule};
+
+/// An in-memory cache of the [`Module`]s already compiled, which saves
+/// both the compilation and the deserialization.
+///
+/// The cache only keeps [`WeakModule`] handles: a module is freed once the
+/// embedder drops it, and loading it again misses the cache. At most
+/// `capacity` modules are tracked, forgetting the least recently used ones
+/// first.
+///
+/// ```
+/// use wasmer::{CompileError, Module, Store};
+/// use wasmer_cache::{Cache, Hash, MemoryCache};
+///
+/// fn compile(cache: &mut MemoryCache, store: &Store, bytes: &[u8]) -> Result<Module, CompileError> {
+///     let key = Hash::generate(bytes);
+///     if let Ok(module) = unsafe { cache.load(store, key) } {
+///         return Ok(module);
+///     }
+///     let module = Module::new(store, bytes)?;
+///     cache.store(key, &module).unwrap();
+///     Ok(module)
+/// }
+/// ```
+#[derive(Debug)]
+pub struct MemoryCache {
+    capacity: usize,
+    entries: Mutex<Entries>,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    modules: HashMap<Hash, WeakModule>,
+    /// The keys, from the least to the most recently used.
+    order: VecDeque<Hash>,
+}
+
+impl Entries {
+    fn touch(&mut self, key: Hash) {
+        if let Some(position) = self.order.iter().position(|k| *k == key) {
+            self.order.remove(position);
+        }
+        self.order.push_back(key);
+    }
+
+    fn remove(&mut self, key: Hash) {
+        self.modules.remove(&key);
+        if let Some(position) = self.order.iter().position(|k| *k == key) {
+            self.order.remove(position);
+        }
+    }
+}
+
+impl MemoryCache {
+    /// Create a cache tracking at most `capacity` modules.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(Entries::default()),
+        }
+    }
+
+    /// The number of modules tracked by the cache, some of which may have
+    /// been freed already.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().modules.len()
+    }
+
+    /// Whether the cache tracks no module.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forget the modules which have been freed.
+    pub fn purge(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        let Entries { modules, order } = &mut *entries;
+        modules.retain(|_, module| module.upgrade().is_some());
+        order.retain(|key| modules.contains_key(key));
+    }
+
+    /// Forget all the modules.
+    pub fn clear(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.modules.clear();
+        entries.order.clear();
+    }
+}
+
+impl Cache for MemoryCache {
+    type DeserializeError = DeserializeError;
+    type SerializeError = Infallible;
+
+    unsafe fn load(&self, store: &Store, key: Hash) -> Result<Module, Self::DeserializeError> {
+        let mut entries = self.entries.lock().unwrap();
+        let module = entries.modules.get(&key).and_then(WeakModule::upgrade);
+        match module {
+            // A module compiled by another engine can't be used.
+            Some(module) if Store::same(module.store(), store) => {
+                entries.touch(key);
+                Ok(module)
+            }
+            Some(_) => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "the module was compiled by another engine",
+            )
+            .into()),
+            None => {
+                entries.remove(key);
+                Err(io::Error::new(io::ErrorKind::NotFound, "the module isn't cached").into())
+            }
+        }
+    }
+
+    fn store(&mut self, key: Hash, module: &Module) -> Result<(), Self::SerializeError> {
+        let capacity = self.capacity;
+        let entries = self.entries.get_mut().unwrap();
+        entries.modules.insert(key, module.downgrade());
+        entries.touch(key);
+
+        while entries.modules.len() > capacity {
+            // Forget the freed modules before the live ones.
+            let freed = entries
+                .order
+                .iter()
+                .copied()
+                .find(|key| entries.modules[key].upgrade().is_none());
+            let evicted = match freed {
+                Some(key) => key,
+                None => match entries.order.front() {
+                    Some(key) => *key,
+                    None => break,
+                },
+            };
+            entries.remove(evicted);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+//! Remote caches, sharing the compiled artifacts between machines.
+
+use crate::cache::Cache;
+use crate::filesystem::{touch, FileSystemCache};
+use crate::hash::Hash;
+use std::error::Error;
+use std::fs;
+use wasmer::{DeserializeError, Module, SerializeError, Store};
+
+/// A store of serialized artifacts shared by several machines, like an
+/// HTTP server or an S3 bucket.
+///
+/// It is used as the second level of a [`TieredCache`], behind a
+/// [`FileSystemCache`].
+pub trait RemoteCache {
+    /// The error returned by the backend
+    type Error: Error + Send + Sync;
+
+    /// Fetch the serialized artifact with the given [`Hash`], if the remote
+    /// cache has one.
+    fn get(&self, key: Hash) -> Result<Option<Vec<u8>>, Self::Error>;
+
+    /// Upload a serialized artifact with the given [`Hash`].
+    fn put(&self, key: Hash, artifact: &[u8]) -> Result<(), Self::Error>;
+}
+
+/// A [`FileSystemCache`] backed by a [`RemoteCache`].
+///
+/// Loading a module missing from the local cache downloads it from the
+/// remote one, and storing a module uploads it to the remote cache, so
+/// that a module is only compiled once for a whole fleet of machines.
+///
+/// The keys of the remote cache are combined with the engine fingerprint
+/// like the ones of the local cache, so that machines with different CPU
+/// features don't share their artifacts.
+///
+/// ```
+/// use wasmer::{Module, SerializeError};
+/// use wasmer_cache::{Cache, FileSystemCache, Hash, RemoteCache, TieredCache};
+///
+/// fn store_module<R: RemoteCache>(remote: R, module: &Module, bytes: &[u8]) -> Result<(), SerializeError> {
+///     let local = FileSystemCache::new("some/directory/goes/here")?;
+///     let mut cache = TieredCache::new(local, remote);
+///
+///     // Store the module in both the local and the remote caches.
+///     cache.store(Hash::generate(bytes), module)?;
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct TieredCache<R: RemoteCache> {
+    local: FileSystemCache,
+    remote: R,
+}
+
+impl<R: RemoteCache> TieredCache<R> {
+    /// Put `remote` behind the `local` cache.
+    pub fn new(local: FileSystemCache, remote: R) -> Self {
+        Self { local, remote }
+    }
+
+    /// The local cache.
+    pub fn local(&self) -> &FileSystemCache {
+        &self.local
+    }
+
+    /// The local cache, to configure it.
+    pub fn local_mut(&mut self) -> &mut FileSystemCache {
+        &mut self.local
+    }
+
+    /// The remote cache.
+    pub fn remote(&self) -> &R {
+        &self.remote
+    }
+
+    /// Download the artifact into the local cache, returning whether the
+    /// remote cache had it.
+    ///
+    /// The key already includes the engine fingerprint, if enabled.
+    fn download(&self, key: Hash) -> Result<bool, DeserializeError> {
+        let artifact = match self.remote.get(key) {
+            Ok(Some(artifact)) => artifact,
+            Ok(None) => return Ok(false),
+            Err(e) => {
+                return Err(DeserializeError::Generic(format!(
+                    "failed to fetch the artifact from the remote cache: {}",
+                    e
+                )))
+            }
+        };
+        fs::write(self.local.path_of(key), artifact)?;
+        self.local.evict()?;
+        Ok(true)
+    }
+}
+
+impl<R: RemoteCache> Cache for TieredCache<R> {
+    type DeserializeError = DeserializeError;
+    type SerializeError = SerializeError;
+
+    unsafe fn load(&self, store: &Store, key: Hash) -> Result<Module, Self::DeserializeError> {
+        let error = match self.local.load(store, key) {
+            Ok(module) => return Ok(module),
+            Err(e) => e,
+        };
+        let key = self.local.key_for(key, store);
+        if !self.download(key)? {
+            return Err(error);
+        }
+        let path = self.local.path_of(key);
+        let module = Module::deserialize_from_file(store, &path)?;
+        touch(&path);
+        Ok(module)
+    }
+
+    fn store(&mut self, key: Hash, module: &Module) -> Result<(), Self::SerializeError> {
+        let key = self.local.key_for(key, module.store());
+        let buffer = module.serialize()?;
+        fs::write(self.local.path_of(key), &buffer)?;
+        self.local.evict()?;
+
+        self.remote.put(key, &buffer).map_err(|e| {
+            SerializeError::Generic(format!(
+                "failed to upload the artifact to the remote cache: {}",
+                e
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    #[derive(Debug, Default)]
+    struct MemoryRemote {
+        artifacts: Mutex<HashMap<String, Vec<u8>>>,
+    }
+
+    impl RemoteCache for MemoryRemote {
+        type Error = std::io::Error;
+
+        fn get(&self, key: Hash) -> Result<Option<Vec<u8>>, Self::Error> {
+            Ok(self
+                .artifacts
+                .lock()
+                .unwrap()
+                .get(&key.to_string())
+                .cloned())
+        }
+
+        fn put(&self, key: Hash, artifact: &[u8]) -> Result<(), Self::Error> {
+            self.artifacts
+                .lock()
+                .unwrap()
+                .insert(key.to_string(), artifact.to_vec());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn download_fills_the_local_cache() {
+        let dirname = format!("wasmer-cache-remote-{}", std::process::id());
+        let path = std::env::temp_dir().join(dirname);
+        let _ = fs::remove_dir_all(&path);
+        let local = FileSystemCache::new(&path).unwrap();
+        let remote = MemoryRemote::default();
+        let key = Hash::new([7; 32]);
+        remote.put(key, b"artifact").unwrap();
+        let cache = TieredCache::new(local, remote);
+
+        assert!(!cache.download(Hash::new([8; 32])).unwrap());
+        assert!(cache.download(key).unwrap());
+        assert_eq!(fs::read(cache.local().path_of(key)).unwrap(), b"artifact");
+
+        fs::remove_dir_all(&path).unwrap();
+    }
+}
@@ -1,7 +1,9 @@
 //! A convenient little abstraction for building up C expressions and generating
 //! simple C code.
 
+pub mod object_file_externs;
 pub mod object_file_header;
+pub mod volumes;
 
 /// An identifier in C.
 pub type CIdent = String;
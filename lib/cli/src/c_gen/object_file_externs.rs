@@ -0,0 +1,109 @@
+//! Generate the Rust declarations for the object file produced by the
+//! ObjectFile engine, the counterpart of the
+//! [C header](super::object_file_header) for the Rust applications.
+
+use wasmer_compiler::{Symbol, SymbolRegistry};
+use wasmer_vm::ModuleInfo;
+
+/// Generate a Rust source file declaring the symbols of the object file, and
+/// a `serialized_module` function returning the bytes to pass to
+/// `Module::deserialize`.
+///
+/// The file is meant to be `include!`d by the application linking the
+/// object file.
+pub fn generate_rust_externs(
+    module_info: &ModuleInfo,
+    symbol_registry: &dyn SymbolRegistry,
+    metadata_length: usize,
+) -> String {
+    let local_functions = module_info
+        .functions
+        .keys()
+        .filter_map(|f_index| module_info.local_func_index(f_index))
+        .map(|index| symbol_registry.symbol_to_name(Symbol::LocalFunction(index)))
+        .collect::<Vec<_>>();
+    let function_trampolines = module_info
+        .signatures
+        .keys()
+        .map(|index| symbol_registry.symbol_to_name(Symbol::FunctionCallTrampoline(index)))
+        .collect::<Vec<_>>();
+    let dynamic_function_trampolines = module_info
+        .functions
+        .keys()
+        .take(module_info.num_imported_functions)
+        .map(|index| symbol_registry.symbol_to_name(Symbol::DynamicFunctionTrampoline(index)))
+        .collect::<Vec<_>>();
+
+    let mut out = String::new();
+    out.push_str(
+        "// Generated by `wasmer create-obj`: the symbols of the object file, and how\n\
+         // to load it with the ObjectFile engine.\n\n",
+    );
+    out.push_str(&format!(
+        "/// The length of the module metadata.\n\
+         pub const MODULE_BYTES_LEN: usize = {};\n\n",
+        metadata_length
+    ));
+
+    out.push_str("#[allow(improper_ctypes, non_upper_case_globals)]\nextern \"C\" {\n");
+    out.push_str(&format!(
+        "    static WASMER_METADATA: [u8; {}];\n",
+        metadata_length
+    ));
+    out.push_str(
+        "\n    // Compiled Wasm functions ordered by function index: the order they\n    \
+         // appeared in in the Wasm module.\n",
+    );
+    for name in &local_functions {
+        out.push_str(&format!("    fn {}();\n", name));
+    }
+    out.push_str("\n    // Trampolines ordered by signature.\n");
+    for name in &function_trampolines {
+        out.push_str(&format!("    fn {}();\n", name));
+    }
+    out.push_str("\n    // Dynamic trampolines of the imported functions.\n");
+    for name in &dynamic_function_trampolines {
+        out.push_str(&format!("    fn {}();\n", name));
+    }
+    out.push_str("}\n\n");
+
+    out.push_str(
+        "/// The serialized module to pass to `Module::deserialize`, with a store\n\
+         /// using the ObjectFile engine.\n\
+         pub fn serialized_module() -> Vec<u8> {\n",
+    );
+    for (name, symbols) in &[
+        ("function_pointers", &local_functions),
+        ("function_trampolines", &function_trampolines),
+        (
+            "dynamic_function_trampoline_pointers",
+            &dynamic_function_trampolines,
+        ),
+    ] {
+        out.push_str(&format!(
+            "    let {}: [unsafe extern \"C\" fn(); {}] = [{}];\n",
+            name,
+            symbols.len(),
+            symbols.join(", ")
+        ));
+    }
+    out.push_str(
+        r#"
+    let mut buffer = Vec::new();
+    buffer.extend_from_slice(unsafe { &WASMER_METADATA[..] });
+    for pointers in &[
+        &function_pointers[..],
+        &function_trampolines[..],
+        &dynamic_function_trampoline_pointers[..],
+    ] {
+        buffer.extend_from_slice(&pointers.len().to_ne_bytes());
+        for pointer in pointers.iter() {
+            buffer.extend_from_slice(&(*pointer as usize).to_ne_bytes());
+        }
+    }
+    buffer
+}
+"#,
+    );
+    out
+}
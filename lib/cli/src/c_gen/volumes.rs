@@ -0,0 +1,204 @@
+//! Generate a header file embedding directory trees in the executables
+//! produced by `wasmer create-exe`.
+//!
+//! The executable unpacks the volumes into a temporary directory when it
+//! starts, and maps them into the WASI filesystem of the guest.
+
+use super::{generate_c, CStatement, CType};
+
+/// An entry of a [`Volume`], with a path relative to its root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VolumeEntry {
+    /// A directory.
+    Directory(String),
+    /// A file and its contents.
+    File(String, Vec<u8>),
+}
+
+/// A directory tree, mapped to `alias` in the guest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Volume {
+    /// The path of the volume in the guest.
+    pub alias: String,
+    /// The entries of the volume, each directory coming before its
+    /// contents.
+    pub entries: Vec<VolumeEntry>,
+}
+
+/// Escape a string into a C string literal.
+fn c_string_literal(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for byte in s.bytes() {
+        match byte {
+            b'"' => out.push_str("\\\""),
+            b'\\' => out.push_str("\\\\"),
+            0x20..=0x7e => out.push(byte as char),
+            // Octal escapes are at most 3 digits long, unlike the hexadecimal
+            // ones which would swallow the following characters.
+            _ => out.push_str(&format!("\\{:03o}", byte)),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Format bytes as a C array literal.
+fn c_byte_array(bytes: &[u8]) -> String {
+    let mut out = String::from("{\n");
+    for chunk in bytes.chunks(16) {
+        out.push('\t');
+        for byte in chunk {
+            out.push_str(&format!("0x{:02x},", byte));
+        }
+        out.push('\n');
+    }
+    out.push('}');
+    out
+}
+
+/// Generate the header file for the given volumes.
+///
+/// `WASMER_VOLUMES` is only defined if there is at least one volume, so
+/// that the executables without any volume don't unpack anything.
+pub fn generate_volumes_header(volumes: &[Volume]) -> String {
+    let mut c_statements = vec![];
+    if volumes.is_empty() {
+        c_statements.push(CStatement::LiteralConstant {
+            value: "// No volume is embedded in this executable.\n".to_string(),
+        });
+        return generate_c(&c_statements);
+    }
+    c_statements.push(CStatement::LiteralConstant {
+        value: "#define WASMER_VOLUMES\n\n".to_string(),
+    });
+
+    let mut entry_statements = vec![];
+    let mut file_index = 0;
+    for (volume_index, volume) in volumes.iter().enumerate() {
+        for entry in &volume.entries {
+            let (path, data, size, is_dir) = match entry {
+                VolumeEntry::Directory(path) => (path, "NULL".to_string(), 0, 1),
+                VolumeEntry::File(path, contents) if contents.is_empty() => {
+                    (path, "NULL".to_string(), 0, 0)
+                }
+                VolumeEntry::File(path, contents) => {
+                    let name = format!("wasmer_volume_file_{}", file_index);
+                    file_index += 1;
+                    c_statements.push(CStatement::Declaration {
+                        name: name.clone(),
+                        is_extern: false,
+                        is_const: true,
+                        ctype: CType::Array {
+                            inner: Box::new(CType::U8),
+                        },
+                        definition: Some(Box::new(CStatement::LiteralConstant {
+                            value: c_byte_array(contents),
+                        })),
+                    });
+                    (path, name, contents.len(), 0)
+                }
+            };
+            entry_statements.push(CStatement::LiteralConstant {
+                value: format!(
+                    "{{ {}, {}, {}, {}, {} }}",
+                    volume_index,
+                    c_string_literal(path),
+                    is_dir,
+                    data,
+                    size
+                ),
+            });
+        }
+    }
+
+    let entry_count = entry_statements.len();
+    // C doesn't allow empty arrays, so the volumes without any entry get a
+    // placeholder, which isn't counted.
+    if entry_statements.is_empty() {
+        entry_statements.push(CStatement::LiteralConstant {
+            value: "{ 0, NULL, 0, NULL, 0 }".to_string(),
+        });
+    }
+
+    c_statements.push(CStatement::Declaration {
+        name: "wasmer_volume_aliases".to_string(),
+        is_extern: false,
+        is_const: false,
+        ctype: CType::Array {
+            inner: Box::new(CType::PointerTo {
+                is_const: true,
+                inner: Box::new(CType::I8),
+            }),
+        },
+        definition: Some(Box::new(CStatement::LiteralArray {
+            items: volumes
+                .iter()
+                .map(|volume| CStatement::LiteralConstant {
+                    value: c_string_literal(&volume.alias),
+                })
+                .collect(),
+        })),
+    });
+    c_statements.push(CStatement::Declaration {
+        name: "wasmer_volume_count".to_string(),
+        is_extern: false,
+        is_const: true,
+        ctype: CType::ISize,
+        definition: Some(Box::new(CStatement::LiteralConstant {
+            value: volumes.len().to_string(),
+        })),
+    });
+    c_statements.push(CStatement::Declaration {
+        name: "wasmer_volume_entries".to_string(),
+        is_extern: false,
+        is_const: true,
+        ctype: CType::Array {
+            inner: Box::new(CType::TypeDef("wasmer_volume_entry_t".to_string())),
+        },
+        definition: Some(Box::new(CStatement::LiteralArray {
+            items: entry_statements,
+        })),
+    });
+    c_statements.push(CStatement::Declaration {
+        name: "wasmer_volume_entry_count".to_string(),
+        is_extern: false,
+        is_const: true,
+        ctype: CType::ISize,
+        definition: Some(Box::new(CStatement::LiteralConstant {
+            value: entry_count.to_string(),
+        })),
+    });
+
+    generate_c(&c_statements)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn generate_volumes() {
+        assert_eq!(
+            c_string_literal("a \"b\"\\c\n\u{e9}"),
+            "\"a \\\"b\\\"\\\\c\\012\\303\\251\""
+        );
+        assert!(!generate_volumes_header(&[]).contains("WASMER_VOLUMES"));
+
+        let header = generate_volumes_header(&[Volume {
+            alias: "assets".to_string(),
+            entries: vec![
+                VolumeEntry::Directory("img".to_string()),
+                VolumeEntry::File("img/a.txt".to_string(), b"hi".to_vec()),
+                VolumeEntry::File("empty".to_string(), vec![]),
+            ],
+        }]);
+        assert!(header.contains("#define WASMER_VOLUMES\n"));
+        assert!(header.contains("const unsigned char wasmer_volume_file_0[] = {\n\t0x68,0x69,\n};"));
+        assert!(header.contains("const char* wasmer_volume_aliases[] = {\n\t\"assets\",\n};"));
+        assert!(header.contains("const size_t wasmer_volume_entry_count = 3;"));
+        assert!(header.contains("{ 0, \"img\", 1, NULL, 0 },"));
+        assert!(header.contains("{ 0, \"img/a.txt\", 0, wasmer_volume_file_0, 2 },"));
+        assert!(header.contains("{ 0, \"empty\", 0, NULL, 0 },"));
+    }
+}
@@ -2,11 +2,13 @@
 
 #[cfg(feature = "compiler")]
 use crate::commands::Compile;
-#[cfg(all(feature = "object-file", feature = "compiler"))]
-use crate::commands::CreateExe;
+#[cfg(all(feature = "compiler", feature = "wat"))]
+use crate::commands::Repl;
 #[cfg(feature = "wast")]
 use crate::commands::Wast;
-use crate::commands::{Cache, Config, Inspect, Run, SelfUpdate, Validate};
+use crate::commands::{Cache, Config, Inspect, Run, SelfUpdate, Strip, Validate};
+#[cfg(all(feature = "object-file", feature = "compiler"))]
+use crate::commands::{CreateExe, CreateObj};
 use crate::error::PrettyError;
 use anyhow::Result;
 
@@ -49,6 +51,12 @@ enum WasmerCLIOptions {
     #[clap(name = "create-exe")]
     CreateExe(CreateExe),
 
+    /// Compile a WebAssembly binary into an object file and a header, to be
+    /// linked into an application
+    #[cfg(all(feature = "object-file", feature = "compiler"))]
+    #[clap(name = "create-obj")]
+    CreateObj(CreateObj),
+
     /// Get various configuration information needed
     /// to compile programs which use Wasmer
     #[clap(name = "config")]
@@ -62,6 +70,16 @@ enum WasmerCLIOptions {
     #[clap(name = "inspect")]
     Inspect(Inspect),
 
+    /// Remove the custom sections, like the names and the debug info, from
+    /// a WebAssembly file or a serialized module
+    #[clap(name = "strip")]
+    Strip(Strip),
+
+    /// Start an interactive prompt to define and call WAT functions
+    #[cfg(all(feature = "compiler", feature = "wat"))]
+    #[clap(name = "repl")]
+    Repl(Repl),
+
     /// Run spec testsuite
     #[cfg(feature = "wast")]
     #[clap(name = "wast")]
@@ -79,8 +97,13 @@ impl WasmerCLIOptions {
             Self::Compile(compile) => compile.execute(),
             #[cfg(all(feature = "object-file", feature = "compiler"))]
             Self::CreateExe(create_exe) => create_exe.execute(),
+            #[cfg(all(feature = "object-file", feature = "compiler"))]
+            Self::CreateObj(create_obj) => create_obj.execute(),
             Self::Config(config) => config.execute(),
             Self::Inspect(inspect) => inspect.execute(),
+            Self::Strip(strip) => strip.execute(),
+            #[cfg(all(feature = "compiler", feature = "wat"))]
+            Self::Repl(repl) => repl.execute(),
             #[cfg(feature = "wast")]
             Self::Wast(wast) => wast.execute(),
         }
@@ -100,8 +123,10 @@ pub fn wasmer_main() {
     let args = std::env::args().collect::<Vec<_>>();
     let command = args.get(1);
     let options = match command.unwrap_or(&"".to_string()).as_ref() {
-        "cache" | "compile" | "config" | "create-exe" | "help" | "inspect" | "run"
-        | "self-update" | "validate" | "wast" => WasmerCLIOptions::parse(),
+        "cache" | "compile" | "config" | "create-exe" | "create-obj" | "help" | "inspect"
+        | "repl" | "run" | "self-update" | "strip" | "validate" | "wast" => {
+            WasmerCLIOptions::parse()
+        }
         _ => {
             WasmerCLIOptions::try_parse_from(args.iter()).unwrap_or_else(|e| {
                 match e.kind {
@@ -5,9 +5,14 @@ mod compile;
 mod config;
 #[cfg(all(feature = "object-file", feature = "compiler"))]
 mod create_exe;
+#[cfg(all(feature = "object-file", feature = "compiler"))]
+mod create_obj;
 mod inspect;
+#[cfg(all(feature = "compiler", feature = "wat"))]
+mod repl;
 mod run;
 mod self_update;
+mod strip;
 mod validate;
 #[cfg(feature = "wast")]
 mod wast;
@@ -16,6 +21,10 @@ mod wast;
 pub use compile::*;
 #[cfg(all(feature = "object-file", feature = "compiler"))]
 pub use create_exe::*;
+#[cfg(all(feature = "object-file", feature = "compiler"))]
+pub use create_obj::*;
+#[cfg(all(feature = "compiler", feature = "wat"))]
+pub use repl::*;
 #[cfg(feature = "wast")]
 pub use wast::*;
-pub use {cache::*, config::*, inspect::*, run::*, self_update::*, validate::*};
+pub use {cache::*, config::*, inspect::*, run::*, self_update::*, strip::*, validate::*};
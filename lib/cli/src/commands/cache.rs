@@ -1,7 +1,12 @@
 use crate::common::get_cache_dir;
-use anyhow::{Context, Result};
+use crate::limits::parse_size;
+use anyhow::{bail, Context, Result};
+use bytesize::ByteSize;
 use clap::Clap;
+use serde::{Deserialize, Serialize};
 use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 #[derive(Debug, Clap)]
 /// The options for the `wasmer cache` subcommand
@@ -13,6 +18,32 @@ pub enum Cache {
     /// Display the location of the cache
     #[clap(name = "dir")]
     Dir,
+
+    /// Display the number of cached artifacts, their size, and how often
+    /// the cache was hit
+    #[clap(name = "stats")]
+    Stats {
+        /// Also list the cached artifacts, most recently used first
+        #[clap(long = "entries")]
+        entries: bool,
+    },
+
+    /// Remove the least recently used artifacts until the cache fits in the
+    /// given size
+    #[clap(name = "prune")]
+    Prune {
+        /// The maximum size of the cache, like `500MiB`
+        #[clap(long = "max-size", parse(try_from_str = parse_size))]
+        max_size: u64,
+    },
+
+    /// Remove the cached artifacts with the given hash, or hash prefix
+    #[clap(name = "rm")]
+    Rm {
+        /// The hash of the artifacts, as listed by `wasmer cache stats --entries`
+        #[clap(name = "HASH")]
+        hash: String,
+    },
 }
 
 impl Cache {
@@ -25,6 +56,18 @@ impl Cache {
             Cache::Dir => {
                 self.dir()?;
             }
+            Cache::Stats { entries } => {
+                self.stats(*entries)
+                    .context("failed to read the wasmer cache.")?;
+            }
+            Cache::Prune { max_size } => {
+                self.prune(*max_size)
+                    .context("failed to prune the wasmer cache.")?;
+            }
+            Cache::Rm { hash } => {
+                self.rm(hash)
+                    .context("failed to remove from the wasmer cache.")?;
+            }
         }
         Ok(())
     }
@@ -41,4 +84,197 @@ impl Cache {
         println!("{}", get_cache_dir().to_string_lossy());
         Ok(())
     }
+    fn stats(&self, list_entries: bool) -> Result<()> {
+        let cache_dir = get_cache_dir();
+        let entries = cache_entries(&cache_dir)?;
+        let counters = CacheCounters::load(&cache_dir);
+        let total_size: u64 = entries.iter().map(|entry| entry.size).sum();
+        println!("Directory: {}", cache_dir.display());
+        println!("Entries: {}", entries.len());
+        println!("Size: {}", ByteSize(total_size));
+        println!("Hits: {}", counters.hits);
+        println!("Misses: {}", counters.misses);
+        if list_entries {
+            println!();
+            for entry in &entries {
+                println!(
+                    "{}  {:>10}  {}",
+                    entry.hash,
+                    ByteSize(entry.size),
+                    entry.kind
+                );
+            }
+        }
+        Ok(())
+    }
+    fn prune(&self, max_size: u64) -> Result<()> {
+        let mut size = 0;
+        let mut removed = 0;
+        let mut freed = 0;
+        for entry in cache_entries(&get_cache_dir())? {
+            if size + entry.size <= max_size {
+                size += entry.size;
+                continue;
+            }
+            fs::remove_file(&entry.path)?;
+            removed += 1;
+            freed += entry.size;
+        }
+        eprintln!(
+            "Removed {} cached artifacts ({}), {} left.",
+            removed,
+            ByteSize(freed),
+            ByteSize(size)
+        );
+        Ok(())
+    }
+    fn rm(&self, hash: &str) -> Result<()> {
+        let hash = hash.to_lowercase();
+        let matching = cache_entries(&get_cache_dir())?
+            .into_iter()
+            .filter(|entry| entry.hash.starts_with(&hash))
+            .collect::<Vec<_>>();
+        if matching.is_empty() {
+            bail!("no cached artifact has the hash `{}`", hash);
+        }
+        if matching.iter().any(|entry| entry.hash != matching[0].hash) {
+            bail!(
+                "the hash prefix `{}` is ambiguous, it matches several artifacts",
+                hash
+            );
+        }
+        for entry in &matching {
+            fs::remove_file(&entry.path)?;
+        }
+        eprintln!(
+            "Removed {} cached artifacts with the hash `{}`.",
+            matching.len(),
+            matching[0].hash
+        );
+        Ok(())
+    }
+}
+
+/// An artifact stored in the cache.
+struct CacheEntry {
+    path: PathBuf,
+    hash: String,
+    /// The directory of the entry in the cache, like `cranelift`.
+    kind: String,
+    size: u64,
+    last_used: SystemTime,
+}
+
+/// The artifacts in the cache, in all its subdirectories, the most recently
+/// used first.
+fn cache_entries(cache_dir: &Path) -> Result<Vec<CacheEntry>> {
+    fn visit(cache_dir: &Path, dir: &Path, entries: &mut Vec<CacheEntry>) -> Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let metadata = entry.metadata()?;
+            if metadata.is_dir() {
+                visit(cache_dir, &path, entries)?;
+                continue;
+            }
+            // Like in `wasmer_cache::FileSystemCache`, the artifacts are
+            // named after the hexadecimal representation of their hash.
+            let hash = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(stem) if stem.len() == 64 && stem.chars().all(|c| c.is_ascii_hexdigit()) => {
+                    stem.to_lowercase()
+                }
+                _ => continue,
+            };
+            let kind = dir
+                .strip_prefix(cache_dir)
+                .unwrap_or(dir)
+                .to_string_lossy()
+                .into_owned();
+            entries.push(CacheEntry {
+                hash,
+                kind,
+                size: metadata.len(),
+                last_used: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                path,
+            });
+        }
+        Ok(())
+    }
+
+    let mut entries = Vec::new();
+    if cache_dir.exists() {
+        visit(cache_dir, cache_dir, &mut entries)?;
+    }
+    entries.sort_by(|a, b| b.last_used.cmp(&a.last_used));
+    Ok(entries)
+}
+
+/// The number of times the cache was hit and missed, persisted in the cache
+/// directory.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheCounters {
+    /// The number of modules loaded from the cache.
+    pub hits: u64,
+    /// The number of modules compiled because they weren't in the cache.
+    pub misses: u64,
+}
+
+impl CacheCounters {
+    const FILE_NAME: &'static str = "counters.json";
+
+    /// Load the counters, which are zero if they were never recorded.
+    pub fn load(cache_dir: &Path) -> Self {
+        fs::read(cache_dir.join(Self::FILE_NAME))
+            .ok()
+            .and_then(|contents| serde_json::from_slice(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Count a hit or a miss of the cache.
+    ///
+    /// The counters are only informative: failing to update them is ignored.
+    pub fn record(cache_dir: &Path, hit: bool) {
+        let mut counters = Self::load(cache_dir);
+        if hit {
+            counters.hits += 1;
+        } else {
+            counters.misses += 1;
+        }
+        if let Ok(contents) = serde_json::to_vec(&counters) {
+            let _ = fs::create_dir_all(cache_dir);
+            let _ = fs::write(cache_dir.join(Self::FILE_NAME), contents);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_entries_and_counters() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let compiler_dir = cache_dir.path().join("cranelift");
+        fs::create_dir_all(&compiler_dir).unwrap();
+        fs::write(
+            compiler_dir.join(format!("{}.so", "ab".repeat(32))),
+            [0; 10],
+        )
+        .unwrap();
+        fs::write(compiler_dir.join("README"), [0; 10]).unwrap();
+
+        CacheCounters::record(cache_dir.path(), true);
+        CacheCounters::record(cache_dir.path(), false);
+        CacheCounters::record(cache_dir.path(), false);
+        assert_eq!(
+            CacheCounters::load(cache_dir.path()),
+            CacheCounters { hits: 1, misses: 2 }
+        );
+
+        let entries = cache_entries(cache_dir.path()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].hash, "ab".repeat(32));
+        assert_eq!(entries[0].kind, "cranelift");
+        assert_eq!(entries[0].size, 10);
+    }
 }
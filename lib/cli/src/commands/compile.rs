@@ -1,32 +1,58 @@
+use crate::output::{OutputOptions, Report};
 use crate::store::{EngineType, StoreOptions};
 use crate::warning;
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use clap::Clap;
 use std::path::PathBuf;
+use std::str::FromStr;
 use wasmer::*;
 
+mod emit;
+mod presets;
+
+pub use emit::Emitter;
+use emit::{emit, EmitKind};
+use presets::{check_native_linker, print_target_presets, TargetPreset};
+
 #[derive(Debug, Clap)]
 /// The options for the `wasmer compile` subcommand
 pub struct Compile {
     /// Input file
     #[clap(name = "FILE", parse(from_os_str))]
-    path: PathBuf,
+    path: Option<PathBuf>,
 
     /// Output file
     #[clap(name = "OUTPUT PATH", short = 'o', parse(from_os_str))]
-    output: PathBuf,
+    output: Option<PathBuf>,
+
+    /// Write the code generated for each function: `obj`, `asm`,
+    /// `llvm-ir` with LLVM, or `clif` with Cranelift
+    #[clap(long = "emit", name = "KIND", multiple = true, number_of_values = 1)]
+    emit: Vec<EmitKind>,
+
+    /// The directory where `--emit` writes its files, `<FILE stem>.emit` by default
+    #[clap(long = "emit-dir", parse(from_os_str))]
+    emit_dir: Option<PathBuf>,
 
     /// Output path for generated header file
     #[clap(name = "HEADER PATH", long = "header", parse(from_os_str))]
     header_path: Option<PathBuf>,
 
-    /// Compilation Target triple
+    /// Compilation target: a triple, or one of the presets listed by
+    /// `--list-targets`, which also select the CPU features and the engine
     #[clap(long = "target")]
-    target_triple: Option<Triple>,
+    target: Option<String>,
+
+    /// List the target presets
+    #[clap(long = "list-targets")]
+    list_targets: bool,
 
     #[clap(flatten)]
     store: StoreOptions,
 
+    #[clap(flatten)]
+    output_options: OutputOptions,
+
     #[clap(short = 'm', multiple = true)]
     cpu_features: Vec<CpuFeature>,
 }
@@ -34,8 +60,63 @@ pub struct Compile {
 impl Compile {
     /// Runs logic for the `compile` subcommand
     pub fn execute(&self) -> Result<()> {
-        self.inner_execute()
-            .context(format!("failed to compile `{}`", self.path.display()))
+        if self.list_targets {
+            print_target_presets();
+            return Ok(());
+        }
+        let path = match &self.path {
+            Some(path) => path,
+            None => bail!("the input file is required"),
+        };
+        if self.output.is_none() && self.emit.is_empty() {
+            bail!("the output path is required, pass it with `-o`");
+        }
+        let output = self.output.as_ref();
+        let report = self.output_options.report("compile");
+        if let Some(report) = &report {
+            report.set("file", path);
+            report.set("output", output);
+        }
+        let result = self
+            .inner_execute(path, output, report.as_ref())
+            .context(format!("failed to compile `{}`", path.display()));
+        if let Some(report) = &report {
+            report.finish(&result);
+        }
+        result
+    }
+
+    /// The target and the engine recommended for it, if it's a preset.
+    fn get_target(&self) -> Result<(Target, Option<EngineType>)> {
+        let name = match &self.target {
+            Some(name) => name,
+            None => return Ok((Target::default(), None)),
+        };
+        if let Some(preset) = TargetPreset::find(name) {
+            if !self.cpu_features.is_empty() {
+                bail!(
+                    "the `{}` preset already selects the CPU features, pass a triple to use `-m`",
+                    name
+                );
+            }
+            return Ok((preset.target()?, Some(preset.engine)));
+        }
+        let triple = Triple::from_str(name).map_err(|e| {
+            anyhow::anyhow!(
+                "`{}` is neither a target triple ({}) nor a preset, see `--list-targets`",
+                name,
+                e
+            )
+        })?;
+        let mut features = self
+            .cpu_features
+            .clone()
+            .into_iter()
+            .fold(CpuFeature::set(), |a, b| a | b);
+        // Cranelift requires SSE2, so we have this "hack" for now to facilitate
+        // usage
+        features |= CpuFeature::SSE2;
+        Ok((Target::new(triple, features), None))
     }
 
     pub(crate) fn get_recommend_extension(
@@ -58,31 +139,81 @@ impl Compile {
         })
     }
 
-    fn inner_execute(&self) -> Result<()> {
-        let target = self
-            .target_triple
-            .as_ref()
-            .map(|target_triple| {
-                let mut features = self
-                    .cpu_features
-                    .clone()
-                    .into_iter()
-                    .fold(CpuFeature::set(), |a, b| a | b);
-                // Cranelift requires SSE2, so we have this "hack" for now to facilitate
-                // usage
-                features |= CpuFeature::SSE2;
-                Target::new(target_triple.clone(), features)
-            })
-            .unwrap_or_default();
-        let (store, engine_type, compiler_type) =
-            self.store.get_store_for_target(target.clone())?;
-        let output_filename = self
-            .output
+    fn inner_execute(
+        &self,
+        path: &PathBuf,
+        output: Option<&PathBuf>,
+        report: Option<&Report>,
+    ) -> Result<()> {
+        let (target, preset_engine) = self.get_target()?;
+        // The engine of the preset, unless it isn't compiled in.
+        let engine_type = match preset_engine {
+            Some(EngineType::JIT) if cfg!(feature = "jit") => {
+                self.store.get_engine_or(EngineType::JIT)?
+            }
+            Some(EngineType::Native) if cfg!(feature = "native") => {
+                self.store.get_engine_or(EngineType::Native)?
+            }
+            Some(EngineType::ObjectFile) if cfg!(feature = "object-file") => {
+                self.store.get_engine_or(EngineType::ObjectFile)?
+            }
+            _ => self.store.get_engine()?,
+        };
+        if engine_type == EngineType::Native && output.is_some() {
+            check_native_linker(&target)?;
+        }
+        let (store, compiler_type) = self
+            .store
+            .get_store_for_target_with_engine(target.clone(), engine_type)?;
+        if let Some(report) = report {
+            report.set("engine", engine_type.to_string());
+            report.set("compiler", compiler_type.to_string());
+            report.set("target", target.triple().to_string());
+        } else {
+            println!("Engine: {}", engine_type.to_string());
+            println!("Compiler: {}", compiler_type.to_string());
+            match preset_engine {
+                Some(_) => println!(
+                    "Target: {} ({})",
+                    target.triple(),
+                    self.target.as_deref().unwrap_or_default()
+                ),
+                None => println!("Target: {}", target.triple()),
+            }
+        }
+
+        if !self.emit.is_empty() {
+            let emit_dir = self.emit_dir.clone().unwrap_or_else(|| {
+                let mut emit_dir = PathBuf::from(
+                    path.file_stem()
+                        .map(|fs| fs.to_string_lossy().to_string())
+                        .unwrap_or_else(|| "wasm_out".to_string()),
+                );
+                emit_dir.set_extension("emit");
+                emit_dir
+            });
+            let wasm = std::fs::read(path)?;
+            #[cfg(feature = "wat")]
+            let wasm = wat2wasm(&wasm)?;
+            emit(&self.store, &target, &wasm, &self.emit, &emit_dir)?;
+            if let Some(report) = report {
+                report.set("emit_dir", &emit_dir);
+            }
+            eprintln!(
+                "✔ Generated code written successfully to `{}`.",
+                emit_dir.display()
+            );
+        }
+        let output = match output {
+            Some(output) => output,
+            None => return Ok(()),
+        };
+        let output_filename = output
             .file_stem()
             .map(|osstr| osstr.to_string_lossy().to_string())
             .unwrap_or_default();
         let recommended_extension = Self::get_recommend_extension(&engine_type, target.triple())?;
-        match self.output.extension() {
+        match output.extension() {
             Some(ext) => {
                 if ext != recommended_extension {
                     warning!("the output file has a wrong extension. We recommend using `{}.{}` for the chosen target", &output_filename, &recommended_extension)
@@ -92,16 +223,13 @@ impl Compile {
                 warning!("the output file has no extension. We recommend using `{}.{}` for the chosen target", &output_filename, &recommended_extension)
             }
         }
-        println!("Engine: {}", engine_type.to_string());
-        println!("Compiler: {}", compiler_type.to_string());
-        println!("Target: {}", target.triple());
 
-        let module = Module::from_file(&store, &self.path)?;
-        let _ = module.serialize_to_file(&self.output)?;
-        eprintln!(
-            "✔ File compiled successfully to `{}`.",
-            self.output.display(),
-        );
+        let module = Module::from_file(&store, path)?;
+        let _ = module.serialize_to_file(output)?;
+        eprintln!("✔ File compiled successfully to `{}`.", output.display(),);
+        if let Some(report) = report {
+            report.set("size", std::fs::metadata(output)?.len());
+        }
 
         #[cfg(feature = "object-file")]
         if engine_type == EngineType::ObjectFile {
@@ -118,8 +246,7 @@ impl Compile {
 
             let header_path = self.header_path.as_ref().cloned().unwrap_or_else(|| {
                 let mut hp = PathBuf::from(
-                    self.path
-                        .file_stem()
+                    path.file_stem()
                         .map(|fs| fs.to_string_lossy().to_string())
                         .unwrap_or_else(|| "wasm_out".to_string()),
                 );
@@ -135,6 +262,9 @@ impl Compile {
 
             use std::io::Write;
             header.write_all(header_file_src.as_bytes())?;
+            if let Some(report) = report {
+                report.set("header", &header_path);
+            }
             eprintln!(
                 "✔ Header file generated successfully at `{}`.",
                 header_path.display(),
@@ -0,0 +1,364 @@
+//! Writing the code generated by the compiler for each function of a
+//! module, with `wasmer compile --emit`.
+
+use crate::store::{CompilerType, StoreOptions};
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use wasmer::{Target, Tunables};
+use wasmer_compiler::{CompileError, CompileModuleInfo, ModuleEnvironment, Symbol, SymbolRegistry};
+use wasmer_types::entity::{EntityRef, PrimaryMap};
+use wasmer_types::LocalFunctionIndex;
+use wasmer_vm::ModuleInfo;
+
+/// The code written by `--emit`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmitKind {
+    /// An object file with a symbol for each function
+    Obj,
+    /// The disassembly of each function
+    Asm,
+    /// The optimized LLVM IR of each function
+    LlvmIr,
+    /// The Cranelift IR of each function
+    Clif,
+}
+
+impl FromStr for EmitKind {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "obj" => Ok(Self::Obj),
+            "asm" => Ok(Self::Asm),
+            "llvm-ir" => Ok(Self::LlvmIr),
+            "clif" => Ok(Self::Clif),
+            kind => bail!(
+                "unknown kind `{}`, expected one of: obj, asm, llvm-ir, clif",
+                kind
+            ),
+        }
+    }
+}
+
+impl EmitKind {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Obj => "obj",
+            Self::Asm => "asm",
+            Self::LlvmIr => "llvm-ir",
+            Self::Clif => "clif",
+        }
+    }
+
+    /// The only compiler generating this kind of code, if it's specific to
+    /// one.
+    fn compiler(self) -> Option<CompilerType> {
+        match self {
+            Self::LlvmIr => Some(CompilerType::LLVM),
+            Self::Clif => Some(CompilerType::Cranelift),
+            Self::Obj | Self::Asm => None,
+        }
+    }
+}
+
+/// Writes the code generated by the compiler in a directory, with a file
+/// per function named after it.
+#[derive(Debug)]
+pub struct Emitter {
+    dir: PathBuf,
+    kinds: Vec<EmitKind>,
+    /// The name of each function, used for its files and its symbol.
+    names: PrimaryMap<LocalFunctionIndex, String>,
+    /// The first error writing a file from the compiler callbacks.
+    error: Mutex<Option<anyhow::Error>>,
+}
+
+impl Emitter {
+    fn new(dir: &Path, kinds: &[EmitKind], module: &ModuleInfo) -> Self {
+        let names = (0..module.functions.len() - module.num_imported_functions)
+            .map(|index| function_name(module, LocalFunctionIndex::new(index)))
+            .collect();
+        Self {
+            dir: dir.to_path_buf(),
+            kinds: kinds.to_vec(),
+            names,
+            error: Mutex::new(None),
+        }
+    }
+
+    fn emits(&self, kind: EmitKind) -> bool {
+        self.kinds.contains(&kind)
+    }
+
+    /// Write a file of a function, keeping the error for later since the
+    /// compiler callbacks can't fail.
+    fn write(&self, function: LocalFunctionIndex, extension: &str, contents: &[u8]) {
+        let path = self
+            .dir
+            .join(format!("{}.{}", self.names[function], extension));
+        if let Err(error) = fs::write(&path, contents) {
+            let mut first_error = self.error.lock().unwrap();
+            if first_error.is_none() {
+                *first_error = Some(
+                    anyhow::Error::new(error)
+                        .context(format!("failed to write `{}`", path.display())),
+                );
+            }
+        }
+    }
+
+    fn take_error(&self) -> Result<()> {
+        match self.error.lock().unwrap().take() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(feature = "cranelift")]
+impl wasmer_compiler_cranelift::CraneliftCallbacks for Emitter {
+    fn clif(&self, function: LocalFunctionIndex, clif: &str) {
+        if self.emits(EmitKind::Clif) {
+            self.write(function, "clif", clif.as_bytes());
+        }
+    }
+}
+
+#[cfg(feature = "llvm")]
+impl wasmer_compiler_llvm::LLVMCallbacks for Emitter {
+    fn preopt_ir(
+        &self,
+        _kind: &wasmer_compiler_llvm::CompiledKind,
+        _module: &wasmer_compiler_llvm::InkwellModule,
+    ) {
+    }
+
+    fn postopt_ir(
+        &self,
+        kind: &wasmer_compiler_llvm::CompiledKind,
+        module: &wasmer_compiler_llvm::InkwellModule,
+    ) {
+        if let wasmer_compiler_llvm::CompiledKind::Local(function) = kind {
+            if self.emits(EmitKind::LlvmIr) {
+                self.write(*function, "ll", module.print_to_string().to_bytes());
+            }
+        }
+    }
+
+    fn obj_memory_buffer(
+        &self,
+        _kind: &wasmer_compiler_llvm::CompiledKind,
+        _memory_buffer: &wasmer_compiler_llvm::InkwellMemoryBuffer,
+    ) {
+    }
+}
+
+/// Names the symbols of the object file after the functions.
+impl SymbolRegistry for Emitter {
+    fn symbol_to_name(&self, symbol: Symbol) -> String {
+        match symbol {
+            Symbol::LocalFunction(index) => self.names[index].clone(),
+            Symbol::Section(index) => format!("section_{}", index.index()),
+            Symbol::FunctionCallTrampoline(index) => {
+                format!("trampoline_call_{}", index.index())
+            }
+            Symbol::DynamicFunctionTrampoline(index) => {
+                format!("trampoline_dynamic_{}", index.index())
+            }
+        }
+    }
+
+    fn name_to_symbol(&self, name: &str) -> Option<Symbol> {
+        self.names
+            .iter()
+            .find(|(_, function_name)| *function_name == name)
+            .map(|(index, _)| Symbol::LocalFunction(index))
+    }
+}
+
+/// The name of a function in the emitted files: its index, and its name
+/// from the name section made of the characters allowed in symbols.
+fn function_name(module: &ModuleInfo, function: LocalFunctionIndex) -> String {
+    let index = module.func_index(function);
+    match module.function_names.get(&index) {
+        Some(name) => {
+            let name: String = name
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .take(64)
+                .collect();
+            format!("func{}_{}", index.index(), name)
+        }
+        None => format!("func{}", index.index()),
+    }
+}
+
+/// Compile `wasm` with the compiler of `store`, writing the code of the
+/// kinds asked for in `dir`.
+pub fn emit(
+    store: &StoreOptions,
+    target: &Target,
+    wasm: &[u8],
+    kinds: &[EmitKind],
+    dir: &Path,
+) -> Result<()> {
+    let translation = ModuleEnvironment::new()
+        .translate(wasm)
+        .map_err(CompileError::Wasm)?;
+    let emitter = Arc::new(Emitter::new(dir, kinds, &translation.module));
+    let (compiler_config, compiler_type, features, tunables) =
+        store.get_compiler_config_with_emitter(target, emitter.clone())?;
+    for kind in kinds {
+        match kind.compiler() {
+            Some(compiler) if compiler != compiler_type => bail!(
+                "`--emit {}` is only supported with the {} compiler, pass `--{}`",
+                kind.name(),
+                compiler.to_string(),
+                compiler.to_string()
+            ),
+            _ => {}
+        }
+    }
+    fs::create_dir_all(dir).with_context(|| format!("failed to create `{}`", dir.display()))?;
+
+    let compiler = compiler_config.compiler();
+    compiler.validate_module(&features, wasm)?;
+    let memory_styles = translation
+        .module
+        .memories
+        .values()
+        .map(|memory_type| tunables.memory_style(memory_type))
+        .collect();
+    let table_styles = translation
+        .module
+        .tables
+        .values()
+        .map(|table_type| tunables.table_style(table_type))
+        .collect();
+    let mut compile_info = CompileModuleInfo {
+        module: Arc::new(translation.module),
+        features,
+        compiler: compiler.fingerprint(),
+        memory_styles,
+        table_styles,
+    };
+    let compilation = compiler.compile_module(
+        target,
+        &mut compile_info,
+        translation
+            .module_translation_state
+            .as_ref()
+            .context("the module wasn't fully translated")?,
+        translation.function_body_inputs,
+    )?;
+    emitter.take_error()?;
+
+    if !emitter.emits(EmitKind::Obj) && !emitter.emits(EmitKind::Asm) {
+        return Ok(());
+    }
+    let triple = target.triple();
+    let mut object = wasmer_object::get_object_for_target(triple)?;
+    wasmer_object::emit_compilation(&mut object, compilation, &*emitter, triple)?;
+    let object = object.write().context("failed to write the object file")?;
+    let object_path = dir.join("module.o");
+    fs::write(&object_path, &object)
+        .with_context(|| format!("failed to write `{}`", object_path.display()))?;
+    if emitter.emits(EmitKind::Asm) {
+        let disassembly = disassemble(&object_path)?;
+        for (function, name) in emitter.names.iter() {
+            if let Some(asm) = disassembly.get(name) {
+                emitter.write(function, "s", asm.as_bytes());
+            }
+        }
+        emitter.take_error()?;
+        if !emitter.emits(EmitKind::Obj) {
+            fs::remove_file(&object_path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Disassemble an object file with `objdump`, or the one in the `OBJDUMP`
+/// environment variable, by symbol.
+fn disassemble(object_path: &Path) -> Result<BTreeMap<String, String>> {
+    let objdump = std::env::var("OBJDUMP").unwrap_or_else(|_| "objdump".to_string());
+    let output = Command::new(&objdump)
+        .arg("--disassemble")
+        .arg("--reloc")
+        .arg("--no-show-raw-insn")
+        .arg(object_path)
+        .output()
+        .with_context(|| {
+            format!(
+                "failed to run `{}`, `--emit asm` needs it to disassemble the code",
+                objdump
+            )
+        })?;
+    if !output.status.success() {
+        bail!(
+            "`{}` failed: {}",
+            objdump,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(split_disassembly(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Split the output of `objdump --disassemble` by symbol, from the
+/// `0000000000000000 <symbol>:` lines starting them.
+fn split_disassembly(disassembly: &str) -> BTreeMap<String, String> {
+    let mut symbols = BTreeMap::new();
+    let mut current: Option<(String, String)> = None;
+    for line in disassembly.lines() {
+        let symbol = line
+            .strip_suffix(">:")
+            .and_then(|line| line.find(" <").map(|i| (&line[..i], &line[i + 2..])))
+            .filter(|(address, _)| address.chars().all(|c| c.is_ascii_hexdigit()));
+        if let Some((_, symbol)) = symbol {
+            symbols.extend(current.take());
+            current = Some((symbol.to_string(), format!("{}\n", line)));
+        } else if let Some((_, asm)) = &mut current {
+            if !line.is_empty() {
+                asm.push_str(line);
+                asm.push('\n');
+            }
+        }
+    }
+    symbols.extend(current);
+    symbols
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_disassembly() {
+        let disassembly = "
+module.o:     file format elf64-x86-64
+
+
+Disassembly of section .text:
+
+0000000000000000 <func0_add>:
+   0:\tpush   %rbp
+   1:\tret
+
+0000000000000010 <trampoline_call_0>:
+  10:\tret
+";
+        let symbols = split_disassembly(disassembly);
+        assert_eq!(
+            symbols.keys().collect::<Vec<_>>(),
+            vec!["func0_add", "trampoline_call_0"]
+        );
+        assert_eq!(
+            symbols["func0_add"],
+            "0000000000000000 <func0_add>:\n   0:\tpush   %rbp\n   1:\tret\n"
+        );
+    }
+}
@@ -0,0 +1,187 @@
+//! Named compilation targets, bundling a triple, CPU features and the
+//! engine recommended for it.
+
+use crate::store::EngineType;
+use anyhow::{bail, Result};
+use std::str::FromStr;
+use wasmer::{CpuFeature, Target, Triple};
+
+/// A named compilation target, like `aarch64-musl-generic`.
+pub struct TargetPreset {
+    /// The name passed to `--target`
+    pub name: &'static str,
+    /// The target triple
+    pub triple: &'static str,
+    /// The CPU features, named like the `-m` flags
+    pub cpu_features: &'static [&'static str],
+    /// The engine producing the most useful artifacts for the target
+    pub engine: EngineType,
+    /// What the preset is meant for
+    pub description: &'static str,
+}
+
+const X86_64_V2: &[&str] = &["sse2", "sse3", "ssse3", "sse4.1", "sse4.2", "popcnt"];
+const X86_64_V3: &[&str] = &[
+    "sse2", "sse3", "ssse3", "sse4.1", "sse4.2", "popcnt", "avx", "avx2", "bmi", "bmi2", "lzcnt",
+];
+
+/// The presets listed by `wasmer compile --list-targets`.
+pub const TARGET_PRESETS: &[TargetPreset] = &[
+    TargetPreset {
+        name: "x86_64-linux-generic",
+        triple: "x86_64-unknown-linux-gnu",
+        cpu_features: &["sse2"],
+        engine: EngineType::Native,
+        description: "Any x86_64 Linux machine",
+    },
+    TargetPreset {
+        name: "x86_64-linux-avx2",
+        triple: "x86_64-unknown-linux-gnu",
+        cpu_features: X86_64_V3,
+        engine: EngineType::Native,
+        description: "x86_64 Linux servers from 2015 onwards",
+    },
+    TargetPreset {
+        name: "x86_64-musl-generic",
+        triple: "x86_64-unknown-linux-musl",
+        cpu_features: &["sse2"],
+        engine: EngineType::Native,
+        description: "Any x86_64 Linux machine using musl, like Alpine",
+    },
+    TargetPreset {
+        name: "aarch64-linux-generic",
+        triple: "aarch64-unknown-linux-gnu",
+        cpu_features: &[],
+        engine: EngineType::Native,
+        description: "Any 64-bit ARM Linux machine, like a Raspberry Pi 4",
+    },
+    TargetPreset {
+        name: "aarch64-musl-generic",
+        triple: "aarch64-unknown-linux-musl",
+        cpu_features: &[],
+        engine: EngineType::Native,
+        description: "Any 64-bit ARM Linux machine using musl, like IoT gateways",
+    },
+    TargetPreset {
+        name: "x86_64-macos-generic",
+        triple: "x86_64-apple-darwin",
+        cpu_features: X86_64_V2,
+        engine: EngineType::Native,
+        description: "Any Intel Mac",
+    },
+    TargetPreset {
+        name: "aarch64-macos-generic",
+        triple: "aarch64-apple-darwin",
+        cpu_features: &[],
+        engine: EngineType::Native,
+        description: "Any Apple Silicon Mac",
+    },
+    TargetPreset {
+        name: "x86_64-windows-generic",
+        triple: "x86_64-pc-windows-msvc",
+        cpu_features: &["sse2"],
+        engine: EngineType::Native,
+        description: "Any x86_64 Windows machine",
+    },
+    TargetPreset {
+        name: "aarch64-windows-generic",
+        triple: "aarch64-pc-windows-msvc",
+        cpu_features: &[],
+        engine: EngineType::Native,
+        description: "Any 64-bit ARM Windows machine, like a Surface Pro X",
+    },
+];
+
+impl TargetPreset {
+    /// Find the preset with the given name.
+    pub fn find(name: &str) -> Option<&'static Self> {
+        TARGET_PRESETS.iter().find(|preset| preset.name == name)
+    }
+
+    /// The target described by the preset.
+    pub fn target(&self) -> Result<Target> {
+        let triple = Triple::from_str(self.triple).map_err(|e| anyhow::anyhow!("{}", e))?;
+        let mut features = CpuFeature::set();
+        for feature in self.cpu_features {
+            features |= CpuFeature::from_str(feature)?;
+        }
+        Ok(Target::new(triple, features))
+    }
+}
+
+/// Print the presets, for `--list-targets`.
+pub fn print_target_presets() {
+    println!(
+        "{:<24} {:<28} {:<8} DESCRIPTION",
+        "NAME", "TRIPLE", "ENGINE"
+    );
+    for preset in TARGET_PRESETS {
+        println!(
+            "{:<24} {:<28} {:<8} {}",
+            preset.name,
+            preset.triple,
+            preset.engine.to_string(),
+            preset.description
+        );
+        if !preset.cpu_features.is_empty() {
+            println!(
+                "{:<24} CPU features: {}",
+                "",
+                preset.cpu_features.join(", ")
+            );
+        }
+    }
+    println!();
+    println!("Any other target triple can be passed to `--target`, with the CPU features given with `-m`.");
+}
+
+/// Fail with a clear error if the native engine can't link the artifacts
+/// for the target, instead of panicking while creating the engine.
+pub fn check_native_linker(target: &Target) -> Result<()> {
+    let (linkers, requirement): (&[_], _) = if *target.triple() != Triple::host() {
+        (
+            &["clang-11", "clang-10", "clang"],
+            "at least one of `clang-11`, `clang-10`, or `clang`",
+        )
+    } else {
+        (&["gcc"], "`gcc`")
+    };
+    if linkers.iter().any(|linker| which::which(linker).is_ok()) {
+        return Ok(());
+    }
+    if *target.triple() != Triple::host() {
+        bail!(
+            "cross-compiling to `{}` with the native engine needs {} installed to link the artifact; \
+             install it or use another engine, like `--jit`",
+            target.triple(),
+            requirement
+        )
+    } else {
+        bail!(
+            "the native engine needs {} installed to link the artifact; \
+             install it or use another engine, like `--jit`",
+            requirement
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target_presets() {
+        for preset in TARGET_PRESETS {
+            let target = preset.target().unwrap();
+            assert_eq!(target.triple().to_string(), preset.triple);
+            assert_eq!(
+                target.cpu_features().len(),
+                preset.cpu_features.len(),
+                "{}",
+                preset.name
+            );
+        }
+        assert!(TargetPreset::find("aarch64-musl-generic").is_some());
+        assert!(TargetPreset::find("aarch64-unknown-linux-musl").is_none());
+    }
+}
@@ -1,6 +1,8 @@
 //! Create a standalone native executable for a given Wasm file.
 
+use crate::c_gen::volumes::{generate_volumes_header, Volume, VolumeEntry};
 use crate::store::{CompilerOptions, EngineType};
+use crate::utils::parse_mapdir;
 use anyhow::{Context, Result};
 use clap::Clap;
 use std::env;
@@ -36,6 +38,14 @@ pub struct CreateExe {
     /// This is useful for fixing linker errors that may occur on some systems.
     #[clap(short = 'l', multiple = true)]
     libraries: Vec<String>,
+
+    /// Embed a directory in the executable, which is preopened for the
+    /// guest at `GUEST_DIR` when it runs.
+    ///
+    /// The directory is unpacked into a temporary directory on startup, so
+    /// the changes made by the guest are discarded when it exits.
+    #[clap(long = "volume", name = "GUEST_DIR:HOST_DIR", multiple = true, parse(try_from_str = parse_mapdir))]
+    volumes: Vec<(String, PathBuf)>,
 }
 
 impl CreateExe {
@@ -65,6 +75,15 @@ impl CreateExe {
         println!("Compiler: {}", compiler_type.to_string());
         println!("Target: {}", target.triple());
 
+        let volumes = self
+            .volumes
+            .iter()
+            .map(|(alias, dir)| {
+                read_volume(alias, dir)
+                    .with_context(|| format!("failed to read the volume `{}`", dir.display()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
         let working_dir = tempfile::tempdir()?;
         let starting_cd = env::current_dir()?;
         let output_path = starting_cd.join(&self.output);
@@ -95,6 +114,10 @@ impl CreateExe {
         );
 
         generate_header(header_file_src.as_bytes())?;
+        write_file(
+            Path::new("my_volumes.h"),
+            generate_volumes_header(&volumes).as_bytes(),
+        )?;
         self.compile_c(wasm_object_path, output_path)?;
 
         eprintln!(
@@ -140,19 +163,57 @@ impl CreateExe {
 }
 
 fn generate_header(header_file_src: &[u8]) -> anyhow::Result<()> {
-    let header_file_path = Path::new("my_wasm.h");
-    let mut header = std::fs::OpenOptions::new()
+    write_file(Path::new("my_wasm.h"), header_file_src)
+}
+
+fn write_file(path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new()
         .create(true)
         .truncate(true)
         .write(true)
-        .open(&header_file_path)?;
+        .open(path)?;
 
     use std::io::Write;
-    header.write_all(header_file_src)?;
+    file.write_all(contents)?;
 
     Ok(())
 }
 
+/// Read the directory tree to embed at `alias`.
+fn read_volume(alias: &str, dir: &Path) -> anyhow::Result<Volume> {
+    fn visit(dir: &Path, prefix: &str, entries: &mut Vec<VolumeEntry>) -> anyhow::Result<()> {
+        let mut children = fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+        // Sorted, so that the executables are reproducible.
+        children.sort_by_key(|child| child.file_name());
+        for child in children {
+            let name = child.file_name();
+            let name = name
+                .to_str()
+                .with_context(|| format!("`{}` isn't valid UTF-8", child.path().display()))?;
+            let path = format!("{}{}", prefix, name);
+            // Follow the symlinks, like a copy of the directory would.
+            let metadata = fs::metadata(child.path())?;
+            if metadata.is_dir() {
+                entries.push(VolumeEntry::Directory(path.clone()));
+                visit(&child.path(), &format!("{}/", path), entries)?;
+            } else {
+                entries.push(VolumeEntry::File(path, fs::read(child.path())?));
+            }
+        }
+        Ok(())
+    }
+
+    if !dir.is_dir() {
+        bail!("`{}` isn't a directory", dir.display());
+    }
+    let mut entries = vec![];
+    visit(dir, "", &mut entries)?;
+    Ok(Volume {
+        alias: alias.to_string(),
+        entries,
+    })
+}
+
 fn get_wasmer_dir() -> anyhow::Result<PathBuf> {
     Ok(PathBuf::from(
         env::var("WASMER_DIR")
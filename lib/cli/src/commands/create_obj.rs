@@ -0,0 +1,116 @@
+//! Create a relocatable object file and its header for a given Wasm file,
+//! to be linked into an application.
+
+use crate::c_gen::object_file_externs::generate_rust_externs;
+use crate::c_gen::object_file_header::generate_header_file;
+use crate::store::{CompilerOptions, EngineType};
+use anyhow::{Context, Result};
+use clap::Clap;
+use std::fs;
+use std::path::PathBuf;
+use wasmer::*;
+
+#[derive(Debug, Clap)]
+/// The options for the `wasmer create-obj` subcommand
+pub struct CreateObj {
+    /// Input file
+    #[clap(name = "FILE", parse(from_os_str))]
+    path: PathBuf,
+
+    /// Output object file
+    #[clap(name = "OUTPUT PATH", short = 'o', parse(from_os_str))]
+    output: PathBuf,
+
+    /// Header file for the object file
+    ///
+    /// Defaults to the output path with the `.h` extension.
+    #[clap(long = "header", parse(from_os_str))]
+    header_path: Option<PathBuf>,
+
+    /// Also generate a Rust file declaring the symbols of the object file,
+    /// to be `include!`d in a Rust application
+    #[clap(long = "rust-externs", parse(from_os_str))]
+    rust_externs_path: Option<PathBuf>,
+
+    /// Compilation Target triple
+    #[clap(long = "target")]
+    target_triple: Option<Triple>,
+
+    #[clap(flatten)]
+    compiler: CompilerOptions,
+
+    #[clap(short = 'm', multiple = true)]
+    cpu_features: Vec<CpuFeature>,
+}
+
+impl CreateObj {
+    /// Runs logic for the `create-obj` subcommand
+    pub fn execute(&self) -> Result<()> {
+        self.inner_execute()
+            .context(format!("failed to compile `{}`", self.path.display()))
+    }
+
+    fn inner_execute(&self) -> Result<()> {
+        let target = self
+            .target_triple
+            .as_ref()
+            .map(|target_triple| {
+                let mut features = self
+                    .cpu_features
+                    .clone()
+                    .into_iter()
+                    .fold(CpuFeature::set(), |a, b| a | b);
+                // Cranelift requires SSE2, so we have this "hack" for now to facilitate
+                // usage
+                features |= CpuFeature::SSE2;
+                Target::new(target_triple.clone(), features)
+            })
+            .unwrap_or_default();
+        let engine_type = EngineType::ObjectFile;
+        let (store, compiler_type) = self
+            .compiler
+            .get_store_for_target_and_engine(target.clone(), engine_type)?;
+
+        println!("Engine: {}", engine_type.to_string());
+        println!("Compiler: {}", compiler_type.to_string());
+        println!("Target: {}", target.triple());
+
+        let module = Module::from_file(&store, &self.path).context("failed to compile Wasm")?;
+        module.serialize_to_file(&self.output)?;
+        eprintln!(
+            "✔ Object file compiled successfully to `{}`.",
+            self.output.display(),
+        );
+
+        let artifact: &wasmer_engine_object_file::ObjectFileArtifact =
+            module.artifact().as_ref().downcast_ref().context(
+                "Engine type is ObjectFile but could not downcast artifact into ObjectFileArtifact",
+            )?;
+        let symbol_registry = artifact.symbol_registry();
+        let metadata_length = artifact.metadata_length();
+        let module_info = module.info();
+
+        let header_path = self
+            .header_path
+            .clone()
+            .unwrap_or_else(|| self.output.with_extension("h"));
+        let header_file_src = generate_header_file(module_info, symbol_registry, metadata_length);
+        fs::write(&header_path, header_file_src)?;
+        eprintln!(
+            "✔ Header file generated successfully at `{}`.",
+            header_path.display(),
+        );
+
+        if let Some(rust_externs_path) = &self.rust_externs_path {
+            let rust_externs_src =
+                generate_rust_externs(module_info, symbol_registry, metadata_length);
+            fs::write(rust_externs_path, rust_externs_src)?;
+            eprintln!(
+                "✔ Rust declarations generated successfully at `{}`.",
+                rust_externs_path.display(),
+            );
+        }
+
+        Ok(())
+    }
+}
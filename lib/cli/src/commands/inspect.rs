@@ -1,74 +1,418 @@
+#[cfg(feature = "compiler")]
+use crate::common::required_proposals;
+use crate::output::{OutputOptions, Report};
 use crate::store::StoreOptions;
 use anyhow::{Context, Result};
 use bytesize::ByteSize;
 use clap::Clap;
+use serde::Serialize;
 use std::path::PathBuf;
 use wasmer::*;
 
 #[derive(Debug, Clap)]
-/// The options for the `wasmer validate` subcommand
+/// The options for the `wasmer inspect` subcommand
 pub struct Inspect {
-    /// File to validate as WebAssembly
+    /// File to inspect: a WebAssembly module or a serialized artifact
     #[clap(name = "FILE", parse(from_os_str))]
     path: PathBuf,
 
     #[clap(flatten)]
     store: StoreOptions,
+
+    #[clap(flatten)]
+    output_options: OutputOptions,
 }
 
-impl Inspect {
-    /// Runs logic for the `validate` subcommand
-    pub fn execute(&self) -> Result<()> {
-        self.inner_execute()
-            .context(format!("failed to inspect `{}`", self.path.display()))
+/// The kind of file being inspected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileKind {
+    Wat,
+    Wasm,
+    JitArtifact,
+    NativeArtifact,
+}
+
+impl FileKind {
+    fn detect(bytes: &[u8]) -> Self {
+        if is_wasm(bytes) {
+            Self::Wasm
+        } else if bytes.starts_with(b"\0wasmer-jit") {
+            Self::JitArtifact
+        } else if object_target(bytes).is_some() {
+            Self::NativeArtifact
+        } else {
+            Self::Wat
+        }
     }
-    fn inner_execute(&self) -> Result<()> {
-        let (store, _engine_type, _compiler_type) = self.store.get_store()?;
-        let module_contents = std::fs::read(&self.path)?;
-        let module = Module::new(&store, &module_contents)?;
-        println!(
-            "Type: {}",
-            if !is_wasm(&module_contents) {
-                "wat"
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Wat => "wat",
+            Self::Wasm => "wasm",
+            Self::JitArtifact => "jit artifact",
+            Self::NativeArtifact => "native artifact",
+        }
+    }
+}
+
+/// The architecture and the format of a shared object, from its header.
+fn object_target(bytes: &[u8]) -> Option<String> {
+    let u16_at = |offset: usize, little_endian: bool| {
+        let b = bytes.get(offset..offset + 2)?;
+        Some(if little_endian {
+            u16::from_le_bytes([b[0], b[1]])
+        } else {
+            u16::from_be_bytes([b[0], b[1]])
+        })
+    };
+    let u32_le_at = |offset: usize| {
+        let b = bytes.get(offset..offset + 4)?;
+        Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    };
+
+    let (architecture, format) = if bytes.starts_with(b"\x7fELF") {
+        let is_64 = *bytes.get(4)? == 2;
+        let little_endian = *bytes.get(5)? == 1;
+        let architecture = match u16_at(18, little_endian)? {
+            3 => "x86",
+            62 => "x86_64",
+            40 => "arm",
+            183 => "aarch64",
+            243 if is_64 => "riscv64",
+            243 => "riscv32",
+            8 => "mips",
+            20 => "powerpc",
+            21 => "powerpc64",
+            _ => "unknown",
+        };
+        (architecture, "elf")
+    } else if bytes.starts_with(&[0xcf, 0xfa, 0xed, 0xfe]) {
+        let architecture = match u32_le_at(4)? {
+            0x0100_0007 => "x86_64",
+            0x0100_000c => "aarch64",
+            _ => "unknown",
+        };
+        (architecture, "macho")
+    } else if bytes.starts_with(b"MZ") {
+        let pe_offset = u32_le_at(0x3c)? as usize;
+        if bytes.get(pe_offset..pe_offset + 4)? != b"PE\0\0" {
+            return None;
+        }
+        let architecture = match u16_at(pe_offset + 4, true)? {
+            0x8664 => "x86_64",
+            0xaa64 => "aarch64",
+            0x14c => "x86",
+            0x1c4 => "arm",
+            _ => "unknown",
+        };
+        (architecture, "coff")
+    } else {
+        return None;
+    };
+    Some(format!("{}-{}", architecture, format))
+}
+
+#[derive(Serialize)]
+struct ImportReport {
+    module: String,
+    name: String,
+    kind: &'static str,
+    #[serde(rename = "type")]
+    ty: String,
+}
+
+#[derive(Serialize)]
+struct ExportReport {
+    name: String,
+    kind: &'static str,
+    #[serde(rename = "type")]
+    ty: String,
+}
+
+#[derive(Serialize)]
+struct MemoryReport {
+    index: usize,
+    imported: bool,
+    minimum_pages: u32,
+    maximum_pages: Option<u32>,
+    shared: bool,
+}
+
+#[derive(Serialize)]
+struct TableReport {
+    index: usize,
+    imported: bool,
+    element_type: String,
+    minimum: u32,
+    maximum: Option<u32>,
+}
+
+#[derive(Serialize)]
+struct CustomSectionReport {
+    name: String,
+    size: usize,
+}
+
+/// Everything `wasmer inspect` prints.
+#[derive(Serialize)]
+struct ModuleReport {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    size: usize,
+    /// The target of a native artifact.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target: Option<String>,
+    /// The proposals required by a wasm module.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    required_features: Option<Vec<&'static str>>,
+    imports: Vec<ImportReport>,
+    exports: Vec<ExportReport>,
+    memories: Vec<MemoryReport>,
+    tables: Vec<TableReport>,
+    custom_sections: Vec<CustomSectionReport>,
+}
+
+fn extern_kind(ty: &ExternType) -> &'static str {
+    match ty {
+        ExternType::Function(_) => "function",
+        ExternType::Global(_) => "global",
+        ExternType::Table(_) => "table",
+        ExternType::Memory(_) => "memory",
+    }
+}
+
+pub(crate) fn extern_type(ty: &ExternType) -> String {
+    match ty {
+        ExternType::Function(ty) => ty.to_string(),
+        ExternType::Global(ty) => ty.to_string(),
+        ExternType::Table(ty) => ty.to_string(),
+        ExternType::Memory(ty) => ty.to_string(),
+    }
+}
+
+impl ModuleReport {
+    fn new(kind: FileKind, contents: &[u8], module: &Module) -> Result<Self> {
+        let info = module.info();
+        let target = match kind {
+            FileKind::NativeArtifact => object_target(contents),
+            // The JIT artifacts don't record their target.
+            FileKind::JitArtifact => Some("unknown".to_string()),
+            FileKind::Wat | FileKind::Wasm => None,
+        };
+        let required_features = match kind {
+            FileKind::Wat | FileKind::Wasm => Self::required_features(contents)?,
+            FileKind::JitArtifact | FileKind::NativeArtifact => None,
+        };
+        let imports = module
+            .imports()
+            .map(|import| ImportReport {
+                module: import.module().to_string(),
+                name: import.name().to_string(),
+                kind: extern_kind(import.ty()),
+                ty: extern_type(import.ty()),
+            })
+            .collect();
+        let exports = module
+            .exports()
+            .map(|export| ExportReport {
+                name: export.name().to_string(),
+                kind: extern_kind(export.ty()),
+                ty: extern_type(export.ty()),
+            })
+            .collect();
+        let memories = info
+            .memories
+            .values()
+            .enumerate()
+            .map(|(index, ty)| MemoryReport {
+                index,
+                imported: index < info.num_imported_memories,
+                minimum_pages: ty.minimum.0,
+                maximum_pages: ty.maximum.map(|pages| pages.0),
+                shared: ty.shared,
+            })
+            .collect();
+        let tables = info
+            .tables
+            .values()
+            .enumerate()
+            .map(|(index, ty)| TableReport {
+                index,
+                imported: index < info.num_imported_tables,
+                element_type: ty.ty.to_string().to_lowercase(),
+                minimum: ty.minimum,
+                maximum: ty.maximum,
+            })
+            .collect();
+        let custom_sections = info
+            .custom_sections
+            .iter()
+            .map(|(name, index)| CustomSectionReport {
+                name: name.clone(),
+                size: info.custom_sections_data[*index].len(),
+            })
+            .collect();
+
+        Ok(Self {
+            kind: kind.name(),
+            size: contents.len(),
+            target,
+            required_features,
+            imports,
+            exports,
+            memories,
+            tables,
+            custom_sections,
+        })
+    }
+
+    #[cfg(feature = "compiler")]
+    fn required_features(contents: &[u8]) -> Result<Option<Vec<&'static str>>> {
+        #[cfg(feature = "wat")]
+        let wasm = wat2wasm(contents)?;
+        #[cfg(not(feature = "wat"))]
+        let wasm = contents;
+        let proposals = required_proposals(&wasm).map_err(|e| anyhow::anyhow!(e))?;
+        Ok(Some(proposals))
+    }
+
+    #[cfg(not(feature = "compiler"))]
+    fn required_features(_contents: &[u8]) -> Result<Option<Vec<&'static str>>> {
+        Ok(None)
+    }
+
+    fn print(&self) {
+        println!("Type: {}", self.kind);
+        println!("Size: {}", ByteSize(self.size as _));
+        if let Some(target) = &self.target {
+            println!("Target: {}", target);
+        }
+        if let Some(features) = &self.required_features {
+            if features.is_empty() {
+                println!("Required features: none");
             } else {
-                "wasm"
+                println!("Required features: {}", features.join(", "));
             }
-        );
-        println!("Size: {}", ByteSize(module_contents.len() as _));
+        }
         println!("Imports:");
-        println!("  Functions:");
-        for f in module.imports().functions() {
-            println!("    \"{}\".\"{}\": {}", f.module(), f.name(), f.ty());
+        for kind in &["function", "memory", "table", "global"] {
+            println!("  {}:", plural(kind));
+            for import in self.imports.iter().filter(|import| import.kind == *kind) {
+                println!(
+                    "    \"{}\".\"{}\": {}",
+                    import.module, import.name, import.ty
+                );
+            }
         }
-        println!("  Memories:");
-        for f in module.imports().memories() {
-            println!("    \"{}\".\"{}\": {}", f.module(), f.name(), f.ty());
+        println!("Exports:");
+        for kind in &["function", "memory", "table", "global"] {
+            println!("  {}:", plural(kind));
+            for export in self.exports.iter().filter(|export| export.kind == *kind) {
+                println!("    \"{}\": {}", export.name, export.ty);
+            }
         }
-        println!("  Tables:");
-        for f in module.imports().tables() {
-            println!("    \"{}\".\"{}\": {}", f.module(), f.name(), f.ty());
+        println!("Memories:");
+        for memory in &self.memories {
+            println!(
+                "  {}: {} pages - {}{}{}",
+                memory.index,
+                memory.minimum_pages,
+                memory
+                    .maximum_pages
+                    .map_or("unbounded".to_string(), |max| format!("{} pages", max)),
+                if memory.shared { ", shared" } else { "" },
+                if memory.imported { " (imported)" } else { "" },
+            );
         }
-        println!("  Globals:");
-        for f in module.imports().globals() {
-            println!("    \"{}\".\"{}\": {}", f.module(), f.name(), f.ty());
+        println!("Tables:");
+        for table in &self.tables {
+            println!(
+                "  {}: {} {} - {}{}",
+                table.index,
+                table.element_type,
+                table.minimum,
+                table
+                    .maximum
+                    .map_or("unbounded".to_string(), |max| max.to_string()),
+                if table.imported { " (imported)" } else { "" },
+            );
         }
-        println!("Exports:");
-        println!("  Functions:");
-        for f in module.exports().functions() {
-            println!("    \"{}\": {}", f.name(), f.ty());
+        println!("Custom sections:");
+        for section in &self.custom_sections {
+            println!("  \"{}\": {}", section.name, ByteSize(section.size as _));
         }
-        println!("  Memories:");
-        for f in module.exports().memories() {
-            println!("    \"{}\": {}", f.name(), f.ty());
+    }
+}
+
+fn plural(kind: &str) -> &'static str {
+    match kind {
+        "function" => "Functions",
+        "memory" => "Memories",
+        "table" => "Tables",
+        _ => "Globals",
+    }
+}
+
+impl Inspect {
+    /// Runs logic for the `inspect` subcommand
+    pub fn execute(&self) -> Result<()> {
+        let report = self.output_options.report("inspect");
+        if let Some(report) = &report {
+            report.set("file", &self.path);
         }
-        println!("  Tables:");
-        for f in module.exports().tables() {
-            println!("    \"{}\": {}", f.name(), f.ty());
+        let result = self
+            .inner_execute(report.as_ref())
+            .context(format!("failed to inspect `{}`", self.path.display()));
+        if let Some(report) = &report {
+            report.finish(&result);
         }
-        println!("  Globals:");
-        for f in module.exports().globals() {
-            println!("    \"{}\": {}", f.name(), f.ty());
+        result
+    }
+    fn inner_execute(&self, report: Option<&Report>) -> Result<()> {
+        let (store, _engine_type, _compiler_type) = self.store.get_store()?;
+        let module_contents = std::fs::read(&self.path)?;
+        let kind = FileKind::detect(&module_contents);
+        let module = match kind {
+            FileKind::Wat | FileKind::Wasm => Module::new(&store, &module_contents)?,
+            FileKind::JitArtifact | FileKind::NativeArtifact => unsafe {
+                Module::deserialize(&store, &module_contents).with_context(|| {
+                    format!(
+                        "failed to load the {}, it may need another engine or target",
+                        kind.name()
+                    )
+                })?
+            },
+        };
+        let module_report = ModuleReport::new(kind, &module_contents, &module)?;
+        match report {
+            Some(report) => report.extend(&module_report),
+            None => module_report.print(),
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_object_target() {
+        let mut elf = b"\x7fELF\x02\x01".to_vec();
+        elf.resize(20, 0);
+        elf[18] = 62;
+        assert_eq!(object_target(&elf).as_deref(), Some("x86_64-elf"));
+
+        let macho = [0xcf, 0xfa, 0xed, 0xfe, 0x0c, 0x00, 0x00, 0x01];
+        assert_eq!(object_target(&macho).as_deref(), Some("aarch64-macho"));
+
+        let mut pe = b"MZ".to_vec();
+        pe.resize(0x40, 0);
+        pe[0x3c] = 0x40;
+        pe.extend_from_slice(b"PE\0\0\x64\xaa");
+        assert_eq!(object_target(&pe).as_deref(), Some("aarch64-coff"));
+
+        assert_eq!(object_target(b"\0asm"), None);
+        assert_eq!(object_target(b"MZ"), None);
+    }
+}
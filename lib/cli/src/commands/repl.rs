@@ -0,0 +1,313 @@
+//! An interactive prompt to define WAT functions, call them and inspect the
+//! memory.
+
+use super::inspect::extern_type;
+use super::run::invoke;
+use crate::store::StoreOptions;
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Clap;
+use std::io::{self, BufRead, Write};
+use wasmer::*;
+
+const HELP: &str = "\
+Enter module fields, like `(func (export \"add\") (param i32 i32) (result i32) ...)`,
+to add them to the module, or one of the commands:
+  :call NAME ARGS...   call the exported function NAME
+  :mem OFFSET [LEN]    dump LEN bytes of the memory from OFFSET (64 by default)
+  :exports             list the exports
+  :list                print the module fields entered so far
+  :reset               forget the module fields and clear the memory
+  :help                print this help
+  :quit                exit
+
+The memory `$memory` is kept across the entries, while the globals and
+tables are reset each time the module is extended.";
+
+#[derive(Debug, Clap)]
+/// The options for the `wasmer repl` subcommand
+pub struct Repl {
+    #[clap(flatten)]
+    store: StoreOptions,
+}
+
+impl Repl {
+    /// Runs logic for the `repl` subcommand
+    pub fn execute(&self) -> Result<()> {
+        let (store, _engine_type, _compiler_type) = self.store.get_store()?;
+        let mut session = Session::new(store);
+
+        println!("Wasmer REPL, type `:help` for help.");
+        let stdin = io::stdin();
+        let mut lines = stdin.lock().lines();
+        let mut entry = String::new();
+        loop {
+            print!("{}", if entry.is_empty() { "wasm> " } else { "....> " });
+            io::stdout().flush()?;
+            let line = match lines.next() {
+                Some(line) => line?,
+                None => break,
+            };
+            entry.push_str(&line);
+            entry.push('\n');
+            // The module fields can span several lines.
+            if !is_complete(&entry) {
+                continue;
+            }
+            let input = std::mem::take(&mut entry);
+            match session.eval(input.trim()) {
+                Ok(Outcome::Output(output)) => {
+                    if !output.is_empty() {
+                        println!("{}", output)
+                    }
+                }
+                Ok(Outcome::Quit) => break,
+                Err(e) => eprintln!("error: {:#}", e),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether the parentheses of the entry are balanced.
+fn is_complete(entry: &str) -> bool {
+    let mut depth = 0i32;
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in entry.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '(' if !in_string => depth += 1,
+            ')' if !in_string => depth -= 1,
+            _ => {}
+        }
+    }
+    depth <= 0
+}
+
+/// What an entry resulted in.
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    /// Something to print.
+    Output(String),
+    /// The session is over.
+    Quit,
+}
+
+/// The state of the REPL: the module fields entered so far, the instance
+/// of the module they form, and the memory shared by its successive
+/// instances.
+struct Session {
+    store: Store,
+    fields: Vec<String>,
+    memory: Memory,
+    instance: Option<Instance>,
+}
+
+impl Session {
+    fn new(store: Store) -> Self {
+        let memory = Self::new_memory(&store);
+        Self {
+            store,
+            fields: Vec::new(),
+            memory,
+            instance: None,
+        }
+    }
+
+    fn new_memory(store: &Store) -> Memory {
+        Memory::new(store, MemoryType::new(1, None, false)).expect("can't create the memory")
+    }
+
+    fn eval(&mut self, entry: &str) -> Result<Outcome> {
+        let output = if entry.starts_with('(') {
+            self.define(entry)?;
+            String::new()
+        } else if let Some(command) = entry.strip_prefix(':') {
+            let mut words = command.split_whitespace();
+            match (words.next(), words.collect::<Vec<_>>()) {
+                (Some("call"), args) if !args.is_empty() => self.call(args[0], &args[1..])?,
+                (Some("mem"), args) if !args.is_empty() && args.len() <= 2 => {
+                    let offset = parse_usize(args[0])?;
+                    let length = args.get(1).map_or(Ok(64), |len| parse_usize(len))?;
+                    self.dump_memory(offset, length)?
+                }
+                (Some("exports"), args) if args.is_empty() => self.exports(),
+                (Some("list"), args) if args.is_empty() => self.fields.join("\n"),
+                (Some("reset"), args) if args.is_empty() => {
+                    self.fields.clear();
+                    self.instance = None;
+                    self.memory = Self::new_memory(&self.store);
+                    String::new()
+                }
+                (Some("help"), args) if args.is_empty() => HELP.to_string(),
+                (Some("quit"), args) | (Some("q"), args) if args.is_empty() => {
+                    return Ok(Outcome::Quit)
+                }
+                _ => bail!("unknown command `{}`, type `:help` for help", entry),
+            }
+        } else if entry.is_empty() {
+            String::new()
+        } else {
+            bail!("expected a module field in parentheses or a command, type `:help` for help");
+        };
+        Ok(Outcome::Output(output))
+    }
+
+    /// Add a module field, keeping it only if the module still compiles and
+    /// instantiates.
+    fn define(&mut self, field: &str) -> Result<()> {
+        self.fields.push(field.to_string());
+        match self.instantiate() {
+            Ok(instance) => {
+                self.instance = Some(instance);
+                Ok(())
+            }
+            Err(e) => {
+                self.fields.pop();
+                Err(e)
+            }
+        }
+    }
+
+    fn instantiate(&self) -> Result<Instance> {
+        let source = format!(
+            "(module\n(import \"repl\" \"memory\" (memory $memory 1))\n{}\n)",
+            self.fields.join("\n")
+        );
+        let module = Module::new(&self.store, source)?;
+        let imports = imports! {
+            "repl" => {
+                "memory" => self.memory.clone(),
+            },
+        };
+        Ok(Instance::new(&module, &imports)?)
+    }
+
+    fn instance(&self) -> Result<&Instance> {
+        self.instance
+            .as_ref()
+            .ok_or_else(|| anyhow!("nothing is defined yet"))
+    }
+
+    fn call(&self, name: &str, args: &[&str]) -> Result<String> {
+        let function = self.instance()?.exports.get_function(name)?;
+        let params = function.ty().params();
+        if params.len() != args.len() {
+            bail!(
+                "`{}` expects {} arguments, but received {}",
+                name,
+                params.len(),
+                args.len()
+            );
+        }
+        let args = args
+            .iter()
+            .zip(params.iter())
+            .map(|(arg, ty)| invoke::parse_arg(arg, *ty))
+            .collect::<Result<Vec<_>>>()?;
+        let results = function.call(&args)?;
+        Ok(results
+            .iter()
+            .map(invoke::format_result)
+            .collect::<Vec<_>>()
+            .join(" "))
+    }
+
+    fn exports(&self) -> String {
+        let instance = match &self.instance {
+            Some(instance) => instance,
+            None => return String::new(),
+        };
+        instance
+            .module()
+            .exports()
+            .map(|export| format!("\"{}\": {}", export.name(), extern_type(export.ty())))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Dump the memory like `hexdump -C`.
+    fn dump_memory(&self, offset: usize, length: usize) -> Result<String> {
+        let view = self.memory.view::<u8>();
+        let end = offset
+            .checked_add(length)
+            .filter(|end| *end <= view.len())
+            .with_context(|| format!("the memory is only {} bytes long", view.len()))?;
+        let bytes = view[offset..end]
+            .iter()
+            .map(|byte| byte.get())
+            .collect::<Vec<_>>();
+        Ok(bytes
+            .chunks(16)
+            .enumerate()
+            .map(|(index, chunk)| {
+                let hex = chunk
+                    .iter()
+                    .map(|byte| format!("{:02x}", byte))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let ascii = chunk
+                    .iter()
+                    .map(|&byte| {
+                        if byte.is_ascii_graphic() || byte == b' ' {
+                            byte as char
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect::<String>();
+                format!("{:08x}  {:<47}  |{}|", offset + index * 16, hex, ascii)
+            })
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+}
+
+fn parse_usize(value: &str) -> Result<usize> {
+    let parsed = match value.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+    parsed.with_context(|| format!("invalid number `{}`", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(session: &mut Session, entry: &str) -> String {
+        match session.eval(entry).unwrap() {
+            Outcome::Output(output) => output,
+            Outcome::Quit => panic!("unexpected quit"),
+        }
+    }
+
+    #[test]
+    fn test_session() {
+        let mut session = Session::new(Store::default());
+        assert!(is_complete("(func (export \"a\"))"));
+        assert!(!is_complete("(func (export \")\")"));
+
+        output(
+            &mut session,
+            "(func (export \"store\") (param i32 i32) local.get 0 local.get 1 i32.store)",
+        );
+        output(&mut session, ":call store 0 0x64636261");
+        // The memory is kept when the module is extended.
+        output(
+            &mut session,
+            "(func (export \"load\") (param i32) (result i32) local.get 0 i32.load)",
+        );
+        assert_eq!(output(&mut session, ":call load 0"), "i32:1684234849");
+        assert!(output(&mut session, ":mem 0 4").ends_with("|abcd|"));
+        assert!(output(&mut session, ":exports").contains("\"load\": [I32] -> [I32]"));
+
+        // A field which doesn't compile is forgotten.
+        assert!(session.eval("(func (export \"bad\") i32.add)").is_err());
+        assert_eq!(session.fields.len(), 2);
+
+        assert_eq!(session.eval(":quit").unwrap(), Outcome::Quit);
+    }
+}
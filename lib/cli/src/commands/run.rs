@@ -1,36 +1,89 @@
+#[cfg(feature = "cache")]
+use crate::commands::CacheCounters;
 use crate::common::get_cache_dir;
 #[cfg(feature = "debug")]
 use crate::logging;
+use crate::output::{OutputOptions, Report};
 use crate::store::{CompilerType, EngineType, StoreOptions};
 use crate::suggestions::suggest_function_exports;
 use crate::warning;
-use anyhow::{anyhow, Context, Result};
-use std::path::PathBuf;
+use anyhow::{anyhow, bail, Context, Result};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+#[cfg(feature = "compiler")]
+use std::sync::Arc;
+use std::time::Duration;
 use wasmer::*;
 #[cfg(feature = "cache")]
 use wasmer_cache::{Cache, FileSystemCache, Hash};
 
 use clap::Clap;
 
+/// The magic number of the WebC containers, which can't be run yet.
+const WEBC_MAGIC: &[u8] = b"\0webc";
+
+#[cfg(feature = "wasi")]
+mod bundle;
+#[cfg(feature = "compiler")]
+mod gas;
+pub(crate) mod invoke;
+#[cfg(feature = "compiler")]
+mod profile;
+#[cfg(unix)]
+mod signals;
+mod source;
+mod timeout;
 #[cfg(feature = "wasi")]
 mod wasi;
+mod watch;
+
+use crate::limits::parse_size;
+#[cfg(feature = "compiler")]
+use gas::GasMeter;
+#[cfg(feature = "compiler")]
+use profile::{ProfileFormat, Profiler};
+#[cfg(unix)]
+use signals::SignalForwarder;
+use source::{verify_sha256, Source};
+use timeout::{parse_duration, Watchdog};
 
 #[cfg(feature = "wasi")]
 use wasi::Wasi;
 
 #[derive(Debug, Clap, Clone)]
+#[clap(after_help = "EXIT CODES:
+    N          The code given by a WASI guest to `proc_exit`, or 255 if it doesn't fit in a byte
+    1          An error, like a module failing to compile or to instantiate
+    100+N      The guest trapped with the trap code N, like 110 for a division by zero
+    124        The guest timed out, with `--timeout`
+    125        The guest ran out of gas, with `--gas`
+    128+N      The guest was stopped by the signal N, like 130 for SIGINT or 143 for SIGTERM")]
 /// The options for the `wasmer run` subcommand
 pub struct Run {
     /// Disable the cache
     #[clap(long = "disable-cache")]
     disable_cache: bool,
 
-    /// File to run
+    /// File to run, `-` to read it from stdin, or an `http(s)://` URL to
+    /// download it from. A local `.tar.gz` bundle runs its command; WebC
+    /// containers aren't supported
     #[clap(name = "FILE", parse(from_os_str))]
     path: PathBuf,
 
-    /// Invoke a specified function
+    /// The maximum size of a module downloaded from a URL
+    #[clap(long = "max-download-size", default_value = "100MiB", parse(try_from_str = parse_size))]
+    max_download_size: u64,
+
+    /// Only run the module if its SHA-256 is this hexadecimal digest
+    #[clap(long = "sha256")]
+    sha256: Option<String>,
+
+    /// Invoke a specified function.
+    ///
+    /// The arguments are converted to the parameter types: integers can be
+    /// written in hexadecimal (`0x..`), floats as `inf` or `nan`, and v128
+    /// values as lanes like `i32x4:1,2,3,4`. The results are printed with
+    /// their types, like `i32:42`.
     #[clap(long = "invoke", short = 'i')]
     invoke: Option<String>,
 
@@ -46,9 +99,56 @@ pub struct Run {
     #[clap(long = "cache-key", hidden = true)]
     cache_key: Option<String>,
 
+    /// Replace the function imports that can't be resolved with stubs
+    /// that trap when called
+    #[clap(long = "stub-missing-imports")]
+    stub_missing_imports: bool,
+
+    /// Stop the guest after the given wall-clock time, like `10s` or
+    /// `500ms`, and exit with the code 124
+    #[clap(long = "timeout", parse(try_from_str = parse_duration))]
+    timeout: Option<Duration>,
+
+    /// Give the guest this many points of gas, one per executed operator,
+    /// and exit with the code 125 when they are exhausted
+    #[cfg(feature = "compiler")]
+    #[clap(long = "gas")]
+    gas: Option<u64>,
+
+    /// Re-run the module each time it changes
+    #[clap(long = "watch")]
+    watch: bool,
+
+    /// With `--watch`, also re-run when a file changes in the directories
+    /// given with `--dir` or `--mapdir`
+    #[cfg(feature = "wasi")]
+    #[clap(long = "watch-dirs", requires = "watch")]
+    watch_dirs: bool,
+
+    /// Print the gas used by the guest to stderr
+    #[cfg(feature = "compiler")]
+    #[clap(long = "print-gas-used", requires = "gas")]
+    print_gas_used: bool,
+
+    /// Sample the call stack of the guest, and write a profile of the hot
+    /// call stacks as `flamegraph` folded stacks or a `json` speedscope
+    /// file
+    #[cfg(feature = "compiler")]
+    #[clap(long = "profile", name = "FORMAT")]
+    profile: Option<ProfileFormat>,
+
+    /// Where to write the profile, `wasmer-profile.folded` or
+    /// `wasmer-profile.speedscope.json` by default
+    #[cfg(feature = "compiler")]
+    #[clap(long = "profile-output", parse(from_os_str), requires = "FORMAT")]
+    profile_output: Option<PathBuf>,
+
     #[clap(flatten)]
     store: StoreOptions,
 
+    #[clap(flatten)]
+    output_options: OutputOptions,
+
     // TODO: refactor WASI structure to allow shared options with Emscripten
     #[cfg(feature = "wasi")]
     #[clap(flatten)]
@@ -76,7 +176,50 @@ impl Run {
         if self.debug {
             logging::set_up_logging().unwrap();
         }
-        self.inner_execute().with_context(|| {
+        if self.watch && !watch::is_watched() {
+            return self.watch().context("failed to watch the module");
+        }
+        #[cfg(feature = "wasi")]
+        if let Source::File(path) = Source::new(&self.path) {
+            if bundle::starts_like_bundle(path) {
+                return self
+                    .with_bundle(path)
+                    .with_context(|| format!("failed to open the bundle `{}`", path.display()))?
+                    .execute();
+            }
+        }
+        let report = self.output_options.report("run");
+        if let Some(report) = &report {
+            report.set("file", &self.path);
+        }
+        let watchdog = self
+            .timeout
+            .map(|timeout| Watchdog::start(timeout, report.clone()));
+        #[cfg(feature = "compiler")]
+        let gas_meter = self
+            .gas
+            .map(|limit| GasMeter::new(limit, self.print_gas_used));
+        #[cfg(feature = "compiler")]
+        let profiler = self
+            .profile
+            .map(|format| Profiler::new(format, self.profile_output.clone()));
+        #[cfg(unix)]
+        let signals = SignalForwarder::start(report.clone())
+            .map_err(|e| warning!("failed to handle the signals: {}", e))
+            .ok();
+        let monitors = Monitors {
+            watchdog: watchdog.as_ref(),
+            #[cfg(feature = "compiler")]
+            gas_meter: gas_meter.as_ref(),
+            #[cfg(feature = "compiler")]
+            profiler: profiler.as_ref(),
+            #[cfg(unix)]
+            signals: signals.as_ref(),
+            report: report.as_ref(),
+        };
+        let result = self.inner_execute(&monitors);
+        monitors.finish(result.is_err());
+        let result = result.with_context(|| {
             format!(
                 "failed to run `{}`{}",
                 self.path.display(),
@@ -86,24 +229,28 @@ impl Run {
                     ""
                 }
             )
-        })
+        });
+        if let Some(report) = &report {
+            report.finish(&result);
+        }
+        result
     }
 
-    fn inner_execute(&self) -> Result<()> {
+    fn inner_execute(&self, monitors: &Monitors) -> Result<()> {
         let module = self.get_module()?;
         // Do we want to invoke a function?
         if let Some(ref invoke) = self.invoke {
             let imports = imports! {};
-            let instance = Instance::new(&module, &imports)?;
+            let instance = self.instantiate(&module, &imports, monitors)?;
             let result = self.invoke_function(&instance, &invoke, &self.args)?;
-            println!(
-                "{}",
-                result
-                    .iter()
-                    .map(|val| val.to_string())
-                    .collect::<Vec<String>>()
-                    .join(" ")
-            );
+            let results = result
+                .iter()
+                .map(invoke::format_result)
+                .collect::<Vec<String>>();
+            match monitors.report {
+                Some(report) => report.set("results", results),
+                None => println!("{}", results.join(" ")),
+            }
             return Ok(());
         }
         #[cfg(feature = "emscripten")]
@@ -119,7 +266,7 @@ impl Run {
                 let mut em_env = EmEnv::new(&emscripten_globals.data, Default::default());
                 let import_object =
                     generate_emscripten_env(module.store(), &mut emscripten_globals, &mut em_env);
-                let mut instance = match Instance::new(&module, &import_object) {
+                let mut instance = match self.instantiate(&module, &import_object, monitors) {
                     Ok(instance) => instance,
                     Err(e) => {
                         let err: Result<(), _> = Err(e);
@@ -165,28 +312,108 @@ impl Run {
                     .unwrap_or_default();
                 return self
                     .wasi
-                    .execute(module, program_name, self.args.clone())
+                    .execute(
+                        module,
+                        program_name,
+                        self.args.clone(),
+                        self.stub_missing_imports,
+                        monitors,
+                    )
                     .with_context(|| "WASI execution failed");
             }
         }
 
         // Try to instantiate the wasm file, with no provided imports
         let imports = imports! {};
-        let instance = Instance::new(&module, &imports)?;
+        let instance = self.instantiate(&module, &imports, monitors)?;
         let start: Function = self.try_find_function(&instance, "_start", &[])?;
         start.call(&[])?;
 
         Ok(())
     }
 
+    fn instantiate(
+        &self,
+        module: &Module,
+        resolver: impl Resolver,
+        monitors: &Monitors,
+    ) -> Result<Instance> {
+        let instance = if self.stub_missing_imports {
+            module.instantiate_with_stubs(resolver)?
+        } else {
+            Instance::new(module, &resolver)?
+        };
+        monitors.watch(&instance);
+        Ok(instance)
+    }
+
+    /// Re-run the guest in a child process whenever the watched paths
+    /// change.
+    /// The options to run the command of the bundle at `path`.
+    #[cfg(feature = "wasi")]
+    fn with_bundle(&self, path: &Path) -> Result<Self> {
+        let contents = std::fs::read(path)?;
+        if let Some(sha256) = &self.sha256 {
+            verify_sha256(&contents, sha256)?;
+        }
+        let bundle = bundle::Bundle::unpack(&contents)?;
+        let mut run = self.clone();
+        run.path = bundle.module();
+        run.sha256 = None;
+        run.args = bundle
+            .manifest
+            .command
+            .args
+            .iter()
+            .chain(self.args.iter())
+            .cloned()
+            .collect();
+        run.wasi.add_bundle(&bundle);
+        Ok(run)
+    }
+
+    fn watch(&self) -> Result<()> {
+        if !matches!(Source::new(&self.path), Source::File(_)) {
+            bail!("`--watch` requires the module to be a local file");
+        }
+        #[allow(unused_mut)]
+        let mut paths = vec![self.path.clone()];
+        #[cfg(feature = "wasi")]
+        if self.watch_dirs {
+            paths.extend(self.wasi.host_dirs());
+        }
+        watch::watch(&paths)
+    }
+
+    /// Whether the guest is metered with `--gas`.
+    fn is_metered(&self) -> bool {
+        #[cfg(feature = "compiler")]
+        return self.gas.is_some();
+        #[cfg(not(feature = "compiler"))]
+        return false;
+    }
+
     fn get_module(&self) -> Result<Module> {
-        let contents = std::fs::read(self.path.clone())?;
+        let source = Source::new(&self.path);
+        let contents = source.read(self.max_download_size)?;
+        if let Some(sha256) = &self.sha256 {
+            verify_sha256(&contents, sha256)?;
+        }
+        if contents.starts_with(WEBC_MAGIC) {
+            bail!(
+                "WebC containers aren't supported yet, package the application as a \
+                 `.tar.gz` bundle with a `wasmer.toml` manifest"
+            );
+        }
         #[cfg(feature = "native")]
         {
             if wasmer_engine_native::NativeArtifact::is_deserializable(&contents) {
                 let engine = wasmer_engine_native::Native::headless().engine();
                 let store = Store::new(&engine);
-                let module = unsafe { Module::deserialize_from_file(&store, &self.path)? };
+                if self.is_metered() {
+                    bail!("`--gas` can't be used with a precompiled module");
+                }
+                let module = unsafe { deserialize(&store, source, &contents)? };
                 return Ok(module);
             }
         }
@@ -195,17 +422,45 @@ impl Run {
             if wasmer_engine_jit::JITArtifact::is_deserializable(&contents) {
                 let engine = wasmer_engine_jit::JIT::headless().engine();
                 let store = Store::new(&engine);
-                let module = unsafe { Module::deserialize_from_file(&store, &self.path)? };
+                if self.is_metered() {
+                    bail!("`--gas` can't be used with a precompiled module");
+                }
+                let module = unsafe { deserialize(&store, source, &contents)? };
                 return Ok(module);
             }
         }
+        // The guest can only be interrupted, metered or profiled if the
+        // module is compiled with the `Interrupt`, `Metering` or `Profiling`
+        // middleware.
+        #[cfg(feature = "compiler")]
+        let (store, engine_type, compiler_type) = {
+            let mut middlewares: Vec<Arc<dyn ModuleMiddleware>> = vec![];
+            if self.timeout.is_some() {
+                middlewares.push(Arc::new(wasmer_middlewares::Interrupt::new()));
+            }
+            if let Some(limit) = self.gas {
+                middlewares.push(gas::metering(limit));
+            }
+            if self.profile.is_some() {
+                middlewares.push(Arc::new(wasmer_middlewares::Profiling::new()));
+            }
+            if middlewares.is_empty() {
+                self.store.get_store()?
+            } else {
+                self.store.get_store_with_middlewares(middlewares)?
+            }
+        };
+        #[cfg(not(feature = "compiler"))]
         let (store, engine_type, compiler_type) = self.store.get_store()?;
         #[cfg(feature = "cache")]
-        let module_result: Result<Module> = if !self.disable_cache && contents.len() > 0x1000 {
-            self.get_module_from_cache(&store, &contents, &engine_type, &compiler_type)
-        } else {
-            Module::new(&store, &contents).map_err(|e| e.into())
-        };
+        // The gas limit is compiled into the metered modules, so they aren't
+        // cached.
+        let module_result: Result<Module> =
+            if !self.disable_cache && !self.is_metered() && contents.len() > 0x1000 {
+                self.get_module_from_cache(&store, &contents, &engine_type, &compiler_type)
+            } else {
+                Module::new(&store, &contents).map_err(|e| e.into())
+            };
         #[cfg(not(feature = "cache"))]
         let module_result = Module::new(&store, &contents);
 
@@ -243,8 +498,12 @@ impl Run {
             .and_then(|key| Hash::from_str(&key).ok())
             .unwrap_or_else(|| Hash::generate(&contents));
         match unsafe { cache.load(&store, hash) } {
-            Ok(module) => Ok(module),
+            Ok(module) => {
+                CacheCounters::record(&get_cache_dir(), true);
+                Ok(module)
+            }
             Err(e) => {
+                CacheCounters::record(&get_cache_dir(), false);
                 match e {
                     DeserializeError::Io(_) => {
                         // Do not notify on IO errors
@@ -270,6 +529,14 @@ impl Run {
     ) -> Result<FileSystemCache> {
         let mut cache_dir_root = get_cache_dir();
         cache_dir_root.push(compiler_type.to_string());
+        // The modules compiled with the `Interrupt` middleware are kept apart.
+        if self.timeout.is_some() {
+            cache_dir_root.push("interruptible");
+        }
+        #[cfg(feature = "compiler")]
+        if self.profile.is_some() {
+            cache_dir_root.push("profiled");
+        }
         let mut cache = FileSystemCache::new(cache_dir_root)?;
         // Important: Native files need to have a `.dll` extension on Windows, otherwise
         // they will not load, so we just add an extension always to make it easier
@@ -358,34 +625,103 @@ impl Run {
         let invoke_args = args
             .iter()
             .zip(func_ty.params().iter())
-            .map(|(arg, param_type)| match param_type {
-                ValType::I32 => {
-                    Ok(Val::I32(arg.parse().map_err(|_| {
-                        anyhow!("Can't convert `{}` into a i32", arg)
-                    })?))
-                }
-                ValType::I64 => {
-                    Ok(Val::I64(arg.parse().map_err(|_| {
-                        anyhow!("Can't convert `{}` into a i64", arg)
-                    })?))
-                }
-                ValType::F32 => {
-                    Ok(Val::F32(arg.parse().map_err(|_| {
-                        anyhow!("Can't convert `{}` into a f32", arg)
-                    })?))
-                }
-                ValType::F64 => {
-                    Ok(Val::F64(arg.parse().map_err(|_| {
-                        anyhow!("Can't convert `{}` into a f64", arg)
-                    })?))
-                }
-                _ => Err(anyhow!(
-                    "Don't know how to convert {} into {:?}",
-                    arg,
-                    param_type
-                )),
-            })
+            .map(|(arg, param_type)| invoke::parse_arg(arg, *param_type))
             .collect::<Result<Vec<_>>>()?;
         Ok(func.call(&invoke_args)?)
     }
 }
+
+/// What watches the guest while it runs: the `--timeout` watchdog, the
+/// `--gas` meter, the `--profile` profiler and the signal forwarder, and
+/// the `--json` report of the run.
+pub struct Monitors<'a> {
+    watchdog: Option<&'a Watchdog>,
+    #[cfg(feature = "compiler")]
+    gas_meter: Option<&'a GasMeter>,
+    #[cfg(feature = "compiler")]
+    profiler: Option<&'a Profiler>,
+    #[cfg(unix)]
+    signals: Option<&'a SignalForwarder>,
+    report: Option<&'a Report>,
+}
+
+impl<'a> Monitors<'a> {
+    /// Watch this instance.
+    pub fn watch(&self, instance: &Instance) {
+        if let Some(watchdog) = self.watchdog {
+            watchdog.watch(instance);
+        }
+        #[cfg(feature = "compiler")]
+        if let Some(gas_meter) = self.gas_meter {
+            gas_meter.watch(instance);
+        }
+        #[cfg(feature = "compiler")]
+        if let Some(profiler) = self.profiler {
+            profiler.watch(instance);
+        }
+        #[cfg(unix)]
+        if let Some(signals) = self.signals {
+            signals.watch(instance);
+        }
+    }
+
+    /// Report on the guest once it has run, exiting with a dedicated code if
+    /// it `failed` because it timed out, ran out of gas or was stopped by a
+    /// signal.
+    pub fn finish(&self, failed: bool) {
+        #[cfg(feature = "compiler")]
+        if let Some(profiler) = self.profiler {
+            if let Err(e) = profiler.finish() {
+                warning!("failed to write the profile: {}", e);
+            }
+        }
+        #[cfg(feature = "compiler")]
+        if let Some(gas_meter) = self.gas_meter {
+            gas_meter.report();
+            if let Some(report) = self.report {
+                report.set("gas_used", gas_meter.gas_used());
+            }
+            if failed {
+                gas_meter.exit_if_exhausted(self.report);
+            }
+        }
+        if failed {
+            if let Some(watchdog) = self.watchdog {
+                watchdog.exit_if_timed_out(self.report);
+            }
+            #[cfg(unix)]
+            if let Some(signals) = self.signals {
+                signals.exit_if_signaled(self.report);
+            }
+        }
+    }
+
+    /// Report on the guest which exited with `exit_code`, and exit with it.
+    pub fn exit(&self, exit_code: u32) -> ! {
+        // Only the lowest byte of the exit code is kept on Unix, so a code
+        // like 256 would otherwise look like a success.
+        let exit_code = if cfg!(unix) && exit_code > 255 {
+            255
+        } else {
+            exit_code as i32
+        };
+        self.finish(false);
+        if let Some(report) = self.report {
+            report.print(exit_code, None);
+        }
+        std::process::exit(exit_code);
+    }
+}
+
+/// Load a precompiled module.
+#[cfg(any(feature = "native", feature = "jit"))]
+unsafe fn deserialize(
+    store: &Store,
+    source: Source,
+    contents: &[u8],
+) -> Result<Module, DeserializeError> {
+    match source {
+        Source::File(path) => Module::deserialize_from_file(store, path),
+        _ => Module::deserialize(store, contents),
+    }
+}
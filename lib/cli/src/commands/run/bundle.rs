@@ -0,0 +1,176 @@
+//! Running the command of a bundle: a `.tar.gz` archive of a wasm
+//! application with a `wasmer.toml` manifest, like:
+//!
+//! ```toml
+//! [command]
+//! module = "bin/app.wasm"
+//! args = ["--config", "/etc/app.toml"]
+//!
+//! [env]
+//! LOG = "info"
+//!
+//! # The directories of the bundle mapped in the guest.
+//! [fs]
+//! "/etc" = "etc"
+//! ```
+//!
+//! The bundle is unpacked once to the cache directory, keyed by its hash.
+//!
+//! Only gzipped tarballs are bundles: WebC containers aren't supported yet.
+
+use crate::common::get_cache_dir;
+use anyhow::{bail, Context, Result};
+use flate2::read::GzDecoder;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+/// The name of the manifest at the root of the bundle.
+const MANIFEST: &str = "wasmer.toml";
+
+/// The magic number of the gzip format.
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+
+/// The manifest of a bundle.
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    /// The command to run.
+    pub command: BundleCommand,
+    /// The environment variables of the command.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// The directories of the bundle, by their path in the guest.
+    #[serde(default)]
+    pub fs: BTreeMap<String, PathBuf>,
+}
+
+/// The command of a bundle.
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct BundleCommand {
+    /// The module to run.
+    pub module: PathBuf,
+    /// The arguments given to the module before the ones of `wasmer run`.
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+/// An unpacked bundle.
+#[derive(Debug)]
+pub struct Bundle {
+    /// The directory where the bundle is unpacked.
+    pub root: PathBuf,
+    /// The manifest of the bundle.
+    pub manifest: Manifest,
+}
+
+impl Bundle {
+    /// Whether these are the first bytes of a bundle.
+    pub fn is_bundle(bytes: &[u8]) -> bool {
+        bytes.starts_with(GZIP_MAGIC)
+    }
+
+    /// Unpack the bundle in the cache directory, unless it already is.
+    pub fn unpack(contents: &[u8]) -> Result<Self> {
+        let hash = hex::encode(Sha256::digest(contents));
+        let root = get_cache_dir().join("bundles").join(hash);
+        if !root.join(MANIFEST).exists() {
+            let parent = root.parent().unwrap();
+            fs::create_dir_all(parent)?;
+            // Unpack to a temporary directory first, so that an interrupted
+            // unpacking is never mistaken for an unpacked bundle.
+            let unpacking = tempfile::tempdir_in(parent)?;
+            tar::Archive::new(GzDecoder::new(contents))
+                .unpack(unpacking.path())
+                .context("failed to unpack the bundle")?;
+            if !unpacking.path().join(MANIFEST).is_file() {
+                bail!("the bundle has no `{}` manifest at its root", MANIFEST);
+            }
+            if root.exists() {
+                fs::remove_dir_all(&root)?;
+            }
+            fs::rename(unpacking.into_path(), &root)?;
+        }
+        let manifest = fs::read_to_string(root.join(MANIFEST))?;
+        let manifest = Manifest::parse(&manifest)
+            .with_context(|| format!("invalid `{}` manifest", MANIFEST))?;
+        Ok(Self { root, manifest })
+    }
+
+    /// The path of the module to run.
+    pub fn module(&self) -> PathBuf {
+        self.root.join(&self.manifest.command.module)
+    }
+
+    /// The directories of the bundle, by their path in the guest.
+    pub fn mapped_dirs(&self) -> impl Iterator<Item = (String, PathBuf)> + '_ {
+        self.manifest
+            .fs
+            .iter()
+            .map(move |(guest, dir)| (guest.clone(), self.root.join(dir)))
+    }
+}
+
+impl Manifest {
+    /// Parse a manifest, checking that its paths stay in the bundle.
+    pub fn parse(manifest: &str) -> Result<Self> {
+        let manifest: Self = toml::from_str(manifest)?;
+        for path in std::iter::once(&manifest.command.module).chain(manifest.fs.values()) {
+            if !is_inside_bundle(path) {
+                bail!("the path `{}` is outside of the bundle", path.display());
+            }
+        }
+        Ok(manifest)
+    }
+}
+
+/// Whether a relative path stays in the bundle.
+fn is_inside_bundle(path: &Path) -> bool {
+    path.components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+/// Read the first bytes of a file, to tell whether it's a bundle.
+pub fn starts_like_bundle(path: &Path) -> bool {
+    let mut magic = [0; 2];
+    fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .map_or(false, |_| Bundle::is_bundle(&magic))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = Manifest::parse(
+            r#"
+            [command]
+            module = "bin/app.wasm"
+            args = ["--verbose"]
+
+            [env]
+            LOG = "info"
+
+            [fs]
+            "/data" = "data"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(manifest.command.module, PathBuf::from("bin/app.wasm"));
+        assert_eq!(manifest.command.args, vec!["--verbose".to_string()]);
+        assert_eq!(manifest.env["LOG"], "info");
+        assert_eq!(manifest.fs["/data"], PathBuf::from("data"));
+
+        assert!(Manifest::parse("[command]\nmodule = \"../app.wasm\"").is_err());
+        assert!(
+            Manifest::parse("[command]\nmodule = \"app.wasm\"\n[fs]\n\"/\" = \"/etc\"").is_err()
+        );
+        assert!(Manifest::parse("[command]\nmodule = \"app.wasm\"\nentry = 1").is_err());
+    }
+}
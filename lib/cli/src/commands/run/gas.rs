@@ -0,0 +1,83 @@
+//! Metering the guest of `wasmer run` with `--gas`.
+
+use crate::output::Report;
+use std::cell::RefCell;
+use std::sync::Arc;
+use wasmer::wasmparser::Operator;
+use wasmer::{Instance, ModuleMiddleware};
+use wasmer_middlewares::metering::{get_remaining_points, MeteringPoints};
+use wasmer_middlewares::Metering;
+
+/// The exit code when the guest runs out of gas.
+pub const GAS_EXHAUSTED_EXIT_CODE: i32 = 125;
+
+/// Every operator costs one point of gas.
+fn cost_function(_: &Operator) -> u64 {
+    1
+}
+
+/// The middleware metering the module, with `limit` points of gas.
+pub fn metering(limit: u64) -> Arc<dyn ModuleMiddleware> {
+    Arc::new(Metering::new(limit, cost_function))
+}
+
+/// The gas given to the guest, and how much it has used.
+pub struct GasMeter {
+    limit: u64,
+    print_gas_used: bool,
+    instance: RefCell<Option<Instance>>,
+}
+
+impl GasMeter {
+    /// Create a meter giving `limit` points of gas to the guest.
+    pub fn new(limit: u64, print_gas_used: bool) -> Self {
+        Self {
+            limit,
+            print_gas_used,
+            instance: RefCell::new(None),
+        }
+    }
+
+    /// Track the gas used by this instance.
+    pub fn watch(&self, instance: &Instance) {
+        *self.instance.borrow_mut() = Some(instance.clone());
+    }
+
+    /// The gas used so far, and whether it is exhausted.
+    fn used(&self) -> Option<(u64, bool)> {
+        let instance = self.instance.borrow();
+        Some(match get_remaining_points(instance.as_ref()?) {
+            MeteringPoints::Remaining(points) => (self.limit - points, false),
+            MeteringPoints::Exhausted => (self.limit, true),
+        })
+    }
+
+    /// The gas used so far, if the guest was instantiated.
+    pub fn gas_used(&self) -> Option<u64> {
+        self.used().map(|(used, _)| used)
+    }
+
+    /// Print the gas used, if requested with `--print-gas-used`.
+    pub fn report(&self) {
+        if !self.print_gas_used {
+            return;
+        }
+        if let Some((used, _)) = self.used() {
+            eprintln!("gas used: {}", used);
+        }
+    }
+
+    /// Exit with [`GAS_EXHAUSTED_EXIT_CODE`] if the guest failed because it
+    /// ran out of gas.
+    pub fn exit_if_exhausted(&self, report: Option<&Report>) {
+        if let Some((_, true)) = self.used() {
+            use colored::*;
+            let message = format!("the guest ran out of gas ({} points)", self.limit);
+            eprintln!("{}: {}", "error".red().bold(), message);
+            if let Some(report) = report {
+                report.print(GAS_EXHAUSTED_EXIT_CODE, Some(("gas_exhausted", message)));
+            }
+            std::process::exit(GAS_EXHAUSTED_EXIT_CODE);
+        }
+    }
+}
@@ -0,0 +1,261 @@
+//! Parsing the arguments of `wasmer run --invoke` and printing its results.
+
+use anyhow::{anyhow, bail, Result};
+use wasmer::{ExternRef, Val, ValType};
+
+/// Parse an integer literal: decimal or `0x` hexadecimal, optionally
+/// negative, with `_` separators like in the text format.
+///
+/// Unsigned values up to `2^bits - 1` are accepted and wrap around, so that
+/// `4294967295` is the same `i32` as `-1`.
+fn parse_int(arg: &str, bits: u32) -> Option<u64> {
+    let literal = arg.replace('_', "");
+    let (negative, literal) = match literal.strip_prefix('-') {
+        Some(rest) => (true, rest.to_string()),
+        None => (false, literal.trim_start_matches('+').to_string()),
+    };
+    let magnitude = match literal.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok()?,
+        None => literal.parse::<u64>().ok()?,
+    };
+    let mask = if bits == 64 {
+        u64::MAX
+    } else {
+        (1 << bits) - 1
+    };
+    if negative {
+        // The smallest value is `-2^(bits - 1)`.
+        if magnitude > 1 << (bits - 1) {
+            return None;
+        }
+        Some(magnitude.wrapping_neg() & mask)
+    } else if magnitude > mask {
+        None
+    } else {
+        Some(magnitude)
+    }
+}
+
+/// Parse a float literal: decimal, `inf`, `nan` or `nan:0x<payload>`.
+fn parse_float<F, B>(arg: &str, from_bits: B, nan_bits: u64, payload_mask: u64) -> Option<F>
+where
+    F: std::str::FromStr,
+    B: Fn(u64) -> F,
+{
+    let literal = arg.replace('_', "");
+    let (sign, literal) = match literal.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, literal.trim_start_matches('+')),
+    };
+    let sign_bit = if sign {
+        nan_bits.next_power_of_two()
+    } else {
+        0
+    };
+    if let Some(payload) = literal.strip_prefix("nan:0x") {
+        let payload = u64::from_str_radix(payload, 16).ok()?;
+        if payload == 0 || payload & !payload_mask != 0 {
+            return None;
+        }
+        return Some(from_bits(sign_bit | (nan_bits & !payload_mask) | payload));
+    }
+    let value = match literal {
+        "nan" => return Some(from_bits(sign_bit | nan_bits)),
+        "inf" | "infinity" => "inf",
+        _ => literal,
+    };
+    let signed = if sign {
+        format!("-{}", value)
+    } else {
+        value.to_string()
+    };
+    signed.parse().ok()
+}
+
+/// Parse a `v128` literal: a `0x` hexadecimal or decimal 128-bit integer,
+/// or the lanes of a shape like `i32x4:1,2,3,4`.
+fn parse_v128(arg: &str) -> Option<u128> {
+    let (shape, lanes) = match arg.find(':') {
+        Some(position) => (&arg[..position], &arg[position + 1..]),
+        None => {
+            let literal = arg.replace('_', "");
+            return match literal.strip_prefix("0x") {
+                Some(hex) => u128::from_str_radix(hex, 16).ok(),
+                None => literal.parse().ok(),
+            };
+        }
+    };
+    let lanes = lanes.split(',').map(str::trim).collect::<Vec<_>>();
+    let (count, bits) = match shape {
+        "i8x16" => (16, 8),
+        "i16x8" => (8, 16),
+        "i32x4" | "f32x4" => (4, 32),
+        "i64x2" | "f64x2" => (2, 64),
+        _ => return None,
+    };
+    if lanes.len() != count {
+        return None;
+    }
+    let mut value = 0u128;
+    for (index, lane) in lanes.iter().enumerate() {
+        let bits_of_lane = match shape {
+            "f32x4" => parse_float(lane, |b| f32::from_bits(b as u32), 0x7fc0_0000, 0x7f_ffff)?
+                .to_bits() as u64,
+            "f64x2" => parse_float(
+                lane,
+                f64::from_bits,
+                0x7ff8_0000_0000_0000,
+                0xf_ffff_ffff_ffff,
+            )?
+            .to_bits(),
+            _ => parse_int(lane, bits)?,
+        };
+        value |= (bits_of_lane as u128) << (index as u32 * bits);
+    }
+    Some(value)
+}
+
+/// Parse an argument of `--invoke` as a value of the parameter type.
+pub fn parse_arg(arg: &str, ty: ValType) -> Result<Val> {
+    let value = match ty {
+        ValType::I32 => parse_int(arg, 32).map(|v| Val::I32(v as u32 as i32)),
+        ValType::I64 => parse_int(arg, 64).map(|v| Val::I64(v as i64)),
+        ValType::F32 => {
+            parse_float(arg, |b| f32::from_bits(b as u32), 0x7fc0_0000, 0x7f_ffff).map(Val::F32)
+        }
+        ValType::F64 => parse_float(
+            arg,
+            f64::from_bits,
+            0x7ff8_0000_0000_0000,
+            0xf_ffff_ffff_ffff,
+        )
+        .map(Val::F64),
+        ValType::V128 => parse_v128(arg).map(Val::V128),
+        ValType::ExternRef | ValType::FuncRef => {
+            if arg != "null" {
+                bail!("Only `null` can be passed as a {}", type_name(ty));
+            }
+            Some(Val::null())
+        }
+    };
+    value.ok_or_else(|| anyhow!("Can't convert `{}` into a {}", arg, type_name(ty)))
+}
+
+/// The name of the type in the text format.
+fn type_name(ty: ValType) -> String {
+    ty.to_string().to_lowercase()
+}
+
+/// Format a result of `--invoke` with its type, like `i32:42`.
+pub fn format_result(value: &Val) -> String {
+    match value {
+        Val::I32(v) => format!("i32:{}", v),
+        Val::I64(v) => format!("i64:{}", v),
+        Val::F32(v) if v.is_finite() => format!("f32:{}", v),
+        Val::F32(v) => format!("f32:{}", format_special(*v as f64)),
+        Val::F64(v) if v.is_finite() => format!("f64:{}", v),
+        Val::F64(v) => format!("f64:{}", format_special(*v)),
+        Val::V128(v) => format!("v128:0x{:032x}", v),
+        Val::ExternRef(r) if r.ptr_eq(&ExternRef::null()) => "externref:null".to_string(),
+        Val::ExternRef(_) => "externref".to_string(),
+        Val::FuncRef(_) => "funcref".to_string(),
+    }
+}
+
+/// Format an infinity or a NaN like in the text format.
+fn format_special(value: f64) -> &'static str {
+    if value.is_nan() {
+        "nan"
+    } else if value > 0.0 {
+        "inf"
+    } else {
+        "-inf"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_int_args() {
+        assert_eq!(parse_arg("42", ValType::I32).unwrap().unwrap_i32(), 42);
+        assert_eq!(parse_arg("-0x10", ValType::I32).unwrap().unwrap_i32(), -16);
+        assert_eq!(
+            parse_arg("4294967295", ValType::I32).unwrap().unwrap_i32(),
+            -1
+        );
+        assert_eq!(
+            parse_arg("-2147483648", ValType::I32).unwrap().unwrap_i32(),
+            i32::MIN
+        );
+        assert!(parse_arg("4294967296", ValType::I32).is_err());
+        assert_eq!(
+            parse_arg("0xffff_ffff_ffff_ffff", ValType::I64)
+                .unwrap()
+                .unwrap_i64(),
+            -1
+        );
+        assert_eq!(
+            parse_arg("-9223372036854775808", ValType::I64)
+                .unwrap()
+                .unwrap_i64(),
+            i64::MIN
+        );
+        assert_eq!(
+            parse_arg("1.5", ValType::I32).unwrap_err().to_string(),
+            "Can't convert `1.5` into a i32"
+        );
+    }
+
+    #[test]
+    fn test_parse_float_args() {
+        assert_eq!(parse_arg("1.5", ValType::F32).unwrap().unwrap_f32(), 1.5);
+        assert_eq!(parse_arg("-2e3", ValType::F64).unwrap().unwrap_f64(), -2e3);
+        assert_eq!(
+            parse_arg("-inf", ValType::F64).unwrap().unwrap_f64(),
+            f64::NEG_INFINITY
+        );
+        assert!(parse_arg("nan", ValType::F32)
+            .unwrap()
+            .unwrap_f32()
+            .is_nan());
+        assert_eq!(
+            parse_arg("nan:0x1", ValType::F32)
+                .unwrap()
+                .unwrap_f32()
+                .to_bits(),
+            0x7f80_0001
+        );
+        assert!(parse_arg("nan:0x0", ValType::F64).is_err());
+    }
+
+    #[test]
+    fn test_parse_v128_args() {
+        assert_eq!(parse_arg("0x1", ValType::V128).unwrap().unwrap_v128(), 1);
+        assert_eq!(
+            parse_arg("i32x4:1,2,3,-1", ValType::V128)
+                .unwrap()
+                .unwrap_v128(),
+            0xffff_ffff_0000_0003_0000_0002_0000_0001
+        );
+        assert_eq!(
+            parse_arg("f64x2:1,0", ValType::V128).unwrap().unwrap_v128(),
+            0x3ff0_0000_0000_0000
+        );
+        assert!(parse_arg("i32x4:1,2,3", ValType::V128).is_err());
+    }
+
+    #[test]
+    fn test_format_results() {
+        assert_eq!(format_result(&Val::I32(-1)), "i32:-1");
+        assert_eq!(format_result(&Val::F32(0.1)), "f32:0.1");
+        assert_eq!(format_result(&Val::F64(f64::INFINITY)), "f64:inf");
+        assert_eq!(format_result(&Val::F64(f64::NAN)), "f64:nan");
+        assert_eq!(
+            format_result(&Val::V128(1)),
+            "v128:0x00000000000000000000000000000001"
+        );
+        assert_eq!(format_result(&Val::null()), "externref:null");
+    }
+}
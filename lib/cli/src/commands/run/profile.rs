@@ -0,0 +1,251 @@
+//! Profiling the guest of `wasmer run` with `--profile`.
+
+use anyhow::{bail, Result};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use wasmer::{Instance, Module};
+use wasmer_middlewares::ProfilingHandle;
+use wasmer_types::FunctionIndex;
+
+/// How often the call stack is sampled.
+const SAMPLING_INTERVAL: Duration = Duration::from_millis(1);
+
+/// The format of the profile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileFormat {
+    /// Folded call stacks, the input of `flamegraph.pl` and `inferno-flamegraph`.
+    Flamegraph,
+    /// A sampled profile for <https://www.speedscope.app>.
+    Json,
+}
+
+impl ProfileFormat {
+    fn default_output(self) -> &'static str {
+        match self {
+            Self::Flamegraph => "wasmer-profile.folded",
+            Self::Json => "wasmer-profile.speedscope.json",
+        }
+    }
+}
+
+impl FromStr for ProfileFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(format: &str) -> Result<Self> {
+        match format {
+            "flamegraph" => Ok(Self::Flamegraph),
+            "json" => Ok(Self::Json),
+            _ => bail!(
+                "Unknown profile format `{}`, expected `flamegraph` or `json`",
+                format
+            ),
+        }
+    }
+}
+
+/// The samples of the call stack, taken by a thread.
+struct Sampler {
+    module: Module,
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<HashMap<Vec<FunctionIndex>, u64>>,
+}
+
+/// Samples the call stack of the guest, and writes the profile once it has
+/// run.
+pub struct Profiler {
+    format: ProfileFormat,
+    output: PathBuf,
+    sampler: RefCell<Option<Sampler>>,
+}
+
+impl Profiler {
+    /// Create a profiler writing to `output`, or to a default file in the
+    /// current directory.
+    pub fn new(format: ProfileFormat, output: Option<PathBuf>) -> Self {
+        Self {
+            format,
+            output: output.unwrap_or_else(|| PathBuf::from(format.default_output())),
+            sampler: RefCell::new(None),
+        }
+    }
+
+    /// Start sampling this instance, if its module was compiled with the
+    /// [`Profiling`](wasmer_middlewares::Profiling) middleware.
+    pub fn watch(&self, instance: &Instance) {
+        let handle = match ProfilingHandle::new(instance) {
+            Ok(handle) => handle,
+            Err(_) => return,
+        };
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let stop = stop.clone();
+            thread::spawn(move || {
+                let mut samples = HashMap::new();
+                while !stop.load(Ordering::SeqCst) {
+                    let stack = handle.call_stack();
+                    if !stack.is_empty() {
+                        *samples.entry(stack).or_insert(0) += 1;
+                    }
+                    thread::sleep(SAMPLING_INTERVAL);
+                }
+                samples
+            })
+        };
+        *self.sampler.borrow_mut() = Some(Sampler {
+            module: instance.module().clone(),
+            stop,
+            thread,
+        });
+    }
+
+    /// Stop sampling and write the profile.
+    pub fn finish(&self) -> Result<()> {
+        let sampler = match self.sampler.borrow_mut().take() {
+            Some(sampler) => sampler,
+            None => bail!("the module can't be profiled"),
+        };
+        sampler.stop.store(true, Ordering::SeqCst);
+        let samples = sampler
+            .thread
+            .join()
+            .map_err(|_| anyhow::anyhow!("the sampling thread panicked"))?;
+
+        let module_name = sampler.module.name().unwrap_or("wasm").to_string();
+        let function_names = &sampler.module.info().function_names;
+        let mut stacks = samples
+            .into_iter()
+            .map(|(stack, count)| {
+                let names = stack
+                    .into_iter()
+                    .map(|index| {
+                        function_names
+                            .get(&index)
+                            .cloned()
+                            .unwrap_or_else(|| format!("func[{}]", index.as_u32()))
+                    })
+                    .collect::<Vec<_>>();
+                (names, count)
+            })
+            .collect::<Vec<_>>();
+        // The hottest stacks first.
+        stacks.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        let contents = match self.format {
+            ProfileFormat::Flamegraph => folded_stacks(&module_name, &stacks),
+            ProfileFormat::Json => speedscope(&module_name, &stacks),
+        };
+        write_profile(&self.output, contents)?;
+        let total: u64 = stacks.iter().map(|(_, count)| count).sum();
+        eprintln!(
+            "Profile of {} samples written to `{}`.",
+            total,
+            self.output.display()
+        );
+        Ok(())
+    }
+}
+
+/// The folded call stacks, one line per stack from the module to the
+/// running function, with its number of samples.
+fn folded_stacks(module_name: &str, stacks: &[(Vec<String>, u64)]) -> String {
+    stacks
+        .iter()
+        .map(|(names, count)| format!("{};{} {}\n", module_name, names.join(";"), count))
+        .collect()
+}
+
+/// A sampled profile in the speedscope file format, with one sample per
+/// call stack weighted by the time spent in it.
+fn speedscope(module_name: &str, stacks: &[(Vec<String>, u64)]) -> String {
+    let interval = SAMPLING_INTERVAL.as_secs_f64() * 1000.0;
+    let total: u64 = stacks.iter().map(|(_, count)| count).sum();
+    // The functions, indexed by the samples.
+    let mut frames: Vec<&str> = Vec::new();
+    let samples = stacks
+        .iter()
+        .map(|(names, _)| {
+            names
+                .iter()
+                .map(|name| {
+                    frames
+                        .iter()
+                        .position(|frame| *frame == name.as_str())
+                        .unwrap_or_else(|| {
+                            frames.push(name);
+                            frames.len() - 1
+                        })
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let profile = serde_json::json!({
+        "$schema": "https://www.speedscope.app/file-format-schema.json",
+        "exporter": format!("wasmer {}", crate::VERSION),
+        "name": module_name,
+        "shared": {
+            "frames": frames
+                .iter()
+                .map(|name| serde_json::json!({ "name": name }))
+                .collect::<Vec<_>>(),
+        },
+        "profiles": [{
+            "type": "sampled",
+            "name": module_name,
+            "unit": "milliseconds",
+            "startValue": 0,
+            "endValue": total as f64 * interval,
+            "samples": samples,
+            "weights": stacks
+                .iter()
+                .map(|(_, count)| *count as f64 * interval)
+                .collect::<Vec<_>>(),
+        }],
+    });
+    profile.to_string()
+}
+
+fn write_profile(path: &Path, contents: String) -> Result<()> {
+    fs::write(path, contents)
+        .map_err(|e| anyhow::anyhow!("failed to write the profile to `{}`: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_formats() {
+        assert_eq!(
+            "flamegraph".parse::<ProfileFormat>().unwrap(),
+            ProfileFormat::Flamegraph
+        );
+        assert!("svg".parse::<ProfileFormat>().is_err());
+
+        let stacks = vec![
+            (
+                vec!["main".to_string(), "fib".to_string(), "fib".to_string()],
+                3,
+            ),
+            (vec!["main".to_string()], 1),
+        ];
+        assert_eq!(
+            folded_stacks("app.wasm", &stacks),
+            "app.wasm;main;fib;fib 3\napp.wasm;main 1\n"
+        );
+        let profile: serde_json::Value =
+            serde_json::from_str(&speedscope("app.wasm", &stacks)).unwrap();
+        assert_eq!(profile["shared"]["frames"][1]["name"], "fib");
+        assert_eq!(
+            profile["profiles"][0]["samples"],
+            serde_json::json!([[0, 1, 1], [0]])
+        );
+        assert_eq!(profile["profiles"][0]["weights"][0], 3.0);
+    }
+}
@@ -0,0 +1,161 @@
+//! Forwarding SIGINT and SIGTERM to the guest of `wasmer run`.
+
+use crate::output::Report;
+use anyhow::Result;
+use std::io;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::Arc;
+#[cfg(feature = "compiler")]
+use std::sync::Mutex;
+use std::thread;
+use wasmer::Instance;
+#[cfg(feature = "compiler")]
+use wasmer_middlewares::InterruptHandle;
+
+/// How long the guest has to reach an interruption check before the
+/// process is exited, unless another signal is received.
+#[cfg(feature = "compiler")]
+const GRACE_PERIOD_MS: libc::c_int = 1000;
+
+/// The exit code of a process stopped by `signal`, like shells report it.
+pub fn signal_exit_code(signal: i32) -> i32 {
+    128 + signal
+}
+
+/// The write end of the pipe the signal handler writes the signals to.
+static SIGNAL_PIPE: AtomicI32 = AtomicI32::new(-1);
+
+extern "C" fn on_signal(signal: libc::c_int) {
+    let signal = signal as u8;
+    // `write` is async-signal-safe, unlike almost everything else.
+    unsafe {
+        libc::write(
+            SIGNAL_PIPE.load(Ordering::SeqCst),
+            &signal as *const u8 as *const libc::c_void,
+            1,
+        );
+    }
+}
+
+/// Read a signal number written by the signal handler, waiting for at most
+/// `timeout_ms` milliseconds, or forever if it's negative.
+fn read_signal(fd: libc::c_int, timeout_ms: libc::c_int) -> Option<i32> {
+    loop {
+        let mut poll_fd = libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let ready = unsafe { libc::poll(&mut poll_fd, 1, timeout_ms) };
+        if ready == 0 {
+            return None;
+        }
+        let mut signal = 0u8;
+        if ready > 0
+            && unsafe { libc::read(fd, &mut signal as *mut u8 as *mut libc::c_void, 1) } == 1
+        {
+            return Some(i32::from(signal));
+        }
+        if io::Error::last_os_error().kind() != io::ErrorKind::Interrupted {
+            return None;
+        }
+    }
+}
+
+/// A thread waiting for SIGINT or SIGTERM to stop the guest.
+///
+/// The guest is interrupted if its module was compiled with the
+/// [`Interrupt`](wasmer_middlewares::Interrupt) middleware, as with
+/// `--timeout`, and its instance has been registered with
+/// [`SignalForwarder::watch`]. Otherwise, if the guest doesn't stop within
+/// a grace period, or on a second signal, the process exits with
+/// [`signal_exit_code`].
+pub struct SignalForwarder {
+    signal: Arc<AtomicI32>,
+    #[cfg(feature = "compiler")]
+    handle: Arc<Mutex<Option<InterruptHandle>>>,
+}
+
+impl SignalForwarder {
+    /// Handle SIGINT and SIGTERM from now on.
+    pub fn start(report: Option<Report>) -> Result<Self> {
+        let mut fds = [0; 2];
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        let (read_fd, write_fd) = (fds[0], fds[1]);
+        SIGNAL_PIPE.store(write_fd, Ordering::SeqCst);
+        unsafe {
+            libc::signal(libc::SIGINT, on_signal as libc::sighandler_t);
+            libc::signal(libc::SIGTERM, on_signal as libc::sighandler_t);
+        }
+
+        let signal = Arc::new(AtomicI32::new(0));
+        #[cfg(feature = "compiler")]
+        let handle = Arc::new(Mutex::new(None::<InterruptHandle>));
+        {
+            let signal = signal.clone();
+            #[cfg(feature = "compiler")]
+            let handle = handle.clone();
+            thread::spawn(move || {
+                let received = match read_signal(read_fd, -1) {
+                    Some(received) => received,
+                    None => return,
+                };
+                signal.store(received, Ordering::SeqCst);
+                #[cfg(feature = "compiler")]
+                {
+                    if let Some(handle) = handle.lock().unwrap().as_ref() {
+                        handle.interrupt();
+                        // The process exits once the interrupted guest
+                        // returns, or after the grace period.
+                        read_signal(read_fd, GRACE_PERIOD_MS);
+                    }
+                }
+                exit_signaled(received, report.as_ref());
+            });
+        }
+
+        Ok(Self {
+            signal,
+            #[cfg(feature = "compiler")]
+            handle,
+        })
+    }
+
+    /// Interrupt this instance on a signal, instead of exiting the process
+    /// right away.
+    #[allow(unused_variables)]
+    pub fn watch(&self, instance: &Instance) {
+        #[cfg(feature = "compiler")]
+        {
+            if let Ok(handle) = InterruptHandle::new(instance) {
+                *self.handle.lock().unwrap() = Some(handle);
+            }
+        }
+    }
+
+    /// Exit with [`signal_exit_code`] if the guest failed because it was
+    /// interrupted by a signal.
+    pub fn exit_if_signaled(&self, report: Option<&Report>) {
+        match self.signal.load(Ordering::SeqCst) {
+            0 => {}
+            signal => exit_signaled(signal, report),
+        }
+    }
+}
+
+fn exit_signaled(signal: i32, report: Option<&Report>) -> ! {
+    use colored::*;
+    let name = match signal {
+        libc::SIGINT => "SIGINT",
+        libc::SIGTERM => "SIGTERM",
+        _ => "a signal",
+    };
+    let message = format!("the guest was stopped by {}", name);
+    eprintln!("{}: {}", "error".red().bold(), message);
+    if let Some(report) = report {
+        report.print(signal_exit_code(signal), Some(("signal", message)));
+    }
+    std::process::exit(signal_exit_code(signal));
+}
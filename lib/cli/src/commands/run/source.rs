@@ -0,0 +1,120 @@
+//! Reading the module to run from a file, from stdin with `-`, or from an
+//! `http(s)://` URL.
+
+use anyhow::{bail, Context, Result};
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Where the module comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source<'a> {
+    /// A local file.
+    File(&'a Path),
+    /// The standard input.
+    Stdin,
+    /// A URL to download.
+    Url(&'a str),
+}
+
+impl<'a> Source<'a> {
+    /// The source designated by the `FILE` argument of `wasmer run`.
+    pub fn new(path: &'a Path) -> Self {
+        match path.to_str() {
+            Some("-") => Self::Stdin,
+            Some(url) if url.starts_with("http://") || url.starts_with("https://") => {
+                Self::Url(url)
+            }
+            _ => Self::File(path),
+        }
+    }
+
+    /// Read the module, downloading at most `max_download_size` bytes.
+    pub fn read(&self, max_download_size: u64) -> Result<Vec<u8>> {
+        match self {
+            Self::File(path) => Ok(std::fs::read(path)?),
+            Self::Stdin => {
+                let mut contents = Vec::new();
+                std::io::stdin()
+                    .read_to_end(&mut contents)
+                    .context("failed to read the module from stdin")?;
+                Ok(contents)
+            }
+            Self::Url(url) => download(url, max_download_size),
+        }
+    }
+}
+
+/// Download the module with `curl`, like `wasmer self-update`.
+fn download(url: &str, max_size: u64) -> Result<Vec<u8>> {
+    let mut curl = Command::new("curl")
+        .arg(url)
+        .arg("-sSfL")
+        .arg("--max-filesize")
+        .arg(max_size.to_string())
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("failed to run `curl` to download the module")?;
+    let mut contents = Vec::new();
+    // The size isn't always known in advance, so it is also checked while
+    // reading.
+    curl.stdout
+        .take()
+        .unwrap()
+        .take(max_size + 1)
+        .read_to_end(&mut contents)?;
+    if contents.len() as u64 > max_size {
+        let _ = curl.kill();
+        bail!(
+            "the module at `{}` is larger than the `--max-download-size` of {} bytes",
+            url,
+            max_size
+        );
+    }
+    let status = curl.wait()?;
+    if !status.success() {
+        bail!("failed to download the module at `{}` ({})", url, status);
+    }
+    Ok(contents)
+}
+
+/// Check that the SHA-256 of the contents is the `expected` hexadecimal
+/// digest.
+pub fn verify_sha256(contents: &[u8], expected: &str) -> Result<()> {
+    let actual = hex::encode(Sha256::digest(contents));
+    if !actual.eq_ignore_ascii_case(expected.trim()) {
+        bail!(
+            "the SHA-256 of the module is `{}`, but `{}` was expected",
+            actual,
+            expected
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source() {
+        assert_eq!(Source::new(Path::new("-")), Source::Stdin);
+        assert_eq!(
+            Source::new(Path::new("https://example.com/a.wasm")),
+            Source::Url("https://example.com/a.wasm")
+        );
+        assert_eq!(
+            Source::new(Path::new("a.wasm")),
+            Source::File(Path::new("a.wasm"))
+        );
+    }
+
+    #[test]
+    fn test_verify_sha256() {
+        let empty = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        assert!(verify_sha256(b"", empty).is_ok());
+        assert!(verify_sha256(b"", &empty.to_uppercase()).is_ok());
+        assert!(verify_sha256(b"wasm", empty).is_err());
+    }
+}
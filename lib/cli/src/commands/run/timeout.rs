@@ -0,0 +1,155 @@
+//! Enforcing the `--timeout` of `wasmer run`.
+
+use crate::output::Report;
+use anyhow::{bail, Result};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+#[cfg(feature = "compiler")]
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use wasmer::Instance;
+#[cfg(feature = "compiler")]
+use wasmer_middlewares::InterruptHandle;
+
+/// The exit code when the guest times out, like `timeout(1)`.
+pub const TIMEOUT_EXIT_CODE: i32 = 124;
+
+/// How long the guest has to reach an interruption check before the
+/// process is exited, for example when it is blocked in a host call.
+#[cfg(feature = "compiler")]
+const GRACE_PERIOD: Duration = Duration::from_secs(1);
+
+/// Parse a duration like `500ms`, `1.5s`, `2m` or `1h`. A number without a
+/// unit is a number of seconds.
+pub fn parse_duration(duration: &str) -> Result<Duration> {
+    let duration = duration.trim();
+    let (value, unit_secs) = if let Some(value) = duration.strip_suffix("ms") {
+        (value, 0.001)
+    } else if let Some(value) = duration.strip_suffix('s') {
+        (value, 1.0)
+    } else if let Some(value) = duration.strip_suffix('m') {
+        (value, 60.0)
+    } else if let Some(value) = duration.strip_suffix('h') {
+        (value, 3600.0)
+    } else {
+        (duration, 1.0)
+    };
+    match value.trim().parse::<f64>() {
+        Ok(value) if value.is_finite() && value > 0.0 => {
+            Ok(Duration::from_secs_f64(value * unit_secs))
+        }
+        _ => bail!(
+            "Invalid duration `{}`, expected a positive number with an optional unit (`ms`, `s`, `m` or `h`)",
+            duration
+        ),
+    }
+}
+
+/// A thread stopping the guest once the timeout expires.
+///
+/// The guest is interrupted if its module was compiled with the
+/// [`Interrupt`](wasmer_middlewares::Interrupt) middleware and its instance
+/// has been registered with [`Watchdog::watch`]. Otherwise, or if the guest
+/// doesn't stop within a grace period, the process exits with
+/// [`TIMEOUT_EXIT_CODE`].
+pub struct Watchdog {
+    timeout: Duration,
+    timed_out: Arc<AtomicBool>,
+    #[cfg(feature = "compiler")]
+    handle: Arc<Mutex<Option<InterruptHandle>>>,
+    /// Stops the thread when dropped.
+    _stop: Sender<()>,
+}
+
+impl Watchdog {
+    /// Start the countdown.
+    pub fn start(timeout: Duration, report: Option<Report>) -> Self {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let timed_out = Arc::new(AtomicBool::new(false));
+        #[cfg(feature = "compiler")]
+        let handle = Arc::new(Mutex::new(None::<InterruptHandle>));
+
+        {
+            let timed_out = timed_out.clone();
+            #[cfg(feature = "compiler")]
+            let handle = handle.clone();
+            thread::spawn(move || {
+                match stopped.recv_timeout(timeout) {
+                    Err(RecvTimeoutError::Timeout) => {}
+                    // The guest has finished.
+                    _ => return,
+                }
+                timed_out.store(true, Ordering::SeqCst);
+                #[cfg(feature = "compiler")]
+                {
+                    if let Some(handle) = handle.lock().unwrap().as_ref() {
+                        handle.interrupt();
+                        if let Err(RecvTimeoutError::Disconnected) =
+                            stopped.recv_timeout(GRACE_PERIOD)
+                        {
+                            return;
+                        }
+                    }
+                }
+                exit_timed_out(timeout, report.as_ref());
+            });
+        }
+
+        Self {
+            timeout,
+            timed_out,
+            #[cfg(feature = "compiler")]
+            handle,
+            _stop: stop,
+        }
+    }
+
+    /// Interrupt this instance when the timeout expires, instead of exiting
+    /// the process right away.
+    #[allow(unused_variables)]
+    pub fn watch(&self, instance: &Instance) {
+        #[cfg(feature = "compiler")]
+        {
+            if let Ok(handle) = InterruptHandle::new(instance) {
+                *self.handle.lock().unwrap() = Some(handle);
+            }
+        }
+    }
+
+    /// Exit with [`TIMEOUT_EXIT_CODE`] if the guest failed because it was
+    /// interrupted.
+    pub fn exit_if_timed_out(&self, report: Option<&Report>) {
+        if self.timed_out.load(Ordering::SeqCst) {
+            exit_timed_out(self.timeout, report);
+        }
+    }
+}
+
+fn exit_timed_out(timeout: Duration, report: Option<&Report>) -> ! {
+    use colored::*;
+    let message = format!("the guest timed out after {:?}", timeout);
+    eprintln!("{}: {}", "error".red().bold(), message);
+    if let Some(report) = report {
+        report.print(TIMEOUT_EXIT_CODE, Some(("timeout", message)));
+    }
+    std::process::exit(TIMEOUT_EXIT_CODE);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("2").unwrap(), Duration::from_secs(2));
+        assert_eq!(parse_duration("1.5s").unwrap(), Duration::from_millis(1500));
+        assert_eq!(parse_duration("250ms").unwrap(), Duration::from_millis(250));
+        assert_eq!(parse_duration("2m").unwrap(), Duration::from_secs(120));
+        assert_eq!(parse_duration("1h").unwrap(), Duration::from_secs(3600));
+        assert!(parse_duration("0").is_err());
+        assert!(parse_duration("-1s").is_err());
+        assert!(parse_duration("ten").is_err());
+    }
+}
@@ -1,8 +1,12 @@
-use crate::utils::{parse_envvar, parse_mapdir};
-use anyhow::{Context, Result};
+use super::bundle::Bundle;
+use super::Monitors;
+use crate::utils::{parse_dir_with_rights, parse_envvar, parse_mapdir_with_rights, DirRights};
+use anyhow::{bail, Context, Result};
 use std::path::PathBuf;
 use wasmer::{Instance, Module};
-use wasmer_wasi::{get_wasi_version, WasiError, WasiState, WasiVersion};
+use wasmer_wasi::{
+    get_wasi_version, NetworkPolicy, StraceTracer, WasiError, WasiState, WasiVersion,
+};
 
 use clap::Clap;
 
@@ -10,17 +14,37 @@ use clap::Clap;
 /// WASI Options
 pub struct Wasi {
     /// WASI pre-opened directory
-    #[clap(long = "dir", name = "DIR", multiple = true, group = "wasi")]
-    pre_opened_directories: Vec<PathBuf>,
+    ///
+    /// Append `:ro` to make it read-only, or a subset of `rwcd` to only
+    /// allow reading, writing, creating or deleting, like `data:rc`.
+    #[clap(long = "dir", name = "DIR", multiple = true, group = "wasi", parse(try_from_str = parse_dir_with_rights))]
+    pre_opened_directories: Vec<(PathBuf, DirRights)>,
 
     /// Map a host directory to a different location for the wasm module
-    #[clap(long = "mapdir", name = "GUEST_DIR:HOST_DIR", multiple = true, parse(try_from_str = parse_mapdir))]
-    mapped_dirs: Vec<(String, PathBuf)>,
+    ///
+    /// The rights can be restricted like with `--dir`, for example
+    /// `/data:data:ro`.
+    #[clap(long = "mapdir", name = "GUEST_DIR:HOST_DIR", multiple = true, parse(try_from_str = parse_mapdir_with_rights))]
+    mapped_dirs: Vec<(String, PathBuf, DirRights)>,
 
     /// Pass custom environment variables
     #[clap(long = "env", name = "KEY=VALUE", multiple = true, parse(try_from_str = parse_envvar))]
     env_vars: Vec<(String, String)>,
 
+    /// Allow the module to open sockets
+    #[clap(long = "enable-network")]
+    enable_network: bool,
+
+    /// Only allow the sockets to the hosts and ports matching a pattern like
+    /// `example.com:443` or `*.example.com:*` (by default, everything is
+    /// allowed with `--enable-network`)
+    #[clap(long = "net-allow", name = "HOST:PORT", multiple = true)]
+    net_allow: Vec<String>,
+
+    /// Print the WASI syscalls made by the module to stderr, like `strace`
+    #[clap(long = "wasi-trace")]
+    trace: bool,
+
     /// Enable experimental IO devices
     #[cfg(feature = "experimental-io-devices")]
     #[clap(long = "enable-experimental-io-devices")]
@@ -43,16 +67,88 @@ impl Wasi {
         get_wasi_version(&module, false).is_some()
     }
 
+    /// Give the environment variables and the directories of the bundle to
+    /// the module, the options of the command line taking precedence.
+    ///
+    /// The directories of the bundle are read-only, since the bundle is
+    /// shared by its runs.
+    pub fn add_bundle(&mut self, bundle: &Bundle) {
+        let env_vars = bundle
+            .manifest
+            .env
+            .iter()
+            .filter(|(key, _)| !self.env_vars.iter().any(|(k, _)| k == *key))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect::<Vec<_>>();
+        self.env_vars.splice(0..0, env_vars);
+        let mapped_dirs = bundle
+            .mapped_dirs()
+            .filter(|(guest, _)| !self.mapped_dirs.iter().any(|(g, _, _)| g == guest))
+            .map(|(guest, dir)| (guest, dir, DirRights::READ_ONLY))
+            .collect::<Vec<_>>();
+        self.mapped_dirs.extend(mapped_dirs);
+    }
+
+    /// The host directories given to the module.
+    pub fn host_dirs(&self) -> Vec<PathBuf> {
+        self.pre_opened_directories
+            .iter()
+            .map(|(dir, _)| dir.clone())
+            .chain(self.mapped_dirs.iter().map(|(_, dir, _)| dir.clone()))
+            .collect()
+    }
+
     /// Helper function for executing Wasi from the `Run` command.
-    pub fn execute(&self, module: Module, program_name: String, args: Vec<String>) -> Result<()> {
+    pub fn execute(
+        &self,
+        module: Module,
+        program_name: String,
+        args: Vec<String>,
+        stub_missing_imports: bool,
+        monitors: &Monitors,
+    ) -> Result<()> {
         let args = args.iter().cloned().map(|arg| arg.into_bytes());
 
         let mut wasi_state_builder = WasiState::new(program_name);
-        wasi_state_builder
-            .args(args)
-            .envs(self.env_vars.clone())
-            .preopen_dirs(self.pre_opened_directories.clone())?
-            .map_dirs(self.mapped_dirs.clone())?;
+        wasi_state_builder.args(args).envs(self.env_vars.clone());
+        let dirs = self
+            .pre_opened_directories
+            .iter()
+            .map(|(dir, rights)| (None, dir, rights))
+            .chain(
+                self.mapped_dirs
+                    .iter()
+                    .map(|(alias, dir, rights)| (Some(alias), dir, rights)),
+            );
+        for (alias, dir, rights) in dirs {
+            wasi_state_builder.preopen(|p| {
+                p.directory(dir)
+                    .read(rights.read)
+                    .write(rights.write)
+                    .create(rights.create)
+                    .delete(rights.delete)
+                    .no_delete(!rights.delete);
+                if let Some(alias) = alias {
+                    p.alias(alias);
+                }
+                p
+            })?;
+        }
+
+        if self.enable_network {
+            if self.net_allow.is_empty() {
+                wasi_state_builder.network_policy(NetworkPolicy::allow_all());
+            }
+            for pattern in &self.net_allow {
+                wasi_state_builder.allow_network(pattern)?;
+            }
+        } else if !self.net_allow.is_empty() {
+            bail!("`--net-allow` requires `--enable-network`");
+        }
+
+        if self.trace {
+            wasi_state_builder.tracer(Box::new(StraceTracer::new()));
+        }
 
         #[cfg(feature = "experimental-io-devices")]
         {
@@ -64,7 +160,12 @@ impl Wasi {
 
         let mut wasi_env = wasi_state_builder.finalize()?;
         let import_object = wasi_env.import_object(&module)?;
-        let instance = Instance::new(&module, &import_object)?;
+        let instance = if stub_missing_imports {
+            module.instantiate_with_stubs(&import_object)?
+        } else {
+            Instance::new(&module, &import_object)?
+        };
+        monitors.watch(&instance);
 
         let start = instance.exports.get_function("_start")?;
         let result = start.call(&[]);
@@ -75,7 +176,7 @@ impl Wasi {
                 let err: anyhow::Error = match err.downcast::<WasiError>() {
                     Ok(WasiError::Exit(exit_code)) => {
                         // We should exit with the provided exit code
-                        std::process::exit(exit_code as _);
+                        monitors.exit(exit_code);
                     }
                     Ok(err) => err.into(),
                     Err(err) => err.into(),
@@ -0,0 +1,93 @@
+//! Re-running the guest of `wasmer run --watch` when its module changes.
+
+use anyhow::{Context, Result};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// Set in the environment of the child process running the guest, so that
+/// it doesn't watch too.
+const WATCHED_ENV_VAR: &str = "WASMER_WATCHED";
+
+/// How often the watched paths are checked.
+const POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+/// Whether this process is running the guest for a `--watch` parent.
+pub fn is_watched() -> bool {
+    env::var_os(WATCHED_ENV_VAR).is_some()
+}
+
+/// The size and modification time of the files under the watched paths.
+type Snapshot = Vec<(PathBuf, u64, Option<SystemTime>)>;
+
+fn snapshot(paths: &[PathBuf]) -> Snapshot {
+    fn visit(path: &Path, snapshot: &mut Snapshot) {
+        let metadata = match fs::metadata(path) {
+            Ok(metadata) => metadata,
+            // The file may be rewritten at this very moment.
+            Err(_) => return snapshot.push((path.to_path_buf(), 0, None)),
+        };
+        if metadata.is_dir() {
+            if let Ok(entries) = fs::read_dir(path) {
+                let mut children = entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .collect::<Vec<_>>();
+                children.sort();
+                for child in children {
+                    visit(&child, snapshot);
+                }
+            }
+        } else {
+            snapshot.push((path.to_path_buf(), metadata.len(), metadata.modified().ok()));
+        }
+    }
+
+    let mut snapshot = Vec::new();
+    for path in paths {
+        visit(path, &mut snapshot);
+    }
+    snapshot
+}
+
+/// Run the same `wasmer` command in a child process, restarting it each
+/// time one of the `paths` changes. The compiled modules are reused through
+/// the cache.
+pub fn watch(paths: &[PathBuf]) -> Result<()> {
+    let exe = env::current_exe().context("failed to find the wasmer executable")?;
+    let spawn = || -> Result<Child> {
+        Command::new(&exe)
+            .args(env::args_os().skip(1))
+            .env(WATCHED_ENV_VAR, "1")
+            .spawn()
+            .context("failed to start the guest")
+    };
+
+    let mut last = snapshot(paths);
+    let mut child = Some(spawn()?);
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        if let Some(running) = &mut child {
+            if let Some(status) = running.try_wait()? {
+                eprintln!("[watch] the guest exited ({}), waiting for changes", status);
+                child = None;
+            }
+        }
+
+        let current = snapshot(paths);
+        if current == last {
+            continue;
+        }
+        // Wait for the writes to settle before restarting.
+        thread::sleep(POLL_INTERVAL);
+        last = snapshot(paths);
+        if let Some(mut running) = child.take() {
+            let _ = running.kill();
+            running.wait()?;
+        }
+        eprintln!("[watch] change detected, restarting");
+        child = Some(spawn()?);
+    }
+}
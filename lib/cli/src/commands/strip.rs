@@ -0,0 +1,194 @@
+//! Removes the custom sections, like the names and the DWARF debug info,
+//! from a wasm file or from a serialized module.
+
+use anyhow::{bail, Context, Result};
+use bytesize::ByteSize;
+use clap::Clap;
+use std::fs;
+use std::path::PathBuf;
+use std::str;
+#[cfg(feature = "jit")]
+use wasmer::*;
+
+#[derive(Debug, Clap)]
+/// The options for the `wasmer strip` subcommand
+pub struct Strip {
+    /// The wasm file, or the module serialized with `wasmer compile`, to strip
+    #[clap(name = "FILE", parse(from_os_str))]
+    path: PathBuf,
+
+    /// Write the stripped file there instead of replacing FILE
+    #[clap(short = 'o', long = "output", parse(from_os_str))]
+    output: Option<PathBuf>,
+
+    /// Only remove the DWARF debug sections, named `.debug_*`
+    #[clap(long = "debug-only")]
+    debug_only: bool,
+
+    /// Keep the custom sections with this name, like `name` or `producers`
+    #[clap(long = "keep", name = "SECTION", multiple = true, number_of_values = 1)]
+    keep: Vec<String>,
+}
+
+impl Strip {
+    /// Runs logic for the `strip` subcommand
+    pub fn execute(&self) -> Result<()> {
+        self.inner_execute()
+            .context(format!("failed to strip `{}`", self.path.display()))
+    }
+
+    fn inner_execute(&self) -> Result<()> {
+        let contents = fs::read(&self.path)?;
+        let mut removed = Vec::new();
+        let stripped = self.strip(&contents, |name| {
+            let strip = self.should_strip(name);
+            if strip && !removed.iter().any(|removed| removed == name) {
+                removed.push(name.to_string());
+            }
+            strip
+        })?;
+        let output = self.output.as_ref().unwrap_or(&self.path);
+        fs::write(output, &stripped)
+            .with_context(|| format!("failed to write `{}`", output.display()))?;
+
+        let saved = contents.len().saturating_sub(stripped.len());
+        eprintln!(
+            "Stripped `{}`: {} -> {}, saved {} ({:.1}%).",
+            output.display(),
+            ByteSize(contents.len() as u64),
+            ByteSize(stripped.len() as u64),
+            ByteSize(saved as u64),
+            saved as f64 * 100.0 / contents.len().max(1) as f64
+        );
+        if removed.is_empty() {
+            eprintln!("No custom sections removed.");
+        } else {
+            eprintln!("Removed custom sections: {}", removed.join(", "));
+        }
+        Ok(())
+    }
+
+    fn should_strip(&self, name: &str) -> bool {
+        !self.keep.iter().any(|keep| keep == name)
+            && (!self.debug_only || name.starts_with(".debug_"))
+    }
+
+    fn strip(&self, contents: &[u8], strip: impl FnMut(&str) -> bool) -> Result<Vec<u8>> {
+        if contents.starts_with(b"\0asm") {
+            return strip_wasm(contents, strip);
+        }
+        #[cfg(feature = "native")]
+        {
+            if wasmer_engine_native::NativeArtifact::is_deserializable(contents) {
+                bail!("a native shared object can't be stripped: strip the wasm file before compiling it");
+            }
+        }
+        #[cfg(feature = "jit")]
+        {
+            if wasmer_engine_jit::JITArtifact::is_deserializable(contents) {
+                return strip_jit_artifact(contents, strip);
+            }
+        }
+        bail!("the file is neither a wasm file nor a serialized module")
+    }
+}
+
+/// Remove the custom sections for which `strip` returns `true` from a wasm
+/// binary, keeping the other sections as they are.
+fn strip_wasm(bytes: &[u8], mut strip: impl FnMut(&str) -> bool) -> Result<Vec<u8>> {
+    // The magic number and the version.
+    const HEADER_LEN: usize = 8;
+    if bytes.len() < HEADER_LEN {
+        bail!("the wasm file is truncated");
+    }
+    let mut stripped = bytes[..HEADER_LEN].to_vec();
+    let mut offset = HEADER_LEN;
+    while offset < bytes.len() {
+        let start = offset;
+        let id = bytes[offset];
+        offset += 1;
+        let size = read_u32(bytes, &mut offset)? as usize;
+        let end = offset
+            .checked_add(size)
+            .filter(|end| *end <= bytes.len())
+            .context("a section ends past the end of the file")?;
+        if id == 0 {
+            let mut name_offset = offset;
+            let name_len = read_u32(bytes, &mut name_offset)? as usize;
+            let name = name_offset
+                .checked_add(name_len)
+                .filter(|name_end| *name_end <= end)
+                .map(|name_end| &bytes[name_offset..name_end])
+                .context("a custom section name is out of bounds")?;
+            let name = str::from_utf8(name).context("a custom section name isn't UTF-8")?;
+            if strip(name) {
+                offset = end;
+                continue;
+            }
+        }
+        stripped.extend_from_slice(&bytes[start..end]);
+        offset = end;
+    }
+    Ok(stripped)
+}
+
+/// Read an unsigned LEB128 integer.
+fn read_u32(bytes: &[u8], offset: &mut usize) -> Result<u32> {
+    let mut value = 0u32;
+    for shift in (0..35).step_by(7) {
+        let byte = *bytes.get(*offset).context("the wasm file is truncated")?;
+        *offset += 1;
+        value |= u32::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    bail!("invalid LEB128 integer")
+}
+
+/// Remove the custom sections for which `strip` returns `true` from a
+/// module serialized by the JIT engine, and its function names if `name`
+/// is stripped.
+#[cfg(feature = "jit")]
+fn strip_jit_artifact(contents: &[u8], mut strip: impl FnMut(&str) -> bool) -> Result<Vec<u8>> {
+    let engine = wasmer_engine_jit::JIT::headless().engine();
+    let store = Store::new(&engine);
+    let mut module = unsafe { Module::deserialize(&store, contents)? };
+    let has_names = module.info().name.is_some() || !module.info().function_names.is_empty();
+    if has_names && strip("name") {
+        module = module.strip_names()?;
+    }
+    let module = module.strip_custom_sections(|name, _| strip(name))?;
+    Ok(module.serialize()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_wasm() {
+        let custom_section = |name: &str, data: &[u8]| {
+            let mut section = vec![0, (1 + name.len() + data.len()) as u8, name.len() as u8];
+            section.extend_from_slice(name.as_bytes());
+            section.extend_from_slice(data);
+            section
+        };
+        let mut module = b"\0asm\x01\0\0\0".to_vec();
+        module.extend(custom_section("producers", b"wasmer"));
+        // An empty type section.
+        module.extend_from_slice(&[1, 1, 0]);
+        module.extend(custom_section(".debug_info", &[0; 16]));
+
+        let stripped = strip_wasm(&module, |name| name.starts_with(".debug_")).unwrap();
+        let mut expected = b"\0asm\x01\0\0\0".to_vec();
+        expected.extend(custom_section("producers", b"wasmer"));
+        expected.extend_from_slice(&[1, 1, 0]);
+        assert_eq!(stripped, expected);
+
+        let stripped = strip_wasm(&module, |_| true).unwrap();
+        assert_eq!(stripped, b"\0asm\x01\0\0\0\x01\x01\0");
+
+        assert!(strip_wasm(&module[..module.len() - 1], |_| true).is_err());
+    }
+}
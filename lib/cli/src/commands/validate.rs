@@ -1,3 +1,6 @@
+#[cfg(feature = "compiler")]
+use crate::common::{proposal_enabled, proposal_has_flag, required_proposals};
+use crate::output::OutputOptions;
 use crate::store::StoreOptions;
 use anyhow::{Context, Result};
 use clap::Clap;
@@ -13,19 +16,88 @@ pub struct Validate {
 
     #[clap(flatten)]
     store: StoreOptions,
+
+    #[clap(flatten)]
+    output_options: OutputOptions,
 }
 
 impl Validate {
     /// Runs logic for the `validate` subcommand
     pub fn execute(&self) -> Result<()> {
-        self.inner_execute()
-            .context(format!("failed to validate `{}`", self.path.display()))
+        let report = self.output_options.report("validate");
+        if let Some(report) = &report {
+            report.set("file", &self.path);
+        }
+        let result = self
+            .inner_execute()
+            .context(format!("failed to validate `{}`", self.path.display()));
+        if let Some(report) = &report {
+            report.finish(&result);
+        }
+        result
     }
     fn inner_execute(&self) -> Result<()> {
         let (store, _engine_type, _compiler_type) = self.store.get_store()?;
         let module_contents = std::fs::read(&self.path)?;
-        Module::validate(&store, &module_contents)?;
+        if let Err(error) = Module::validate(&store, &module_contents) {
+            return Err(self.explain(error, &module_contents));
+        }
         eprintln!("Validation passed for `{}`.", self.path.display());
         Ok(())
     }
+
+    /// Tell which proposals the module needs, if it only failed to validate
+    /// because they aren't enabled.
+    #[cfg(feature = "compiler")]
+    fn explain(&self, error: CompileError, module_contents: &[u8]) -> anyhow::Error {
+        let features = match self.store.get_enabled_features() {
+            Ok(features) => features,
+            Err(_) => return error.into(),
+        };
+        let missing = match required_proposals(module_contents) {
+            Ok(required) => required
+                .into_iter()
+                .filter(|proposal| !proposal_enabled(&features, proposal))
+                .collect::<Vec<_>>(),
+            Err(_) => return error.into(),
+        };
+        if missing.is_empty() {
+            return error.into();
+        }
+
+        let proposals = missing
+            .iter()
+            .map(|proposal| format!("`{}`", proposal))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let flags = missing
+            .iter()
+            .filter(|proposal| proposal_has_flag(proposal))
+            .map(|proposal| format!("`--enable-{}`", proposal))
+            .collect::<Vec<_>>();
+        let (proposals, pronoun) = if missing.len() == 1 {
+            (format!("the {} proposal", proposals), "it")
+        } else {
+            (format!("the {} proposals", proposals), "them")
+        };
+        let hint = if flags.len() == missing.len() {
+            format!(
+                "the module requires {}: enable {} with {}",
+                proposals,
+                pronoun,
+                flags.join(" ")
+            )
+        } else {
+            format!(
+                "the module requires {}, which can't be enabled from the command line yet",
+                proposals
+            )
+        };
+        anyhow::Error::new(error).context(hint)
+    }
+
+    #[cfg(not(feature = "compiler"))]
+    fn explain(&self, error: CompileError, _module_contents: &[u8]) -> anyhow::Error {
+        error.into()
+    }
 }
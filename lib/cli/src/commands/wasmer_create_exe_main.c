@@ -6,6 +6,17 @@
 #include <stdlib.h>
 #include <string.h>
 
+// An entry of a directory tree embedded with `--volume`.
+typedef struct {
+  unsigned int volume;
+  const char *path;
+  int is_dir;
+  const unsigned char *data;
+  size_t size;
+} wasmer_volume_entry_t;
+
+#include "my_volumes.h"
+
 #define own
 
 // TODO: make this define templated so that the Rust code can toggle it on/off
@@ -43,6 +54,131 @@ static void pass_mapdir_arg(wasi_config_t *wasi_config, char *mapdir) {
   free(dir);
 }
 
+#ifdef WASMER_VOLUMES
+#ifdef _WIN32
+#include <direct.h>
+#define volume_mkdir(path) _mkdir(path)
+#define volume_rmdir(path) _rmdir(path)
+#else
+#include <sys/stat.h>
+#include <unistd.h>
+#define volume_mkdir(path) mkdir(path, 0755)
+#define volume_rmdir(path) rmdir(path)
+#endif
+
+// The temporary directory the volumes are unpacked into.
+static char *volumes_root = NULL;
+
+// The path of an entry of a volume, or of the volume itself if `path` is
+// `NULL`. The result must be freed.
+static char *volume_path(unsigned int volume, const char *path) {
+  size_t len = strlen(volumes_root) + (path ? strlen(path) : 0) + 16;
+  char *out = (char *)malloc(len);
+  if (path) {
+    snprintf(out, len, "%s/%u/%s", volumes_root, volume, path);
+  } else {
+    snprintf(out, len, "%s/%u", volumes_root, volume);
+  }
+  return out;
+}
+
+// Remove the unpacked volumes. The files created by the guest are left
+// behind, along with the directories containing them.
+static void remove_volumes(void) {
+  for (size_t i = wasmer_volume_entry_count; i > 0; --i) {
+    const wasmer_volume_entry_t *entry = &wasmer_volume_entries[i - 1];
+    char *path = volume_path(entry->volume, entry->path);
+    if (entry->is_dir) {
+      volume_rmdir(path);
+    } else {
+      remove(path);
+    }
+    free(path);
+  }
+  for (size_t i = 0; i < wasmer_volume_count; ++i) {
+    char *path = volume_path(i, NULL);
+    volume_rmdir(path);
+    free(path);
+  }
+  volume_rmdir(volumes_root);
+  free(volumes_root);
+}
+
+static char *create_volumes_root(void) {
+#ifdef _WIN32
+  char *root = _tempnam(NULL, "wasmer-volumes-");
+  if (root && _mkdir(root) != 0) {
+    free(root);
+    return NULL;
+  }
+  return root;
+#else
+  const char *tmp = getenv("TMPDIR");
+  if (!tmp || !*tmp) {
+    tmp = "/tmp";
+  }
+  size_t len = strlen(tmp) + sizeof("/wasmer-volumes-XXXXXX");
+  char *root = (char *)malloc(len);
+  snprintf(root, len, "%s/wasmer-volumes-XXXXXX", tmp);
+  if (!mkdtemp(root)) {
+    free(root);
+    return NULL;
+  }
+  return root;
+#endif
+}
+
+// Unpack the volumes embedded in the executable and map them into the
+// filesystem of the guest. They are removed when the program exits, so the
+// changes made by the guest don't persist.
+static void unpack_volumes(wasi_config_t *wasi_config) {
+  volumes_root = create_volumes_root();
+  if (!volumes_root) {
+    fprintf(stderr, "Failed to create a temporary directory for the volumes\n");
+    exit(-1);
+  }
+  atexit(remove_volumes);
+
+  for (size_t i = 0; i < wasmer_volume_count; ++i) {
+    char *path = volume_path(i, NULL);
+    if (volume_mkdir(path) != 0) {
+      fprintf(stderr, "Failed to create the directory `%s`\n", path);
+      exit(-1);
+    }
+    free(path);
+  }
+  for (size_t i = 0; i < wasmer_volume_entry_count; ++i) {
+    const wasmer_volume_entry_t *entry = &wasmer_volume_entries[i];
+    char *path = volume_path(entry->volume, entry->path);
+    if (entry->is_dir) {
+      if (volume_mkdir(path) != 0) {
+        fprintf(stderr, "Failed to create the directory `%s`\n", path);
+        exit(-1);
+      }
+    } else {
+      FILE *file = fopen(path, "wb");
+      if (!file || (entry->size > 0 &&
+                    fwrite(entry->data, 1, entry->size, file) != entry->size)) {
+        fprintf(stderr, "Failed to write the file `%s`\n", path);
+        exit(-1);
+      }
+      fclose(file);
+    }
+    free(path);
+  }
+  for (size_t i = 0; i < wasmer_volume_count; ++i) {
+    char *path = volume_path(i, NULL);
+    if (!wasi_config_mapdir(wasi_config, wasmer_volume_aliases[i], path)) {
+      fprintf(stderr, "Failed to map the volume `%s`\n",
+              wasmer_volume_aliases[i]);
+      print_wasmer_error();
+      exit(-1);
+    }
+    free(path);
+  }
+}
+#endif
+
 // We try to parse out `--dir` and `--mapdir` ahead of time and process those
 // specially. All other arguments are passed to the guest program.
 static void handle_arguments(wasi_config_t *wasi_config, int argc,
@@ -105,6 +241,9 @@ int main(int argc, char *argv[]) {
 
 #ifdef WASI
   wasi_config_t *wasi_config = wasi_config_new(argv[0]);
+#ifdef WASMER_VOLUMES
+  unpack_volumes(wasi_config);
+#endif
   handle_arguments(wasi_config, argc, argv);
 
   wasi_env_t *wasi_env = wasi_env_new(wasi_config);
@@ -1,16 +1,17 @@
 //! Runs a .wast WebAssembly test suites
 use crate::store::StoreOptions;
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use clap::Clap;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 use wasmer_wast::Wast as WastSpectest;
 
 #[derive(Debug, Clap)]
 /// The options for the `wasmer wast` subcommand
 pub struct Wast {
-    /// Wast file to run
-    #[clap(name = "FILE", parse(from_os_str))]
-    path: PathBuf,
+    /// Wast files to run, or directories to run the `.wast` files of
+    #[clap(name = "FILE", parse(from_os_str), required = true, multiple = true)]
+    paths: Vec<PathBuf>,
 
     #[clap(flatten)]
     store: StoreOptions,
@@ -21,17 +22,62 @@ pub struct Wast {
 }
 
 impl Wast {
-    /// Runs logic for the `validate` subcommand
+    /// Runs logic for the `wast` subcommand
     pub fn execute(&self) -> Result<()> {
-        self.inner_execute()
-            .context(format!("failed to test the wast `{}`", self.path.display()))
+        let mut files = Vec::new();
+        for path in &self.paths {
+            collect_wast_files(path, &mut files)
+                .with_context(|| format!("failed to read `{}`", path.display()))?;
+        }
+        if files.is_empty() {
+            bail!("no `.wast` files found");
+        }
+
+        let mut failed = 0;
+        for file in &files {
+            if let Err(e) = self.run_file(file) {
+                failed += 1;
+                eprintln!("✘ {}\n{:?}", file.display(), e);
+                if self.fail_fast {
+                    break;
+                }
+            } else {
+                eprintln!("✔ {}", file.display());
+            }
+        }
+        if failed > 0 {
+            bail!("{} of {} wast files failed", failed, files.len());
+        }
+        eprintln!("Wast tests succeeded for {} files.", files.len());
+        Ok(())
     }
-    fn inner_execute(&self) -> Result<()> {
+
+    fn run_file(&self, path: &Path) -> Result<()> {
         let (store, _engine_name, _compiler_name) = self.store.get_store()?;
         let mut wast = WastSpectest::new_with_spectest(store);
         wast.fail_fast = self.fail_fast;
-        wast.run_file(&self.path).with_context(|| "tests failed")?;
-        eprintln!("Wast tests succeeded for `{}`.", self.path.display());
-        Ok(())
+        wast.run_file(path).with_context(|| "tests failed")
+    }
+}
+
+/// Collect the file at `path`, or the `.wast` files in the directory at
+/// `path` and its subdirectories, in a stable order.
+fn collect_wast_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    if !path.is_dir() {
+        if !path.exists() {
+            bail!("no such file or directory");
+        }
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+    let mut entries = fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+    for entry in entries {
+        if entry.is_dir() || entry.extension().map_or(false, |ext| ext == "wast") {
+            collect_wast_files(&entry, files)?;
+        }
     }
+    Ok(())
 }
@@ -34,6 +34,99 @@ pub struct WasmFeatures {
     pub all: bool,
 }
 
+/// The WebAssembly proposals which a module can require, named like the
+/// `--enable-*` flags.
+#[cfg(feature = "compiler")]
+pub const PROPOSALS: &[&str] = &[
+    "threads",
+    "simd",
+    "reference-types",
+    "multi-value",
+    "bulk-memory",
+    "tail-call",
+    "module-linking",
+    "multi-memory",
+    "memory64",
+    "exceptions",
+];
+
+#[cfg(feature = "compiler")]
+fn proposal_flag<'a>(
+    features: &'a mut wasmer_compiler::wasmparser::WasmFeatures,
+    proposal: &str,
+) -> &'a mut bool {
+    match proposal {
+        "threads" => &mut features.threads,
+        "simd" => &mut features.simd,
+        "reference-types" => &mut features.reference_types,
+        "multi-value" => &mut features.multi_value,
+        "bulk-memory" => &mut features.bulk_memory,
+        "tail-call" => &mut features.tail_call,
+        "module-linking" => &mut features.module_linking,
+        "multi-memory" => &mut features.multi_memory,
+        "memory64" => &mut features.memory64,
+        "exceptions" => &mut features.exceptions,
+        _ => unreachable!("unknown proposal {}", proposal),
+    }
+}
+
+/// Whether the proposal is enabled in `features`.
+#[cfg(feature = "compiler")]
+pub fn proposal_enabled(features: &wasmer_compiler::Features, proposal: &str) -> bool {
+    match proposal {
+        "threads" => features.threads,
+        "simd" => features.simd,
+        "reference-types" => features.reference_types,
+        "multi-value" => features.multi_value,
+        "bulk-memory" => features.bulk_memory,
+        "tail-call" => features.tail_call,
+        "module-linking" => features.module_linking,
+        "multi-memory" => features.multi_memory,
+        "memory64" => features.memory64,
+        "exceptions" => features.exceptions,
+        _ => unreachable!("unknown proposal {}", proposal),
+    }
+}
+
+/// Whether the proposal can be enabled with an `--enable-*` flag.
+#[cfg(feature = "compiler")]
+pub fn proposal_has_flag(proposal: &str) -> bool {
+    matches!(
+        proposal,
+        "threads" | "simd" | "reference-types" | "multi-value" | "bulk-memory"
+    )
+}
+
+/// Find the proposals required by a WebAssembly binary: the ones without
+/// which it doesn't validate.
+///
+/// Returns the validation error if the module is invalid even with all
+/// the proposals enabled.
+#[cfg(feature = "compiler")]
+pub fn required_proposals(wasm: &[u8]) -> Result<Vec<&'static str>, String> {
+    use wasmer_compiler::wasmparser::{Validator, WasmFeatures};
+
+    let validate = |disabled: Option<&str>| {
+        let mut features = WasmFeatures {
+            deterministic_only: false,
+            ..WasmFeatures::default()
+        };
+        for proposal in PROPOSALS {
+            *proposal_flag(&mut features, proposal) = Some(*proposal) != disabled;
+        }
+        let mut validator = Validator::new();
+        validator.wasm_features(features);
+        validator.validate_all(wasm).map_err(|e| e.to_string())
+    };
+
+    validate(None)?;
+    Ok(PROPOSALS
+        .iter()
+        .copied()
+        .filter(|proposal| validate(Some(proposal)).is_err())
+        .collect())
+}
+
 /// Get the cache dir
 pub fn get_cache_dir() -> PathBuf {
     match env::var("WASMER_CACHE_DIR") {
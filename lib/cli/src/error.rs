@@ -25,13 +25,45 @@ impl PrettyError {
         std::process::exit(match result {
             Ok(_t) => 0,
             Err(error) => {
+                let exit_code = exit_code(&error);
                 eprintln!("{:?}", PrettyError { error });
-                1
+                exit_code
             }
         });
     }
 }
 
+/// The exit code when a guest traps is this plus the trap code.
+pub const TRAP_EXIT_CODE_BASE: i32 = 100;
+
+/// The exit code of a failed command: [`TRAP_EXIT_CODE_BASE`] plus the
+/// trap code if a guest trapped, like 110 for an integer division by zero
+/// or 112 for `unreachable`, and 1 otherwise.
+pub fn exit_code(error: &Error) -> i32 {
+    error
+        .chain()
+        .filter_map(|cause| cause.downcast_ref::<wasmer::RuntimeError>())
+        .find_map(|runtime_error| runtime_error.clone().to_trap())
+        .map_or(1, |trap_code| TRAP_EXIT_CODE_BASE + trap_code as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasmer_vm::{Trap, TrapCode};
+
+    #[test]
+    fn test_exit_code() {
+        let trap = Trap::new_from_runtime(TrapCode::IntegerDivisionByZero);
+        let error = Error::new(wasmer_engine::RuntimeError::from_trap(trap))
+            .context("failed to run `module.wasm`");
+        assert_eq!(exit_code(&error), 110);
+        let error = Error::new(wasmer_engine::RuntimeError::new("host error"));
+        assert_eq!(exit_code(&error), 1);
+        assert_eq!(exit_code(&anyhow::anyhow!("invalid module")), 1);
+    }
+}
+
 impl Debug for PrettyError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let error = &self.error;
@@ -22,8 +22,10 @@ pub mod common;
 pub mod error;
 pub mod c_gen;
 pub mod cli;
+pub mod limits;
 #[cfg(feature = "debug")]
 pub mod logging;
+pub mod output;
 pub mod store;
 pub mod suggestions;
 pub mod utils;
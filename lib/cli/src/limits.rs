@@ -0,0 +1,222 @@
+//! Limiting the resources of the guests, with `--max-memory` and
+//! `--max-table-elements`.
+
+use anyhow::{bail, Result};
+use clap::Clap;
+use loupe::MemoryUsage;
+use std::ptr::NonNull;
+use std::sync::Arc;
+use wasmer::vm::{
+    self, MemoryError, MemoryStyle, TableStyle, VMMemoryDefinition, VMTableDefinition,
+};
+use wasmer::{MemoryType, Pages, TableType, Tunables, WASM_MAX_PAGES, WASM_PAGE_SIZE};
+
+/// Parse a size in bytes, like `1048576`, `64KiB`, `16MiB` or `1GB`.
+pub fn parse_size(size: &str) -> Result<u64> {
+    let size = size.trim();
+    let split = size
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(size.len());
+    let (value, unit) = size.split_at(split);
+    let multiplier: u64 = match unit.trim() {
+        "" | "B" => 1,
+        "K" | "KiB" => 1 << 10,
+        "M" | "MiB" => 1 << 20,
+        "G" | "GiB" => 1 << 30,
+        "KB" => 1_000,
+        "MB" => 1_000_000,
+        "GB" => 1_000_000_000,
+        unit => bail!(
+            "Invalid unit `{}` in `{}`, expected `KiB`, `MiB`, `GiB`, `KB`, `MB` or `GB`",
+            unit,
+            size
+        ),
+    };
+    match value
+        .parse::<u64>()
+        .ok()
+        .and_then(|v| v.checked_mul(multiplier))
+    {
+        Some(bytes) => Ok(bytes),
+        None => bail!("Invalid size `{}`", size),
+    }
+}
+
+#[derive(Debug, Clone, Default, Clap)]
+/// The resource limits of the guest
+pub struct LimitOptions {
+    /// The maximum size of each linear memory, in bytes (like `64MiB`),
+    /// rounded down to whole Wasm pages
+    #[clap(long = "max-memory", parse(try_from_str = parse_size))]
+    max_memory: Option<u64>,
+
+    /// The maximum number of elements of each table
+    #[clap(long = "max-table-elements")]
+    max_table_elements: Option<u32>,
+}
+
+impl LimitOptions {
+    /// Wrap the `base` tunables to enforce the limits.
+    pub fn limit<T: Tunables>(&self, base: T) -> LimitingTunables<T> {
+        let memory_limit = self.max_memory.map(|bytes| {
+            let pages = bytes / WASM_PAGE_SIZE as u64;
+            Pages(pages.min(WASM_MAX_PAGES as u64) as u32)
+        });
+        LimitingTunables {
+            base,
+            memory_limit,
+            table_limit: self.max_table_elements,
+        }
+    }
+}
+
+/// Tunables capping the memories and the tables, and delegating everything
+/// else to the `base` tunables.
+///
+/// The maximum of the memories and tables is lowered to the limit, so that
+/// they can't grow past it. A module requiring more than the limit to start
+/// fails to instantiate.
+#[derive(MemoryUsage)]
+pub struct LimitingTunables<T: Tunables> {
+    base: T,
+    /// The maximum number of pages of a memory.
+    memory_limit: Option<Pages>,
+    /// The maximum number of elements of a table.
+    table_limit: Option<u32>,
+}
+
+impl<T: Tunables> LimitingTunables<T> {
+    /// Lower the maximum of the memory to the limit.
+    fn adjust_memory(&self, requested: &MemoryType) -> MemoryType {
+        let mut adjusted = *requested;
+        if let Some(limit) = self.memory_limit {
+            adjusted.maximum = Some(requested.maximum.map_or(limit, |max| max.min(limit)));
+        }
+        adjusted
+    }
+
+    /// Ensure the memory can start within the limit.
+    fn validate_memory(&self, ty: &MemoryType) -> Result<(), MemoryError> {
+        match self.memory_limit {
+            Some(limit) if ty.minimum > limit => Err(MemoryError::Generic(format!(
+                "the memory needs at least {} pages, more than the `--max-memory` limit of {} pages",
+                ty.minimum.0, limit.0
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Lower the maximum of the table to the limit.
+    fn adjust_table(&self, requested: &TableType) -> TableType {
+        let mut adjusted = *requested;
+        if let Some(limit) = self.table_limit {
+            adjusted.maximum = Some(requested.maximum.map_or(limit, |max| max.min(limit)));
+        }
+        adjusted
+    }
+
+    /// Ensure the table can start within the limit.
+    fn validate_table(&self, ty: &TableType) -> Result<(), String> {
+        match self.table_limit {
+            Some(limit) if ty.minimum > limit => Err(format!(
+                "the table needs at least {} elements, more than the `--max-table-elements` limit of {}",
+                ty.minimum, limit
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<T: Tunables> Tunables for LimitingTunables<T> {
+    fn memory_style(&self, memory: &MemoryType) -> MemoryStyle {
+        self.base.memory_style(&self.adjust_memory(memory))
+    }
+
+    fn table_style(&self, table: &TableType) -> TableStyle {
+        self.base.table_style(&self.adjust_table(table))
+    }
+
+    fn create_host_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+    ) -> Result<Arc<dyn vm::Memory>, MemoryError> {
+        let adjusted = self.adjust_memory(ty);
+        self.validate_memory(&adjusted)?;
+        self.base.create_host_memory(&adjusted, style)
+    }
+
+    unsafe fn create_vm_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+        vm_definition_location: NonNull<VMMemoryDefinition>,
+    ) -> Result<Arc<dyn vm::Memory>, MemoryError> {
+        let adjusted = self.adjust_memory(ty);
+        self.validate_memory(&adjusted)?;
+        self.base
+            .create_vm_memory(&adjusted, style, vm_definition_location)
+    }
+
+    fn create_host_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+    ) -> Result<Arc<dyn vm::Table>, String> {
+        let adjusted = self.adjust_table(ty);
+        self.validate_table(&adjusted)?;
+        self.base.create_host_table(&adjusted, style)
+    }
+
+    unsafe fn create_vm_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+        vm_definition_location: NonNull<VMTableDefinition>,
+    ) -> Result<Arc<dyn vm::Table>, String> {
+        let adjusted = self.adjust_table(ty);
+        self.validate_table(&adjusted)?;
+        self.base
+            .create_vm_table(&adjusted, style, vm_definition_location)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasmer::{BaseTunables, Type};
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1024").unwrap(), 1024);
+        assert_eq!(parse_size("64KiB").unwrap(), 64 * 1024);
+        assert_eq!(parse_size("16M").unwrap(), 16 << 20);
+        assert_eq!(parse_size("1GB").unwrap(), 1_000_000_000);
+        assert!(parse_size("1TiB").is_err());
+        assert!(parse_size("MiB").is_err());
+        assert!(parse_size("99999999999999GiB").is_err());
+    }
+
+    #[test]
+    fn test_limiting_tunables() {
+        let limits = LimitOptions {
+            max_memory: Some(parse_size("1MiB").unwrap()),
+            max_table_elements: Some(10),
+        };
+        let tunables = limits.limit(BaseTunables::tiny());
+
+        let memory = tunables.adjust_memory(&MemoryType::new(1, None, false));
+        assert_eq!(memory.maximum, Some(Pages(16)));
+        let memory = tunables.adjust_memory(&MemoryType::new(1, Some(4), false));
+        assert_eq!(memory.maximum, Some(Pages(4)));
+        assert!(tunables
+            .validate_memory(&tunables.adjust_memory(&MemoryType::new(17, None, false)))
+            .is_err());
+
+        let table = tunables.adjust_table(&TableType::new(Type::FuncRef, 1, None));
+        assert_eq!(table.maximum, Some(10));
+        assert!(tunables
+            .validate_table(&tunables.adjust_table(&TableType::new(Type::FuncRef, 11, None)))
+            .is_err());
+    }
+}
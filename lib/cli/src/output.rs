@@ -0,0 +1,171 @@
+//! The machine-readable output of the commands, printed with `--json`.
+
+use crate::error::exit_code;
+use anyhow::Error;
+use clap::Clap;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// The options selecting the output of a command.
+#[derive(Debug, Clone, Clap)]
+pub struct OutputOptions {
+    /// Print the result of the command as a JSON object on stdout, with its
+    /// exit code, error and duration
+    #[clap(long = "json")]
+    json: bool,
+}
+
+impl OutputOptions {
+    /// The report of the `command`, if it's printed as JSON.
+    pub fn report(&self, command: &'static str) -> Option<Report> {
+        if self.json {
+            Some(Report::new(command))
+        } else {
+            None
+        }
+    }
+}
+
+/// The result of a command, printed as a single line of JSON on stdout once
+/// it's over.
+///
+/// The report is shared with the threads which may end the process, like the
+/// `--timeout` watchdog, and is only printed once.
+#[derive(Debug, Clone)]
+pub struct Report {
+    inner: Arc<Mutex<ReportInner>>,
+}
+
+#[derive(Debug)]
+struct ReportInner {
+    command: &'static str,
+    start: Instant,
+    fields: Map<String, Value>,
+    printed: bool,
+}
+
+/// Why a command failed, in the report.
+#[derive(Debug, Serialize)]
+struct ErrorReport<'a> {
+    kind: &'a str,
+    message: String,
+}
+
+impl Report {
+    /// Start the report of the `command`, timing it from now.
+    pub fn new(command: &'static str) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(ReportInner {
+                command,
+                start: Instant::now(),
+                fields: Map::new(),
+                printed: false,
+            })),
+        }
+    }
+
+    /// Add a field to the report.
+    pub fn set(&self, key: &str, value: impl Serialize) {
+        let value = serde_json::to_value(value).unwrap_or(Value::Null);
+        self.inner
+            .lock()
+            .unwrap()
+            .fields
+            .insert(key.to_string(), value);
+    }
+
+    /// Add the fields of a serializable struct to the report.
+    pub fn extend(&self, value: impl Serialize) {
+        if let Ok(Value::Object(fields)) = serde_json::to_value(value) {
+            self.inner.lock().unwrap().fields.extend(fields);
+        }
+    }
+
+    /// Print the report of a command which returned `result`.
+    pub fn finish<T>(&self, result: &Result<T, Error>) {
+        match result {
+            Ok(_) => self.print(0, None),
+            Err(error) => self.print(
+                exit_code(error),
+                Some((error_kind(error), format!("{:#}", error))),
+            ),
+        }
+    }
+
+    /// Print the report of a command ending with `exit_code`, because of the
+    /// error of the given kind and message, if any.
+    pub fn print(&self, exit_code: i32, error: Option<(&str, String)>) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.printed {
+            return;
+        }
+        inner.printed = true;
+        let mut report = Map::new();
+        report.insert("command".to_string(), inner.command.into());
+        report.insert("success".to_string(), (exit_code == 0).into());
+        report.insert("exit_code".to_string(), exit_code.into());
+        report.insert(
+            "duration_ms".to_string(),
+            (inner.start.elapsed().as_secs_f64() * 1000.0).into(),
+        );
+        report.extend(inner.fields.clone());
+        let error = error.map(|(kind, message)| ErrorReport { kind, message });
+        report.insert(
+            "error".to_string(),
+            serde_json::to_value(error).unwrap_or(Value::Null),
+        );
+        println!("{}", Value::Object(report));
+    }
+}
+
+/// The kind of an error, from the first error of its chain with a known
+/// type.
+pub fn error_kind(error: &Error) -> &'static str {
+    for cause in error.chain() {
+        if cause.is::<wasmer::CompileError>() {
+            return "compile";
+        }
+        if cause.is::<wasmer::InstantiationError>() {
+            return "instantiation";
+        }
+        if cause.is::<wasmer::RuntimeError>() {
+            return "runtime";
+        }
+        if cause.is::<wasmer::ExportError>() {
+            return "export";
+        }
+        if cause.is::<wasmer::DeserializeError>() {
+            return "deserialize";
+        }
+        if cause.is::<wasmer::SerializeError>() {
+            return "serialize";
+        }
+        #[cfg(feature = "wasi")]
+        if cause.is::<wasmer_wasi::WasiStateCreationError>() || cause.is::<wasmer_wasi::WasiError>()
+        {
+            return "wasi";
+        }
+        if cause.is::<std::io::Error>() {
+            return "io";
+        }
+    }
+    "other"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_kind() {
+        let error = Error::new(wasmer::CompileError::Validate("invalid".to_string()))
+            .context("failed to compile");
+        assert_eq!(error_kind(&error), "compile");
+        let error = Error::new(std::io::Error::from(std::io::ErrorKind::NotFound))
+            .context("failed to read");
+        assert_eq!(error_kind(&error), "io");
+        assert_eq!(error_kind(&anyhow::anyhow!("unknown")), "other");
+    }
+}
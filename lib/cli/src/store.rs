@@ -1,7 +1,10 @@
 //! Common module with common used structures across different
 //! commands.
 
+#[cfg(feature = "compiler")]
+use crate::commands::Emitter;
 use crate::common::WasmFeatures;
+use crate::limits::LimitOptions;
 use anyhow::{Error, Result};
 use clap::Clap;
 use std::path::PathBuf;
@@ -30,6 +33,13 @@ pub struct StoreOptions {
     /// Use ObjectFile Engine.
     #[clap(long, conflicts_with_all = &["jit", "native"])]
     object_file: bool,
+
+    /// The tunables profile used to create memories: `default`, `tiny` or `server`.
+    #[clap(long, default_value = "default")]
+    tunables: TunablesProfile,
+
+    #[clap(flatten)]
+    limits: LimitOptions,
 }
 
 #[derive(Debug, Clone, Clap)]
@@ -168,8 +178,17 @@ impl CompilerOptions {
     }
 
     /// Get the Compiler Config for the current options
-    #[allow(unused_variables)]
     pub(crate) fn get_compiler_config(&self) -> Result<(Box<dyn CompilerConfig>, CompilerType)> {
+        self.get_compiler_config_with_emitter(None)
+    }
+
+    /// Get the Compiler Config for the current options, writing the code
+    /// generated by the compiler with `emitter`
+    #[allow(unused_variables)]
+    pub(crate) fn get_compiler_config_with_emitter(
+        &self,
+        emitter: Option<Arc<Emitter>>,
+    ) -> Result<(Box<dyn CompilerConfig>, CompilerType)> {
         let compiler = self.get_compiler()?;
         let compiler_config: Box<dyn CompilerConfig> = match compiler {
             CompilerType::Headless => bail!("The headless engine can't be chosen"),
@@ -184,6 +203,9 @@ impl CompilerOptions {
             #[cfg(feature = "cranelift")]
             CompilerType::Cranelift => {
                 let mut config = wasmer_compiler_cranelift::Cranelift::new();
+                if let Some(emitter) = emitter {
+                    config.callbacks(Some(emitter));
+                }
                 if self.enable_verifier {
                     config.enable_verifier();
                 }
@@ -283,7 +305,9 @@ impl CompilerOptions {
                     }
                 }
 
-                if let Some(ref llvm_debug_dir) = self.llvm_debug_dir {
+                if let Some(emitter) = emitter {
+                    config.callbacks(Some(emitter));
+                } else if let Some(ref llvm_debug_dir) = self.llvm_debug_dir {
                     config.callbacks(Some(Arc::new(Callbacks::new(llvm_debug_dir.clone())?)));
                 }
                 if self.enable_verifier {
@@ -354,6 +378,50 @@ impl FromStr for CompilerType {
     }
 }
 
+/// The tunables profile used for the store
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TunablesProfile {
+    /// The default tunables for the target
+    Default,
+    /// Tunables for memory-constrained targets
+    Tiny,
+    /// Tunables for 64-bit servers
+    Server,
+}
+
+impl TunablesProfile {
+    /// Get the tunables for this profile and the provided target
+    pub fn get_tunables(&self, target: &Target) -> BaseTunables {
+        match self {
+            Self::Default => BaseTunables::for_target(target),
+            Self::Tiny => BaseTunables::tiny(),
+            Self::Server => BaseTunables::server(),
+        }
+    }
+}
+
+impl ToString for TunablesProfile {
+    fn to_string(&self) -> String {
+        match self {
+            Self::Default => "default".to_string(),
+            Self::Tiny => "tiny".to_string(),
+            Self::Server => "server".to_string(),
+        }
+    }
+}
+
+impl FromStr for TunablesProfile {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "default" => Ok(Self::Default),
+            "tiny" => Ok(Self::Tiny),
+            "server" => Ok(Self::Server),
+            profile => bail!("The `{}` tunables profile does not exist.", profile),
+        }
+    }
+}
+
 /// The engine used for the store
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum EngineType {
@@ -389,11 +457,74 @@ impl StoreOptions {
         target: Target,
     ) -> Result<(Store, EngineType, CompilerType)> {
         let (compiler_config, compiler_type) = self.compiler.get_compiler_config()?;
+        let tunables = self.tunables.get_tunables(&target);
         let (engine, engine_type) = self.get_engine_with_compiler(target, compiler_config)?;
-        let store = Store::new(&*engine);
+        let store = Store::new_with_tunables(&*engine, self.limits.limit(tunables));
         Ok((store, engine_type, compiler_type))
     }
 
+    /// Gets the store for the host target, with the given middlewares
+    /// applied to the compiled modules.
+    pub fn get_store_with_middlewares(
+        &self,
+        middlewares: Vec<Arc<dyn ModuleMiddleware>>,
+    ) -> Result<(Store, EngineType, CompilerType)> {
+        let target = Target::default();
+        let (mut compiler_config, compiler_type) = self.compiler.get_compiler_config()?;
+        for middleware in middlewares {
+            compiler_config.push_middleware(middleware);
+        }
+        let tunables = self.tunables.get_tunables(&target);
+        let (engine, engine_type) = self.get_engine_with_compiler(target, compiler_config)?;
+        let store = Store::new_with_tunables(&*engine, self.limits.limit(tunables));
+        Ok((store, engine_type, compiler_type))
+    }
+
+    /// Gets the store for a given target with the given engine.
+    pub fn get_store_for_target_with_engine(
+        &self,
+        target: Target,
+        engine_type: EngineType,
+    ) -> Result<(Store, CompilerType)> {
+        let (compiler_config, compiler_type) = self.compiler.get_compiler_config()?;
+        let tunables = self.tunables.get_tunables(&target);
+        let engine = self
+            .compiler
+            .get_engine_by_type(target, compiler_config, engine_type)?;
+        let store = Store::new_with_tunables(&*engine, self.limits.limit(tunables));
+        Ok((store, compiler_type))
+    }
+
+    /// Gets the compiler config for a target, with the code it generates
+    /// written by `emitter`, and the Wasm features and tunables to compile
+    /// modules without an engine.
+    pub(crate) fn get_compiler_config_with_emitter(
+        &self,
+        target: &Target,
+        emitter: Arc<Emitter>,
+    ) -> Result<(
+        Box<dyn CompilerConfig>,
+        CompilerType,
+        Features,
+        BaseTunables,
+    )> {
+        let (compiler_config, compiler_type) = self
+            .compiler
+            .get_compiler_config_with_emitter(Some(emitter))?;
+        let features = self
+            .compiler
+            .get_features(compiler_config.default_features_for_target(target))?;
+        let tunables = self.tunables.get_tunables(target);
+        Ok((compiler_config, compiler_type, features, tunables))
+    }
+
+    /// Gets the Wasm features enabled for the host target.
+    pub fn get_enabled_features(&self) -> Result<Features> {
+        let (compiler_config, _compiler_type) = self.compiler.get_compiler_config()?;
+        self.compiler
+            .get_features(compiler_config.default_features_for_target(&Target::default()))
+    }
+
     fn get_engine_with_compiler(
         &self,
         target: Target,
@@ -410,7 +541,17 @@ impl StoreOptions {
 
 #[cfg(feature = "engine")]
 impl StoreOptions {
-    fn get_engine(&self) -> Result<EngineType> {
+    /// The engine selected with a flag, or `default_engine`.
+    pub fn get_engine_or(&self, default_engine: EngineType) -> Result<EngineType> {
+        if self.jit || self.native || self.object_file {
+            self.get_engine()
+        } else {
+            Ok(default_engine)
+        }
+    }
+
+    /// The engine selected with a flag, or the best one available.
+    pub fn get_engine(&self) -> Result<EngineType> {
         if self.jit {
             Ok(EngineType::JIT)
         } else if self.native {
@@ -458,7 +599,8 @@ impl StoreOptions {
     /// Get the store (headless engine)
     pub fn get_store(&self) -> Result<(Store, EngineType, CompilerType)> {
         let (engine, engine_type) = self.get_engine_headless()?;
-        let store = Store::new(&*engine);
+        let tunables = self.tunables.get_tunables(engine.target());
+        let store = Store::new_with_tunables(&*engine, self.limits.limit(tunables));
         Ok((store, engine_type, CompilerType::Headless))
     }
 
@@ -40,6 +40,101 @@ pub fn parse_mapdir(entry: &str) -> Result<(String, PathBuf)> {
     }
 }
 
+/// The rights of the guest on a directory mapped from the host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DirRights {
+    /// Reading the files.
+    pub read: bool,
+    /// Writing the files.
+    pub write: bool,
+    /// Creating files and directories.
+    pub create: bool,
+    /// Removing and renaming files and directories.
+    pub delete: bool,
+}
+
+impl DirRights {
+    /// All the rights, the default.
+    pub const ALL: Self = Self {
+        read: true,
+        write: true,
+        create: true,
+        delete: true,
+    };
+
+    /// Only reading.
+    pub const READ_ONLY: Self = Self {
+        read: true,
+        write: false,
+        create: false,
+        delete: false,
+    };
+
+    /// Parses rights like `ro`, `rw`, or a subset of `rwcd` (read, write,
+    /// create and delete).
+    fn parse(rights: &str) -> Option<Self> {
+        match rights {
+            "ro" => return Some(Self::READ_ONLY),
+            "rw" => return Some(Self::ALL),
+            "" => return None,
+            _ => {}
+        }
+        let mut parsed = Self {
+            read: false,
+            write: false,
+            create: false,
+            delete: false,
+        };
+        for right in rights.chars() {
+            match right {
+                'r' => parsed.read = true,
+                'w' => parsed.write = true,
+                'c' => parsed.create = true,
+                'd' => parsed.delete = true,
+                _ => return None,
+            }
+        }
+        Some(parsed)
+    }
+}
+
+impl Default for DirRights {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+/// Splits the rights suffix, like `:ro`, off a directory argument.
+fn split_dir_rights(entry: &str) -> Option<(&str, DirRights)> {
+    for separator in &["::", ":"] {
+        if let Some(index) = entry.rfind(separator) {
+            if let Some(rights) = DirRights::parse(&entry[index + separator.len()..]) {
+                return Some((&entry[..index], rights));
+            }
+        }
+    }
+    None
+}
+
+/// Parses a pre-opened directory with optional rights, like `data` or
+/// `data:ro`
+pub fn parse_dir_with_rights(entry: &str) -> Result<(PathBuf, DirRights)> {
+    let (dir, rights) = split_dir_rights(entry).unwrap_or((entry, DirRights::ALL));
+    Ok((PathBuf::from(dir), rights))
+}
+
+/// Parses a mapdir with optional rights, like `/data:data` or
+/// `/data:data:ro`
+pub fn parse_mapdir_with_rights(entry: &str) -> Result<(String, PathBuf, DirRights)> {
+    let (mapping, rights) = match split_dir_rights(entry) {
+        // The rights can't be the host directory of a mapping.
+        Some((mapping, rights)) if mapping.contains(':') => (mapping, rights),
+        _ => (entry, DirRights::ALL),
+    };
+    let (alias, dir) = parse_mapdir(mapping)?;
+    Ok((alias, dir, rights))
+}
+
 /// Parses an environment variable.
 pub fn parse_envvar(entry: &str) -> Result<(String, String)> {
     let entry = entry.trim();
@@ -66,7 +161,8 @@ pub fn parse_envvar(entry: &str) -> Result<(String, String)> {
 
 #[cfg(test)]
 mod tests {
-    use super::parse_envvar;
+    use super::{parse_dir_with_rights, parse_envvar, parse_mapdir_with_rights, DirRights};
+    use std::path::PathBuf;
 
     #[test]
     fn test_parse_envvar() {
@@ -89,4 +185,43 @@ mod tests {
             ("A".into(), "B=C=D".into())
         );
     }
+
+    #[test]
+    fn test_parse_dir_rights() {
+        assert_eq!(
+            parse_dir_with_rights("data").unwrap(),
+            (PathBuf::from("data"), DirRights::ALL)
+        );
+        assert_eq!(
+            parse_dir_with_rights("data:ro").unwrap(),
+            (PathBuf::from("data"), DirRights::READ_ONLY)
+        );
+        assert_eq!(
+            parse_dir_with_rights("data:rc").unwrap().1,
+            DirRights {
+                read: true,
+                write: false,
+                create: true,
+                delete: false,
+            }
+        );
+        assert_eq!(
+            parse_dir_with_rights("data:rx").unwrap(),
+            (PathBuf::from("data:rx"), DirRights::ALL)
+        );
+
+        let dir = env!("CARGO_MANIFEST_DIR");
+        assert_eq!(
+            parse_mapdir_with_rights(&format!("/app:{}:ro", dir)).unwrap(),
+            ("/app".to_string(), PathBuf::from(dir), DirRights::READ_ONLY)
+        );
+        assert_eq!(
+            parse_mapdir_with_rights(&format!("/app::{}::rw", dir)).unwrap(),
+            ("/app".to_string(), PathBuf::from(dir), DirRights::ALL)
+        );
+        assert_eq!(
+            parse_mapdir_with_rights(&format!("/app:{}", dir)).unwrap(),
+            ("/app".to_string(), PathBuf::from(dir), DirRights::ALL)
+        );
+    }
 }
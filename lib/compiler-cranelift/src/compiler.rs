@@ -1,7 +1,7 @@
 //! Support for compiling with Cranelift.
 
 use crate::address_map::get_function_address_map;
-use crate::config::Cranelift;
+use crate::config::{frontend_config, Cranelift};
 #[cfg(feature = "unwind")]
 use crate::dwarf::WriterRelocate;
 use crate::func_environ::{get_function_name, FuncEnvironment};
@@ -51,6 +51,14 @@ impl CraneliftCompiler {
 }
 
 impl Compiler for CraneliftCompiler {
+    fn name(&self) -> &str {
+        "cranelift"
+    }
+
+    fn fingerprint(&self) -> String {
+        self.config.fingerprint()
+    }
+
     /// Compile the module using Cranelift, producing a compilation result with
     /// associated relocations.
     fn compile_module(
@@ -61,7 +69,7 @@ impl Compiler for CraneliftCompiler {
         function_body_inputs: PrimaryMap<LocalFunctionIndex, FunctionBodyData<'_>>,
     ) -> Result<Compilation, CompileError> {
         let isa = self.config().isa(target);
-        let frontend_config = isa.frontend_config();
+        let frontend_config = frontend_config(&*isa);
         let memory_styles = &compile_info.memory_styles;
         let table_styles = &compile_info.table_styles;
         let mut module = (*compile_info.module).clone();
@@ -107,7 +115,7 @@ impl Compiler for CraneliftCompiler {
                 let func_index = module.func_index(*i);
                 let mut context = Context::new();
                 let mut func_env = FuncEnvironment::new(
-                    isa.frontend_config(),
+                    frontend_config,
                     module,
                     &signatures,
                     &memory_styles,
@@ -145,6 +153,10 @@ impl Compiler for CraneliftCompiler {
                         CompileError::Codegen(pretty_error(&context.func, Some(&*isa), error))
                     })?;
 
+                if let Some(callbacks) = &self.config.callbacks {
+                    callbacks.clif(*i, &context.func.display(&*isa).to_string());
+                }
+
                 let unwind_info = match compiled_function_unwind_info(&*isa, &context)? {
                     #[cfg(feature = "unwind")]
                     CraneliftUnwindInfo::FDE(fde) => {
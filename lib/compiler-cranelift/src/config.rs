@@ -1,11 +1,14 @@
 use crate::compiler::CraneliftCompiler;
-use cranelift_codegen::isa::{lookup, TargetIsa};
+use cranelift_codegen::isa::{lookup, CallConv, TargetFrontendConfig, TargetIsa};
 use cranelift_codegen::settings::{self, Configurable};
 use loupe::MemoryUsage;
+use std::fmt::Debug;
 use std::sync::Arc;
 use wasmer_compiler::{
-    Architecture, Compiler, CompilerConfig, CpuFeature, ModuleMiddleware, Target,
+    Architecture, Compiler, CompilerConfig, CpuFeature, ModuleMiddleware, ModuleMiddlewareChain,
+    OperatingSystem, Target,
 };
+use wasmer_types::LocalFunctionIndex;
 
 // Runtime Environment
 
@@ -23,6 +26,13 @@ pub enum CraneliftOptLevel {
     SpeedAndSize,
 }
 
+/// Callbacks to the different Cranelift compilation phases.
+pub trait CraneliftCallbacks: Debug + Send + Sync {
+    /// Called with the Cranelift IR of a locally-defined function, once
+    /// it has been optimized and lowered to machine code.
+    fn clif(&self, function: LocalFunctionIndex, clif: &str);
+}
+
 /// Global configuration options used to create an
 /// `wasmer_engine::Engine` and customize its behavior.
 ///
@@ -35,6 +45,8 @@ pub struct Cranelift {
     enable_simd: bool,
     enable_pic: bool,
     opt_level: CraneliftOptLevel,
+    #[loupe(skip)]
+    pub(crate) callbacks: Option<Arc<dyn CraneliftCallbacks>>,
     /// The middleware chain.
     pub(crate) middlewares: Vec<Arc<dyn ModuleMiddleware>>,
 }
@@ -49,6 +61,7 @@ impl Cranelift {
             opt_level: CraneliftOptLevel::Speed,
             enable_pic: false,
             enable_simd: true,
+            callbacks: None,
             middlewares: vec![],
         }
     }
@@ -74,6 +87,13 @@ impl Cranelift {
         self
     }
 
+    /// Callbacks that will triggered in the different compilation
+    /// phases in Cranelift.
+    pub fn callbacks(&mut self, callbacks: Option<Arc<dyn CraneliftCallbacks>>) -> &mut Self {
+        self.callbacks = callbacks;
+        self
+    }
+
     /// Generates the ISA for the provided target
     pub fn isa(&self, target: &Target) -> Box<dyn TargetIsa> {
         let mut builder =
@@ -129,6 +149,18 @@ impl Cranelift {
         builder.finish(self.flags())
     }
 
+    /// The fingerprint of the options that change the generated code.
+    pub(crate) fn fingerprint(&self) -> String {
+        format!(
+            "cranelift(nan_canonicalization={},simd={},pic={},opt_level={:?},middlewares=[{}])",
+            self.enable_nan_canonicalization,
+            self.enable_simd,
+            self.enable_pic,
+            self.opt_level,
+            self.middlewares.fingerprint(),
+        )
+    }
+
     /// Generates the flags for the compiler
     pub fn flags(&self) -> settings::Flags {
         let mut flags = settings::builder();
@@ -185,6 +217,26 @@ impl Cranelift {
     }
 }
 
+/// Returns the frontend configuration of the `isa`, used to build the
+/// signatures of the functions and the trampolines.
+pub(crate) fn frontend_config(isa: &dyn TargetIsa) -> TargetFrontendConfig {
+    let mut frontend_config = isa.frontend_config();
+    let triple = isa.triple();
+    if triple.operating_system == OperatingSystem::Windows
+        && matches!(triple.architecture, Architecture::Aarch64(_))
+    {
+        // Cranelift picks `WindowsFastcall` for every Windows target,
+        // but Windows on ARM64 uses the standard AAPCS64 calling
+        // convention, which Cranelift names `SystemV` on AArch64.
+        //
+        // Note that Cranelift doesn't emit stack probes on AArch64, so
+        // frames larger than a page may skip the guard page of the
+        // stack, which Windows commits lazily.
+        frontend_config.default_call_conv = CallConv::SystemV;
+    }
+    frontend_config
+}
+
 impl CompilerConfig for Cranelift {
     fn enable_pic(&mut self) {
         self.enable_pic = true;
@@ -194,6 +246,10 @@ impl CompilerConfig for Cranelift {
         self.enable_verifier = true;
     }
 
+    fn enable_nan_canonicalization(&mut self) {
+        self.enable_nan_canonicalization = true;
+    }
+
     /// Transform it into the compiler
     fn compiler(self: Box<Self>) -> Box<dyn Compiler> {
         Box::new(CraneliftCompiler::new(*self))
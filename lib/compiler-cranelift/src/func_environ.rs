@@ -83,6 +83,21 @@ pub struct FuncEnvironment<'module_environment> {
     /// The external function signature for implementing wasm's `data.drop`.
     data_drop_sig: Option<ir::SigRef>,
 
+    /// The external function signature for implementing wasm's
+    /// `memory.atomic.wait32` (it's the same for both local and imported
+    /// memories).
+    memory_atomic_wait32_sig: Option<ir::SigRef>,
+
+    /// The external function signature for implementing wasm's
+    /// `memory.atomic.wait64` (it's the same for both local and imported
+    /// memories).
+    memory_atomic_wait64_sig: Option<ir::SigRef>,
+
+    /// The external function signature for implementing wasm's
+    /// `memory.atomic.notify` (it's the same for both local and imported
+    /// memories).
+    memory_atomic_notify_sig: Option<ir::SigRef>,
+
     /// Offsets to struct fields accessed by JIT code.
     offsets: VMOffsets,
 
@@ -115,6 +130,9 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
             memory_fill_sig: None,
             memory_init_sig: None,
             data_drop_sig: None,
+            memory_atomic_wait32_sig: None,
+            memory_atomic_wait64_sig: None,
+            memory_atomic_notify_sig: None,
             offsets: VMOffsets::new(target_config.pointer_bytes(), module),
             memory_styles,
             table_styles,
@@ -392,6 +410,105 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
         }
     }
 
+    fn get_memory_atomic_wait_sig(&mut self, func: &mut Function, ty: ir::Type) -> ir::SigRef {
+        let cached = if ty == I32 {
+            self.memory_atomic_wait32_sig
+        } else {
+            self.memory_atomic_wait64_sig
+        };
+        let sig = cached.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![
+                    AbiParam::special(self.pointer_type(), ArgumentPurpose::VMContext),
+                    // Memory index.
+                    AbiParam::new(I32),
+                    // Address.
+                    AbiParam::new(I64),
+                    // Expected value.
+                    AbiParam::new(ty),
+                    // Timeout.
+                    AbiParam::new(I64),
+                ],
+                returns: vec![AbiParam::new(I32)],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        if ty == I32 {
+            self.memory_atomic_wait32_sig = Some(sig);
+        } else {
+            self.memory_atomic_wait64_sig = Some(sig);
+        }
+        sig
+    }
+
+    /// Return the memory.atomic.wait function signature to call for the given
+    /// index, along with the translated index value to pass to it and its
+    /// index in `VMBuiltinFunctionsArray`.
+    fn get_memory_atomic_wait_func(
+        &mut self,
+        func: &mut Function,
+        memory_index: MemoryIndex,
+        ty: ir::Type,
+    ) -> (ir::SigRef, usize, VMBuiltinFunctionIndex) {
+        let sig = self.get_memory_atomic_wait_sig(func, ty);
+        if let Some(local_memory_index) = self.module.local_memory_index(memory_index) {
+            let func_idx = if ty == I32 {
+                VMBuiltinFunctionIndex::get_memory_atomic_wait32_index()
+            } else {
+                VMBuiltinFunctionIndex::get_memory_atomic_wait64_index()
+            };
+            (sig, local_memory_index.index(), func_idx)
+        } else {
+            let func_idx = if ty == I32 {
+                VMBuiltinFunctionIndex::get_imported_memory_atomic_wait32_index()
+            } else {
+                VMBuiltinFunctionIndex::get_imported_memory_atomic_wait64_index()
+            };
+            (sig, memory_index.index(), func_idx)
+        }
+    }
+
+    fn get_memory_atomic_notify_sig(&mut self, func: &mut Function) -> ir::SigRef {
+        let sig = self.memory_atomic_notify_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![
+                    AbiParam::special(self.pointer_type(), ArgumentPurpose::VMContext),
+                    // Memory index.
+                    AbiParam::new(I32),
+                    // Address.
+                    AbiParam::new(I64),
+                    // Count.
+                    AbiParam::new(I32),
+                ],
+                returns: vec![AbiParam::new(I32)],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.memory_atomic_notify_sig = Some(sig);
+        sig
+    }
+
+    fn get_memory_atomic_notify_func(
+        &mut self,
+        func: &mut Function,
+        memory_index: MemoryIndex,
+    ) -> (ir::SigRef, usize, VMBuiltinFunctionIndex) {
+        let sig = self.get_memory_atomic_notify_sig(func);
+        if let Some(local_memory_index) = self.module.local_memory_index(memory_index) {
+            (
+                sig,
+                local_memory_index.index(),
+                VMBuiltinFunctionIndex::get_memory_atomic_notify_index(),
+            )
+        } else {
+            (
+                sig,
+                memory_index.index(),
+                VMBuiltinFunctionIndex::get_imported_memory_atomic_notify_index(),
+            )
+        }
+    }
+
     fn get_memory_init_sig(&mut self, func: &mut Function) -> ir::SigRef {
         let sig = self.memory_init_sig.unwrap_or_else(|| {
             func.import_signature(Signature {
@@ -1105,28 +1222,49 @@ impl<'module_environment> BaseFuncEnvironment for FuncEnvironment<'module_enviro
 
     fn translate_atomic_wait(
         &mut self,
-        _pos: FuncCursor,
-        _index: MemoryIndex,
+        mut pos: FuncCursor,
+        index: MemoryIndex,
         _heap: ir::Heap,
-        _addr: ir::Value,
-        _expected: ir::Value,
-        _timeout: ir::Value,
+        addr: ir::Value,
+        expected: ir::Value,
+        timeout: ir::Value,
     ) -> WasmResult<ir::Value> {
-        Err(WasmError::Unsupported(
-            "wasm atomics (fn translate_atomic_wait)".to_string(),
-        ))
+        let ty = pos.func.dfg.value_type(expected);
+        let (func_sig, memory_index, func_idx) =
+            self.get_memory_atomic_wait_func(&mut pos.func, index, ty);
+
+        let memory_index_arg = pos.ins().iconst(I32, memory_index as i64);
+
+        let (vmctx, func_addr) = self.translate_load_builtin_function_address(&mut pos, func_idx);
+
+        let call_inst = pos.ins().call_indirect(
+            func_sig,
+            func_addr,
+            &[vmctx, memory_index_arg, addr, expected, timeout],
+        );
+
+        Ok(*pos.func.dfg.inst_results(call_inst).first().unwrap())
     }
 
     fn translate_atomic_notify(
         &mut self,
-        _pos: FuncCursor,
-        _index: MemoryIndex,
+        mut pos: FuncCursor,
+        index: MemoryIndex,
         _heap: ir::Heap,
-        _addr: ir::Value,
-        _count: ir::Value,
+        addr: ir::Value,
+        count: ir::Value,
     ) -> WasmResult<ir::Value> {
-        Err(WasmError::Unsupported(
-            "wasm atomics (fn translate_atomic_notify)".to_string(),
-        ))
+        let (func_sig, memory_index, func_idx) =
+            self.get_memory_atomic_notify_func(&mut pos.func, index);
+
+        let memory_index_arg = pos.ins().iconst(I32, memory_index as i64);
+
+        let (vmctx, func_addr) = self.translate_load_builtin_function_address(&mut pos, func_idx);
+
+        let call_inst =
+            pos.ins()
+                .call_indirect(func_sig, func_addr, &[vmctx, memory_index_arg, addr, count]);
+
+        Ok(*pos.func.dfg.inst_results(call_inst).first().unwrap())
     }
 }
@@ -56,7 +56,7 @@ mod trampoline;
 mod translator;
 
 pub use crate::compiler::CraneliftCompiler;
-pub use crate::config::{Cranelift, CraneliftOptLevel};
+pub use crate::config::{Cranelift, CraneliftCallbacks, CraneliftOptLevel};
 pub use crate::debug::{ModuleInfoMemoryOffset, ModuleInfoVmctxInfo, ValueLabelsRanges};
 pub use crate::trampoline::make_trampoline_function_call;
 
@@ -4,6 +4,7 @@
 //! A trampoline generator for calling dynamic host functions from Wasm.
 
 use super::binemit::TrampolineRelocSink;
+use crate::config::frontend_config;
 use crate::translator::{compiled_function_unwind_info, signature_to_cranelift_ir};
 use cranelift_codegen::ir::{
     ExternalName, Function, InstBuilder, MemFlags, StackSlotData, StackSlotKind,
@@ -28,7 +29,7 @@ pub fn make_trampoline_dynamic_function(
     func_type: &FunctionType,
 ) -> Result<FunctionBody, CompileError> {
     let pointer_type = isa.pointer_type();
-    let frontend_config = isa.frontend_config();
+    let frontend_config = frontend_config(isa);
     let signature = signature_to_cranelift_ir(func_type, frontend_config);
     let mut stub_sig = ir::Signature::new(frontend_config.default_call_conv);
     // Add the caller `vmctx` parameter.
@@ -9,6 +9,7 @@
 //! my_func.call([1, 2])
 //! ```
 use super::binemit::TrampolineRelocSink;
+use crate::config::frontend_config;
 use crate::translator::{
     compiled_function_unwind_info, signature_to_cranelift_ir, /*transform_jump_table, */
 };
@@ -29,7 +30,7 @@ pub fn make_trampoline_function_call(
     func_type: &FunctionType,
 ) -> Result<FunctionBody, CompileError> {
     let pointer_type = isa.pointer_type();
-    let frontend_config = isa.frontend_config();
+    let frontend_config = frontend_config(isa);
     let signature = signature_to_cranelift_ir(func_type, frontend_config);
     let mut wrapper_sig = ir::Signature::new(frontend_config.default_call_conv);
 
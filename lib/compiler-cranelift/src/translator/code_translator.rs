@@ -990,6 +990,7 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
             let timeout = state.pop1(); // 64 (fixed)
             let expected = state.pop1(); // 32 or 64 (per the `Ixx` in `IxxAtomicWait`)
             let addr = state.pop1(); // 32 (fixed)
+            let addr = effective_atomic_address(addr, memarg, builder);
             assert!(builder.func.dfg.value_type(expected) == implied_ty);
             // `fn translate_atomic_wait` can inspect the type of `expected` to figure out what
             // code it needs to generate, if it wants.
@@ -1008,6 +1009,7 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
             let heap = state.get_heap(builder.func, memarg.memory, environ)?;
             let count = state.pop1(); // 32 (fixed)
             let addr = state.pop1(); // 32 (fixed)
+            let addr = effective_atomic_address(addr, memarg, builder);
             let res =
                 environ.translate_atomic_notify(builder.cursor(), heap_index, heap, addr, count)?;
             state.push1(res);
@@ -2158,6 +2160,22 @@ fn finalise_atomic_mem_addr<FE: FuncEnvironment + ?Sized>(
     Ok(final_effective_address)
 }
 
+// For `memory.atomic.wait` and `memory.atomic.notify`, compute the 64-bit effective
+// address, so that adding the static offset can't wrap around. The bounds and the
+// alignment are checked by the environment.
+fn effective_atomic_address(
+    linear_mem_addr: Value,
+    memarg: &MemoryImmediate,
+    builder: &mut FunctionBuilder,
+) -> Value {
+    let addr = builder.ins().uextend(I64, linear_mem_addr);
+    if memarg.offset == 0 {
+        addr
+    } else {
+        builder.ins().iadd_imm(addr, i64::from(memarg.offset))
+    }
+}
+
 fn translate_atomic_rmw<FE: FuncEnvironment + ?Sized>(
     widened_ty: Type,
     access_ty: Type,
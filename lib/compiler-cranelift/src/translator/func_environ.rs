@@ -410,6 +410,8 @@ pub trait FuncEnvironment: TargetEnvironment {
     /// to wait on, and `heap` is the heap reference returned by `make_heap`
     /// for the same index.  Whether the waited-on value is 32- or 64-bit can be
     /// determined by examining the type of `expected`, which must be only I32 or I64.
+    /// `addr` is the I64 effective address, the static offset of the instruction
+    /// already added; its bounds and alignment are not checked yet.
     ///
     /// Returns an i32, which is negative if the helper call failed.
     fn translate_atomic_wait(
@@ -425,7 +427,8 @@ pub trait FuncEnvironment: TargetEnvironment {
     /// Translate an `atomic.notify` WebAssembly instruction.
     /// The `index` provided identifies the linear memory containing the value
     /// to wait on, and `heap` is the heap reference returned by `make_heap`
-    /// for the same index.
+    /// for the same index. `addr` is the I64 effective address, the static offset
+    /// of the instruction already added; its bounds and alignment are not checked yet.
     ///
     /// Returns an i64, which is negative if the helper call failed.
     fn translate_atomic_notify(
@@ -188,6 +188,14 @@ impl LLVMCompiler {
 }
 
 impl Compiler for LLVMCompiler {
+    fn name(&self) -> &str {
+        "llvm"
+    }
+
+    fn fingerprint(&self) -> String {
+        self.config.fingerprint()
+    }
+
     fn experimental_native_compile_module<'data, 'module>(
         &self,
         target: &Target,
@@ -9,7 +9,9 @@ use loupe::MemoryUsage;
 use std::fmt::Debug;
 use std::sync::Arc;
 use target_lexicon::Architecture;
-use wasmer_compiler::{Compiler, CompilerConfig, ModuleMiddleware, Target, Triple};
+use wasmer_compiler::{
+    Compiler, CompilerConfig, ModuleMiddleware, ModuleMiddlewareChain, Target, Triple,
+};
 use wasmer_types::{FunctionType, LocalFunctionIndex};
 
 /// The InkWell ModuleInfo type
@@ -87,6 +89,17 @@ impl LLVM {
         self
     }
 
+    /// The fingerprint of the options that change the generated code.
+    pub(crate) fn fingerprint(&self) -> String {
+        format!(
+            "llvm(nan_canonicalization={},pic={},opt_level={:?},middlewares=[{}])",
+            self.enable_nan_canonicalization,
+            self.is_pic,
+            self.opt_level,
+            self.middlewares.fingerprint(),
+        )
+    }
+
     fn reloc_mode(&self) -> RelocMode {
         if self.is_pic {
             RelocMode::PIC
@@ -127,6 +140,14 @@ impl LLVM {
             //
             // Since both linux and darwin use SysV ABI, this should work.
             wasmer_compiler::OperatingSystem::Linux
+        } else if target.triple().operating_system == wasmer_compiler::OperatingSystem::Windows
+            && matches!(target.triple().architecture, Architecture::Aarch64(_))
+            && !self.is_pic
+        {
+            // Same with Windows on ARM64, which would otherwise get
+            // `__chkstk` calls that can't be relocated in an ELF object.
+            // Both use the AAPCS64 ABI for non-variadic functions.
+            wasmer_compiler::OperatingSystem::Linux
         } else {
             target.triple().operating_system
         };
@@ -209,6 +230,11 @@ impl CompilerConfig for LLVM {
         self.enable_verifier = true;
     }
 
+    /// Canonicalize the NaNs.
+    fn enable_nan_canonicalization(&mut self) {
+        self.enable_nan_canonicalization = true;
+    }
+
     /// Transform it into the compiler.
     fn compiler(self: Box<Self>) -> Box<dyn Compiler> {
         Box::new(LLVMCompiler::new(*self))
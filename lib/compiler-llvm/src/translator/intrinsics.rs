@@ -638,7 +638,7 @@ impl<'ctx, 'a> CtxType<'ctx, 'a> {
                     "",
                 )
                 .unwrap();
-            if let MemoryStyle::Dynamic { .. } = memory_style {
+            if memory_style.needs_bounds_checks() {
                 let current_length_ptr = cache_builder
                     .build_struct_gep(
                         memory_definition_ptr,
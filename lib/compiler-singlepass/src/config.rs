@@ -4,7 +4,9 @@
 use crate::compiler::SinglepassCompiler;
 use loupe::MemoryUsage;
 use std::sync::Arc;
-use wasmer_compiler::{Compiler, CompilerConfig, CpuFeature, ModuleMiddleware, Target};
+use wasmer_compiler::{
+    Compiler, CompilerConfig, CpuFeature, ModuleMiddleware, ModuleMiddlewareChain, Target,
+};
 use wasmer_types::Features;
 
 #[derive(Debug, Clone, MemoryUsage)]
@@ -46,6 +48,16 @@ impl Singlepass {
         self.enable_nan_canonicalization = enable;
         self
     }
+
+    /// The fingerprint of the options that change the generated code.
+    pub(crate) fn fingerprint(&self) -> String {
+        format!(
+            "singlepass(nan_canonicalization={},stack_check={},middlewares=[{}])",
+            self.enable_nan_canonicalization,
+            self.enable_stack_check,
+            self.middlewares.fingerprint(),
+        )
+    }
 }
 
 impl CompilerConfig for Singlepass {
@@ -54,6 +66,10 @@ impl CompilerConfig for Singlepass {
         // PIC code.
     }
 
+    fn enable_nan_canonicalization(&mut self) {
+        self.enable_nan_canonicalization = true;
+    }
+
     /// Transform it into the compiler
     fn compiler(self: Box<Self>) -> Box<dyn Compiler> {
         Box::new(SinglepassCompiler::new(*self))
@@ -37,6 +37,16 @@ pub trait CompilerConfig {
         // in case they create an IR that they can verify.
     }
 
+    /// Enable NaN canonicalization.
+    ///
+    /// For compilers capable of doing so, this makes the floating-point
+    /// operations return the canonical NaN, so that running WebAssembly
+    /// is deterministic across architectures.
+    fn enable_nan_canonicalization(&mut self) {
+        // By default we do nothing, each backend will need to customize this
+        // in case they can canonicalize NaNs.
+    }
+
     /// Gets the custom compiler config
     fn compiler(self: Box<Self>) -> Box<dyn Compiler>;
 
@@ -60,6 +70,21 @@ where
 
 /// An implementation of a Compiler from parsed WebAssembly module to Compiled native code.
 pub trait Compiler: Send + MemoryUsage {
+    /// The name of the compiler, identifying the artifacts it produces.
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+
+    /// A fingerprint of the compiler and of the parts of its configuration
+    /// that change the generated code, such as NaN canonicalization or
+    /// the middlewares.
+    ///
+    /// It's stored in the artifacts, so that an engine doesn't load an
+    /// artifact compiled with a different configuration.
+    fn fingerprint(&self) -> String {
+        self.name().to_string()
+    }
+
     /// Validates a module.
     ///
     /// It returns the a succesful Result in case is valid, `CompileError` in case is not.
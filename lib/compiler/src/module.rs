@@ -1,4 +1,6 @@
+use crate::lib::std::string::String;
 use crate::lib::std::sync::Arc;
+#[cfg(feature = "std")]
 use loupe::MemoryUsage;
 #[cfg(feature = "enable-serde")]
 use serde::{Deserialize, Serialize};
@@ -11,11 +13,17 @@ use wasmer_vm::{MemoryStyle, ModuleInfo, TableStyle};
 /// This differs from [`ModuleInfo`] because it have extra info only
 /// possible after translation (such as the features used for compiling,
 /// or the `MemoryStyle` and `TableStyle`).
-#[derive(Debug, MemoryUsage)]
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 #[cfg_attr(feature = "enable-serde", derive(Deserialize, Serialize))]
 pub struct CompileModuleInfo {
     /// The features used for compiling the module
     pub features: Features,
+    /// The [`Compiler::fingerprint`] of the compiler used for compiling
+    /// the module.
+    ///
+    /// [`Compiler::fingerprint`]: crate::Compiler::fingerprint
+    pub compiler: String,
     /// The module information
     pub module: Arc<ModuleInfo>,
     /// The memory styles used for compiling.
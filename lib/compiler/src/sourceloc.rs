@@ -8,6 +8,7 @@
 //! and tracing errors.
 
 use crate::lib::std::fmt;
+#[cfg(feature = "std")]
 use loupe::MemoryUsage;
 #[cfg(feature = "enable-serde")]
 use serde::{Deserialize, Serialize};
@@ -22,7 +23,8 @@ use serde::{Deserialize, Serialize};
     serde(transparent)
 )]
 #[repr(transparent)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, MemoryUsage)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 pub struct SourceLoc(u32);
 
 impl SourceLoc {
@@ -3,6 +3,7 @@ use crate::error::ParseCpuFeatureError;
 use crate::lib::std::str::FromStr;
 use crate::lib::std::string::{String, ToString};
 use enumset::{EnumSet, EnumSetType};
+#[cfg(feature = "std")]
 use loupe::MemoryUsage;
 pub use target_lexicon::{
     Architecture, BinaryFormat, CallingConvention, Endianness, OperatingSystem, PointerWidth,
@@ -44,7 +45,7 @@ pub enum CpuFeature {
 }
 
 impl CpuFeature {
-    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[cfg(all(feature = "std", any(target_arch = "x86", target_arch = "x86_64")))]
     /// Retrieves the features for the current Host
     pub fn for_host() -> EnumSet<Self> {
         let mut features = EnumSet::new();
@@ -93,10 +94,11 @@ impl CpuFeature {
         }
         features
     }
-    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+    #[cfg(not(all(feature = "std", any(target_arch = "x86", target_arch = "x86_64"))))]
     /// Retrieves the features for the current Host
     pub fn for_host() -> EnumSet<Self> {
-        // We default to an empty hash set
+        // We default to an empty hash set, features can only be detected
+        // at runtime with `std`
         EnumSet::new()
     }
 
@@ -161,11 +163,12 @@ impl ToString for CpuFeature {
 
 /// This is the target that we will use for compiling
 /// the WebAssembly ModuleInfo, and then run it.
-#[derive(Clone, Debug, PartialEq, Eq, Hash, MemoryUsage)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 pub struct Target {
-    #[loupe(skip)]
+    #[cfg_attr(feature = "std", loupe(skip))]
     triple: Triple,
-    #[loupe(skip)]
+    #[cfg_attr(feature = "std", loupe(skip))]
     cpu_features: EnumSet<CpuFeature>,
 }
 
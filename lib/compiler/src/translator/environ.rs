@@ -367,6 +367,11 @@ impl<'data> ModuleEnvironment<'data> {
         Ok(())
     }
 
+    pub(crate) fn declare_code_section_offset(&mut self, offset: usize) -> WasmResult<()> {
+        self.result.module.code_section_offset = Some(offset as u64);
+        Ok(())
+    }
+
     pub(crate) fn define_function_body(
         &mut self,
         _module_translation_state: &ModuleTranslationState,
@@ -26,6 +26,12 @@ pub trait ModuleMiddleware: Debug + Send + Sync + MemoryUsage {
 
     /// Transforms a `ModuleInfo` struct in-place. This is called before application on functions begins.
     fn transform_module_info(&self, _: &mut ModuleInfo) {}
+
+    /// A fingerprint of how this middleware transforms the code, so that
+    /// artifacts compiled with a different configuration aren't loaded.
+    fn fingerprint(&self) -> String {
+        std::any::type_name::<Self>().to_string()
+    }
 }
 
 /// A function middleware specialized for a single function.
@@ -71,6 +77,9 @@ pub trait ModuleMiddlewareChain {
 
     /// Applies the chain on a `ModuleInfo` struct.
     fn apply_on_module_info(&self, module_info: &mut ModuleInfo);
+
+    /// A fingerprint of the whole chain, in order.
+    fn fingerprint(&self) -> String;
 }
 
 impl<T: Deref<Target = dyn ModuleMiddleware>> ModuleMiddlewareChain for [T] {
@@ -90,6 +99,14 @@ impl<T: Deref<Target = dyn ModuleMiddleware>> ModuleMiddlewareChain for [T] {
             item.transform_module_info(module_info);
         }
     }
+
+    /// A fingerprint of the whole chain, in order.
+    fn fingerprint(&self) -> String {
+        self.iter()
+            .map(|x| x.fingerprint())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
 }
 
 impl<'a> MiddlewareReaderState<'a> {
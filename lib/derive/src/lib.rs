@@ -7,7 +7,7 @@ use syn::{spanned::Spanned, *};
 
 mod parse;
 
-use crate::parse::WasmerAttr;
+use crate::parse::{WasmerAttr, WasmerContainerAttr};
 
 #[proc_macro_error]
 #[proc_macro_derive(WasmerEnv, attributes(wasmer))]
@@ -21,9 +21,10 @@ fn impl_wasmer_env_for_struct(
     name: &Ident,
     data: &DataStruct,
     generics: &Generics,
-    _attrs: &[Attribute],
+    attrs: &[Attribute],
 ) -> TokenStream {
-    let (trait_methods, helper_methods) = derive_struct_fields(data);
+    let (mut trait_methods, helper_methods) = derive_struct_fields(data);
+    trait_methods.extend(derive_finish(attrs));
     let lifetimes_and_generics = generics.params.clone();
     let where_clause = generics.where_clause.clone();
     quote! {
@@ -65,6 +66,31 @@ fn impl_wasmer_env(input: &DeriveInput) -> TokenStream {
     }*/
 }
 
+fn derive_finish(attrs: &[Attribute]) -> TokenStream {
+    let mut finish = None;
+    for attr in attrs {
+        if attr.path.is_ident(&Ident::new("wasmer", attr.span())) {
+            match syn::parse2(attr.tokens.clone()) {
+                Ok(WasmerContainerAttr::Finish { path }) => {
+                    finish = Some(path);
+                }
+                Err(e) => {
+                    abort!(attr, "Failed to parse `wasmer` attribute: {}", e);
+                }
+            }
+        }
+    }
+
+    match finish {
+        Some(path) => quote! {
+            fn finish(&mut self, instance: &::wasmer::Instance) -> Result<(), ::wasmer::HostEnvInitError> {
+                #path(self, instance)
+            }
+        },
+        None => quote! {},
+    }
+}
+
 fn derive_struct_fields(data: &DataStruct) -> (TokenStream, TokenStream) {
     let mut finish = vec![];
     let mut helpers = vec![];
@@ -98,7 +124,7 @@ fn derive_struct_fields(data: &DataStruct) -> (TokenStream, TokenStream) {
         }
 
         if let Some(wasmer_attr) = wasmer_attr {
-            let inner_type = get_identifier(top_level_ty);
+            let (inner_type, export_type, is_option) = get_identifier(top_level_ty);
             if let Some(name) = &name {
                 let name_ref_str = format!("{}_ref", name);
                 let name_ref = syn::Ident::new(&name_ref_str, name.span());
@@ -136,15 +162,21 @@ fn derive_struct_fields(data: &DataStruct) -> (TokenStream, TokenStream) {
                             identifier.unwrap_or_else(|| LitStr::new(&name_str, name.span()));
                         let mut access_expr = quote_spanned! {
                             f.span() =>
-                                instance.exports.get_with_generics::<#inner_type, _, _>(#item_name)
+                                instance.exports.get_with_generics::<#export_type, _, _>(#item_name)
                         };
                         for alias in aliases {
                             access_expr = quote_spanned! {
                                 f.span()=>
-                                    #access_expr .or_else(|_| instance.exports.get_with_generics::<#inner_type, _, _>(#alias))
+                                    #access_expr .or_else(|_| instance.exports.get_with_generics::<#export_type, _, _>(#alias))
                             };
                         }
-                        if optional {
+                        if is_option {
+                            quote_spanned! {
+                                f.span()=>
+                                    let #name: #inner_type = #access_expr.ok();
+                                    self.#name.initialize(#name);
+                            }
+                        } else if optional {
                             quote_spanned! {
                                 f.span()=>
                                     match #access_expr {
@@ -163,17 +195,23 @@ fn derive_struct_fields(data: &DataStruct) -> (TokenStream, TokenStream) {
                         if let Some(identifier) = identifier {
                             let mut access_expr = quote_spanned! {
                                 f.span() =>
-                                    instance.exports.get_with_generics::<#inner_type, _, _>(#identifier)
+                                    instance.exports.get_with_generics::<#export_type, _, _>(#identifier)
                             };
                             for alias in aliases {
                                 access_expr = quote_spanned! {
                                     f.span()=>
-                                        #access_expr .or_else(|_| instance.exports.get_with_generics::<#inner_type, _, _>(#alias))
+                                        #access_expr .or_else(|_| instance.exports.get_with_generics::<#export_type, _, _>(#alias))
                                 };
                             }
                             let local_var =
                                 Ident::new(&format!("field_{}", field_num), identifier.span());
-                            if optional {
+                            if is_option {
+                                quote_spanned! {
+                                    f.span()=>
+                                        let #local_var: #inner_type = #access_expr.ok();
+                                    self.#field_idx.initialize(#local_var);
+                                }
+                            } else if optional {
                                 quote_spanned! {
                                     f.span()=>
                                         match #access_expr {
@@ -219,7 +257,9 @@ fn derive_struct_fields(data: &DataStruct) -> (TokenStream, TokenStream) {
 }
 
 // TODO: name this something that makes sense
-fn get_identifier(ty: &Type) -> TokenStream {
+/// Returns the type stored in the `LazyInit`, the type of the export,
+/// and whether the stored type is an `Option` of the export type.
+fn get_identifier(ty: &Type) -> (TokenStream, TokenStream, bool) {
     match ty {
         Type::Path(TypePath {
             path: Path { segments, .. },
@@ -243,10 +283,33 @@ fn get_identifier(ty: &Type) -> TokenStream {
                         ..
                     })) = &args[0]
                     {
-                        segments
+                        let segment = segments
                             .last()
-                            .expect("there must be at least one segment; TODO: error handling")
-                            .to_token_stream()
+                            .expect("there must be at least one segment; TODO: error handling");
+                        // `LazyInit<Option<T>>` stores whether the export `T` is present.
+                        if segment.ident == "Option" {
+                            if let PathArguments::AngleBracketed(AngleBracketedGenericArguments {
+                                args,
+                                ..
+                            }) = &segment.arguments
+                            {
+                                if let Some(GenericArgument::Type(Type::Path(TypePath {
+                                    path: Path { segments, .. },
+                                    ..
+                                }))) = args.first()
+                                {
+                                    if let Some(inner) = segments.last() {
+                                        return (
+                                            segment.to_token_stream(),
+                                            inner.to_token_stream(),
+                                            true,
+                                        );
+                                    }
+                                }
+                            }
+                            abort!(segment, "Expected a type parameter on `Option`");
+                        }
+                        (segment.to_token_stream(), segment.to_token_stream(), false)
                     } else {
                         abort!(
                             &args[0],
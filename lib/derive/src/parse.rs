@@ -3,7 +3,7 @@ use proc_macro_error::abort;
 use syn::{
     parenthesized,
     parse::{Parse, ParseStream},
-    token, Ident, LitBool, LitStr, Token,
+    token, Ident, LitBool, LitStr, Path, Token,
 };
 
 pub enum WasmerAttr {
@@ -37,15 +37,20 @@ impl Parse for ExportOptions {
         let mut aliases: Vec<LitStr> = vec![];
         loop {
             let ident = input.parse::<Ident>()?;
-            let _ = input.parse::<Token![=]>()?;
             let ident_str = ident.to_string();
 
+            // `optional` alone is a shorthand for `optional = true`.
+            let is_flag = ident_str == "optional" && !input.peek(Token![=]);
+            if !is_flag {
+                let _ = input.parse::<Token![=]>()?;
+            }
+
             match ident_str.as_str() {
                 "name" => {
                     name = Some(input.parse::<LitStr>()?);
                 }
                 "optional" => {
-                    optional = input.parse::<LitBool>()?.value;
+                    optional = is_flag || input.parse::<LitBool>()?.value;
                 }
                 "alias" => {
                     let alias = input.parse::<LitStr>()?;
@@ -54,7 +59,7 @@ impl Parse for ExportOptions {
                 otherwise => {
                     abort!(
                         ident,
-                        "Unrecognized argument in export options: expected `name = \"string\"`, `optional = bool`, or `alias = \"string\"` found `{}`",
+                        "Unrecognized argument in export options: expected `name = \"string\"`, `optional`, `optional = bool`, or `alias = \"string\"` found `{}`",
                         otherwise
                     );
                 }
@@ -141,3 +146,30 @@ impl Parse for WasmerAttr {
         Ok(attr_inner.parse::<WasmerAttrInner>()?.0)
     }
 }
+
+/// The `wasmer` attributes of the struct itself.
+pub enum WasmerContainerAttr {
+    /// The function to call in `WasmerEnv::finish`.
+    Finish { path: Path },
+}
+
+impl Parse for WasmerContainerAttr {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let attr_inner;
+        parenthesized!(attr_inner in input);
+        let ident: Ident = attr_inner.parse()?;
+        let ident_str = ident.to_string();
+        match ident_str.as_str() {
+            "finish" => {
+                let _ = attr_inner.parse::<Token![=]>()?;
+                let path = attr_inner.parse::<LitStr>()?.parse::<Path>()?;
+                Ok(WasmerContainerAttr::Finish { path })
+            }
+            otherwise => abort!(
+                ident,
+                "Unexpected identifier `{}`. Expected `finish`.",
+                otherwise
+            ),
+        }
+    }
+}
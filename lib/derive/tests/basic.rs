@@ -117,3 +117,55 @@ struct StructWithAliases {
 fn test_derive_with_aliases() {
     assert!(impls_wasmer_env::<StructWithAliases>());
 }
+
+#[derive(WasmerEnv, Clone)]
+struct StructWithOptionalExports {
+    #[wasmer(export(name = "__heap_base", optional))]
+    heap_base: LazyInit<Option<Global>>,
+    #[wasmer(export(optional, alias = "_memory"))]
+    memory: LazyInit<Option<Memory>>,
+    #[wasmer(export(optional))]
+    table: LazyInit<Table>,
+}
+
+#[derive(WasmerEnv, Clone)]
+struct TupleStructWithOptionalExport(
+    #[wasmer(export(name = "memory", optional))] LazyInit<Option<Memory>>,
+);
+
+#[test]
+fn test_derive_with_optional_exports() -> Result<(), Box<dyn std::error::Error>> {
+    use wasmer::{imports, Instance, Module, Store};
+
+    assert!(impls_wasmer_env::<StructWithOptionalExports>());
+    assert!(impls_wasmer_env::<TupleStructWithOptionalExport>());
+
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+        (module
+          (memory (export "_memory") 1)
+          (global (export "__heap_base") i32 (i32.const 1024)))
+        "#,
+    )?;
+    let instance = Instance::new(&module, &imports! {})?;
+
+    let mut env = StructWithOptionalExports {
+        heap_base: LazyInit::new(),
+        memory: LazyInit::new(),
+        table: LazyInit::new(),
+    };
+    env.init_with_instance(&instance)?;
+    assert!(env.heap_base_ref().unwrap().is_some());
+    assert!(env.memory_ref().unwrap().is_some());
+    assert!(env.table_ref().is_none());
+
+    let module = Module::new(&store, "(module)")?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let mut env = TupleStructWithOptionalExport(LazyInit::new());
+    env.init_with_instance(&instance)?;
+    assert!(matches!(env.0.get_ref(), Some(None)));
+
+    Ok(())
+}
@@ -62,6 +62,7 @@ mod unistd;
 mod utils;
 mod varargs;
 
+pub use self::linking::{DylinkInfo, DynamicLinker};
 pub use self::storage::{align_memory, static_alloc};
 pub use self::utils::{
     allocate_cstr_on_stack, allocate_on_stack, get_emscripten_memory_size, get_emscripten_metadata,
@@ -79,6 +80,7 @@ impl WasmerEnv for EmEnv {
     fn init_with_instance(&mut self, instance: &Instance) -> Result<(), wasmer::HostEnvInitError> {
         let mut ed = self.data.lock().unwrap();
         ed.init_with_instance(instance)?;
+        ed.dynamic_linker.set_main_instance(instance);
         Ok(())
     }
 }
@@ -275,6 +277,7 @@ pub struct EmscriptenData {
     #[wasmer(export(name = "setThrew", alias = "_setThrew", optional = true))]
     pub set_threw: LazyInit<NativeFunc<(i32, i32)>>,
     pub mapped_dirs: HashMap<String, PathBuf>,
+    pub dynamic_linker: linking::DynamicLinker,
 }
 
 impl EmscriptenData {
@@ -1037,6 +1040,13 @@ pub fn generate_emscripten_env(
         },
     };
 
+    env.data.lock().unwrap().dynamic_linker = linking::DynamicLinker::new(
+        store,
+        &globals.table,
+        &import_object,
+        globals.data.memory_base,
+    );
+
     import_object
 }
 
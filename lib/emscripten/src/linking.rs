@@ -1,27 +1,638 @@
+//! Dynamic linking of side modules, built with `-s SIDE_MODULE=1`, loaded
+//! by the main module with `dlopen`.
+//!
+//! A side module describes the memory and the table slots it needs in its
+//! `dylink` custom section. It's given its own region of the memory and of
+//! the table, and its imports are resolved against the exports of the main
+//! module, of the side modules loaded before it, and the Emscripten imports.
+//! The addresses of data symbols and the table indices of functions are
+//! given through mutable globals imported from `GOT.mem` and `GOT.func`.
+
+use crate::env::{call_malloc, get_emscripten_data};
+use crate::utils::{get_cstr_path, read_string_from_wasm};
 use crate::EmEnv;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use wasmer::{
+    Exports, Extern, ExternRef, ExternType, Function, Global, ImportObject, Instance, Linker,
+    Module, RuntimeError, Store, Table, Val,
+};
+
+/// The handle returned by `dlopen(NULL, ...)`, for the main module.
+const MAIN_HANDLE: u32 = u32::MAX;
+
+/// The handle given to `dlsym` to search all the loaded modules.
+const RTLD_DEFAULT: u32 = 0;
+
+/// The memory and table requirements of a side module, read from its
+/// `dylink` or `dylink.0` custom section.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DylinkInfo {
+    /// The size of the static data of the module, in bytes.
+    pub memory_size: u32,
+    /// The log2 of the alignment of the static data.
+    pub memory_alignment: u32,
+    /// The number of table slots needed by the module.
+    pub table_size: u32,
+    /// The log2 of the alignment of the table slots.
+    pub table_alignment: u32,
+    /// The side modules which must be loaded before this one.
+    pub needed: Vec<String>,
+}
+
+impl DylinkInfo {
+    /// Read the `dylink` section of `module`, returning `None` if it isn't
+    /// a side module.
+    pub fn from_module(module: &Module) -> Result<Option<Self>, String> {
+        if let Some(section) = module.custom_sections("dylink.0").next() {
+            return Self::parse_subsections(&section[..]).map(Some);
+        }
+        if let Some(section) = module.custom_sections("dylink").next() {
+            return Self::parse_legacy(&section[..]).map(Some);
+        }
+        Ok(None)
+    }
+
+    /// The format of the `dylink` section, with the fields in order.
+    fn parse_legacy(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = SectionReader { bytes, position: 0 };
+        let mut info = Self {
+            memory_size: reader.read_u32()?,
+            memory_alignment: reader.read_u32()?,
+            table_size: reader.read_u32()?,
+            table_alignment: reader.read_u32()?,
+            needed: vec![],
+        };
+        // the list of needed modules was added later
+        if !reader.is_empty() {
+            info.needed = reader.read_strings()?;
+        }
+        Ok(info)
+    }
+
+    /// The format of the `dylink.0` section, made of subsections.
+    fn parse_subsections(bytes: &[u8]) -> Result<Self, String> {
+        const MEMORY_INFO: u8 = 1;
+        const NEEDED: u8 = 2;
+
+        let mut reader = SectionReader { bytes, position: 0 };
+        let mut info = Self::default();
+        while !reader.is_empty() {
+            let kind = reader.read_byte()?;
+            let payload = reader.read_bytes()?;
+            let mut payload = SectionReader {
+                bytes: payload,
+                position: 0,
+            };
+            match kind {
+                MEMORY_INFO => {
+                    info.memory_size = payload.read_u32()?;
+                    info.memory_alignment = payload.read_u32()?;
+                    info.table_size = payload.read_u32()?;
+                    info.table_alignment = payload.read_u32()?;
+                }
+                NEEDED => info.needed = payload.read_strings()?,
+                // the other subsections describe the symbols, which are
+                // found in the exports
+                _ => (),
+            }
+        }
+        Ok(info)
+    }
+}
+
+struct SectionReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> SectionReader<'a> {
+    fn is_empty(&self) -> bool {
+        self.position >= self.bytes.len()
+    }
+
+    fn read_byte(&mut self) -> Result<u8, String> {
+        let byte = *self
+            .bytes
+            .get(self.position)
+            .ok_or_else(|| "unexpected end of the dylink section".to_string())?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    /// Read an unsigned LEB128 number.
+    fn read_u32(&mut self) -> Result<u32, String> {
+        let mut result = 0u32;
+        for shift in (0..35).step_by(7) {
+            let byte = self.read_byte()?;
+            result |= ((byte & 0x7f) as u32)
+                .checked_shl(shift)
+                .ok_or_else(|| "invalid number in the dylink section".to_string())?;
+            if byte & 0x80 == 0 {
+                return Ok(result);
+            }
+        }
+        Err("invalid number in the dylink section".to_string())
+    }
+
+    fn read_bytes(&mut self) -> Result<&'a [u8], String> {
+        let len = self.read_u32()? as usize;
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + len)
+            .ok_or_else(|| "unexpected end of the dylink section".to_string())?;
+        self.position += len;
+        Ok(bytes)
+    }
+
+    fn read_strings(&mut self) -> Result<Vec<String>, String> {
+        (0..self.read_u32()?)
+            .map(|_| {
+                self.read_bytes()
+                    .map(|bytes| String::from_utf8_lossy(bytes).into_owned())
+            })
+            .collect()
+    }
+}
+
+/// A side module loaded with `dlopen`.
+#[derive(Clone)]
+struct Library {
+    path: PathBuf,
+    instance: Instance,
+    /// The imports of the instance, which doesn't keep the globals and the
+    /// host functions created for it alive
+    _imports: Linker,
+    /// The address of the static data of the module
+    memory_base: u32,
+    /// The number of times it's been opened and not closed
+    references: u32,
+}
+
+/// The state of the dynamic linker, kept in
+/// [`EmscriptenData`](crate::EmscriptenData).
+#[derive(Clone, Default)]
+pub struct DynamicLinker {
+    table: Option<Table>,
+    /// The items the imports of the side modules are resolved against: the
+    /// Emscripten imports, the exports of the main module and the functions
+    /// of the side modules
+    symbols: Option<Linker>,
+    main_exports: Option<Exports>,
+    main_memory_base: u32,
+    /// The loaded side modules, with handles starting at 1
+    libraries: Vec<Library>,
+    /// The table indices of the functions returned by `dlsym`, by handle and
+    /// name
+    function_indices: HashMap<(u32, String), u32>,
+    /// The error returned by the next call to `dlerror`
+    error: Option<String>,
+    /// The string returned by the last call to `dlerror`, freed by the next
+    /// one
+    error_ptr: u32,
+}
+
+impl DynamicLinker {
+    pub(crate) fn new(
+        store: &Store,
+        table: &Table,
+        imports: &ImportObject,
+        main_memory_base: u32,
+    ) -> Self {
+        let mut symbols = Linker::new(store);
+        symbols.allow_shadowing(true);
+        for ((module, name), export) in imports.clone() {
+            symbols
+                .define(&module, &name, Extern::from_vm_export(store, export))
+                .expect("the Emscripten imports come from the same store");
+        }
+        Self {
+            table: Some(table.clone()),
+            symbols: Some(symbols),
+            main_memory_base,
+            ..Default::default()
+        }
+    }
+
+    /// Make the exports of the main module available to the side modules.
+    ///
+    /// It's called with the side modules too, as they import functions
+    /// using the environment of the main module, and only the first
+    /// instance is kept.
+    pub(crate) fn set_main_instance(&mut self, instance: &Instance) {
+        if self.main_exports.is_some() {
+            return;
+        }
+        if let Some(symbols) = &mut self.symbols {
+            // the exports replace the imports of the same name
+            let _ = symbols.instance("env", instance);
+        }
+        self.main_exports = Some(instance.exports.clone());
+    }
+
+    fn library(&self, handle: u32) -> Option<&Library> {
+        self.libraries.get((handle as usize).checked_sub(1)?)
+    }
 
-// TODO: Need to implement.
+    /// Find the function or the data symbol `name` in the exports of the
+    /// module of `handle`, or of all the modules, returning it with the
+    /// address of the static data of its module.
+    fn find_symbol(
+        &self,
+        handle: u32,
+        name: &str,
+        is_function: Option<bool>,
+    ) -> Option<(Extern, u32)> {
+        let main = self
+            .main_exports
+            .iter()
+            .map(|exports| (exports, self.main_memory_base));
+        let libraries = self
+            .libraries
+            .iter()
+            .map(|library| (&library.instance.exports, library.memory_base));
+        let exports: Vec<(&Exports, u32)> = match handle {
+            RTLD_DEFAULT => main.chain(libraries).collect(),
+            MAIN_HANDLE => main.collect(),
+            handle => self
+                .library(handle)
+                .map(|library| (&library.instance.exports, library.memory_base))
+                .into_iter()
+                .collect(),
+        };
+        exports.into_iter().find_map(|(exports, memory_base)| {
+            symbol_names(name)
+                .iter()
+                .filter_map(|name| exports.get_extern(name))
+                .find(|item| match (item, is_function) {
+                    (Extern::Function(_), Some(false)) => false,
+                    (Extern::Global(_), Some(true)) => false,
+                    (Extern::Function(_), _) | (Extern::Global(_), _) => true,
+                    _ => false,
+                })
+                .map(|item| (item.clone(), memory_base))
+        })
+    }
+}
+
+/// The names a C symbol can be exported with: the older versions of
+/// Emscripten prefix them with `_`.
+fn symbol_names(name: &str) -> Vec<String> {
+    match name.strip_prefix('_') {
+        Some(stripped) => vec![name.to_string(), stripped.to_string()],
+        None => vec![name.to_string(), format!("_{}", name)],
+    }
+}
+
+/// Put `function` in a new slot of `table`, returning its index.
+fn add_to_table(table: &Table, function: &Function) -> Result<u32, String> {
+    table
+        .grow(1, Val::FuncRef(function.clone()))
+        .map_err(|e| e.message())
+}
+
+/// Load the side module at `path` and the ones it needs, returning its
+/// handle.
+fn load_library(ctx: &EmEnv, path: &Path) -> Result<u32, String> {
+    let (table, mut symbols) = {
+        let mut data = get_emscripten_data(ctx);
+        let linker = &mut data.dynamic_linker;
+        if let Some(idx) = linker.libraries.iter().position(|lib| lib.path == path) {
+            linker.libraries[idx].references += 1;
+            return Ok(idx as u32 + 1);
+        }
+        match (&linker.table, &linker.symbols) {
+            (Some(table), Some(symbols)) => (table.clone(), symbols.clone()),
+            _ => return Err("dynamic linking isn't set up".to_string()),
+        }
+    };
+    let memory = ctx.memory(0).clone();
+    let store = memory.store().clone();
+
+    let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let module = Module::new(&store, bytes).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let info = DylinkInfo::from_module(&module)?
+        .ok_or_else(|| format!("{}: not a side module", path.display()))?;
+
+    for needed in &info.needed {
+        let sibling = path.with_file_name(needed);
+        if sibling.exists() {
+            load_library(ctx, &sibling)?;
+        } else {
+            load_library(ctx, Path::new(needed))?;
+        }
+    }
+    // the symbols of the modules it needs were just added
+    if !info.needed.is_empty() {
+        if let Some(updated) = &get_emscripten_data(ctx).dynamic_linker.symbols {
+            symbols = updated.clone();
+        }
+    }
+
+    let memory_base = if info.memory_size > 0 {
+        let alignment = 1u32 << info.memory_alignment.min(16);
+        let memalign = get_emscripten_data(ctx).memalign_ref().cloned();
+        let base = memalign
+            .ok_or_else(|| "memalign isn't exported by the main module".to_string())?
+            .call(alignment, info.memory_size)
+            .map_err(|e| e.message())?;
+        if base == 0 {
+            return Err(format!("{}: out of memory", path.display()));
+        }
+        let view = memory.view::<u8>();
+        for cell in &view[base as usize..(base + info.memory_size) as usize] {
+            cell.set(0);
+        }
+        base
+    } else {
+        0
+    };
+
+    let table_alignment = 1u32 << info.table_alignment.min(16);
+    let table_start = table.size();
+    let table_base = (table_start + table_alignment - 1) / table_alignment * table_alignment;
+    table
+        .grow(
+            table_base - table_start + info.table_size,
+            Val::ExternRef(ExternRef::null()),
+        )
+        .map_err(|e| e.message())?;
+
+    for name in &["memoryBase", "__memory_base"] {
+        symbols
+            .define(
+                "env",
+                name,
+                Global::new(&store, Val::I32(memory_base as i32)),
+            )
+            .map_err(|e| e.to_string())?;
+    }
+    for name in &["tableBase", "__table_base"] {
+        symbols
+            .define(
+                "env",
+                name,
+                Global::new(&store, Val::I32(table_base as i32)),
+            )
+            .map_err(|e| e.to_string())?;
+    }
+    symbols
+        .define("env", "__indirect_function_table", table.clone())
+        .map_err(|e| e.to_string())?;
+
+    let mut got_mem = vec![];
+    let mut got_func = vec![];
+    for import in module.imports() {
+        let (namespace, name) = (import.module(), import.name());
+        match (namespace, import.ty()) {
+            ("GOT.mem", _) | ("GOT.func", _) => {
+                let global = Global::new_mut(&store, Val::I32(0));
+                symbols
+                    .define(namespace, name, global.clone())
+                    .map_err(|e| e.to_string())?;
+                if namespace == "GOT.mem" {
+                    got_mem.push((name.to_string(), global));
+                } else {
+                    got_func.push((name.to_string(), global));
+                }
+            }
+            _ if symbols.get(namespace, name).is_some() => (),
+            (_, ExternType::Function(ty)) => {
+                let found = symbol_names(name)
+                    .iter()
+                    .find_map(|alias| symbols.get(namespace, alias).cloned());
+                let item = match found {
+                    Some(item) => item,
+                    // like with lazy binding, the module fails only if it
+                    // calls the function
+                    None => {
+                        let message = format!("undefined symbol: {}", name);
+                        Function::new(&store, ty, move |_| Err(RuntimeError::new(message.clone())))
+                            .into()
+                    }
+                };
+                symbols
+                    .define(namespace, name, item)
+                    .map_err(|e| e.to_string())?;
+            }
+            _ => {
+                if let Some(item) = symbol_names(name)
+                    .iter()
+                    .find_map(|alias| symbols.get(namespace, alias).cloned())
+                {
+                    symbols
+                        .define(namespace, name, item)
+                        .map_err(|e| e.to_string())?;
+                }
+            }
+        }
+    }
+
+    let instance = symbols
+        .instantiate(&module)
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    let handle = {
+        let mut data = get_emscripten_data(ctx);
+        let linker = &mut data.dynamic_linker;
+        linker.libraries.push(Library {
+            path: path.to_path_buf(),
+            instance: instance.clone(),
+            _imports: symbols,
+            memory_base,
+            references: 1,
+        });
+        if let Some(symbols) = &mut linker.symbols {
+            for (name, item) in instance.exports.iter() {
+                if let Extern::Function(_) = item {
+                    if symbols.get("env", name).is_none() {
+                        let _ = symbols.define("env", name, item.clone());
+                    }
+                }
+            }
+        }
+        linker.libraries.len() as u32
+    };
+
+    for (name, global) in got_mem {
+        let address = {
+            let data = get_emscripten_data(ctx);
+            match data
+                .dynamic_linker
+                .find_symbol(RTLD_DEFAULT, &name, Some(false))
+            {
+                Some((Extern::Global(symbol), base)) => match symbol.get() {
+                    Val::I32(offset) => offset as u32 + base,
+                    _ => return Err(format!("invalid data symbol: {}", name)),
+                },
+                _ => return Err(format!("undefined symbol: {}", name)),
+            }
+        };
+        global
+            .set(Val::I32(address as i32))
+            .map_err(|e| e.message())?;
+    }
+    for (name, global) in got_func {
+        let function = {
+            let data = get_emscripten_data(ctx);
+            match data
+                .dynamic_linker
+                .find_symbol(RTLD_DEFAULT, &name, Some(true))
+            {
+                Some((Extern::Function(function), _)) => function,
+                _ => return Err(format!("undefined symbol: {}", name)),
+            }
+        };
+        let index = add_to_table(&table, &function)?;
+        global
+            .set(Val::I32(index as i32))
+            .map_err(|e| e.message())?;
+    }
+
+    // the relocations of the data, then the constructors
+    for name in &[
+        "__wasm_apply_relocs",
+        "__post_instantiate",
+        "__wasm_call_ctors",
+    ] {
+        if let Ok(function) = instance.exports.get_function(name) {
+            function.call(&[]).map_err(|e| e.message())?;
+        }
+    }
+
+    Ok(handle)
+}
+
+fn set_error(ctx: &EmEnv, error: String) {
+    debug!("emscripten::dl: {}", error);
+    get_emscripten_data(ctx).dynamic_linker.error = Some(error);
+}
 
 /// emscripten: dlopen(filename: *const c_char, flag: c_int) -> *mut c_void
-pub fn _dlopen(_ctx: &EmEnv, _filename: u32, _flag: u32) -> i32 {
+pub fn _dlopen(ctx: &EmEnv, filename: u32, _flag: u32) -> i32 {
     debug!("emscripten::_dlopen");
-    -1
+    if filename == 0 {
+        return MAIN_HANDLE as i32;
+    }
+
+    let memory = ctx.memory(0);
+    let path = get_cstr_path(
+        ctx,
+        emscripten_memory_pointer!(memory, filename) as *const _,
+    )
+    .map(|path| PathBuf::from(path.to_string_lossy().into_owned()))
+    .unwrap_or_else(|| PathBuf::from(read_string_from_wasm(memory, filename)));
+    match load_library(ctx, &path) {
+        Ok(handle) => handle as i32,
+        Err(error) => {
+            set_error(ctx, error);
+            0
+        }
+    }
 }
 
 /// emscripten: dlclose(handle: *mut c_void) -> c_int
-pub fn _dlclose(_ctx: &EmEnv, _filename: u32) -> i32 {
+pub fn _dlclose(ctx: &EmEnv, handle: u32) -> i32 {
     debug!("emscripten::_dlclose");
-    -1
+    if handle == MAIN_HANDLE {
+        return 0;
+    }
+
+    let mut data = get_emscripten_data(ctx);
+    let linker = &mut data.dynamic_linker;
+    // like Emscripten, the modules stay loaded, as their functions may
+    // still be in the table
+    match (handle as usize)
+        .checked_sub(1)
+        .and_then(|idx| linker.libraries.get_mut(idx))
+    {
+        Some(library) if library.references > 0 => {
+            library.references -= 1;
+            0
+        }
+        _ => {
+            linker.error = Some(format!("invalid handle: {}", handle));
+            -1
+        }
+    }
 }
 
 /// emscripten: dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void
-pub fn _dlsym(_ctx: &EmEnv, _filepath: u32, _symbol: u32) -> i32 {
+pub fn _dlsym(ctx: &EmEnv, handle: u32, symbol: u32) -> i32 {
     debug!("emscripten::_dlsym");
-    -1
+    let name = read_string_from_wasm(ctx.memory(0), symbol);
+
+    let (found, table, cached) = {
+        let data = get_emscripten_data(ctx);
+        let linker = &data.dynamic_linker;
+        let cached = linker
+            .function_indices
+            .get(&(handle, name.clone()))
+            .cloned();
+        (
+            linker.find_symbol(handle, &name, None),
+            linker.table.clone(),
+            cached,
+        )
+    };
+    if let Some(index) = cached {
+        return index as i32;
+    }
+
+    let result = match (found, table) {
+        (Some((Extern::Function(function), _)), Some(table)) => add_to_table(&table, &function)
+            .map(|index| {
+                get_emscripten_data(ctx)
+                    .dynamic_linker
+                    .function_indices
+                    .insert((handle, name.clone()), index);
+                index
+            }),
+        (Some((Extern::Global(global), memory_base)), _) => match global.get() {
+            Val::I32(offset) => Ok(offset as u32 + memory_base),
+            _ => Err(format!("invalid data symbol: {}", name)),
+        },
+        _ => Err(format!("undefined symbol: {}", name)),
+    };
+    match result {
+        Ok(address) => address as i32,
+        Err(error) => {
+            set_error(ctx, error);
+            0
+        }
+    }
 }
 
 /// emscripten: dlerror() -> *mut c_char
-pub fn _dlerror(_ctx: &EmEnv) -> i32 {
+pub fn _dlerror(ctx: &EmEnv) -> i32 {
     debug!("emscripten::_dlerror");
-    -1
+    let (error, previous_ptr) = {
+        let mut data = get_emscripten_data(ctx);
+        let linker = &mut data.dynamic_linker;
+        (
+            linker.error.take(),
+            std::mem::replace(&mut linker.error_ptr, 0),
+        )
+    };
+    if previous_ptr != 0 {
+        let free = get_emscripten_data(ctx).free_ref().cloned();
+        if let Some(free) = free {
+            let _ = free.call(previous_ptr);
+        }
+    }
+    let error = match error {
+        Some(error) => error,
+        None => return 0,
+    };
+
+    let ptr = call_malloc(ctx, error.len() as u32 + 1);
+    let view = ctx.memory(0).view::<u8>();
+    for (cell, byte) in view[ptr as usize..]
+        .iter()
+        .zip(error.bytes().chain(std::iter::once(0)))
+    {
+        cell.set(byte);
+    }
+    get_emscripten_data(ctx).dynamic_linker.error_ptr = ptr;
+    ptr as i32
 }
@@ -0,0 +1,165 @@
+use anyhow::Result;
+use std::io::Write;
+use wasmer::*;
+use wasmer_emscripten::{generate_emscripten_env, EmEnv, EmscriptenGlobals};
+
+/// Where the main module keeps the names it looks up, and where the test
+/// writes the path of the side module.
+const NAME_ADD: u32 = 12_000_000;
+const NAME_ANSWER: u32 = 12_000_016;
+const NAME_MISSING: u32 = 12_000_032;
+const PATH: u32 = 12_000_064;
+
+fn main_module_wat() -> String {
+    format!(
+        r#"(module
+  (type $binop (func (param i32 i32) (result i32)))
+  (import "env" "memory" (memory 256 256))
+  (import "env" "table" (table 1 funcref))
+  (import "env" "_dlopen" (func $dlopen (param i32 i32) (result i32)))
+  (import "env" "_dlsym" (func $dlsym (param i32 i32) (result i32)))
+  (import "env" "_dlerror" (func $dlerror (result i32)))
+  (global $heap (mut i32) (i32.const 13000000))
+  (data (i32.const {add}) "add\00")
+  (data (i32.const {answer}) "answer\00")
+  (data (i32.const {missing}) "missing\00")
+  (func $memalign (export "_memalign") (param $align i32) (param $size i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr
+      (i32.and
+        (i32.add (global.get $heap) (i32.sub (local.get $align) (i32.const 1)))
+        (i32.sub (i32.const 0) (local.get $align))))
+    (global.set $heap (i32.add (local.get $ptr) (local.get $size)))
+    (local.get $ptr))
+  (func (export "_malloc") (param $size i32) (result i32)
+    (call $memalign (i32.const 8) (local.get $size)))
+  (func (export "_free") (param i32))
+  (func (export "load") (param $path i32) (result i32)
+    (call $dlopen (local.get $path) (i32.const 0)))
+  (func (export "lookup_add") (param $handle i32) (result i32)
+    (call $dlsym (local.get $handle) (i32.const {add})))
+  (func (export "lookup_missing") (param $handle i32) (result i32)
+    (call $dlsym (local.get $handle) (i32.const {missing})))
+  (func (export "call_add") (param $handle i32) (param i32 i32) (result i32)
+    (call_indirect (type $binop)
+      (local.get 1)
+      (local.get 2)
+      (call $dlsym (local.get $handle) (i32.const {add}))))
+  (func (export "read_answer") (param $handle i32) (result i32)
+    (i32.load (call $dlsym (local.get $handle) (i32.const {answer}))))
+  (func (export "error") (result i32)
+    (call $dlerror)))"#,
+        add = NAME_ADD,
+        answer = NAME_ANSWER,
+        missing = NAME_MISSING,
+    )
+}
+
+const SIDE_MODULE_WAT: &str = r#"(module
+  (import "env" "memory" (memory 1))
+  (import "env" "__memory_base" (global $memory_base i32))
+  (data (global.get $memory_base) "\2a\00\00\00")
+  (global (export "answer") i32 (i32.const 0))
+  (func (export "add") (param i32 i32) (result i32)
+    (i32.add (local.get 0) (local.get 1))))"#;
+
+/// A side module: `SIDE_MODULE_WAT` with a `dylink` section asking for 4
+/// bytes of static data aligned to 4 and no table slots.
+fn side_module() -> Result<Vec<u8>> {
+    let mut bytes = wat::parse_str(SIDE_MODULE_WAT)?;
+    let name = b"dylink";
+    let payload = [4, 2, 0, 0];
+    bytes.push(0);
+    bytes.push((1 + name.len() + payload.len()) as u8);
+    bytes.push(name.len() as u8);
+    bytes.extend_from_slice(name);
+    bytes.extend_from_slice(&payload);
+    Ok(bytes)
+}
+
+fn write_string(memory: &Memory, ptr: u32, string: &str) {
+    let view = memory.view::<u8>();
+    for (cell, byte) in view[ptr as usize..]
+        .iter()
+        .zip(string.bytes().chain(std::iter::once(0)))
+    {
+        cell.set(byte);
+    }
+}
+
+fn read_string(memory: &Memory, ptr: u32) -> String {
+    let view = memory.view::<u8>();
+    let bytes: Vec<u8> = view[ptr as usize..]
+        .iter()
+        .map(|cell| cell.get())
+        .take_while(|byte| *byte != 0)
+        .collect();
+    String::from_utf8(bytes).unwrap()
+}
+
+#[test]
+fn dlopen_side_module_and_call_through_dlsym() -> Result<()> {
+    let mut side_module_file = tempfile::NamedTempFile::new()?;
+    side_module_file.write_all(&side_module()?)?;
+    let side_module_path = side_module_file.path().to_str().unwrap().to_string();
+
+    let store = Store::default();
+    let module = Module::new(&store, main_module_wat())?;
+    let mut globals = EmscriptenGlobals::new(&store, &module).map_err(anyhow::Error::msg)?;
+    let mut env = EmEnv::new(&globals.data, Default::default());
+    let import_object = generate_emscripten_env(&store, &mut globals, &env);
+    let instance = Instance::new(&module, &import_object)?;
+    env.set_memory(globals.memory.clone());
+    let memory = &globals.memory;
+
+    let load = instance.exports.get_native_function::<u32, u32>("load")?;
+    let lookup_add = instance
+        .exports
+        .get_native_function::<u32, u32>("lookup_add")?;
+    let lookup_missing = instance
+        .exports
+        .get_native_function::<u32, u32>("lookup_missing")?;
+    let call_add = instance
+        .exports
+        .get_native_function::<(u32, i32, i32), i32>("call_add")?;
+    let read_answer = instance
+        .exports
+        .get_native_function::<u32, i32>("read_answer")?;
+    let error = instance.exports.get_native_function::<(), u32>("error")?;
+
+    write_string(memory, PATH, &side_module_path);
+    let handle = load.call(PATH)?;
+    assert_ne!(handle, 0);
+    assert_eq!(error.call()?, 0);
+    // the library is loaded once
+    assert_eq!(load.call(PATH)?, handle);
+
+    assert_eq!(call_add.call(handle, 2, 3)?, 5);
+    assert_eq!(call_add.call(handle, -7, 10)?, 3);
+    // the function keeps its table slot
+    let index = lookup_add.call(handle)?;
+    assert_ne!(index, 0);
+    assert_eq!(lookup_add.call(handle)?, index);
+    assert_eq!(
+        globals.table.get(index).map(|val| val.ty()),
+        Some(ValType::FuncRef)
+    );
+
+    // the data symbol is relocated to the static data of the library
+    assert_eq!(read_answer.call(handle)?, 42);
+
+    assert_eq!(lookup_missing.call(handle)?, 0);
+    let message = error.call()?;
+    assert_ne!(message, 0);
+    assert_eq!(read_string(memory, message), "undefined symbol: missing");
+    // the error is reported once
+    assert_eq!(error.call()?, 0);
+
+    let missing_path = format!("{}.missing", side_module_path);
+    write_string(memory, PATH, &missing_path);
+    assert_eq!(load.call(PATH)?, 0);
+    let message = error.call()?;
+    assert!(read_string(memory, message).starts_with(&missing_path));
+
+    Ok(())
+}
@@ -11,12 +11,12 @@ use std::sync::{Arc, Mutex};
 use wasmer_compiler::{CompileError, Features, Triple};
 #[cfg(feature = "compiler")]
 use wasmer_compiler::{CompileModuleInfo, ModuleEnvironment};
+#[cfg(feature = "compiler")]
+use wasmer_engine::{check_artifact_compiler, Engine, SerializableFunctionFrameInfo, Tunables};
 use wasmer_engine::{
     register_frame_info, Artifact, DeserializeError, FunctionExtent, GlobalFrameInfoRegistration,
     SerializeError,
 };
-#[cfg(feature = "compiler")]
-use wasmer_engine::{Engine, SerializableFunctionFrameInfo, Tunables};
 use wasmer_types::entity::{BoxedSlice, PrimaryMap};
 use wasmer_types::{
     FunctionIndex, LocalFunctionIndex, MemoryIndex, OwnedDataInitializer, SignatureIndex,
@@ -37,6 +37,7 @@ pub struct JITArtifact {
     signatures: BoxedSlice<SignatureIndex, VMSharedSignatureIndex>,
     frame_info_registration: Mutex<Option<GlobalFrameInfoRegistration>>,
     finished_function_lengths: BoxedSlice<LocalFunctionIndex, usize>,
+    debug_info: bool,
 }
 
 impl JITArtifact {
@@ -73,15 +74,16 @@ impl JITArtifact {
             .map(|table_type| tunables.table_style(table_type))
             .collect();
 
+        let compiler = inner_jit.compiler()?;
+
         let mut compile_info = CompileModuleInfo {
             module: Arc::new(translation.module),
             features: features.clone(),
+            compiler: compiler.fingerprint(),
             memory_styles,
             table_styles,
         };
 
-        let compiler = inner_jit.compiler()?;
-
         // Compile the Module
         let compilation = compiler.compile_module(
             &jit.target(),
@@ -151,7 +153,17 @@ impl JITArtifact {
         let serializable: SerializableModule = bincode::deserialize(inner_bytes)
             .map_err(|e| DeserializeError::CorruptedBinary(format!("{:?}", e)))?;
 
-        Self::from_parts(&mut jit.inner_mut(), serializable).map_err(DeserializeError::Compiler)
+        let mut inner_jit = jit.inner_mut();
+        #[cfg(feature = "compiler")]
+        if let Ok(compiler) = inner_jit.compiler() {
+            check_artifact_compiler(
+                &serializable.compile_info,
+                &compiler.fingerprint(),
+                inner_jit.features(),
+            )?;
+        }
+
+        Self::from_parts(&mut inner_jit, serializable).map_err(DeserializeError::Compiler)
     }
 
     /// Construct a `JITArtifact` from component parts.
@@ -192,6 +204,23 @@ impl JITArtifact {
                 .map(|sig| signature_registry.register(sig))
                 .collect::<PrimaryMap<_, _>>()
         };
+        // The code memory of the trampolines lives as long as the engine,
+        // so they can be shared with the functions of other modules.
+        for (index, trampoline) in finished_function_call_trampolines.iter() {
+            unsafe {
+                inner_jit
+                    .signatures()
+                    .register_trampoline(signatures[index], *trampoline);
+            }
+        }
+        for (index, trampoline) in finished_dynamic_function_trampolines.iter() {
+            let signature = signatures[serializable.compile_info.module.functions[index]];
+            unsafe {
+                inner_jit
+                    .signatures()
+                    .register_dynamic_function_trampoline(signature, *trampoline);
+            }
+        }
 
         let eh_frame = match &serializable.compilation.debug {
             Some(debug) => {
@@ -235,6 +264,7 @@ impl JITArtifact {
             signatures,
             frame_info_registration: Mutex::new(None),
             finished_function_lengths,
+            debug_info: inner_jit.debug_info(),
         })
     }
 
@@ -279,6 +309,7 @@ impl Artifact for JITArtifact {
             self.serializable.compile_info.module.clone(),
             &finished_function_extents,
             frame_infos.clone(),
+            self.debug_info,
         );
     }
 
@@ -7,6 +7,7 @@ pub struct JIT {
     compiler_config: Option<Box<dyn CompilerConfig>>,
     target: Option<Target>,
     features: Option<Features>,
+    debug_info: bool,
 }
 
 impl JIT {
@@ -19,6 +20,7 @@ impl JIT {
             compiler_config: Some(compiler_config.into()),
             target: None,
             features: None,
+            debug_info: false,
         }
     }
 
@@ -28,6 +30,7 @@ impl JIT {
             compiler_config: None,
             target: None,
             features: None,
+            debug_info: false,
         }
     }
 
@@ -43,11 +46,18 @@ impl JIT {
         self
     }
 
+    /// Use the DWARF sections embedded in the modules to report the
+    /// source file, line and column of each frame of a trap.
+    pub fn debug_info(mut self, enable: bool) -> Self {
+        self.debug_info = enable;
+        self
+    }
+
     /// Build the `JITEngine` for this configuration
     #[cfg(feature = "compiler")]
     pub fn engine(self) -> JITEngine {
         let target = self.target.unwrap_or_default();
-        if let Some(compiler_config) = self.compiler_config {
+        let engine = if let Some(compiler_config) = self.compiler_config {
             let features = self
                 .features
                 .unwrap_or_else(|| compiler_config.default_features_for_target(&target));
@@ -55,12 +65,16 @@ impl JIT {
             JITEngine::new(compiler, target, features)
         } else {
             JITEngine::headless()
-        }
+        };
+        engine.inner_mut().set_debug_info(self.debug_info);
+        engine
     }
 
     /// Build the `JITEngine` for this configuration
     #[cfg(not(feature = "compiler"))]
     pub fn engine(self) -> JITEngine {
-        JITEngine::headless()
+        let engine = JITEngine::headless();
+        engine.inner_mut().set_debug_info(self.debug_info);
+        engine
     }
 }
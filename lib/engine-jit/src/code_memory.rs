@@ -5,7 +5,7 @@
 use crate::unwind::UnwindRegistry;
 use loupe::MemoryUsage;
 use wasmer_compiler::{CompiledFunctionUnwindInfo, CustomSection, FunctionBody};
-use wasmer_vm::{Mmap, VMFunctionBody};
+use wasmer_vm::{platform, Mmap, VMFunctionBody};
 
 /// The optimal alignment for functions.
 ///
@@ -52,7 +52,7 @@ impl CodeMemory {
         let mut data_section_result = vec![];
         let mut executable_section_result = vec![];
 
-        let page_size = region::page::size();
+        let page_size = platform().page_size();
 
         // 1. Calculate the total size, that is:
         // - function body size, including all trampolines
@@ -143,14 +143,9 @@ impl CodeMemory {
             return;
         }
         assert!(self.mmap.len() >= self.start_of_nonexecutable_pages);
-        unsafe {
-            region::protect(
-                self.mmap.as_mut_ptr(),
-                self.start_of_nonexecutable_pages,
-                region::Protection::READ_EXECUTE,
-            )
-        }
-        .expect("unable to make memory readonly and executable");
+        self.mmap
+            .make_executable(0, self.start_of_nonexecutable_pages)
+            .expect("unable to make memory readonly and executable");
     }
 
     /// Calculates the allocation size of the given compiled function.
@@ -3,12 +3,16 @@
 use crate::{CodeMemory, JITArtifact};
 use loupe::MemoryUsage;
 use std::sync::{Arc, Mutex};
-#[cfg(feature = "compiler")]
-use wasmer_compiler::Compiler;
 use wasmer_compiler::{
     CompileError, CustomSection, CustomSectionProtection, FunctionBody, SectionIndex, Target,
 };
-use wasmer_engine::{Artifact, DeserializeError, Engine, EngineId, FunctionExtent, Tunables};
+#[cfg(feature = "compiler")]
+use wasmer_compiler::{CompileModuleInfo, Compiler, ModuleTranslationState};
+use wasmer_engine::{
+    artifact_fingerprint, Artifact, DeserializeError, Engine, EngineId, FunctionExtent, Tunables,
+};
+#[cfg(feature = "compiler")]
+use wasmer_types::entity::EntityRef;
 use wasmer_types::entity::PrimaryMap;
 use wasmer_types::Features;
 use wasmer_types::{FunctionIndex, FunctionType, LocalFunctionIndex, SignatureIndex};
@@ -36,6 +40,7 @@ impl JITEngine {
                 code_memory: vec![],
                 signatures: SignatureRegistry::new(),
                 features,
+                debug_info: false,
             })),
             target: Arc::new(target),
             engine_id: EngineId::default(),
@@ -63,6 +68,7 @@ impl JITEngine {
                 code_memory: vec![],
                 signatures: SignatureRegistry::new(),
                 features: Features::default(),
+                debug_info: false,
             })),
             target: Arc::new(Target::default()),
             engine_id: EngineId::default(),
@@ -96,6 +102,27 @@ impl Engine for JITEngine {
         compiler.signatures().lookup(sig)
     }
 
+    /// Lookup the trampoline of a signature
+    fn lookup_trampoline(&self, sig: VMSharedSignatureIndex) -> Option<VMTrampoline> {
+        let compiler = self.inner();
+        compiler.signatures().lookup_trampoline(sig)
+    }
+
+    /// Get the dynamic function trampoline of a signature, compiling it
+    /// if no module compiled it yet
+    fn dynamic_function_trampoline(&self, sig: VMSharedSignatureIndex) -> Option<FunctionBodyPtr> {
+        let mut compiler = self.inner_mut();
+        if let Some(trampoline) = compiler
+            .signatures()
+            .lookup_dynamic_function_trampoline(sig)
+        {
+            return Some(trampoline);
+        }
+        compiler
+            .compile_dynamic_function_trampoline(&self.target, sig)
+            .ok()
+    }
+
     /// Validates a WebAssembly module
     fn validate(&self, binary: &[u8]) -> Result<(), CompileError> {
         self.inner().validate(binary)
@@ -136,6 +163,10 @@ impl Engine for JITEngine {
     fn cloned(&self) -> Arc<dyn Engine + Send + Sync> {
         Arc::new(self.clone())
     }
+
+    fn fingerprint(&self) -> String {
+        artifact_fingerprint("jit", &self.target)
+    }
 }
 
 /// The inner contents of `JITEngine`
@@ -152,6 +183,9 @@ pub struct JITEngineInner {
     /// The signature registry is used mainly to operate with trampolines
     /// performantly.
     signatures: SignatureRegistry,
+    /// Whether the DWARF sections of the modules are used to report
+    /// source locations in traps.
+    debug_info: bool,
 }
 
 impl JITEngineInner {
@@ -184,6 +218,18 @@ impl JITEngineInner {
         &self.features
     }
 
+    /// Whether the DWARF sections of the modules are used to report
+    /// source locations in traps.
+    pub fn debug_info(&self) -> bool {
+        self.debug_info
+    }
+
+    /// Set whether the DWARF sections of the modules are used to report
+    /// source locations in traps.
+    pub fn set_debug_info(&mut self, debug_info: bool) {
+        self.debug_info = debug_info;
+    }
+
     /// Allocate compiled functions into memory
     #[allow(clippy::type_complexity)]
     pub(crate) fn allocate(
@@ -295,6 +341,67 @@ impl JITEngineInner {
         Ok(())
     }
 
+    /// Compile the trampoline to call the dynamic host functions with
+    /// the signature `sig` from WebAssembly, as the one of the only
+    /// imported function of a module, and register it.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn compile_dynamic_function_trampoline(
+        &mut self,
+        target: &Target,
+        sig: VMSharedSignatureIndex,
+    ) -> Result<FunctionBodyPtr, CompileError> {
+        let func_type = self.signatures.lookup(sig).ok_or_else(|| {
+            CompileError::Codegen("The signature isn't registered in the JITEngine".to_string())
+        })?;
+        let mut module = ModuleInfo::new();
+        let signature = module.signatures.push(func_type);
+        module.functions.push(signature);
+        module.num_imported_functions = 1;
+        let mut compile_info = CompileModuleInfo {
+            module: Arc::new(module),
+            features: self.features.clone(),
+            compiler: self.compiler()?.fingerprint(),
+            memory_styles: PrimaryMap::new(),
+            table_styles: PrimaryMap::new(),
+        };
+
+        let compilation = self.compiler()?.compile_module(
+            target,
+            &mut compile_info,
+            &ModuleTranslationState::new(),
+            PrimaryMap::new(),
+        )?;
+        let (_, _, dynamic_function_trampolines, _) = self.allocate(
+            &compile_info.module,
+            &PrimaryMap::new(),
+            &PrimaryMap::new(),
+            &compilation.get_dynamic_function_trampolines(),
+            &PrimaryMap::new(),
+        )?;
+        self.publish_compiled_code();
+
+        let trampoline = dynamic_function_trampolines[FunctionIndex::new(0)];
+        unsafe {
+            self.signatures
+                .register_dynamic_function_trampoline(sig, trampoline);
+        }
+        Ok(trampoline)
+    }
+
+    /// Compile the trampoline to call the dynamic host functions with
+    /// the signature `sig` from WebAssembly.
+    #[cfg(not(feature = "compiler"))]
+    pub(crate) fn compile_dynamic_function_trampoline(
+        &mut self,
+        _target: &Target,
+        _sig: VMSharedSignatureIndex,
+    ) -> Result<FunctionBodyPtr, CompileError> {
+        Err(CompileError::Codegen(
+            "The JITEngine is not compiled with compiler support, which is required for compiling trampolines"
+                .to_string(),
+        ))
+    }
+
     /// Shared signature registry.
     pub fn signatures(&self) -> &SignatureRegistry {
         &self.signatures
@@ -7,6 +7,7 @@ cfg_if::cfg_if! {
         pub use self::systemv::*;
     } else {
         // Otherwise, we provide a dummy fallback without unwinding
+        // (e.g. on Windows on ARM64, where Cranelift emits no unwind info)
         mod dummy;
         pub use self::dummy::DummyUnwindRegistry as UnwindRegistry;
     }
@@ -18,11 +18,11 @@ use tracing::trace;
 use wasmer_compiler::{CompileError, Features, OperatingSystem, Symbol, SymbolRegistry, Triple};
 #[cfg(feature = "compiler")]
 use wasmer_compiler::{
-    CompileModuleInfo, FunctionBodyData, ModuleEnvironment, ModuleTranslationState,
+    CompileModuleInfo, Compiler, FunctionBodyData, ModuleEnvironment, ModuleTranslationState,
 };
-use wasmer_engine::{Artifact, DeserializeError, InstantiationError, SerializeError};
 #[cfg(feature = "compiler")]
-use wasmer_engine::{Engine, Tunables};
+use wasmer_engine::{check_artifact_compiler, Engine, Tunables};
+use wasmer_engine::{Artifact, DeserializeError, InstantiationError, SerializeError};
 #[cfg(feature = "compiler")]
 use wasmer_object::{emit_compilation, emit_data, get_object_for_target};
 use wasmer_types::entity::{BoxedSlice, PrimaryMap};
@@ -100,6 +100,7 @@ impl NativeArtifact {
     /// Generate a compilation
     fn generate_metadata<'data>(
         data: &'data [u8],
+        compiler: &dyn Compiler,
         features: &Features,
         tunables: &dyn Tunables,
     ) -> Result<
@@ -129,6 +130,7 @@ impl NativeArtifact {
         let compile_info = CompileModuleInfo {
             module: Arc::new(translation.module),
             features: features.clone(),
+            compiler: compiler.fingerprint(),
             memory_styles,
             table_styles,
         };
@@ -151,7 +153,7 @@ impl NativeArtifact {
         let target = engine.target();
         let compiler = engine_inner.compiler()?;
         let (compile_info, function_body_inputs, data_initializers, module_translation) =
-            Self::generate_metadata(data, engine_inner.features(), tunables)?;
+            Self::generate_metadata(data, compiler, engine_inner.features(), tunables)?;
 
         let data_initializers = data_initializers
             .iter()
@@ -278,8 +280,11 @@ impl NativeArtifact {
             ]
         } else {
             // We are explicit on the target when the host system is
-            // Apple Silicon, otherwise compilation fails.
-            if target_triple_str == "arm64-apple-darwin" {
+            // Apple Silicon or Windows on ARM64, otherwise compilation
+            // fails (`clang` may itself be an emulated x86_64 binary).
+            if target_triple_str == "arm64-apple-darwin"
+                || target_triple_str == "aarch64-pc-windows-msvc"
+            {
                 vec![format!("--target={}", target_triple_str)]
             } else {
                 vec![]
@@ -447,6 +452,15 @@ impl NativeArtifact {
                 .map(|sig| engine_inner.signatures().register(sig))
                 .collect::<PrimaryMap<_, _>>()
         };
+        // The library is kept loaded as long as the engine, so the
+        // trampolines can be shared with the functions of other modules.
+        for (index, trampoline) in finished_function_call_trampolines.iter() {
+            unsafe {
+                engine_inner
+                    .signatures()
+                    .register_trampoline(signatures[index], *trampoline);
+            }
+        }
 
         engine_inner.add_library(lib);
 
@@ -551,6 +565,14 @@ impl NativeArtifact {
         let metadata: ModuleMetadata = bincode::deserialize(metadata_slice)
             .map_err(|e| DeserializeError::CorruptedBinary(format!("{:?}", e)))?;
         let mut engine_inner = engine.inner_mut();
+        #[cfg(feature = "compiler")]
+        if let Ok(compiler) = engine_inner.compiler() {
+            check_artifact_compiler(
+                &metadata.compile_info,
+                &compiler.fingerprint(),
+                engine_inner.features(),
+            )?;
+        }
 
         Self::from_parts(&mut engine_inner, metadata, shared_path, lib)
             .map_err(DeserializeError::Compiler)
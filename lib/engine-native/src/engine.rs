@@ -9,11 +9,11 @@ use std::sync::Mutex;
 use wasmer_compiler::{CompileError, Target};
 #[cfg(feature = "compiler")]
 use wasmer_compiler::{Compiler, Triple};
-use wasmer_engine::{Artifact, DeserializeError, Engine, EngineId, Tunables};
+use wasmer_engine::{artifact_fingerprint, Artifact, DeserializeError, Engine, EngineId, Tunables};
 #[cfg(feature = "compiler")]
 use wasmer_types::Features;
 use wasmer_types::FunctionType;
-use wasmer_vm::{SignatureRegistry, VMSharedSignatureIndex};
+use wasmer_vm::{SignatureRegistry, VMSharedSignatureIndex, VMTrampoline};
 
 /// A WebAssembly `Native` Engine.
 #[derive(Clone, MemoryUsage)]
@@ -122,6 +122,12 @@ impl Engine for NativeEngine {
         compiler.signatures().lookup(sig)
     }
 
+    /// Lookup the trampoline of a signature
+    fn lookup_trampoline(&self, sig: VMSharedSignatureIndex) -> Option<VMTrampoline> {
+        let compiler = self.inner();
+        compiler.signatures().lookup_trampoline(sig)
+    }
+
     /// Validates a WebAssembly module
     fn validate(&self, binary: &[u8]) -> Result<(), CompileError> {
         self.inner().validate(binary)
@@ -173,6 +179,10 @@ impl Engine for NativeEngine {
     fn cloned(&self) -> Arc<dyn Engine + Send + Sync> {
         Arc::new(self.clone())
     }
+
+    fn fingerprint(&self) -> String {
+        artifact_fingerprint("native", &self.target)
+    }
 }
 
 #[derive(Clone, Copy, MemoryUsage)]
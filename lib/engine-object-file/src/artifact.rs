@@ -11,11 +11,11 @@ use std::sync::Arc;
 use wasmer_compiler::{CompileError, Features, OperatingSystem, SymbolRegistry, Triple};
 #[cfg(feature = "compiler")]
 use wasmer_compiler::{
-    CompileModuleInfo, FunctionBodyData, ModuleEnvironment, ModuleTranslationState,
+    CompileModuleInfo, Compiler, FunctionBodyData, ModuleEnvironment, ModuleTranslationState,
 };
-use wasmer_engine::{Artifact, DeserializeError, InstantiationError, SerializeError};
 #[cfg(feature = "compiler")]
-use wasmer_engine::{Engine, Tunables};
+use wasmer_engine::{check_artifact_compiler, Engine, Tunables};
+use wasmer_engine::{Artifact, DeserializeError, InstantiationError, SerializeError};
 #[cfg(feature = "compiler")]
 use wasmer_object::{emit_compilation, emit_data, get_object_for_target};
 use wasmer_types::entity::EntityRef;
@@ -98,6 +98,7 @@ impl ObjectFileArtifact {
     /// Generate a compilation
     fn generate_metadata<'data>(
         data: &'data [u8],
+        compiler: &dyn Compiler,
         features: &Features,
         tunables: &dyn Tunables,
     ) -> Result<
@@ -126,6 +127,7 @@ impl ObjectFileArtifact {
         let compile_info = CompileModuleInfo {
             module: Arc::new(translation.module),
             features: features.clone(),
+            compiler: compiler.fingerprint(),
             memory_styles,
             table_styles,
         };
@@ -150,7 +152,7 @@ impl ObjectFileArtifact {
         let target = engine.target();
         let compiler = engine_inner.compiler()?;
         let (compile_info, function_body_inputs, data_initializers, module_translation) =
-            Self::generate_metadata(data, engine_inner.features(), tunables)?;
+            Self::generate_metadata(data, compiler, engine_inner.features(), tunables)?;
 
         let data_initializers = data_initializers
             .iter()
@@ -304,6 +306,17 @@ impl ObjectFileArtifact {
         let data_len = leb128::read::unsigned(&mut reader).unwrap() as usize;
 
         let metadata: ModuleMetadata = bincode::deserialize(&bytes[10..(data_len + 10)]).unwrap();
+        #[cfg(feature = "compiler")]
+        {
+            let engine_inner = engine.inner();
+            if let Ok(compiler) = engine_inner.compiler() {
+                check_artifact_compiler(
+                    &metadata.compile_info,
+                    &compiler.fingerprint(),
+                    engine_inner.features(),
+                )?;
+            }
+        }
 
         const WORD_SIZE: usize = mem::size_of::<usize>();
         let mut byte_buffer = [0u8; WORD_SIZE];
@@ -359,6 +372,13 @@ impl ObjectFileArtifact {
             finished_function_call_trampolines.push(trampoline);
             // TODO: we can read back the length here if we serialize it. This will improve debug output.
         }
+        // The trampolines are linked into the executable, so they can be
+        // shared with the functions of other modules.
+        for (index, trampoline) in finished_function_call_trampolines.iter() {
+            if let Some(shared_idx) = signatures.get(index) {
+                signature_registry.register_trampoline(*shared_idx, *trampoline);
+            }
+        }
 
         // read dynamic function trampolines in order now...
         let mut finished_dynamic_function_trampolines = PrimaryMap::new();
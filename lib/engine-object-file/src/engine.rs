@@ -6,11 +6,11 @@ use std::sync::{Arc, Mutex};
 #[cfg(feature = "compiler")]
 use wasmer_compiler::Compiler;
 use wasmer_compiler::{CompileError, Target};
-use wasmer_engine::{Artifact, DeserializeError, Engine, EngineId, Tunables};
+use wasmer_engine::{artifact_fingerprint, Artifact, DeserializeError, Engine, EngineId, Tunables};
 #[cfg(feature = "compiler")]
 use wasmer_types::Features;
 use wasmer_types::FunctionType;
-use wasmer_vm::{SignatureRegistry, VMSharedSignatureIndex};
+use wasmer_vm::{SignatureRegistry, VMSharedSignatureIndex, VMTrampoline};
 
 /// A WebAssembly `ObjectFile` Engine.
 #[derive(Clone, MemoryUsage)]
@@ -110,6 +110,12 @@ impl Engine for ObjectFileEngine {
         compiler.signatures().lookup(sig)
     }
 
+    /// Lookup the trampoline of a signature
+    fn lookup_trampoline(&self, sig: VMSharedSignatureIndex) -> Option<VMTrampoline> {
+        let compiler = self.inner();
+        compiler.signatures().lookup_trampoline(sig)
+    }
+
     /// Validates a WebAssembly module
     fn validate(&self, binary: &[u8]) -> Result<(), CompileError> {
         self.inner().validate(binary)
@@ -163,6 +169,10 @@ impl Engine for ObjectFileEngine {
     fn cloned(&self) -> Arc<dyn Engine + Send + Sync> {
         Arc::new(self.clone())
     }
+
+    fn fingerprint(&self) -> String {
+        artifact_fingerprint("object-file", &self.target)
+    }
 }
 
 /// The inner contents of `ObjectFileEngine`
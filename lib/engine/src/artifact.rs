@@ -1,11 +1,18 @@
+use crate::lib::std::any::Any;
+use crate::lib::std::boxed::Box;
+#[cfg(feature = "std")]
+use crate::lib::std::fs;
+#[cfg(feature = "std")]
+use crate::lib::std::path::Path;
+use crate::lib::std::sync::Arc;
+use crate::lib::std::vec::Vec;
+#[cfg(feature = "core")]
+use crate::lib::MemoryUsage;
 use crate::{
     resolve_imports, InstantiationError, Resolver, RuntimeError, SerializeError, Tunables,
 };
+#[cfg(feature = "std")]
 use loupe::MemoryUsage;
-use std::any::Any;
-use std::fs;
-use std::path::Path;
-use std::sync::Arc;
 use wasmer_compiler::Features;
 use wasmer_types::entity::{BoxedSlice, PrimaryMap};
 use wasmer_types::{
@@ -71,6 +78,7 @@ pub trait Artifact: Send + Sync + Upcastable + MemoryUsage {
     fn serialize(&self) -> Result<Vec<u8>, SerializeError>;
 
     /// Serializes an artifact into a file path
+    #[cfg(feature = "std")]
     fn serialize_to_file(&self, path: &Path) -> Result<(), SerializeError> {
         let serialized = self.serialize()?;
         fs::write(&path, serialized)?;
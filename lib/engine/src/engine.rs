@@ -1,15 +1,21 @@
 //! JIT compilation.
 
+#[cfg(feature = "std")]
+use crate::lib::std::path::Path;
+use crate::lib::std::string::String;
+use crate::lib::std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+use crate::lib::std::sync::Arc;
+#[cfg(feature = "core")]
+use crate::lib::MemoryUsage;
 use crate::tunables::Tunables;
 use crate::{Artifact, DeserializeError};
+#[cfg(feature = "std")]
 use loupe::MemoryUsage;
+#[cfg(feature = "std")]
 use memmap2::Mmap;
-use std::path::Path;
-use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
-use std::sync::Arc;
-use wasmer_compiler::{CompileError, Target};
-use wasmer_types::FunctionType;
-use wasmer_vm::VMSharedSignatureIndex;
+use wasmer_compiler::{CompileError, CompileModuleInfo, Target};
+use wasmer_types::{Features, FunctionType};
+use wasmer_vm::{FunctionBodyPtr, VMSharedSignatureIndex, VMTrampoline};
 
 /// A unimplemented Wasmer `Engine`.
 ///
@@ -27,6 +33,19 @@ pub trait Engine: MemoryUsage {
     /// Lookup a signature
     fn lookup_signature(&self, sig: VMSharedSignatureIndex) -> Option<FunctionType>;
 
+    /// Lookup the trampoline to call the functions with the signature
+    /// `sig` from the host, if this engine compiled one.
+    fn lookup_trampoline(&self, _sig: VMSharedSignatureIndex) -> Option<VMTrampoline> {
+        None
+    }
+
+    /// Get the trampoline to call the dynamic host functions with the
+    /// signature `sig` from WebAssembly, e.g. through a table, compiling
+    /// it if needed and if this engine can.
+    fn dynamic_function_trampoline(&self, _sig: VMSharedSignatureIndex) -> Option<FunctionBodyPtr> {
+        None
+    }
+
     /// Validates a WebAssembly module
     fn validate(&self, binary: &[u8]) -> Result<(), CompileError>;
 
@@ -49,11 +68,12 @@ pub trait Engine: MemoryUsage {
     /// # Safety
     ///
     /// The file's content must represent a serialized WebAssembly module.
+    #[cfg(feature = "std")]
     unsafe fn deserialize_from_file(
         &self,
         file_ref: &Path,
     ) -> Result<Arc<dyn Artifact>, DeserializeError> {
-        let file = std::fs::File::open(file_ref)?;
+        let file = crate::lib::std::fs::File::open(file_ref)?;
         let mmap = Mmap::map(&file)?;
         self.deserialize(&mmap)
     }
@@ -67,9 +87,62 @@ pub trait Engine: MemoryUsage {
 
     /// Clone the engine
     fn cloned(&self) -> Arc<dyn Engine + Send + Sync>;
+
+    /// A fingerprint of what the artifacts produced by this engine depend
+    /// on: the Wasmer version, the engine and the target.
+    ///
+    /// Caches use it so that an artifact built by another engine misses
+    /// the cache instead of failing to load. It doesn't include the
+    /// compiler, so that headless engines can load the artifacts of
+    /// compiling engines: the compiler configuration and the features are
+    /// stored in the artifacts instead, and checked by
+    /// [`check_artifact_compiler`] when they're loaded.
+    fn fingerprint(&self) -> String {
+        artifact_fingerprint("custom", self.target())
+    }
+}
+
+/// Build the [`Engine::fingerprint`] of the artifacts produced by the
+/// `engine` for `target`.
+pub fn artifact_fingerprint(engine: &str, target: &Target) -> String {
+    format!(
+        "wasmer-{}/{}/{}/{:?}",
+        crate::VERSION,
+        engine,
+        target.triple(),
+        target.cpu_features(),
+    )
+}
+
+/// Check that an artifact, described by its `compile_info`, was compiled
+/// with the same compiler configuration and features as the ones of a
+/// compiling engine, given by the `Compiler::fingerprint` of its compiler, so that e.g. a metered engine doesn't load an
+/// artifact compiled without metering.
+///
+/// Headless engines don't have a compiler to compare with, so they don't
+/// need this check.
+pub fn check_artifact_compiler(
+    compile_info: &CompileModuleInfo,
+    compiler: &str,
+    features: &Features,
+) -> Result<(), DeserializeError> {
+    if compile_info.compiler != compiler {
+        return Err(DeserializeError::Incompatible(format!(
+            "The module was compiled with `{}`, but the engine compiles with `{}`",
+            compile_info.compiler, compiler
+        )));
+    }
+    if &compile_info.features != features {
+        return Err(DeserializeError::Incompatible(format!(
+            "The module was compiled with the features {:?}, but the engine uses {:?}",
+            compile_info.features, features
+        )));
+    }
+    Ok(())
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, MemoryUsage)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 #[repr(transparent)]
 /// A unique identifier for an Engine.
 pub struct EngineId {
@@ -1,41 +1,49 @@
 //! The WebAssembly possible errors
+#[cfg(feature = "std")]
+use crate::lib::std::io;
+use crate::lib::std::string::String;
 use crate::trap::RuntimeError;
-use std::io;
+#[cfg(feature = "std")]
 use thiserror::Error;
 use wasmer_compiler::CompileError;
 use wasmer_types::ExternType;
+use wasmer_vm::MemoryStyle;
 
 /// The Serialize error can occur when serializing a
 /// compiled Module into a binary.
-#[derive(Error, Debug)]
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(Error))]
 pub enum SerializeError {
     /// An IO error
+    #[cfg(feature = "std")]
     #[error(transparent)]
     Io(#[from] io::Error),
     /// A generic serialization error
-    #[error("{0}")]
+    #[cfg_attr(feature = "std", error("{0}"))]
     Generic(String),
 }
 
 /// The Deserialize error can occur when loading a
 /// compiled Module from a binary.
-#[derive(Error, Debug)]
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(Error))]
 pub enum DeserializeError {
     /// An IO error
+    #[cfg(feature = "std")]
     #[error(transparent)]
     Io(#[from] io::Error),
     /// A generic deserialization error
-    #[error("{0}")]
+    #[cfg_attr(feature = "std", error("{0}"))]
     Generic(String),
     /// Incompatible serialized binary
-    #[error("incompatible binary: {0}")]
+    #[cfg_attr(feature = "std", error("incompatible binary: {0}"))]
     Incompatible(String),
     /// The provided binary is corrupted
-    #[error("corrupted binary: {0}")]
+    #[cfg_attr(feature = "std", error("corrupted binary: {0}"))]
     CorruptedBinary(String),
     /// The binary was valid, but we got an error when
     /// trying to allocate the required resources.
-    #[error(transparent)]
+    #[cfg_attr(feature = "std", error(transparent))]
     Compiler(CompileError),
 }
 
@@ -43,16 +51,29 @@ pub enum DeserializeError {
 ///
 /// Note: this error is not standard to WebAssembly, but it's
 /// useful to determine the import issue on the API side.
-#[derive(Error, Debug)]
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(Error))]
 pub enum ImportError {
     /// Incompatible Import Type.
     /// This error occurs when the import types mismatch.
-    #[error("incompatible import type. Expected {0:?} but received {1:?}")]
+    #[cfg_attr(
+        feature = "std",
+        error("incompatible import type. Expected {0:?} but received {1:?}")
+    )]
     IncompatibleType(ExternType, ExternType),
 
+    /// Incompatible Memory Style.
+    /// This error occurs when an imported memory doesn't provide the
+    /// bounds-checking guarantees the importing module was compiled for.
+    #[cfg_attr(
+        feature = "std",
+        error("incompatible memory style. Expected {0:?} but received {1:?}")
+    )]
+    IncompatibleMemoryStyle(MemoryStyle, MemoryStyle),
+
     /// Unknown Import.
     /// This error occurs when an import was expected but not provided.
-    #[error("unknown import. Expected {0:?}")]
+    #[cfg_attr(feature = "std", error("unknown import. Expected {0:?}"))]
     UnknownImport(ExternType),
 }
 
@@ -62,19 +83,20 @@ pub enum ImportError {
 /// This is based on the [link error][link-error] API.
 ///
 /// [link-error]: https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/WebAssembly/LinkError
-#[derive(Error, Debug)]
-#[error("Link error: {0}")]
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(Error))]
+#[cfg_attr(feature = "std", error("Link error: {0}"))]
 pub enum LinkError {
     /// An error occurred when checking the import types.
-    #[error("Error while importing {0:?}.{1:?}: {2}")]
+    #[cfg_attr(feature = "std", error("Error while importing {0:?}.{1:?}: {2}"))]
     Import(String, String, ImportError),
 
     /// A trap ocurred during linking.
-    #[error("RuntimeError occurred during linking: {0}")]
-    Trap(#[source] RuntimeError),
+    #[cfg_attr(feature = "std", error("RuntimeError occurred during linking: {0}"))]
+    Trap(#[cfg_attr(feature = "std", source)] RuntimeError),
 
     /// Insufficient resources available for linking.
-    #[error("Insufficient resources: {0}")]
+    #[cfg_attr(feature = "std", error("Insufficient resources: {0}"))]
     Resource(String),
 }
 
@@ -85,13 +107,14 @@ pub enum LinkError {
 /// that happens while linking, on instantiation) and a
 /// Trap that occurs when calling the WebAssembly module
 /// start function.
-#[derive(Error, Debug)]
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(Error))]
 pub enum InstantiationError {
     /// A linking ocurred during instantiation.
-    #[error(transparent)]
+    #[cfg_attr(feature = "std", error(transparent))]
     Link(LinkError),
 
     /// A runtime error occured while invoking the start function
-    #[error(transparent)]
+    #[cfg_attr(feature = "std", error(transparent))]
     Start(RuntimeError),
 }
@@ -1,5 +1,8 @@
+use crate::lib::std::any::TypeId;
+use crate::lib::std::ffi::c_void;
+use crate::lib::std::sync::Arc;
+#[cfg(feature = "std")]
 use loupe::MemoryUsage;
-use std::sync::Arc;
 use wasmer_vm::{
     ImportInitializerFuncPtr, VMExport, VMExportFunction, VMExportGlobal, VMExportMemory,
     VMExportTable,
@@ -54,7 +57,8 @@ impl From<VMExport> for Export {
 ///
 /// This struct owns the original `host_env`, thus when it gets dropped
 /// it calls the `drop` function on it.
-#[derive(Debug, PartialEq, MemoryUsage)]
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 pub struct ExportFunctionMetadata {
     /// This field is stored here to be accessible by `Drop`.
     ///
@@ -68,7 +72,7 @@ pub struct ExportFunctionMetadata {
     ///
     /// See `wasmer_vm::export::VMExportFunction::vmctx` for the version of
     /// this pointer that is used by the VM when creating an `Instance`.
-    pub(crate) host_env: *mut std::ffi::c_void,
+    pub(crate) host_env: *mut c_void,
 
     /// Function pointer to `WasmerEnv::init_with_instance(&mut self, instance: &Instance)`.
     ///
@@ -76,20 +80,28 @@ pub struct ExportFunctionMetadata {
     /// we create the `api::Instance`.
     // This one is optional for now because dynamic host envs need the rest
     // of this without the init fn
-    #[loupe(skip)]
+    #[cfg_attr(feature = "std", loupe(skip))]
     pub(crate) import_init_function_ptr: Option<ImportInitializerFuncPtr>,
 
+    /// Function pointer to `WasmerEnv::finish(&mut self, instance: &Instance)`,
+    /// with the `TypeId` of the `WasmerEnv`.
+    ///
+    /// This function is called once per `api::Instance` and per type of
+    /// env, after every env has been initialized.
+    #[cfg_attr(feature = "std", loupe(skip))]
+    pub(crate) import_finish_function_ptr: Option<(TypeId, ImportInitializerFuncPtr)>,
+
     /// A function analogous to `Clone::clone` that returns a leaked `Box`.
-    #[loupe(skip)]
-    pub(crate) host_env_clone_fn: fn(*mut std::ffi::c_void) -> *mut std::ffi::c_void,
+    #[cfg_attr(feature = "std", loupe(skip))]
+    pub(crate) host_env_clone_fn: fn(*mut c_void) -> *mut c_void,
 
     /// The destructor to free the host environment.
     ///
     /// # Safety
     /// - This function should only be called in when properly synchronized.
     /// For example, in the `Drop` implementation of this type.
-    #[loupe(skip)]
-    pub(crate) host_env_drop_fn: unsafe fn(*mut std::ffi::c_void),
+    #[cfg_attr(feature = "std", loupe(skip))]
+    pub(crate) host_env_drop_fn: unsafe fn(*mut c_void),
 }
 
 /// This can be `Send` because `host_env` comes from `WasmerEnv` which is
@@ -107,14 +119,16 @@ impl ExportFunctionMetadata {
     /// - the `host_env` must be `Send`.
     /// - all function pointers must work on any thread.
     pub unsafe fn new(
-        host_env: *mut std::ffi::c_void,
+        host_env: *mut c_void,
         import_init_function_ptr: Option<ImportInitializerFuncPtr>,
-        host_env_clone_fn: fn(*mut std::ffi::c_void) -> *mut std::ffi::c_void,
-        host_env_drop_fn: fn(*mut std::ffi::c_void),
+        import_finish_function_ptr: Option<(TypeId, ImportInitializerFuncPtr)>,
+        host_env_clone_fn: fn(*mut c_void) -> *mut c_void,
+        host_env_drop_fn: fn(*mut c_void),
     ) -> Self {
         Self {
             host_env,
             import_init_function_ptr,
+            import_finish_function_ptr,
             host_env_clone_fn,
             host_env_drop_fn,
         }
@@ -138,7 +152,8 @@ impl Drop for ExportFunctionMetadata {
 
 /// A function export value with an extra function pointer to initialize
 /// host environments.
-#[derive(Debug, Clone, PartialEq, MemoryUsage)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 pub struct ExportFunction {
     /// The VM function, containing most of the data.
     pub vm_function: VMExportFunction,
@@ -2,6 +2,7 @@
 
 #![deny(missing_docs, trivial_numeric_casts, unused_extern_crates)]
 #![warn(unused_import_braces)]
+#![cfg_attr(not(feature = "std"), no_std)]
 #![cfg_attr(
     feature = "cargo-clippy",
     allow(clippy::new_without_default, clippy::new_without_default)
@@ -20,6 +21,59 @@
     )
 )]
 
+#[cfg(all(feature = "std", feature = "core"))]
+compile_error!(
+    "The `std` and `core` features are both enabled, which is an error. Please enable only once."
+);
+
+#[cfg(all(not(feature = "std"), not(feature = "core")))]
+compile_error!("Both the `std` and `core` features are disabled. Please enable one of them.");
+
+#[cfg(feature = "core")]
+#[macro_use]
+extern crate alloc;
+
+/// The `lib` module defines a `std` module that is identical whether
+/// the `core` or the `std` feature is enabled.
+mod lib {
+    #[cfg(feature = "core")]
+    pub mod std {
+        pub use ::alloc::{boxed, string, vec};
+        pub use core::{any, cmp, ffi, fmt, ptr};
+
+        pub mod error {
+            pub use wasmer_vm::Error;
+        }
+
+        pub mod collections {
+            pub use ::alloc::collections::BTreeMap;
+            pub use hashbrown::HashMap;
+        }
+
+        pub mod sync {
+            pub use ::alloc::sync::Arc;
+            pub use core::sync::atomic;
+            pub use wasmer_vm::lock::{RwLock, RwLockReadGuard};
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub mod std {
+        pub use std::{
+            any, boxed, cmp, collections, error, ffi, fmt, fs, io, path, ptr, string, sync, vec,
+        };
+    }
+
+    /// Without the `std` feature, memory usage isn't measured and this
+    /// trait stands in for `loupe::MemoryUsage` in the bounds of the
+    /// `Engine`, `Artifact` and `Tunables` traits.
+    #[cfg(feature = "core")]
+    pub trait MemoryUsage {}
+
+    #[cfg(feature = "core")]
+    impl<T: ?Sized> MemoryUsage for T {}
+}
+
 mod artifact;
 mod engine;
 mod error;
@@ -30,7 +84,7 @@ mod trap;
 mod tunables;
 
 pub use crate::artifact::Artifact;
-pub use crate::engine::{Engine, EngineId};
+pub use crate::engine::{artifact_fingerprint, check_artifact_compiler, Engine, EngineId};
 pub use crate::error::{
     DeserializeError, ImportError, InstantiationError, LinkError, SerializeError,
 };
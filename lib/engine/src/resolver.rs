@@ -2,10 +2,11 @@
 //! references.
 
 use crate::{Export, ExportFunctionMetadata, ImportError, LinkError};
-use more_asserts::assert_ge;
 use wasmer_types::entity::{BoxedSlice, EntityRef, PrimaryMap};
 use wasmer_types::{ExternType, FunctionIndex, ImportIndex, MemoryIndex, TableIndex};
 
+use crate::lib::std::boxed::Box;
+use crate::lib::std::string::ToString;
 use wasmer_vm::{
     FunctionBodyPtr, ImportFunctionEnv, Imports, MemoryStyle, ModuleInfo, TableStyle,
     VMFunctionBody, VMFunctionEnvironment, VMFunctionImport, VMFunctionKind, VMGlobalImport,
@@ -205,6 +206,10 @@ pub fn resolve_imports(
                 });
 
                 let initializer = f.metadata.as_ref().and_then(|m| m.import_init_function_ptr);
+                let finisher = f
+                    .metadata
+                    .as_ref()
+                    .and_then(|m| m.import_finish_function_ptr);
                 let clone = f.metadata.as_ref().map(|m| m.host_env_clone_fn);
                 let destructor = f.metadata.as_ref().map(|m| m.host_env_drop_fn);
                 let import_function_env =
@@ -213,6 +218,7 @@ pub fn resolve_imports(
                             env,
                             clone,
                             initializer,
+                            finisher,
                             destructor,
                         }
                     } else {
@@ -234,20 +240,30 @@ pub fn resolve_imports(
                         // guard-page protections the importing module expects it to have.
                         let export_memory_style = m.vm_memory.style();
                         let import_memory_style = &memory_styles[*index];
-                        if let (
-                            MemoryStyle::Static { bound, .. },
-                            MemoryStyle::Static {
-                                bound: import_bound,
-                                ..
-                            },
-                        ) = (export_memory_style.clone(), &import_memory_style)
+                        let compatible_bound = match (export_memory_style, import_memory_style) {
+                            (
+                                MemoryStyle::Static { bound, .. },
+                                MemoryStyle::Static {
+                                    bound: import_bound,
+                                    ..
+                                },
+                            ) => bound >= import_bound,
+                            (MemoryStyle::Dynamic { .. }, MemoryStyle::Static { .. }) => false,
+                            (_, MemoryStyle::Dynamic { .. }) => true,
+                        };
+                        if !compatible_bound
+                            || export_memory_style.offset_guard_size()
+                                < import_memory_style.offset_guard_size()
                         {
-                            assert_ge!(bound, *import_bound);
+                            return Err(LinkError::Import(
+                                module_name.to_string(),
+                                field.to_string(),
+                                ImportError::IncompatibleMemoryStyle(
+                                    import_memory_style.clone(),
+                                    export_memory_style.clone(),
+                                ),
+                            ));
                         }
-                        assert_ge!(
-                            export_memory_style.offset_guard_size(),
-                            import_memory_style.offset_guard_size()
-                        );
                     }
                     _ => {
                         // This should never be reached, as we did compatibility
@@ -1,12 +1,17 @@
+use crate::lib::std::fmt;
+use crate::lib::std::vec::Vec;
+#[cfg(feature = "std")]
 use loupe::MemoryUsage;
 use serde::de::{Deserializer, Visitor};
+#[cfg(feature = "core")]
+use serde::ser::Error as _;
 use serde::ser::Serializer;
 use serde::{Deserialize, Serialize};
-use std::fmt;
 use wasmer_compiler::CompiledFunctionFrameInfo;
 
 /// This is the unserialized verison of `CompiledFunctionFrameInfo`.
-#[derive(Clone, Serialize, Deserialize, MemoryUsage)]
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 #[serde(transparent)]
 #[repr(transparent)]
 pub struct UnprocessedFunctionFrameInfo {
@@ -14,6 +19,7 @@ pub struct UnprocessedFunctionFrameInfo {
     bytes: Vec<u8>,
 }
 
+#[cfg(feature = "std")]
 impl UnprocessedFunctionFrameInfo {
     /// Converts the `UnprocessedFunctionFrameInfo` to a `CompiledFunctionFrameInfo`
     pub fn deserialize(&self) -> CompiledFunctionFrameInfo {
@@ -45,7 +51,8 @@ impl UnprocessedFunctionFrameInfo {
 /// of compiling at the same time that emiting the JIT.
 /// In that case, we don't need to deserialize/process anything
 /// as the data is already in memory.
-#[derive(Clone, MemoryUsage)]
+#[derive(Clone)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 pub enum SerializableFunctionFrameInfo {
     /// The unprocessed frame info (binary)
     Unprocessed(UnprocessedFunctionFrameInfo),
@@ -70,7 +77,14 @@ impl Serialize for SerializableFunctionFrameInfo {
         S: Serializer,
     {
         let unprocessed = match self {
+            #[cfg(feature = "std")]
             Self::Processed(processed) => UnprocessedFunctionFrameInfo::serialize(processed),
+            #[cfg(feature = "core")]
+            Self::Processed(_) => {
+                return Err(S::Error::custom(
+                    "processed frame info can't be serialized without the `std` feature",
+                ))
+            }
             Self::Unprocessed(unprocessed) => unprocessed.clone(),
         };
         s.serialize_bytes(&unprocessed.bytes)
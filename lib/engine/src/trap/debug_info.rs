@@ -0,0 +1,202 @@
+//! Source locations of WebAssembly instructions, read from the DWARF
+//! sections embedded in a module.
+//!
+//! Following the WebAssembly DWARF conventions, the addresses of the line
+//! programs are offsets from the start of the contents of the code section.
+
+use crate::lib::std::cmp::Ordering;
+use crate::lib::std::collections::HashMap;
+use crate::lib::std::fmt;
+#[cfg(feature = "std")]
+use crate::lib::std::path::PathBuf;
+use crate::lib::std::string::{String, ToString};
+use crate::lib::std::vec::Vec;
+use gimli::{EndianSlice, LittleEndian, SectionId};
+use wasmer_vm::ModuleInfo;
+
+type Reader<'data> = EndianSlice<'data, LittleEndian>;
+
+/// A location in the source code a WebAssembly module was compiled
+/// from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    file: String,
+    line: u32,
+    column: Option<u32>,
+}
+
+impl SourceLocation {
+    /// Returns the path of the source file, as recorded in the debug
+    /// information.
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// Returns the line in the source file, starting at 1.
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    /// Returns the column in the line, starting at 1, if known.
+    pub fn column(&self) -> Option<u32> {
+        self.column
+    }
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)?;
+        if let Some(column) = self.column {
+            write!(f, ":{}", column)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct LineRow {
+    address: u64,
+    /// `None` for the end of a sequence, or for addresses that don't
+    /// map to a line.
+    location: Option<SourceLocation>,
+}
+
+/// The rows of all the line programs of a module, sorted by address.
+#[derive(Debug)]
+pub(crate) struct LineTable {
+    code_section_offset: u64,
+    rows: Vec<LineRow>,
+}
+
+impl LineTable {
+    /// Parse the DWARF line programs of `module`.
+    ///
+    /// Returns `None` when the module has no (valid) line information.
+    pub(crate) fn parse(module: &ModuleInfo) -> Option<Self> {
+        let code_section_offset = module.code_section_offset?;
+        if !module.custom_sections.contains_key(".debug_line") {
+            return None;
+        }
+        let dwarf = gimli::Dwarf::load(
+            |id: SectionId| -> Result<Reader, gimli::Error> {
+                let data = module
+                    .custom_sections
+                    .get(id.name())
+                    .map_or(&[][..], |index| &*module.custom_sections_data[*index]);
+                Ok(EndianSlice::new(data, LittleEndian))
+            },
+            |_| Ok(EndianSlice::new(&[], LittleEndian)),
+        )
+        .ok()?;
+
+        let mut rows = Vec::new();
+        let mut units = dwarf.units();
+        while let Some(header) = units.next().ok()? {
+            let unit = dwarf.unit(header).ok()?;
+            let program = match unit.line_program.clone() {
+                Some(program) => program,
+                None => continue,
+            };
+            let mut files = HashMap::new();
+            let mut program_rows = program.rows();
+            while let Some((header, row)) = program_rows.next_row().ok()? {
+                let location = match row.line() {
+                    Some(line) if !row.end_sequence() => {
+                        let file = files
+                            .entry(row.file_index())
+                            .or_insert_with(|| {
+                                row.file(header)
+                                    .and_then(|file| {
+                                        let directory = match file.directory(header) {
+                                            Some(directory) => {
+                                                Some(dwarf.attr_string(&unit, directory).ok()?)
+                                            }
+                                            None => None,
+                                        };
+                                        let name =
+                                            dwarf.attr_string(&unit, file.path_name()).ok()?;
+                                        Some(join_path(
+                                            directory
+                                                .as_ref()
+                                                .map(|d| d.to_string_lossy())
+                                                .as_deref(),
+                                            &name.to_string_lossy(),
+                                        ))
+                                    })
+                                    .unwrap_or_else(|| "<unknown>".to_string())
+                            })
+                            .clone();
+                        Some(SourceLocation {
+                            file,
+                            line: line as u32,
+                            column: match row.column() {
+                                gimli::ColumnType::LeftEdge => None,
+                                gimli::ColumnType::Column(column) => Some(column as u32),
+                            },
+                        })
+                    }
+                    _ => None,
+                };
+                rows.push(LineRow {
+                    address: row.address(),
+                    location,
+                });
+            }
+        }
+        if rows.is_empty() {
+            return None;
+        }
+        // A sequence may start where another one ends, so at a given address
+        // the rows with a location must come last.
+        rows.sort_by_key(|row| (row.address, row.location.is_some()));
+        Some(Self {
+            code_section_offset,
+            rows,
+        })
+    }
+
+    /// Returns the source location of the instruction at `module_offset`
+    /// in the wasm file.
+    pub(crate) fn lookup(&self, module_offset: u64) -> Option<&SourceLocation> {
+        let address = module_offset.checked_sub(self.code_section_offset)?;
+        // Find the first row after `address`. The comparison never returns
+        // `Equal`, so the search always fails at that row.
+        let index = self
+            .rows
+            .binary_search_by(|row| {
+                if row.address <= address {
+                    Ordering::Less
+                } else {
+                    Ordering::Greater
+                }
+            })
+            .unwrap_or_else(|index| index);
+        self.rows[..index].last()?.location.as_ref()
+    }
+}
+
+/// Joins the `name` of a source file to the `directory` it's in, unless
+/// `name` is an absolute path.
+#[cfg(feature = "std")]
+fn join_path(directory: Option<&str>, name: &str) -> String {
+    let mut path = PathBuf::new();
+    if let Some(directory) = directory {
+        path.push(directory);
+    }
+    path.push(name);
+    path.to_string_lossy().into_owned()
+}
+
+/// Joins the `name` of a source file to the `directory` it's in, unless
+/// `name` is an absolute path.
+///
+/// Without `std`, paths are assumed to use `/` as separator.
+#[cfg(feature = "core")]
+fn join_path(directory: Option<&str>, name: &str) -> String {
+    match directory {
+        Some(directory) if !directory.is_empty() && !name.starts_with('/') => {
+            format!("{}/{}", directory.trim_end_matches('/'), name)
+        }
+        _ => name.to_string(),
+    }
+}
@@ -1,9 +1,15 @@
-use super::frame_info::{FrameInfo, GlobalFrameInfo, FRAME_INFO};
+use super::frame_info::{wasm_trace, FrameInfo, GlobalFrameInfo, FRAME_INFO};
+use crate::lib::std::boxed::Box;
+use crate::lib::std::error::Error;
+use crate::lib::std::fmt;
+use crate::lib::std::string::String;
+use crate::lib::std::sync::Arc;
+use crate::lib::std::sync::RwLockReadGuard;
+use crate::lib::std::vec::Vec;
+#[cfg(feature = "std")]
 use backtrace::Backtrace;
-use std::error::Error;
-use std::fmt;
-use std::sync::Arc;
-use std::sync::RwLockReadGuard;
+#[cfg(feature = "core")]
+use wasmer_vm::Backtrace;
 use wasmer_vm::{raise_user_trap, Trap, TrapCode};
 
 /// A struct representing an aborted instruction execution, with a message
@@ -109,6 +115,40 @@ impl RuntimeError {
         }
     }
 
+    /// Creates a `RuntimeError` wrapping a custom user error.
+    ///
+    /// This is useful to return an error from a dynamic host function
+    /// that the embedder can later recover with [`RuntimeError::downcast`].
+    ///
+    /// # Example
+    /// ```
+    /// # use std::fmt;
+    /// #[derive(Debug)]
+    /// struct Rejected;
+    /// # impl fmt::Display for Rejected {
+    /// #     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "rejected") }
+    /// # }
+    /// # impl std::error::Error for Rejected {}
+    ///
+    /// let trap = wasmer_engine::RuntimeError::user(Box::new(Rejected));
+    /// assert!(trap.is::<Rejected>());
+    /// assert_eq!("rejected", trap.message());
+    /// ```
+    pub fn user(error: Box<dyn Error + Send + Sync>) -> Self {
+        match error.downcast::<Self>() {
+            Ok(runtime_error) => *runtime_error,
+            Err(error) => {
+                let info = FRAME_INFO.read().unwrap();
+                Self::new_with_trace(
+                    info,
+                    None,
+                    RuntimeErrorSource::User(error),
+                    Backtrace::new_unresolved(),
+                )
+            }
+        }
+    }
+
     /// Raises a custom user Error
     pub fn raise(error: Box<dyn Error + Send + Sync>) -> ! {
         unsafe { raise_user_trap(error) }
@@ -120,58 +160,7 @@ impl RuntimeError {
         source: RuntimeErrorSource,
         native_trace: Backtrace,
     ) -> Self {
-        let frames: Vec<usize> = native_trace
-            .frames()
-            .iter()
-            .filter_map(|frame| {
-                let pc = frame.ip() as usize;
-                if pc == 0 {
-                    None
-                } else {
-                    // Note that we need to be careful about the pc we pass in here to
-                    // lookup frame information. This program counter is used to
-                    // translate back to an original source location in the origin wasm
-                    // module. If this pc is the exact pc that the trap happened at,
-                    // then we look up that pc precisely. Otherwise backtrace
-                    // information typically points at the pc *after* the call
-                    // instruction (because otherwise it's likely a call instruction on
-                    // the stack). In that case we want to lookup information for the
-                    // previous instruction (the call instruction) so we subtract one as
-                    // the lookup.
-                    let pc_to_lookup = if Some(pc) == trap_pc { pc } else { pc - 1 };
-                    Some(pc_to_lookup)
-                }
-            })
-            .collect();
-
-        // If any of the frames is not processed, we adquire the lock to
-        // modify the GlobalFrameInfo module.
-        let info = if frames
-            .iter()
-            .any(|pc| info.should_process_frame(*pc).unwrap_or(false))
-        {
-            // We drop the read lock, to get a write one.
-            // Note: this is not guaranteed because it's a RwLock:
-            // the following code may cause deadlocks.
-            // TODO: clean up this code
-            drop(info);
-            {
-                let mut info = FRAME_INFO.write().unwrap();
-                for pc in frames.iter() {
-                    info.maybe_process_frame(*pc);
-                }
-            }
-            FRAME_INFO.read().unwrap()
-        } else {
-            info
-        };
-
-        // Let's construct the trace
-        let wasm_trace = frames
-            .into_iter()
-            .filter_map(|pc| info.lookup_frame_info(pc))
-            .collect::<Vec<_>>();
-
+        let wasm_trace = wasm_trace(info, trap_pc, &native_trace);
         Self {
             inner: Arc::new(RuntimeErrorInner {
                 source,
@@ -188,11 +177,18 @@ impl RuntimeError {
 
     /// Returns a list of function frames in WebAssembly code that led to this
     /// trap happening.
+    ///
+    /// The innermost frame comes first. See [`FrameInfo`] for the details
+    /// available on each frame.
     pub fn trace(&self) -> &[FrameInfo] {
         &self.inner.wasm_trace
     }
 
     /// Attempts to downcast the `RuntimeError` to a concrete type.
+    ///
+    /// This only succeeds for errors returned by host functions (or
+    /// created with [`RuntimeError::user`]), never for traps raised by
+    /// WebAssembly code.
     pub fn downcast<T: Error + 'static>(self) -> Result<T, Self> {
         match Arc::try_unwrap(self.inner) {
             // We only try to downcast user errors
@@ -207,6 +203,18 @@ impl RuntimeError {
         }
     }
 
+    /// Returns a reference to the custom user error of type `T` this
+    /// `RuntimeError` was created from, if any.
+    ///
+    /// Unlike [`RuntimeError::downcast`], this also works when the
+    /// `RuntimeError` has been cloned.
+    pub fn downcast_ref<T: Error + 'static>(&self) -> Option<&T> {
+        match &self.inner.source {
+            RuntimeErrorSource::User(err) => err.downcast_ref::<T>(),
+            _ => None,
+        }
+    }
+
     /// Returns trap code, if it's a Trap
     pub fn to_trap(self) -> Option<TrapCode> {
         if let RuntimeErrorSource::Trap(trap_code) = self.inner.source {
@@ -247,11 +255,8 @@ impl fmt::Display for RuntimeError {
             let func_index = frame.func_index();
             writeln!(f)?;
             write!(f, "    at ")?;
-            match frame.function_name() {
-                Some(name) => match rustc_demangle::try_demangle(name) {
-                    Ok(name) => write!(f, "{}", name)?,
-                    Err(_) => write!(f, "{}", name)?,
-                },
+            match frame.qualified_function_name() {
+                Some(name) => write!(f, "{}", name)?,
                 None => write!(f, "<unnamed>")?,
             }
             write!(
@@ -261,13 +266,17 @@ impl fmt::Display for RuntimeError {
                 func_index,
                 frame.module_offset()
             )?;
+            if let Some(location) = frame.source_location() {
+                write!(f, " at {}", location)?;
+            }
         }
         Ok(())
     }
 }
 
-impl std::error::Error for RuntimeError {
-    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+#[cfg(feature = "std")]
+impl Error for RuntimeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
         match &self.inner.source {
             RuntimeErrorSource::User(err) => Some(&**err),
             RuntimeErrorSource::Trap(err) => Some(err),
@@ -10,14 +10,22 @@
 //! let module: ModuleInfo = ...;
 //! FRAME_INFO.register(module, compiled_functions);
 //! ```
+use super::debug_info::{LineTable, SourceLocation};
+use crate::lib::std::cmp;
+use crate::lib::std::collections::BTreeMap;
+use crate::lib::std::string::String;
+use crate::lib::std::sync::{Arc, RwLock, RwLockReadGuard};
+use crate::lib::std::vec::Vec;
 use crate::serialize::SerializableFunctionFrameInfo;
+#[cfg(feature = "std")]
+use backtrace::Backtrace;
+#[cfg(feature = "std")]
 use loupe::MemoryUsage;
-use std::cmp;
-use std::collections::BTreeMap;
-use std::sync::{Arc, RwLock};
 use wasmer_compiler::{CompiledFunctionFrameInfo, SourceLoc, TrapInformation};
 use wasmer_types::entity::{BoxedSlice, EntityRef, PrimaryMap};
 use wasmer_types::LocalFunctionIndex;
+#[cfg(feature = "core")]
+use wasmer_vm::Backtrace;
 use wasmer_vm::{FunctionBodyPtr, ModuleInfo};
 
 lazy_static::lazy_static! {
@@ -45,7 +53,7 @@ pub struct GlobalFrameInfo {
 
 /// An RAII structure used to unregister a module's frame information when the
 /// module is destroyed.
-#[derive(MemoryUsage)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 pub struct GlobalFrameInfoRegistration {
     /// The key that will be removed from the global `ranges` map when this is
     /// dropped.
@@ -57,6 +65,7 @@ struct ModuleInfoFrameInfo {
     functions: BTreeMap<usize, FunctionInfo>,
     module: Arc<ModuleInfo>,
     frame_infos: PrimaryMap<LocalFunctionIndex, SerializableFunctionFrameInfo>,
+    line_table: Option<LineTable>,
 }
 
 impl ModuleInfoFrameInfo {
@@ -67,6 +76,7 @@ impl ModuleInfoFrameInfo {
         &self.frame_infos.get(local_index).unwrap()
     }
 
+    #[cfg(feature = "std")]
     fn process_function_debug_info(&mut self, local_index: LocalFunctionIndex) {
         let func = self.frame_infos.get_mut(local_index).unwrap();
         let processed: CompiledFunctionFrameInfo = match func {
@@ -79,13 +89,19 @@ impl ModuleInfoFrameInfo {
         *func = SerializableFunctionFrameInfo::Processed(processed)
     }
 
+    /// Without `std` the frame info can't be decoded, so the functions of
+    /// deserialized artifacts are left without frame info.
+    #[cfg(feature = "core")]
+    fn process_function_debug_info(&mut self, _local_index: LocalFunctionIndex) {}
+
+    /// Returns `None` if the frame info couldn't be processed.
     fn processed_function_frame_info(
         &self,
         local_index: LocalFunctionIndex,
-    ) -> &CompiledFunctionFrameInfo {
+    ) -> Option<&CompiledFunctionFrameInfo> {
         match self.function_debug_info(local_index) {
-            SerializableFunctionFrameInfo::Processed(di) => &di,
-            _ => unreachable!("frame info should already be processed"),
+            SerializableFunctionFrameInfo::Processed(di) => Some(&di),
+            _ => None,
         }
     }
 
@@ -119,7 +135,7 @@ impl GlobalFrameInfo {
         // map that to a wasm original source location.
         let rel_pos = pc - func.start;
         let instr_map = &module
-            .processed_function_frame_info(func.local_index)
+            .processed_function_frame_info(func.local_index)?
             .address_map;
         let pos = match instr_map
             .instructions
@@ -158,12 +174,19 @@ impl GlobalFrameInfo {
             None => instr_map.start_srcloc,
         };
         let func_index = module.module.func_index(func.local_index);
+        let source_location = module
+            .line_table
+            .as_ref()
+            .and_then(|line_table| line_table.lookup(instr.bits() as u64))
+            .cloned();
         Some(FrameInfo {
             module_name: module.module.name(),
             func_index: func_index.index() as u32,
             function_name: module.module.function_names.get(&func_index).cloned(),
             instr,
             func_start: instr_map.start_srcloc,
+            source_location,
+            trapping: false,
         })
     }
 
@@ -171,7 +194,9 @@ impl GlobalFrameInfo {
     pub fn lookup_trap_info(&self, pc: usize) -> Option<&TrapInformation> {
         let module = self.module_info(pc)?;
         let func = module.function_info(pc)?;
-        let traps = &module.processed_function_frame_info(func.local_index).traps;
+        let traps = &module
+            .processed_function_frame_info(func.local_index)?
+            .traps;
         let idx = traps
             .binary_search_by_key(&((pc - func.start) as u32), |info| info.code_offset)
             .ok()?;
@@ -222,6 +247,98 @@ impl Drop for GlobalFrameInfoRegistration {
     }
 }
 
+/// Symbolicates the WebAssembly frames of `native_trace`.
+///
+/// `trap_pc` is the program counter of the trapping instruction, if the
+/// trace was captured by a trap handler.
+pub(crate) fn wasm_trace(
+    info: RwLockReadGuard<'_, GlobalFrameInfo>,
+    trap_pc: Option<usize>,
+    native_trace: &Backtrace,
+) -> Vec<FrameInfo> {
+    #[cfg(feature = "std")]
+    let frames: Vec<usize> = native_trace
+        .frames()
+        .iter()
+        .filter_map(|frame| {
+            let pc = frame.ip() as usize;
+            if pc == 0 {
+                None
+            } else {
+                // Note that we need to be careful about the pc we pass in here to
+                // lookup frame information. This program counter is used to
+                // translate back to an original source location in the origin wasm
+                // module. If this pc is the exact pc that the trap happened at,
+                // then we look up that pc precisely. Otherwise backtrace
+                // information typically points at the pc *after* the call
+                // instruction (because otherwise it's likely a call instruction on
+                // the stack). In that case we want to lookup information for the
+                // previous instruction (the call instruction) so we subtract one as
+                // the lookup.
+                let pc_to_lookup = if Some(pc) == trap_pc { pc } else { pc - 1 };
+                Some(pc_to_lookup)
+            }
+        })
+        .collect();
+    // Without `std` the native stack can't be walked, so only the
+    // trapping frame is known.
+    #[cfg(feature = "core")]
+    let frames: Vec<usize> = {
+        let _ = native_trace;
+        trap_pc.into_iter().collect()
+    };
+
+    // If any of the frames is not processed, we adquire the lock to
+    // modify the GlobalFrameInfo module.
+    let info = if frames
+        .iter()
+        .any(|pc| info.should_process_frame(*pc).unwrap_or(false))
+    {
+        // We drop the read lock, to get a write one.
+        // Note: this is not guaranteed because it's a RwLock:
+        // the following code may cause deadlocks.
+        // TODO: clean up this code
+        drop(info);
+        {
+            let mut info = FRAME_INFO.write().unwrap();
+            for pc in frames.iter() {
+                info.maybe_process_frame(*pc);
+            }
+        }
+        FRAME_INFO.read().unwrap()
+    } else {
+        info
+    };
+
+    // Let's construct the trace
+    frames
+        .into_iter()
+        .filter_map(|pc| {
+            let mut frame = info.lookup_frame_info(pc)?;
+            if Some(pc) == trap_pc {
+                frame.mark_trapping();
+            }
+            Some(frame)
+        })
+        .collect()
+}
+
+/// Returns the WebAssembly frames of the current call stack, innermost
+/// first.
+///
+/// This is meant to be called from a host function, to find which
+/// WebAssembly code led to the host call. None of the frames are
+/// [trapping frames](FrameInfo::is_trapping_frame).
+///
+/// Dynamic host functions are entered through a trampoline that may
+/// have no unwind information, in which case the WebAssembly frames
+/// can't be found and the returned list is empty. Native host functions
+/// are called directly and don't have this limitation.
+pub fn current_backtrace() -> Vec<FrameInfo> {
+    let info = FRAME_INFO.read().unwrap();
+    wasm_trace(info, None, &Backtrace::new_unresolved())
+}
+
 /// Represents a continuous region of executable memory starting with a function
 /// entry point.
 #[derive(Debug)]
@@ -240,10 +357,14 @@ pub struct FunctionExtent {
 /// compiled functions within `module`. If the `module` has no functions
 /// then `None` will be returned. Otherwise the returned object, when
 /// dropped, will be used to unregister all name information from this map.
+///
+/// When `debug_info` is `true`, the DWARF sections of `module` are parsed
+/// so that frames can report their [`SourceLocation`].
 pub fn register(
     module: Arc<ModuleInfo>,
     finished_functions: &BoxedSlice<LocalFunctionIndex, FunctionExtent>,
     frame_infos: PrimaryMap<LocalFunctionIndex, SerializableFunctionFrameInfo>,
+    debug_info: bool,
 ) -> Option<GlobalFrameInfoRegistration> {
     let mut min = usize::max_value();
     let mut max = 0;
@@ -270,6 +391,12 @@ pub fn register(
         return None;
     }
 
+    let line_table = if debug_info {
+        LineTable::parse(&module)
+    } else {
+        None
+    };
+
     let mut info = FRAME_INFO.write().unwrap();
     // First up assert that our chunk of jit functions doesn't collide with
     // any other known chunks of jit functions...
@@ -288,6 +415,7 @@ pub fn register(
             functions,
             module,
             frame_infos,
+            line_table,
         },
     );
     assert!(prev.is_none());
@@ -309,6 +437,8 @@ pub struct FrameInfo {
     function_name: Option<String>,
     func_start: SourceLoc,
     instr: SourceLoc,
+    source_location: Option<SourceLocation>,
+    trapping: bool,
 }
 
 impl FrameInfo {
@@ -351,6 +481,20 @@ impl FrameInfo {
         self.function_name.as_deref()
     }
 
+    /// Returns the name of the function for this frame, demangled and
+    /// qualified with the name of its module, like `my_module::compute`.
+    ///
+    /// This function returns `None` when [`FrameInfo::function_name`]
+    /// does.
+    pub fn qualified_function_name(&self) -> Option<String> {
+        let name = self.function_name()?;
+        Some(format!(
+            "{}::{}",
+            self.module_name,
+            rustc_demangle::demangle(name)
+        ))
+    }
+
     /// Returns the offset within the original wasm module this frame's program
     /// counter was at.
     ///
@@ -369,4 +513,25 @@ impl FrameInfo {
     pub fn func_offset(&self) -> usize {
         (self.instr.bits() - self.func_start.bits()) as usize
     }
+
+    /// Returns the location in the source code of this frame's program
+    /// counter.
+    ///
+    /// This is only available when the module embeds DWARF line
+    /// information and the engine was configured to use debug info.
+    pub fn source_location(&self) -> Option<&SourceLocation> {
+        self.source_location.as_ref()
+    }
+
+    /// Returns whether this is the frame that raised the trap.
+    ///
+    /// This is `false` for every frame of errors raised by host
+    /// functions, since the trap didn't happen in WebAssembly code.
+    pub fn is_trapping_frame(&self) -> bool {
+        self.trapping
+    }
+
+    pub(crate) fn mark_trapping(&mut self) {
+        self.trapping = true;
+    }
 }
@@ -1,7 +1,9 @@
+mod debug_info;
 mod error;
 mod frame_info;
+pub use debug_info::SourceLocation;
 pub use error::RuntimeError;
 pub use frame_info::{
-    register as register_frame_info, FrameInfo, FunctionExtent, GlobalFrameInfoRegistration,
-    FRAME_INFO,
+    current_backtrace, register as register_frame_info, FrameInfo, FunctionExtent,
+    GlobalFrameInfoRegistration, FRAME_INFO,
 };
@@ -1,14 +1,19 @@
 use crate::error::LinkError;
+use crate::lib::std::boxed::Box;
+use crate::lib::std::ptr::NonNull;
+use crate::lib::std::string::String;
+use crate::lib::std::sync::Arc;
+#[cfg(feature = "core")]
+use crate::lib::MemoryUsage;
+#[cfg(feature = "std")]
 use loupe::MemoryUsage;
-use std::ptr::NonNull;
-use std::sync::Arc;
 use wasmer_types::entity::{EntityRef, PrimaryMap};
 use wasmer_types::{
     GlobalType, LocalGlobalIndex, LocalMemoryIndex, LocalTableIndex, MemoryIndex, MemoryType,
     TableIndex, TableType,
 };
 use wasmer_vm::MemoryError;
-use wasmer_vm::{Global, Memory, ModuleInfo, Table};
+use wasmer_vm::{Global, HostBuffer, HostBufferMemory, Memory, ModuleInfo, Table};
 use wasmer_vm::{MemoryStyle, TableStyle};
 use wasmer_vm::{VMMemoryDefinition, VMTableDefinition};
 
@@ -28,6 +33,33 @@ pub trait Tunables: MemoryUsage {
         style: &MemoryStyle,
     ) -> Result<Arc<dyn Memory>, MemoryError>;
 
+    /// Create a memory owned by the host given a [`MemoryType`], backed by
+    /// `buffer` instead of memory allocated by the tunables.
+    ///
+    /// The buffer has no guard pages, so the memory always has a dynamic
+    /// [`MemoryStyle`] without offset guard.
+    fn create_host_memory_with_buffer(
+        &self,
+        ty: &MemoryType,
+        buffer: Box<dyn HostBuffer>,
+    ) -> Result<Arc<dyn Memory>, MemoryError> {
+        Ok(Arc::new(HostBufferMemory::new(ty, buffer)?))
+    }
+
+    /// Create a memory owned by the host given a [`MemoryType`] and a
+    /// [`MemoryStyle`], mapped from `file`.
+    #[cfg(all(feature = "std", not(target_os = "windows")))]
+    fn create_host_memory_file_backed(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+        file: crate::lib::std::fs::File,
+    ) -> Result<Arc<dyn Memory>, MemoryError> {
+        Ok(Arc::new(wasmer_vm::LinearMemory::new_file_backed(
+            ty, style, file,
+        )?))
+    }
+
     /// Create a memory owned by the VM given a [`MemoryType`] and a [`MemoryStyle`].
     ///
     /// # Safety
@@ -77,10 +109,14 @@ pub trait Tunables: MemoryUsage {
             let ty = &module.memories[mi];
             let style = &memory_styles[mi];
             let mdl = memory_definition_locations[index];
-            memories.push(
-                self.create_vm_memory(ty, style, mdl)
-                    .map_err(|e| LinkError::Resource(format!("Failed to create memory: {}", e)))?,
-            );
+            memories.push(self.create_vm_memory(ty, style, mdl).map_err(|e| {
+                #[cfg(feature = "std")]
+                let message = format!("Failed to create memory: {}", e);
+                // Errors don't implement `Display` without `std`.
+                #[cfg(feature = "core")]
+                let message = format!("Failed to create memory: {:?}", e);
+                LinkError::Resource(message)
+            })?);
         }
         Ok(memories)
     }
@@ -0,0 +1,249 @@
+//! `interrupt` is a middleware for stopping a running instance from another
+//! thread, for example when it runs for too long.
+//!
+//! The middleware checks an interrupt flag on entry of every function and on
+//! every loop iteration, and traps if it is set. The flag is set with an
+//! [`InterruptHandle`], which can be sent to another thread.
+
+use loupe::{MemoryUsage, MemoryUsageTracker};
+use std::convert::TryInto;
+use std::fmt;
+use std::mem;
+use std::sync::Mutex;
+use wasmer::wasmparser::{Operator, Type as WpType, TypeOrFuncType as WpTypeOrFuncType};
+use wasmer::{
+    ExportError, ExportIndex, FunctionMiddleware, Global, GlobalInit, GlobalType, Instance,
+    LocalFunctionIndex, MiddlewareError, MiddlewareReaderState, ModuleMiddleware, Mutability, Type,
+};
+use wasmer_types::GlobalIndex;
+use wasmer_vm::ModuleInfo;
+
+/// The name of the exported global holding the interrupt flag.
+const INTERRUPT_FLAG_EXPORT: &str = "wasmer_interrupt_flag";
+
+/// The module-level interrupt middleware.
+///
+/// The instances of the modules compiled with it can be stopped with an
+/// [`InterruptHandle`]. The execution then fails with an `unreachable` trap.
+///
+/// Host functions aren't interrupted: the guest is stopped once they
+/// return.
+///
+/// # Panic
+///
+/// An instance of `Interrupt` should not be shared among different modules, since it tracks
+/// module-specific information like the global index of the interrupt flag. Attempts to use
+/// an `Interrupt` instance from multiple modules will result in a panic.
+#[derive(Debug, Default)]
+pub struct Interrupt {
+    /// The global index of the interrupt flag.
+    global_index: Mutex<Option<GlobalIndex>>,
+}
+
+/// The function-level interrupt middleware.
+#[derive(Debug)]
+pub struct FunctionInterrupt {
+    /// The global index of the interrupt flag.
+    global_index: GlobalIndex,
+
+    /// Whether the check on entry of the function has been inserted.
+    entered: bool,
+}
+
+impl Interrupt {
+    /// Creates an `Interrupt` middleware.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ModuleMiddleware for Interrupt {
+    /// Generates a `FunctionMiddleware` for a given function.
+    fn generate_function_middleware(&self, _: LocalFunctionIndex) -> Box<dyn FunctionMiddleware> {
+        Box::new(FunctionInterrupt {
+            global_index: self.global_index.lock().unwrap().unwrap(),
+            entered: false,
+        })
+    }
+
+    /// Transforms a `ModuleInfo` struct in-place. This is called before application on functions begins.
+    fn transform_module_info(&self, module_info: &mut ModuleInfo) {
+        let mut global_index = self.global_index.lock().unwrap();
+
+        if global_index.is_some() {
+            panic!("Interrupt::transform_module_info: Attempting to use an `Interrupt` middleware from multiple modules.");
+        }
+
+        // The flag is represented as a i32 global:
+        //   * 0: the instance keeps running
+        //   * 1: the instance has been interrupted
+        let interrupt_flag_global_index = module_info
+            .globals
+            .push(GlobalType::new(Type::I32, Mutability::Var));
+
+        module_info
+            .global_initializers
+            .push(GlobalInit::I32Const(0));
+
+        module_info.exports.insert(
+            INTERRUPT_FLAG_EXPORT.to_string(),
+            ExportIndex::Global(interrupt_flag_global_index),
+        );
+
+        *global_index = Some(interrupt_flag_global_index);
+    }
+}
+
+impl MemoryUsage for Interrupt {
+    fn size_of_val(&self, tracker: &mut dyn MemoryUsageTracker) -> usize {
+        mem::size_of_val(self) + self.global_index.size_of_val(tracker)
+            - mem::size_of_val(&self.global_index)
+    }
+}
+
+impl FunctionInterrupt {
+    fn check(&self, state: &mut MiddlewareReaderState<'_>) {
+        state.extend(&[
+            Operator::GlobalGet {
+                global_index: self.global_index.as_u32(),
+            },
+            Operator::If {
+                ty: WpTypeOrFuncType::Type(WpType::EmptyBlockType),
+            },
+            Operator::Unreachable,
+            Operator::End,
+        ]);
+    }
+}
+
+impl FunctionMiddleware for FunctionInterrupt {
+    fn feed<'a>(
+        &mut self,
+        operator: Operator<'a>,
+        state: &mut MiddlewareReaderState<'a>,
+    ) -> Result<(), MiddlewareError> {
+        // Every unbounded execution either loops or recurses, so checking on
+        // entry of the functions and at the loop headers is enough.
+        if !self.entered {
+            self.entered = true;
+            self.check(state);
+        }
+        match operator {
+            Operator::Loop { .. } => {
+                state.push_operator(operator);
+                self.check(state);
+            }
+            _ => state.push_operator(operator),
+        }
+
+        Ok(())
+    }
+}
+
+/// A handle to interrupt an instance compiled with the [`Interrupt`]
+/// middleware, from any thread.
+#[derive(Clone)]
+pub struct InterruptHandle {
+    flag: Global,
+}
+
+impl fmt::Debug for InterruptHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InterruptHandle")
+            .field("interrupted", &self.is_interrupted())
+            .finish()
+    }
+}
+
+impl InterruptHandle {
+    /// Get the handle of an instance, failing if its module wasn't compiled
+    /// with the [`Interrupt`] middleware.
+    pub fn new(instance: &Instance) -> Result<Self, ExportError> {
+        let flag = instance.exports.get_global(INTERRUPT_FLAG_EXPORT)?.clone();
+        Ok(Self { flag })
+    }
+
+    /// Stop the instance the next time it enters a function or loops.
+    pub fn interrupt(&self) {
+        self.flag
+            .set(1i32.into())
+            .expect("Can't set `wasmer_interrupt_flag` in Instance");
+    }
+
+    /// Whether the instance has been interrupted.
+    pub fn is_interrupted(&self) -> bool {
+        let flag: i32 = self
+            .flag
+            .get()
+            .try_into()
+            .expect("`wasmer_interrupt_flag` from Instance has wrong type");
+        flag != 0
+    }
+
+    /// Clear the interrupt flag, so that the instance can run again.
+    pub fn reset(&self) {
+        self.flag
+            .set(0i32.into())
+            .expect("Can't set `wasmer_interrupt_flag` in Instance");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+    use wasmer::{imports, wat2wasm, CompilerConfig, Cranelift, Module, Store, JIT};
+
+    fn bytecode() -> Vec<u8> {
+        wat2wasm(
+            br#"
+            (module
+            (func $spin (export "spin")
+                (loop $l (br $l)))
+            (func $add_one (export "add_one") (param i32) (result i32)
+                local.get 0
+                i32.const 1
+                i32.add))
+            "#,
+        )
+        .unwrap()
+        .into()
+    }
+
+    #[test]
+    fn interrupt_stops_infinite_loops() {
+        let mut compiler_config = Cranelift::default();
+        compiler_config.push_middleware(Arc::new(Interrupt::new()));
+        let store = Store::new(&JIT::new(compiler_config).engine());
+        let module = Module::new(&store, bytecode()).unwrap();
+        let instance = Instance::new(&module, &imports! {}).unwrap();
+        let handle = InterruptHandle::new(&instance).unwrap();
+        assert!(!handle.is_interrupted());
+
+        let watchdog = {
+            let handle = handle.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(100));
+                handle.interrupt();
+            })
+        };
+        let spin = instance.exports.get_function("spin").unwrap();
+        assert!(spin.call(&[]).is_err());
+        watchdog.join().unwrap();
+        assert!(handle.is_interrupted());
+
+        // The instance can't run while interrupted, but can once reset.
+        let add_one = instance
+            .exports
+            .get_function("add_one")
+            .unwrap()
+            .native::<i32, i32>()
+            .unwrap();
+        assert!(add_one.call(1).is_err());
+        handle.reset();
+        assert_eq!(add_one.call(1).unwrap(), 2);
+    }
+}
@@ -1,5 +1,11 @@
+pub mod interrupt;
 pub mod metering;
+pub mod profiling;
+pub mod stats;
 
 // The most commonly used symbol are exported at top level of the module. Others are available
 // via modules, e.g. `wasmer_middlewares::metering::get_remaining_points`
-pub use metering::Metering;
+pub use interrupt::{Interrupt, InterruptHandle};
+pub use metering::{Metering, MeteringHandle};
+pub use profiling::{Profiling, ProfilingHandle};
+pub use stats::{ExecutionStats, InstanceExecutionStats};
@@ -8,8 +8,8 @@ use std::mem;
 use std::sync::{Arc, Mutex};
 use wasmer::wasmparser::{Operator, Type as WpType, TypeOrFuncType as WpTypeOrFuncType};
 use wasmer::{
-    ExportIndex, FunctionMiddleware, GlobalInit, GlobalType, Instance, LocalFunctionIndex,
-    MiddlewareError, MiddlewareReaderState, ModuleMiddleware, Mutability, Type,
+    ExportError, ExportIndex, FunctionMiddleware, Global, GlobalInit, GlobalType, Instance,
+    LocalFunctionIndex, MiddlewareError, MiddlewareReaderState, ModuleMiddleware, Mutability, Type,
 };
 use wasmer_types::GlobalIndex;
 use wasmer_vm::ModuleInfo;
@@ -113,6 +113,15 @@ impl<F: Fn(&Operator) -> u64 + Send + Sync + 'static> ModuleMiddleware for Meter
         })
     }
 
+    /// The cost function can't be compared, so its type stands for it.
+    fn fingerprint(&self) -> String {
+        format!(
+            "{}(initial_limit={})",
+            std::any::type_name::<Self>(),
+            self.initial_limit
+        )
+    }
+
     /// Transforms a `ModuleInfo` struct in-place. This is called before application on functions begins.
     fn transform_module_info(&self, module_info: &mut ModuleInfo) {
         let mut global_indexes = self.global_indexes.lock().unwrap();
@@ -283,6 +292,75 @@ pub fn set_remaining_points(instance: &Instance, points: u64) {
         .expect("Can't set `wasmer_metering_points_exhausted` in Instance");
 }
 
+/// A handle to read and set the metering points of an instance compiled with
+/// the [`Metering`] middleware, from any thread.
+///
+/// Unlike [`get_remaining_points`] and [`set_remaining_points`], the handle
+/// doesn't borrow the instance, so it can be sent to another thread, for
+/// example to refill the points of a running instance.
+#[derive(Clone)]
+pub struct MeteringHandle {
+    remaining_points: Global,
+    points_exhausted: Global,
+}
+
+impl fmt::Debug for MeteringHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MeteringHandle")
+            .field("points", &self.get_remaining_points())
+            .finish()
+    }
+}
+
+impl MeteringHandle {
+    /// Get the handle of an instance, failing if its module wasn't compiled
+    /// with the [`Metering`] middleware.
+    pub fn new(instance: &Instance) -> Result<Self, ExportError> {
+        Ok(Self {
+            remaining_points: instance
+                .exports
+                .get_global("wasmer_metering_remaining_points")?
+                .clone(),
+            points_exhausted: instance
+                .exports
+                .get_global("wasmer_metering_points_exhausted")?
+                .clone(),
+        })
+    }
+
+    /// Get the remaining points of the instance.
+    pub fn get_remaining_points(&self) -> MeteringPoints {
+        let exhausted: i32 = self
+            .points_exhausted
+            .get()
+            .try_into()
+            .expect("`wasmer_metering_points_exhausted` from Instance has wrong type");
+
+        if exhausted > 0 {
+            return MeteringPoints::Exhausted;
+        }
+
+        let points = self
+            .remaining_points
+            .get()
+            .try_into()
+            .expect("`wasmer_metering_remaining_points` from Instance has wrong type");
+
+        MeteringPoints::Remaining(points)
+    }
+
+    /// Set the remaining points of the instance.
+    pub fn set_remaining_points(&self, points: u64) {
+        self.remaining_points
+            .set(points.into())
+            .expect("Can't set `wasmer_metering_remaining_points` in Instance");
+
+        self.points_exhausted
+            .set(0i32.into())
+            .expect("Can't set `wasmer_metering_points_exhausted` in Instance");
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -412,4 +490,40 @@ mod tests {
             MeteringPoints::Remaining(4)
         );
     }
+
+    #[test]
+    fn metering_handle_works_from_another_thread() {
+        let metering = Arc::new(Metering::new(10, cost_function));
+        let mut compiler_config = Cranelift::default();
+        compiler_config.push_middleware(metering.clone());
+        let store = Store::new(&JIT::new(compiler_config).engine());
+        let module = Module::new(&store, bytecode()).unwrap();
+        let instance = Instance::new(&module, &imports! {}).unwrap();
+        let handle = MeteringHandle::new(&instance).unwrap();
+        let add_one = instance
+            .exports
+            .get_function("add_one")
+            .unwrap()
+            .native::<i32, i32>()
+            .unwrap();
+
+        add_one.call(1).unwrap();
+        add_one.call(1).unwrap();
+        assert!(add_one.call(1).is_err());
+        assert_eq!(handle.get_remaining_points(), MeteringPoints::Exhausted);
+
+        // Refill the points from another thread.
+        {
+            let handle = handle.clone();
+            std::thread::spawn(move || handle.set_remaining_points(4))
+                .join()
+                .unwrap();
+        }
+        assert_eq!(handle.get_remaining_points(), MeteringPoints::Remaining(4));
+        add_one.call(1).unwrap();
+        assert_eq!(
+            get_remaining_points(&instance),
+            MeteringPoints::Remaining(0)
+        );
+    }
 }
@@ -0,0 +1,561 @@
+//! `profiling` is a middleware for sampling the call stack of an instance,
+//! to find where its time goes.
+//!
+//! The middleware keeps the call stack in globals: every function pushes its
+//! index on entry and pops it when it returns. The index of the running
+//! function is kept in another global, set on entry of every function and
+//! set back after every call returns. A [`ProfilingHandle`] reads them from
+//! any thread, so a sampler thread can periodically record the call stack.
+
+use loupe::{MemoryUsage, MemoryUsageTracker};
+use std::convert::TryInto;
+use std::fmt;
+use std::mem;
+use std::sync::Mutex;
+use wasmer::wasmparser::{
+    BinaryReader, Operator, Type as WpType, TypeOrFuncType as WpTypeOrFuncType,
+};
+use wasmer::{
+    ExportError, ExportIndex, FunctionMiddleware, Global, GlobalInit, GlobalType, Instance,
+    LocalFunctionIndex, MiddlewareError, MiddlewareReaderState, ModuleMiddleware, Mutability, Type,
+};
+use wasmer_types::{FunctionIndex, GlobalIndex};
+use wasmer_vm::ModuleInfo;
+
+/// The number of frames of the call stack which are recorded. The frames
+/// deeper than that are skipped, except the running function.
+pub const MAX_STACK_DEPTH: usize = 32;
+
+/// The name of the exported global holding the index of the running
+/// function.
+const CURRENT_FUNCTION_EXPORT: &str = "wasmer_profiling_current_function";
+
+/// The name of the exported global holding the depth of the call stack.
+const STACK_DEPTH_EXPORT: &str = "wasmer_profiling_stack_depth";
+
+/// The prefix of the names of the exported globals holding the frames of
+/// the call stack, followed by their depth.
+const STACK_FRAME_EXPORT_PREFIX: &str = "wasmer_profiling_stack_frame_";
+
+/// The encoding of a `br_table` jumping to the `n`th label for a depth `n`
+/// of the call stack, and to the label `MAX_STACK_DEPTH` past it.
+///
+/// `wasmparser` can only read a `br_table`, so it's read from this encoding.
+/// Every label is encoded in a byte, as `MAX_STACK_DEPTH` is below 128.
+static STACK_BR_TABLE: [u8; MAX_STACK_DEPTH + 3] = stack_br_table();
+
+const fn stack_br_table() -> [u8; MAX_STACK_DEPTH + 3] {
+    let mut bytes = [0; MAX_STACK_DEPTH + 3];
+    // the `br_table` opcode and the number of labels before the default one
+    bytes[0] = 0x0e;
+    bytes[1] = MAX_STACK_DEPTH as u8;
+    let mut label = 0;
+    while label <= MAX_STACK_DEPTH {
+        bytes[label + 2] = label as u8;
+        label += 1;
+    }
+    bytes
+}
+
+/// The module-level profiling middleware.
+///
+/// The time spent in host functions is attributed to the Wasm function
+/// calling them.
+///
+/// The functions returning several values aren't pushed on the call stack:
+/// their time is attributed to their caller in the call stack, though they
+/// are still the running function. A trap leaves the frames of the
+/// functions it unwinds on the call stack.
+///
+/// # Panic
+///
+/// An instance of `Profiling` should not be shared among different modules, since it tracks
+/// module-specific information like the global index of the running function. Attempts to
+/// use a `Profiling` instance from multiple modules will result in a panic.
+#[derive(Debug, Default)]
+pub struct Profiling {
+    /// The globals of the module, once it has been transformed.
+    globals: Mutex<Option<ProfilingGlobals>>,
+}
+
+/// The globals added to a module by the profiling middleware.
+#[derive(Debug, Clone, MemoryUsage)]
+struct ProfilingGlobals {
+    /// The global index of the running function.
+    current_function: GlobalIndex,
+
+    /// The global index of the depth of the call stack.
+    stack_depth: GlobalIndex,
+
+    /// The global index of the first of the `MAX_STACK_DEPTH` consecutive
+    /// globals holding the frames of the call stack.
+    stack: GlobalIndex,
+
+    /// The number of imported functions.
+    num_imported_functions: usize,
+
+    /// The results of the local functions.
+    results: Vec<Vec<Type>>,
+}
+
+/// The function-level profiling middleware.
+#[derive(Debug)]
+pub struct FunctionProfiling {
+    /// The global index of the running function.
+    current_function: GlobalIndex,
+
+    /// The global index of the depth of the call stack.
+    stack_depth: GlobalIndex,
+
+    /// The global index of the first frame of the call stack.
+    stack: GlobalIndex,
+
+    /// The index of this function.
+    function_index: FunctionIndex,
+
+    /// The type of the block wrapping the body of this function, so that
+    /// its frame is popped however the function returns, or `None` if it
+    /// returns several values and isn't pushed on the call stack.
+    body_type: Option<WpTypeOrFuncType>,
+
+    /// The number of blocks the operators fed so far are nested in.
+    nested_blocks: u32,
+
+    /// Whether the function has been pushed and marked as running on entry.
+    entered: bool,
+}
+
+impl Profiling {
+    /// Creates a `Profiling` middleware.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ModuleMiddleware for Profiling {
+    /// Generates a `FunctionMiddleware` for a given function.
+    fn generate_function_middleware(
+        &self,
+        local_function_index: LocalFunctionIndex,
+    ) -> Box<dyn FunctionMiddleware> {
+        let globals = self.globals.lock().unwrap();
+        let globals = globals.as_ref().unwrap();
+        let body_type = match &globals.results[local_function_index.as_u32() as usize][..] {
+            [] => Some(WpTypeOrFuncType::Type(WpType::EmptyBlockType)),
+            [result] => Some(WpTypeOrFuncType::Type(match result {
+                Type::I32 => WpType::I32,
+                Type::I64 => WpType::I64,
+                Type::F32 => WpType::F32,
+                Type::F64 => WpType::F64,
+                Type::V128 => WpType::V128,
+                Type::ExternRef => WpType::ExternRef,
+                Type::FuncRef => WpType::FuncRef,
+            })),
+            _ => None,
+        };
+        Box::new(FunctionProfiling {
+            current_function: globals.current_function,
+            stack_depth: globals.stack_depth,
+            stack: globals.stack,
+            function_index: FunctionIndex::from_u32(
+                globals.num_imported_functions as u32 + local_function_index.as_u32(),
+            ),
+            body_type,
+            nested_blocks: 0,
+            entered: false,
+        })
+    }
+
+    /// Transforms a `ModuleInfo` struct in-place. This is called before application on functions begins.
+    fn transform_module_info(&self, module_info: &mut ModuleInfo) {
+        let mut globals = self.globals.lock().unwrap();
+
+        if globals.is_some() {
+            panic!("Profiling::transform_module_info: Attempting to use a `Profiling` middleware from multiple modules.");
+        }
+
+        let mut add_global = |name: String, init: i32| {
+            let global_index = module_info
+                .globals
+                .push(GlobalType::new(Type::I32, Mutability::Var));
+            module_info
+                .global_initializers
+                .push(GlobalInit::I32Const(init));
+            module_info
+                .exports
+                .insert(name, ExportIndex::Global(global_index));
+            global_index
+        };
+
+        // The running function is represented as a i32 global, which is -1
+        // until a function runs.
+        let current_function = add_global(CURRENT_FUNCTION_EXPORT.to_string(), -1);
+        // The call stack is an i32 global per frame, the frames past its
+        // depth being stale.
+        let stack_depth = add_global(STACK_DEPTH_EXPORT.to_string(), 0);
+        let stack = add_global(format!("{}0", STACK_FRAME_EXPORT_PREFIX), -1);
+        for depth in 1..MAX_STACK_DEPTH {
+            add_global(format!("{}{}", STACK_FRAME_EXPORT_PREFIX, depth), -1);
+        }
+
+        let results = module_info
+            .functions
+            .values()
+            .skip(module_info.num_imported_functions)
+            .map(|signature_index| module_info.signatures[*signature_index].results().to_vec())
+            .collect();
+
+        *globals = Some(ProfilingGlobals {
+            current_function,
+            stack_depth,
+            stack,
+            num_imported_functions: module_info.num_imported_functions,
+            results,
+        });
+    }
+}
+
+impl MemoryUsage for Profiling {
+    fn size_of_val(&self, tracker: &mut dyn MemoryUsageTracker) -> usize {
+        mem::size_of_val(self) + self.globals.size_of_val(tracker) - mem::size_of_val(&self.globals)
+    }
+}
+
+impl FunctionProfiling {
+    fn mark_running(&self, state: &mut MiddlewareReaderState<'_>) {
+        state.extend(vec![
+            Operator::I32Const {
+                value: self.function_index.as_u32() as i32,
+            },
+            Operator::GlobalSet {
+                global_index: self.current_function.as_u32(),
+            },
+        ]);
+    }
+
+    /// Push the function on the call stack, recording it if the stack isn't
+    /// deeper than `MAX_STACK_DEPTH`.
+    fn push_frame(&self, state: &mut MiddlewareReaderState<'_>) {
+        let br_table = BinaryReader::new(&STACK_BR_TABLE)
+            .read_operator()
+            .expect("invalid `br_table` encoding");
+        // The outermost block skips the frames past `MAX_STACK_DEPTH`, and
+        // each inner block ends before the recording of a frame.
+        let block = Operator::Block {
+            ty: WpTypeOrFuncType::Type(WpType::EmptyBlockType),
+        };
+        state.extend(vec![block; MAX_STACK_DEPTH + 1]);
+        state.extend(vec![
+            Operator::GlobalGet {
+                global_index: self.stack_depth.as_u32(),
+            },
+            br_table,
+        ]);
+        for depth in 0..MAX_STACK_DEPTH as u32 {
+            state.extend(vec![
+                Operator::End,
+                Operator::I32Const {
+                    value: self.function_index.as_u32() as i32,
+                },
+                Operator::GlobalSet {
+                    global_index: self.stack.as_u32() + depth,
+                },
+                Operator::Br {
+                    relative_depth: MAX_STACK_DEPTH as u32 - 1 - depth,
+                },
+            ]);
+        }
+        state.extend(vec![Operator::End]);
+        self.add_to_stack_depth(state, 1);
+    }
+
+    /// Pop the function from the call stack.
+    fn pop_frame(&self, state: &mut MiddlewareReaderState<'_>) {
+        self.add_to_stack_depth(state, -1);
+    }
+
+    fn add_to_stack_depth(&self, state: &mut MiddlewareReaderState<'_>, value: i32) {
+        state.extend(vec![
+            Operator::GlobalGet {
+                global_index: self.stack_depth.as_u32(),
+            },
+            Operator::I32Const { value },
+            Operator::I32Add,
+            Operator::GlobalSet {
+                global_index: self.stack_depth.as_u32(),
+            },
+        ]);
+    }
+}
+
+impl FunctionMiddleware for FunctionProfiling {
+    fn feed<'a>(
+        &mut self,
+        operator: Operator<'a>,
+        state: &mut MiddlewareReaderState<'a>,
+    ) -> Result<(), MiddlewareError> {
+        if !self.entered {
+            self.entered = true;
+            self.mark_running(state);
+            // The body is wrapped in a block, so that branching to the
+            // function's label falls through to the popping of the frame.
+            if let Some(ty) = self.body_type {
+                self.push_frame(state);
+                state.push_operator(Operator::Block { ty });
+            }
+        }
+        match operator {
+            Operator::Block { .. }
+            | Operator::Loop { .. }
+            | Operator::If { .. }
+            | Operator::Try { .. } => {
+                self.nested_blocks += 1;
+                state.push_operator(operator);
+            }
+            // The end of the function.
+            Operator::End if self.nested_blocks == 0 => {
+                if self.body_type.is_some() {
+                    state.push_operator(Operator::End);
+                    self.pop_frame(state);
+                }
+                state.push_operator(operator);
+            }
+            Operator::End => {
+                self.nested_blocks -= 1;
+                state.push_operator(operator);
+            }
+            Operator::Return => {
+                if self.body_type.is_some() {
+                    self.pop_frame(state);
+                }
+                state.push_operator(operator);
+            }
+            // The callee marked itself as running.
+            Operator::Call { .. } | Operator::CallIndirect { .. } => {
+                state.push_operator(operator);
+                self.mark_running(state);
+            }
+            _ => state.push_operator(operator),
+        }
+
+        Ok(())
+    }
+}
+
+/// A handle to read the running function and the call stack of an instance
+/// compiled with the [`Profiling`] middleware, from any thread.
+#[derive(Clone)]
+pub struct ProfilingHandle {
+    current_function: Global,
+    stack_depth: Global,
+    stack: Vec<Global>,
+}
+
+impl fmt::Debug for ProfilingHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProfilingHandle")
+            .field("current_function", &self.current_function())
+            .field("call_stack", &self.call_stack())
+            .finish()
+    }
+}
+
+/// Read an i32 global of the middleware.
+fn get_i32(global: &Global) -> i32 {
+    global
+        .get()
+        .try_into()
+        .expect("a global of the `Profiling` middleware from Instance has wrong type")
+}
+
+impl ProfilingHandle {
+    /// Get the handle of an instance, failing if its module wasn't compiled
+    /// with the [`Profiling`] middleware.
+    pub fn new(instance: &Instance) -> Result<Self, ExportError> {
+        let get_global = |name: &str| -> Result<Global, ExportError> {
+            Ok(instance.exports.get_global(name)?.clone())
+        };
+        Ok(Self {
+            current_function: get_global(CURRENT_FUNCTION_EXPORT)?,
+            stack_depth: get_global(STACK_DEPTH_EXPORT)?,
+            stack: (0..MAX_STACK_DEPTH)
+                .map(|depth| get_global(&format!("{}{}", STACK_FRAME_EXPORT_PREFIX, depth)))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// The function running, or which ran last, if any has run.
+    pub fn current_function(&self) -> Option<FunctionIndex> {
+        let index = get_i32(&self.current_function);
+        if index < 0 {
+            None
+        } else {
+            Some(FunctionIndex::from_u32(index as u32))
+        }
+    }
+
+    /// The functions being called, from the outermost one, which is empty
+    /// when no function runs.
+    ///
+    /// Past `MAX_STACK_DEPTH` frames, only the running function is recorded
+    /// after them.
+    pub fn call_stack(&self) -> Vec<FunctionIndex> {
+        let depth = get_i32(&self.stack_depth).max(0) as usize;
+        let mut frames = self
+            .stack
+            .iter()
+            .take(depth)
+            .map(|frame| FunctionIndex::from_u32(get_i32(frame) as u32))
+            .collect::<Vec<_>>();
+        if depth > MAX_STACK_DEPTH {
+            frames.extend(self.current_function());
+        }
+        frames
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+    use wasmer::{
+        imports, wat2wasm, CompilerConfig, Cranelift, Function, FunctionType, Module, Store, Val,
+        JIT,
+    };
+
+    fn bytecode() -> Vec<u8> {
+        wat2wasm(
+            br#"
+            (module
+            (import "env" "observe" (func $observe))
+            (func $inner (export "inner")
+                call $observe)
+            (func $outer (export "outer")
+                call $inner
+                call $observe)
+            (func $early (export "early") (param i32)
+                local.get 0
+                br_if 0
+                call $observe
+                return)
+            (func $answer (export "answer") (result i32)
+                call $observe
+                i32.const 42)
+            (func $recurse (export "recurse") (param i32)
+                local.get 0
+                if
+                    local.get 0
+                    i32.const 1
+                    i32.sub
+                    call $recurse
+                else
+                    call $observe
+                end))
+            "#,
+        )
+        .unwrap()
+        .into()
+    }
+
+    /// The running function and the call stack, whenever the host function
+    /// is called.
+    type Observed = Arc<Mutex<Vec<(Option<FunctionIndex>, Vec<FunctionIndex>)>>>;
+
+    /// Instantiate the module, observing the host function calls.
+    fn instantiate() -> (Instance, ProfilingHandle, Observed) {
+        let mut compiler_config = Cranelift::default();
+        compiler_config.push_middleware(Arc::new(Profiling::new()));
+        let store = Store::new(&JIT::new(compiler_config).engine());
+        let module = Module::new(&store, bytecode()).unwrap();
+
+        let observed = Arc::new(Mutex::new(Vec::new()));
+        let handle = Arc::new(Mutex::new(None::<ProfilingHandle>));
+        let observe = {
+            let observed = observed.clone();
+            let handle = handle.clone();
+            Function::new(&store, FunctionType::new(vec![], vec![]), move |_| {
+                let handle = handle.lock().unwrap();
+                let handle = handle.as_ref().unwrap();
+                observed
+                    .lock()
+                    .unwrap()
+                    .push((handle.current_function(), handle.call_stack()));
+                Ok(vec![])
+            })
+        };
+        let instance =
+            Instance::new(&module, &imports! { "env" => { "observe" => observe } }).unwrap();
+        let profiling_handle = ProfilingHandle::new(&instance).unwrap();
+        *handle.lock().unwrap() = Some(profiling_handle.clone());
+        (instance, profiling_handle, observed)
+    }
+
+    fn functions(indices: &[u32]) -> Vec<FunctionIndex> {
+        indices
+            .iter()
+            .copied()
+            .map(FunctionIndex::from_u32)
+            .collect()
+    }
+
+    #[test]
+    fn profiling_tracks_the_running_function() {
+        let (instance, handle, observed) = instantiate();
+        assert_eq!(handle.current_function(), None);
+        assert_eq!(handle.call_stack(), vec![]);
+
+        let outer = instance.exports.get_function("outer").unwrap();
+        outer.call(&[]).unwrap();
+        // `inner` is the function 1 and `outer` the function 2: the host
+        // function is attributed to its caller.
+        assert_eq!(
+            *observed.lock().unwrap(),
+            vec![
+                (Some(FunctionIndex::from_u32(1)), functions(&[2, 1])),
+                (Some(FunctionIndex::from_u32(2)), functions(&[2])),
+            ]
+        );
+        assert_eq!(handle.call_stack(), vec![]);
+    }
+
+    #[test]
+    fn profiling_pops_the_frames_however_functions_return() {
+        let (instance, handle, observed) = instantiate();
+
+        let early = instance.exports.get_function("early").unwrap();
+        early.call(&[Val::I32(1)]).unwrap();
+        assert_eq!(handle.call_stack(), vec![]);
+        early.call(&[Val::I32(0)]).unwrap();
+        assert_eq!(handle.call_stack(), vec![]);
+
+        let answer = instance.exports.get_function("answer").unwrap();
+        assert_eq!(*answer.call(&[]).unwrap(), [Val::I32(42)]);
+        assert_eq!(handle.call_stack(), vec![]);
+
+        assert_eq!(
+            observed
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(_, stack)| stack.clone())
+                .collect::<Vec<_>>(),
+            vec![functions(&[3]), functions(&[4])]
+        );
+    }
+
+    #[test]
+    fn profiling_records_the_running_function_past_the_maximum_depth() {
+        let (instance, handle, observed) = instantiate();
+
+        let recurse = instance.exports.get_function("recurse").unwrap();
+        let depth = MAX_STACK_DEPTH as i32 + 8;
+        recurse.call(&[Val::I32(depth)]).unwrap();
+        assert_eq!(handle.call_stack(), vec![]);
+
+        let observed = observed.lock().unwrap();
+        // `recurse` is the function 5, recorded `MAX_STACK_DEPTH` times and
+        // then as the running function.
+        assert_eq!(observed[0].1, functions(&[5; MAX_STACK_DEPTH + 1]));
+    }
+}
@@ -0,0 +1,391 @@
+//! `stats` is a middleware for counting how many times each function is
+//! called and how many operators are executed in total.
+//!
+//! The counters live in globals of the instance, so they are cheap to
+//! update and can be read at any time with
+//! [`InstanceExecutionStats::execution_stats`].
+
+use loupe::{MemoryUsage, MemoryUsageTracker};
+use std::convert::TryInto;
+use std::fmt;
+use std::mem;
+use std::sync::Mutex;
+use wasmer::wasmparser::Operator;
+use wasmer::{
+    ExportIndex, Extern, FunctionMiddleware, Global, GlobalInit, GlobalType, Instance,
+    LocalFunctionIndex, MiddlewareError, MiddlewareReaderState, ModuleMiddleware, Mutability, Type,
+};
+use wasmer_types::entity::PrimaryMap;
+use wasmer_types::{FunctionIndex, GlobalIndex};
+use wasmer_vm::ModuleInfo;
+
+/// The prefix of the exported globals counting the calls of each function.
+const CALLS_EXPORT_PREFIX: &str = "wasmer_stats_calls_";
+
+/// The exported global counting the executed operators.
+const INSTRUCTIONS_EXPORT: &str = "wasmer_stats_instructions";
+
+#[derive(Clone, Debug, MemoryUsage)]
+struct StatsGlobalIndexes {
+    /// The global index for the number of executed operators.
+    instructions: GlobalIndex,
+    /// The global index for the number of calls, for each local function.
+    calls: PrimaryMap<LocalFunctionIndex, GlobalIndex>,
+}
+
+/// The module-level execution statistics middleware.
+///
+/// # Panic
+///
+/// An instance of `ExecutionStats` should not be shared among different modules, since it
+/// tracks module-specific information like the global indexes of the counters. Attempts to
+/// use an `ExecutionStats` instance from multiple modules will result in a panic.
+pub struct ExecutionStats {
+    /// The global indexes of the counters.
+    global_indexes: Mutex<Option<StatsGlobalIndexes>>,
+}
+
+/// The function-level execution statistics middleware.
+pub struct FunctionExecutionStats {
+    /// The global index for the number of executed operators.
+    instructions: GlobalIndex,
+
+    /// The global index for the number of calls of this function.
+    calls: GlobalIndex,
+
+    /// Whether the call counter has been incremented at the start of the function.
+    entered: bool,
+
+    /// Number of operators of the current basic block.
+    accumulated_instructions: u64,
+}
+
+/// The statistics collected by the [`ExecutionStats`] middleware.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+    /// The total number of operators executed.
+    ///
+    /// The operators are counted when their basic block ends, so the
+    /// operators of a block interrupted by a trap are not counted.
+    pub instructions: u64,
+
+    /// The number of calls of each function defined in the module.
+    pub calls: Vec<FunctionCalls>,
+}
+
+/// The number of calls of a function.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionCalls {
+    /// The index of the function in the module.
+    pub index: FunctionIndex,
+    /// The name of the function, if the module has a name section.
+    pub name: Option<String>,
+    /// The number of times the function was called.
+    pub calls: u64,
+}
+
+impl ExecutionStats {
+    /// Creates an `ExecutionStats` middleware.
+    pub fn new() -> Self {
+        Self {
+            global_indexes: Mutex::new(None),
+        }
+    }
+}
+
+impl Default for ExecutionStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for ExecutionStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExecutionStats")
+            .field("global_indexes", &self.global_indexes)
+            .finish()
+    }
+}
+
+impl ModuleMiddleware for ExecutionStats {
+    /// Generates a `FunctionMiddleware` for a given function.
+    fn generate_function_middleware(
+        &self,
+        local_function_index: LocalFunctionIndex,
+    ) -> Box<dyn FunctionMiddleware> {
+        let global_indexes = self.global_indexes.lock().unwrap();
+        let global_indexes = global_indexes.as_ref().unwrap();
+        Box::new(FunctionExecutionStats {
+            instructions: global_indexes.instructions,
+            calls: global_indexes.calls[local_function_index],
+            entered: false,
+            accumulated_instructions: 0,
+        })
+    }
+
+    /// Transforms a `ModuleInfo` struct in-place. This is called before application on functions begins.
+    fn transform_module_info(&self, module_info: &mut ModuleInfo) {
+        let mut global_indexes = self.global_indexes.lock().unwrap();
+
+        if global_indexes.is_some() {
+            panic!("ExecutionStats::transform_module_info: Attempting to use an `ExecutionStats` middleware from multiple modules.");
+        }
+
+        let add_counter = |module_info: &mut ModuleInfo, name: String| {
+            let index = module_info
+                .globals
+                .push(GlobalType::new(Type::I64, Mutability::Var));
+            module_info
+                .global_initializers
+                .push(GlobalInit::I64Const(0));
+            module_info.exports.insert(name, ExportIndex::Global(index));
+            index
+        };
+
+        let instructions = add_counter(module_info, INSTRUCTIONS_EXPORT.to_string());
+        let calls = (module_info.num_imported_functions..module_info.functions.len())
+            .map(|index| add_counter(module_info, format!("{}{}", CALLS_EXPORT_PREFIX, index)))
+            .collect();
+
+        *global_indexes = Some(StatsGlobalIndexes {
+            instructions,
+            calls,
+        });
+    }
+}
+
+impl MemoryUsage for ExecutionStats {
+    fn size_of_val(&self, tracker: &mut dyn MemoryUsageTracker) -> usize {
+        mem::size_of_val(self) + self.global_indexes.size_of_val(tracker)
+            - mem::size_of_val(&self.global_indexes)
+    }
+}
+
+impl fmt::Debug for FunctionExecutionStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FunctionExecutionStats")
+            .field("instructions", &self.instructions)
+            .field("calls", &self.calls)
+            .finish()
+    }
+}
+
+impl FunctionMiddleware for FunctionExecutionStats {
+    fn feed<'a>(
+        &mut self,
+        operator: Operator<'a>,
+        state: &mut MiddlewareReaderState<'a>,
+    ) -> Result<(), MiddlewareError> {
+        if !self.entered {
+            self.entered = true;
+            state.extend(&[
+                // globals[calls] += 1;
+                Operator::GlobalGet {
+                    global_index: self.calls.as_u32(),
+                },
+                Operator::I64Const { value: 1 },
+                Operator::I64Add,
+                Operator::GlobalSet {
+                    global_index: self.calls.as_u32(),
+                },
+            ]);
+        }
+
+        self.accumulated_instructions += 1;
+
+        // Possible sources and targets of a branch, as in the metering middleware.
+        match operator {
+            Operator::Loop { .. }
+            | Operator::End
+            | Operator::Else
+            | Operator::Br { .. }
+            | Operator::BrTable { .. }
+            | Operator::BrIf { .. }
+            | Operator::Call { .. }
+            | Operator::CallIndirect { .. }
+            | Operator::Return => {
+                state.extend(&[
+                    // globals[instructions] += self.accumulated_instructions;
+                    Operator::GlobalGet {
+                        global_index: self.instructions.as_u32(),
+                    },
+                    Operator::I64Const {
+                        value: self.accumulated_instructions as i64,
+                    },
+                    Operator::I64Add,
+                    Operator::GlobalSet {
+                        global_index: self.instructions.as_u32(),
+                    },
+                ]);
+                self.accumulated_instructions = 0;
+            }
+            _ => {}
+        }
+        state.push_operator(operator);
+
+        Ok(())
+    }
+}
+
+/// Gives access to the statistics of an `Instance` collected by the
+/// [`ExecutionStats`] middleware.
+///
+/// # Panic
+///
+/// The instance Module must have been processed with the [`ExecutionStats`] middleware
+/// at compile time, otherwise the methods will panic.
+pub trait InstanceExecutionStats {
+    /// Returns the statistics collected since the instance was created,
+    /// or since they were last reset.
+    fn execution_stats(&self) -> Stats;
+
+    /// Resets all the counters to zero.
+    fn reset_execution_stats(&self);
+}
+
+impl InstanceExecutionStats for Instance {
+    fn execution_stats(&self) -> Stats {
+        let instructions: i64 = instructions_global(self)
+            .get()
+            .try_into()
+            .expect("`wasmer_stats_instructions` from Instance has wrong type");
+
+        let function_names = &self.module().info().function_names;
+        let calls = calls_globals(self)
+            .map(|(index, global)| {
+                let calls: i64 = global
+                    .get()
+                    .try_into()
+                    .expect("`wasmer_stats_calls_*` from Instance has wrong type");
+                FunctionCalls {
+                    index,
+                    name: function_names.get(&index).cloned(),
+                    calls: calls as u64,
+                }
+            })
+            .collect();
+
+        Stats {
+            instructions: instructions as u64,
+            calls,
+        }
+    }
+
+    fn reset_execution_stats(&self) {
+        let globals =
+            std::iter::once(instructions_global(self)).chain(calls_globals(self).map(|(_, g)| g));
+        for global in globals {
+            global
+                .set(0i64.into())
+                .expect("Can't reset `wasmer_stats_*` in Instance");
+        }
+    }
+}
+
+/// The global counting the executed operators of `instance`.
+fn instructions_global(instance: &Instance) -> &Global {
+    instance
+        .exports
+        .get_global(INSTRUCTIONS_EXPORT)
+        .expect("Can't get `wasmer_stats_instructions` from Instance")
+}
+
+/// The globals counting the calls of each function of `instance`.
+fn calls_globals(instance: &Instance) -> impl Iterator<Item = (FunctionIndex, &Global)> {
+    instance.exports.iter().filter_map(|(name, export)| {
+        let index = name.strip_prefix(CALLS_EXPORT_PREFIX)?.parse().ok()?;
+        match export {
+            Extern::Global(global) => Some((FunctionIndex::from_u32(index), global)),
+            _ => None,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use wasmer::{imports, wat2wasm, CompilerConfig, Cranelift, Module, Store, JIT};
+
+    fn instantiate() -> Instance {
+        let mut compiler_config = Cranelift::default();
+        compiler_config.push_middleware(Arc::new(ExecutionStats::new()));
+        let store = Store::new(&JIT::new(compiler_config).engine());
+        let bytecode = wat2wasm(
+            br#"
+            (module
+              (func $add_one (param i32) (result i32)
+                local.get 0
+                i32.const 1
+                i32.add)
+              (func (export "add_two") (param i32) (result i32)
+                local.get 0
+                call $add_one
+                call $add_one))
+            "#,
+        )
+        .unwrap();
+        let module = Module::new(&store, bytecode).unwrap();
+        Instance::new(&module, &imports! {}).unwrap()
+    }
+
+    fn calls(stats: &Stats) -> Vec<(u32, Option<&str>, u64)> {
+        stats
+            .calls
+            .iter()
+            .map(|function| {
+                (
+                    function.index.as_u32(),
+                    function.name.as_deref(),
+                    function.calls,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn execution_stats_works() {
+        let instance = instantiate();
+
+        let stats = instance.execution_stats();
+        assert_eq!(stats.instructions, 0);
+        assert_eq!(calls(&stats), vec![(0, Some("add_one"), 0), (1, None, 0)]);
+
+        let add_two = instance
+            .exports
+            .get_native_function::<i32, i32>("add_two")
+            .unwrap();
+        assert_eq!(add_two.call(1).unwrap(), 3);
+
+        let stats = instance.execution_stats();
+        // `add_two` executes 4 operators, and `add_one` 4 operators twice.
+        assert_eq!(stats.instructions, 12);
+        assert_eq!(calls(&stats), vec![(0, Some("add_one"), 2), (1, None, 1)]);
+    }
+
+    #[test]
+    fn execution_stats_accumulate_and_reset() {
+        let instance = instantiate();
+        let add_two = instance
+            .exports
+            .get_native_function::<i32, i32>("add_two")
+            .unwrap();
+
+        for _ in 0..3 {
+            add_two.call(1).unwrap();
+        }
+        let stats = instance.execution_stats();
+        assert_eq!(stats.instructions, 36);
+        assert_eq!(calls(&stats), vec![(0, Some("add_one"), 6), (1, None, 3)]);
+
+        instance.reset_execution_stats();
+        let stats = instance.execution_stats();
+        assert_eq!(stats.instructions, 0);
+        assert_eq!(calls(&stats), vec![(0, Some("add_one"), 0), (1, None, 0)]);
+
+        add_two.call(1).unwrap();
+        let stats = instance.execution_stats();
+        assert_eq!(stats.instructions, 12);
+        assert_eq!(calls(&stats), vec![(0, Some("add_one"), 2), (1, None, 1)]);
+    }
+}
@@ -10,7 +10,9 @@ use crate::lib::std::boxed::Box;
 use crate::lib::std::marker::PhantomData;
 use crate::lib::std::ops::{Index, IndexMut};
 use crate::lib::std::slice;
+#[cfg(feature = "std")]
 use loupe::{MemoryUsage, MemoryUsageTracker};
+#[cfg(feature = "std")]
 use std::mem;
 
 /// A slice mapping `K -> V` allocating dense entity references.
@@ -146,6 +148,7 @@ where
     }
 }
 
+#[cfg(feature = "std")]
 impl<K, V> MemoryUsage for BoxedSlice<K, V>
 where
     K: EntityRef,
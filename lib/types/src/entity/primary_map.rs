@@ -12,9 +12,11 @@ use crate::lib::std::marker::PhantomData;
 use crate::lib::std::ops::{Index, IndexMut};
 use crate::lib::std::slice;
 use crate::lib::std::vec::Vec;
+#[cfg(feature = "std")]
 use loupe::{MemoryUsage, MemoryUsageTracker};
 #[cfg(feature = "enable-serde")]
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
 use std::mem;
 
 /// A primary mapping `K -> V` allocating dense entity references.
@@ -238,6 +240,7 @@ where
     }
 }
 
+#[cfg(feature = "std")]
 impl<K, V> MemoryUsage for PrimaryMap<K, V>
 where
     K: EntityRef,
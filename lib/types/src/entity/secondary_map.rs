@@ -11,6 +11,7 @@ use crate::lib::std::marker::PhantomData;
 use crate::lib::std::ops::{Index, IndexMut};
 use crate::lib::std::slice;
 use crate::lib::std::vec::Vec;
+#[cfg(feature = "std")]
 use loupe::{MemoryUsage, MemoryUsageTracker};
 #[cfg(feature = "enable-serde")]
 use serde::{
@@ -18,6 +19,7 @@ use serde::{
     ser::{SerializeSeq, Serializer},
     Deserialize, Serialize,
 };
+#[cfg(feature = "std")]
 use std::mem;
 
 /// A mapping `K -> V` for densely indexed entity references.
@@ -281,6 +283,7 @@ where
     }
 }
 
+#[cfg(feature = "std")]
 impl<K, V> MemoryUsage for SecondaryMap<K, V>
 where
     K: EntityRef,
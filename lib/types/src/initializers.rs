@@ -1,12 +1,15 @@
 use crate::indexes::{FunctionIndex, GlobalIndex, MemoryIndex, TableIndex};
 use crate::lib::std::boxed::Box;
+#[cfg(feature = "std")]
 use loupe::MemoryUsage;
 
 #[cfg(feature = "enable-serde")]
 use serde::{Deserialize, Serialize};
 
 /// A WebAssembly table initializer.
-#[derive(Clone, Debug, Hash, Serialize, Deserialize, MemoryUsage)]
+#[derive(Clone, Debug, Hash)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
 pub struct TableInitializer {
     /// The index of a table to initialize.
     pub table_index: TableIndex,
@@ -20,7 +23,8 @@ pub struct TableInitializer {
 
 /// A memory index and offset within that memory where a data initialization
 /// should be performed.
-#[derive(Clone, Debug, MemoryUsage)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
 pub struct DataInitializerLocation {
     /// The index of the memory to initialize.
@@ -46,7 +50,8 @@ pub struct DataInitializer<'data> {
 
 /// As `DataInitializer` but owning the data rather than
 /// holding a reference to it
-#[derive(Debug, Clone, MemoryUsage)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
 pub struct OwnedDataInitializer {
     /// The location where the initialization is to be performed.
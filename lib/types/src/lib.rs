@@ -40,8 +40,10 @@ pub mod lib {
     /// Custom `std` module.
     #[cfg(feature = "core")]
     pub mod std {
-        pub use alloc::{borrow, boxed, format, iter, rc, slice, string, vec};
-        pub use core::{any, cell, cmp, convert, fmt, hash, marker, mem, ops, ptr, sync, u32};
+        pub use alloc::{borrow, boxed, format, rc, slice, string, vec};
+        pub use core::{
+            any, cell, cmp, convert, fmt, hash, iter, marker, mem, ops, ptr, sync, u32,
+        };
     }
 
     /// Custom `std` module.
@@ -77,11 +79,11 @@ pub use crate::initializers::{
 };
 pub use crate::memory_view::{Atomically, MemoryView};
 pub use crate::native::{NativeWasmType, ValueType};
-pub use crate::r#ref::{ExternRef, HostInfo, HostRef};
+pub use crate::r#ref::{ExternRef, ExternRefScope, HostInfo, HostRef};
 pub use crate::units::{
     Bytes, PageCountOutOfRange, Pages, WASM_MAX_PAGES, WASM_MIN_PAGES, WASM_PAGE_SIZE,
 };
-pub use crate::values::Value;
+pub use crate::values::{NullableFuncRef, Value};
 pub use types::{
     ExportType, ExternType, FunctionType, GlobalInit, GlobalType, ImportType, MemoryType,
     Mutability, TableType, Type, V128,
@@ -1,6 +1,8 @@
 use crate::lib::std::cell::Cell;
 use crate::lib::std::marker::PhantomData;
+use crate::lib::std::mem;
 use crate::lib::std::ops::Deref;
+use crate::lib::std::ptr;
 use crate::lib::std::slice;
 use crate::lib::std::sync::atomic::{
     AtomicI16, AtomicI32, AtomicI64, AtomicI8, AtomicU16, AtomicU32, AtomicU64, AtomicU8,
@@ -64,6 +66,87 @@ where
             _phantom: PhantomData,
         }
     }
+
+    /// Returns the view of the `len` elements starting at element
+    /// `offset`, or `None` if they are not all within this view.
+    pub fn subview(&self, offset: usize, len: usize) -> Option<MemoryView<'a, T>> {
+        if offset.checked_add(len)? > self.length {
+            return None;
+        }
+        Some(MemoryView {
+            ptr: unsafe { self.ptr.add(offset) },
+            length: len,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Returns the total size of this view, in bytes.
+    pub fn byte_len(&self) -> usize {
+        self.length * mem::size_of::<T>()
+    }
+
+    /// Reads a `U` at `byte_offset` bytes from the start of this view.
+    ///
+    /// The offset doesn't need to be aligned for `U`. Returns `None` if
+    /// the value is not entirely within this view.
+    pub fn read_at<U: ValueType>(&self, byte_offset: usize) -> Option<U> {
+        let src = self.byte_ptr::<U>(byte_offset)?;
+        Some(unsafe { ptr::read_unaligned(src) })
+    }
+
+    /// Writes `value` at `byte_offset` bytes from the start of this view.
+    ///
+    /// The offset doesn't need to be aligned for `U`. Returns `None`, and
+    /// writes nothing, if the value is not entirely within this view.
+    pub fn write_at<U: ValueType>(&self, byte_offset: usize, value: U) -> Option<()> {
+        let dst = self.byte_ptr::<U>(byte_offset)?;
+        unsafe { ptr::write_unaligned(dst, value) };
+        Some(())
+    }
+
+    /// Returns a view of the elements of type `U` starting at `byte_offset`
+    /// bytes from the start of this view, as many as fit in it.
+    ///
+    /// Returns `None` if `byte_offset` is past the end of this view, or is
+    /// not aligned for `U`.
+    pub fn view_at<U: ValueType>(&self, byte_offset: usize) -> Option<MemoryView<'a, U>> {
+        let remaining = self.byte_len().checked_sub(byte_offset)?;
+        let ptr = unsafe { (self.ptr as *mut u8).add(byte_offset) } as *mut U;
+        if ptr as usize % mem::align_of::<U>() != 0 {
+            return None;
+        }
+        Some(MemoryView {
+            ptr,
+            length: remaining / mem::size_of::<U>(),
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Returns an iterator over views of `chunk_len` elements of this
+    /// view. The last chunk is shorter if `chunk_len` doesn't divide the
+    /// length of the view.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_len` is 0.
+    pub fn chunks(&self, chunk_len: usize) -> impl Iterator<Item = MemoryView<'a, T>> + 'a {
+        assert!(chunk_len != 0, "chunk length must not be zero");
+        let (base, length) = (self.ptr, self.length);
+        (0..length).step_by(chunk_len).map(move |start| MemoryView {
+            ptr: unsafe { base.add(start) },
+            length: chunk_len.min(length - start),
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Returns a pointer to a `U` at `byte_offset`, if it fits in this view.
+    fn byte_ptr<U>(&self, byte_offset: usize) -> Option<*mut U> {
+        let end = byte_offset.checked_add(mem::size_of::<U>())?;
+        if end > self.byte_len() {
+            return None;
+        }
+        Some(unsafe { (self.ptr as *mut u8).add(byte_offset) } as *mut U)
+    }
 }
 
 impl<'a, T: Atomic> MemoryView<'a, T> {
@@ -92,3 +175,41 @@ impl<'a, T> Deref for MemoryView<'a, T, Atomically> {
         unsafe { slice::from_raw_parts(self.ptr as *const T, self.length) }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subview_and_chunks() {
+        let mut data = [0u8, 1, 2, 3, 4, 5, 6, 7, 8, 9];
+        let view: MemoryView<u8> = unsafe { MemoryView::new(data.as_mut_ptr(), 10) };
+
+        let sub = view.subview(2, 5).unwrap();
+        assert_eq!(sub.len(), 5);
+        assert_eq!(sub[0].get(), 2);
+        assert!(view.subview(8, 3).is_none());
+        assert!(view.subview(usize::MAX, 2).is_none());
+
+        let lens: Vec<_> = view.chunks(4).map(|chunk| chunk.len()).collect();
+        assert_eq!(lens, vec![4, 4, 2]);
+        assert_eq!(view.chunks(4).nth(2).unwrap()[1].get(), 9);
+    }
+
+    #[test]
+    fn typed_access() {
+        let mut data = [0u32; 4];
+        let view: MemoryView<u8> = unsafe { MemoryView::new(data.as_mut_ptr() as *mut u8, 16) };
+
+        view.write_at(1, 0x0102_0304u32).unwrap();
+        assert_eq!(view.read_at::<u32>(1), Some(0x0102_0304));
+        assert_eq!(view.read_at::<u16>(1), Some(0x0304));
+        assert_eq!(view.read_at::<u64>(9), None);
+        assert_eq!(view.write_at(15, 0u16), None);
+
+        let words = view.view_at::<u32>(4).unwrap();
+        assert_eq!(words.len(), 3);
+        assert!(view.view_at::<u32>(1).is_none());
+        assert!(view.view_at::<u32>(17).is_none());
+    }
+}
@@ -5,7 +5,9 @@ use crate::lib::std::boxed::Box;
 use crate::lib::std::cell::{self, RefCell};
 use crate::lib::std::fmt;
 use crate::lib::std::hash;
+use crate::lib::std::mem;
 use crate::lib::std::rc::{Rc, Weak};
+use crate::lib::std::vec::Vec;
 
 pub trait HostInfo {
     fn finalize(&mut self) {}
@@ -34,9 +36,12 @@ impl InternalRef {
     }
 }
 
+type Finalizer = Box<dyn FnOnce(Box<dyn Any>)>;
+
 struct AnyAndHostInfo {
     any: Box<dyn Any>,
     host_info: Option<Box<dyn HostInfo>>,
+    finalizer: Option<Finalizer>,
 }
 
 impl Drop for AnyAndHostInfo {
@@ -44,6 +49,10 @@ impl Drop for AnyAndHostInfo {
         if let Some(info) = &mut self.host_info {
             info.finalize();
         }
+        if let Some(finalizer) = self.finalizer.take() {
+            let any = mem::replace(&mut self.any, Box::new(()));
+            finalizer(any);
+        }
     }
 }
 
@@ -79,6 +88,45 @@ impl ExternRef {
         let info = AnyAndHostInfo {
             any: data,
             host_info: None,
+            finalizer: None,
+        };
+        Self::Other(OtherRef(Rc::new(RefCell::new(info))))
+    }
+
+    /// Creates a new instance of `ExternRef` from `Box<dyn Any>`, that
+    /// calls `finalizer` with the data once the last reference to it is
+    /// dropped, whether that reference was held by the host or by
+    /// WebAssembly.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer_types::ExternRef;
+    /// # use std::cell::Cell;
+    /// # use std::rc::Rc;
+    /// let closed = Rc::new(Cell::new(false));
+    /// let externref = ExternRef::new_with_finalizer(Box::new(3u32), {
+    ///     let closed = closed.clone();
+    ///     move |data| {
+    ///         assert_eq!(data.downcast_ref::<u32>(), Some(&3));
+    ///         closed.set(true);
+    ///     }
+    /// });
+    ///
+    /// let other = externref.clone();
+    /// drop(externref);
+    /// assert!(!closed.get());
+    /// drop(other);
+    /// assert!(closed.get());
+    /// ```
+    pub fn new_with_finalizer<F>(data: Box<dyn Any>, finalizer: F) -> Self
+    where
+        F: FnOnce(Box<dyn Any>) + 'static,
+    {
+        let info = AnyAndHostInfo {
+            any: data,
+            host_info: None,
+            finalizer: Some(Box::new(finalizer)),
         };
         Self::Other(OtherRef(Rc::new(RefCell::new(info))))
     }
@@ -144,6 +192,142 @@ impl ExternRef {
             }
         }
     }
+
+    /// Returns the number of `ExternRef`s pointing to the same value as
+    /// this one, including this one.
+    ///
+    /// Returns `0` for a `Null` reference. This is meant for diagnostics,
+    /// for example to find out what keeps a host object alive.
+    pub fn strong_count(&self) -> usize {
+        match self {
+            Self::Null => 0,
+            Self::Ref(InternalRef(r)) => Rc::strong_count(r),
+            Self::Other(OtherRef(r)) => Rc::strong_count(r),
+        }
+    }
+
+    fn downgrade(&self) -> Option<WeakExternRef> {
+        match self {
+            Self::Null => None,
+            Self::Ref(InternalRef(r)) => Some(WeakExternRef::Ref(Rc::downgrade(r))),
+            Self::Other(OtherRef(r)) => Some(WeakExternRef::Other(Rc::downgrade(r))),
+        }
+    }
+}
+
+enum WeakExternRef {
+    Ref(Weak<dyn InternalRefBase>),
+    Other(Weak<RefCell<AnyAndHostInfo>>),
+}
+
+impl WeakExternRef {
+    fn upgrade(&self) -> Option<ExternRef> {
+        match self {
+            Self::Ref(r) => r.upgrade().map(|r| ExternRef::Ref(InternalRef(r))),
+            Self::Other(r) => r.upgrade().map(|r| ExternRef::Other(OtherRef(r))),
+        }
+    }
+}
+
+/// Roots the `ExternRef`s created within a scope.
+///
+/// The references created with [`ExternRefScope::new_ref`] or passed to
+/// [`ExternRefScope::root`] are kept alive until the scope is dropped,
+/// whatever the guest does with them in the meantime. When the scope is
+/// dropped the roots are released, and the values die unless something
+/// else still refers to them.
+///
+/// [`ExternRefScope::assert_released`] closes the scope and checks that
+/// none of its references outlived it.
+///
+/// # Example
+///
+/// ```
+/// # use wasmer_types::{ExternRef, ExternRefScope};
+/// let mut scope = ExternRefScope::new();
+/// let handle = scope.new_ref(Box::new(42u32));
+/// assert_eq!(handle.strong_count(), 2);
+///
+/// drop(handle);
+/// scope.assert_released();
+/// ```
+#[derive(Default)]
+pub struct ExternRefScope {
+    roots: Vec<ExternRef>,
+}
+
+impl ExternRefScope {
+    /// Creates a new, empty scope.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new `ExternRef` from `data`, like [`ExternRef::new`],
+    /// rooted in this scope.
+    pub fn new_ref(&mut self, data: Box<dyn Any>) -> ExternRef {
+        self.root(ExternRef::new(data))
+    }
+
+    /// Roots `externref` in this scope, and returns it.
+    ///
+    /// `Null` references are not tracked.
+    pub fn root(&mut self, externref: ExternRef) -> ExternRef {
+        if !matches!(externref, ExternRef::Null) {
+            self.roots.push(externref.clone());
+        }
+        externref
+    }
+
+    /// Returns the number of references rooted in this scope.
+    pub fn len(&self) -> usize {
+        self.roots.len()
+    }
+
+    /// Returns true if no reference is rooted in this scope.
+    pub fn is_empty(&self) -> bool {
+        self.roots.is_empty()
+    }
+
+    /// Releases the roots of this scope, and returns the references that
+    /// are still alive afterwards.
+    pub fn release(mut self) -> Vec<ExternRef> {
+        let weaks = self
+            .roots
+            .drain(..)
+            .filter_map(|externref| externref.downgrade())
+            .collect::<Vec<_>>();
+        let mut alive: Vec<ExternRef> = Vec::new();
+        for externref in weaks.iter().filter_map(WeakExternRef::upgrade) {
+            // The same value may have been rooted more than once.
+            if !alive.iter().any(|other| other.ptr_eq(&externref)) {
+                alive.push(externref);
+            }
+        }
+        alive
+    }
+
+    /// Releases the roots of this scope.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the references rooted in this scope is still
+    /// alive afterwards.
+    pub fn assert_released(self) {
+        let alive = self.release();
+        assert!(
+            alive.is_empty(),
+            "{} `ExternRef`(s) outlived their scope",
+            alive.len()
+        );
+    }
+}
+
+impl fmt::Debug for ExternRefScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExternRefScope")
+            .field("roots", &self.roots.len())
+            .finish()
+    }
 }
 
 impl fmt::Debug for ExternRef {
@@ -265,3 +449,64 @@ impl<T: fmt::Debug> fmt::Debug for HostRef<T> {
         write!(f, ")")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strong_count() {
+        assert_eq!(ExternRef::null().strong_count(), 0);
+
+        let externref = ExternRef::new(Box::new(1u32));
+        assert_eq!(externref.strong_count(), 1);
+        let other = externref.clone();
+        assert_eq!(externref.strong_count(), 2);
+        drop(other);
+        assert_eq!(externref.strong_count(), 1);
+
+        let host_ref = HostRef::new(1u32);
+        let a = host_ref.externref();
+        let b = host_ref.externref();
+        assert_eq!(a.strong_count(), 2);
+        drop(b);
+        assert_eq!(a.strong_count(), 1);
+    }
+
+    #[test]
+    fn finalizer_runs_once_on_last_drop() {
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let externref = ExternRef::new_with_finalizer(Box::new(7u32), {
+            let calls = calls.clone();
+            move |data| calls.borrow_mut().push(*data.downcast::<u32>().unwrap())
+        });
+        let other = externref.clone();
+        drop(externref);
+        assert!(calls.borrow().is_empty());
+        drop(other);
+        assert_eq!(*calls.borrow(), [7]);
+    }
+
+    #[test]
+    fn scope_roots_until_released() {
+        let mut scope = ExternRefScope::new();
+        let weak = scope.new_ref(Box::new(1u32)).downgrade().unwrap();
+        scope.root(ExternRef::null());
+        assert_eq!(scope.len(), 1);
+        assert!(weak.upgrade().is_some());
+
+        let kept = scope.new_ref(Box::new(2u32));
+        let alive = scope.release();
+        assert!(weak.upgrade().is_none());
+        assert_eq!(alive.len(), 1);
+        assert!(alive[0].ptr_eq(&kept));
+    }
+
+    #[test]
+    #[should_panic(expected = "1 `ExternRef`(s) outlived their scope")]
+    fn scope_assert_released() {
+        let mut scope = ExternRefScope::new();
+        let _leaked = scope.new_ref(Box::new(1u32));
+        scope.assert_released();
+    }
+}
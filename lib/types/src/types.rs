@@ -1,11 +1,13 @@
 use crate::indexes::{FunctionIndex, GlobalIndex};
 use crate::lib::std::borrow::ToOwned;
+use crate::lib::std::boxed::Box;
 use crate::lib::std::fmt;
 use crate::lib::std::format;
 use crate::lib::std::string::{String, ToString};
 use crate::lib::std::vec::Vec;
 use crate::units::Pages;
 use crate::values::Value;
+#[cfg(feature = "std")]
 use loupe::{MemoryUsage, MemoryUsageTracker};
 
 #[cfg(feature = "enable-serde")]
@@ -16,7 +18,8 @@ use serde::{Deserialize, Serialize};
 // Value Types
 
 /// A list of all possible value types in WebAssembly.
-#[derive(Copy, Debug, Clone, Eq, PartialEq, Hash, MemoryUsage)]
+#[derive(Copy, Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
 pub enum Type {
     /// Signed 32 bit integer.
@@ -98,6 +101,7 @@ impl From<&[u8]> for V128 {
     }
 }
 
+#[cfg(feature = "std")]
 impl MemoryUsage for V128 {
     fn size_of_val(&self, tracker: &mut dyn MemoryUsageTracker) -> usize {
         self.as_slice().size_of_val(tracker)
@@ -232,7 +236,8 @@ impl ExternType {
 /// in a Wasm module or exposed to Wasm by the host.
 ///
 /// WebAssembly functions can have 0 or more parameters and results.
-#[derive(Debug, Clone, PartialEq, Eq, Hash, MemoryUsage)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
 pub struct FunctionType {
     /// The parameters of the function
@@ -317,7 +322,8 @@ impl From<&FunctionType> for FunctionType {
 }
 
 /// Indicator of whether a global is mutable or not
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, MemoryUsage)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
 pub enum Mutability {
     /// The global is constant and its value does not change
@@ -353,7 +359,8 @@ impl From<Mutability> for bool {
 }
 
 /// WebAssembly global.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, MemoryUsage)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
 pub struct GlobalType {
     /// The type of the value stored in the global.
@@ -396,7 +403,8 @@ impl fmt::Display for GlobalType {
 }
 
 /// Globals are initialized via the `const` operators or by referring to another import.
-#[derive(Debug, Clone, Copy, MemoryUsage)]
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
 pub enum GlobalInit {
     /// An `i32.const`.
@@ -447,7 +455,8 @@ impl GlobalInit {
 /// Tables are contiguous chunks of a specific element, typically a `funcref` or
 /// an `externref`. The most common use for tables is a function table through
 /// which `call_indirect` can invoke other functions.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, MemoryUsage)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
 pub struct TableType {
     /// The type of data stored in elements of the table.
@@ -486,7 +495,8 @@ impl fmt::Display for TableType {
 ///
 /// Memories are described in units of pages (64KB) and represent contiguous
 /// chunks of addressable memory.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, MemoryUsage)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
 pub struct MemoryType {
     /// The minimum number of pages in the memory.
@@ -638,4 +648,18 @@ mod tests {
         assert_eq!(ty.params().len(), 9);
         assert_eq!(ty.results().len(), 9);
     }
+
+    #[cfg(feature = "enable-serde")]
+    #[test]
+    fn serialize_extern_types() {
+        let types = vec![
+            ExternType::Function(V128_I64_TO_I32.into()),
+            ExternType::Global(GlobalType::new(Type::F64, Mutability::Var)),
+            ExternType::Table(TableType::new(Type::FuncRef, 1, None)),
+            ExternType::Memory(MemoryType::new(1, Some(16), true)),
+        ];
+        let json = serde_json::to_string(&types).unwrap();
+        let deserialized: Vec<ExternType> = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, types);
+    }
 }
@@ -1,10 +1,11 @@
-use crate::lib::std::convert::TryFrom;
+use crate::lib::std::convert::{TryFrom, TryInto};
 use crate::lib::std::fmt;
 use crate::lib::std::ops::{Add, Sub};
+#[cfg(feature = "std")]
 use loupe::MemoryUsage;
 #[cfg(feature = "enable-serde")]
 use serde::{Deserialize, Serialize};
-use std::convert::TryInto;
+#[cfg(feature = "std")]
 use thiserror::Error;
 
 /// WebAssembly page sizes are fixed to be 64KiB.
@@ -20,7 +21,8 @@ pub const WASM_MAX_PAGES: u32 = 0x10000;
 pub const WASM_MIN_PAGES: u32 = 0x100;
 
 /// Units of WebAssembly pages (as specified to be 65,536 bytes).
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, MemoryUsage)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
 pub struct Pages(pub u32);
 
@@ -112,8 +114,9 @@ where
 }
 
 /// The only error that can happen when converting `Bytes` to `Pages`
-#[derive(Debug, Clone, Copy, PartialEq, Error)]
-#[error("Number of pages exceeds uint32 range")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "std", derive(Error))]
+#[cfg_attr(feature = "std", error("Number of pages exceeds uint32 range"))]
 pub struct PageCountOutOfRange;
 
 impl TryFrom<Bytes> for Pages {
@@ -4,9 +4,14 @@ use crate::lib::std::ptr;
 use crate::lib::std::string::{String, ToString};
 use crate::r#ref::ExternRef;
 use crate::types::Type;
+#[cfg(feature = "enable-serde")]
+use serde::{ser, Deserialize, Deserializer, Serialize, Serializer};
 
 /// Possible runtime values that a WebAssembly module can either consume or
 /// produce.
+///
+/// With the `enable-serde` feature, numeric values and null references
+/// can be serialized. Serializing any other reference fails.
 #[derive(Clone, PartialEq)]
 pub enum Value<T> {
     /// A 32-bit integer.
@@ -277,6 +282,97 @@ impl<T> TryFrom<Value<T>> for f64 {
     }
 }
 
+/// The function references held by a [`Value::FuncRef`].
+///
+/// It tells null function references apart, as they are the only
+/// function references that can be serialized.
+pub trait NullableFuncRef: Sized {
+    /// Returns whether this is a null function reference.
+    fn is_null(&self) -> bool;
+
+    /// Returns a null function reference, or `None` if `Self` can't
+    /// represent one. A null `externref` is used instead then.
+    fn null() -> Option<Self>;
+}
+
+impl NullableFuncRef for () {
+    fn is_null(&self) -> bool {
+        false
+    }
+
+    fn null() -> Option<Self> {
+        None
+    }
+}
+
+impl<T> NullableFuncRef for Option<T> {
+    fn is_null(&self) -> bool {
+        self.is_none()
+    }
+
+    fn null() -> Option<Self> {
+        Some(None)
+    }
+}
+
+/// The serialized form of a [`Value`].
+///
+/// References can't be sent outside of the process that created them,
+/// so only null references are serializable.
+#[cfg(feature = "enable-serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Value")]
+enum SerializableValue {
+    I32(i32),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+    NullExternRef,
+    V128(u128),
+    NullFuncRef,
+}
+
+#[cfg(feature = "enable-serde")]
+impl<T: NullableFuncRef> Serialize for Value<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let value = match self {
+            Self::I32(v) => SerializableValue::I32(*v),
+            Self::I64(v) => SerializableValue::I64(*v),
+            Self::F32(v) => SerializableValue::F32(*v),
+            Self::F64(v) => SerializableValue::F64(*v),
+            Self::V128(v) => SerializableValue::V128(*v),
+            Self::ExternRef(ExternRef::Null) => SerializableValue::NullExternRef,
+            Self::ExternRef(_) => {
+                return Err(ser::Error::custom(
+                    "only null `externref` values can be serialized",
+                ))
+            }
+            Self::FuncRef(f) if f.is_null() => SerializableValue::NullFuncRef,
+            Self::FuncRef(_) => {
+                return Err(ser::Error::custom(
+                    "only null `funcref` values can be serialized",
+                ))
+            }
+        };
+        value.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "enable-serde")]
+impl<'de, T: NullableFuncRef> Deserialize<'de> for Value<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match SerializableValue::deserialize(deserializer)? {
+            SerializableValue::I32(v) => Self::I32(v),
+            SerializableValue::I64(v) => Self::I64(v),
+            SerializableValue::F32(v) => Self::F32(v),
+            SerializableValue::F64(v) => Self::F64(v),
+            SerializableValue::NullExternRef => Self::ExternRef(ExternRef::Null),
+            SerializableValue::V128(v) => Self::V128(v),
+            SerializableValue::NullFuncRef => T::null().map_or_else(Self::null, Self::FuncRef),
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -408,4 +504,40 @@ mod tests {
         let result = f64::try_from(value);
         assert_eq!(result.unwrap_err(), "Value is not of Wasm type f64");
     }
+
+    #[cfg(feature = "enable-serde")]
+    #[test]
+    fn serialize_values() {
+        let values = vec![
+            Value::<()>::I32(-1),
+            Value::I64(i64::MIN),
+            Value::F32(1.5),
+            Value::F64(-0.25),
+            Value::V128(u128::MAX),
+            Value::null(),
+        ];
+        let json = serde_json::to_string(&values).unwrap();
+        let deserialized: Vec<Value<()>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, values);
+
+        assert!(serde_json::to_string(&Value::FuncRef(())).is_err());
+        assert!(serde_json::to_string(&Value::FuncRef(Some(()))).is_err());
+        assert!(
+            serde_json::to_string(&Value::<()>::ExternRef(ExternRef::new(Box::new(1)))).is_err()
+        );
+    }
+
+    #[cfg(feature = "enable-serde")]
+    #[test]
+    fn serialize_null_funcref() {
+        let values = vec![Value::<Option<()>>::FuncRef(None), Value::null()];
+        let json = serde_json::to_string(&values).unwrap();
+        let deserialized: Vec<Value<Option<()>>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, values);
+
+        // Without a null function reference, a null `externref` is used.
+        let deserialized: Value<()> =
+            serde_json::from_str(&serde_json::to_string(&values[0]).unwrap()).unwrap();
+        assert_eq!(deserialized, Value::null());
+    }
 }
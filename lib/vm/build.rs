@@ -1,7 +1,13 @@
 //! Runtime build script compiles C code using setjmp for trap handling.
+//!
+//! Without the `std` feature, `setjmp`/`longjmp` are provided by the
+//! `Platform` instead.
 
 fn main() {
     println!("cargo:rerun-if-changed=src/trap/helpers.c");
+    if std::env::var_os("CARGO_FEATURE_STD").is_none() {
+        return;
+    }
     cc::Build::new()
         .warnings(true)
         .file("src/trap/helpers.c")
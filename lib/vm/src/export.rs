@@ -3,11 +3,12 @@
 
 use crate::global::Global;
 use crate::instance::InstanceRef;
+use crate::lib::std::sync::Arc;
 use crate::memory::{Memory, MemoryStyle};
 use crate::table::{Table, TableStyle};
 use crate::vmcontext::{VMFunctionBody, VMFunctionEnvironment, VMFunctionKind, VMTrampoline};
+#[cfg(feature = "std")]
 use loupe::MemoryUsage;
-use std::sync::Arc;
 use wasmer_types::{FunctionType, MemoryType, TableType};
 
 /// The value of an export passed from one instance to another.
@@ -27,7 +28,8 @@ pub enum VMExport {
 }
 
 /// A function export value.
-#[derive(Debug, Clone, PartialEq, MemoryUsage)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 pub struct VMExportFunction {
     /// The address of the native-code function.
     pub address: *const VMFunctionBody,
@@ -47,7 +49,7 @@ pub struct VMExportFunction {
     ///
     /// May be `None` when the function is a host function (`FunctionType`
     /// == `Dynamic` or `vmctx` == `nullptr`).
-    #[loupe(skip)]
+    #[cfg_attr(feature = "std", loupe(skip))]
     pub call_trampoline: Option<VMTrampoline>,
 
     /// A “reference” to the instance through the
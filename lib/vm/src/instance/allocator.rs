@@ -1,10 +1,11 @@
 use super::{Instance, InstanceRef};
+use crate::lib::std::alloc::{self, Layout};
+use crate::lib::std::convert::TryFrom;
+use crate::lib::std::mem;
+use crate::lib::std::ptr::{self, NonNull};
+use crate::lib::std::vec::Vec;
 use crate::vmcontext::{VMMemoryDefinition, VMTableDefinition};
 use crate::{ModuleInfo, VMOffsets};
-use std::alloc::{self, Layout};
-use std::convert::TryFrom;
-use std::mem;
-use std::ptr::{self, NonNull};
 use wasmer_types::entity::EntityRef;
 use wasmer_types::{LocalMemoryIndex, LocalTableIndex};
 
@@ -51,7 +52,7 @@ impl Drop for InstanceAllocator {
             let instance_ptr = self.instance_ptr.as_ptr();
 
             unsafe {
-                std::alloc::dealloc(instance_ptr as *mut u8, self.instance_layout);
+                alloc::dealloc(instance_ptr as *mut u8, self.instance_layout);
             }
         }
     }
@@ -170,7 +171,7 @@ impl InstanceAllocator {
 
         // We need to do some pointer arithmetic now. The unit is `u8`.
         let ptr = self.instance_ptr.cast::<u8>().as_ptr();
-        let base_ptr = ptr.add(std::mem::size_of::<Instance>());
+        let base_ptr = ptr.add(mem::size_of::<Instance>());
 
         for i in 0..num_tables {
             let table_offset = self
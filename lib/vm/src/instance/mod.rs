@@ -16,6 +16,20 @@ pub use r#ref::InstanceRef;
 use crate::export::VMExport;
 use crate::global::Global;
 use crate::imports::Imports;
+use crate::lib::std::any::{Any, TypeId};
+use crate::lib::std::boxed::Box;
+use crate::lib::std::cell::{Cell, RefCell};
+use crate::lib::std::collections::HashSet;
+use crate::lib::std::convert::{TryFrom, TryInto};
+use crate::lib::std::ffi;
+use crate::lib::std::fmt;
+use crate::lib::std::mem;
+use crate::lib::std::ptr::{self, NonNull};
+use crate::lib::std::slice;
+use crate::lib::std::string::String;
+use crate::lib::std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use crate::lib::std::sync::Arc;
+use crate::lib::std::time::Duration;
 use crate::memory::{Memory, MemoryError};
 use crate::table::Table;
 use crate::trap::{catch_traps, init_traps, Trap, TrapCode};
@@ -27,18 +41,10 @@ use crate::vmcontext::{
 };
 use crate::{FunctionBodyPtr, ModuleInfo, VMOffsets};
 use crate::{VMExportFunction, VMExportGlobal, VMExportMemory, VMExportTable};
+#[cfg(feature = "std")]
 use loupe::{MemoryUsage, MemoryUsageTracker};
 use memoffset::offset_of;
 use more_asserts::assert_lt;
-use std::any::Any;
-use std::cell::{Cell, RefCell};
-use std::convert::{TryFrom, TryInto};
-use std::ffi;
-use std::fmt;
-use std::mem;
-use std::ptr::{self, NonNull};
-use std::slice;
-use std::sync::Arc;
 use wasmer_types::entity::{packed_option::ReservedValue, BoxedSlice, EntityRef, PrimaryMap};
 use wasmer_types::{
     DataIndex, DataInitializer, ElemIndex, ExportIndex, FunctionIndex, GlobalIndex, GlobalInit,
@@ -57,7 +63,7 @@ pub type ImportInitializerFuncPtr<ResultErr = *mut ffi::c_void> =
 /// contain various data. That's why the type has a C representation
 /// to ensure that the `vmctx` field is last. See the documentation of
 /// the `vmctx` field to learn more.
-#[derive(MemoryUsage)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 #[repr(C)]
 pub(crate) struct Instance {
     /// The `ModuleInfo` this `Instance` was instantiated from.
@@ -79,7 +85,7 @@ pub(crate) struct Instance {
     functions: BoxedSlice<LocalFunctionIndex, FunctionBodyPtr>,
 
     /// Pointers to function call trampolines in executable memory.
-    #[loupe(skip)]
+    #[cfg_attr(feature = "std", loupe(skip))]
     function_call_trampolines: BoxedSlice<SignatureIndex, VMTrampoline>,
 
     /// Passive elements in this instantiation. As `elem.drop`s happen, these
@@ -94,7 +100,7 @@ pub(crate) struct Instance {
     host_state: Box<dyn Any>,
 
     /// Handler run when `SIGBUS`, `SIGFPE`, `SIGILL`, or `SIGSEGV` are caught by the instance thread.
-    #[loupe(skip)]
+    #[cfg_attr(feature = "std", loupe(skip))]
     pub(crate) signal_handler: Cell<Option<Box<SignalHandler>>>,
 
     /// Functions to operate on host environments in the imports
@@ -108,7 +114,7 @@ pub(crate) struct Instance {
     /// field is last, and represents a dynamically-sized array that
     /// extends beyond the nominal end of the struct (similar to a
     /// flexible array member).
-    #[loupe(skip)]
+    #[cfg_attr(feature = "std", loupe(skip))]
     vmctx: VMContext,
 }
 
@@ -135,6 +141,12 @@ pub enum ImportFunctionEnv {
         /// should be set to `None` after use to prevent double
         /// initialization.
         initializer: Option<ImportInitializerFuncPtr>,
+        /// The function to call, with the type of the user-supplied host
+        /// env, once every host env of the instance is initialized.
+        ///
+        /// It's only called for the first host env of each type, and
+        /// should be set to `None` after use like `initializer`.
+        finisher: Option<(TypeId, ImportInitializerFuncPtr)>,
         /// The destructor to clean up the type in `env`.
         ///
         /// # Safety
@@ -163,6 +175,7 @@ impl Clone for ImportFunctionEnv {
                 clone,
                 destructor,
                 initializer,
+                finisher,
             } => {
                 let new_env = (*clone)(*env);
                 Self::Env {
@@ -170,6 +183,7 @@ impl Clone for ImportFunctionEnv {
                     clone: *clone,
                     destructor: *destructor,
                     initializer: *initializer,
+                    finisher: *finisher,
                 }
             }
         }
@@ -194,6 +208,7 @@ impl Drop for ImportFunctionEnv {
     }
 }
 
+#[cfg(feature = "std")]
 impl MemoryUsage for ImportFunctionEnv {
     fn size_of_val(&self, _: &mut dyn MemoryUsageTracker) -> usize {
         mem::size_of_val(self)
@@ -762,6 +777,104 @@ impl Instance {
         passive_data[data_index] = None;
     }
 
+    /// Perform the `memory.atomic.wait32` operation on a locally defined
+    /// memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Trap` error if the address is out of bounds or unaligned,
+    /// or if the memory is not shared.
+    pub(crate) fn local_memory_atomic_wait32(
+        &self,
+        memory_index: LocalMemoryIndex,
+        addr: u64,
+        expected: u32,
+        timeout: i64,
+    ) -> Result<u32, Trap> {
+        memory_atomic_wait32(&*self.memories[memory_index], addr, expected, timeout)
+    }
+
+    /// Perform the `memory.atomic.wait32` operation on an imported memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Trap` error if the address is out of bounds or unaligned,
+    /// or if the memory is not shared.
+    pub(crate) fn imported_memory_atomic_wait32(
+        &self,
+        memory_index: MemoryIndex,
+        addr: u64,
+        expected: u32,
+        timeout: i64,
+    ) -> Result<u32, Trap> {
+        let import = self.imported_memory(memory_index);
+        memory_atomic_wait32(&*import.from, addr, expected, timeout)
+    }
+
+    /// Perform the `memory.atomic.wait64` operation on a locally defined
+    /// memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Trap` error if the address is out of bounds or unaligned,
+    /// or if the memory is not shared.
+    pub(crate) fn local_memory_atomic_wait64(
+        &self,
+        memory_index: LocalMemoryIndex,
+        addr: u64,
+        expected: u64,
+        timeout: i64,
+    ) -> Result<u32, Trap> {
+        memory_atomic_wait64(&*self.memories[memory_index], addr, expected, timeout)
+    }
+
+    /// Perform the `memory.atomic.wait64` operation on an imported memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Trap` error if the address is out of bounds or unaligned,
+    /// or if the memory is not shared.
+    pub(crate) fn imported_memory_atomic_wait64(
+        &self,
+        memory_index: MemoryIndex,
+        addr: u64,
+        expected: u64,
+        timeout: i64,
+    ) -> Result<u32, Trap> {
+        let import = self.imported_memory(memory_index);
+        memory_atomic_wait64(&*import.from, addr, expected, timeout)
+    }
+
+    /// Perform the `memory.atomic.notify` operation on a locally defined
+    /// memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Trap` error if the address is out of bounds or unaligned.
+    pub(crate) fn local_memory_atomic_notify(
+        &self,
+        memory_index: LocalMemoryIndex,
+        addr: u64,
+        count: u32,
+    ) -> Result<u32, Trap> {
+        memory_atomic_notify(&*self.memories[memory_index], addr, count)
+    }
+
+    /// Perform the `memory.atomic.notify` operation on an imported memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Trap` error if the address is out of bounds or unaligned.
+    pub(crate) fn imported_memory_atomic_notify(
+        &self,
+        memory_index: MemoryIndex,
+        addr: u64,
+        count: u32,
+    ) -> Result<u32, Trap> {
+        let import = self.imported_memory(memory_index);
+        memory_atomic_notify(&*import.from, addr, count)
+    }
+
     /// Get a table by index regardless of whether it is locally-defined or an
     /// imported, foreign table.
     pub(crate) fn get_table(&self, table_index: TableIndex) -> &dyn Table {
@@ -789,7 +902,8 @@ impl Instance {
 ///
 /// This is more or less a public facade of the private `Instance`,
 /// providing useful higher-level API.
-#[derive(Debug, PartialEq, MemoryUsage)]
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 pub struct InstanceHandle {
     /// The [`InstanceRef`]. See its documentation to learn more.
     instance: InstanceRef,
@@ -1143,6 +1257,10 @@ impl InstanceHandle {
 
     /// Initializes the host environments.
     ///
+    /// All the host environments are initialized first. Then, the
+    /// finisher of the first host environment of each type is called,
+    /// once per instance.
+    ///
     /// # Safety
     /// - This function must be called with the correct `Err` type parameter: the error type is not
     ///   visible to code in `wasmer_vm`, so it's the caller's responsibility to ensure these
@@ -1174,12 +1292,33 @@ impl InstanceHandle {
                 ImportFunctionEnv::NoEnv => (),
             }
         }
+
+        let mut finished_env_types = HashSet::new();
+        for import_function_env in instance_ref.imported_function_envs.values_mut() {
+            if let ImportFunctionEnv::Env {
+                env,
+                ref mut finisher,
+                ..
+            } = import_function_env
+            {
+                if let Some((env_type, f)) = finisher.take() {
+                    if finished_env_types.insert(env_type) {
+                        // transmute our function pointer into one with the correct error type
+                        let f = mem::transmute::<
+                            ImportInitializerFuncPtr,
+                            ImportInitializerFuncPtr<Err>,
+                        >(f);
+                        f(*env, instance_ptr)?;
+                    }
+                }
+            }
+        }
         Ok(())
     }
 }
 
 cfg_if::cfg_if! {
-    if #[cfg(unix)] {
+    if #[cfg(all(feature = "std", unix))] {
         pub type SignalHandler = dyn Fn(libc::c_int, *const libc::siginfo_t, *const libc::c_void) -> bool;
 
         impl InstanceHandle {
@@ -1191,7 +1330,7 @@ cfg_if::cfg_if! {
                 self.instance().as_ref().signal_handler.set(Some(Box::new(handler)));
             }
         }
-    } else if #[cfg(target_os = "windows")] {
+    } else if #[cfg(all(feature = "std", target_os = "windows"))] {
         pub type SignalHandler = dyn Fn(winapi::um::winnt::PEXCEPTION_POINTERS) -> bool;
 
         impl InstanceHandle {
@@ -1203,6 +1342,21 @@ cfg_if::cfg_if! {
                 self.instance().as_ref().signal_handler.set(Some(Box::new(handler)));
             }
         }
+    } else {
+        // Without the `std` feature, the faults are handled by the
+        // platform, which passes their program counter to `handle_fault`.
+        pub type SignalHandler = dyn Fn(*const u8) -> bool;
+
+        impl InstanceHandle {
+            /// Set a custom signal handler, called with the program counter
+            /// of the faults passed to [`handle_fault`](crate::handle_fault).
+            pub fn set_signal_handler<H>(&self, handler: H)
+            where
+                H: 'static + Fn(*const u8) -> bool,
+            {
+                self.instance().as_ref().signal_handler.set(Some(Box::new(handler)));
+            }
+        }
     }
 }
 
@@ -1395,3 +1549,77 @@ fn initialize_globals(instance: &Instance) {
         }
     }
 }
+
+/// Block the current thread on `addr` of `memory`, provided that the 32-bit
+/// value at `addr` is `expected`, until it is notified or `timeout`
+/// nanoseconds elapsed. A negative `timeout` waits indefinitely.
+///
+/// The wait shares its queues with [`crate::ParkingSpot`] users on the
+/// host, so the host and the guest can notify each other.
+fn memory_atomic_wait32(
+    memory: &dyn Memory,
+    addr: u64,
+    expected: u32,
+    timeout: i64,
+) -> Result<u32, Trap> {
+    let value = checked_atomic_ptr(memory, addr, 4)? as *const AtomicU32;
+    memory_atomic_wait(memory, addr, timeout, || unsafe {
+        (*value).load(Ordering::SeqCst) == expected
+    })
+}
+
+/// Like [`memory_atomic_wait32`], with a 64-bit value.
+fn memory_atomic_wait64(
+    memory: &dyn Memory,
+    addr: u64,
+    expected: u64,
+    timeout: i64,
+) -> Result<u32, Trap> {
+    let value = checked_atomic_ptr(memory, addr, 8)? as *const AtomicU64;
+    memory_atomic_wait(memory, addr, timeout, || unsafe {
+        (*value).load(Ordering::SeqCst) == expected
+    })
+}
+
+fn memory_atomic_wait(
+    memory: &dyn Memory,
+    addr: u64,
+    timeout: i64,
+    validate: impl FnOnce() -> bool,
+) -> Result<u32, Trap> {
+    let spot = match memory.parking_spot() {
+        Some(spot) if memory.ty().shared => spot,
+        _ => return Err(Trap::new_from_runtime(TrapCode::AtomicWaitNonSharedMemory)),
+    };
+    let timeout = u64::try_from(timeout).ok().map(Duration::from_nanos);
+    Ok(spot.wait(addr, validate, timeout) as u32)
+}
+
+/// Wake up to `count` threads waiting on `addr` of `memory`, and return the
+/// number of threads woken. No thread can wait on a memory that is not
+/// shared, so none is woken.
+fn memory_atomic_notify(memory: &dyn Memory, addr: u64, count: u32) -> Result<u32, Trap> {
+    checked_atomic_ptr(memory, addr, 4)?;
+    if !memory.ty().shared {
+        return Ok(0);
+    }
+    Ok(memory
+        .parking_spot()
+        .map_or(0, |spot| spot.notify(addr, count)))
+}
+
+/// Returns a pointer to the `size` bytes at `addr` of `memory`, after
+/// checking that they are within the memory and aligned.
+fn checked_atomic_ptr(memory: &dyn Memory, addr: u64, size: u64) -> Result<*const u8, Trap> {
+    let definition = unsafe { memory.vmmemory().as_ref() };
+    if addr
+        .checked_add(size)
+        .map_or(true, |end| end > u64::from(definition.current_length))
+    {
+        return Err(Trap::new_from_runtime(TrapCode::HeapAccessOutOfBounds));
+    }
+    if addr % size != 0 {
+        return Err(Trap::new_from_runtime(TrapCode::UnalignedAtomic));
+    }
+    Ok(unsafe { definition.base.add(addr as usize) })
+}
@@ -1,9 +1,11 @@
 use super::Instance;
+use crate::lib::std::alloc::{self, Layout};
+#[cfg(feature = "std")]
+use crate::lib::std::mem;
+use crate::lib::std::ptr::{self, NonNull};
+use crate::lib::std::sync::{atomic, Arc};
+#[cfg(feature = "std")]
 use loupe::{MemoryUsage, MemoryUsageTracker};
-use std::alloc::Layout;
-use std::mem;
-use std::ptr::{self, NonNull};
-use std::sync::{atomic, Arc};
 
 /// An `InstanceRef` is responsible to properly deallocate,
 /// and to give access to an `Instance`, in such a way that `Instance`
@@ -78,7 +80,7 @@ impl InstanceRef {
     ///
     /// Going above this limit will make the program to panic at exactly
     /// `MAX_REFCOUNT` references.
-    const MAX_REFCOUNT: usize = std::usize::MAX - 1;
+    const MAX_REFCOUNT: usize = usize::MAX - 1;
 
     /// Deallocate `Instance`.
     ///
@@ -90,7 +92,7 @@ impl InstanceRef {
         let instance_ptr = self.instance.as_ptr();
 
         ptr::drop_in_place(instance_ptr);
-        std::alloc::dealloc(instance_ptr as *mut u8, self.instance_layout);
+        alloc::dealloc(instance_ptr as *mut u8, self.instance_layout);
     }
 
     /// Get the number of strong references pointing to this
@@ -211,6 +213,7 @@ impl Drop for InstanceRef {
     }
 }
 
+#[cfg(feature = "std")]
 impl MemoryUsage for InstanceRef {
     fn size_of_val(&self, tracker: &mut dyn MemoryUsageTracker) -> usize {
         mem::size_of_val(self) + self.strong.size_of_val(tracker) - mem::size_of_val(&self.strong)
@@ -2,6 +2,7 @@
 
 #![deny(missing_docs, trivial_numeric_casts, unused_extern_crates)]
 #![warn(unused_import_braces)]
+#![cfg_attr(not(feature = "std"), no_std)]
 #![cfg_attr(
     feature = "cargo-clippy",
     allow(clippy::new_without_default, vtable_address_comparisons)
@@ -20,13 +21,113 @@
     )
 )]
 
+#[cfg(all(feature = "std", feature = "core"))]
+compile_error!(
+    "The `std` and `core` features are both enabled, which is an error. Please enable only once."
+);
+
+#[cfg(all(not(feature = "std"), not(feature = "core")))]
+compile_error!("Both the `std` and `core` features are disabled. Please enable one of them.");
+
+#[cfg(feature = "core")]
+#[macro_use]
+extern crate alloc;
+
+/// The `lib` module defines a `std` module that is identical whether
+/// the `core` or the `std` feature is enabled.
+mod lib {
+    #[cfg(feature = "core")]
+    pub mod std {
+        pub use ::alloc::{alloc, borrow, boxed, string, vec};
+        pub use core::{any, cell, cmp, convert, ffi, fmt, iter, mem, ops, ptr, slice, time, u32};
+
+        pub mod collections {
+            pub use ::alloc::collections::VecDeque;
+            pub use hashbrown::{hash_map, HashMap, HashSet};
+        }
+
+        pub mod error {
+            use ::alloc::boxed::Box;
+            use core::any::TypeId;
+            use core::fmt::{Debug, Display};
+
+            /// Stands in for `std::error::Error`, which isn't available
+            /// without the `std` feature.
+            pub trait Error: Debug + Display {
+                #[doc(hidden)]
+                fn error_type_id(&self) -> TypeId
+                where
+                    Self: 'static,
+                {
+                    TypeId::of::<Self>()
+                }
+            }
+
+            impl<T: Debug + Display> Error for T {}
+
+            impl dyn Error + Send + Sync {
+                /// Returns `true` if the boxed error is of type `T`.
+                pub fn is<T: Error + 'static>(&self) -> bool {
+                    self.error_type_id() == TypeId::of::<T>()
+                }
+
+                /// Returns a reference to the error if it is of type `T`.
+                pub fn downcast_ref<T: Error + 'static>(&self) -> Option<&T> {
+                    if self.is::<T>() {
+                        unsafe { Some(&*(self as *const Self as *const T)) }
+                    } else {
+                        None
+                    }
+                }
+
+                /// Attempts to downcast the box to a concrete type.
+                pub fn downcast<T: Error + 'static>(self: Box<Self>) -> Result<Box<T>, Box<Self>> {
+                    if self.is::<T>() {
+                        unsafe { Ok(Box::from_raw(Box::into_raw(self) as *mut T)) }
+                    } else {
+                        Err(self)
+                    }
+                }
+            }
+        }
+
+        pub mod sync {
+            pub use crate::lock::{Mutex, Once, RwLock};
+            pub use ::alloc::sync::Arc;
+            pub use core::sync::atomic;
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub mod std {
+        pub use std::{
+            alloc, any, borrow, boxed, cell, cmp, collections, convert, error, ffi, fmt, fs, io,
+            iter, mem, ops, os, panic, ptr, slice, string, sync, time, u32, vec,
+        };
+    }
+
+    /// Without the `std` feature, memory usage isn't measured and this
+    /// trait stands in for `loupe::MemoryUsage` in the bounds of the
+    /// `Memory` and `Table` traits.
+    #[cfg(feature = "core")]
+    pub trait MemoryUsage {}
+
+    #[cfg(feature = "core")]
+    impl<T: ?Sized> MemoryUsage for T {}
+}
+
 mod export;
 mod global;
 mod imports;
 mod instance;
+mod limiter;
+#[cfg(feature = "core")]
+pub mod lock;
 mod memory;
 mod mmap;
 mod module;
+mod parking_spot;
+mod platform;
 mod probestack;
 mod sig_registry;
 mod table;
@@ -42,9 +143,19 @@ pub use crate::imports::Imports;
 pub use crate::instance::{
     ImportFunctionEnv, ImportInitializerFuncPtr, InstanceAllocator, InstanceHandle,
 };
-pub use crate::memory::{LinearMemory, Memory, MemoryError, MemoryStyle};
+#[cfg(feature = "core")]
+pub use crate::lib::std::error::Error;
+pub use crate::limiter::{LimitedMemory, LimitedTable, ResourceLimiter};
+pub use crate::memory::{
+    HostBuffer, HostBufferMemory, LinearMemory, Memory, MemoryError, MemoryGrowCallback,
+    MemoryStyle,
+};
 pub use crate::mmap::Mmap;
 pub use crate::module::{ExportsIterator, ImportsIterator, ModuleInfo};
+pub use crate::parking_spot::{ParkingSpot, WaitResult};
+#[cfg(feature = "std")]
+pub use crate::platform::OsPlatform;
+pub use crate::platform::{platform, set_platform, Platform, StaticPlatform};
 pub use crate::probestack::PROBESTACK;
 pub use crate::sig_registry::SignatureRegistry;
 pub use crate::table::{LinearTable, Table, TableStyle};
@@ -56,17 +167,19 @@ pub use crate::vmcontext::{
     VMTableImport, VMTrampoline,
 };
 pub use crate::vmoffsets::{TargetSharedSignatureIndex, VMOffsets};
+#[cfg(feature = "std")]
 use loupe::MemoryUsage;
 
 /// Version number of this crate.
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 /// A safe wrapper around `VMFunctionBody`.
-#[derive(Clone, Copy, Debug, MemoryUsage)]
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 #[repr(transparent)]
 pub struct FunctionBodyPtr(pub *const VMFunctionBody);
 
-impl std::ops::Deref for FunctionBodyPtr {
+impl crate::lib::std::ops::Deref for FunctionBodyPtr {
     type Target = *const VMFunctionBody;
 
     fn deref(&self) -> &Self::Target {
@@ -88,7 +201,7 @@ unsafe impl Sync for FunctionBodyPtr {}
 #[repr(transparent)]
 pub struct SectionBodyPtr(pub *const u8);
 
-impl std::ops::Deref for SectionBodyPtr {
+impl crate::lib::std::ops::Deref for SectionBodyPtr {
     type Target = *const u8;
 
     fn deref(&self) -> &Self::Target {
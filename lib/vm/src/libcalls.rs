@@ -35,14 +35,65 @@
 //!   }
 //!   ```
 
+use crate::lib::std::fmt;
 use crate::probestack::PROBESTACK;
 use crate::trap::{raise_lib_trap, Trap, TrapCode};
 use crate::vmcontext::VMContext;
+#[cfg(feature = "std")]
 use loupe::MemoryUsage;
 use serde::{Deserialize, Serialize};
-use std::fmt;
 use wasmer_types::{DataIndex, ElemIndex, LocalMemoryIndex, MemoryIndex, TableIndex};
 
+/// Without the `std` feature, the floating point functions missing from
+/// `core` are provided by `libm`.
+#[cfg(not(feature = "std"))]
+trait FloatExt {
+    fn ceil(self) -> Self;
+    fn floor(self) -> Self;
+    fn trunc(self) -> Self;
+    // `abs` is only provided by `core` since Rust 1.85.
+    #[allow(dead_code)]
+    fn abs(self) -> Self;
+}
+
+#[cfg(not(feature = "std"))]
+impl FloatExt for f32 {
+    fn ceil(self) -> Self {
+        libm::ceilf(self)
+    }
+
+    fn floor(self) -> Self {
+        libm::floorf(self)
+    }
+
+    fn trunc(self) -> Self {
+        libm::truncf(self)
+    }
+
+    fn abs(self) -> Self {
+        libm::fabsf(self)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl FloatExt for f64 {
+    fn ceil(self) -> Self {
+        libm::ceil(self)
+    }
+
+    fn floor(self) -> Self {
+        libm::floor(self)
+    }
+
+    fn trunc(self) -> Self {
+        libm::trunc(self)
+    }
+
+    fn abs(self) -> Self {
+        libm::fabs(self)
+    }
+}
+
 /// Implementation of f32.ceil
 #[no_mangle]
 pub extern "C" fn wasmer_f32_ceil(x: f32) -> f32 {
@@ -382,6 +433,142 @@ pub unsafe extern "C" fn wasmer_data_drop(vmctx: *mut VMContext, data_index: u32
     instance.data_drop(data_index)
 }
 
+/// Implementation of `memory.atomic.wait32` for locally defined memories.
+///
+/// # Safety
+///
+/// `vmctx` must be valid and not null.
+pub unsafe extern "C" fn wasmer_memory_atomic_wait32(
+    vmctx: *mut VMContext,
+    memory_index: u32,
+    addr: u64,
+    expected: u32,
+    timeout: i64,
+) -> u32 {
+    let result = {
+        let memory_index = LocalMemoryIndex::from_u32(memory_index);
+        let instance = (&*vmctx).instance();
+        instance.local_memory_atomic_wait32(memory_index, addr, expected, timeout)
+    };
+    match result {
+        Ok(value) => value,
+        Err(trap) => raise_lib_trap(trap),
+    }
+}
+
+/// Implementation of `memory.atomic.wait32` for imported memories.
+///
+/// # Safety
+///
+/// `vmctx` must be valid and not null.
+pub unsafe extern "C" fn wasmer_imported_memory_atomic_wait32(
+    vmctx: *mut VMContext,
+    memory_index: u32,
+    addr: u64,
+    expected: u32,
+    timeout: i64,
+) -> u32 {
+    let result = {
+        let memory_index = MemoryIndex::from_u32(memory_index);
+        let instance = (&*vmctx).instance();
+        instance.imported_memory_atomic_wait32(memory_index, addr, expected, timeout)
+    };
+    match result {
+        Ok(value) => value,
+        Err(trap) => raise_lib_trap(trap),
+    }
+}
+
+/// Implementation of `memory.atomic.wait64` for locally defined memories.
+///
+/// # Safety
+///
+/// `vmctx` must be valid and not null.
+pub unsafe extern "C" fn wasmer_memory_atomic_wait64(
+    vmctx: *mut VMContext,
+    memory_index: u32,
+    addr: u64,
+    expected: u64,
+    timeout: i64,
+) -> u32 {
+    let result = {
+        let memory_index = LocalMemoryIndex::from_u32(memory_index);
+        let instance = (&*vmctx).instance();
+        instance.local_memory_atomic_wait64(memory_index, addr, expected, timeout)
+    };
+    match result {
+        Ok(value) => value,
+        Err(trap) => raise_lib_trap(trap),
+    }
+}
+
+/// Implementation of `memory.atomic.wait64` for imported memories.
+///
+/// # Safety
+///
+/// `vmctx` must be valid and not null.
+pub unsafe extern "C" fn wasmer_imported_memory_atomic_wait64(
+    vmctx: *mut VMContext,
+    memory_index: u32,
+    addr: u64,
+    expected: u64,
+    timeout: i64,
+) -> u32 {
+    let result = {
+        let memory_index = MemoryIndex::from_u32(memory_index);
+        let instance = (&*vmctx).instance();
+        instance.imported_memory_atomic_wait64(memory_index, addr, expected, timeout)
+    };
+    match result {
+        Ok(value) => value,
+        Err(trap) => raise_lib_trap(trap),
+    }
+}
+
+/// Implementation of `memory.atomic.notify` for locally defined memories.
+///
+/// # Safety
+///
+/// `vmctx` must be valid and not null.
+pub unsafe extern "C" fn wasmer_memory_atomic_notify(
+    vmctx: *mut VMContext,
+    memory_index: u32,
+    addr: u64,
+    count: u32,
+) -> u32 {
+    let result = {
+        let memory_index = LocalMemoryIndex::from_u32(memory_index);
+        let instance = (&*vmctx).instance();
+        instance.local_memory_atomic_notify(memory_index, addr, count)
+    };
+    match result {
+        Ok(value) => value,
+        Err(trap) => raise_lib_trap(trap),
+    }
+}
+
+/// Implementation of `memory.atomic.notify` for imported memories.
+///
+/// # Safety
+///
+/// `vmctx` must be valid and not null.
+pub unsafe extern "C" fn wasmer_imported_memory_atomic_notify(
+    vmctx: *mut VMContext,
+    memory_index: u32,
+    addr: u64,
+    count: u32,
+) -> u32 {
+    let result = {
+        let memory_index = MemoryIndex::from_u32(memory_index);
+        let instance = (&*vmctx).instance();
+        instance.imported_memory_atomic_notify(memory_index, addr, count)
+    };
+    match result {
+        Ok(value) => value,
+        Err(trap) => raise_lib_trap(trap),
+    }
+}
+
 /// Implementation for raising a trap
 ///
 /// # Safety
@@ -406,7 +593,8 @@ pub static wasmer_probestack: unsafe extern "C" fn() = PROBESTACK;
 /// The name of a runtime library routine.
 ///
 /// This list is likely to grow over time.
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, MemoryUsage)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 pub enum LibCall {
     /// ceil.f32
     CeilF32,
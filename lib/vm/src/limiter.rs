@@ -0,0 +1,160 @@
+//! Dynamic resource limits for linear memories and tables.
+//!
+//! The static limits provided by the `Tunables` are decided once, when a
+//! memory or a table is created. A [`ResourceLimiter`] is consulted every
+//! time a memory or a table tries to grow, so hosts can enforce quotas that
+//! change over time (for example a budget shared by several tenants).
+
+use crate::lib::std::fmt;
+use crate::lib::std::ptr::NonNull;
+use crate::lib::std::sync::Arc;
+use crate::memory::{Memory, MemoryError, MemoryGrowCallback, MemoryStyle};
+use crate::parking_spot::ParkingSpot;
+use crate::table::{Table, TableStyle};
+use crate::trap::Trap;
+use crate::vmcontext::{VMCallerCheckedAnyfunc, VMMemoryDefinition, VMTableDefinition};
+#[cfg(feature = "std")]
+use loupe::MemoryUsage;
+use wasmer_types::{MemoryType, Pages, TableType};
+
+/// A hook consulted on every `memory.grow` and `table.grow`, whether
+/// the growth is requested by WebAssembly code or by the host.
+///
+/// Returning `false` denies the growth: `memory.grow` and `table.grow`
+/// will then return `-1` to the guest, and the host APIs will return an
+/// error.
+pub trait ResourceLimiter: fmt::Debug + Send + Sync {
+    /// Called before a linear memory grows from `current` to `desired` pages.
+    ///
+    /// `maximum` is the maximum declared by the memory type, if any.
+    fn memory_growing(&self, current: Pages, desired: Pages, maximum: Option<Pages>) -> bool;
+
+    /// Called before a table grows from `current` to `desired` elements.
+    ///
+    /// `maximum` is the maximum declared by the table type, if any.
+    fn table_growing(&self, current: u32, desired: u32, maximum: Option<u32>) -> bool;
+}
+
+/// A [`Memory`] that consults a [`ResourceLimiter`] before growing.
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
+pub struct LimitedMemory {
+    inner: Arc<dyn Memory>,
+    #[cfg_attr(feature = "std", loupe(skip))]
+    limiter: Arc<dyn ResourceLimiter>,
+}
+
+impl LimitedMemory {
+    /// Wrap `inner` so that all its growth goes through `limiter`.
+    pub fn new(inner: Arc<dyn Memory>, limiter: Arc<dyn ResourceLimiter>) -> Self {
+        Self { inner, limiter }
+    }
+}
+
+impl Memory for LimitedMemory {
+    fn ty(&self) -> &MemoryType {
+        self.inner.ty()
+    }
+
+    fn style(&self) -> &MemoryStyle {
+        self.inner.style()
+    }
+
+    fn size(&self) -> Pages {
+        self.inner.size()
+    }
+
+    fn grow(&self, delta: Pages) -> Result<Pages, MemoryError> {
+        let current = self.inner.size();
+        if delta.0 != 0 {
+            let desired = current
+                .checked_add(delta)
+                .ok_or(MemoryError::CouldNotGrow {
+                    current,
+                    attempted_delta: delta,
+                })?;
+            if !self
+                .limiter
+                .memory_growing(current, desired, self.inner.ty().maximum)
+            {
+                return Err(MemoryError::CouldNotGrow {
+                    current,
+                    attempted_delta: delta,
+                });
+            }
+        }
+        self.inner.grow(delta)
+    }
+
+    fn vmmemory(&self) -> NonNull<VMMemoryDefinition> {
+        self.inner.vmmemory()
+    }
+
+    fn flush(&self) -> Result<(), MemoryError> {
+        self.inner.flush()
+    }
+
+    fn subscribe_grow(&self, callback: MemoryGrowCallback) -> Result<(), MemoryError> {
+        self.inner.subscribe_grow(callback)
+    }
+
+    fn parking_spot(&self) -> Option<&ParkingSpot> {
+        self.inner.parking_spot()
+    }
+}
+
+/// A [`Table`] that consults a [`ResourceLimiter`] before growing.
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
+pub struct LimitedTable {
+    inner: Arc<dyn Table>,
+    #[cfg_attr(feature = "std", loupe(skip))]
+    limiter: Arc<dyn ResourceLimiter>,
+}
+
+impl LimitedTable {
+    /// Wrap `inner` so that all its growth goes through `limiter`.
+    pub fn new(inner: Arc<dyn Table>, limiter: Arc<dyn ResourceLimiter>) -> Self {
+        Self { inner, limiter }
+    }
+}
+
+impl Table for LimitedTable {
+    fn style(&self) -> &TableStyle {
+        self.inner.style()
+    }
+
+    fn ty(&self) -> &TableType {
+        self.inner.ty()
+    }
+
+    fn size(&self) -> u32 {
+        self.inner.size()
+    }
+
+    fn grow(&self, delta: u32) -> Option<u32> {
+        let current = self.inner.size();
+        if delta != 0 {
+            let desired = current.checked_add(delta)?;
+            if !self
+                .limiter
+                .table_growing(current, desired, self.inner.ty().maximum)
+            {
+                return None;
+            }
+        }
+        self.inner.grow(delta)
+    }
+
+    fn get(&self, index: u32) -> Option<VMCallerCheckedAnyfunc> {
+        self.inner.get(index)
+    }
+
+    fn set(&self, index: u32, func: VMCallerCheckedAnyfunc) -> Result<(), Trap> {
+        self.inner.set(index, func)
+    }
+
+    fn vmtable(&self) -> NonNull<VMTableDefinition> {
+        self.inner.vmtable()
+    }
+}
@@ -0,0 +1,122 @@
+//! Locks used in place of `std::sync` ones when the `core` feature is
+//! enabled.
+//!
+//! They are spin locks with the same API as the `std` ones, so that this
+//! crate and the ones built on it don't depend on which feature is
+//! enabled. They can't be poisoned, so the results they return are
+//! always `Ok`.
+
+use core::ops::{Deref, DerefMut};
+
+/// The result of locking a [`Mutex`] or a [`RwLock`].
+pub type LockResult<Guard> = Result<Guard, PoisonError>;
+
+/// The error of a poisoned lock, like `std::sync::PoisonError`.
+///
+/// It's never returned, since these locks can't be poisoned.
+#[derive(Debug)]
+pub struct PoisonError(());
+
+/// A mutual exclusion primitive, like `std::sync::Mutex`.
+#[derive(Debug, Default)]
+pub struct Mutex<T: ?Sized>(spin::Mutex<T>);
+
+/// The guard returned by [`Mutex::lock`].
+pub struct MutexGuard<'a, T: ?Sized>(spin::MutexGuard<'a, T>);
+
+impl<T> Mutex<T> {
+    /// Creates a new unlocked mutex.
+    pub const fn new(value: T) -> Self {
+        Self(spin::Mutex::new(value))
+    }
+}
+
+impl<T: ?Sized> Mutex<T> {
+    /// Acquires the mutex, spinning until it is available.
+    pub fn lock(&self) -> LockResult<MutexGuard<'_, T>> {
+        Ok(MutexGuard(self.0.lock()))
+    }
+}
+
+impl<T: ?Sized> Deref for MutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: ?Sized> DerefMut for MutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+/// A reader-writer lock, like `std::sync::RwLock`.
+#[derive(Debug, Default)]
+pub struct RwLock<T: ?Sized>(spin::RwLock<T>);
+
+/// The guard returned by [`RwLock::read`].
+pub struct RwLockReadGuard<'a, T: ?Sized>(spin::RwLockReadGuard<'a, T>);
+
+/// The guard returned by [`RwLock::write`].
+pub struct RwLockWriteGuard<'a, T: ?Sized>(spin::RwLockWriteGuard<'a, T>);
+
+impl<T> RwLock<T> {
+    /// Creates a new unlocked reader-writer lock.
+    pub const fn new(value: T) -> Self {
+        Self(spin::RwLock::new(value))
+    }
+}
+
+impl<T: ?Sized> RwLock<T> {
+    /// Locks with shared read access, spinning while a writer holds the lock.
+    pub fn read(&self) -> LockResult<RwLockReadGuard<'_, T>> {
+        Ok(RwLockReadGuard(self.0.read()))
+    }
+
+    /// Locks with exclusive write access, spinning until it is available.
+    pub fn write(&self) -> LockResult<RwLockWriteGuard<'_, T>> {
+        Ok(RwLockWriteGuard(self.0.write()))
+    }
+}
+
+impl<T: ?Sized> Deref for RwLockReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: ?Sized> Deref for RwLockWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: ?Sized> DerefMut for RwLockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+/// A synchronization primitive to run a one-time initialization, like
+/// `std::sync::Once`.
+#[derive(Debug)]
+pub struct Once(spin::Once<()>);
+
+impl Once {
+    /// Creates a new `Once` value.
+    pub const fn new() -> Self {
+        Self(spin::Once::new())
+    }
+
+    /// Runs `f` if it is the first time `call_once` is called on this
+    /// value.
+    pub fn call_once<F: FnOnce()>(&self, f: F) {
+        self.0.call_once(f);
+    }
+}
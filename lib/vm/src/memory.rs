@@ -5,29 +5,42 @@
 //!
 //! `LinearMemory` is to WebAssembly linear memories what `Table` is to WebAssembly tables.
 
+use crate::lib::std::borrow::BorrowMut;
+use crate::lib::std::boxed::Box;
+use crate::lib::std::cell::UnsafeCell;
+use crate::lib::std::cmp;
+use crate::lib::std::convert::TryInto;
+use crate::lib::std::fmt;
+#[cfg(feature = "std")]
+use crate::lib::std::fs::File;
+use crate::lib::std::ptr::{self, NonNull};
+use crate::lib::std::string::String;
+use crate::lib::std::string::ToString;
+use crate::lib::std::sync::Mutex;
+use crate::lib::std::vec::Vec;
+#[cfg(not(feature = "std"))]
+use crate::lib::MemoryUsage;
 use crate::mmap::Mmap;
+use crate::parking_spot::ParkingSpot;
 use crate::vmcontext::VMMemoryDefinition;
+#[cfg(feature = "std")]
 use loupe::MemoryUsage;
 use more_asserts::assert_ge;
 use serde::{Deserialize, Serialize};
-use std::borrow::BorrowMut;
-use std::cell::UnsafeCell;
-use std::convert::TryInto;
-use std::fmt;
-use std::ptr::NonNull;
-use std::sync::Mutex;
+#[cfg(feature = "std")]
 use thiserror::Error;
-use wasmer_types::{Bytes, MemoryType, Pages};
+use wasmer_types::{Bytes, MemoryType, Pages, WASM_PAGE_SIZE};
 
 /// Error type describing things that can go wrong when operating on Wasm Memories.
-#[derive(Error, Debug, Clone, PartialEq, Hash)]
+#[derive(Debug, Clone, PartialEq, Hash)]
+#[cfg_attr(feature = "std", derive(Error))]
 pub enum MemoryError {
     /// Low level error with mmap.
-    #[error("Error when allocating memory: {0}")]
+    #[cfg_attr(feature = "std", error("Error when allocating memory: {0}"))]
     Region(String),
     /// The operation would cause the size of the memory to exceed the maximum or would cause
     /// an overflow leading to unindexable memory.
-    #[error("The memory could not grow: current size {} pages, requested increase: {} pages", current.0, attempted_delta.0)]
+    #[cfg_attr(feature = "std", error("The memory could not grow: current size {} pages, requested increase: {} pages", current.0, attempted_delta.0))]
     CouldNotGrow {
         /// The current size in pages.
         current: Pages,
@@ -35,13 +48,13 @@ pub enum MemoryError {
         attempted_delta: Pages,
     },
     /// The operation would cause the size of the memory size exceed the maximum.
-    #[error("The memory is invalid because {}", reason)]
+    #[cfg_attr(feature = "std", error("The memory is invalid because {}", reason))]
     InvalidMemory {
         /// The reason why the provided memory is invalid.
         reason: String,
     },
     /// Caller asked for more minimum memory than we can give them.
-    #[error("The minimum requested ({} pages) memory is greater than the maximum allowed memory ({} pages)", min_requested.0, max_allowed.0)]
+    #[cfg_attr(feature = "std", error("The minimum requested ({} pages) memory is greater than the maximum allowed memory ({} pages)", min_requested.0, max_allowed.0))]
     MinimumMemoryTooLarge {
         /// The number of pages requested as the minimum amount of memory.
         min_requested: Pages,
@@ -49,7 +62,7 @@ pub enum MemoryError {
         max_allowed: Pages,
     },
     /// Caller asked for a maximum memory greater than we can give them.
-    #[error("The maximum requested memory ({} pages) is greater than the maximum allowed memory ({} pages)", max_requested.0, max_allowed.0)]
+    #[cfg_attr(feature = "std", error("The maximum requested memory ({} pages) is greater than the maximum allowed memory ({} pages)", max_requested.0, max_allowed.0))]
     MaximumMemoryTooLarge {
         /// The number of pages requested as the maximum amount of memory.
         max_requested: Pages,
@@ -57,12 +70,13 @@ pub enum MemoryError {
         max_allowed: Pages,
     },
     /// A user defined error value, used for error cases not listed above.
-    #[error("A user-defined error occurred: {0}")]
+    #[cfg_attr(feature = "std", error("A user-defined error occurred: {0}"))]
     Generic(String),
 }
 
 /// Implementation styles for WebAssembly linear memory.
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, MemoryUsage)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 pub enum MemoryStyle {
     /// The actual memory can be resized and moved.
     Dynamic {
@@ -94,6 +108,19 @@ impl MemoryStyle {
             } => *offset_guard_size,
         }
     }
+
+    /// Returns whether generated code must explicitly bounds check
+    /// accesses to a memory with this style.
+    ///
+    /// Only static memories whose reservation covers the whole 32-bit
+    /// index space can rely on the reservation alone to catch
+    /// out-of-bounds accesses.
+    pub fn needs_bounds_checks(&self) -> bool {
+        match self {
+            Self::Dynamic { .. } => true,
+            Self::Static { bound, .. } => *bound < Pages::max_value(),
+        }
+    }
 }
 
 /// Trait for implementing Wasm Memory used by Wasmer.
@@ -114,10 +141,71 @@ pub trait Memory: fmt::Debug + Send + Sync + MemoryUsage {
     ///
     /// The pointer returned in [`VMMemoryDefinition`] must be valid for the lifetime of this memory.
     fn vmmemory(&self) -> NonNull<VMMemoryDefinition>;
+
+    /// Write back the contents of the memory to its backing storage, if it
+    /// has any (for example a file).
+    ///
+    /// Memories without backing storage have nothing to do.
+    fn flush(&self) -> Result<(), MemoryError> {
+        Ok(())
+    }
+
+    /// Register a callback invoked after every successful growth of this
+    /// memory, with the previous and the new number of wasm pages.
+    ///
+    /// The base pointer of the memory may have moved when the callback
+    /// is invoked, so any cached pointer into the memory must be refreshed.
+    fn subscribe_grow(&self, callback: MemoryGrowCallback) -> Result<(), MemoryError> {
+        let _ = callback;
+        Err(MemoryError::Generic(
+            "this memory does not support growth notifications".to_string(),
+        ))
+    }
+
+    /// Returns the queues of the threads waiting on addresses of this
+    /// memory, if it supports atomic waits.
+    fn parking_spot(&self) -> Option<&ParkingSpot> {
+        None
+    }
+}
+
+/// A callback invoked after a memory grew, with the previous and the new
+/// number of wasm pages.
+pub type MemoryGrowCallback = Box<dyn Fn(Pages, Pages) + Send + Sync>;
+
+/// The callbacks subscribed to the growth of a memory.
+#[derive(Default)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
+struct GrowObservers {
+    #[cfg_attr(feature = "std", loupe(skip))]
+    callbacks: Mutex<Vec<MemoryGrowCallback>>,
+}
+
+impl GrowObservers {
+    fn subscribe(&self, callback: MemoryGrowCallback) {
+        self.callbacks.lock().unwrap().push(callback);
+    }
+
+    /// Notify the subscribers. Must be called without holding any lock
+    /// of the memory, as the callbacks may access it.
+    fn notify(&self, prev_pages: Pages, new_pages: Pages) {
+        for callback in self.callbacks.lock().unwrap().iter() {
+            callback(prev_pages, new_pages);
+        }
+    }
+}
+
+impl fmt::Debug for GrowObservers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("GrowObservers")
+            .field("len", &self.callbacks.lock().unwrap().len())
+            .finish()
+    }
 }
 
 /// A linear memory instance.
-#[derive(Debug, MemoryUsage)]
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 pub struct LinearMemory {
     // The underlying allocation.
     mmap: Mutex<WasmMmap>,
@@ -141,11 +229,19 @@ pub struct LinearMemory {
     // Records whether we're using a bounds-checking strategy which requires
     // handlers to catch trapping accesses.
     pub(crate) needs_signal_handlers: bool,
+
+    // Callbacks invoked after the memory grew.
+    grow_observers: GrowObservers,
+
+    // The threads waiting on addresses of the memory.
+    #[cfg_attr(feature = "std", loupe(skip))]
+    parking_spot: ParkingSpot,
 }
 
 /// A type to help manage who is responsible for the backing memory of them
 /// `VMMemoryDefinition`.
-#[derive(Debug, MemoryUsage)]
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 enum VMMemoryDefinitionOwnership {
     /// The `VMMemoryDefinition` is owned by the `Instance` and we should use
     /// its memory. This is how a local memory that's exported should be stored.
@@ -168,12 +264,21 @@ unsafe impl Send for LinearMemory {}
 /// This is correct because all internal mutability is protected by a mutex.
 unsafe impl Sync for LinearMemory {}
 
-#[derive(Debug, MemoryUsage)]
+/// Memories can't be backed by files without the `std` feature.
+#[cfg(not(feature = "std"))]
+#[derive(Debug)]
+enum File {}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 struct WasmMmap {
     // Our OS allocation of mmap'd memory.
     alloc: Mmap,
     // The current logical size in wasm pages of this linear memory.
     size: Pages,
+    // The file the accessible part of `alloc` is mapped from, if any.
+    #[cfg_attr(feature = "std", loupe(skip))]
+    file: Option<File>,
 }
 
 impl LinearMemory {
@@ -182,7 +287,26 @@ impl LinearMemory {
     /// This creates a `LinearMemory` with owned metadata: this can be used to create a memory
     /// that will be imported into Wasm modules.
     pub fn new(memory: &MemoryType, style: &MemoryStyle) -> Result<Self, MemoryError> {
-        unsafe { Self::new_internal(memory, style, None) }
+        unsafe { Self::new_internal(memory, style, None, None) }
+    }
+
+    /// Create a new linear memory instance backed by `file`.
+    ///
+    /// The memory is mapped from the file, so its contents persist in the
+    /// file: if the file is not empty, its current contents become the
+    /// initial contents of the memory, and the memory starts with as many
+    /// pages as needed to hold them. The file is extended as the memory
+    /// grows. Use [`Memory::flush`] to synchronously write changes back.
+    ///
+    /// This creates a `LinearMemory` with owned metadata: this can be used to create a memory
+    /// that will be imported into Wasm modules.
+    #[cfg(feature = "std")]
+    pub fn new_file_backed(
+        memory: &MemoryType,
+        style: &MemoryStyle,
+        file: File,
+    ) -> Result<Self, MemoryError> {
+        unsafe { Self::new_internal(memory, style, None, Some(file)) }
     }
 
     /// Create a new linear memory instance with specified minimum and maximum number of wasm pages.
@@ -197,7 +321,7 @@ impl LinearMemory {
         style: &MemoryStyle,
         vm_memory_location: NonNull<VMMemoryDefinition>,
     ) -> Result<Self, MemoryError> {
-        Self::new_internal(memory, style, Some(vm_memory_location))
+        Self::new_internal(memory, style, Some(vm_memory_location), None)
     }
 
     /// Build a `LinearMemory` with either self-owned or VM owned metadata.
@@ -205,6 +329,7 @@ impl LinearMemory {
         memory: &MemoryType,
         style: &MemoryStyle,
         vm_memory_location: Option<NonNull<VMMemoryDefinition>>,
+        file: Option<File>,
     ) -> Result<Self, MemoryError> {
         if memory.minimum > Pages::max_value() {
             return Err(MemoryError::MinimumMemoryTooLarge {
@@ -230,6 +355,26 @@ impl LinearMemory {
             }
         }
 
+        // A file-backed memory starts with enough pages to hold the
+        // current contents of the file.
+        let initial_pages = match &file {
+            Some(file) => {
+                let file_pages = Self::file_pages(file)?;
+                if file_pages > Pages::max_value()
+                    || memory.maximum.map_or(false, |max| file_pages > max)
+                {
+                    return Err(MemoryError::InvalidMemory {
+                        reason: format!(
+                            "the backing file ({} pages) is larger than the maximum allowed",
+                            file_pages.0
+                        ),
+                    });
+                }
+                cmp::max(memory.minimum, file_pages)
+            }
+            None => memory.minimum,
+        };
+
         let offset_guard_bytes = style.offset_guard_size() as usize;
 
         // If we have an offset guard, or if we're doing the static memory
@@ -242,30 +387,53 @@ impl LinearMemory {
             };
 
         let minimum_pages = match style {
-            MemoryStyle::Dynamic { .. } => memory.minimum,
+            MemoryStyle::Dynamic { .. } => initial_pages,
             MemoryStyle::Static { bound, .. } => {
                 assert_ge!(*bound, memory.minimum);
+                if initial_pages > *bound {
+                    return Err(MemoryError::InvalidMemory {
+                        reason: format!(
+                            "the backing file ({} pages) is larger than the static bound ({} pages)",
+                            initial_pages.0, bound.0
+                        ),
+                    });
+                }
                 *bound
             }
         };
         let minimum_bytes = minimum_pages.bytes().0;
         let request_bytes = minimum_bytes.checked_add(offset_guard_bytes).unwrap();
-        let mapped_pages = memory.minimum;
+        let mapped_pages = initial_pages;
         let mapped_bytes = mapped_pages.bytes();
 
-        let mut mmap = WasmMmap {
-            alloc: Mmap::accessible_reserved(mapped_bytes.0, request_bytes)
-                .map_err(MemoryError::Region)?,
-            size: memory.minimum,
+        let mut mmap = match file {
+            Some(file) => {
+                let mut alloc =
+                    Mmap::accessible_reserved(0, request_bytes).map_err(MemoryError::Region)?;
+                Self::map_file(&mut alloc, &file, 0, mapped_bytes.0)?;
+                WasmMmap {
+                    alloc,
+                    size: initial_pages,
+                    file: Some(file),
+                }
+            }
+            None => WasmMmap {
+                alloc: Mmap::accessible_reserved(mapped_bytes.0, request_bytes)
+                    .map_err(MemoryError::Region)?,
+                size: initial_pages,
+                file: None,
+            },
         };
 
         let base_ptr = mmap.alloc.as_mut_ptr();
-        let mem_length = memory.minimum.bytes().0.try_into().unwrap();
+        let mem_length = initial_pages.bytes().0.try_into().unwrap();
         Ok(Self {
             mmap: Mutex::new(mmap),
             maximum: memory.maximum,
             offset_guard_size: offset_guard_bytes,
             needs_signal_handlers,
+            grow_observers: GrowObservers::default(),
+            parking_spot: ParkingSpot::default(),
             vm_memory_definition: if let Some(mem_loc) = vm_memory_location {
                 {
                     let mut ptr = mem_loc;
@@ -287,47 +455,50 @@ impl LinearMemory {
         })
     }
 
-    /// Get the `VMMemoryDefinition`.
-    ///
-    /// # Safety
-    /// - You must ensure that you have mutually exclusive access before calling
-    ///   this function. You can get this by locking the `mmap` mutex.
-    unsafe fn get_vm_memory_definition(&self) -> NonNull<VMMemoryDefinition> {
-        match &self.vm_memory_definition {
-            VMMemoryDefinitionOwnership::VMOwned(ptr) => *ptr,
-            VMMemoryDefinitionOwnership::HostOwned(boxed_ptr) => {
-                NonNull::new_unchecked(boxed_ptr.get())
-            }
-        }
+    /// Returns the number of pages needed to hold the contents of `file`.
+    #[cfg(feature = "std")]
+    fn file_pages(file: &File) -> Result<Pages, MemoryError> {
+        let file_len = file
+            .metadata()
+            .map_err(|e| MemoryError::Region(e.to_string()))?
+            .len();
+        let file_pages = (file_len + WASM_PAGE_SIZE as u64 - 1) / WASM_PAGE_SIZE as u64;
+        Ok(Pages(file_pages.try_into().unwrap_or(u32::MAX)))
     }
-}
 
-impl Memory for LinearMemory {
-    /// Returns the type for this memory.
-    fn ty(&self) -> &MemoryType {
-        &self.memory
+    #[cfg(not(feature = "std"))]
+    fn file_pages(file: &File) -> Result<Pages, MemoryError> {
+        match *file {}
     }
 
-    /// Returns the memory style for this memory.
-    fn style(&self) -> &MemoryStyle {
-        &self.style
+    /// Extend `file` to `start + len` bytes and map that range over `alloc`.
+    #[cfg(feature = "std")]
+    fn map_file(
+        alloc: &mut Mmap,
+        file: &File,
+        start: usize,
+        len: usize,
+    ) -> Result<(), MemoryError> {
+        file.set_len((start + len) as u64)
+            .map_err(|e| MemoryError::Region(e.to_string()))?;
+        alloc
+            .map_file(file, start, len)
+            .map_err(MemoryError::Region)
     }
 
-    /// Returns the number of allocated wasm pages.
-    fn size(&self) -> Pages {
-        // TODO: investigate this function for race conditions
-        unsafe {
-            let md_ptr = self.get_vm_memory_definition();
-            let md = md_ptr.as_ref();
-            Bytes::from(md.current_length).try_into().unwrap()
-        }
+    #[cfg(not(feature = "std"))]
+    fn map_file(
+        _alloc: &mut Mmap,
+        file: &File,
+        _start: usize,
+        _len: usize,
+    ) -> Result<(), MemoryError> {
+        match *file {}
     }
 
-    /// Grow memory by the specified amount of wasm pages.
-    ///
-    /// Returns `None` if memory can't be grown by the specified amount
-    /// of wasm pages.
-    fn grow(&self, delta: Pages) -> Result<Pages, MemoryError> {
+    /// Grow the memory while holding the `mmap` lock, without notifying
+    /// the subscribers.
+    fn grow_locked(&self, delta: Pages) -> Result<Pages, MemoryError> {
         let mut mmap_guard = self.mmap.lock().unwrap();
         let mmap = mmap_guard.borrow_mut();
         // Optimization of memory.grow 0 calls.
@@ -380,18 +551,34 @@ impl Memory for LinearMemory {
                         attempted_delta: Bytes(guard_bytes).try_into().unwrap(),
                     })?;
 
-            let mut new_mmap =
-                Mmap::accessible_reserved(new_bytes, request_bytes).map_err(MemoryError::Region)?;
+            let new_mmap = if let Some(file) = &mmap.file {
+                // The contents are already in the file: map all of it again
+                // in the new reservation.
+                let mut new_mmap =
+                    Mmap::accessible_reserved(0, request_bytes).map_err(MemoryError::Region)?;
+                Self::map_file(&mut new_mmap, file, 0, new_bytes)?;
+                new_mmap
+            } else {
+                let mut new_mmap = Mmap::accessible_reserved(new_bytes, request_bytes)
+                    .map_err(MemoryError::Region)?;
 
-            let copy_len = mmap.alloc.len() - self.offset_guard_size;
-            new_mmap.as_mut_slice()[..copy_len].copy_from_slice(&mmap.alloc.as_slice()[..copy_len]);
+                let copy_len = mmap.alloc.len() - self.offset_guard_size;
+                new_mmap.as_mut_slice()[..copy_len]
+                    .copy_from_slice(&mmap.alloc.as_slice()[..copy_len]);
+                new_mmap
+            };
 
             mmap.alloc = new_mmap;
         } else if delta_bytes > 0 {
             // Make the newly allocated pages accessible.
-            mmap.alloc
-                .make_accessible(prev_bytes, delta_bytes)
-                .map_err(MemoryError::Region)?;
+            let WasmMmap { alloc, file, .. } = &mut **mmap;
+            if let Some(file) = file {
+                Self::map_file(alloc, file, prev_bytes, delta_bytes)?;
+            } else {
+                alloc
+                    .make_accessible(prev_bytes, delta_bytes)
+                    .map_err(MemoryError::Region)?;
+            }
         }
 
         mmap.size = new_pages;
@@ -407,9 +594,296 @@ impl Memory for LinearMemory {
         Ok(prev_pages)
     }
 
+    /// Get the `VMMemoryDefinition`.
+    ///
+    /// # Safety
+    /// - You must ensure that you have mutually exclusive access before calling
+    ///   this function. You can get this by locking the `mmap` mutex.
+    unsafe fn get_vm_memory_definition(&self) -> NonNull<VMMemoryDefinition> {
+        match &self.vm_memory_definition {
+            VMMemoryDefinitionOwnership::VMOwned(ptr) => *ptr,
+            VMMemoryDefinitionOwnership::HostOwned(boxed_ptr) => {
+                NonNull::new_unchecked(boxed_ptr.get())
+            }
+        }
+    }
+}
+
+impl Memory for LinearMemory {
+    /// Returns the type for this memory.
+    fn ty(&self) -> &MemoryType {
+        &self.memory
+    }
+
+    /// Returns the memory style for this memory.
+    fn style(&self) -> &MemoryStyle {
+        &self.style
+    }
+
+    /// Returns the number of allocated wasm pages.
+    fn size(&self) -> Pages {
+        // TODO: investigate this function for race conditions
+        unsafe {
+            let md_ptr = self.get_vm_memory_definition();
+            let md = md_ptr.as_ref();
+            Bytes::from(md.current_length).try_into().unwrap()
+        }
+    }
+
+    /// Grow memory by the specified amount of wasm pages.
+    ///
+    /// Returns `None` if memory can't be grown by the specified amount
+    /// of wasm pages.
+    fn grow(&self, delta: Pages) -> Result<Pages, MemoryError> {
+        let prev_pages = self.grow_locked(delta)?;
+        if delta.0 != 0 {
+            self.grow_observers
+                .notify(prev_pages, Pages(prev_pages.0 + delta.0));
+        }
+        Ok(prev_pages)
+    }
+
     /// Return a `VMMemoryDefinition` for exposing the memory to compiled wasm code.
     fn vmmemory(&self) -> NonNull<VMMemoryDefinition> {
         let _mmap_guard = self.mmap.lock().unwrap();
         unsafe { self.get_vm_memory_definition() }
     }
+
+    fn subscribe_grow(&self, callback: MemoryGrowCallback) -> Result<(), MemoryError> {
+        self.grow_observers.subscribe(callback);
+        Ok(())
+    }
+
+    fn parking_spot(&self) -> Option<&ParkingSpot> {
+        Some(&self.parking_spot)
+    }
+
+    /// Write back the contents of a file-backed memory to its file.
+    fn flush(&self) -> Result<(), MemoryError> {
+        let mmap = self.mmap.lock().unwrap();
+        if mmap.file.is_some() {
+            mmap.alloc
+                .flush(0, mmap.size.bytes().0)
+                .map_err(MemoryError::Region)?;
+        }
+        Ok(())
+    }
+}
+
+/// A host-owned allocation that can back a linear memory.
+///
+/// This allows embedders to place guest memory in shared memory
+/// segments, pinned memory or pre-allocated arenas.
+///
+/// # Safety
+/// - `as_mut_ptr` must always return the same pointer, to an allocation
+///   of at least `len` bytes.
+/// - The allocation must stay valid, and must not be accessed other than
+///   through the memory it backs, until the `HostBuffer` is dropped.
+pub unsafe trait HostBuffer: Send + Sync {
+    /// Returns a pointer to the start of the buffer.
+    fn as_mut_ptr(&mut self) -> *mut u8;
+
+    /// Returns the size of the buffer in bytes.
+    fn len(&self) -> usize;
+
+    /// Returns `true` if the buffer has a length of 0.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+unsafe impl HostBuffer for Vec<u8> {
+    fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.as_mut_slice().as_mut_ptr()
+    }
+
+    fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+}
+
+unsafe impl HostBuffer for Box<[u8]> {
+    fn as_mut_ptr(&mut self) -> *mut u8 {
+        (**self).as_mut_ptr()
+    }
+
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+}
+
+/// A linear memory backed by a [`HostBuffer`].
+///
+/// The buffer is never reallocated: the memory grows in place up to the
+/// size of the buffer, and its base pointer never moves. The current
+/// contents of the buffer are used as the initial contents of the memory.
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
+pub struct HostBufferMemory {
+    // The current logical size in wasm pages of this linear memory.
+    size: Mutex<Pages>,
+
+    // The number of whole wasm pages that fit in the buffer.
+    capacity: Pages,
+
+    /// The WebAssembly linear memory description.
+    memory: MemoryType,
+
+    /// Our chosen implementation style.
+    style: MemoryStyle,
+
+    /// The memory definition used by the generated code.
+    vm_memory_definition: Box<UnsafeCell<VMMemoryDefinition>>,
+
+    // The underlying allocation, kept alive as long as the memory.
+    #[cfg_attr(feature = "std", loupe(skip))]
+    buffer: Mutex<Box<dyn HostBuffer>>,
+
+    // Callbacks invoked after the memory grew.
+    grow_observers: GrowObservers,
+
+    // The threads waiting on addresses of the memory.
+    #[cfg_attr(feature = "std", loupe(skip))]
+    parking_spot: ParkingSpot,
+}
+
+/// This is correct because the buffer is required to be `Send` and
+/// the memory definition only points into it.
+unsafe impl Send for HostBufferMemory {}
+
+/// This is correct because all internal mutability is protected by a mutex.
+unsafe impl Sync for HostBufferMemory {}
+
+impl fmt::Debug for dyn HostBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HostBuffer")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl HostBufferMemory {
+    /// Create a new linear memory of type `memory` backed by `buffer`.
+    ///
+    /// The memory can never grow beyond the number of wasm pages that
+    /// fit in `buffer`, even if its type allows it.
+    pub fn new(memory: &MemoryType, mut buffer: Box<dyn HostBuffer>) -> Result<Self, MemoryError> {
+        let capacity = Pages(
+            (buffer.len() / WASM_PAGE_SIZE)
+                .min(Pages::max_value().0 as usize)
+                .try_into()
+                .unwrap(),
+        );
+        if memory.minimum > capacity {
+            return Err(MemoryError::MinimumMemoryTooLarge {
+                min_requested: memory.minimum,
+                max_allowed: capacity,
+            });
+        }
+        if let Some(max) = memory.maximum {
+            if max < memory.minimum {
+                return Err(MemoryError::InvalidMemory {
+                    reason: format!(
+                        "the maximum ({} pages) is less than the minimum ({} pages)",
+                        max.0, memory.minimum.0
+                    ),
+                });
+            }
+        }
+
+        let base = buffer.as_mut_ptr();
+        Ok(Self {
+            size: Mutex::new(memory.minimum),
+            capacity,
+            memory: *memory,
+            // The buffer has no guard pages, so every access must be
+            // bounds-checked against the current length.
+            style: MemoryStyle::Dynamic {
+                offset_guard_size: 0,
+            },
+            vm_memory_definition: Box::new(UnsafeCell::new(VMMemoryDefinition {
+                base,
+                current_length: memory.minimum.bytes().0.try_into().unwrap(),
+            })),
+            buffer: Mutex::new(buffer),
+            grow_observers: GrowObservers::default(),
+            parking_spot: ParkingSpot::default(),
+        })
+    }
+
+    /// Grow the memory while holding the `size` lock, without notifying
+    /// the subscribers.
+    fn grow_locked(&self, delta: Pages) -> Result<Pages, MemoryError> {
+        let mut size = self.size.lock().unwrap();
+        let prev_pages = *size;
+        let new_pages = prev_pages
+            .checked_add(delta)
+            .filter(|new_pages| {
+                *new_pages <= self.capacity
+                    && self.memory.maximum.map_or(true, |max| *new_pages <= max)
+            })
+            .ok_or(MemoryError::CouldNotGrow {
+                current: prev_pages,
+                attempted_delta: delta,
+            })?;
+        if delta.0 == 0 {
+            return Ok(prev_pages);
+        }
+
+        let mut buffer = self.buffer.lock().unwrap();
+        let prev_bytes = prev_pages.bytes().0;
+        let delta_bytes = delta.bytes().0;
+        unsafe {
+            ptr::write_bytes(buffer.as_mut_ptr().add(prev_bytes), 0, delta_bytes);
+            let md = &mut *self.vm_memory_definition.get();
+            md.current_length = new_pages.bytes().0.try_into().unwrap();
+        }
+        *size = new_pages;
+
+        Ok(prev_pages)
+    }
+}
+
+impl Memory for HostBufferMemory {
+    /// Returns the type for this memory.
+    fn ty(&self) -> &MemoryType {
+        &self.memory
+    }
+
+    /// Returns the memory style for this memory.
+    fn style(&self) -> &MemoryStyle {
+        &self.style
+    }
+
+    /// Returns the number of allocated wasm pages.
+    fn size(&self) -> Pages {
+        *self.size.lock().unwrap()
+    }
+
+    /// Grow memory in place by the specified amount of wasm pages.
+    ///
+    /// The new pages are zeroed.
+    fn grow(&self, delta: Pages) -> Result<Pages, MemoryError> {
+        let prev_pages = self.grow_locked(delta)?;
+        if delta.0 != 0 {
+            self.grow_observers
+                .notify(prev_pages, Pages(prev_pages.0 + delta.0));
+        }
+        Ok(prev_pages)
+    }
+
+    /// Return a `VMMemoryDefinition` for exposing the memory to compiled wasm code.
+    fn vmmemory(&self) -> NonNull<VMMemoryDefinition> {
+        unsafe { NonNull::new_unchecked(self.vm_memory_definition.get()) }
+    }
+
+    fn subscribe_grow(&self, callback: MemoryGrowCallback) -> Result<(), MemoryError> {
+        self.grow_observers.subscribe(callback);
+        Ok(())
+    }
+
+    fn parking_spot(&self) -> Option<&ParkingSpot> {
+        Some(&self.parking_spot)
+    }
 }
@@ -4,12 +4,17 @@
 //! Low-level abstraction for allocating and managing zero-filled pages
 //! of memory.
 
+#[cfg(feature = "std")]
+use crate::lib::std::fs::File;
+use crate::lib::std::ptr::NonNull;
+use crate::lib::std::slice;
+use crate::lib::std::string::String;
+use crate::lib::std::vec::Vec;
+use crate::platform::platform;
+#[cfg(feature = "std")]
 use loupe::{MemoryUsage, MemoryUsageTracker};
 use more_asserts::assert_le;
 use more_asserts::assert_lt;
-use std::io;
-use std::ptr;
-use std::slice;
 
 /// Round `size` up to the nearest multiple of `page_size`.
 fn round_up_to_page_size(size: usize, page_size: usize) -> usize {
@@ -43,7 +48,7 @@ impl Mmap {
 
     /// Create a new `Mmap` pointing to at least `size` bytes of page-aligned accessible memory.
     pub fn with_at_least(size: usize) -> Result<Self, String> {
-        let page_size = region::page::size();
+        let page_size = platform().page_size();
         let rounded_size = round_up_to_page_size(size, page_size);
         Self::accessible_reserved(rounded_size, rounded_size)
     }
@@ -51,12 +56,11 @@ impl Mmap {
     /// Create a new `Mmap` pointing to `accessible_size` bytes of page-aligned accessible memory,
     /// within a reserved mapping of `mapping_size` bytes. `accessible_size` and `mapping_size`
     /// must be native page-size multiples.
-    #[cfg(not(target_os = "windows"))]
     pub fn accessible_reserved(
         accessible_size: usize,
         mapping_size: usize,
     ) -> Result<Self, String> {
-        let page_size = region::page::size();
+        let page_size = platform().page_size();
         assert_le!(accessible_size, mapping_size);
         assert_eq!(mapping_size & (page_size - 1), 0);
         assert_eq!(accessible_size & (page_size - 1), 0);
@@ -69,104 +73,18 @@ impl Mmap {
 
         Ok(if accessible_size == mapping_size {
             // Allocate a single read-write region at once.
-            let ptr = unsafe {
-                libc::mmap(
-                    ptr::null_mut(),
-                    mapping_size,
-                    libc::PROT_READ | libc::PROT_WRITE,
-                    libc::MAP_PRIVATE | libc::MAP_ANON,
-                    -1,
-                    0,
-                )
-            };
-            if ptr as isize == -1_isize {
-                return Err(io::Error::last_os_error().to_string());
-            }
-
-            Self {
-                ptr: ptr as usize,
-                len: mapping_size,
-            }
-        } else {
-            // Reserve the mapping size.
-            let ptr = unsafe {
-                libc::mmap(
-                    ptr::null_mut(),
-                    mapping_size,
-                    libc::PROT_NONE,
-                    libc::MAP_PRIVATE | libc::MAP_ANON,
-                    -1,
-                    0,
-                )
-            };
-            if ptr as isize == -1_isize {
-                return Err(io::Error::last_os_error().to_string());
-            }
-
-            let mut result = Self {
-                ptr: ptr as usize,
-                len: mapping_size,
-            };
-
-            if accessible_size != 0 {
-                // Commit the accessible size.
-                result.make_accessible(0, accessible_size)?;
-            }
-
-            result
-        })
-    }
-
-    /// Create a new `Mmap` pointing to `accessible_size` bytes of page-aligned accessible memory,
-    /// within a reserved mapping of `mapping_size` bytes. `accessible_size` and `mapping_size`
-    /// must be native page-size multiples.
-    #[cfg(target_os = "windows")]
-    pub fn accessible_reserved(
-        accessible_size: usize,
-        mapping_size: usize,
-    ) -> Result<Self, String> {
-        use winapi::um::memoryapi::VirtualAlloc;
-        use winapi::um::winnt::{MEM_COMMIT, MEM_RESERVE, PAGE_NOACCESS, PAGE_READWRITE};
-
-        let page_size = region::page::size();
-        assert_le!(accessible_size, mapping_size);
-        assert_eq!(mapping_size & (page_size - 1), 0);
-        assert_eq!(accessible_size & (page_size - 1), 0);
-
-        // VirtualAlloc may return ERROR_INVALID_PARAMETER if the size is zero,
-        // so just special-case that.
-        if mapping_size == 0 {
-            return Ok(Self::new());
-        }
-
-        Ok(if accessible_size == mapping_size {
-            // Allocate a single read-write region at once.
-            let ptr = unsafe {
-                VirtualAlloc(
-                    ptr::null_mut(),
-                    mapping_size,
-                    MEM_RESERVE | MEM_COMMIT,
-                    PAGE_READWRITE,
-                )
-            };
-            if ptr.is_null() {
-                return Err(io::Error::last_os_error().to_string());
-            }
+            let ptr = unsafe { platform().allocate(mapping_size)? };
 
             Self {
-                ptr: ptr as usize,
+                ptr: ptr.as_ptr() as usize,
                 len: mapping_size,
             }
         } else {
             // Reserve the mapping size.
-            let ptr =
-                unsafe { VirtualAlloc(ptr::null_mut(), mapping_size, MEM_RESERVE, PAGE_NOACCESS) };
-            if ptr.is_null() {
-                return Err(io::Error::last_os_error().to_string());
-            }
+            let ptr = unsafe { platform().reserve(mapping_size)? };
 
             let mut result = Self {
-                ptr: ptr as usize,
+                ptr: ptr.as_ptr() as usize,
                 len: mapping_size,
             };
 
@@ -182,50 +100,61 @@ impl Mmap {
     /// Make the memory starting at `start` and extending for `len` bytes accessible.
     /// `start` and `len` must be native page-size multiples and describe a range within
     /// `self`'s reserved memory.
-    #[cfg(not(target_os = "windows"))]
     pub fn make_accessible(&mut self, start: usize, len: usize) -> Result<(), String> {
-        let page_size = region::page::size();
+        let page_size = platform().page_size();
         assert_eq!(start & (page_size - 1), 0);
         assert_eq!(len & (page_size - 1), 0);
         assert_lt!(len, self.len);
         assert_lt!(start, self.len - len);
 
         // Commit the accessible size.
-        let ptr = self.ptr as *const u8;
-        unsafe { region::protect(ptr.add(start), len, region::Protection::READ_WRITE) }
-            .map_err(|e| e.to_string())
+        unsafe { platform().commit(self.non_null_at(start), len) }
     }
 
-    /// Make the memory starting at `start` and extending for `len` bytes accessible.
+    /// Make the `len` bytes starting at `start` readable and executable, but
+    /// no longer writable. The range is extended to whole pages, and must be
+    /// within `self`'s accessible memory.
+    pub fn make_executable(&mut self, start: usize, len: usize) -> Result<(), String> {
+        assert_le!(len, self.len);
+        assert_le!(start, self.len - len);
+
+        unsafe { platform().make_executable(self.non_null_at(start), len) }
+    }
+
+    /// Map `len` bytes of `file`, starting at the file offset `start`, over
+    /// the memory starting at `start`, and make them accessible. Writes to
+    /// that memory are carried through to the file.
     /// `start` and `len` must be native page-size multiples and describe a range within
-    /// `self`'s reserved memory.
-    #[cfg(target_os = "windows")]
-    pub fn make_accessible(&mut self, start: usize, len: usize) -> Result<(), String> {
-        use winapi::ctypes::c_void;
-        use winapi::um::memoryapi::VirtualAlloc;
-        use winapi::um::winnt::{MEM_COMMIT, PAGE_READWRITE};
-        let page_size = region::page::size();
+    /// `self`'s reserved memory, and `file` must be at least `start + len` bytes long.
+    #[cfg(feature = "std")]
+    pub fn map_file(&mut self, file: &File, start: usize, len: usize) -> Result<(), String> {
+        let page_size = platform().page_size();
         assert_eq!(start & (page_size - 1), 0);
         assert_eq!(len & (page_size - 1), 0);
-        assert_lt!(len, self.len);
-        assert_lt!(start, self.len - len);
+        assert_le!(len, self.len);
+        assert_le!(start, self.len - len);
 
-        // Commit the accessible size.
-        let ptr = self.ptr as *const u8;
-        if unsafe {
-            VirtualAlloc(
-                ptr.add(start) as *mut c_void,
-                len,
-                MEM_COMMIT,
-                PAGE_READWRITE,
-            )
+        if len == 0 {
+            return Ok(());
         }
-        .is_null()
-        {
-            return Err(io::Error::last_os_error().to_string());
+
+        unsafe { platform().map_file(self.non_null_at(start), len, file, start as u64) }
+    }
+
+    /// Synchronously write back the `len` bytes of memory starting at `start`
+    /// to the file they were mapped from with [`Mmap::map_file`].
+    /// `start` must be a native page-size multiple.
+    pub fn flush(&self, start: usize, len: usize) -> Result<(), String> {
+        let page_size = platform().page_size();
+        assert_eq!(start & (page_size - 1), 0);
+        assert_le!(len, self.len);
+        assert_le!(start, self.len - len);
+
+        if len == 0 {
+            return Ok(());
         }
 
-        Ok(())
+        unsafe { platform().flush(self.non_null_at(start), len) }
     }
 
     /// Return the allocated memory as a slice of u8.
@@ -257,29 +186,23 @@ impl Mmap {
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
-}
 
-impl Drop for Mmap {
-    #[cfg(not(target_os = "windows"))]
-    fn drop(&mut self) {
-        if self.len != 0 {
-            let r = unsafe { libc::munmap(self.ptr as *mut libc::c_void, self.len) };
-            assert_eq!(r, 0, "munmap failed: {}", io::Error::last_os_error());
-        }
+    /// Return the allocated memory at `offset` as a non-null pointer.
+    fn non_null_at(&self, offset: usize) -> NonNull<u8> {
+        unsafe { NonNull::new_unchecked((self.ptr + offset) as *mut u8) }
     }
+}
 
-    #[cfg(target_os = "windows")]
+impl Drop for Mmap {
     fn drop(&mut self) {
         if self.len != 0 {
-            use winapi::ctypes::c_void;
-            use winapi::um::memoryapi::VirtualFree;
-            use winapi::um::winnt::MEM_RELEASE;
-            let r = unsafe { VirtualFree(self.ptr as *mut c_void, 0, MEM_RELEASE) };
-            assert_ne!(r, 0);
+            let r = unsafe { platform().release(self.non_null_at(0), self.len) };
+            assert!(r.is_ok(), "releasing the memory failed: {}", r.unwrap_err());
         }
     }
 }
 
+#[cfg(feature = "std")]
 impl MemoryUsage for Mmap {
     fn size_of_val(&self, tracker: &mut dyn MemoryUsageTracker) -> usize {
         tracker.track(self.as_ptr() as *const ());
@@ -4,14 +4,21 @@
 //! Data structure for representing WebAssembly modules in a
 //! `wasmer::Module`.
 
+use crate::lib::std::boxed::Box;
+use crate::lib::std::collections::HashMap;
+use crate::lib::std::fmt;
+use crate::lib::std::iter::ExactSizeIterator;
+use crate::lib::std::mem;
+use crate::lib::std::string::String;
+use crate::lib::std::string::ToString;
+use crate::lib::std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+use crate::lib::std::sync::Arc;
+use crate::lib::std::vec::Vec;
+#[cfg(feature = "std")]
 use indexmap::IndexMap;
+#[cfg(feature = "std")]
 use loupe::MemoryUsage;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::fmt;
-use std::iter::ExactSizeIterator;
-use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
-use std::sync::Arc;
 use wasmer_types::entity::{EntityRef, PrimaryMap};
 use wasmer_types::{
     CustomSectionIndex, DataIndex, ElemIndex, ExportIndex, ExportType, ExternType, FunctionIndex,
@@ -20,7 +27,12 @@ use wasmer_types::{
     TableIndex, TableInitializer, TableType,
 };
 
-#[derive(Debug, Clone, MemoryUsage)]
+/// Without the `std` feature, the maps use the hasher of `hashbrown`.
+#[cfg(not(feature = "std"))]
+type IndexMap<K, V> = indexmap::IndexMap<K, V, hashbrown::hash_map::DefaultHashBuilder>;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 pub struct ModuleId {
     id: usize,
 }
@@ -42,7 +54,8 @@ impl Default for ModuleId {
 
 /// A translated WebAssembly module, excluding the function bodies and
 /// memory initializers.
-#[derive(Debug, Clone, Serialize, Deserialize, MemoryUsage)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 pub struct ModuleInfo {
     /// A unique identifier (within this process) for this module.
     ///
@@ -103,6 +116,11 @@ pub struct ModuleInfo {
     /// The data for each CustomSection in the module.
     pub custom_sections_data: PrimaryMap<CustomSectionIndex, Arc<[u8]>>,
 
+    /// The offset of the contents of the code section in the wasm file.
+    ///
+    /// DWARF addresses are relative to this offset.
+    pub code_section_offset: Option<u64>,
+
     /// Number of imported functions in the module.
     pub num_imported_functions: usize,
 
@@ -122,8 +140,8 @@ impl ModuleInfo {
         Self {
             id: ModuleId::default(),
             name: None,
-            imports: IndexMap::new(),
-            exports: IndexMap::new(),
+            imports: IndexMap::default(),
+            exports: IndexMap::default(),
             start_function: None,
             table_initializers: Vec::new(),
             passive_elements: PrimaryMap::new(),
@@ -139,8 +157,9 @@ impl ModuleInfo {
             num_imported_tables: 0,
             num_imported_memories: 0,
             num_imported_globals: 0,
-            custom_sections: IndexMap::new(),
+            custom_sections: IndexMap::default(),
             custom_sections_data: PrimaryMap::new(),
+            code_section_offset: None,
         }
     }
 
@@ -239,6 +258,28 @@ impl ModuleInfo {
             })
     }
 
+    /// Add a custom section named `name`, replacing any custom section
+    /// with the same name.
+    pub fn add_custom_section(&mut self, name: &str, data: Arc<[u8]>) {
+        self.retain_custom_sections(|section_name, _| section_name != name);
+        let section_index = self.custom_sections_data.push(data);
+        self.custom_sections.insert(name.to_string(), section_index);
+    }
+
+    /// Keep only the custom sections for which `f` returns `true`, and
+    /// drop the data of the others.
+    pub fn retain_custom_sections(&mut self, mut f: impl FnMut(&str, &[u8]) -> bool) {
+        let sections = mem::take(&mut self.custom_sections);
+        let sections_data = mem::take(&mut self.custom_sections_data);
+        for (name, section_index) in sections {
+            let data = &sections_data[section_index];
+            if f(&name, data) {
+                let section_index = self.custom_sections_data.push(data.clone());
+                self.custom_sections.insert(name, section_index);
+            }
+        }
+    }
+
     /// Convert a `LocalFunctionIndex` into a `FunctionIndex`.
     pub fn func_index(&self, local_func: LocalFunctionIndex) -> FunctionIndex {
         FunctionIndex::new(self.num_imported_functions + local_func.index())
@@ -0,0 +1,138 @@
+//! A futex-like table of the threads waiting on addresses of a linear
+//! memory, used to implement the `wait` and `notify` atomic operations.
+
+use crate::lib::std::collections::{HashMap, VecDeque};
+use crate::lib::std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "std")]
+use crate::lib::std::sync::Condvar;
+use crate::lib::std::sync::{Arc, Mutex};
+use crate::lib::std::time::Duration;
+#[cfg(feature = "std")]
+use crate::lib::std::time::Instant;
+
+/// The outcome of waiting on an address of a memory.
+///
+/// The discriminants are the values returned by the `memory.atomic.wait`
+/// instructions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum WaitResult {
+    /// The waiting thread was woken by a notification.
+    Woken = 0,
+    /// The value at the address was not the expected one, so the thread
+    /// didn't wait.
+    Mismatch = 1,
+    /// The timeout expired before any notification.
+    TimedOut = 2,
+}
+
+#[derive(Debug, Default)]
+struct Waiter {
+    // Only modified while the `waiters` lock of the spot is held.
+    notified: AtomicBool,
+    #[cfg(feature = "std")]
+    condvar: Condvar,
+}
+
+/// The queues of threads waiting on the addresses of a memory.
+///
+/// Threads are woken in the order they started waiting.
+#[derive(Debug, Default)]
+pub struct ParkingSpot {
+    waiters: Mutex<HashMap<u64, VecDeque<Arc<Waiter>>>>,
+}
+
+impl ParkingSpot {
+    /// Block the current thread on `address` until it is notified or
+    /// `timeout` expires.
+    ///
+    /// `validate` is called before the thread starts waiting, while no
+    /// notification can be delivered. If it returns `false`, the thread
+    /// doesn't wait and [`WaitResult::Mismatch`] is returned.
+    #[cfg(feature = "std")]
+    pub fn wait(
+        &self,
+        address: u64,
+        validate: impl FnOnce() -> bool,
+        timeout: Option<Duration>,
+    ) -> WaitResult {
+        let mut waiters = self.waiters.lock().unwrap();
+        if !validate() {
+            return WaitResult::Mismatch;
+        }
+
+        let waiter = Arc::new(Waiter::default());
+        waiters
+            .entry(address)
+            .or_default()
+            .push_back(waiter.clone());
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            if waiter.notified.load(Ordering::Relaxed) {
+                return WaitResult::Woken;
+            }
+            match deadline {
+                None => waiters = waiter.condvar.wait(waiters).unwrap(),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        if let Some(queue) = waiters.get_mut(&address) {
+                            queue.retain(|other| !Arc::ptr_eq(other, &waiter));
+                            if queue.is_empty() {
+                                waiters.remove(&address);
+                            }
+                        }
+                        return WaitResult::TimedOut;
+                    }
+                    waiters = waiter
+                        .condvar
+                        .wait_timeout(waiters, deadline - now)
+                        .unwrap()
+                        .0;
+                }
+            }
+        }
+    }
+
+    /// Without the `std` feature, threads can't be blocked: once `validate`
+    /// returned `true`, [`WaitResult::TimedOut`] is returned at once.
+    #[cfg(not(feature = "std"))]
+    pub fn wait(
+        &self,
+        _address: u64,
+        validate: impl FnOnce() -> bool,
+        _timeout: Option<Duration>,
+    ) -> WaitResult {
+        let _waiters = self.waiters.lock().unwrap();
+        if !validate() {
+            return WaitResult::Mismatch;
+        }
+        WaitResult::TimedOut
+    }
+
+    /// Wake up to `count` threads waiting on `address`, and return the
+    /// number of threads woken.
+    pub fn notify(&self, address: u64, count: u32) -> u32 {
+        let mut waiters = self.waiters.lock().unwrap();
+        let queue = match waiters.get_mut(&address) {
+            Some(queue) => queue,
+            None => return 0,
+        };
+        let mut woken = 0;
+        while woken < count {
+            match queue.pop_front() {
+                Some(waiter) => {
+                    waiter.notified.store(true, Ordering::Relaxed);
+                    #[cfg(feature = "std")]
+                    waiter.condvar.notify_one();
+                    woken += 1;
+                }
+                None => break,
+            }
+        }
+        if queue.is_empty() {
+            waiters.remove(&address);
+        }
+        woken
+    }
+}
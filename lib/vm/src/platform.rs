@@ -0,0 +1,676 @@
+//! The services of the host system the runtime is built on.
+//!
+//! The runtime only reaches the operating system through a [`Platform`]:
+//! to reserve and commit the pages of the linear memories and of the
+//! code, to make the code executable, to map the files backing memories,
+//! to install the handlers that turn hardware faults into traps and to
+//! unwind the stack to the caller of the WebAssembly code when a trap
+//! happens. With the `std` feature, [`OsPlatform`] is used by default;
+//! hosts that want to provide these services themselves, e.g. an RTOS,
+//! install their own [`Platform`] with [`set_platform`] before creating
+//! any memory or instance. Hosts without virtual memory can use a
+//! [`StaticPlatform`], which allocates from a static buffer.
+//!
+//! Without the `std` feature, i.e. with the `core` one, there is no
+//! default platform: one must be installed before the runtime is used.
+
+use crate::lib::std::boxed::Box;
+#[cfg(feature = "std")]
+use crate::lib::std::cell::Cell;
+use crate::lib::std::fmt;
+#[cfg(feature = "std")]
+use crate::lib::std::fs::File;
+#[cfg(feature = "std")]
+use crate::lib::std::io;
+#[cfg(all(feature = "std", unix))]
+use crate::lib::std::os::unix::io::AsRawFd;
+use crate::lib::std::ptr::{self, NonNull};
+use crate::lib::std::string::String;
+use crate::lib::std::sync::atomic::{AtomicPtr, Ordering};
+use crate::lib::std::sync::Mutex;
+use crate::lib::std::vec::Vec;
+#[cfg(feature = "std")]
+use crate::trap::install_trap_handlers;
+
+#[cfg(feature = "std")]
+extern "C" {
+    fn RegisterSetjmp(
+        jmp_buf: *mut *const u8,
+        callback: extern "C" fn(*mut u8),
+        payload: *mut u8,
+    ) -> i32;
+    fn Unwind(jmp_buf: *const u8) -> !;
+}
+
+/// The services of the host system needed by the runtime.
+///
+/// The addresses and the sizes passed to the methods are always
+/// multiples of [`Platform::page_size`], except for
+/// [`Platform::make_executable`].
+pub trait Platform: fmt::Debug + Send + Sync {
+    /// The size of a page, the granularity of the page permissions.
+    fn page_size(&self) -> usize;
+
+    /// Reserve `size` bytes of address space, which are not accessible
+    /// until they are committed with [`Platform::commit`].
+    ///
+    /// # Safety
+    ///
+    /// `size` must be a non-zero multiple of the page size.
+    unsafe fn reserve(&self, size: usize) -> Result<NonNull<u8>, String>;
+
+    /// Make the `size` reserved bytes starting at `ptr` accessible for
+    /// reading and writing. They are zeroed the first time they are
+    /// committed.
+    ///
+    /// # Safety
+    ///
+    /// The range must be within memory returned by [`Platform::reserve`]
+    /// or [`Platform::allocate`].
+    unsafe fn commit(&self, ptr: NonNull<u8>, size: usize) -> Result<(), String>;
+
+    /// Release the `size` bytes starting at `ptr`, that were returned
+    /// by [`Platform::reserve`] or [`Platform::allocate`].
+    ///
+    /// # Safety
+    ///
+    /// The memory must not be used after it's released.
+    unsafe fn release(&self, ptr: NonNull<u8>, size: usize) -> Result<(), String>;
+
+    /// Make the `size` bytes starting at `ptr` readable and executable,
+    /// but no longer writable. The range is extended to whole pages.
+    ///
+    /// # Safety
+    ///
+    /// The range must be within committed memory, and no reference to
+    /// it must be used to write it afterwards.
+    unsafe fn make_executable(&self, ptr: NonNull<u8>, size: usize) -> Result<(), String>;
+
+    /// Install the handlers that turn the faults of WebAssembly code,
+    /// e.g. an out-of-bounds access or a stack overflow, into traps.
+    ///
+    /// It's called once, before the first instance is created. A
+    /// platform that can't catch faults must only run modules whose
+    /// accesses are checked explicitly, i.e. with dynamic memories.
+    fn init_traps(&self);
+
+    /// Prepare the current thread to handle the traps of the WebAssembly
+    /// code it calls, e.g. by installing an alternate signal stack.
+    ///
+    /// It's called every time WebAssembly code is called. Does nothing by
+    /// default.
+    fn init_thread(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Call `body` with `payload`, after saving the execution context in
+    /// a buffer whose address is stored in `jmp_buf`, like `setjmp` does.
+    ///
+    /// Returns 1 if `body` returned, or 0 if [`Platform::unwind`] was
+    /// called with the buffer while `body` was running.
+    ///
+    /// With the `std` feature, it's implemented with `setjmp` by default.
+    /// Otherwise, `body` is called without saving the context, which
+    /// means that traps can't be caught: see [`Platform::unwind`].
+    ///
+    /// # Safety
+    ///
+    /// The destructors of the values living on the stack between this
+    /// call and [`Platform::unwind`] are not run.
+    unsafe fn register_setjmp(
+        &self,
+        jmp_buf: *mut *const u8,
+        body: extern "C" fn(*mut u8),
+        payload: *mut u8,
+    ) -> i32 {
+        #[cfg(feature = "std")]
+        {
+            RegisterSetjmp(jmp_buf, body, payload)
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            let _ = jmp_buf;
+            body(payload);
+            1
+        }
+    }
+
+    /// Restore the execution context saved in `jmp_buf` by
+    /// [`Platform::register_setjmp`], like `longjmp` does, which then
+    /// returns 0.
+    ///
+    /// With the `std` feature, it's implemented with `longjmp` by
+    /// default. Otherwise, it panics by default, as no context was saved.
+    ///
+    /// # Safety
+    ///
+    /// `jmp_buf` must be a buffer saved by [`Platform::register_setjmp`]
+    /// that didn't return yet.
+    unsafe fn unwind(&self, jmp_buf: *const u8) -> ! {
+        #[cfg(feature = "std")]
+        {
+            Unwind(jmp_buf)
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            let _ = jmp_buf;
+            panic!("the platform can't unwind the stack to handle a trap")
+        }
+    }
+
+    /// Restore the guard page of the stack of the current thread after a
+    /// stack overflow was caught and unwound, so that the next one is
+    /// caught too. Does nothing by default.
+    fn reset_stack_guard(&self) {}
+
+    /// Returns the state of the WebAssembly calls of the current thread,
+    /// last set with [`Platform::set_trap_state`], or null.
+    ///
+    /// With the `std` feature, the state is kept in a thread local by
+    /// default. Otherwise it's kept in a global by default, so
+    /// WebAssembly code must only be called from one thread at a time;
+    /// platforms with threads keep it per thread instead.
+    fn trap_state(&self) -> *const u8 {
+        #[cfg(feature = "std")]
+        {
+            TRAP_STATE.with(|state| state.get())
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            TRAP_STATE.load(Ordering::Acquire)
+        }
+    }
+
+    /// Set the state returned by [`Platform::trap_state`] for the current
+    /// thread.
+    fn set_trap_state(&self, state: *const u8) {
+        #[cfg(feature = "std")]
+        {
+            TRAP_STATE.with(|cell| cell.set(state))
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            TRAP_STATE.store(state as *mut u8, Ordering::Release)
+        }
+    }
+
+    /// Map the `size` bytes of `file` starting at `offset` over the
+    /// `size` reserved bytes starting at `ptr`, and make them accessible
+    /// for reading and writing. Writes to the memory are carried through
+    /// to the file.
+    ///
+    /// Memories can't be backed by files by default.
+    ///
+    /// # Safety
+    ///
+    /// The range must be within memory returned by [`Platform::reserve`],
+    /// and `file` must be at least `offset + size` bytes long.
+    #[cfg(feature = "std")]
+    unsafe fn map_file(
+        &self,
+        ptr: NonNull<u8>,
+        size: usize,
+        file: &File,
+        offset: u64,
+    ) -> Result<(), String> {
+        let _ = (ptr, size, file, offset);
+        Err(String::from(
+            "memories can't be backed by files on this platform",
+        ))
+    }
+
+    /// Synchronously write back the `size` bytes starting at `ptr` to the
+    /// file they were mapped from with [`Platform::map_file`].
+    ///
+    /// # Safety
+    ///
+    /// The range must be within memory mapped with [`Platform::map_file`].
+    unsafe fn flush(&self, ptr: NonNull<u8>, size: usize) -> Result<(), String> {
+        let _ = (ptr, size);
+        Err(String::from(
+            "memories can't be backed by files on this platform",
+        ))
+    }
+
+    /// Reserve `size` bytes of address space and commit them at once.
+    ///
+    /// # Safety
+    ///
+    /// `size` must be a non-zero multiple of the page size.
+    unsafe fn allocate(&self, size: usize) -> Result<NonNull<u8>, String> {
+        let ptr = self.reserve(size)?;
+        if let Err(error) = self.commit(ptr, size) {
+            let _ = self.release(ptr, size);
+            return Err(error);
+        }
+        Ok(ptr)
+    }
+}
+
+#[cfg(feature = "std")]
+thread_local!(static TRAP_STATE: Cell<*const u8> = Cell::new(ptr::null()));
+
+#[cfg(not(feature = "std"))]
+static TRAP_STATE: AtomicPtr<u8> = AtomicPtr::new(ptr::null_mut());
+
+/// The [`Platform`] of the operating system the runtime is compiled
+/// for, built on `mmap` and signals on Unix, and on `VirtualAlloc` and
+/// vectored exception handlers on Windows.
+#[cfg(feature = "std")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct OsPlatform;
+
+#[cfg(feature = "std")]
+impl Platform for OsPlatform {
+    fn page_size(&self) -> usize {
+        region::page::size()
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    unsafe fn reserve(&self, size: usize) -> Result<NonNull<u8>, String> {
+        mmap_anonymous(size, libc::PROT_NONE)
+    }
+
+    #[cfg(target_os = "windows")]
+    unsafe fn reserve(&self, size: usize) -> Result<NonNull<u8>, String> {
+        use winapi::um::memoryapi::VirtualAlloc;
+        use winapi::um::winnt::{MEM_RESERVE, PAGE_NOACCESS};
+
+        NonNull::new(VirtualAlloc(ptr::null_mut(), size, MEM_RESERVE, PAGE_NOACCESS) as *mut u8)
+            .ok_or_else(|| io::Error::last_os_error().to_string())
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    unsafe fn commit(&self, ptr: NonNull<u8>, size: usize) -> Result<(), String> {
+        region::protect(ptr.as_ptr(), size, region::Protection::READ_WRITE)
+            .map_err(|e| e.to_string())
+    }
+
+    #[cfg(target_os = "windows")]
+    unsafe fn commit(&self, ptr: NonNull<u8>, size: usize) -> Result<(), String> {
+        use winapi::ctypes::c_void;
+        use winapi::um::memoryapi::VirtualAlloc;
+        use winapi::um::winnt::{MEM_COMMIT, PAGE_READWRITE};
+
+        if VirtualAlloc(
+            ptr.as_ptr() as *mut c_void,
+            size,
+            MEM_COMMIT,
+            PAGE_READWRITE,
+        )
+        .is_null()
+        {
+            return Err(io::Error::last_os_error().to_string());
+        }
+
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    unsafe fn release(&self, ptr: NonNull<u8>, size: usize) -> Result<(), String> {
+        if libc::munmap(ptr.as_ptr() as *mut libc::c_void, size) != 0 {
+            return Err(io::Error::last_os_error().to_string());
+        }
+
+        Ok(())
+    }
+
+    #[cfg(target_os = "windows")]
+    unsafe fn release(&self, ptr: NonNull<u8>, _size: usize) -> Result<(), String> {
+        use winapi::ctypes::c_void;
+        use winapi::um::memoryapi::VirtualFree;
+        use winapi::um::winnt::MEM_RELEASE;
+
+        if VirtualFree(ptr.as_ptr() as *mut c_void, 0, MEM_RELEASE) == 0 {
+            return Err(io::Error::last_os_error().to_string());
+        }
+
+        Ok(())
+    }
+
+    unsafe fn make_executable(&self, ptr: NonNull<u8>, size: usize) -> Result<(), String> {
+        region::protect(ptr.as_ptr(), size, region::Protection::READ_EXECUTE)
+            .map_err(|e| e.to_string())
+    }
+
+    fn init_traps(&self) {
+        unsafe { install_trap_handlers() }
+    }
+
+    #[cfg(unix)]
+    fn init_thread(&self) -> Result<(), String> {
+        crate::trap::setup_unix_sigaltstack()
+    }
+
+    #[cfg(target_os = "windows")]
+    fn reset_stack_guard(&self) {
+        extern "C" {
+            fn _resetstkoflw() -> winapi::ctypes::c_int;
+        }
+
+        // We need to restore guard page under stack to handle future stack overflows properly.
+        // https://docs.microsoft.com/en-us/cpp/c-runtime-library/reference/resetstkoflw?view=vs-2019
+        if unsafe { _resetstkoflw() } == 0 {
+            panic!("failed to restore stack guard page");
+        }
+    }
+
+    #[cfg(unix)]
+    unsafe fn map_file(
+        &self,
+        ptr: NonNull<u8>,
+        size: usize,
+        file: &File,
+        offset: u64,
+    ) -> Result<(), String> {
+        let ptr = libc::mmap(
+            ptr.as_ptr() as *mut libc::c_void,
+            size,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_SHARED | libc::MAP_FIXED,
+            file.as_raw_fd(),
+            offset as libc::off_t,
+        );
+        if ptr as isize == -1_isize {
+            return Err(io::Error::last_os_error().to_string());
+        }
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    unsafe fn flush(&self, ptr: NonNull<u8>, size: usize) -> Result<(), String> {
+        if libc::msync(ptr.as_ptr() as *mut libc::c_void, size, libc::MS_SYNC) != 0 {
+            return Err(io::Error::last_os_error().to_string());
+        }
+
+        Ok(())
+    }
+
+    // Allocate a single read-write region at once.
+    #[cfg(not(target_os = "windows"))]
+    unsafe fn allocate(&self, size: usize) -> Result<NonNull<u8>, String> {
+        mmap_anonymous(size, libc::PROT_READ | libc::PROT_WRITE)
+    }
+
+    // Allocate a single read-write region at once.
+    #[cfg(target_os = "windows")]
+    unsafe fn allocate(&self, size: usize) -> Result<NonNull<u8>, String> {
+        use winapi::um::memoryapi::VirtualAlloc;
+        use winapi::um::winnt::{MEM_COMMIT, MEM_RESERVE, PAGE_READWRITE};
+
+        NonNull::new(VirtualAlloc(
+            ptr::null_mut(),
+            size,
+            MEM_RESERVE | MEM_COMMIT,
+            PAGE_READWRITE,
+        ) as *mut u8)
+        .ok_or_else(|| io::Error::last_os_error().to_string())
+    }
+}
+
+#[cfg(all(feature = "std", not(target_os = "windows")))]
+unsafe fn mmap_anonymous(size: usize, protection: libc::c_int) -> Result<NonNull<u8>, String> {
+    let ptr = libc::mmap(
+        ptr::null_mut(),
+        size,
+        protection,
+        libc::MAP_PRIVATE | libc::MAP_ANON,
+        -1,
+        0,
+    );
+    if ptr as isize == -1_isize {
+        return Err(io::Error::last_os_error().to_string());
+    }
+
+    Ok(NonNull::new_unchecked(ptr as *mut u8))
+}
+
+/// A [`Platform`] for hosts without virtual memory, e.g. a
+/// microcontroller, that places the code and the linear memories in a
+/// buffer provided by the caller.
+///
+/// The memory is allocated from the buffer as soon as it's reserved,
+/// so the memories must not reserve more address space than they use:
+/// the store must use tunables without guard pages, such as
+/// `BaseTunables::without_virtual_memory`, so that every access is
+/// bounds-checked by the generated code. The code is not protected
+/// either, as there are no page permissions.
+///
+/// No handler is installed to turn faults into traps. A host that can
+/// catch them wraps this platform in its own [`Platform`] that installs
+/// its handlers in [`Platform::init_traps`].
+pub struct StaticPlatform {
+    base: NonNull<u8>,
+    len: usize,
+    page_size: usize,
+    // The free ranges of the buffer, as sorted and disjoint
+    // `(offset, len)` pairs.
+    free: Mutex<Vec<(usize, usize)>>,
+}
+
+/// This is correct because the buffer is only accessed through the
+/// ranges allocated by the platform.
+unsafe impl Send for StaticPlatform {}
+
+/// This is correct because the free ranges are protected by a mutex.
+unsafe impl Sync for StaticPlatform {}
+
+impl StaticPlatform {
+    /// Create a platform allocating from `buffer`, in pages of
+    /// `page_size` bytes. The beginning of the buffer is skipped if it's
+    /// not aligned to the page size.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `page_size` is not a power of two.
+    pub fn new(buffer: &'static mut [u8], page_size: usize) -> Self {
+        assert!(
+            page_size.is_power_of_two(),
+            "the page size must be a power of two"
+        );
+        let padding = buffer.as_ptr().align_offset(page_size).min(buffer.len());
+        let buffer = &mut buffer[padding..];
+        let len = buffer.len() & !(page_size - 1);
+
+        Self {
+            base: NonNull::new(buffer.as_mut_ptr()).unwrap(),
+            len,
+            page_size,
+            free: Mutex::new(if len == 0 { vec![] } else { vec![(0, len)] }),
+        }
+    }
+
+    /// Returns the number of bytes of the buffer that are not allocated.
+    pub fn available(&self) -> usize {
+        self.free.lock().unwrap().iter().map(|(_, len)| len).sum()
+    }
+
+    /// Returns the offset of `ptr` in the buffer, after checking that
+    /// the `size` bytes starting at `ptr` are within the buffer.
+    fn offset_of(&self, ptr: NonNull<u8>, size: usize) -> usize {
+        let offset = (ptr.as_ptr() as usize)
+            .checked_sub(self.base.as_ptr() as usize)
+            .expect("the memory is not allocated from the buffer");
+        assert!(
+            offset <= self.len && size <= self.len - offset,
+            "the memory is not allocated from the buffer"
+        );
+        offset
+    }
+}
+
+impl fmt::Debug for StaticPlatform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("StaticPlatform")
+            .field("len", &self.len)
+            .field("page_size", &self.page_size)
+            .field("available", &self.available())
+            .finish()
+    }
+}
+
+impl Platform for StaticPlatform {
+    fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    unsafe fn reserve(&self, size: usize) -> Result<NonNull<u8>, String> {
+        let mut free = self.free.lock().unwrap();
+        let index = free
+            .iter()
+            .position(|(_, len)| *len >= size)
+            .ok_or_else(|| {
+                format!(
+                    "cannot allocate {} bytes from the static buffer ({} bytes are free)",
+                    size,
+                    free.iter().map(|(_, len)| len).sum::<usize>()
+                )
+            })?;
+        let (offset, len) = free[index];
+        if len == size {
+            free.remove(index);
+        } else {
+            free[index] = (offset + size, len - size);
+        }
+
+        let ptr = self.base.as_ptr().add(offset);
+        // The released memory may have been used: the reserved
+        // memory is zeroed so that it's zeroed once committed.
+        ptr::write_bytes(ptr, 0, size);
+        Ok(NonNull::new_unchecked(ptr))
+    }
+
+    unsafe fn commit(&self, ptr: NonNull<u8>, size: usize) -> Result<(), String> {
+        // The memory is accessible as soon as it's reserved.
+        self.offset_of(ptr, size);
+        Ok(())
+    }
+
+    unsafe fn release(&self, ptr: NonNull<u8>, size: usize) -> Result<(), String> {
+        let offset = self.offset_of(ptr, size);
+        let mut free = self.free.lock().unwrap();
+        let index = free
+            .iter()
+            .position(|(free_offset, _)| *free_offset > offset)
+            .unwrap_or_else(|| free.len());
+        free.insert(index, (offset, size));
+
+        // Merge the range with the next and the previous ones, if they
+        // are contiguous.
+        if index + 1 < free.len() && offset + size == free[index + 1].0 {
+            free[index].1 += free.remove(index + 1).1;
+        }
+        if index > 0 && free[index - 1].0 + free[index - 1].1 == offset {
+            free[index - 1].1 += free.remove(index).1;
+        }
+
+        Ok(())
+    }
+
+    unsafe fn make_executable(&self, ptr: NonNull<u8>, size: usize) -> Result<(), String> {
+        // There are no page permissions to change.
+        self.offset_of(ptr, size);
+        Ok(())
+    }
+
+    fn init_traps(&self) {}
+}
+
+/// The installed platform, or null until the first use.
+static PLATFORM: AtomicPtr<&'static dyn Platform> = AtomicPtr::new(ptr::null_mut());
+
+/// Install the [`Platform`] used by the runtime.
+///
+/// It must be called before creating any memory or instance, as the
+/// platform can't change once it's used. If a platform is already in
+/// use, `platform` is returned as an error.
+pub fn set_platform(platform: &'static dyn Platform) -> Result<(), &'static dyn Platform> {
+    let new = Box::into_raw(Box::new(platform));
+    PLATFORM
+        .compare_exchange(ptr::null_mut(), new, Ordering::AcqRel, Ordering::Acquire)
+        .map(|_| ())
+        .map_err(|_| *unsafe { Box::from_raw(new) })
+}
+
+/// Returns the [`Platform`] used by the runtime, which is
+/// [`OsPlatform`] unless another one was installed with
+/// [`set_platform`].
+///
+/// # Panics
+///
+/// Without the `std` feature, panics if no platform was installed.
+pub fn platform() -> &'static dyn Platform {
+    let current = PLATFORM.load(Ordering::Acquire);
+    if !current.is_null() {
+        return unsafe { *current };
+    }
+
+    #[cfg(feature = "std")]
+    {
+        static OS_PLATFORM: OsPlatform = OsPlatform;
+        // Another thread may install a platform meanwhile, in which case it
+        // wins and is returned.
+        let _ = set_platform(&OS_PLATFORM);
+        unsafe { *PLATFORM.load(Ordering::Acquire) }
+    }
+
+    #[cfg(not(feature = "std"))]
+    panic!("no platform was installed with `set_platform`")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn os_platform_allocates_and_commits() {
+        let platform = OsPlatform;
+        let page_size = platform.page_size();
+        assert!(page_size.is_power_of_two());
+
+        unsafe {
+            let ptr = platform.reserve(2 * page_size).unwrap();
+            platform.commit(ptr, page_size).unwrap();
+            *ptr.as_ptr() = 42;
+            assert_eq!(*ptr.as_ptr(), 42);
+            assert_eq!(*ptr.as_ptr().add(page_size - 1), 0);
+            platform.release(ptr, 2 * page_size).unwrap();
+
+            let ptr = platform.allocate(page_size).unwrap();
+            *ptr.as_ptr().add(page_size - 1) = 42;
+            platform.release(ptr, page_size).unwrap();
+        }
+    }
+
+    #[test]
+    fn static_platform_allocates_from_the_buffer() {
+        let buffer = Box::leak(vec![1u8; 5 * 4096].into_boxed_slice());
+        let platform = StaticPlatform::new(buffer, 4096);
+        let available = platform.available();
+        assert!(available >= 4 * 4096);
+
+        unsafe {
+            let first = platform.allocate(4096).unwrap();
+            let second = platform.reserve(2 * 4096).unwrap();
+            platform.commit(second, 4096).unwrap();
+            assert_eq!(first.as_ptr().align_offset(4096), 0);
+            assert_eq!(second.as_ptr() as usize - first.as_ptr() as usize, 4096);
+            assert_eq!(*second.as_ptr().add(2 * 4096 - 1), 0);
+            assert!(platform.reserve(available).is_err());
+
+            platform.release(first, 4096).unwrap();
+            platform.release(second, 2 * 4096).unwrap();
+            assert_eq!(platform.available(), available);
+            assert_eq!(platform.reserve(available).unwrap(), first);
+        }
+    }
+
+    #[test]
+    fn the_platform_is_set_once() {
+        static OTHER: OsPlatform = OsPlatform;
+
+        let _ = platform();
+        assert!(set_platform(&OTHER).is_err());
+    }
+}
@@ -25,7 +25,10 @@ cfg_if::cfg_if! {
         extern "C" {
             pub fn __chkstk();
         }
-        /// The probestack for Windows when compiled with MSVC
+        /// The probestack for Windows when compiled with MSVC.
+        ///
+        /// On AArch64, `__chkstk` takes the frame size in `x15`, which is
+        /// how LLVM emits the stack probes for Windows.
         pub const PROBESTACK: unsafe extern "C" fn() = __chkstk;
     } else if #[cfg(all(target_os = "windows", target_env = "gnu"))] {
         extern "C" {
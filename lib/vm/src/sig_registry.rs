@@ -4,19 +4,22 @@
 //! Implement a registry of function signatures, for fast indirect call
 //! signature checking.
 
-use crate::vmcontext::VMSharedSignatureIndex;
+use crate::lib::std::collections::{hash_map, HashMap};
+use crate::lib::std::convert::TryFrom;
+use crate::lib::std::sync::RwLock;
+use crate::vmcontext::{VMSharedSignatureIndex, VMTrampoline};
+use crate::FunctionBodyPtr;
+#[cfg(feature = "std")]
 use loupe::MemoryUsage;
 use more_asserts::{assert_lt, debug_assert_lt};
-use std::collections::{hash_map, HashMap};
-use std::convert::TryFrom;
-use std::sync::RwLock;
 use wasmer_types::FunctionType;
 
 /// WebAssembly requires that the caller and callee signatures in an indirect
 /// call must match. To implement this efficiently, keep a registry of all
 /// signatures, shared by all instances, so that call sites can just do an
 /// index comparison.
-#[derive(Debug, MemoryUsage)]
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 pub struct SignatureRegistry {
     // This structure is stored in an `Engine` and is intended to be shared
     // across many instances. Ideally instances can themselves be sent across
@@ -26,10 +29,14 @@ pub struct SignatureRegistry {
     inner: RwLock<Inner>,
 }
 
-#[derive(Debug, Default, MemoryUsage)]
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 struct Inner {
     signature2index: HashMap<FunctionType, VMSharedSignatureIndex>,
     index2signature: HashMap<VMSharedSignatureIndex, FunctionType>,
+    #[cfg_attr(feature = "std", loupe(skip))]
+    index2trampoline: HashMap<VMSharedSignatureIndex, VMTrampoline>,
+    index2dynamic_function_trampoline: HashMap<VMSharedSignatureIndex, FunctionBodyPtr>,
 }
 
 impl SignatureRegistry {
@@ -51,7 +58,7 @@ impl SignatureRegistry {
                 // is reserved for VMSharedSignatureIndex::default().
                 debug_assert_lt!(
                     len,
-                    std::u32::MAX as usize,
+                    u32::MAX as usize,
                     "Invariant check: signature_hash.len() < std::u32::MAX"
                 );
                 let sig_id = VMSharedSignatureIndex::new(u32::try_from(len).unwrap());
@@ -74,4 +81,72 @@ impl SignatureRegistry {
             .get(&idx)
             .cloned()
     }
+
+    /// Register the trampoline used to call the functions with the
+    /// signature `idx` from the host.
+    ///
+    /// The first trampoline registered for a signature is kept.
+    ///
+    /// # Safety
+    ///
+    /// The trampoline must stay valid as long as this registry is alive,
+    /// and must implement the signature `idx`.
+    pub unsafe fn register_trampoline(
+        &self,
+        idx: VMSharedSignatureIndex,
+        trampoline: VMTrampoline,
+    ) {
+        self.inner
+            .write()
+            .unwrap()
+            .index2trampoline
+            .entry(idx)
+            .or_insert(trampoline);
+    }
+
+    /// Looks up the trampoline registered for a shared signature index.
+    pub fn lookup_trampoline(&self, idx: VMSharedSignatureIndex) -> Option<VMTrampoline> {
+        self.inner
+            .read()
+            .unwrap()
+            .index2trampoline
+            .get(&idx)
+            .cloned()
+    }
+
+    /// Register the trampoline used to call the dynamic host functions
+    /// with the signature `idx` from WebAssembly.
+    ///
+    /// The first trampoline registered for a signature is kept.
+    ///
+    /// # Safety
+    ///
+    /// The trampoline must stay valid as long as this registry is alive,
+    /// and must implement the signature `idx`.
+    pub unsafe fn register_dynamic_function_trampoline(
+        &self,
+        idx: VMSharedSignatureIndex,
+        trampoline: FunctionBodyPtr,
+    ) {
+        self.inner
+            .write()
+            .unwrap()
+            .index2dynamic_function_trampoline
+            .entry(idx)
+            .or_insert(trampoline);
+    }
+
+    /// Looks up the dynamic function trampoline registered for a shared
+    /// signature index.
+    pub fn lookup_dynamic_function_trampoline(
+        &self,
+        idx: VMSharedSignatureIndex,
+    ) -> Option<FunctionBodyPtr> {
+        self.inner
+            .read()
+            .unwrap()
+            .index2dynamic_function_trampoline
+            .get(&idx)
+            .cloned()
+    }
 }
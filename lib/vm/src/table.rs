@@ -5,20 +5,28 @@
 //!
 //! `Table` is to WebAssembly tables what `LinearMemory` is to WebAssembly linear memories.
 
+use crate::lib::std::borrow::{Borrow, BorrowMut};
+use crate::lib::std::boxed::Box;
+use crate::lib::std::cell::UnsafeCell;
+use crate::lib::std::convert::TryFrom;
+use crate::lib::std::fmt;
+use crate::lib::std::ptr::NonNull;
+use crate::lib::std::string::String;
+use crate::lib::std::string::ToString;
+use crate::lib::std::sync::Mutex;
+use crate::lib::std::vec::Vec;
+#[cfg(not(feature = "std"))]
+use crate::lib::MemoryUsage;
 use crate::trap::{Trap, TrapCode};
 use crate::vmcontext::{VMCallerCheckedAnyfunc, VMTableDefinition};
+#[cfg(feature = "std")]
 use loupe::MemoryUsage;
 use serde::{Deserialize, Serialize};
-use std::borrow::{Borrow, BorrowMut};
-use std::cell::UnsafeCell;
-use std::convert::TryFrom;
-use std::fmt;
-use std::ptr::NonNull;
-use std::sync::Mutex;
 use wasmer_types::{TableType, Type as ValType};
 
 /// Implementation styles for WebAssembly tables.
-#[derive(Debug, Clone, Hash, Serialize, Deserialize, MemoryUsage)]
+#[derive(Debug, Clone, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 pub enum TableStyle {
     /// Signatures are stored in the table and checked in the caller.
     CallerChecksSignature,
@@ -101,10 +109,55 @@ pub trait Table: fmt::Debug + Send + Sync + MemoryUsage {
 
         Ok(())
     }
+
+    /// Set the `len` elements of `self[index..]` to `item`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error, without modifying the table, if the range is out
+    /// of bounds of the table.
+    fn fill(&self, index: u32, item: VMCallerCheckedAnyfunc, len: u32) -> Result<(), Trap> {
+        // https://webassembly.github.io/bulk-memory-operations/core/exec/instructions.html#exec-table-fill
+
+        if index.checked_add(len).map_or(true, |n| n > self.size()) {
+            return Err(Trap::new_from_runtime(TrapCode::TableAccessOutOfBounds));
+        }
+
+        for i in index..index + len {
+            self.set(i, item.clone())?;
+        }
+
+        Ok(())
+    }
+
+    /// Copy `items` into `self[index..]`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error, without modifying the table, if the range is out
+    /// of bounds of the table.
+    fn init(&self, index: u32, items: &[VMCallerCheckedAnyfunc]) -> Result<(), Trap> {
+        // https://webassembly.github.io/bulk-memory-operations/core/exec/instructions.html#exec-table-init
+
+        if u32::try_from(items.len())
+            .ok()
+            .and_then(|len| index.checked_add(len))
+            .map_or(true, |n| n > self.size())
+        {
+            return Err(Trap::new_from_runtime(TrapCode::TableAccessOutOfBounds));
+        }
+
+        for (i, item) in (index..).zip(items) {
+            self.set(i, item.clone())?;
+        }
+
+        Ok(())
+    }
 }
 
 /// A table instance.
-#[derive(Debug, MemoryUsage)]
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 pub struct LinearTable {
     // TODO: we can remove the mutex by using atomic swaps and preallocating the max table size
     vec: Mutex<Vec<VMCallerCheckedAnyfunc>>,
@@ -118,7 +171,8 @@ pub struct LinearTable {
 
 /// A type to help manage who is responsible for the backing table of the
 /// `VMTableDefinition`.
-#[derive(Debug, MemoryUsage)]
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 enum VMTableDefinitionOwnership {
     /// The `VMTableDefinition` is owned by the `Instance` and we should use
     /// its table. This is how a local table that's exported should be stored.
@@ -299,4 +353,40 @@ impl Table for LinearTable {
         let _vec_guard = self.vec.lock().unwrap();
         unsafe { self.get_vm_table_definition() }
     }
+
+    /// Set the `len` elements of `self[index..]` to `item`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error, without modifying the table, if the range is out
+    /// of bounds of the table.
+    fn fill(&self, index: u32, item: VMCallerCheckedAnyfunc, len: u32) -> Result<(), Trap> {
+        let mut vec_guard = self.vec.lock().unwrap();
+        let vec = vec_guard.borrow_mut();
+        let slots = index
+            .checked_add(len)
+            .and_then(|end| vec.get_mut(index as usize..end as usize))
+            .ok_or_else(|| Trap::new_from_runtime(TrapCode::TableAccessOutOfBounds))?;
+        for slot in slots {
+            *slot = item.clone();
+        }
+        Ok(())
+    }
+
+    /// Copy `items` into `self[index..]`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error, without modifying the table, if the range is out
+    /// of bounds of the table.
+    fn init(&self, index: u32, items: &[VMCallerCheckedAnyfunc]) -> Result<(), Trap> {
+        let mut vec_guard = self.vec.lock().unwrap();
+        let vec = vec_guard.borrow_mut();
+        let slots = (index as usize)
+            .checked_add(items.len())
+            .and_then(|end| vec.get_mut(index as usize..end))
+            .ok_or_else(|| Trap::new_from_runtime(TrapCode::TableAccessOutOfBounds))?;
+        slots.clone_from_slice(items);
+        Ok(())
+    }
 }
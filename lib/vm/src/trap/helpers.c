@@ -11,6 +11,12 @@ int RegisterSetjmp(
   if (setjmp(buf) != 0) {
     return 0;
   }
+#if defined(_MSC_VER) && defined(_M_ARM64)
+  // `longjmp` unwinds the stack with SEH on Windows, which needs unwind
+  // information for every frame in between. It isn't emitted for the wasm
+  // code on ARM64, so skip the unwinding like `longjmp` does on Unix.
+  ((_JUMP_BUFFER *)&buf)->Frame = 0;
+#endif
   *buf_storage = &buf;
   body(payload);
   return 1;
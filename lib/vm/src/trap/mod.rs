@@ -7,8 +7,16 @@ mod trapcode;
 mod traphandlers;
 
 pub use trapcode::TrapCode;
+pub use traphandlers::init_traps;
+#[cfg(feature = "std")]
+pub(crate) use traphandlers::install_trap_handlers;
+#[cfg(feature = "std")]
+pub use traphandlers::resume_panic;
+#[cfg(all(feature = "std", unix))]
+pub(crate) use traphandlers::setup_unix_sigaltstack;
 pub use traphandlers::{
     catch_traps, catch_traps_with_result, raise_lib_trap, raise_user_trap, wasmer_call_trampoline,
     Trap,
 };
-pub use traphandlers::{init_traps, resume_panic};
+#[cfg(not(feature = "std"))]
+pub use traphandlers::{handle_fault, Backtrace};
@@ -5,14 +5,18 @@
 
 use core::fmt::{self, Display, Formatter};
 use core::str::FromStr;
+#[cfg(feature = "std")]
 use loupe::MemoryUsage;
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
 use thiserror::Error;
 
 /// A trap code describing the reason for a trap.
 ///
 /// All trap instructions have an explicit trap code.
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, Serialize, Deserialize, Error, MemoryUsage)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "std", derive(Error))]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 #[repr(u32)]
 pub enum TrapCode {
     /// The current stack space was exhausted.
@@ -74,6 +78,9 @@ pub enum TrapCode {
 
     /// A trap indicating that the runtime was unable to allocate sufficient memory.
     VMOutOfMemory = 15,
+
+    /// A `memory.atomic.wait` was executed on a memory that is not shared.
+    AtomicWaitNonSharedMemory = 16,
     // /// A user-defined trap code.
     // User(u16),
 }
@@ -98,6 +105,7 @@ impl TrapCode {
             Self::Interrupt => "interrupt",
             Self::UnalignedAtomic => "unaligned atomic access",
             Self::VMOutOfMemory => "out of memory",
+            Self::AtomicWaitNonSharedMemory => "atomic wait on non-shared memory",
             // Self::User(_) => unreachable!(),
         }
     }
@@ -122,6 +130,7 @@ impl Display for TrapCode {
             Self::Interrupt => "interrupt",
             Self::UnalignedAtomic => "unalign_atom",
             Self::VMOutOfMemory => "oom",
+            Self::AtomicWaitNonSharedMemory => "wait_nonshared",
             // User(x) => return write!(f, "user{}", x),
         };
         f.write_str(identifier)
@@ -150,6 +159,7 @@ impl FromStr for TrapCode {
             "interrupt" => Ok(Interrupt),
             "unalign_atom" => Ok(UnalignedAtomic),
             "oom" => Ok(VMOutOfMemory),
+            "wait_nonshared" => Ok(AtomicWaitNonSharedMemory),
             // _ if s.starts_with("user") => s[4..].parse().map(User).map_err(|_| ()),
             _ => Err(()),
         }
@@ -161,7 +171,7 @@ mod tests {
     use super::*;
 
     // Everything but user-defined codes.
-    const CODES: [TrapCode; 15] = [
+    const CODES: [TrapCode; 16] = [
         TrapCode::StackOverflow,
         TrapCode::HeapSetterOutOfBounds,
         TrapCode::HeapAccessOutOfBounds,
@@ -177,6 +187,7 @@ mod tests {
         TrapCode::UnreachableCodeReached,
         TrapCode::Interrupt,
         TrapCode::UnalignedAtomic,
+        TrapCode::AtomicWaitNonSharedMemory,
     ];
 
     #[test]
@@ -6,35 +6,31 @@
 
 use super::trapcode::TrapCode;
 use crate::instance::{Instance, SignalHandler};
+#[cfg(feature = "std")]
+use crate::lib::std::any::Any;
+use crate::lib::std::boxed::Box;
+use crate::lib::std::cell::Cell;
+use crate::lib::std::error::Error;
+#[cfg(feature = "std")]
+use crate::lib::std::io;
+use crate::lib::std::mem;
+use crate::lib::std::ptr;
+use crate::lib::std::sync::Once;
+use crate::platform::platform;
 use crate::vmcontext::{VMFunctionBody, VMFunctionEnvironment, VMTrampoline};
+#[cfg(feature = "std")]
 use backtrace::Backtrace;
-use std::any::Any;
-use std::cell::Cell;
-use std::error::Error;
-use std::io;
-use std::mem;
-use std::ptr;
-use std::sync::Once;
-
-extern "C" {
-    fn RegisterSetjmp(
-        jmp_buf: *mut *const u8,
-        callback: extern "C" fn(*mut u8),
-        payload: *mut u8,
-    ) -> i32;
-    fn Unwind(jmp_buf: *const u8) -> !;
-}
 
 cfg_if::cfg_if! {
-    if #[cfg(unix)] {
-        use std::mem::MaybeUninit;
+    if #[cfg(all(feature = "std", unix))] {
+        use crate::lib::std::mem::MaybeUninit;
 
         static mut PREV_SIGSEGV: MaybeUninit<libc::sigaction> = MaybeUninit::uninit();
         static mut PREV_SIGBUS: MaybeUninit<libc::sigaction> = MaybeUninit::uninit();
         static mut PREV_SIGILL: MaybeUninit<libc::sigaction> = MaybeUninit::uninit();
         static mut PREV_SIGFPE: MaybeUninit<libc::sigaction> = MaybeUninit::uninit();
 
-        unsafe fn platform_init() {
+        pub(crate) unsafe fn install_trap_handlers() {
             let register = |slot: &mut MaybeUninit<libc::sigaction>, signal: i32| {
                 let mut handler: libc::sigaction = mem::zeroed();
                 // The flags here are relatively careful, and they are...
@@ -160,7 +156,7 @@ cfg_if::cfg_if! {
                 } else if jmp_buf as usize == 1 {
                     true
                 } else {
-                    Unwind(jmp_buf)
+                    platform().unwind(jmp_buf)
                 }
             });
 
@@ -209,7 +205,7 @@ cfg_if::cfg_if! {
                     let cx = &*(cx as *const libc::ucontext_t);
                     (*cx.uc_mcontext).__ss.__rip as *const u8
                 } else if #[cfg(all(target_os = "macos", target_arch = "aarch64"))] {
-                    use std::mem;
+                    use crate::lib::std::mem;
                     // TODO: This should be integrated into rust/libc
                     // Related issue: https://github.com/rust-lang/libc/issues/1977
                     #[allow(non_camel_case_types)]
@@ -276,13 +272,14 @@ cfg_if::cfg_if! {
                 }
             }
         }
-    } else if #[cfg(target_os = "windows")] {
+    } else if #[cfg(all(feature = "std", target_os = "windows"))] {
+        use winapi::shared::minwindef::DWORD;
         use winapi::um::errhandlingapi::*;
         use winapi::um::winnt::*;
         use winapi::um::minwinbase::*;
         use winapi::vc::excpt::*;
 
-        unsafe fn platform_init() {
+        pub(crate) unsafe fn install_trap_handlers() {
             // our trap handler needs to go first, so that we can recover from
             // wasm faults and continue execution, so pass `1` as a true value
             // here.
@@ -302,7 +299,8 @@ cfg_if::cfg_if! {
                 record.ExceptionCode != EXCEPTION_ILLEGAL_INSTRUCTION &&
                 record.ExceptionCode != EXCEPTION_STACK_OVERFLOW &&
                 record.ExceptionCode != EXCEPTION_INT_DIVIDE_BY_ZERO &&
-                record.ExceptionCode != EXCEPTION_INT_OVERFLOW
+                record.ExceptionCode != EXCEPTION_INT_OVERFLOW &&
+                !is_arch_trap(record.ExceptionCode)
             {
                 return EXCEPTION_CONTINUE_SEARCH;
             }
@@ -327,7 +325,7 @@ cfg_if::cfg_if! {
                     None => return EXCEPTION_CONTINUE_SEARCH,
                 };
                 let jmp_buf = info.handle_trap(
-                    (*(*exception_info).ContextRecord).Rip as *const u8,
+                    get_pc(&*(*exception_info).ContextRecord),
                     record.ExceptionCode == EXCEPTION_STACK_OVERFLOW,
                     // TODO: fix the signal trap associated to memory access in Windows
                     None,
@@ -338,10 +336,28 @@ cfg_if::cfg_if! {
                 } else if jmp_buf as usize == 1 {
                     EXCEPTION_CONTINUE_EXECUTION
                 } else {
-                    Unwind(jmp_buf)
+                    platform().unwind(jmp_buf)
                 }
             })
         }
+
+        /// On AArch64, the `brk` instructions emitted by LLVM for traps are
+        /// reported as breakpoints.
+        fn is_arch_trap(code: DWORD) -> bool {
+            cfg!(target_arch = "aarch64") && code == EXCEPTION_BREAKPOINT
+        }
+
+        fn get_pc(cx: &CONTEXT) -> *const u8 {
+            cfg_if::cfg_if! {
+                if #[cfg(target_arch = "x86_64")] {
+                    cx.Rip as *const u8
+                } else if #[cfg(target_arch = "aarch64")] {
+                    cx.Pc as *const u8
+                } else {
+                    compile_error!("unsupported platform");
+                }
+            }
+        }
     }
 }
 
@@ -356,13 +372,7 @@ cfg_if::cfg_if! {
 /// times, having no effect after the first call.
 pub fn init_traps() {
     static INIT: Once = Once::new();
-    INIT.call_once(real_init);
-}
-
-fn real_init() {
-    unsafe {
-        platform_init();
-    }
+    INIT.call_once(|| platform().init_traps());
 }
 
 /// Raises a user-defined trap immediately.
@@ -401,26 +411,60 @@ pub unsafe fn raise_lib_trap(trap: Trap) -> ! {
 ///
 /// Only safe to call when wasm code is on the stack, aka `wasmer_call` or
 /// `wasmer_call_trampoline` must have been previously called.
+#[cfg(feature = "std")]
 pub unsafe fn resume_panic(payload: Box<dyn Any + Send>) -> ! {
     tls::with(|info| info.unwrap().unwind_with(UnwindReason::Panic(payload)))
 }
 
-#[cfg(target_os = "windows")]
-fn reset_guard_page() {
-    extern "C" {
-        fn _resetstkoflw() -> winapi::ctypes::c_int;
-    }
+/// Handles a fault of WebAssembly code caught by the handlers that a
+/// [`Platform`](crate::Platform) installs in
+/// [`Platform::init_traps`](crate::Platform::init_traps).
+///
+/// `pc` is the program counter of the faulting instruction, and
+/// `signal_trap` the trap code the fault corresponds to, if it's known.
+///
+/// If the fault happened in WebAssembly code, it's turned into a trap and
+/// the stack is unwound to the caller of the WebAssembly code with
+/// [`Platform::unwind`](crate::Platform::unwind), so this doesn't return.
+/// Otherwise, returns `true` if the custom signal handler of an instance
+/// handled the fault and the execution can resume, or `false` if the
+/// fault isn't handled.
+///
+/// # Safety
+///
+/// Must only be called by the fault handler, on the thread that faulted.
+#[cfg(not(feature = "std"))]
+pub unsafe fn handle_fault(pc: *const u8, signal_trap: Option<TrapCode>) -> bool {
+    tls::with(|info| {
+        let info = match info {
+            Some(info) => info,
+            None => return false,
+        };
+        let jmp_buf = info.handle_trap(pc, false, signal_trap, |handler| handler(pc));
+        if jmp_buf.is_null() {
+            false
+        } else if jmp_buf as usize == 1 {
+            true
+        } else {
+            platform().unwind(jmp_buf)
+        }
+    })
+}
 
-    // We need to restore guard page under stack to handle future stack overflows properly.
-    // https://docs.microsoft.com/en-us/cpp/c-runtime-library/reference/resetstkoflw?view=vs-2019
-    if unsafe { _resetstkoflw() } == 0 {
-        panic!("failed to restore stack guard page");
+/// Without the `std` feature, no native backtrace is captured when a trap
+/// happens: this type stands in for `backtrace::Backtrace`.
+#[cfg(not(feature = "std"))]
+#[derive(Debug, Clone, Default)]
+pub struct Backtrace(());
+
+#[cfg(not(feature = "std"))]
+impl Backtrace {
+    /// Returns an empty backtrace.
+    pub fn new_unresolved() -> Self {
+        Self(())
     }
 }
 
-#[cfg(not(target_os = "windows"))]
-fn reset_guard_page() {}
-
 /// Stores trace message with backtrace.
 #[derive(Debug)]
 pub enum Trap {
@@ -517,12 +561,14 @@ pub unsafe fn catch_traps<F>(vmctx: VMFunctionEnvironment, mut closure: F) -> Re
 where
     F: FnMut(),
 {
-    // Ensure that we have our sigaltstack installed.
-    #[cfg(unix)]
-    setup_unix_sigaltstack()?;
+    // Ensure that the thread is ready to handle traps, e.g. that we have
+    // our sigaltstack installed.
+    platform()
+        .init_thread()
+        .map_err(|_| Trap::new_from_runtime(TrapCode::VMOutOfMemory))?;
 
     return CallThreadState::new(vmctx).with(|cx| {
-        RegisterSetjmp(
+        platform().register_setjmp(
             cx.jmp_buf.as_ptr(),
             call_closure::<F>,
             &mut closure as *mut F as *mut u8,
@@ -573,6 +619,7 @@ pub struct CallThreadState {
 
 enum UnwindReason {
     None,
+    #[cfg(feature = "std")]
     Panic(Box<dyn Any + Send>),
     UserTrap(Box<dyn Error + Send + Sync>),
     LibTrap(Trap),
@@ -617,9 +664,10 @@ impl CallThreadState {
                     debug_assert_eq!(ret, 0);
                     Err(Trap::new_from_wasm(pc, backtrace, signal_trap))
                 }
+                #[cfg(feature = "std")]
                 UnwindReason::Panic(panic) => {
                     debug_assert_eq!(ret, 0);
-                    std::panic::resume_unwind(panic)
+                    crate::lib::std::panic::resume_unwind(panic)
                 }
             }
         })
@@ -646,7 +694,7 @@ impl CallThreadState {
     fn unwind_with(&self, reason: UnwindReason) -> ! {
         self.unwind.replace(reason);
         unsafe {
-            Unwind(self.jmp_buf.get());
+            platform().unwind(self.jmp_buf.get());
         }
     }
 
@@ -727,7 +775,7 @@ impl CallThreadState {
 impl Drop for CallThreadState {
     fn drop(&mut self) {
         if self.reset_guard_page.get() {
-            reset_guard_page();
+            platform().reset_stack_guard();
         }
     }
 }
@@ -736,39 +784,33 @@ impl Drop for CallThreadState {
 // calls in wasm. The WebAssembly code is called from C++ and then a trap may
 // happen which requires us to read some contextual state to figure out what to
 // do with the trap. This `tls` module is used to persist that information from
-// the caller to the trap site.
+// the caller to the trap site, in the slot provided by the platform.
 mod tls {
     use super::CallThreadState;
-    use std::cell::Cell;
-    use std::ptr;
-
-    thread_local!(static PTR: Cell<*const CallThreadState> = Cell::new(ptr::null()));
+    use crate::platform::platform;
 
     /// Configures thread local state such that for the duration of the
     /// execution of `closure` any call to `with` will yield `ptr`, unless this
     /// is recursively called again.
     pub fn set<R>(ptr: &CallThreadState, closure: impl FnOnce() -> R) -> R {
-        struct Reset<'a, T: Copy>(&'a Cell<T>, T);
+        struct Reset(*const u8);
 
-        impl<T: Copy> Drop for Reset<'_, T> {
+        impl Drop for Reset {
             fn drop(&mut self) {
-                self.0.set(self.1);
+                platform().set_trap_state(self.0);
             }
         }
 
-        PTR.with(|p| {
-            let _r = Reset(p, p.replace(ptr));
-            closure()
-        })
+        let _r = Reset(platform().trap_state());
+        platform().set_trap_state(ptr as *const CallThreadState as *const u8);
+        closure()
     }
 
     /// Returns the last pointer configured with `set` above. Panics if `set`
     /// has not been previously called.
     pub fn with<R>(closure: impl FnOnce(Option<&CallThreadState>) -> R) -> R {
-        PTR.with(|ptr| {
-            let p = ptr.get();
-            unsafe { closure(if p.is_null() { None } else { Some(&*p) }) }
-        })
+        let p = platform().trap_state() as *const CallThreadState;
+        unsafe { closure(if p.is_null() { None } else { Some(&*p) }) }
     }
 }
 
@@ -778,10 +820,10 @@ mod tls {
 /// always large enough for our signal handling code. Override it by creating
 /// and registering our own alternate stack that is large enough and has a guard
 /// page.
-#[cfg(unix)]
-fn setup_unix_sigaltstack() -> Result<(), Trap> {
-    use std::cell::RefCell;
-    use std::ptr::null_mut;
+#[cfg(all(feature = "std", unix))]
+pub(crate) fn setup_unix_sigaltstack() -> Result<(), String> {
+    use crate::lib::std::cell::RefCell;
+    use crate::lib::std::ptr::null_mut;
 
     thread_local! {
         /// Thread-local state is lazy-initialized on the first time it's used,
@@ -834,7 +876,7 @@ fn setup_unix_sigaltstack() -> Result<(), Trap> {
             0,
         );
         if ptr == libc::MAP_FAILED {
-            return Err(Trap::new_from_runtime(TrapCode::VMOutOfMemory));
+            return Err(io::Error::last_os_error().to_string());
         }
 
         // Prepare the stack with readable/writable memory and then register it
@@ -6,17 +6,20 @@
 
 use crate::global::Global;
 use crate::instance::Instance;
+use crate::lib::std::any::Any;
+use crate::lib::std::convert::TryFrom;
+use crate::lib::std::ffi;
+use crate::lib::std::fmt;
+#[cfg(feature = "std")]
+use crate::lib::std::mem;
+use crate::lib::std::ptr::{self, NonNull};
+use crate::lib::std::sync::Arc;
+use crate::lib::std::u32;
 use crate::memory::Memory;
 use crate::table::Table;
 use crate::trap::{Trap, TrapCode};
+#[cfg(feature = "std")]
 use loupe::{MemoryUsage, MemoryUsageTracker, POINTER_BYTE_SIZE};
-use std::any::Any;
-use std::convert::TryFrom;
-use std::fmt;
-use std::mem;
-use std::ptr::{self, NonNull};
-use std::sync::Arc;
-use std::u32;
 
 /// Union representing the first parameter passed when calling a function.
 ///
@@ -27,7 +30,7 @@ pub union VMFunctionEnvironment {
     /// Wasm functions take a pointer to [`VMContext`].
     pub vmctx: *mut VMContext,
     /// Host functions can have custom environments.
-    pub host_env: *mut std::ffi::c_void,
+    pub host_env: *mut ffi::c_void,
 }
 
 impl VMFunctionEnvironment {
@@ -37,20 +40,21 @@ impl VMFunctionEnvironment {
     }
 }
 
-impl std::fmt::Debug for VMFunctionEnvironment {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl fmt::Debug for VMFunctionEnvironment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("VMFunctionEnvironment")
             .field("vmctx_or_hostenv", unsafe { &self.host_env })
             .finish()
     }
 }
 
-impl std::cmp::PartialEq for VMFunctionEnvironment {
+impl PartialEq for VMFunctionEnvironment {
     fn eq(&self, rhs: &Self) -> bool {
         unsafe { self.host_env as usize == rhs.host_env as usize }
     }
 }
 
+#[cfg(feature = "std")]
 impl MemoryUsage for VMFunctionEnvironment {
     fn size_of_val(&self, _: &mut dyn MemoryUsageTracker) -> usize {
         mem::size_of_val(self)
@@ -58,7 +62,8 @@ impl MemoryUsage for VMFunctionEnvironment {
 }
 
 /// An imported function.
-#[derive(Debug, Copy, Clone, MemoryUsage)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 #[repr(C)]
 pub struct VMFunctionImport {
     /// A pointer to the imported function body.
@@ -71,9 +76,9 @@ pub struct VMFunctionImport {
 #[cfg(test)]
 mod test_vmfunction_import {
     use super::VMFunctionImport;
+    use crate::lib::std::mem::size_of;
     use crate::{ModuleInfo, VMOffsets};
     use memoffset::offset_of;
-    use std::mem::size_of;
 
     #[test]
     fn check_vmfunction_import_offsets() {
@@ -133,9 +138,9 @@ impl<T: Sized + Clone + Send + Sync> Clone for VMDynamicFunctionContext<T> {
 #[cfg(test)]
 mod test_vmdynamicfunction_import_context {
     use super::VMDynamicFunctionContext;
+    use crate::lib::std::mem::size_of;
     use crate::{ModuleInfo, VMOffsets};
     use memoffset::offset_of;
-    use std::mem::size_of;
 
     #[test]
     fn check_vmdynamicfunction_import_context_offsets() {
@@ -166,7 +171,7 @@ pub struct VMFunctionBody(u8);
 #[cfg(test)]
 mod test_vmfunction_body {
     use super::VMFunctionBody;
-    use std::mem::size_of;
+    use crate::lib::std::mem::size_of;
 
     #[test]
     fn check_vmfunction_body_offsets() {
@@ -175,7 +180,8 @@ mod test_vmfunction_body {
 }
 
 /// A function kind is a calling convention into and out of wasm code.
-#[derive(Debug, Copy, Clone, PartialEq, MemoryUsage)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 #[repr(C)]
 pub enum VMFunctionKind {
     /// A static function has the native signature:
@@ -196,7 +202,8 @@ pub enum VMFunctionKind {
 
 /// The fields compiled code needs to access to utilize a WebAssembly table
 /// imported from another instance.
-#[derive(Debug, Clone, MemoryUsage)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 #[repr(C)]
 pub struct VMTableImport {
     /// A pointer to the imported table description.
@@ -209,9 +216,9 @@ pub struct VMTableImport {
 #[cfg(test)]
 mod test_vmtable_import {
     use super::VMTableImport;
+    use crate::lib::std::mem::size_of;
     use crate::{ModuleInfo, VMOffsets};
     use memoffset::offset_of;
-    use std::mem::size_of;
 
     #[test]
     fn check_vmtable_import_offsets() {
@@ -234,7 +241,8 @@ mod test_vmtable_import {
 
 /// The fields compiled code needs to access to utilize a WebAssembly linear
 /// memory imported from another instance.
-#[derive(Debug, Clone, MemoryUsage)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 #[repr(C)]
 pub struct VMMemoryImport {
     /// A pointer to the imported memory description.
@@ -247,9 +255,9 @@ pub struct VMMemoryImport {
 #[cfg(test)]
 mod test_vmmemory_import {
     use super::VMMemoryImport;
+    use crate::lib::std::mem::size_of;
     use crate::{ModuleInfo, VMOffsets};
     use memoffset::offset_of;
-    use std::mem::size_of;
 
     #[test]
     fn check_vmmemory_import_offsets() {
@@ -272,7 +280,8 @@ mod test_vmmemory_import {
 
 /// The fields compiled code needs to access to utilize a WebAssembly global
 /// variable imported from another instance.
-#[derive(Debug, Clone, MemoryUsage)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 #[repr(C)]
 pub struct VMGlobalImport {
     /// A pointer to the imported global variable description.
@@ -297,9 +306,9 @@ unsafe impl Sync for VMGlobalImport {}
 #[cfg(test)]
 mod test_vmglobal_import {
     use super::VMGlobalImport;
+    use crate::lib::std::mem::size_of;
     use crate::{ModuleInfo, VMOffsets};
     use memoffset::offset_of;
-    use std::mem::size_of;
 
     #[test]
     fn check_vmglobal_import_offsets() {
@@ -344,6 +353,7 @@ unsafe impl Send for VMMemoryDefinition {}
 /// correctness in a multi-threaded context is concerned.
 unsafe impl Sync for VMMemoryDefinition {}
 
+#[cfg(feature = "std")]
 impl MemoryUsage for VMMemoryDefinition {
     fn size_of_val(&self, tracker: &mut dyn MemoryUsageTracker) -> usize {
         if tracker.track(self.base as *const _ as *const ()) {
@@ -422,9 +432,9 @@ impl VMMemoryDefinition {
 #[cfg(test)]
 mod test_vmmemory_definition {
     use super::VMMemoryDefinition;
+    use crate::lib::std::mem::size_of;
     use crate::{ModuleInfo, VMOffsets};
     use memoffset::offset_of;
-    use std::mem::size_of;
 
     #[test]
     fn check_vmmemory_definition_offsets() {
@@ -463,6 +473,7 @@ pub struct VMTableDefinition {
     pub current_elements: u32,
 }
 
+#[cfg(feature = "std")]
 impl MemoryUsage for VMTableDefinition {
     fn size_of_val(&self, tracker: &mut dyn MemoryUsageTracker) -> usize {
         if tracker.track(self.base as *const _ as *const ()) {
@@ -476,9 +487,9 @@ impl MemoryUsage for VMTableDefinition {
 #[cfg(test)]
 mod test_vmtable_definition {
     use super::VMTableDefinition;
+    use crate::lib::std::mem::size_of;
     use crate::{ModuleInfo, VMOffsets};
     use memoffset::offset_of;
-    use std::mem::size_of;
 
     #[test]
     fn check_vmtable_definition_offsets() {
@@ -527,6 +538,7 @@ impl fmt::Debug for VMGlobalDefinitionStorage {
     }
 }
 
+#[cfg(feature = "std")]
 impl MemoryUsage for VMGlobalDefinitionStorage {
     fn size_of_val(&self, _: &mut dyn MemoryUsageTracker) -> usize {
         mem::size_of_val(self)
@@ -537,7 +549,8 @@ impl MemoryUsage for VMGlobalDefinitionStorage {
 ///
 /// TODO: Pack the globals more densely, rather than using the same size
 /// for every type.
-#[derive(Debug, Clone, MemoryUsage)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 #[repr(C, align(16))]
 pub struct VMGlobalDefinition {
     storage: VMGlobalDefinitionStorage,
@@ -547,9 +560,9 @@ pub struct VMGlobalDefinition {
 #[cfg(test)]
 mod test_vmglobal_definition {
     use super::VMGlobalDefinition;
+    use crate::lib::std::mem::{align_of, size_of};
     use crate::{ModuleInfo, VMOffsets};
     use more_asserts::assert_ge;
-    use std::mem::{align_of, size_of};
 
     #[test]
     fn check_vmglobal_definition_alignment() {
@@ -739,15 +752,16 @@ impl VMGlobalDefinition {
 /// An index into the shared signature registry, usable for checking signatures
 /// at indirect calls.
 #[repr(C)]
-#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash, MemoryUsage)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 pub struct VMSharedSignatureIndex(u32);
 
 #[cfg(test)]
 mod test_vmshared_signature_index {
     use super::VMSharedSignatureIndex;
+    use crate::lib::std::mem::size_of;
     use crate::module::ModuleInfo;
     use crate::vmoffsets::{TargetSharedSignatureIndex, VMOffsets};
-    use std::mem::size_of;
 
     #[test]
     fn check_vmshared_signature_index() {
@@ -784,7 +798,8 @@ impl Default for VMSharedSignatureIndex {
 /// The VM caller-checked "anyfunc" record, for caller-side signature checking.
 /// It consists of the actual function pointer and a signature id to be checked
 /// by the caller.
-#[derive(Debug, Clone, MemoryUsage)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 #[repr(C)]
 pub struct VMCallerCheckedAnyfunc {
     /// Function body.
@@ -799,9 +814,9 @@ pub struct VMCallerCheckedAnyfunc {
 #[cfg(test)]
 mod test_vmcaller_checked_anyfunc {
     use super::VMCallerCheckedAnyfunc;
+    use crate::lib::std::mem::size_of;
     use crate::{ModuleInfo, VMOffsets};
     use memoffset::offset_of;
-    use std::mem::size_of;
 
     #[test]
     fn check_vmcaller_checked_anyfunc_offsets() {
@@ -900,9 +915,39 @@ impl VMBuiltinFunctionIndex {
     pub const fn get_raise_trap_index() -> Self {
         Self(13)
     }
+    /// Returns an index for wasm's `memory.atomic.wait32` for locally
+    /// defined memories.
+    pub const fn get_memory_atomic_wait32_index() -> Self {
+        Self(14)
+    }
+    /// Returns an index for wasm's `memory.atomic.wait32` for imported
+    /// memories.
+    pub const fn get_imported_memory_atomic_wait32_index() -> Self {
+        Self(15)
+    }
+    /// Returns an index for wasm's `memory.atomic.wait64` for locally
+    /// defined memories.
+    pub const fn get_memory_atomic_wait64_index() -> Self {
+        Self(16)
+    }
+    /// Returns an index for wasm's `memory.atomic.wait64` for imported
+    /// memories.
+    pub const fn get_imported_memory_atomic_wait64_index() -> Self {
+        Self(17)
+    }
+    /// Returns an index for wasm's `memory.atomic.notify` for locally
+    /// defined memories.
+    pub const fn get_memory_atomic_notify_index() -> Self {
+        Self(18)
+    }
+    /// Returns an index for wasm's `memory.atomic.notify` for imported
+    /// memories.
+    pub const fn get_imported_memory_atomic_notify_index() -> Self {
+        Self(19)
+    }
     /// Returns the total number of builtin functions.
     pub const fn builtin_functions_total_number() -> u32 {
-        14
+        20
     }
 
     /// Return the index as an u32 number.
@@ -961,6 +1006,19 @@ impl VMBuiltinFunctionsArray {
         ptrs[VMBuiltinFunctionIndex::get_raise_trap_index().index() as usize] =
             wasmer_raise_trap as usize;
 
+        ptrs[VMBuiltinFunctionIndex::get_memory_atomic_wait32_index().index() as usize] =
+            wasmer_memory_atomic_wait32 as usize;
+        ptrs[VMBuiltinFunctionIndex::get_imported_memory_atomic_wait32_index().index() as usize] =
+            wasmer_imported_memory_atomic_wait32 as usize;
+        ptrs[VMBuiltinFunctionIndex::get_memory_atomic_wait64_index().index() as usize] =
+            wasmer_memory_atomic_wait64 as usize;
+        ptrs[VMBuiltinFunctionIndex::get_imported_memory_atomic_wait64_index().index() as usize] =
+            wasmer_imported_memory_atomic_wait64 as usize;
+        ptrs[VMBuiltinFunctionIndex::get_memory_atomic_notify_index().index() as usize] =
+            wasmer_memory_atomic_notify as usize;
+        ptrs[VMBuiltinFunctionIndex::get_imported_memory_atomic_notify_index().index() as usize] =
+            wasmer_imported_memory_atomic_notify as usize;
+
         debug_assert!(ptrs.iter().cloned().all(|p| p != 0));
 
         Self { ptrs }
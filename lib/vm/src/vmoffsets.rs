@@ -6,11 +6,12 @@
 
 #![deny(broken_intra_doc_links)]
 
+use crate::lib::std::convert::TryFrom;
 use crate::module::ModuleInfo;
 use crate::VMBuiltinFunctionIndex;
+#[cfg(feature = "std")]
 use loupe::MemoryUsage;
 use more_asserts::assert_lt;
-use std::convert::TryFrom;
 use wasmer_types::{
     FunctionIndex, GlobalIndex, LocalGlobalIndex, LocalMemoryIndex, LocalTableIndex, MemoryIndex,
     SignatureIndex, TableIndex,
@@ -34,7 +35,8 @@ const fn align(offset: u32, width: u32) -> u32 {
 /// related structs that JIT code accesses directly.
 ///
 /// [`VMContext`]: crate::vmcontext::VMContext
-#[derive(Clone, Debug, MemoryUsage)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "std", derive(MemoryUsage))]
 pub struct VMOffsets {
     /// The size in bytes of a pointer on the target.
     pub pointer_size: u8,
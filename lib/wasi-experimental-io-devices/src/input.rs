@@ -0,0 +1,279 @@
+//! The queues of input events read by the guest, which the host can inject
+//! events into.
+
+use crate::util::{bytes_for_input_event, InputEvent};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::sync::{Arc, Mutex};
+use wasmer_wasi::types::*;
+use wasmer_wasi::{Fd, WasiFile, WasiFs, WasiFsError, ALL_RIGHTS, VIRTUAL_ROOT_FD};
+
+/// A handle to a queue of input events read by the guest from
+/// `_wasmer/dev/input0/events` and `_wasmer/dev/fb0/input`.
+///
+/// Each [`WasiFs`] is given its own queue by [`initialize_input`] or
+/// [`initialize_with_input_events`](crate::initialize_with_input_events).
+/// The queue is shared by the clones of the handle and the threads: the
+/// host can push events from any thread, like the one of an emulator's UI.
+#[derive(Debug, Clone, Default)]
+pub struct InputEventQueue {
+    events: Arc<Mutex<VecDeque<InputEvent>>>,
+}
+
+impl InputEventQueue {
+    /// an arbitrary large number
+    pub const MAX_EVENTS: usize = 128;
+
+    /// Create an empty queue.
+    pub fn new() -> Self {
+        Self {
+            events: Arc::new(Mutex::new(VecDeque::with_capacity(Self::MAX_EVENTS))),
+        }
+    }
+
+    /// Push an event for the guest to read, returning it back if the queue
+    /// is full.
+    pub fn push(&self, event: InputEvent) -> Result<(), InputEvent> {
+        let mut events = self.events.lock().unwrap();
+        if events.len() >= Self::MAX_EVENTS {
+            return Err(event);
+        }
+
+        events.push_back(event);
+        Ok(())
+    }
+
+    /// The number of events which the guest hasn't read yet.
+    pub fn len(&self) -> usize {
+        self.events.lock().unwrap().len()
+    }
+
+    /// Whether the guest has read all the events.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop the events which the guest hasn't read yet.
+    pub fn clear(&self) {
+        self.events.lock().unwrap().clear();
+    }
+
+    /// Copy as many whole events as fit into `buf`, removing them from the
+    /// queue, and return the number of bytes written.
+    pub(crate) fn read_into(&self, buf: &mut [u8]) -> usize {
+        let mut events = self.events.lock().unwrap();
+        let mut idx = 0;
+
+        while let Some(next_elem) = events.front() {
+            let (tag_byte, data, size) = bytes_for_input_event(*next_elem);
+            if buf.len() - idx < 1 + size {
+                break;
+            }
+            buf[idx] = tag_byte;
+            buf[idx + 1..idx + 1 + size].copy_from_slice(&data[..size]);
+            idx += 1 + size;
+            events.pop_front().unwrap();
+        }
+        idx
+    }
+
+    /// The number of bytes of the events which the guest hasn't read yet.
+    fn bytes_len(&self) -> usize {
+        self.events
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|event| 1 + bytes_for_input_event(*event).2)
+            .sum()
+    }
+}
+
+/// The file `_wasmer/dev/input0/events`, reading the events pushed by the
+/// host without needing a window.
+///
+/// The queue is not serialized: an unfrozen device starts with an empty
+/// queue, which the host can't reach.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InputDevice {
+    #[serde(skip)]
+    events: InputEventQueue,
+}
+
+impl InputDevice {
+    /// Create the device reading the events of `events`.
+    pub fn new(events: InputEventQueue) -> Self {
+        Self { events }
+    }
+}
+
+impl Read for InputDevice {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        Ok(self.events.read_into(buf))
+    }
+}
+
+impl Seek for InputDevice {
+    fn seek(&mut self, _pos: SeekFrom) -> std::io::Result<u64> {
+        Err(std::io::Error::from(std::io::ErrorKind::Other))
+    }
+}
+
+impl Write for InputDevice {
+    fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+        Ok(0)
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[typetag::serde]
+impl WasiFile for InputDevice {
+    fn last_accessed(&self) -> u64 {
+        0
+    }
+    fn last_modified(&self) -> u64 {
+        0
+    }
+    fn created_time(&self) -> u64 {
+        0
+    }
+    fn size(&self) -> u64 {
+        0
+    }
+    fn set_len(&mut self, _new_size: __wasi_filesize_t) -> Result<(), WasiFsError> {
+        Ok(())
+    }
+    fn unlink(&mut self) -> Result<(), WasiFsError> {
+        Ok(())
+    }
+    fn bytes_available(&self) -> Result<usize, WasiFsError> {
+        Ok(self.events.bytes_len())
+    }
+}
+
+/// Create `_wasmer/dev/input0/events`, for guests which only need the input
+/// events injected by the host into `events`.
+///
+/// ```
+/// use wasmer_wasi::WasiState;
+/// use wasmer_wasi_experimental_io_devices::{initialize_input, InputEvent, InputEventQueue, Key};
+///
+/// let events = InputEventQueue::new();
+/// let device_events = events.clone();
+/// let state = WasiState::new("game")
+///     .setup_fs(Box::new(move |fs| initialize_input(fs, device_events.clone())))
+///     .build()
+///     .unwrap();
+///
+/// events.push(InputEvent::KeyPress(Key::Space)).unwrap();
+/// events.push(InputEvent::KeyRelease(Key::Space)).unwrap();
+/// # assert_eq!(events.len(), 2);
+/// # drop(state);
+/// ```
+pub fn initialize_input(fs: &mut WasiFs, events: InputEventQueue) -> Result<(), String> {
+    let base_dir_fd = unsafe {
+        fs.open_dir_all(
+            VIRTUAL_ROOT_FD,
+            "_wasmer/dev/input0".to_string(),
+            ALL_RIGHTS,
+            ALL_RIGHTS,
+            0,
+        )
+        .map_err(|e| format!("input: Failed to create dev folder {:?}", e))?
+    };
+
+    let _fd = fs
+        .open_file_at(
+            base_dir_fd,
+            Box::new(InputDevice::new(events)),
+            Fd::READ,
+            "events".to_string(),
+            ALL_RIGHTS,
+            ALL_RIGHTS,
+            0,
+        )
+        .map_err(|e| format!("input: Failed to init input events {:?}", e))?;
+
+    tracing::debug!("Input events open on fd {}", _fd);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::{KEY_PRESS, KEY_RELEASE};
+    use minifb::Key;
+    use wasmer_wasi::WasiState;
+
+    /// Take the `_wasmer/dev/input0/events` device out of `fs`.
+    fn take_input_device(fs: &mut WasiFs) -> Box<dyn WasiFile> {
+        let fd = fs
+            .open_fds()
+            .into_iter()
+            .find(|open_fd| open_fd.name == "events")
+            .expect("`events` is not open")
+            .fd;
+        fs.swap_file(fd, Box::new(InputDevice::new(InputEventQueue::new())))
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn queue_reads_whole_events() {
+        let events = InputEventQueue::new();
+        events.push(InputEvent::KeyPress(Key::Space)).unwrap();
+        events.push(InputEvent::MouseMoved(1, 2)).unwrap();
+        assert_eq!(events.len(), 2);
+
+        // The mouse event doesn't fit, so it stays in the queue.
+        let mut buf = [0; 4];
+        assert_eq!(events.read_into(&mut buf), 2);
+        assert_eq!(buf[0], KEY_PRESS);
+        assert_eq!(events.len(), 1);
+
+        let mut buf = [0; 16];
+        assert_eq!(events.read_into(&mut buf), 9);
+        assert_eq!(&buf[1..9], &[1, 0, 0, 0, 2, 0, 0, 0]);
+        assert!(events.is_empty());
+
+        for _ in 0..InputEventQueue::MAX_EVENTS {
+            events.push(InputEvent::WindowClosed).unwrap();
+        }
+        assert!(events.push(InputEvent::WindowClosed).is_err());
+        events.clear();
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn each_fs_reads_its_own_events() {
+        let build_state = |events: &InputEventQueue| {
+            let events = events.clone();
+            WasiState::new("input")
+                .setup_fs(Box::new(move |fs| initialize_input(fs, events.clone())))
+                .build()
+                .unwrap()
+        };
+        let first_events = InputEventQueue::new();
+        let second_events = InputEventQueue::new();
+        let mut first_state = build_state(&first_events);
+        let mut second_state = build_state(&second_events);
+
+        first_events.push(InputEvent::KeyPress(Key::A)).unwrap();
+        first_events.push(InputEvent::KeyRelease(Key::A)).unwrap();
+
+        let mut first_device = take_input_device(&mut first_state.fs);
+        let mut second_device = take_input_device(&mut second_state.fs);
+        assert_eq!(first_device.bytes_available().unwrap(), 4);
+        assert_eq!(second_device.bytes_available().unwrap(), 0);
+
+        let mut buf = [0; 16];
+        assert_eq!(second_device.read(&mut buf).unwrap(), 0);
+        assert_eq!(first_device.read(&mut buf).unwrap(), 4);
+        assert_eq!(buf[0], KEY_PRESS);
+        assert_eq!(buf[2], KEY_RELEASE);
+        assert!(first_events.is_empty());
+    }
+}
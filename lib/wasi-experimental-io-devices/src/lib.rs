@@ -1,16 +1,19 @@
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeSet, VecDeque};
+use std::collections::BTreeSet;
 use std::convert::TryInto;
 use std::io::{Read, Seek, SeekFrom, Write};
 use tracing::debug;
 use wasmer_wasi::types::*;
 use wasmer_wasi::{Fd, WasiFile, WasiFs, WasiFsError, ALL_RIGHTS, VIRTUAL_ROOT_FD};
 
-use minifb::{Key, KeyRepeat, MouseButton, Scale, Window, WindowOptions};
+use minifb::{KeyRepeat, Scale, Window, WindowOptions};
 
+mod input;
 mod util;
 
-use util::*;
+pub use input::{initialize_input, InputDevice, InputEventQueue};
+pub use minifb::{Key, MouseButton};
+pub use util::InputEvent;
 
 use std::cell::RefCell;
 std::thread_local! {
@@ -43,14 +46,11 @@ pub(crate) struct FrameBufferState {
     pub window: Window,
 
     pub last_mouse_pos: (u32, u32),
-    pub inputs: VecDeque<InputEvent>,
     pub keys_pressed: BTreeSet<minifb::Key>,
+    pub mouse_buttons_down: BTreeSet<u8>,
 }
 
 impl FrameBufferState {
-    /// an arbitrary large number
-    const MAX_INPUTS: usize = 128;
-
     pub fn new() -> Self {
         let x = 100;
         let y = 200;
@@ -67,8 +67,8 @@ impl FrameBufferState {
 
             window,
             last_mouse_pos: (0, 0),
-            inputs: VecDeque::with_capacity(Self::MAX_INPUTS),
             keys_pressed: BTreeSet::new(),
+            mouse_buttons_down: BTreeSet::new(),
         }
     }
 
@@ -101,30 +101,22 @@ impl FrameBufferState {
         Some(())
     }
 
-    fn push_input_event(&mut self, input_event: InputEvent) -> Option<()> {
-        if self.inputs.len() >= Self::MAX_INPUTS {
-            return None;
-        }
-
-        self.inputs.push_back(input_event);
-        Some(())
-    }
-
-    pub fn fill_input_buffer(&mut self) -> Option<()> {
+    /// Push the events of the window into `events`.
+    pub fn fill_input_buffer(&mut self, events: &InputEventQueue) -> Option<()> {
         let keys_pressed = self.keys_pressed.iter().cloned().collect::<Vec<Key>>();
         if !self.window.is_open() {
-            self.push_input_event(InputEvent::WindowClosed)?;
+            events.push(InputEvent::WindowClosed).ok()?;
         }
         for key in keys_pressed {
             if self.window.is_key_released(key) {
                 self.keys_pressed.remove(&key);
-                self.push_input_event(InputEvent::KeyRelease(key))?;
+                events.push(InputEvent::KeyRelease(key)).ok()?;
             }
         }
         let keys = self.window.get_keys_pressed(KeyRepeat::No)?;
         for key in keys {
             self.keys_pressed.insert(key.clone());
-            self.push_input_event(InputEvent::KeyPress(key))?;
+            events.push(InputEvent::KeyPress(key)).ok()?;
         }
 
         let mouse_position = self.window.get_mouse_pos(minifb::MouseMode::Clamp)?;
@@ -132,32 +124,25 @@ impl FrameBufferState {
             || mouse_position.1 as u32 != self.last_mouse_pos.1
         {
             self.last_mouse_pos = (mouse_position.0 as u32, mouse_position.1 as u32);
-            self.push_input_event(InputEvent::MouseMoved(
-                self.last_mouse_pos.0,
-                self.last_mouse_pos.1,
-            ))?;
+            events
+                .push(InputEvent::MouseMoved(
+                    self.last_mouse_pos.0,
+                    self.last_mouse_pos.1,
+                ))
+                .ok()?;
         }
 
-        if self.window.get_mouse_down(MouseButton::Left) {
-            self.push_input_event(InputEvent::MouseEvent(
-                mouse_position.0 as u32,
-                mouse_position.1 as u32,
-                MouseButton::Left,
-            ))?;
-        }
-        if self.window.get_mouse_down(MouseButton::Right) {
-            self.push_input_event(InputEvent::MouseEvent(
-                mouse_position.0 as u32,
-                mouse_position.1 as u32,
-                MouseButton::Right,
-            ))?;
-        }
-        if self.window.get_mouse_down(MouseButton::Middle) {
-            self.push_input_event(InputEvent::MouseEvent(
-                mouse_position.0 as u32,
-                mouse_position.1 as u32,
-                MouseButton::Middle,
-            ))?;
+        for (idx, button) in [MouseButton::Left, MouseButton::Right, MouseButton::Middle]
+            .iter()
+            .enumerate()
+        {
+            let (x, y) = (mouse_position.0 as u32, mouse_position.1 as u32);
+            if self.window.get_mouse_down(*button) {
+                self.mouse_buttons_down.insert(idx as u8);
+                events.push(InputEvent::MouseEvent(x, y, *button)).ok()?;
+            } else if self.mouse_buttons_down.remove(&(idx as u8)) {
+                events.push(InputEvent::MouseRelease(x, y, *button)).ok()?;
+            }
         }
         Some(())
     }
@@ -228,6 +213,9 @@ impl FrameBufferState {
 pub struct FrameBuffer {
     fb_type: FrameBufferFileType,
     cursor: u32,
+    /// The queue read by the `Input` file; it is not serialized.
+    #[serde(skip)]
+    input_events: InputEventQueue,
 }
 
 impl Read for FrameBuffer {
@@ -275,24 +263,9 @@ impl Read for FrameBuffer {
                 }
 
                 FrameBufferFileType::Input => {
-                    let mut idx = 0;
-                    fb_state.fill_input_buffer();
-
-                    while let Some(next_elem) = fb_state.inputs.front() {
-                        let remaining_length = buf.len() - idx;
-                        let (tag_byte, data, size) = bytes_for_input_event(*next_elem);
-                        if remaining_length > 1 + size {
-                            buf[idx] = tag_byte;
-                            for i in 0..size {
-                                buf[idx + 1 + i] = data[i];
-                            }
-                            idx += 1 + size;
-                        } else {
-                            break;
-                        }
-                        fb_state.inputs.pop_front().unwrap();
-                    }
-                    Ok(idx)
+                    fb_state.fill_input_buffer(&self.input_events);
+
+                    Ok(self.input_events.read_into(buf))
                 }
             }
         })
@@ -429,21 +402,35 @@ impl WasiFile for FrameBuffer {
 }
 
 pub fn initialize(fs: &mut WasiFs) -> Result<(), String> {
+    initialize_with_input_events(fs, InputEventQueue::new())
+}
+
+/// Like [`initialize`], with the queue of input events shared by
+/// `_wasmer/dev/fb0/input` and `_wasmer/dev/input0/events`, into which the
+/// host can inject events besides the ones of the window.
+pub fn initialize_with_input_events(
+    fs: &mut WasiFs,
+    input_events: InputEventQueue,
+) -> Result<(), String> {
     let frame_buffer_file = Box::new(FrameBuffer {
         fb_type: FrameBufferFileType::Buffer,
         cursor: 0,
+        input_events: InputEventQueue::default(),
     });
     let resolution_file = Box::new(FrameBuffer {
         fb_type: FrameBufferFileType::Resolution,
         cursor: 0,
+        input_events: InputEventQueue::default(),
     });
     let draw_file = Box::new(FrameBuffer {
         fb_type: FrameBufferFileType::Draw,
         cursor: 0,
+        input_events: InputEventQueue::default(),
     });
     let input_file = Box::new(FrameBuffer {
         fb_type: FrameBufferFileType::Input,
         cursor: 0,
+        input_events: input_events.clone(),
     });
 
     let base_dir_fd = unsafe {
@@ -513,5 +500,5 @@ pub fn initialize(fs: &mut WasiFs) -> Result<(), String> {
 
     debug!("Framebuffer draw open on fd {}", _fd);
 
-    Ok(())
+    initialize_input(fs, input_events)
 }
@@ -7,6 +7,9 @@ pub const MOUSE_PRESS_LEFT: u8 = 4;
 pub const MOUSE_PRESS_RIGHT: u8 = 5;
 pub const MOUSE_PRESS_MIDDLE: u8 = 7;
 pub const WINDOW_CLOSED: u8 = 8;
+pub const MOUSE_RELEASE_LEFT: u8 = 9;
+pub const MOUSE_RELEASE_RIGHT: u8 = 10;
+pub const MOUSE_RELEASE_MIDDLE: u8 = 11;
 
 use minifb::{Key, MouseButton};
 
@@ -14,7 +17,10 @@ use minifb::{Key, MouseButton};
 pub enum InputEvent {
     KeyPress(Key),
     KeyRelease(Key),
+    /// A button is down at a position, sent for as long as it's held
     MouseEvent(u32, u32, MouseButton),
+    /// A button was released at a position
+    MouseRelease(u32, u32, MouseButton),
     MouseMoved(u32, u32),
     WindowClosed,
 }
@@ -49,6 +55,16 @@ pub fn bytes_for_input_event(input_event: InputEvent) -> (u8, [u8; 8], usize) {
             }
             (tag, data, 8)
         }
+        InputEvent::MouseRelease(x, y, btn) => {
+            let tag = match btn {
+                MouseButton::Left => MOUSE_RELEASE_LEFT,
+                MouseButton::Right => MOUSE_RELEASE_RIGHT,
+                MouseButton::Middle => MOUSE_RELEASE_MIDDLE,
+            };
+            data[..4].copy_from_slice(&x.to_le_bytes());
+            data[4..].copy_from_slice(&y.to_le_bytes());
+            (tag, data, 8)
+        }
         InputEvent::MouseMoved(x, y) => {
             let x_bytes = x.to_le_bytes();
             for i in 0..4 {
@@ -0,0 +1,273 @@
+//! The signature algorithms, key pairs and key stores.
+
+use super::CryptoError;
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::signature::{self, EcdsaKeyPair, Ed25519KeyPair, KeyPair as _, UnparsedPublicKey};
+use std::collections::HashMap;
+use std::fmt;
+
+/// A signature algorithm of wasi-crypto.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SignatureAlgorithm {
+    Ed25519,
+    EcdsaP256Sha256,
+    EcdsaP384Sha384,
+}
+
+impl SignatureAlgorithm {
+    /// Get the algorithm named `name` by wasi-crypto, like `Ed25519`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Ed25519" => Some(SignatureAlgorithm::Ed25519),
+            "ECDSA_P256_SHA256" => Some(SignatureAlgorithm::EcdsaP256Sha256),
+            "ECDSA_P384_SHA384" => Some(SignatureAlgorithm::EcdsaP384Sha384),
+            _ => None,
+        }
+    }
+
+    /// The wasi-crypto name of the algorithm.
+    pub fn name(self) -> &'static str {
+        match self {
+            SignatureAlgorithm::Ed25519 => "Ed25519",
+            SignatureAlgorithm::EcdsaP256Sha256 => "ECDSA_P256_SHA256",
+            SignatureAlgorithm::EcdsaP384Sha384 => "ECDSA_P384_SHA384",
+        }
+    }
+
+    fn ecdsa_signing(self) -> Option<&'static signature::EcdsaSigningAlgorithm> {
+        match self {
+            SignatureAlgorithm::Ed25519 => None,
+            SignatureAlgorithm::EcdsaP256Sha256 => {
+                Some(&signature::ECDSA_P256_SHA256_FIXED_SIGNING)
+            }
+            SignatureAlgorithm::EcdsaP384Sha384 => {
+                Some(&signature::ECDSA_P384_SHA384_FIXED_SIGNING)
+            }
+        }
+    }
+
+    /// The length of a public key, in its raw encoding.
+    pub(crate) fn public_key_len(self) -> usize {
+        match self {
+            SignatureAlgorithm::Ed25519 => 32,
+            SignatureAlgorithm::EcdsaP256Sha256 => 65,
+            SignatureAlgorithm::EcdsaP384Sha384 => 97,
+        }
+    }
+
+    /// The length of a signature, in its raw encoding.
+    pub(crate) fn signature_len(self) -> usize {
+        match self {
+            SignatureAlgorithm::Ed25519 | SignatureAlgorithm::EcdsaP256Sha256 => 64,
+            SignatureAlgorithm::EcdsaP384Sha384 => 96,
+        }
+    }
+
+    /// Generate a key pair, in PKCS#8.
+    pub(crate) fn generate_pkcs8(self) -> Result<Vec<u8>, CryptoError> {
+        let rng = SystemRandom::new();
+        let document = match self.ecdsa_signing() {
+            None => Ed25519KeyPair::generate_pkcs8(&rng),
+            Some(algorithm) => EcdsaKeyPair::generate_pkcs8(algorithm, &rng),
+        }
+        .map_err(|_| CryptoError::RngError)?;
+        Ok(document.as_ref().to_vec())
+    }
+
+    /// Get the raw public key of a key pair in PKCS#8.
+    pub fn public_key(self, pkcs8: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let public_key = match self.ecdsa_signing() {
+            None => Ed25519KeyPair::from_pkcs8_maybe_unchecked(pkcs8)
+                .map_err(|_| CryptoError::InvalidKey)?
+                .public_key()
+                .as_ref()
+                .to_vec(),
+            Some(algorithm) => EcdsaKeyPair::from_pkcs8(algorithm, pkcs8)
+                .map_err(|_| CryptoError::InvalidKey)?
+                .public_key()
+                .as_ref()
+                .to_vec(),
+        };
+        Ok(public_key)
+    }
+
+    /// Sign `message` with a key pair in PKCS#8, returning the raw signature.
+    pub fn sign(self, pkcs8: &[u8], message: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let signature = match self.ecdsa_signing() {
+            None => Ed25519KeyPair::from_pkcs8_maybe_unchecked(pkcs8)
+                .map_err(|_| CryptoError::InvalidKey)?
+                .sign(message),
+            Some(algorithm) => EcdsaKeyPair::from_pkcs8(algorithm, pkcs8)
+                .map_err(|_| CryptoError::InvalidKey)?
+                .sign(&SystemRandom::new(), message)
+                .map_err(|_| CryptoError::AlgorithmFailure)?,
+        };
+        Ok(signature.as_ref().to_vec())
+    }
+
+    /// Verify the raw `signature` of `message` with a raw public key.
+    pub(crate) fn verify(
+        self,
+        public_key: &[u8],
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), CryptoError> {
+        let algorithm: &dyn signature::VerificationAlgorithm = match self {
+            SignatureAlgorithm::Ed25519 => &signature::ED25519,
+            SignatureAlgorithm::EcdsaP256Sha256 => &signature::ECDSA_P256_SHA256_FIXED,
+            SignatureAlgorithm::EcdsaP384Sha384 => &signature::ECDSA_P384_SHA384_FIXED,
+        };
+        UnparsedPublicKey::new(algorithm, public_key)
+            .verify(message, signature)
+            .map_err(|_| CryptoError::VerificationFailed)
+    }
+}
+
+/// A key pair given to the module.
+#[derive(Clone)]
+pub(crate) enum KeyPair {
+    /// A key pair generated or imported by the module, in PKCS#8
+    Local {
+        algorithm: SignatureAlgorithm,
+        pkcs8: Vec<u8>,
+    },
+    /// A key pair of the [`KeyStore`]
+    Stored {
+        algorithm: SignatureAlgorithm,
+        id: Vec<u8>,
+    },
+}
+
+impl fmt::Debug for KeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyPair::Local { algorithm, .. } => f
+                .debug_struct("Local")
+                .field("algorithm", algorithm)
+                .finish(),
+            KeyPair::Stored { algorithm, id } => f
+                .debug_struct("Stored")
+                .field("algorithm", algorithm)
+                .field("id", id)
+                .finish(),
+        }
+    }
+}
+
+impl KeyPair {
+    pub(crate) fn algorithm(&self) -> SignatureAlgorithm {
+        match self {
+            KeyPair::Local { algorithm, .. } | KeyPair::Stored { algorithm, .. } => *algorithm,
+        }
+    }
+}
+
+/// A raw public key given to the module.
+#[derive(Debug, Clone)]
+pub(crate) struct PublicKey {
+    pub(crate) algorithm: SignatureAlgorithm,
+    pub(crate) bytes: Vec<u8>,
+}
+
+/// The secrets manager of wasi-crypto, storing the key pairs of the module.
+///
+/// The module only refers to a stored key pair by its id: signing with it is
+/// done by the key store.
+pub trait KeyStore: fmt::Debug + Send + 'static {
+    /// Store a key pair given in PKCS#8, returning its id.
+    fn store(
+        &mut self,
+        algorithm: SignatureAlgorithm,
+        pkcs8: &[u8],
+    ) -> Result<Vec<u8>, CryptoError>;
+
+    /// Get the algorithm and the raw public key of the key pair `id`, or
+    /// [`CryptoError::NotFound`].
+    fn public_key(&self, id: &[u8]) -> Result<(SignatureAlgorithm, Vec<u8>), CryptoError>;
+
+    /// Sign `message` with the key pair `id`, returning the raw signature.
+    fn sign(&self, id: &[u8], message: &[u8]) -> Result<Vec<u8>, CryptoError>;
+}
+
+/// A [`KeyStore`] keeping the key pairs in memory, with random ids of 16
+/// bytes.
+#[derive(Default)]
+pub struct MemoryKeyStore {
+    keys: HashMap<Vec<u8>, (SignatureAlgorithm, Vec<u8>)>,
+}
+
+impl MemoryKeyStore {
+    /// Create an empty key store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn get(&self, id: &[u8]) -> Result<&(SignatureAlgorithm, Vec<u8>), CryptoError> {
+        self.keys.get(id).ok_or(CryptoError::NotFound)
+    }
+}
+
+impl fmt::Debug for MemoryKeyStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryKeyStore")
+            .field("keys", &self.keys.len())
+            .finish()
+    }
+}
+
+impl KeyStore for MemoryKeyStore {
+    fn store(
+        &mut self,
+        algorithm: SignatureAlgorithm,
+        pkcs8: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        let mut id = vec![0; 16];
+        SystemRandom::new()
+            .fill(&mut id)
+            .map_err(|_| CryptoError::RngError)?;
+        self.keys.insert(id.clone(), (algorithm, pkcs8.to_vec()));
+        Ok(id)
+    }
+
+    fn public_key(&self, id: &[u8]) -> Result<(SignatureAlgorithm, Vec<u8>), CryptoError> {
+        let (algorithm, pkcs8) = self.get(id)?;
+        Ok((*algorithm, algorithm.public_key(pkcs8)?))
+    }
+
+    fn sign(&self, id: &[u8], message: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let (algorithm, pkcs8) = self.get(id)?;
+        algorithm.sign(pkcs8, message)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn memory_keystore_signs_with_stored_keys() {
+        let mut keystore = MemoryKeyStore::new();
+        for algorithm in &[
+            SignatureAlgorithm::Ed25519,
+            SignatureAlgorithm::EcdsaP256Sha256,
+            SignatureAlgorithm::EcdsaP384Sha384,
+        ] {
+            let pkcs8 = algorithm.generate_pkcs8().unwrap();
+            let id = keystore.store(*algorithm, &pkcs8).unwrap();
+            let (stored_algorithm, public_key) = keystore.public_key(&id).unwrap();
+            assert_eq!(stored_algorithm, *algorithm);
+            assert_eq!(public_key.len(), algorithm.public_key_len());
+
+            let signature = keystore.sign(&id, b"message").unwrap();
+            assert_eq!(signature.len(), algorithm.signature_len());
+            assert!(algorithm
+                .verify(&public_key, b"message", &signature)
+                .is_ok());
+            assert_eq!(
+                algorithm.verify(&public_key, b"other message", &signature),
+                Err(CryptoError::VerificationFailed)
+            );
+        }
+        assert_eq!(keystore.sign(b"unknown", b""), Err(CryptoError::NotFound));
+    }
+}
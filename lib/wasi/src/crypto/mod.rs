@@ -0,0 +1,243 @@
+//! The `wasi_ephemeral_crypto_*` imports of the wasi-crypto proposal:
+//! hashing, signatures and the storage of key pairs.
+//!
+//! The supported algorithms are:
+//! - the hash functions `SHA-256`, `SHA-384`, `SHA-512` and `SHA-512/256`;
+//! - the signature algorithms `Ed25519`, `ECDSA_P256_SHA256` and
+//!   `ECDSA_P384_SHA384`.
+//!
+//! Key pairs are stored with `keypair_store` in the [`KeyStore`] given to
+//! [`WasiStateBuilder::crypto_keystore`](crate::WasiStateBuilder::crypto_keystore),
+//! which signs with them without giving their secret key to the module, so
+//! that keys can be kept in an HSM. [`MemoryKeyStore`] is the default one.
+//!
+//! Options are not supported: the optional options given to the imports
+//! must be `none`.
+
+#![allow(non_camel_case_types)]
+
+mod keys;
+mod syscalls;
+
+pub use self::keys::{KeyStore, MemoryKeyStore, SignatureAlgorithm};
+pub(crate) use self::syscalls::exports;
+
+use self::keys::{KeyPair, PublicKey};
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// An error of the wasi-crypto imports or of a [`KeyStore`], reported to
+/// the module as a wasi-crypto errno.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum CryptoError {
+    #[error("the module gave invalid memory")]
+    GuestError,
+    #[error("unsupported feature")]
+    UnsupportedFeature,
+    #[error("prohibited operation")]
+    ProhibitedOperation,
+    #[error("unsupported encoding")]
+    UnsupportedEncoding,
+    #[error("unsupported algorithm")]
+    UnsupportedAlgorithm,
+    #[error("unsupported option")]
+    UnsupportedOption,
+    #[error("invalid key")]
+    InvalidKey,
+    #[error("invalid length")]
+    InvalidLength,
+    #[error("verification failed")]
+    VerificationFailed,
+    #[error("random number generator failure")]
+    RngError,
+    #[error("algorithm failure")]
+    AlgorithmFailure,
+    #[error("invalid signature")]
+    InvalidSignature,
+    #[error("invalid handle")]
+    InvalidHandle,
+    #[error("overflow")]
+    Overflow,
+    #[error("internal error: {0}")]
+    InternalError(String),
+    #[error("too many handles")]
+    TooManyHandles,
+    #[error("the algorithm doesn't use a key")]
+    KeyNotSupported,
+    #[error("key required")]
+    KeyRequired,
+    #[error("not found")]
+    NotFound,
+}
+
+impl CryptoError {
+    fn into_crypto_errno(self) -> __wasi_crypto_errno_t {
+        match self {
+            CryptoError::GuestError => __WASI_CRYPTO_EGUEST_ERROR,
+            CryptoError::UnsupportedFeature => __WASI_CRYPTO_EUNSUPPORTED_FEATURE,
+            CryptoError::ProhibitedOperation => __WASI_CRYPTO_EPROHIBITED_OPERATION,
+            CryptoError::UnsupportedEncoding => __WASI_CRYPTO_EUNSUPPORTED_ENCODING,
+            CryptoError::UnsupportedAlgorithm => __WASI_CRYPTO_EUNSUPPORTED_ALGORITHM,
+            CryptoError::UnsupportedOption => __WASI_CRYPTO_EUNSUPPORTED_OPTION,
+            CryptoError::InvalidKey => __WASI_CRYPTO_EINVALID_KEY,
+            CryptoError::InvalidLength => __WASI_CRYPTO_EINVALID_LENGTH,
+            CryptoError::VerificationFailed => __WASI_CRYPTO_EVERIFICATION_FAILED,
+            CryptoError::RngError => __WASI_CRYPTO_ERNG_ERROR,
+            CryptoError::AlgorithmFailure => __WASI_CRYPTO_EALGORITHM_FAILURE,
+            CryptoError::InvalidSignature => __WASI_CRYPTO_EINVALID_SIGNATURE,
+            CryptoError::InvalidHandle => __WASI_CRYPTO_EINVALID_HANDLE,
+            CryptoError::Overflow => __WASI_CRYPTO_EOVERFLOW,
+            CryptoError::InternalError(_) => __WASI_CRYPTO_EINTERNAL_ERROR,
+            CryptoError::TooManyHandles => __WASI_CRYPTO_ETOO_MANY_HANDLES,
+            CryptoError::KeyNotSupported => __WASI_CRYPTO_EKEY_NOT_SUPPORTED,
+            CryptoError::KeyRequired => __WASI_CRYPTO_EKEY_REQUIRED,
+            CryptoError::NotFound => __WASI_CRYPTO_ENOT_FOUND,
+        }
+    }
+}
+
+pub type __wasi_crypto_errno_t = u16;
+pub const __WASI_CRYPTO_ESUCCESS: u16 = 0;
+pub const __WASI_CRYPTO_EGUEST_ERROR: u16 = 1;
+pub const __WASI_CRYPTO_ENOT_IMPLEMENTED: u16 = 2;
+pub const __WASI_CRYPTO_EUNSUPPORTED_FEATURE: u16 = 3;
+pub const __WASI_CRYPTO_EPROHIBITED_OPERATION: u16 = 4;
+pub const __WASI_CRYPTO_EUNSUPPORTED_ENCODING: u16 = 5;
+pub const __WASI_CRYPTO_EUNSUPPORTED_ALGORITHM: u16 = 6;
+pub const __WASI_CRYPTO_EUNSUPPORTED_OPTION: u16 = 7;
+pub const __WASI_CRYPTO_EINVALID_KEY: u16 = 8;
+pub const __WASI_CRYPTO_EINVALID_LENGTH: u16 = 9;
+pub const __WASI_CRYPTO_EVERIFICATION_FAILED: u16 = 10;
+pub const __WASI_CRYPTO_ERNG_ERROR: u16 = 11;
+pub const __WASI_CRYPTO_EALGORITHM_FAILURE: u16 = 12;
+pub const __WASI_CRYPTO_EINVALID_SIGNATURE: u16 = 13;
+pub const __WASI_CRYPTO_ECLOSED: u16 = 14;
+pub const __WASI_CRYPTO_EINVALID_HANDLE: u16 = 15;
+pub const __WASI_CRYPTO_EOVERFLOW: u16 = 16;
+pub const __WASI_CRYPTO_EINTERNAL_ERROR: u16 = 17;
+pub const __WASI_CRYPTO_ETOO_MANY_HANDLES: u16 = 18;
+pub const __WASI_CRYPTO_EKEY_NOT_SUPPORTED: u16 = 19;
+pub const __WASI_CRYPTO_EKEY_REQUIRED: u16 = 20;
+pub const __WASI_CRYPTO_ENOT_FOUND: u16 = 26;
+
+pub type __wasi_crypto_algorithm_type_t = u16;
+pub const __WASI_CRYPTO_ALGORITHM_TYPE_SIGNATURES: u16 = 0;
+pub const __WASI_CRYPTO_ALGORITHM_TYPE_SYMMETRIC: u16 = 1;
+pub const __WASI_CRYPTO_ALGORITHM_TYPE_KEY_EXCHANGE: u16 = 2;
+
+pub type __wasi_crypto_keypair_encoding_t = u16;
+pub const __WASI_CRYPTO_KEYPAIR_ENCODING_RAW: u16 = 0;
+pub const __WASI_CRYPTO_KEYPAIR_ENCODING_PKCS8: u16 = 1;
+
+pub type __wasi_crypto_publickey_encoding_t = u16;
+pub const __WASI_CRYPTO_PUBLICKEY_ENCODING_RAW: u16 = 0;
+pub const __WASI_CRYPTO_PUBLICKEY_ENCODING_SEC: u16 = 3;
+
+pub type __wasi_crypto_signature_encoding_t = u16;
+pub const __WASI_CRYPTO_SIGNATURE_ENCODING_RAW: u16 = 0;
+
+pub const __WASI_CRYPTO_OPT_NONE: u8 = 1;
+
+/// The handles of the objects of a kind, given to the module.
+#[derive(Debug)]
+struct Handles<T> {
+    next: u32,
+    items: HashMap<u32, T>,
+}
+
+impl<T> Default for Handles<T> {
+    fn default() -> Self {
+        Self {
+            next: 0,
+            items: HashMap::new(),
+        }
+    }
+}
+
+impl<T> Handles<T> {
+    fn insert(&mut self, item: T) -> Result<u32, CryptoError> {
+        let handle = self.next;
+        self.next = self
+            .next
+            .checked_add(1)
+            .ok_or(CryptoError::TooManyHandles)?;
+        self.items.insert(handle, item);
+        Ok(handle)
+    }
+
+    fn get(&self, handle: u32) -> Result<&T, CryptoError> {
+        self.items.get(&handle).ok_or(CryptoError::InvalidHandle)
+    }
+
+    fn get_mut(&mut self, handle: u32) -> Result<&mut T, CryptoError> {
+        self.items
+            .get_mut(&handle)
+            .ok_or(CryptoError::InvalidHandle)
+    }
+
+    fn remove(&mut self, handle: u32) -> Result<T, CryptoError> {
+        self.items.remove(&handle).ok_or(CryptoError::InvalidHandle)
+    }
+}
+
+/// The state of a hash function.
+#[derive(Clone)]
+struct HashState(ring::digest::Context);
+
+impl std::fmt::Debug for HashState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("HashState")
+            .field(self.0.algorithm())
+            .finish()
+    }
+}
+
+/// A signature of `algorithm`, in its raw encoding.
+#[derive(Debug, Clone)]
+struct Signature {
+    algorithm: SignatureAlgorithm,
+    bytes: Vec<u8>,
+}
+
+/// The message signed or verified so far.
+#[derive(Debug)]
+struct SignatureState<K> {
+    key: K,
+    message: Vec<u8>,
+}
+
+/// The objects created by the wasi-crypto imports of a module.
+#[derive(Debug)]
+pub(crate) struct WasiCrypto {
+    keystore: Box<dyn KeyStore>,
+    array_outputs: Handles<Vec<u8>>,
+    secrets_managers: Handles<()>,
+    keypairs: Handles<KeyPair>,
+    publickeys: Handles<PublicKey>,
+    signatures: Handles<Signature>,
+    signature_states: Handles<SignatureState<KeyPair>>,
+    verification_states: Handles<SignatureState<PublicKey>>,
+    hash_states: Handles<HashState>,
+}
+
+impl WasiCrypto {
+    pub(crate) fn new(keystore: Box<dyn KeyStore>) -> Self {
+        Self {
+            keystore,
+            array_outputs: Handles::default(),
+            secrets_managers: Handles::default(),
+            keypairs: Handles::default(),
+            publickeys: Handles::default(),
+            signatures: Handles::default(),
+            signature_states: Handles::default(),
+            verification_states: Handles::default(),
+            hash_states: Handles::default(),
+        }
+    }
+}
+
+impl Default for WasiCrypto {
+    fn default() -> Self {
+        Self::new(Box::new(MemoryKeyStore::new()))
+    }
+}
@@ -0,0 +1,821 @@
+//! The functions of the `wasi_ephemeral_crypto_*` namespaces.
+
+#![allow(clippy::too_many_arguments)]
+
+use super::keys::{KeyPair, PublicKey};
+use super::*;
+use crate::ptr::{Array, WasmPtr};
+use crate::WasiEnv;
+use ring::digest;
+use tracing::debug;
+use wasmer::{Exports, Function, Memory, Store, ValueType};
+
+/// An optional handle, like `opt_options` or `opt_symmetric_key`.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub(crate) struct __wasi_crypto_opt_handle_t {
+    tag: u8,
+    handle: u32,
+}
+
+unsafe impl ValueType for __wasi_crypto_opt_handle_t {}
+
+/// Run a wasi-crypto call, turning its error into a wasi-crypto errno.
+fn crypto_call(f: impl FnOnce() -> Result<(), CryptoError>) -> __wasi_crypto_errno_t {
+    match f() {
+        Ok(()) => __WASI_CRYPTO_ESUCCESS,
+        Err(err) => {
+            debug!("=> {}", err);
+            err.into_crypto_errno()
+        }
+    }
+}
+
+fn read_bytes(memory: &Memory, ptr: WasmPtr<u8, Array>, len: u32) -> Result<Vec<u8>, CryptoError> {
+    let cells = ptr
+        .deref(memory, 0, len)
+        .map_err(|_| CryptoError::GuestError)?;
+    Ok(cells.iter().map(|cell| cell.get()).collect())
+}
+
+fn write_bytes(
+    memory: &Memory,
+    ptr: WasmPtr<u8, Array>,
+    max_len: u32,
+    bytes: &[u8],
+) -> Result<(), CryptoError> {
+    if bytes.len() > max_len as usize {
+        return Err(CryptoError::Overflow);
+    }
+    let cells = ptr
+        .deref(memory, 0, bytes.len() as u32)
+        .map_err(|_| CryptoError::GuestError)?;
+    for (cell, byte) in cells.iter().zip(bytes) {
+        cell.set(*byte);
+    }
+    Ok(())
+}
+
+fn write_value<T: Copy + ValueType>(
+    memory: &Memory,
+    ptr: WasmPtr<T>,
+    value: T,
+) -> Result<(), CryptoError> {
+    ptr.deref(memory)
+        .map_err(|_| CryptoError::GuestError)?
+        .set(value);
+    Ok(())
+}
+
+/// Check that the optional options are `none`.
+fn check_no_options(
+    memory: &Memory,
+    options: WasmPtr<__wasi_crypto_opt_handle_t>,
+) -> Result<(), CryptoError> {
+    let options = options
+        .deref(memory)
+        .map_err(|_| CryptoError::GuestError)?
+        .get();
+    if options.tag == __WASI_CRYPTO_OPT_NONE {
+        Ok(())
+    } else {
+        Err(CryptoError::UnsupportedOption)
+    }
+}
+
+fn read_signature_algorithm(
+    memory: &Memory,
+    algorithm_type: u32,
+    algorithm: WasmPtr<u8, Array>,
+    algorithm_len: u32,
+) -> Result<SignatureAlgorithm, CryptoError> {
+    if algorithm_type != u32::from(__WASI_CRYPTO_ALGORITHM_TYPE_SIGNATURES) {
+        return Err(CryptoError::UnsupportedAlgorithm);
+    }
+    read_signature_algorithm_name(memory, algorithm, algorithm_len)
+}
+
+fn read_signature_algorithm_name(
+    memory: &Memory,
+    algorithm: WasmPtr<u8, Array>,
+    algorithm_len: u32,
+) -> Result<SignatureAlgorithm, CryptoError> {
+    let name = read_bytes(memory, algorithm, algorithm_len)?;
+    std::str::from_utf8(&name)
+        .ok()
+        .and_then(SignatureAlgorithm::from_name)
+        .ok_or(CryptoError::UnsupportedAlgorithm)
+}
+
+/// ### `array_output_len()`
+/// Get the length of an array output.
+pub(crate) fn array_output_len(
+    env: &WasiEnv,
+    array_output: u32,
+    len: WasmPtr<u32>,
+) -> __wasi_crypto_errno_t {
+    debug!(
+        "wasi_crypto::array_output_len: array_output={}",
+        array_output
+    );
+    crypto_call(|| {
+        let (memory, state) = env.get_memory_and_wasi_state(0);
+        let output_len = state.crypto.array_outputs.get(array_output)?.len();
+        write_value(memory, len, output_len as u32)
+    })
+}
+
+/// ### `array_output_pull()`
+/// Copy an array output to `buf`, and close it.
+pub(crate) fn array_output_pull(
+    env: &WasiEnv,
+    array_output: u32,
+    buf: WasmPtr<u8, Array>,
+    buf_len: u32,
+    len: WasmPtr<u32>,
+) -> __wasi_crypto_errno_t {
+    debug!(
+        "wasi_crypto::array_output_pull: array_output={}",
+        array_output
+    );
+    crypto_call(|| {
+        let (memory, mut state) = env.get_memory_and_wasi_state(0);
+        let outputs = &mut state.crypto.array_outputs;
+        let output = outputs.get(array_output)?;
+        write_bytes(memory, buf, buf_len, output)?;
+        write_value(memory, len, output.len() as u32)?;
+        outputs.remove(array_output)?;
+        Ok(())
+    })
+}
+
+/// ### `secrets_manager_open()`
+/// Open the key store of the host.
+pub(crate) fn secrets_manager_open(
+    env: &WasiEnv,
+    options: WasmPtr<__wasi_crypto_opt_handle_t>,
+    secrets_manager: WasmPtr<u32>,
+) -> __wasi_crypto_errno_t {
+    debug!("wasi_crypto::secrets_manager_open");
+    crypto_call(|| {
+        let (memory, mut state) = env.get_memory_and_wasi_state(0);
+        check_no_options(memory, options)?;
+        let handle = state.crypto.secrets_managers.insert(())?;
+        write_value(memory, secrets_manager, handle)
+    })
+}
+
+/// ### `secrets_manager_close()`
+pub(crate) fn secrets_manager_close(env: &WasiEnv, secrets_manager: u32) -> __wasi_crypto_errno_t {
+    debug!("wasi_crypto::secrets_manager_close");
+    crypto_call(|| env.state().crypto.secrets_managers.remove(secrets_manager))
+}
+
+/// ### `keypair_generate()`
+/// Generate a key pair of `algorithm`.
+pub(crate) fn keypair_generate(
+    env: &WasiEnv,
+    algorithm_type: u32,
+    algorithm: WasmPtr<u8, Array>,
+    algorithm_len: u32,
+    options: WasmPtr<__wasi_crypto_opt_handle_t>,
+    keypair: WasmPtr<u32>,
+) -> __wasi_crypto_errno_t {
+    debug!("wasi_crypto::keypair_generate");
+    crypto_call(|| {
+        let (memory, mut state) = env.get_memory_and_wasi_state(0);
+        let algorithm = read_signature_algorithm(memory, algorithm_type, algorithm, algorithm_len)?;
+        check_no_options(memory, options)?;
+        let pkcs8 = algorithm.generate_pkcs8()?;
+        let handle = state
+            .crypto
+            .keypairs
+            .insert(KeyPair::Local { algorithm, pkcs8 })?;
+        write_value(memory, keypair, handle)
+    })
+}
+
+/// ### `keypair_import()`
+/// Import a key pair of `algorithm` encoded in PKCS#8.
+pub(crate) fn keypair_import(
+    env: &WasiEnv,
+    algorithm_type: u32,
+    algorithm: WasmPtr<u8, Array>,
+    algorithm_len: u32,
+    encoded: WasmPtr<u8, Array>,
+    encoded_len: u32,
+    encoding: u32,
+    keypair: WasmPtr<u32>,
+) -> __wasi_crypto_errno_t {
+    debug!("wasi_crypto::keypair_import");
+    crypto_call(|| {
+        let (memory, mut state) = env.get_memory_and_wasi_state(0);
+        let algorithm = read_signature_algorithm(memory, algorithm_type, algorithm, algorithm_len)?;
+        if encoding != u32::from(__WASI_CRYPTO_KEYPAIR_ENCODING_PKCS8) {
+            return Err(CryptoError::UnsupportedEncoding);
+        }
+        let pkcs8 = read_bytes(memory, encoded, encoded_len)?;
+        algorithm.public_key(&pkcs8)?;
+        let handle = state
+            .crypto
+            .keypairs
+            .insert(KeyPair::Local { algorithm, pkcs8 })?;
+        write_value(memory, keypair, handle)
+    })
+}
+
+/// ### `keypair_store()`
+/// Store a key pair in the key store, and write its id to `keypair_id`.
+pub(crate) fn keypair_store(
+    env: &WasiEnv,
+    secrets_manager: u32,
+    keypair: u32,
+    keypair_id: WasmPtr<u8, Array>,
+    keypair_id_max_len: u32,
+) -> __wasi_crypto_errno_t {
+    debug!("wasi_crypto::keypair_store: keypair={}", keypair);
+    crypto_call(|| {
+        let (memory, mut state) = env.get_memory_and_wasi_state(0);
+        let crypto = &mut state.crypto;
+        crypto.secrets_managers.get(secrets_manager)?;
+        let (algorithm, pkcs8) = match crypto.keypairs.get(keypair)? {
+            KeyPair::Local { algorithm, pkcs8 } => (*algorithm, pkcs8.clone()),
+            KeyPair::Stored { .. } => return Err(CryptoError::ProhibitedOperation),
+        };
+        let id = crypto.keystore.store(algorithm, &pkcs8)?;
+        write_bytes(memory, keypair_id, keypair_id_max_len, &id)?;
+        *crypto.keypairs.get_mut(keypair)? = KeyPair::Stored { algorithm, id };
+        Ok(())
+    })
+}
+
+/// ### `keypair_id()`
+/// Get the id of a stored key pair, and its version, which is always 0.
+pub(crate) fn keypair_id(
+    env: &WasiEnv,
+    keypair: u32,
+    keypair_id: WasmPtr<u8, Array>,
+    keypair_id_max_len: u32,
+    keypair_id_len: WasmPtr<u32>,
+    version: WasmPtr<u64>,
+) -> __wasi_crypto_errno_t {
+    debug!("wasi_crypto::keypair_id: keypair={}", keypair);
+    crypto_call(|| {
+        let (memory, state) = env.get_memory_and_wasi_state(0);
+        let id = match state.crypto.keypairs.get(keypair)? {
+            KeyPair::Stored { id, .. } => id,
+            KeyPair::Local { .. } => return Err(CryptoError::UnsupportedFeature),
+        };
+        write_bytes(memory, keypair_id, keypair_id_max_len, id)?;
+        write_value(memory, keypair_id_len, id.len() as u32)?;
+        write_value(memory, version, 0)
+    })
+}
+
+/// ### `keypair_from_id()`
+/// Get a key pair of the key store from its id; the version is ignored.
+pub(crate) fn keypair_from_id(
+    env: &WasiEnv,
+    secrets_manager: u32,
+    keypair_id: WasmPtr<u8, Array>,
+    keypair_id_len: u32,
+    _version: u64,
+    keypair: WasmPtr<u32>,
+) -> __wasi_crypto_errno_t {
+    debug!("wasi_crypto::keypair_from_id");
+    crypto_call(|| {
+        let (memory, mut state) = env.get_memory_and_wasi_state(0);
+        let crypto = &mut state.crypto;
+        crypto.secrets_managers.get(secrets_manager)?;
+        let id = read_bytes(memory, keypair_id, keypair_id_len)?;
+        let (algorithm, _) = crypto.keystore.public_key(&id)?;
+        let handle = crypto.keypairs.insert(KeyPair::Stored { algorithm, id })?;
+        write_value(memory, keypair, handle)
+    })
+}
+
+/// ### `keypair_publickey()`
+/// Get the public key of a key pair.
+pub(crate) fn keypair_publickey(
+    env: &WasiEnv,
+    keypair: u32,
+    publickey: WasmPtr<u32>,
+) -> __wasi_crypto_errno_t {
+    debug!("wasi_crypto::keypair_publickey: keypair={}", keypair);
+    crypto_call(|| {
+        let (memory, mut state) = env.get_memory_and_wasi_state(0);
+        let crypto = &mut state.crypto;
+        let (algorithm, bytes) = match crypto.keypairs.get(keypair)? {
+            KeyPair::Local { algorithm, pkcs8 } => (*algorithm, algorithm.public_key(pkcs8)?),
+            KeyPair::Stored { id, .. } => crypto.keystore.public_key(id)?,
+        };
+        let handle = crypto.publickeys.insert(PublicKey { algorithm, bytes })?;
+        write_value(memory, publickey, handle)
+    })
+}
+
+/// ### `keypair_export()`
+/// Export a key pair generated or imported by the module, in PKCS#8, to an
+/// array output. The key pairs of the key store can't be exported.
+pub(crate) fn keypair_export(
+    env: &WasiEnv,
+    keypair: u32,
+    encoding: u32,
+    array_output: WasmPtr<u32>,
+) -> __wasi_crypto_errno_t {
+    debug!("wasi_crypto::keypair_export: keypair={}", keypair);
+    crypto_call(|| {
+        let (memory, mut state) = env.get_memory_and_wasi_state(0);
+        let crypto = &mut state.crypto;
+        let pkcs8 = match crypto.keypairs.get(keypair)? {
+            KeyPair::Local { pkcs8, .. } => pkcs8.clone(),
+            KeyPair::Stored { .. } => return Err(CryptoError::ProhibitedOperation),
+        };
+        if encoding != u32::from(__WASI_CRYPTO_KEYPAIR_ENCODING_PKCS8) {
+            return Err(CryptoError::UnsupportedEncoding);
+        }
+        let handle = crypto.array_outputs.insert(pkcs8)?;
+        write_value(memory, array_output, handle)
+    })
+}
+
+/// ### `keypair_close()`
+pub(crate) fn keypair_close(env: &WasiEnv, keypair: u32) -> __wasi_crypto_errno_t {
+    debug!("wasi_crypto::keypair_close: keypair={}", keypair);
+    crypto_call(|| env.state().crypto.keypairs.remove(keypair).map(drop))
+}
+
+/// ### `publickey_import()`
+/// Import a raw public key of `algorithm`.
+pub(crate) fn publickey_import(
+    env: &WasiEnv,
+    algorithm_type: u32,
+    algorithm: WasmPtr<u8, Array>,
+    algorithm_len: u32,
+    encoded: WasmPtr<u8, Array>,
+    encoded_len: u32,
+    encoding: u32,
+    publickey: WasmPtr<u32>,
+) -> __wasi_crypto_errno_t {
+    debug!("wasi_crypto::publickey_import");
+    crypto_call(|| {
+        let (memory, mut state) = env.get_memory_and_wasi_state(0);
+        let algorithm = read_signature_algorithm(memory, algorithm_type, algorithm, algorithm_len)?;
+        let encoding = encoding as __wasi_crypto_publickey_encoding_t;
+        if encoding != __WASI_CRYPTO_PUBLICKEY_ENCODING_RAW
+            && encoding != __WASI_CRYPTO_PUBLICKEY_ENCODING_SEC
+        {
+            return Err(CryptoError::UnsupportedEncoding);
+        }
+        let bytes = read_bytes(memory, encoded, encoded_len)?;
+        if bytes.len() != algorithm.public_key_len() {
+            return Err(CryptoError::InvalidKey);
+        }
+        let handle = state
+            .crypto
+            .publickeys
+            .insert(PublicKey { algorithm, bytes })?;
+        write_value(memory, publickey, handle)
+    })
+}
+
+/// ### `publickey_export()`
+/// Export a raw public key to an array output.
+pub(crate) fn publickey_export(
+    env: &WasiEnv,
+    publickey: u32,
+    encoding: u32,
+    array_output: WasmPtr<u32>,
+) -> __wasi_crypto_errno_t {
+    debug!("wasi_crypto::publickey_export: publickey={}", publickey);
+    crypto_call(|| {
+        let (memory, mut state) = env.get_memory_and_wasi_state(0);
+        let crypto = &mut state.crypto;
+        let bytes = crypto.publickeys.get(publickey)?.bytes.clone();
+        let encoding = encoding as __wasi_crypto_publickey_encoding_t;
+        if encoding != __WASI_CRYPTO_PUBLICKEY_ENCODING_RAW
+            && encoding != __WASI_CRYPTO_PUBLICKEY_ENCODING_SEC
+        {
+            return Err(CryptoError::UnsupportedEncoding);
+        }
+        let handle = crypto.array_outputs.insert(bytes)?;
+        write_value(memory, array_output, handle)
+    })
+}
+
+/// ### `publickey_close()`
+pub(crate) fn publickey_close(env: &WasiEnv, publickey: u32) -> __wasi_crypto_errno_t {
+    debug!("wasi_crypto::publickey_close: publickey={}", publickey);
+    crypto_call(|| env.state().crypto.publickeys.remove(publickey).map(drop))
+}
+
+/// ### `signature_export()`
+/// Export a raw signature to an array output.
+pub(crate) fn signature_export(
+    env: &WasiEnv,
+    signature: u32,
+    encoding: u32,
+    array_output: WasmPtr<u32>,
+) -> __wasi_crypto_errno_t {
+    debug!("wasi_crypto::signature_export: signature={}", signature);
+    crypto_call(|| {
+        let (memory, mut state) = env.get_memory_and_wasi_state(0);
+        let crypto = &mut state.crypto;
+        let bytes = crypto.signatures.get(signature)?.bytes.clone();
+        if encoding != u32::from(__WASI_CRYPTO_SIGNATURE_ENCODING_RAW) {
+            return Err(CryptoError::UnsupportedEncoding);
+        }
+        let handle = crypto.array_outputs.insert(bytes)?;
+        write_value(memory, array_output, handle)
+    })
+}
+
+/// ### `signature_import()`
+/// Import a raw signature of `algorithm`.
+pub(crate) fn signature_import(
+    env: &WasiEnv,
+    algorithm: WasmPtr<u8, Array>,
+    algorithm_len: u32,
+    encoded: WasmPtr<u8, Array>,
+    encoded_len: u32,
+    encoding: u32,
+    signature: WasmPtr<u32>,
+) -> __wasi_crypto_errno_t {
+    debug!("wasi_crypto::signature_import");
+    crypto_call(|| {
+        let (memory, mut state) = env.get_memory_and_wasi_state(0);
+        let algorithm = read_signature_algorithm_name(memory, algorithm, algorithm_len)?;
+        if encoding != u32::from(__WASI_CRYPTO_SIGNATURE_ENCODING_RAW) {
+            return Err(CryptoError::UnsupportedEncoding);
+        }
+        let bytes = read_bytes(memory, encoded, encoded_len)?;
+        if bytes.len() != algorithm.signature_len() {
+            return Err(CryptoError::InvalidSignature);
+        }
+        let handle = state
+            .crypto
+            .signatures
+            .insert(Signature { algorithm, bytes })?;
+        write_value(memory, signature, handle)
+    })
+}
+
+/// ### `signature_close()`
+pub(crate) fn signature_close(env: &WasiEnv, signature: u32) -> __wasi_crypto_errno_t {
+    debug!("wasi_crypto::signature_close: signature={}", signature);
+    crypto_call(|| env.state().crypto.signatures.remove(signature).map(drop))
+}
+
+/// ### `signature_state_open()`
+/// Start signing a message with a key pair.
+pub(crate) fn signature_state_open(
+    env: &WasiEnv,
+    keypair: u32,
+    state_ptr: WasmPtr<u32>,
+) -> __wasi_crypto_errno_t {
+    debug!("wasi_crypto::signature_state_open: keypair={}", keypair);
+    crypto_call(|| {
+        let (memory, mut state) = env.get_memory_and_wasi_state(0);
+        let crypto = &mut state.crypto;
+        let key = crypto.keypairs.get(keypair)?.clone();
+        let handle = crypto.signature_states.insert(SignatureState {
+            key,
+            message: Vec::new(),
+        })?;
+        write_value(memory, state_ptr, handle)
+    })
+}
+
+/// ### `signature_state_update()`
+/// Append `input` to the message to sign.
+pub(crate) fn signature_state_update(
+    env: &WasiEnv,
+    signature_state: u32,
+    input: WasmPtr<u8, Array>,
+    input_len: u32,
+) -> __wasi_crypto_errno_t {
+    debug!(
+        "wasi_crypto::signature_state_update: state={}",
+        signature_state
+    );
+    crypto_call(|| {
+        let (memory, mut state) = env.get_memory_and_wasi_state(0);
+        let input = read_bytes(memory, input, input_len)?;
+        state
+            .crypto
+            .signature_states
+            .get_mut(signature_state)?
+            .message
+            .extend_from_slice(&input);
+        Ok(())
+    })
+}
+
+/// ### `signature_state_sign()`
+/// Sign the message.
+pub(crate) fn signature_state_sign(
+    env: &WasiEnv,
+    signature_state: u32,
+    signature: WasmPtr<u32>,
+) -> __wasi_crypto_errno_t {
+    debug!(
+        "wasi_crypto::signature_state_sign: state={}",
+        signature_state
+    );
+    crypto_call(|| {
+        let (memory, mut state) = env.get_memory_and_wasi_state(0);
+        let crypto = &mut state.crypto;
+        let signature_state = crypto.signature_states.get(signature_state)?;
+        let algorithm = signature_state.key.algorithm();
+        let bytes = match &signature_state.key {
+            KeyPair::Local { pkcs8, .. } => algorithm.sign(pkcs8, &signature_state.message)?,
+            KeyPair::Stored { id, .. } => crypto.keystore.sign(id, &signature_state.message)?,
+        };
+        let handle = crypto.signatures.insert(Signature { algorithm, bytes })?;
+        write_value(memory, signature, handle)
+    })
+}
+
+/// ### `signature_state_close()`
+pub(crate) fn signature_state_close(env: &WasiEnv, signature_state: u32) -> __wasi_crypto_errno_t {
+    debug!(
+        "wasi_crypto::signature_state_close: state={}",
+        signature_state
+    );
+    crypto_call(|| {
+        env.state()
+            .crypto
+            .signature_states
+            .remove(signature_state)
+            .map(drop)
+    })
+}
+
+/// ### `signature_verification_state_open()`
+/// Start verifying the signature of a message with a public key.
+pub(crate) fn signature_verification_state_open(
+    env: &WasiEnv,
+    publickey: u32,
+    state_ptr: WasmPtr<u32>,
+) -> __wasi_crypto_errno_t {
+    debug!(
+        "wasi_crypto::signature_verification_state_open: publickey={}",
+        publickey
+    );
+    crypto_call(|| {
+        let (memory, mut state) = env.get_memory_and_wasi_state(0);
+        let crypto = &mut state.crypto;
+        let key = crypto.publickeys.get(publickey)?.clone();
+        let handle = crypto.verification_states.insert(SignatureState {
+            key,
+            message: Vec::new(),
+        })?;
+        write_value(memory, state_ptr, handle)
+    })
+}
+
+/// ### `signature_verification_state_update()`
+/// Append `input` to the message to verify.
+pub(crate) fn signature_verification_state_update(
+    env: &WasiEnv,
+    verification_state: u32,
+    input: WasmPtr<u8, Array>,
+    input_len: u32,
+) -> __wasi_crypto_errno_t {
+    debug!(
+        "wasi_crypto::signature_verification_state_update: state={}",
+        verification_state
+    );
+    crypto_call(|| {
+        let (memory, mut state) = env.get_memory_and_wasi_state(0);
+        let input = read_bytes(memory, input, input_len)?;
+        state
+            .crypto
+            .verification_states
+            .get_mut(verification_state)?
+            .message
+            .extend_from_slice(&input);
+        Ok(())
+    })
+}
+
+/// ### `signature_verification_state_verify()`
+/// Verify `signature` of the message, failing with `verification_failed`
+/// if it's not valid.
+pub(crate) fn signature_verification_state_verify(
+    env: &WasiEnv,
+    verification_state: u32,
+    signature: u32,
+) -> __wasi_crypto_errno_t {
+    debug!(
+        "wasi_crypto::signature_verification_state_verify: state={}",
+        verification_state
+    );
+    crypto_call(|| {
+        let state = env.state();
+        let crypto = &state.crypto;
+        let verification_state = crypto.verification_states.get(verification_state)?;
+        let signature = crypto.signatures.get(signature)?;
+        let key = &verification_state.key;
+        if signature.algorithm != key.algorithm {
+            return Err(CryptoError::InvalidSignature);
+        }
+        key.algorithm
+            .verify(&key.bytes, &verification_state.message, &signature.bytes)
+    })
+}
+
+/// ### `signature_verification_state_close()`
+pub(crate) fn signature_verification_state_close(
+    env: &WasiEnv,
+    verification_state: u32,
+) -> __wasi_crypto_errno_t {
+    debug!(
+        "wasi_crypto::signature_verification_state_close: state={}",
+        verification_state
+    );
+    crypto_call(|| {
+        env.state()
+            .crypto
+            .verification_states
+            .remove(verification_state)
+            .map(drop)
+    })
+}
+
+/// ### `symmetric_state_open()`
+/// Start hashing with the hash function `algorithm`, which doesn't take a
+/// key.
+pub(crate) fn symmetric_state_open(
+    env: &WasiEnv,
+    algorithm: WasmPtr<u8, Array>,
+    algorithm_len: u32,
+    key: WasmPtr<__wasi_crypto_opt_handle_t>,
+    options: WasmPtr<__wasi_crypto_opt_handle_t>,
+    state_ptr: WasmPtr<u32>,
+) -> __wasi_crypto_errno_t {
+    debug!("wasi_crypto::symmetric_state_open");
+    crypto_call(|| {
+        let (memory, mut state) = env.get_memory_and_wasi_state(0);
+        let name = read_bytes(memory, algorithm, algorithm_len)?;
+        let algorithm = match &name[..] {
+            b"SHA-256" => &digest::SHA256,
+            b"SHA-384" => &digest::SHA384,
+            b"SHA-512" => &digest::SHA512,
+            b"SHA-512/256" => &digest::SHA512_256,
+            _ => return Err(CryptoError::UnsupportedAlgorithm),
+        };
+        let key = key
+            .deref(memory)
+            .map_err(|_| CryptoError::GuestError)?
+            .get();
+        if key.tag != __WASI_CRYPTO_OPT_NONE {
+            return Err(CryptoError::KeyNotSupported);
+        }
+        check_no_options(memory, options)?;
+        let handle = state
+            .crypto
+            .hash_states
+            .insert(HashState(digest::Context::new(algorithm)))?;
+        write_value(memory, state_ptr, handle)
+    })
+}
+
+/// ### `symmetric_state_absorb()`
+/// Hash `data`.
+pub(crate) fn symmetric_state_absorb(
+    env: &WasiEnv,
+    symmetric_state: u32,
+    data: WasmPtr<u8, Array>,
+    data_len: u32,
+) -> __wasi_crypto_errno_t {
+    debug!(
+        "wasi_crypto::symmetric_state_absorb: state={}",
+        symmetric_state
+    );
+    crypto_call(|| {
+        let (memory, mut state) = env.get_memory_and_wasi_state(0);
+        let data = read_bytes(memory, data, data_len)?;
+        state
+            .crypto
+            .hash_states
+            .get_mut(symmetric_state)?
+            .0
+            .update(&data);
+        Ok(())
+    })
+}
+
+/// ### `symmetric_state_squeeze()`
+/// Write the first `out_len` bytes of the hash of the data absorbed so far
+/// to `out`.
+pub(crate) fn symmetric_state_squeeze(
+    env: &WasiEnv,
+    symmetric_state: u32,
+    out: WasmPtr<u8, Array>,
+    out_len: u32,
+) -> __wasi_crypto_errno_t {
+    debug!(
+        "wasi_crypto::symmetric_state_squeeze: state={}",
+        symmetric_state
+    );
+    crypto_call(|| {
+        let (memory, state) = env.get_memory_and_wasi_state(0);
+        let hash = state
+            .crypto
+            .hash_states
+            .get(symmetric_state)?
+            .0
+            .clone()
+            .finish();
+        let hash = hash
+            .as_ref()
+            .get(..out_len as usize)
+            .ok_or(CryptoError::InvalidLength)?;
+        write_bytes(memory, out, out_len, hash)
+    })
+}
+
+/// ### `symmetric_state_close()`
+pub(crate) fn symmetric_state_close(env: &WasiEnv, symmetric_state: u32) -> __wasi_crypto_errno_t {
+    debug!(
+        "wasi_crypto::symmetric_state_close: state={}",
+        symmetric_state
+    );
+    crypto_call(|| {
+        env.state()
+            .crypto
+            .hash_states
+            .remove(symmetric_state)
+            .map(drop)
+    })
+}
+
+/// Create the `wasi_ephemeral_crypto_*` imports, by namespace.
+pub(crate) fn exports(store: &Store, env: &WasiEnv) -> Vec<(&'static str, Exports)> {
+    macro_rules! exports {
+        ($($name:ident),* $(,)?) => {{
+            let mut exports = Exports::new();
+            $(
+                exports.insert(
+                    stringify!($name),
+                    Function::new_native_with_env(store, env.clone(), $name),
+                );
+            )*
+            exports
+        }};
+    }
+
+    vec![
+        (
+            "wasi_ephemeral_crypto_common",
+            exports!(
+                array_output_len,
+                array_output_pull,
+                secrets_manager_open,
+                secrets_manager_close,
+            ),
+        ),
+        (
+            "wasi_ephemeral_crypto_asymmetric_common",
+            exports!(
+                keypair_generate,
+                keypair_import,
+                keypair_store,
+                keypair_id,
+                keypair_from_id,
+                keypair_publickey,
+                keypair_export,
+                keypair_close,
+                publickey_import,
+                publickey_export,
+                publickey_close,
+            ),
+        ),
+        (
+            "wasi_ephemeral_crypto_signatures",
+            exports!(
+                signature_export,
+                signature_import,
+                signature_close,
+                signature_state_open,
+                signature_state_update,
+                signature_state_sign,
+                signature_state_close,
+                signature_verification_state_open,
+                signature_verification_state_update,
+                signature_verification_state_verify,
+                signature_verification_state_close,
+            ),
+        ),
+        (
+            "wasi_ephemeral_crypto_symmetric",
+            exports!(
+                symmetric_state_open,
+                symmetric_state_absorb,
+                symmetric_state_squeeze,
+                symmetric_state_close,
+            ),
+        ),
+    ]
+}
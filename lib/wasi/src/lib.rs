@@ -14,22 +14,39 @@
 
 #[macro_use]
 mod macros;
+#[cfg(feature = "wasi-crypto")]
+pub mod crypto;
+#[cfg(feature = "wasi-nn")]
+pub mod nn;
+mod policy;
 mod ptr;
 mod state;
 mod syscalls;
+mod threads;
+mod trace;
 mod utils;
 
+use crate::state::{validate_arg, validate_env};
 use crate::syscalls::*;
+use crate::threads::WasiThreads;
+use crate::trace::Syscalls;
 
+pub use crate::policy::{SyscallAllowlist, SyscallPolicy};
 pub use crate::state::{
-    Fd, Pipe, Stderr, Stdin, Stdout, WasiFile, WasiFs, WasiFsError, WasiState, WasiStateBuilder,
-    WasiStateCreationError, ALL_RIGHTS, VIRTUAL_ROOT_FD,
+    bounded_pipe, Fd, HostClock, HostFs, HostRandom, LogLine, LogSink, LogStream, ManualClock,
+    MemFile, MemFs, NetworkPolicy, OffsetClock, OpenFd, OverlayFile, OverlayFs, Pipe, PipeReader,
+    PipeWriter, SeededRandom, Stderr, Stdin, Stdout, SymlinkPolicy, VirtualDirEntry, VirtualFs,
+    VirtualMetadata, VirtualOpenOptions, WasiClock, WasiFile, WasiFs, WasiFsError, WasiFsQuota,
+    WasiRandom, WasiState, WasiStateBuilder, WasiStateCreationError, ALL_RIGHTS, VIRTUAL_ROOT_FD,
 };
 pub use crate::syscalls::types;
+pub use crate::trace::{errno_name, StraceTracer, WasiTracer};
 pub use crate::utils::{get_wasi_version, is_wasi_module, WasiVersion};
 
 use thiserror::Error;
-use wasmer::{imports, Function, ImportObject, LazyInit, Memory, Module, Store, WasmerEnv};
+use wasmer::{
+    imports, Function, ImportObject, LazyInit, Memory, Module, RuntimeError, Store, WasmerEnv,
+};
 #[cfg(all(target_os = "macos", target_arch = "aarch64",))]
 use wasmer::{FunctionType, ValType};
 
@@ -38,11 +55,33 @@ use std::sync::{Arc, Mutex, MutexGuard};
 /// This is returned in `RuntimeError`.
 /// Use `downcast` or `downcast_ref` to retrieve the `ExitCode`.
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum WasiError {
     #[error("WASI exited with code: {0}")]
     Exit(syscalls::types::__wasi_exitcode_t),
     #[error("The WASI version could not be determined")]
     UnknownWasiVersion,
+    #[error("The module can't use WASI threads: {0}")]
+    InvalidThreadsModule(String),
+}
+
+impl WasiError {
+    /// Get the exit code of a module from the error returned by a call into
+    /// it, if the module exited by calling `proc_exit`.
+    ///
+    /// ```
+    /// # use wasmer::RuntimeError;
+    /// # use wasmer_wasi::WasiError;
+    /// let error = RuntimeError::user(Box::new(WasiError::Exit(0)));
+    /// assert_eq!(WasiError::exit_code(&error), Some(0));
+    /// assert_eq!(WasiError::exit_code(&RuntimeError::new("trap")), None);
+    /// ```
+    pub fn exit_code(error: &RuntimeError) -> Option<syscalls::types::__wasi_exitcode_t> {
+        match error.downcast_ref::<WasiError>()? {
+            WasiError::Exit(code) => Some(*code),
+            _ => None,
+        }
+    }
 }
 
 /// The environment provided to the WASI imports.
@@ -57,6 +96,8 @@ pub struct WasiEnv {
     pub state: Arc<Mutex<WasiState>>,
     #[wasmer(export)]
     memory: LazyInit<Memory>,
+    /// The threads of the module, if it uses wasi-threads.
+    threads: Option<WasiThreads>,
 }
 
 impl WasiEnv {
@@ -64,6 +105,7 @@ impl WasiEnv {
         Self {
             state: Arc::new(Mutex::new(state)),
             memory: LazyInit::new(),
+            threads: None,
         }
     }
 
@@ -76,6 +118,38 @@ impl WasiEnv {
         ))
     }
 
+    /// Create the [`ImportObject`] of a module using wasi-threads, like the
+    /// modules built for the `wasm32-wasi-threads` target.
+    ///
+    /// Besides the WASI imports, it provides the `wasi.thread-spawn` import,
+    /// and the shared memory imported by the module, which is created from
+    /// the type of the import. The module must also export its memory.
+    ///
+    /// Every thread spawned by the module runs a new instance of `module`
+    /// sharing this [`WasiEnv`]; use [`WasiEnv::join_threads`] to wait for
+    /// them.
+    pub fn import_object_with_threads(
+        &mut self,
+        module: &Module,
+    ) -> Result<ImportObject, WasiError> {
+        let wasi_version = get_wasi_version(module, false).ok_or(WasiError::UnknownWasiVersion)?;
+        let threads = WasiThreads::new(module, wasi_version)?;
+        self.threads = Some(threads.clone());
+        Ok(threads.import_object(self))
+    }
+
+    /// Wait for the threads spawned by the module, including the threads
+    /// spawned while waiting.
+    ///
+    /// Returns the first error of a thread, like a trap or a
+    /// [`WasiError::Exit`].
+    pub fn join_threads(&self) -> Result<(), RuntimeError> {
+        match &self.threads {
+            Some(threads) => threads.join(),
+            None => Ok(()),
+        }
+    }
+
     /// Get the WASI state
     ///
     /// Be careful when using this in host functions that call into Wasm:
@@ -85,6 +159,11 @@ impl WasiEnv {
         self.state.lock().unwrap()
     }
 
+    /// List the open fds of the module, for example to find leaked ones.
+    pub fn open_fds(&self) -> Vec<OpenFd> {
+        self.state().fs.open_fds()
+    }
+
     // TODO: delete this method before 1.0.0 release
     #[doc(hidden)]
     #[deprecated(since = "1.0.0-beta1", note = "Please use the `state` method instead")]
@@ -92,6 +171,63 @@ impl WasiEnv {
         self.state.lock().unwrap()
     }
 
+    /// Set the environment variable `key` of the module to `value`.
+    ///
+    /// The module sees the change the next time it reads its environment,
+    /// for example when its `_start` function is called again.
+    pub fn set_env<Key, Value>(&self, key: Key, value: Value) -> Result<(), WasiStateCreationError>
+    where
+        Key: AsRef<[u8]>,
+        Value: AsRef<[u8]>,
+    {
+        let (key, value) = (key.as_ref(), value.as_ref());
+        validate_env(key, value)?;
+        let mut env = Vec::with_capacity(key.len() + value.len() + 1);
+        env.extend_from_slice(key);
+        env.push(b'=');
+        env.extend_from_slice(value);
+
+        let mut state = self.state();
+        match state.envs.iter_mut().find(|env| is_env_key(env, key)) {
+            Some(existing) => *existing = env,
+            None => state.envs.push(env),
+        }
+        Ok(())
+    }
+
+    /// Remove the environment variable `key` of the module.
+    ///
+    /// Returns whether the variable was set.
+    pub fn remove_env<Key: AsRef<[u8]>>(&self, key: Key) -> bool {
+        let mut state = self.state();
+        let len = state.envs.len();
+        state.envs.retain(|env| !is_env_key(env, key.as_ref()));
+        state.envs.len() != len
+    }
+
+    /// Replace the arguments of the module, except its program name.
+    ///
+    /// Like for [`WasiEnv::set_env`], the module sees them the next time it
+    /// reads them.
+    pub fn set_args<I, Arg>(&self, args: I) -> Result<(), WasiStateCreationError>
+    where
+        I: IntoIterator<Item = Arg>,
+        Arg: AsRef<[u8]>,
+    {
+        let args = args
+            .into_iter()
+            .map(|arg| arg.as_ref().to_vec())
+            .collect::<Vec<_>>();
+        for (i, arg) in args.iter().enumerate() {
+            validate_arg(i + 1, arg)?;
+        }
+
+        let mut state = self.state();
+        state.args.truncate(1);
+        state.args.extend(args);
+        Ok(())
+    }
+
     /// Get a reference to the memory
     pub fn memory(&self) -> &Memory {
         self.memory_ref()
@@ -108,27 +244,56 @@ impl WasiEnv {
     }
 }
 
+/// Whether `env`, formatted as `key=value`, is the variable `key`.
+fn is_env_key(env: &[u8], key: &[u8]) -> bool {
+    env.starts_with(key) && env.get(key.len()) == Some(&b'=')
+}
+
 /// Create an [`ImportObject`] with an existing [`WasiEnv`]. `WasiEnv`
 /// needs a [`WasiState`], that can be constructed from a
 /// [`WasiStateBuilder`](state::WasiStateBuilder).
+///
+/// With a [deterministic](Store::is_deterministic) `store`, the clock and
+/// random source of the state are made reproducible, see
+/// [`WasiState::make_deterministic`].
 pub fn generate_import_object_from_env(
     store: &Store,
     wasi_env: WasiEnv,
     version: WasiVersion,
 ) -> ImportObject {
-    match version {
+    if store.is_deterministic() {
+        wasi_env.state().make_deterministic();
+    }
+    #[cfg(feature = "wasi-nn")]
+    let nn_exports = nn::exports(store, &wasi_env);
+    #[cfg(feature = "wasi-crypto")]
+    let crypto_exports = crypto::exports(store, &wasi_env);
+    #[allow(unused_mut)]
+    let mut import_object = match version {
         WasiVersion::Snapshot0 => generate_import_object_snapshot0(store, wasi_env),
         WasiVersion::Snapshot1 | WasiVersion::Latest => {
             generate_import_object_snapshot1(store, wasi_env)
         }
+    };
+    #[cfg(feature = "wasi-nn")]
+    import_object.register("wasi_ephemeral_nn", nn_exports);
+    #[cfg(feature = "wasi-crypto")]
+    for (namespace, exports) in crypto_exports {
+        import_object.register(namespace, exports);
     }
+    import_object
 }
 
 // Note: we use this wrapper because native functions with more than 9 params
 // fail on Apple Silicon (with Cranelift).
-fn get_path_open_for_store(store: &Store, env: WasiEnv) -> Function {
+fn get_path_open_for_store(store: &Store, syscalls: &Syscalls) -> Function {
+    // traced and checked syscalls are dynamic functions already
+    if syscalls.is_dynamic() {
+        return syscalls.function("path_open", path_open);
+    }
+    let env = syscalls.env();
     #[cfg(not(all(target_os = "macos", target_arch = "aarch64",)))]
-    let path_open = Function::new_native_with_env(store, env, path_open);
+    let path_open = Function::new_native_with_env(store, env.clone(), path_open);
     #[cfg(all(target_os = "macos", target_arch = "aarch64",))]
     let path_open = Function::new_with_env(
         store,
@@ -154,106 +319,113 @@ fn get_path_open_for_store(store: &Store, env: WasiEnv) -> Function {
 
 /// Combines a state generating function with the import list for legacy WASI
 fn generate_import_object_snapshot0(store: &Store, env: WasiEnv) -> ImportObject {
+    let syscalls = Syscalls::new(store, env);
     imports! {
         "wasi_unstable" => {
-            "args_get" => Function::new_native_with_env(store, env.clone(), args_get),
-            "args_sizes_get" => Function::new_native_with_env(store, env.clone(), args_sizes_get),
-            "clock_res_get" => Function::new_native_with_env(store, env.clone(), clock_res_get),
-            "clock_time_get" => Function::new_native_with_env(store, env.clone(), clock_time_get),
-            "environ_get" => Function::new_native_with_env(store, env.clone(), environ_get),
-            "environ_sizes_get" => Function::new_native_with_env(store, env.clone(), environ_sizes_get),
-            "fd_advise" => Function::new_native_with_env(store, env.clone(), fd_advise),
-            "fd_allocate" => Function::new_native_with_env(store, env.clone(), fd_allocate),
-            "fd_close" => Function::new_native_with_env(store, env.clone(), fd_close),
-            "fd_datasync" => Function::new_native_with_env(store, env.clone(), fd_datasync),
-            "fd_fdstat_get" => Function::new_native_with_env(store, env.clone(), fd_fdstat_get),
-            "fd_fdstat_set_flags" => Function::new_native_with_env(store, env.clone(), fd_fdstat_set_flags),
-            "fd_fdstat_set_rights" => Function::new_native_with_env(store, env.clone(), fd_fdstat_set_rights),
-            "fd_filestat_get" => Function::new_native_with_env(store, env.clone(), legacy::snapshot0::fd_filestat_get),
-            "fd_filestat_set_size" => Function::new_native_with_env(store, env.clone(), fd_filestat_set_size),
-            "fd_filestat_set_times" => Function::new_native_with_env(store, env.clone(), fd_filestat_set_times),
-            "fd_pread" => Function::new_native_with_env(store, env.clone(), fd_pread),
-            "fd_prestat_get" => Function::new_native_with_env(store, env.clone(), fd_prestat_get),
-            "fd_prestat_dir_name" => Function::new_native_with_env(store, env.clone(), fd_prestat_dir_name),
-            "fd_pwrite" => Function::new_native_with_env(store, env.clone(), fd_pwrite),
-            "fd_read" => Function::new_native_with_env(store, env.clone(), fd_read),
-            "fd_readdir" => Function::new_native_with_env(store, env.clone(), fd_readdir),
-            "fd_renumber" => Function::new_native_with_env(store, env.clone(), fd_renumber),
-            "fd_seek" => Function::new_native_with_env(store, env.clone(), legacy::snapshot0::fd_seek),
-            "fd_sync" => Function::new_native_with_env(store, env.clone(), fd_sync),
-            "fd_tell" => Function::new_native_with_env(store, env.clone(), fd_tell),
-            "fd_write" => Function::new_native_with_env(store, env.clone(), fd_write),
-            "path_create_directory" => Function::new_native_with_env(store, env.clone(), path_create_directory),
-            "path_filestat_get" => Function::new_native_with_env(store, env.clone(), legacy::snapshot0::path_filestat_get),
-            "path_filestat_set_times" => Function::new_native_with_env(store, env.clone(), path_filestat_set_times),
-            "path_link" => Function::new_native_with_env(store, env.clone(), path_link),
-            "path_open" => get_path_open_for_store(store, env.clone()),
-            "path_readlink" => Function::new_native_with_env(store, env.clone(), path_readlink),
-            "path_remove_directory" => Function::new_native_with_env(store, env.clone(), path_remove_directory),
-            "path_rename" => Function::new_native_with_env(store, env.clone(), path_rename),
-            "path_symlink" => Function::new_native_with_env(store, env.clone(), path_symlink),
-            "path_unlink_file" => Function::new_native_with_env(store, env.clone(), path_unlink_file),
-            "poll_oneoff" => Function::new_native_with_env(store, env.clone(), legacy::snapshot0::poll_oneoff),
-            "proc_exit" => Function::new_native_with_env(store, env.clone(), proc_exit),
-            "proc_raise" => Function::new_native_with_env(store, env.clone(), proc_raise),
-            "random_get" => Function::new_native_with_env(store, env.clone(), random_get),
-            "sched_yield" => Function::new_native_with_env(store, env.clone(), sched_yield),
-            "sock_recv" => Function::new_native_with_env(store, env.clone(), sock_recv),
-            "sock_send" => Function::new_native_with_env(store, env.clone(), sock_send),
-            "sock_shutdown" => Function::new_native_with_env(store, env.clone(), sock_shutdown),
+            "args_get" => syscalls.function("args_get", args_get),
+            "args_sizes_get" => syscalls.function("args_sizes_get", args_sizes_get),
+            "clock_res_get" => syscalls.function("clock_res_get", clock_res_get),
+            "clock_time_get" => syscalls.function("clock_time_get", clock_time_get),
+            "environ_get" => syscalls.function("environ_get", environ_get),
+            "environ_sizes_get" => syscalls.function("environ_sizes_get", environ_sizes_get),
+            "fd_advise" => syscalls.function("fd_advise", fd_advise),
+            "fd_allocate" => syscalls.function("fd_allocate", fd_allocate),
+            "fd_close" => syscalls.function("fd_close", fd_close),
+            "fd_datasync" => syscalls.function("fd_datasync", fd_datasync),
+            "fd_fdstat_get" => syscalls.function("fd_fdstat_get", fd_fdstat_get),
+            "fd_fdstat_set_flags" => syscalls.function("fd_fdstat_set_flags", fd_fdstat_set_flags),
+            "fd_fdstat_set_rights" => syscalls.function("fd_fdstat_set_rights", fd_fdstat_set_rights),
+            "fd_filestat_get" => syscalls.function("fd_filestat_get", legacy::snapshot0::fd_filestat_get),
+            "fd_filestat_set_size" => syscalls.function("fd_filestat_set_size", fd_filestat_set_size),
+            "fd_filestat_set_times" => syscalls.function("fd_filestat_set_times", fd_filestat_set_times),
+            "fd_pread" => syscalls.function("fd_pread", fd_pread),
+            "fd_prestat_get" => syscalls.function("fd_prestat_get", fd_prestat_get),
+            "fd_prestat_dir_name" => syscalls.function("fd_prestat_dir_name", fd_prestat_dir_name),
+            "fd_pwrite" => syscalls.function("fd_pwrite", fd_pwrite),
+            "fd_read" => syscalls.function("fd_read", fd_read),
+            "fd_readdir" => syscalls.function("fd_readdir", fd_readdir),
+            "fd_renumber" => syscalls.function("fd_renumber", fd_renumber),
+            "fd_seek" => syscalls.function("fd_seek", legacy::snapshot0::fd_seek),
+            "fd_sync" => syscalls.function("fd_sync", fd_sync),
+            "fd_tell" => syscalls.function("fd_tell", fd_tell),
+            "fd_write" => syscalls.function("fd_write", fd_write),
+            "path_create_directory" => syscalls.function("path_create_directory", path_create_directory),
+            "path_filestat_get" => syscalls.function("path_filestat_get", legacy::snapshot0::path_filestat_get),
+            "path_filestat_set_times" => syscalls.function("path_filestat_set_times", path_filestat_set_times),
+            "path_link" => syscalls.function("path_link", path_link),
+            "path_open" => get_path_open_for_store(store, &syscalls),
+            "path_readlink" => syscalls.function("path_readlink", path_readlink),
+            "path_remove_directory" => syscalls.function("path_remove_directory", path_remove_directory),
+            "path_rename" => syscalls.function("path_rename", path_rename),
+            "path_symlink" => syscalls.function("path_symlink", path_symlink),
+            "path_unlink_file" => syscalls.function("path_unlink_file", path_unlink_file),
+            "poll_oneoff" => syscalls.function("poll_oneoff", legacy::snapshot0::poll_oneoff),
+            "proc_exit" => syscalls.function("proc_exit", proc_exit),
+            "proc_raise" => syscalls.function("proc_raise", proc_raise),
+            "random_get" => syscalls.function("random_get", random_get),
+            "sched_yield" => syscalls.function("sched_yield", sched_yield),
+            "sock_recv" => syscalls.function("sock_recv", sock_recv),
+            "sock_send" => syscalls.function("sock_send", sock_send),
+            "sock_shutdown" => syscalls.function("sock_shutdown", sock_shutdown),
         },
     }
 }
 
 /// Combines a state generating function with the import list for snapshot 1
 fn generate_import_object_snapshot1(store: &Store, env: WasiEnv) -> ImportObject {
+    let syscalls = Syscalls::new(store, env);
     imports! {
         "wasi_snapshot_preview1" => {
-            "args_get" => Function::new_native_with_env(store, env.clone(), args_get),
-            "args_sizes_get" => Function::new_native_with_env(store, env.clone(), args_sizes_get),
-            "clock_res_get" => Function::new_native_with_env(store, env.clone(), clock_res_get),
-            "clock_time_get" => Function::new_native_with_env(store, env.clone(), clock_time_get),
-            "environ_get" => Function::new_native_with_env(store, env.clone(), environ_get),
-            "environ_sizes_get" => Function::new_native_with_env(store, env.clone(), environ_sizes_get),
-            "fd_advise" => Function::new_native_with_env(store, env.clone(), fd_advise),
-            "fd_allocate" => Function::new_native_with_env(store, env.clone(), fd_allocate),
-            "fd_close" => Function::new_native_with_env(store, env.clone(), fd_close),
-            "fd_datasync" => Function::new_native_with_env(store, env.clone(), fd_datasync),
-            "fd_fdstat_get" => Function::new_native_with_env(store, env.clone(), fd_fdstat_get),
-            "fd_fdstat_set_flags" => Function::new_native_with_env(store, env.clone(), fd_fdstat_set_flags),
-            "fd_fdstat_set_rights" => Function::new_native_with_env(store, env.clone(), fd_fdstat_set_rights),
-            "fd_filestat_get" => Function::new_native_with_env(store, env.clone(), fd_filestat_get),
-            "fd_filestat_set_size" => Function::new_native_with_env(store, env.clone(), fd_filestat_set_size),
-            "fd_filestat_set_times" => Function::new_native_with_env(store, env.clone(), fd_filestat_set_times),
-            "fd_pread" => Function::new_native_with_env(store, env.clone(), fd_pread),
-            "fd_prestat_get" => Function::new_native_with_env(store, env.clone(), fd_prestat_get),
-            "fd_prestat_dir_name" => Function::new_native_with_env(store, env.clone(), fd_prestat_dir_name),
-            "fd_pwrite" => Function::new_native_with_env(store, env.clone(), fd_pwrite),
-            "fd_read" => Function::new_native_with_env(store, env.clone(), fd_read),
-            "fd_readdir" => Function::new_native_with_env(store, env.clone(), fd_readdir),
-            "fd_renumber" => Function::new_native_with_env(store, env.clone(), fd_renumber),
-            "fd_seek" => Function::new_native_with_env(store, env.clone(), fd_seek),
-            "fd_sync" => Function::new_native_with_env(store, env.clone(), fd_sync),
-            "fd_tell" => Function::new_native_with_env(store, env.clone(), fd_tell),
-            "fd_write" => Function::new_native_with_env(store, env.clone(), fd_write),
-            "path_create_directory" => Function::new_native_with_env(store, env.clone(), path_create_directory),
-            "path_filestat_get" => Function::new_native_with_env(store, env.clone(), path_filestat_get),
-            "path_filestat_set_times" => Function::new_native_with_env(store, env.clone(), path_filestat_set_times),
-            "path_link" => Function::new_native_with_env(store, env.clone(), path_link),
-            "path_open" => get_path_open_for_store(store, env.clone()),
-            "path_readlink" => Function::new_native_with_env(store, env.clone(), path_readlink),
-            "path_remove_directory" => Function::new_native_with_env(store, env.clone(), path_remove_directory),
-            "path_rename" => Function::new_native_with_env(store, env.clone(), path_rename),
-            "path_symlink" => Function::new_native_with_env(store, env.clone(), path_symlink),
-            "path_unlink_file" => Function::new_native_with_env(store, env.clone(), path_unlink_file),
-            "poll_oneoff" => Function::new_native_with_env(store, env.clone(), poll_oneoff),
-            "proc_exit" => Function::new_native_with_env(store, env.clone(), proc_exit),
-            "proc_raise" => Function::new_native_with_env(store, env.clone(), proc_raise),
-            "random_get" => Function::new_native_with_env(store, env.clone(), random_get),
-            "sched_yield" => Function::new_native_with_env(store, env.clone(), sched_yield),
-            "sock_recv" => Function::new_native_with_env(store, env.clone(), sock_recv),
-            "sock_send" => Function::new_native_with_env(store, env.clone(), sock_send),
-            "sock_shutdown" => Function::new_native_with_env(store, env.clone(), sock_shutdown),
+            "args_get" => syscalls.function("args_get", args_get),
+            "args_sizes_get" => syscalls.function("args_sizes_get", args_sizes_get),
+            "clock_res_get" => syscalls.function("clock_res_get", clock_res_get),
+            "clock_time_get" => syscalls.function("clock_time_get", clock_time_get),
+            "environ_get" => syscalls.function("environ_get", environ_get),
+            "environ_sizes_get" => syscalls.function("environ_sizes_get", environ_sizes_get),
+            "fd_advise" => syscalls.function("fd_advise", fd_advise),
+            "fd_allocate" => syscalls.function("fd_allocate", fd_allocate),
+            "fd_close" => syscalls.function("fd_close", fd_close),
+            "fd_datasync" => syscalls.function("fd_datasync", fd_datasync),
+            "fd_fdstat_get" => syscalls.function("fd_fdstat_get", fd_fdstat_get),
+            "fd_fdstat_set_flags" => syscalls.function("fd_fdstat_set_flags", fd_fdstat_set_flags),
+            "fd_fdstat_set_rights" => syscalls.function("fd_fdstat_set_rights", fd_fdstat_set_rights),
+            "fd_filestat_get" => syscalls.function("fd_filestat_get", fd_filestat_get),
+            "fd_filestat_set_size" => syscalls.function("fd_filestat_set_size", fd_filestat_set_size),
+            "fd_filestat_set_times" => syscalls.function("fd_filestat_set_times", fd_filestat_set_times),
+            "fd_pread" => syscalls.function("fd_pread", fd_pread),
+            "fd_prestat_get" => syscalls.function("fd_prestat_get", fd_prestat_get),
+            "fd_prestat_dir_name" => syscalls.function("fd_prestat_dir_name", fd_prestat_dir_name),
+            "fd_pwrite" => syscalls.function("fd_pwrite", fd_pwrite),
+            "fd_read" => syscalls.function("fd_read", fd_read),
+            "fd_readdir" => syscalls.function("fd_readdir", fd_readdir),
+            "fd_renumber" => syscalls.function("fd_renumber", fd_renumber),
+            "fd_seek" => syscalls.function("fd_seek", fd_seek),
+            "fd_sync" => syscalls.function("fd_sync", fd_sync),
+            "fd_tell" => syscalls.function("fd_tell", fd_tell),
+            "fd_write" => syscalls.function("fd_write", fd_write),
+            "path_create_directory" => syscalls.function("path_create_directory", path_create_directory),
+            "path_filestat_get" => syscalls.function("path_filestat_get", path_filestat_get),
+            "path_filestat_set_times" => syscalls.function("path_filestat_set_times", path_filestat_set_times),
+            "path_link" => syscalls.function("path_link", path_link),
+            "path_open" => get_path_open_for_store(store, &syscalls),
+            "path_readlink" => syscalls.function("path_readlink", path_readlink),
+            "path_remove_directory" => syscalls.function("path_remove_directory", path_remove_directory),
+            "path_rename" => syscalls.function("path_rename", path_rename),
+            "path_symlink" => syscalls.function("path_symlink", path_symlink),
+            "path_unlink_file" => syscalls.function("path_unlink_file", path_unlink_file),
+            "poll_oneoff" => syscalls.function("poll_oneoff", poll_oneoff),
+            "proc_exit" => syscalls.function("proc_exit", proc_exit),
+            "proc_raise" => syscalls.function("proc_raise", proc_raise),
+            "random_get" => syscalls.function("random_get", random_get),
+            "sched_yield" => syscalls.function("sched_yield", sched_yield),
+            "sock_recv" => syscalls.function("sock_recv", sock_recv),
+            "sock_send" => syscalls.function("sock_send", sock_send),
+            "sock_accept" => syscalls.function("sock_accept", sock_accept),
+            "sock_shutdown" => syscalls.function("sock_shutdown", sock_shutdown),
+            // extensions of WASI, allowed by the `NetworkPolicy` of the module
+            "sock_addr_resolve" => syscalls.function("sock_addr_resolve", sock_addr_resolve),
+            "sock_connect" => syscalls.function("sock_connect", sock_connect),
+            "sock_listen" => syscalls.function("sock_listen", sock_listen),
         }
     }
 }
@@ -0,0 +1,177 @@
+//! A reference backend running a single dense layer.
+
+use super::{ExecutionTarget, NnBackend, NnError, NnExecutionContext, NnGraph, Tensor, TensorType};
+use byteorder::{ByteOrder, LittleEndian};
+
+/// A backend running a single dense layer, `output = weights · input + bias`,
+/// on the CPU.
+///
+/// The graph is the first builder, made of little endian values:
+/// - `u32 rows`, `u32 cols`
+/// - `rows * cols` `f32` weights, row by row
+/// - `rows` `f32` biases
+///
+/// The input 0 is an `f32` tensor of `cols` elements, and the output 0 is
+/// `rows` `f32` in little endian.
+#[derive(Debug, Default)]
+pub struct DenseBackend;
+
+impl DenseBackend {
+    /// Create a dense layer backend.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[derive(Debug, Clone)]
+struct DenseGraph {
+    rows: usize,
+    cols: usize,
+    weights: Vec<f32>,
+    bias: Vec<f32>,
+}
+
+#[derive(Debug)]
+struct DenseContext {
+    graph: DenseGraph,
+    input: Option<Vec<f32>>,
+    output: Option<Vec<f32>>,
+}
+
+fn read_f32s(bytes: &[u8]) -> Vec<f32> {
+    bytes.chunks_exact(4).map(LittleEndian::read_f32).collect()
+}
+
+impl NnBackend for DenseBackend {
+    fn load(
+        &mut self,
+        builders: &[Vec<u8>],
+        target: ExecutionTarget,
+    ) -> Result<Box<dyn NnGraph>, NnError> {
+        let invalid = |reason: &str| NnError::InvalidArgument(reason.to_string());
+        if target != ExecutionTarget::Cpu {
+            return Err(invalid("the dense backend only runs on the CPU"));
+        }
+        let graph = builders.first().ok_or_else(|| invalid("no graph"))?;
+        if graph.len() < 8 {
+            return Err(invalid("the graph is too short"));
+        }
+        let rows = LittleEndian::read_u32(&graph[0..4]) as usize;
+        let cols = LittleEndian::read_u32(&graph[4..8]) as usize;
+        let values = rows
+            .checked_mul(cols)
+            .and_then(|weights| weights.checked_add(rows))
+            .ok_or_else(|| invalid("the graph is too large"))?;
+        if graph.len() - 8 != values.saturating_mul(4) {
+            return Err(invalid("the graph doesn't match its dimensions"));
+        }
+        let mut values = read_f32s(&graph[8..]);
+        let bias = values.split_off(rows * cols);
+
+        Ok(Box::new(DenseGraph {
+            rows,
+            cols,
+            weights: values,
+            bias,
+        }))
+    }
+}
+
+impl NnGraph for DenseGraph {
+    fn init_execution_context(&self) -> Result<Box<dyn NnExecutionContext>, NnError> {
+        Ok(Box::new(DenseContext {
+            graph: self.clone(),
+            input: None,
+            output: None,
+        }))
+    }
+}
+
+impl NnExecutionContext for DenseContext {
+    fn set_input(&mut self, index: u32, tensor: Tensor) -> Result<(), NnError> {
+        let invalid = |reason: &str| NnError::InvalidArgument(reason.to_string());
+        if index != 0 {
+            return Err(invalid("the dense backend has a single input"));
+        }
+        if tensor.ty != TensorType::F32 {
+            return Err(invalid("the input must be a f32 tensor"));
+        }
+        let elements = tensor
+            .dimensions
+            .iter()
+            .try_fold(1usize, |elements, dimension| {
+                elements.checked_mul(*dimension as usize)
+            });
+        if elements != Some(self.graph.cols) || tensor.data.len() != self.graph.cols * 4 {
+            return Err(invalid("the input doesn't match the graph"));
+        }
+        self.input = Some(read_f32s(&tensor.data));
+        Ok(())
+    }
+
+    fn compute(&mut self) -> Result<(), NnError> {
+        let input = self
+            .input
+            .as_ref()
+            .ok_or_else(|| NnError::RuntimeError("the input is not set".to_string()))?;
+        let graph = &self.graph;
+        let output = (0..graph.rows)
+            .map(|row| {
+                let weights = &graph.weights[row * graph.cols..(row + 1) * graph.cols];
+                weights.iter().zip(input).map(|(w, x)| w * x).sum::<f32>() + graph.bias[row]
+            })
+            .collect();
+        self.output = Some(output);
+        Ok(())
+    }
+
+    fn get_output(&self, index: u32) -> Result<Vec<u8>, NnError> {
+        if index != 0 {
+            return Err(NnError::InvalidArgument(
+                "the dense backend has a single output".to_string(),
+            ));
+        }
+        let output = self
+            .output
+            .as_ref()
+            .ok_or_else(|| NnError::RuntimeError("the graph has not been computed".to_string()))?;
+        let mut bytes = vec![0; output.len() * 4];
+        LittleEndian::write_f32_into(output, &mut bytes);
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dense_backend_computes_the_layer() {
+        let mut graph = Vec::new();
+        for value in &[2u32, 3] {
+            graph.extend_from_slice(&value.to_le_bytes());
+        }
+        for value in &[1f32, 2., 3., -1., 0., 1., 0.5, -0.5] {
+            graph.extend_from_slice(&value.to_le_bytes());
+        }
+        let graph = DenseBackend::new()
+            .load(&[graph], ExecutionTarget::Cpu)
+            .unwrap();
+        let mut context = graph.init_execution_context().unwrap();
+
+        assert!(context.compute().is_err());
+        let mut data = Vec::new();
+        for value in &[1f32, 1., 2.] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        let input = Tensor {
+            dimensions: vec![1, 3],
+            ty: TensorType::F32,
+            data,
+        };
+        context.set_input(0, input).unwrap();
+        context.compute().unwrap();
+        let output = read_f32s(&context.get_output(0).unwrap());
+        assert_eq!(output, vec![9.5, 0.5]);
+    }
+}
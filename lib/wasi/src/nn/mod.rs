@@ -0,0 +1,398 @@
+//! The `wasi_ephemeral_nn` imports of the wasi-nn proposal, which run
+//! machine learning inference on the host.
+//!
+//! The inference itself is done by the [`NnBackend`]s given to
+//! [`WasiStateBuilder::nn_backend`](crate::WasiStateBuilder::nn_backend), one
+//! per [`GraphEncoding`]. [`DenseBackend`] is a small reference backend.
+
+#![allow(non_camel_case_types)]
+
+mod dense;
+
+pub use self::dense::DenseBackend;
+
+use crate::ptr::{Array, WasmPtr};
+use crate::WasiEnv;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use thiserror::Error;
+use tracing::debug;
+use wasmer::{Exports, Function, Store, ValueType};
+
+/// The format of a graph given to `load`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GraphEncoding {
+    Openvino,
+    Onnx,
+    Tensorflow,
+    Pytorch,
+    TensorflowLite,
+    Ggml,
+    Autodetect,
+}
+
+/// The device a graph should run on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExecutionTarget {
+    Cpu,
+    Gpu,
+    Tpu,
+}
+
+/// The type of the elements of a [`Tensor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TensorType {
+    F16,
+    F32,
+    U8,
+    I32,
+}
+
+impl TensorType {
+    /// The size of an element, in bytes.
+    pub fn size(self) -> usize {
+        match self {
+            TensorType::F16 => 2,
+            TensorType::F32 | TensorType::I32 => 4,
+            TensorType::U8 => 1,
+        }
+    }
+}
+
+/// An input of a graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tensor {
+    pub dimensions: Vec<u32>,
+    pub ty: TensorType,
+    /// The elements, in little endian
+    pub data: Vec<u8>,
+}
+
+/// An error of an [`NnBackend`], reported to the module as a wasi-nn errno.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum NnError {
+    #[error("invalid argument: {0}")]
+    InvalidArgument(String),
+    #[error("no backend supports the encoding {0:?}")]
+    InvalidEncoding(GraphEncoding),
+    #[error("the backend is busy")]
+    Busy,
+    #[error("runtime error: {0}")]
+    RuntimeError(String),
+}
+
+impl NnError {
+    fn into_nn_errno(self) -> __wasi_nn_errno_t {
+        match self {
+            NnError::InvalidArgument(_) => __WASI_NN_EINVALID_ARGUMENT,
+            NnError::InvalidEncoding(_) => __WASI_NN_EINVALID_ENCODING,
+            NnError::Busy => __WASI_NN_EBUSY,
+            NnError::RuntimeError(_) => __WASI_NN_ERUNTIME_ERROR,
+        }
+    }
+}
+
+/// A backend loading the graphs of an encoding.
+pub trait NnBackend: fmt::Debug + Send + 'static {
+    /// Load a graph from the buffers given by the module.
+    fn load(
+        &mut self,
+        builders: &[Vec<u8>],
+        target: ExecutionTarget,
+    ) -> Result<Box<dyn NnGraph>, NnError>;
+}
+
+/// A graph loaded by an [`NnBackend`].
+pub trait NnGraph: fmt::Debug + Send {
+    /// Create a context to run the graph.
+    fn init_execution_context(&self) -> Result<Box<dyn NnExecutionContext>, NnError>;
+}
+
+/// The inputs and the outputs of a run of an [`NnGraph`].
+pub trait NnExecutionContext: fmt::Debug + Send {
+    /// Set the input `index`.
+    fn set_input(&mut self, index: u32, tensor: Tensor) -> Result<(), NnError>;
+
+    /// Run the graph on the inputs.
+    fn compute(&mut self) -> Result<(), NnError>;
+
+    /// Get the bytes of the output `index` of the last run.
+    fn get_output(&self, index: u32) -> Result<Vec<u8>, NnError>;
+}
+
+/// The backends, graphs and execution contexts of a module.
+#[derive(Debug, Default)]
+pub(crate) struct WasiNn {
+    backends: HashMap<GraphEncoding, Box<dyn NnBackend>>,
+    graphs: Vec<Box<dyn NnGraph>>,
+    contexts: Vec<Box<dyn NnExecutionContext>>,
+}
+
+impl WasiNn {
+    pub(crate) fn new(backends: HashMap<GraphEncoding, Box<dyn NnBackend>>) -> Self {
+        Self {
+            backends,
+            ..Self::default()
+        }
+    }
+
+    fn context_mut(&mut self, context: u32) -> Result<&mut Box<dyn NnExecutionContext>, NnError> {
+        self.contexts
+            .get_mut(context as usize)
+            .ok_or_else(|| NnError::InvalidArgument(format!("unknown context {}", context)))
+    }
+}
+
+pub type __wasi_nn_errno_t = u16;
+pub const __WASI_NN_ESUCCESS: u16 = 0;
+pub const __WASI_NN_EINVALID_ARGUMENT: u16 = 1;
+pub const __WASI_NN_EINVALID_ENCODING: u16 = 2;
+pub const __WASI_NN_EMISSING_MEMORY: u16 = 3;
+pub const __WASI_NN_EBUSY: u16 = 4;
+pub const __WASI_NN_ERUNTIME_ERROR: u16 = 5;
+
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub(crate) struct __wasi_nn_graph_builder_t {
+    buf: WasmPtr<u8, Array>,
+    buf_len: u32,
+}
+
+unsafe impl ValueType for __wasi_nn_graph_builder_t {}
+
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub(crate) struct __wasi_nn_tensor_t {
+    dimensions: WasmPtr<u32, Array>,
+    dimensions_len: u32,
+    ty: u8,
+    data: WasmPtr<u8, Array>,
+    data_len: u32,
+}
+
+unsafe impl ValueType for __wasi_nn_tensor_t {}
+
+fn invalid(what: &str) -> NnError {
+    NnError::InvalidArgument(what.to_string())
+}
+
+impl TryFrom<u32> for GraphEncoding {
+    type Error = NnError;
+
+    fn try_from(encoding: u32) -> Result<Self, NnError> {
+        Ok(match encoding {
+            0 => GraphEncoding::Openvino,
+            1 => GraphEncoding::Onnx,
+            2 => GraphEncoding::Tensorflow,
+            3 => GraphEncoding::Pytorch,
+            4 => GraphEncoding::TensorflowLite,
+            5 => GraphEncoding::Ggml,
+            6 => GraphEncoding::Autodetect,
+            _ => return Err(invalid("unknown graph encoding")),
+        })
+    }
+}
+
+impl TryFrom<u32> for ExecutionTarget {
+    type Error = NnError;
+
+    fn try_from(target: u32) -> Result<Self, NnError> {
+        Ok(match target {
+            0 => ExecutionTarget::Cpu,
+            1 => ExecutionTarget::Gpu,
+            2 => ExecutionTarget::Tpu,
+            _ => return Err(invalid("unknown execution target")),
+        })
+    }
+}
+
+impl TryFrom<u8> for TensorType {
+    type Error = NnError;
+
+    fn try_from(ty: u8) -> Result<Self, NnError> {
+        Ok(match ty {
+            0 => TensorType::F16,
+            1 => TensorType::F32,
+            2 => TensorType::U8,
+            3 => TensorType::I32,
+            _ => return Err(invalid("unknown tensor type")),
+        })
+    }
+}
+
+/// Run a wasi-nn call, turning its error into a wasi-nn errno.
+fn nn_call(f: impl FnOnce() -> Result<(), NnError>) -> __wasi_nn_errno_t {
+    match f() {
+        Ok(()) => __WASI_NN_ESUCCESS,
+        Err(err) => {
+            debug!("=> {}", err);
+            err.into_nn_errno()
+        }
+    }
+}
+
+/// ### `load()`
+/// Load a graph from the buffers of `builder`, with the backend of
+/// `encoding`.
+pub(crate) fn load(
+    env: &WasiEnv,
+    builder: WasmPtr<__wasi_nn_graph_builder_t, Array>,
+    builder_len: u32,
+    encoding: u32,
+    target: u32,
+    graph: WasmPtr<u32>,
+) -> __wasi_nn_errno_t {
+    debug!("wasi_nn::load");
+    nn_call(|| {
+        let (memory, mut state) = env.get_memory_and_wasi_state(0);
+        let encoding = GraphEncoding::try_from(encoding)?;
+        let target = ExecutionTarget::try_from(target)?;
+        let graph_cell = graph.deref(memory).map_err(|_| invalid("graph"))?;
+        let builders = builder
+            .deref(memory, 0, builder_len)
+            .map_err(|_| invalid("builder"))?
+            .iter()
+            .map(|builder| {
+                let builder = builder.get();
+                let bytes = builder
+                    .buf
+                    .deref(memory, 0, builder.buf_len)
+                    .map_err(|_| invalid("builder"))?;
+                Ok(bytes.iter().map(|byte| byte.get()).collect())
+            })
+            .collect::<Result<Vec<Vec<u8>>, NnError>>()?;
+
+        let nn = &mut state.nn;
+        let backend = nn
+            .backends
+            .get_mut(&encoding)
+            .ok_or(NnError::InvalidEncoding(encoding))?;
+        let loaded = backend.load(&builders, target)?;
+        nn.graphs.push(loaded);
+        graph_cell.set(nn.graphs.len() as u32 - 1);
+        Ok(())
+    })
+}
+
+/// ### `init_execution_context()`
+/// Create an execution context for `graph`.
+pub(crate) fn init_execution_context(
+    env: &WasiEnv,
+    graph: u32,
+    context: WasmPtr<u32>,
+) -> __wasi_nn_errno_t {
+    debug!("wasi_nn::init_execution_context: graph={}", graph);
+    nn_call(|| {
+        let (memory, mut state) = env.get_memory_and_wasi_state(0);
+        let context_cell = context.deref(memory).map_err(|_| invalid("context"))?;
+        let nn = &mut state.nn;
+        let graph = nn
+            .graphs
+            .get(graph as usize)
+            .ok_or_else(|| invalid("unknown graph"))?;
+        let new_context = graph.init_execution_context()?;
+        nn.contexts.push(new_context);
+        context_cell.set(nn.contexts.len() as u32 - 1);
+        Ok(())
+    })
+}
+
+/// ### `set_input()`
+/// Set the input `index` of `context` to `tensor`.
+pub(crate) fn set_input(
+    env: &WasiEnv,
+    context: u32,
+    index: u32,
+    tensor: WasmPtr<__wasi_nn_tensor_t>,
+) -> __wasi_nn_errno_t {
+    debug!("wasi_nn::set_input: context={} index={}", context, index);
+    nn_call(|| {
+        let (memory, mut state) = env.get_memory_and_wasi_state(0);
+        let tensor = tensor.deref(memory).map_err(|_| invalid("tensor"))?.get();
+        let dimensions = tensor
+            .dimensions
+            .deref(memory, 0, tensor.dimensions_len)
+            .map_err(|_| invalid("tensor dimensions"))?
+            .iter()
+            .map(|dimension| dimension.get())
+            .collect();
+        let data = tensor
+            .data
+            .deref(memory, 0, tensor.data_len)
+            .map_err(|_| invalid("tensor data"))?
+            .iter()
+            .map(|byte| byte.get())
+            .collect();
+        let tensor = Tensor {
+            dimensions,
+            ty: TensorType::try_from(tensor.ty)?,
+            data,
+        };
+        state.nn.context_mut(context)?.set_input(index, tensor)
+    })
+}
+
+/// ### `compute()`
+/// Run the graph of `context` on its inputs.
+pub(crate) fn compute(env: &WasiEnv, context: u32) -> __wasi_nn_errno_t {
+    debug!("wasi_nn::compute: context={}", context);
+    nn_call(|| env.state().nn.context_mut(context)?.compute())
+}
+
+/// ### `get_output()`
+/// Copy the output `index` of `context` to `out_buffer`.
+pub(crate) fn get_output(
+    env: &WasiEnv,
+    context: u32,
+    index: u32,
+    out_buffer: WasmPtr<u8, Array>,
+    out_buffer_max_size: u32,
+    bytes_written: WasmPtr<u32>,
+) -> __wasi_nn_errno_t {
+    debug!("wasi_nn::get_output: context={} index={}", context, index);
+    nn_call(|| {
+        let (memory, mut state) = env.get_memory_and_wasi_state(0);
+        let bytes_written_cell = bytes_written
+            .deref(memory)
+            .map_err(|_| invalid("bytes_written"))?;
+        let output = state.nn.context_mut(context)?.get_output(index)?;
+        if output.len() > out_buffer_max_size as usize {
+            return Err(invalid("the output buffer is too small"));
+        }
+        let out_cells = out_buffer
+            .deref(memory, 0, output.len() as u32)
+            .map_err(|_| invalid("out_buffer"))?;
+        for (cell, byte) in out_cells.iter().zip(output.iter()) {
+            cell.set(*byte);
+        }
+        bytes_written_cell.set(output.len() as u32);
+        Ok(())
+    })
+}
+
+/// Create the `wasi_ephemeral_nn` imports.
+pub(crate) fn exports(store: &Store, env: &WasiEnv) -> Exports {
+    let mut exports = Exports::new();
+    exports.insert(
+        "load",
+        Function::new_native_with_env(store, env.clone(), load),
+    );
+    exports.insert(
+        "init_execution_context",
+        Function::new_native_with_env(store, env.clone(), init_execution_context),
+    );
+    exports.insert(
+        "set_input",
+        Function::new_native_with_env(store, env.clone(), set_input),
+    );
+    exports.insert(
+        "compute",
+        Function::new_native_with_env(store, env.clone(), compute),
+    );
+    exports.insert(
+        "get_output",
+        Function::new_native_with_env(store, env.clone(), get_output),
+    );
+    exports
+}
@@ -0,0 +1,137 @@
+//! Policies allowing or denying the WASI syscalls made by a module.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use wasmer::Val;
+
+/// A policy deciding which WASI syscalls a module may make, given to
+/// [`WasiStateBuilder::syscall_policy`](crate::WasiStateBuilder::syscall_policy).
+///
+/// The syscalls of `wasi_unstable` and `wasi_snapshot_preview1` are checked
+/// before they run, with their arguments as given by the module: pointers
+/// are offsets into its memory. A denied syscall returns
+/// `__WASI_ENOTCAPABLE` without running.
+///
+/// Syscalls which don't return an errno, like `proc_exit`, are always
+/// allowed.
+///
+/// Closures taking the name and the arguments of a syscall are policies:
+///
+/// ```
+/// # use wasmer::Val;
+/// # use wasmer_wasi::{types::__WASI_O_CREAT, WasiState, WasiStateCreationError};
+/// # fn main() -> Result<(), WasiStateCreationError> {
+/// WasiState::new("program")
+///     // the module can't create files
+///     .syscall_policy(|name: &str, args: &[Val]| match (name, args.get(4)) {
+///         ("path_open", Some(Val::I32(o_flags))) => *o_flags as u16 & __WASI_O_CREAT == 0,
+///         _ => true,
+///     })
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+pub trait SyscallPolicy: Send + Sync + 'static {
+    /// Whether the syscall `name` can run with `args`.
+    fn allows(&self, name: &str, args: &[Val]) -> bool;
+}
+
+impl fmt::Debug for dyn SyscallPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SyscallPolicy")
+    }
+}
+
+impl<F> SyscallPolicy for F
+where
+    F: Fn(&str, &[Val]) -> bool + Send + Sync + 'static,
+{
+    fn allows(&self, name: &str, args: &[Val]) -> bool {
+        self(name, args)
+    }
+}
+
+/// A condition on the arguments of a syscall.
+type ArgsCondition = Arc<dyn Fn(&[Val]) -> bool + Send + Sync>;
+
+/// A [`SyscallPolicy`] denying every syscall which isn't listed.
+///
+/// ```
+/// # use wasmer_wasi::SyscallAllowlist;
+/// let mut allowlist = SyscallAllowlist::new();
+/// allowlist
+///     .allow("args_sizes_get")
+///     .allow("args_get")
+///     // only write to stdout and stderr
+///     .allow_if("fd_write", |args| matches!(args[0].i32(), Some(1) | Some(2)));
+/// ```
+#[derive(Clone, Default)]
+pub struct SyscallAllowlist {
+    /// The allowed syscalls, with the conditions on their arguments, if any
+    syscalls: HashMap<String, Option<ArgsCondition>>,
+}
+
+impl SyscallAllowlist {
+    /// Create an allowlist allowing nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow the syscall `name`.
+    pub fn allow(&mut self, name: &str) -> &mut Self {
+        self.syscalls.insert(name.to_string(), None);
+
+        self
+    }
+
+    /// Allow the syscall `name` when `condition` holds for its arguments,
+    /// replacing the previous rule for it.
+    pub fn allow_if<F>(&mut self, name: &str, condition: F) -> &mut Self
+    where
+        F: Fn(&[Val]) -> bool + Send + Sync + 'static,
+    {
+        self.syscalls
+            .insert(name.to_string(), Some(Arc::new(condition)));
+
+        self
+    }
+}
+
+impl fmt::Debug for SyscallAllowlist {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut syscalls = self.syscalls.keys().collect::<Vec<_>>();
+        syscalls.sort();
+        f.debug_struct("SyscallAllowlist")
+            .field("syscalls", &syscalls)
+            .finish()
+    }
+}
+
+impl SyscallPolicy for SyscallAllowlist {
+    fn allows(&self, name: &str, args: &[Val]) -> bool {
+        match self.syscalls.get(name) {
+            Some(Some(condition)) => condition(args),
+            Some(None) => true,
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn allowlist_checks_names_and_arguments() {
+        let mut allowlist = SyscallAllowlist::new();
+        allowlist
+            .allow("args_get")
+            .allow_if("fd_write", |args| args[0].i32() == Some(1));
+
+        assert!(allowlist.allows("args_get", &[Val::I32(0), Val::I32(4)]));
+        assert!(allowlist.allows("fd_write", &[Val::I32(1)]));
+        assert!(!allowlist.allows("fd_write", &[Val::I32(3)]));
+        assert!(!allowlist.allows("path_open", &[]));
+    }
+}
@@ -1,9 +1,21 @@
 //! Builder system for configuring a [`WasiState`] and creating it.
 
-use crate::state::{WasiFile, WasiFs, WasiFsError, WasiState};
-use crate::syscalls::types::{__WASI_STDERR_FILENO, __WASI_STDIN_FILENO, __WASI_STDOUT_FILENO};
-use crate::WasiEnv;
+#[cfg(feature = "wasi-crypto")]
+use crate::crypto::KeyStore;
+#[cfg(feature = "wasi-nn")]
+use crate::nn::{GraphEncoding, NnBackend};
+use crate::state::{
+    ExitHook, HostClock, HostFs, HostRandom, NetworkPolicy, OverlayFs, SeededRandom, SymlinkPolicy,
+    VirtualFs, WasiClock, WasiFile, WasiFs, WasiFsError, WasiFsQuota, WasiRandom, WasiState,
+};
+use crate::syscalls::types::{
+    __wasi_exitcode_t, __WASI_STDERR_FILENO, __WASI_STDIN_FILENO, __WASI_STDOUT_FILENO,
+};
+use crate::{SyscallPolicy, WasiEnv, WasiTracer};
+#[cfg(feature = "wasi-nn")]
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use thiserror::Error;
 
 /// Creates an empty [`WasiStateBuilder`].
@@ -42,11 +54,27 @@ pub struct WasiStateBuilder {
     stdout_override: Option<Box<dyn WasiFile>>,
     stderr_override: Option<Box<dyn WasiFile>>,
     stdin_override: Option<Box<dyn WasiFile>>,
+    fs_backend: Option<Box<dyn VirtualFs>>,
+    fs_quota: WasiFsQuota,
+    max_open_fds: Option<u32>,
+    jail: bool,
+    symlink_policy: SymlinkPolicy,
+    clock: Option<Box<dyn WasiClock>>,
+    random: Option<Box<dyn WasiRandom>>,
+    on_exit: Option<ExitHook>,
+    tracer: Option<Arc<dyn WasiTracer>>,
+    syscall_policy: Option<Arc<dyn SyscallPolicy>>,
+    network: NetworkPolicy,
+    #[cfg(feature = "wasi-nn")]
+    nn_backends: HashMap<GraphEncoding, Box<dyn NnBackend>>,
+    #[cfg(feature = "wasi-crypto")]
+    crypto_keystore: Option<Box<dyn KeyStore>>,
 }
 
 impl std::fmt::Debug for WasiStateBuilder {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("WasiStateBuilder")
+        let mut debug = f.debug_struct("WasiStateBuilder");
+        debug
             .field("args", &self.args)
             .field("envs", &self.envs)
             .field("preopens", &self.preopens)
@@ -54,7 +82,22 @@ impl std::fmt::Debug for WasiStateBuilder {
             .field("stdout_override exists", &self.stdout_override.is_some())
             .field("stderr_override exists", &self.stderr_override.is_some())
             .field("stdin_override exists", &self.stdin_override.is_some())
-            .finish()
+            .field("fs_backend", &self.fs_backend)
+            .field("fs_quota", &self.fs_quota)
+            .field("max_open_fds", &self.max_open_fds)
+            .field("jail", &self.jail)
+            .field("symlink_policy", &self.symlink_policy)
+            .field("clock", &self.clock)
+            .field("random", &self.random)
+            .field("on_exit exists", &self.on_exit.is_some())
+            .field("tracer", &self.tracer)
+            .field("syscall_policy", &self.syscall_policy)
+            .field("network", &self.network);
+        #[cfg(feature = "wasi-nn")]
+        debug.field("nn_backends", &self.nn_backends);
+        #[cfg(feature = "wasi-crypto")]
+        debug.field("crypto_keystore", &self.crypto_keystore);
+        debug.finish()
     }
 }
 
@@ -77,6 +120,72 @@ pub enum WasiStateCreationError {
     WasiFsSetupError(String),
     #[error(transparent)]
     WasiFsError(WasiFsError),
+    #[error("network address pattern has wrong format: `{0}`")]
+    NetworkPatternFormatError(String),
+}
+
+/// Check that the `i`th argument of a module can be passed to it.
+pub(crate) fn validate_arg(i: usize, arg: &[u8]) -> Result<(), WasiStateCreationError> {
+    if arg.iter().any(|&b| b == 0) {
+        return Err(WasiStateCreationError::ArgumentContainsNulByte(
+            std::str::from_utf8(arg)
+                .unwrap_or(if i == 0 {
+                    "Inner error: program name is invalid utf8!"
+                } else {
+                    "Inner error: arg is invalid utf8!"
+                })
+                .to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Check that an environment variable can be passed to a module.
+pub(crate) fn validate_env(env_key: &[u8], env_value: &[u8]) -> Result<(), WasiStateCreationError> {
+    enum InvalidCharacter {
+        Nul,
+        Equal,
+    }
+
+    match env_key.iter().find_map(|&ch| {
+        if ch == 0 {
+            Some(InvalidCharacter::Nul)
+        } else if ch == b'=' {
+            Some(InvalidCharacter::Equal)
+        } else {
+            None
+        }
+    }) {
+        Some(InvalidCharacter::Nul) => {
+            return Err(WasiStateCreationError::EnvironmentVariableFormatError(
+                format!(
+                    "found nul byte in env var key \"{}\" (key=value)",
+                    String::from_utf8_lossy(env_key)
+                ),
+            ))
+        }
+
+        Some(InvalidCharacter::Equal) => {
+            return Err(WasiStateCreationError::EnvironmentVariableFormatError(
+                format!(
+                    "found equal sign in env var key \"{}\" (key=value)",
+                    String::from_utf8_lossy(env_key)
+                ),
+            ))
+        }
+
+        None => (),
+    }
+
+    if env_value.iter().any(|&ch| ch == 0) {
+        return Err(WasiStateCreationError::EnvironmentVariableFormatError(
+            format!(
+                "found nul byte in env var value \"{}\" (key=value)",
+                String::from_utf8_lossy(env_value)
+            ),
+        ));
+    }
+    Ok(())
 }
 
 fn validate_mapped_dir_alias(alias: &str) -> Result<(), WasiStateCreationError> {
@@ -85,6 +194,18 @@ fn validate_mapped_dir_alias(alias: &str) -> Result<(), WasiStateCreationError>
             format!("Alias \"{}\" contains a nul byte", alias),
         ));
     }
+    if alias.contains('/')
+        && alias
+            .split('/')
+            .any(|component| matches!(component, "" | "." | ".."))
+    {
+        return Err(WasiStateCreationError::MappedDirAliasFormattingError(
+            format!(
+                "Nested alias \"{}\" must only contain names of directories",
+                alias
+            ),
+        ));
+    }
 
     Ok(())
 }
@@ -156,7 +277,8 @@ impl WasiStateBuilder {
     /// Preopen a directory
     ///
     /// This opens the given directory at the virtual root, `/`, and allows
-    /// the WASI module to read and write to the given directory.
+    /// the WASI module to read, write, create and delete files in the given
+    /// directory.
     pub fn preopen_dir<FilePath>(
         &mut self,
         po_dir: FilePath,
@@ -166,8 +288,12 @@ impl WasiStateBuilder {
     {
         let mut pdb = PreopenDirBuilder::new();
         let path = po_dir.as_ref();
-        pdb.directory(path).read(true).write(true).create(true);
-        let preopen = pdb.build()?;
+        pdb.directory(path)
+            .read(true)
+            .write(true)
+            .create(true)
+            .delete(true);
+        let preopen = pdb.build(self.preopen_fs())?;
 
         self.preopens.push(preopen);
 
@@ -176,6 +302,10 @@ impl WasiStateBuilder {
 
     /// Preopen a directory and configure it.
     ///
+    /// Unlike [`preopen_dir`](Self::preopen_dir), only the permissions which
+    /// are set on the [`PreopenDirBuilder`] are granted to the WASI module:
+    /// a directory with only `read` set can't be modified in any way.
+    ///
     /// Usage:
     ///
     /// ```no_run
@@ -193,7 +323,7 @@ impl WasiStateBuilder {
         F: Fn(&mut PreopenDirBuilder) -> &mut PreopenDirBuilder,
     {
         let mut pdb = PreopenDirBuilder::new();
-        let po_dir = inner(&mut pdb).build()?;
+        let po_dir = inner(&mut pdb).build(self.preopen_fs())?;
 
         self.preopens.push(po_dir);
 
@@ -220,6 +350,10 @@ impl WasiStateBuilder {
     }
 
     /// Preopen a directory with a different name exposed to the WASI.
+    ///
+    /// The alias can be nested, like `app/data`: the directory is then
+    /// mapped in the directory mapped at `app`, or in a virtual read only
+    /// directory if none is.
     pub fn map_dir<FilePath>(
         &mut self,
         alias: &str,
@@ -234,8 +368,9 @@ impl WasiStateBuilder {
             .alias(alias)
             .read(true)
             .write(true)
-            .create(true);
-        let preopen = pdb.build()?;
+            .create(true)
+            .delete(true);
+        let preopen = pdb.build(self.preopen_fs())?;
 
         self.preopens.push(preopen);
 
@@ -282,6 +417,185 @@ impl WasiStateBuilder {
         self
     }
 
+    /// Set the filesystem in which the preopened directories are looked up,
+    /// for example a [`MemFs`] to not give any access to the host disk.
+    ///
+    /// Defaults to [`HostFs`]. The preopened directories must exist in the
+    /// filesystem, so this must be called before preopening them.
+    ///
+    /// [`MemFs`]: crate::MemFs
+    pub fn fs_backend(&mut self, fs_backend: Box<dyn VirtualFs>) -> &mut Self {
+        self.fs_backend = Some(fs_backend);
+
+        self
+    }
+
+    /// Stack `upper` on top of the current filesystem with an [`OverlayFs`].
+    ///
+    /// The WASI module sees the union of both filesystems, and all of its
+    /// modifications are made in `upper`. This can be called several times to
+    /// stack more layers.
+    pub fn overlay_fs(&mut self, upper: Box<dyn VirtualFs>) -> &mut Self {
+        let lower = self.fs_backend.take().unwrap_or_else(|| Box::new(HostFs));
+        self.fs_backend = Some(Box::new(OverlayFs::new(lower, upper)));
+
+        self
+    }
+
+    /// Limit the total number of bytes that the WASI module can write to
+    /// files, including the bytes files are grown by; writes going over the
+    /// limit fail with `ENOSPC`.
+    pub fn max_bytes_written(&mut self, max_bytes: u64) -> &mut Self {
+        self.fs_quota.max_bytes_written = Some(max_bytes);
+
+        self
+    }
+
+    /// Limit the number of files, directories and links that the WASI
+    /// module can create; creating more fails with `ENOSPC`.
+    pub fn max_files(&mut self, max_files: u64) -> &mut Self {
+        self.fs_quota.max_files = Some(max_files);
+
+        self
+    }
+
+    /// Limit the number of fds that the WASI module can have open at once,
+    /// including stdio and the preopened directories; opening more fails
+    /// with `EMFILE`.
+    pub fn max_open_fds(&mut self, max_open_fds: u32) -> &mut Self {
+        self.max_open_fds = Some(max_open_fds);
+
+        self
+    }
+
+    /// Jail the WASI module in the virtual root containing the preopened
+    /// directories, like `chroot`: absolute paths, including the targets
+    /// of symlinks, are resolved from the virtual root, and the directories
+    /// preopened without an alias are named after their last component
+    /// instead of their host path, so that host paths are never shown to
+    /// the module.
+    pub fn jail(&mut self, toggle: bool) -> &mut Self {
+        self.jail = toggle;
+
+        self
+    }
+
+    /// Set which symlinks are followed when resolving the paths given by the
+    /// WASI module; all of them are followed by default.
+    pub fn symlink_policy(&mut self, policy: SymlinkPolicy) -> &mut Self {
+        self.symlink_policy = policy;
+
+        self
+    }
+
+    /// Trace the WASI syscalls made by the module with `tracer`, like
+    /// [`StraceTracer`](crate::StraceTracer) does with `strace`.
+    ///
+    /// The syscalls of a traced module are slower, as they are dynamic
+    /// functions.
+    pub fn tracer(&mut self, tracer: Box<dyn WasiTracer>) -> &mut Self {
+        self.tracer = Some(Arc::from(tracer));
+
+        self
+    }
+
+    /// Only allow the WASI syscalls allowed by `policy`, like a
+    /// [`SyscallAllowlist`](crate::SyscallAllowlist); the denied syscalls
+    /// return `__WASI_ENOTCAPABLE`.
+    ///
+    /// Like traced syscalls, the syscalls of a module with a policy are
+    /// slower.
+    pub fn syscall_policy<P: SyscallPolicy>(&mut self, policy: P) -> &mut Self {
+        self.syscall_policy = Some(Arc::new(policy));
+
+        self
+    }
+
+    /// Set the source of time of the WASI module, for example a
+    /// [`ManualClock`] to freeze the time when replaying an execution.
+    ///
+    /// Defaults to [`HostClock`].
+    ///
+    /// [`ManualClock`]: crate::ManualClock
+    pub fn clock(&mut self, clock: Box<dyn WasiClock>) -> &mut Self {
+        self.clock = Some(clock);
+
+        self
+    }
+
+    /// Set the source of the random bytes of the WASI module.
+    ///
+    /// Defaults to [`HostRandom`].
+    pub fn random_source(&mut self, random: Box<dyn WasiRandom>) -> &mut Self {
+        self.random = Some(random);
+
+        self
+    }
+
+    /// Make the random bytes of the WASI module reproducible by generating
+    /// them from `seed` with a [`SeededRandom`].
+    ///
+    /// Such bytes must not be used for cryptography.
+    pub fn random_seed(&mut self, seed: u64) -> &mut Self {
+        self.random_source(Box::new(SeededRandom::new(seed)))
+    }
+
+    /// Set the hosts and ports the WASI module may use through sockets.
+    ///
+    /// Sockets are disabled by default.
+    pub fn network_policy(&mut self, network: NetworkPolicy) -> &mut Self {
+        self.network = network;
+
+        self
+    }
+
+    /// Allow the WASI module to use the hosts and ports matching `pattern`
+    /// through sockets; see [`NetworkPolicy`] for the format of the pattern.
+    pub fn allow_network(&mut self, pattern: &str) -> Result<&mut Self, WasiStateCreationError> {
+        self.network.allow(pattern)?;
+
+        Ok(self)
+    }
+
+    /// Call `callback` with the exit code of the WASI module when it calls
+    /// `proc_exit`, before the call into the module fails with
+    /// [`WasiError::Exit`](crate::WasiError::Exit).
+    ///
+    /// The callback must not call into the module.
+    pub fn on_exit<F>(&mut self, callback: F) -> &mut Self
+    where
+        F: Fn(__wasi_exitcode_t) + Send + Sync + 'static,
+    {
+        self.on_exit = Some(ExitHook(Arc::new(callback)));
+
+        self
+    }
+
+    /// Run the wasi-nn graphs of `encoding` with `backend`.
+    ///
+    /// Loading a graph with an encoding without backend fails with the
+    /// `invalid_encoding` error. The backends are moved to the first
+    /// [`WasiState`] built.
+    #[cfg(feature = "wasi-nn")]
+    pub fn nn_backend(
+        &mut self,
+        encoding: GraphEncoding,
+        backend: Box<dyn NnBackend>,
+    ) -> &mut Self {
+        self.nn_backends.insert(encoding, backend);
+
+        self
+    }
+
+    /// Store the wasi-crypto key pairs of the module in `keystore`, instead
+    /// of a [`MemoryKeyStore`](crate::crypto::MemoryKeyStore).
+    #[cfg(feature = "wasi-crypto")]
+    pub fn crypto_keystore(&mut self, keystore: Box<dyn KeyStore>) -> &mut Self {
+        self.crypto_keystore = Some(keystore);
+
+        self
+    }
+
     /// Setup the WASI filesystem before running
     // TODO: improve ergonomics on this function
     pub fn setup_fs(
@@ -293,77 +607,27 @@ impl WasiStateBuilder {
         self
     }
 
+    fn preopen_fs(&self) -> &dyn VirtualFs {
+        self.fs_backend.as_deref().unwrap_or(&HostFs)
+    }
+
     /// Consumes the [`WasiStateBuilder`] and produces a [`WasiState`]
     ///
     /// Returns the error from `WasiFs::new` if there's an error
     pub fn build(&mut self) -> Result<WasiState, WasiStateCreationError> {
         for (i, arg) in self.args.iter().enumerate() {
-            for b in arg.iter() {
-                if *b == 0 {
-                    return Err(WasiStateCreationError::ArgumentContainsNulByte(
-                        std::str::from_utf8(arg)
-                            .unwrap_or(if i == 0 {
-                                "Inner error: program name is invalid utf8!"
-                            } else {
-                                "Inner error: arg is invalid utf8!"
-                            })
-                            .to_string(),
-                    ));
-                }
-            }
+            validate_arg(i, arg)?;
         }
-
-        enum InvalidCharacter {
-            Nul,
-            Equal,
-        }
-
         for (env_key, env_value) in self.envs.iter() {
-            match env_key.iter().find_map(|&ch| {
-                if ch == 0 {
-                    Some(InvalidCharacter::Nul)
-                } else if ch == b'=' {
-                    Some(InvalidCharacter::Equal)
-                } else {
-                    None
-                }
-            }) {
-                Some(InvalidCharacter::Nul) => {
-                    return Err(WasiStateCreationError::EnvironmentVariableFormatError(
-                        format!(
-                            "found nul byte in env var key \"{}\" (key=value)",
-                            String::from_utf8_lossy(env_key)
-                        ),
-                    ))
-                }
-
-                Some(InvalidCharacter::Equal) => {
-                    return Err(WasiStateCreationError::EnvironmentVariableFormatError(
-                        format!(
-                            "found equal sign in env var key \"{}\" (key=value)",
-                            String::from_utf8_lossy(env_key)
-                        ),
-                    ))
-                }
-
-                None => (),
-            }
-
-            if env_value.iter().any(|&ch| ch == 0) {
-                return Err(WasiStateCreationError::EnvironmentVariableFormatError(
-                    format!(
-                        "found nul byte in env var value \"{}\" (key=value)",
-                        String::from_utf8_lossy(env_value)
-                    ),
-                ));
-            }
+            validate_env(env_key, env_value)?;
         }
 
         // self.preopens are checked in [`PreopenDirBuilder::build`]
 
         // this deprecation warning only applies to external callers
         #[allow(deprecated)]
-        let mut wasi_fs = WasiFs::new_with_preopen(&self.preopens)
+        let fs_backend = self.fs_backend.take().unwrap_or_else(|| Box::new(HostFs));
+        let mut wasi_fs = WasiFs::new_with_preopen(&self.preopens, fs_backend, self.jail)
             .map_err(WasiStateCreationError::WasiFsCreationError)?;
         // set up the file system, overriding base files and calling the setup function
         if let Some(stdin_override) = self.stdin_override.take() {
@@ -381,6 +645,9 @@ impl WasiStateBuilder {
                 .swap_file(__WASI_STDERR_FILENO, stderr_override)
                 .map_err(WasiStateCreationError::WasiFsError)?;
         }
+        wasi_fs.quota = self.fs_quota.clone();
+        wasi_fs.max_open_fds = self.max_open_fds;
+        wasi_fs.symlink_policy = self.symlink_policy;
         if let Some(f) = &self.setup_fs_fn {
             f(&mut wasi_fs).map_err(WasiStateCreationError::WasiFsSetupError)?;
         }
@@ -399,6 +666,22 @@ impl WasiStateBuilder {
                     env
                 })
                 .collect(),
+            uses_host_clock: self.clock.is_none(),
+            uses_host_random: self.random.is_none(),
+            clock: self.clock.take().unwrap_or_else(|| Box::new(HostClock)),
+            random: self.random.take().unwrap_or_else(|| Box::new(HostRandom)),
+            on_exit: self.on_exit.clone(),
+            tracer: self.tracer.clone(),
+            syscall_policy: self.syscall_policy.clone(),
+            network: self.network.clone(),
+            #[cfg(feature = "wasi-nn")]
+            nn: crate::nn::WasiNn::new(std::mem::take(&mut self.nn_backends)),
+            #[cfg(feature = "wasi-crypto")]
+            crypto: self
+                .crypto_keystore
+                .take()
+                .map(crate::crypto::WasiCrypto::new)
+                .unwrap_or_default(),
         })
     }
 
@@ -419,6 +702,8 @@ pub struct PreopenDirBuilder {
     read: bool,
     write: bool,
     create: bool,
+    delete: bool,
+    no_delete: bool,
 }
 
 /// The built version of `PreopenDirBuilder`
@@ -429,6 +714,7 @@ pub(crate) struct PreopenedDir {
     pub(crate) read: bool,
     pub(crate) write: bool,
     pub(crate) create: bool,
+    pub(crate) delete: bool,
 }
 
 impl PreopenDirBuilder {
@@ -451,8 +737,8 @@ impl PreopenDirBuilder {
     /// Make this preopened directory appear to the WASI program as `alias`
     pub fn alias(&mut self, alias: &str) -> &mut Self {
         // We mount at preopened dirs at `/` by default and multiple `/` in a row
-        // are equal to a single `/`.
-        let alias = alias.trim_start_matches('/');
+        // are equal to a single `/`; a trailing `/` is ignored too.
+        let alias = alias.trim_matches('/');
         self.alias = Some(alias.to_string());
 
         self
@@ -466,6 +752,9 @@ impl PreopenDirBuilder {
     }
 
     /// Set write permissions affecting files in the directory
+    ///
+    /// Write implies `delete` permissions, unless they are turned off
+    /// with [`PreopenDirBuilder::no_delete`].
     pub fn write(&mut self, toggle: bool) -> &mut Self {
         self.write = toggle;
 
@@ -484,7 +773,32 @@ impl PreopenDirBuilder {
         self
     }
 
-    pub(crate) fn build(&self) -> Result<PreopenedDir, WasiStateCreationError> {
+    /// Set delete permissions affecting files in the directory: removing
+    /// files and directories, and renaming them away.
+    ///
+    /// Delete implies `write` permissions
+    pub fn delete(&mut self, toggle: bool) -> &mut Self {
+        self.delete = toggle;
+        if toggle {
+            self.write = true;
+        }
+
+        self
+    }
+
+    /// Withhold the delete permissions implied by `write`, so that the
+    /// files in the directory can be modified but not removed or renamed
+    /// away.
+    pub fn no_delete(&mut self, toggle: bool) -> &mut Self {
+        self.no_delete = toggle;
+
+        self
+    }
+
+    pub(crate) fn build(
+        &self,
+        fs_backend: &dyn VirtualFs,
+    ) -> Result<PreopenedDir, WasiStateCreationError> {
         // ensure at least one is set
         if !(self.read || self.write || self.create) {
             return Err(WasiStateCreationError::PreopenedDirectoryError("Preopened directories must have at least one of read, write, create permissions set".to_string()));
@@ -497,7 +811,7 @@ impl PreopenDirBuilder {
         }
         let path = self.path.clone().unwrap();
 
-        if !path.exists() {
+        if fs_backend.metadata(&path).is_err() {
             return Err(WasiStateCreationError::PreopenedDirectoryNotFound(path));
         }
         if let Some(alias) = &self.alias {
@@ -510,6 +824,7 @@ impl PreopenDirBuilder {
             read: self.read,
             write: self.write,
             create: self.create,
+            delete: (self.write || self.delete) && !self.no_delete,
         })
     }
 }
@@ -517,6 +832,7 @@ impl PreopenDirBuilder {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::state::MemFs;
 
     #[test]
     fn env_var_errors() {
@@ -572,4 +888,32 @@ mod test {
             _ => assert!(false),
         }
     }
+
+    #[test]
+    fn write_implies_delete_unless_opted_out() {
+        let fs = MemFs::new();
+        fs.create_dir_all("/data").unwrap();
+        let delete = |configure: &dyn Fn(&mut PreopenDirBuilder)| {
+            let mut builder = PreopenDirBuilder::new();
+            builder.directory("/data");
+            configure(&mut builder);
+            builder.build(&fs).unwrap().delete
+        };
+
+        assert!(!delete(&|p| {
+            p.read(true);
+        }));
+        assert!(delete(&|p| {
+            p.write(true);
+        }));
+        assert!(delete(&|p| {
+            p.read(true).delete(true);
+        }));
+        assert!(!delete(&|p| {
+            p.write(true).no_delete(true);
+        }));
+        assert!(!delete(&|p| {
+            p.create(true).delete(true).no_delete(true);
+        }));
+    }
 }
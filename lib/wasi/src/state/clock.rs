@@ -0,0 +1,202 @@
+//! The clocks read by the `clock_res_get` and `clock_time_get` syscalls.
+
+use crate::syscalls::types::*;
+use crate::syscalls::{platform_clock_res_get, platform_clock_time_get};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// A source of time for a WASI module.
+///
+/// [`HostClock`] is used by default; [`ManualClock`] and [`OffsetClock`] make
+/// it possible to freeze, shift or scale the time seen by the module, for
+/// example to replay an execution deterministically.
+pub trait WasiClock: fmt::Debug + Send + Sync + 'static {
+    /// Get the resolution of the clock `clock_id`, in nanoseconds.
+    fn resolution(&self, clock_id: __wasi_clockid_t) -> Result<__wasi_timestamp_t, __wasi_errno_t>;
+
+    /// Get the time of the clock `clock_id`, in nanoseconds.
+    fn time(
+        &self,
+        clock_id: __wasi_clockid_t,
+        precision: __wasi_timestamp_t,
+    ) -> Result<__wasi_timestamp_t, __wasi_errno_t>;
+}
+
+/// The [`WasiClock`] reading the clocks of the host.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HostClock;
+
+impl WasiClock for HostClock {
+    fn resolution(&self, clock_id: __wasi_clockid_t) -> Result<__wasi_timestamp_t, __wasi_errno_t> {
+        let resolution = Cell::new(0);
+        match platform_clock_res_get(clock_id, &resolution) {
+            __WASI_ESUCCESS => Ok(resolution.get()),
+            errno => Err(errno),
+        }
+    }
+
+    fn time(
+        &self,
+        clock_id: __wasi_clockid_t,
+        precision: __wasi_timestamp_t,
+    ) -> Result<__wasi_timestamp_t, __wasi_errno_t> {
+        let time = Cell::new(0);
+        match platform_clock_time_get(clock_id, precision, &time) {
+            __WASI_ESUCCESS => Ok(time.get()),
+            errno => Err(errno),
+        }
+    }
+}
+
+fn check_clock_id(clock_id: __wasi_clockid_t) -> Result<(), __wasi_errno_t> {
+    match clock_id {
+        __WASI_CLOCK_REALTIME
+        | __WASI_CLOCK_MONOTONIC
+        | __WASI_CLOCK_PROCESS_CPUTIME_ID
+        | __WASI_CLOCK_THREAD_CPUTIME_ID => Ok(()),
+        _ => Err(__WASI_EINVAL),
+    }
+}
+
+/// A [`WasiClock`] which only moves when the host tells it to.
+///
+/// All the clocks read the same time, which starts at the value given to
+/// [`ManualClock::new`]. Clones share the same time, so a clone can be kept
+/// to move the time of a running module:
+///
+/// ```
+/// # use wasmer_wasi::{ManualClock, WasiState, WasiStateCreationError};
+/// # fn main() -> Result<(), WasiStateCreationError> {
+/// let clock = ManualClock::new(0);
+/// let wasi_env = WasiState::new("program_name")
+///     .clock(Box::new(clock.clone()))
+///     .finalize()?;
+/// // ... run the module, then let one second pass:
+/// clock.advance(1_000_000_000);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    time: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    /// Create a clock frozen at `time`, in nanoseconds.
+    pub fn new(time: __wasi_timestamp_t) -> Self {
+        Self {
+            time: Arc::new(AtomicU64::new(time)),
+        }
+    }
+
+    /// Set the time of the clock, in nanoseconds.
+    pub fn set(&self, time: __wasi_timestamp_t) {
+        self.time.store(time, Ordering::SeqCst);
+    }
+
+    /// Move the time of the clock forward by `nanos` nanoseconds.
+    pub fn advance(&self, nanos: __wasi_timestamp_t) {
+        self.time.fetch_add(nanos, Ordering::SeqCst);
+    }
+}
+
+impl WasiClock for ManualClock {
+    fn resolution(&self, clock_id: __wasi_clockid_t) -> Result<__wasi_timestamp_t, __wasi_errno_t> {
+        check_clock_id(clock_id)?;
+        Ok(1)
+    }
+
+    fn time(
+        &self,
+        clock_id: __wasi_clockid_t,
+        _precision: __wasi_timestamp_t,
+    ) -> Result<__wasi_timestamp_t, __wasi_errno_t> {
+        check_clock_id(clock_id)?;
+        Ok(self.time.load(Ordering::SeqCst))
+    }
+}
+
+/// A [`WasiClock`] shifting and scaling the time of another clock.
+///
+/// The time of each clock flows at `speed` times the speed of the inner
+/// clock from its first reading, and is shifted by `offset` nanoseconds.
+#[derive(Debug)]
+pub struct OffsetClock {
+    inner: Box<dyn WasiClock>,
+    offset: i64,
+    speed: f64,
+    /// The first reading of each clock of `inner`.
+    origins: Mutex<HashMap<__wasi_clockid_t, __wasi_timestamp_t>>,
+}
+
+impl OffsetClock {
+    /// Create a clock reading the time of `inner` shifted by `offset`
+    /// nanoseconds.
+    pub fn new(inner: Box<dyn WasiClock>, offset: i64) -> Self {
+        Self {
+            inner,
+            offset,
+            speed: 1.0,
+            origins: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Make the time flow `speed` times as fast as the time of the inner
+    /// clock, for example `0.5` for half as fast.
+    pub fn with_speed(mut self, speed: f64) -> Self {
+        self.speed = speed;
+        self
+    }
+}
+
+impl WasiClock for OffsetClock {
+    fn resolution(&self, clock_id: __wasi_clockid_t) -> Result<__wasi_timestamp_t, __wasi_errno_t> {
+        let resolution = self.inner.resolution(clock_id)? as f64 / self.speed;
+        Ok((resolution as __wasi_timestamp_t).max(1))
+    }
+
+    fn time(
+        &self,
+        clock_id: __wasi_clockid_t,
+        precision: __wasi_timestamp_t,
+    ) -> Result<__wasi_timestamp_t, __wasi_errno_t> {
+        let time = self.inner.time(clock_id, precision)?;
+        let origin = *self.origins.lock().unwrap().entry(clock_id).or_insert(time);
+        let elapsed = (time.saturating_sub(origin) as f64 * self.speed) as __wasi_timestamp_t;
+        let scaled = origin.saturating_add(elapsed);
+        Ok(if self.offset < 0 {
+            scaled.saturating_sub(self.offset.wrapping_neg() as u64)
+        } else {
+            scaled.saturating_add(self.offset as u64)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn manual_clock_only_moves_when_told() {
+        let clock = ManualClock::new(10);
+        let handle = clock.clone();
+        assert_eq!(clock.time(__WASI_CLOCK_MONOTONIC, 0), Ok(10));
+        handle.advance(5);
+        assert_eq!(clock.time(__WASI_CLOCK_REALTIME, 0), Ok(15));
+        handle.set(3);
+        assert_eq!(clock.time(__WASI_CLOCK_MONOTONIC, 0), Ok(3));
+        assert_eq!(clock.time(42, 0), Err(__WASI_EINVAL));
+    }
+
+    #[test]
+    fn offset_clock_shifts_and_scales() {
+        let inner = ManualClock::new(1_000);
+        let clock = OffsetClock::new(Box::new(inner.clone()), -100).with_speed(2.0);
+        assert_eq!(clock.time(__WASI_CLOCK_MONOTONIC, 0), Ok(900));
+        inner.advance(10);
+        assert_eq!(clock.time(__WASI_CLOCK_MONOTONIC, 0), Ok(920));
+    }
+}
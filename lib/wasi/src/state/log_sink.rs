@@ -0,0 +1,302 @@
+//! Capture of the stdout and stderr of a WASI module, line by line.
+
+use crate::state::{WasiFile, WasiFsError};
+use serde::{de, ser, Deserialize, Serialize};
+use std::fmt;
+use std::io::{self, Read, Seek, Write};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// The stream a [`LogLine`] was written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+/// A line written by a WASI module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogLine {
+    /// The stream the line was written to
+    pub stream: LogStream,
+    /// When the end of the line was written
+    pub timestamp: SystemTime,
+    /// The line, without its trailing newline; invalid UTF-8 is replaced
+    pub line: String,
+    /// Whether the end of the line was cut off by a limit of the [`LogSink`]
+    pub truncated: bool,
+}
+
+type LogCallback = Arc<dyn Fn(LogLine) + Send + Sync>;
+
+/// A [`WasiFile`] splitting what is written to it into [`LogLine`]s, which
+/// are given to a callback.
+///
+/// Usage:
+///
+/// ```
+/// # use wasmer_wasi::{LogSink, LogStream, WasiState, WasiStateCreationError};
+/// # fn main() -> Result<(), WasiStateCreationError> {
+/// let wasi_env = WasiState::new("program_name")
+///     .stdout(Box::new(
+///         LogSink::new(LogStream::Stdout, |line| println!("[guest] {}", line.line))
+///             .max_line_length(1024)
+///             .max_bytes(1024 * 1024),
+///     ))
+///     .stderr(Box::new(LogSink::tracing(LogStream::Stderr)))
+///     .finalize()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// A line which isn't terminated by a newline is given to the callback when
+/// the sink is dropped.
+pub struct LogSink {
+    stream: LogStream,
+    callback: LogCallback,
+    /// The current line
+    buffer: Vec<u8>,
+    /// Whether the current line went over `max_line_length`
+    line_truncated: bool,
+    max_line_length: Option<usize>,
+    max_bytes: Option<u64>,
+    /// The number of bytes given to the callback so far
+    bytes_logged: u64,
+}
+
+impl LogSink {
+    /// Create a sink giving the lines written to `stream` to `callback`.
+    pub fn new<F>(stream: LogStream, callback: F) -> Self
+    where
+        F: Fn(LogLine) + Send + Sync + 'static,
+    {
+        Self {
+            stream,
+            callback: Arc::new(callback),
+            buffer: Vec::new(),
+            line_truncated: false,
+            max_line_length: None,
+            max_bytes: None,
+            bytes_logged: 0,
+        }
+    }
+
+    /// Create a sink emitting the lines written to `stream` as `tracing`
+    /// events with the `wasi::guest` target, at the `INFO` level for stdout
+    /// and the `WARN` level for stderr.
+    pub fn tracing(stream: LogStream) -> Self {
+        Self::new(stream, |line| match line.stream {
+            LogStream::Stdout => {
+                tracing::info!(target: "wasi::guest", truncated = line.truncated, "{}", line.line)
+            }
+            LogStream::Stderr => {
+                tracing::warn!(target: "wasi::guest", truncated = line.truncated, "{}", line.line)
+            }
+        })
+    }
+
+    /// Cut the lines longer than `max_line_length` bytes.
+    pub fn max_line_length(mut self, max_line_length: usize) -> Self {
+        self.max_line_length = Some(max_line_length);
+        self
+    }
+
+    /// Stop giving lines to the callback once `max_bytes` bytes have been
+    /// logged; the line going over the limit is cut.
+    ///
+    /// The module isn't told about it: its writes keep succeeding.
+    pub fn max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    fn quota_reached(&self) -> bool {
+        matches!(self.max_bytes, Some(max) if self.bytes_logged >= max)
+    }
+
+    /// Whether the current line fills what is left of `max_bytes`.
+    fn quota_reached_with_buffer(&self) -> bool {
+        matches!(self.max_bytes, Some(max) if self.bytes_logged + self.buffer.len() as u64 >= max)
+    }
+
+    fn push_bytes(&mut self, bytes: &[u8]) {
+        let mut room = self.max_line_length.unwrap_or(usize::MAX) - self.buffer.len();
+        if let Some(max_bytes) = self.max_bytes {
+            let left = max_bytes - self.bytes_logged - self.buffer.len() as u64;
+            room = room.min(left as usize);
+        }
+        if bytes.len() > room {
+            self.line_truncated = true;
+        }
+        self.buffer
+            .extend_from_slice(&bytes[..bytes.len().min(room)]);
+    }
+
+    fn end_line(&mut self) {
+        let line = std::mem::take(&mut self.buffer);
+        self.bytes_logged += line.len() as u64;
+        let truncated = std::mem::replace(&mut self.line_truncated, false);
+        (self.callback)(LogLine {
+            stream: self.stream,
+            timestamp: SystemTime::now(),
+            line: String::from_utf8_lossy(&line).into_owned(),
+            truncated,
+        });
+    }
+}
+
+impl fmt::Debug for LogSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LogSink")
+            .field("stream", &self.stream)
+            .field("max_line_length", &self.max_line_length)
+            .field("max_bytes", &self.max_bytes)
+            .field("bytes_logged", &self.bytes_logged)
+            .finish()
+    }
+}
+
+impl Write for LogSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut rest = buf;
+        while !rest.is_empty() && !self.quota_reached() {
+            match rest.iter().position(|&byte| byte == b'\n') {
+                Some(newline) => {
+                    self.push_bytes(&rest[..newline]);
+                    self.end_line();
+                    rest = &rest[newline + 1..];
+                }
+                None => {
+                    self.push_bytes(rest);
+                    if self.line_truncated && self.quota_reached_with_buffer() {
+                        self.end_line();
+                    }
+                    break;
+                }
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for LogSink {
+    fn drop(&mut self) {
+        if !self.buffer.is_empty() || self.line_truncated {
+            self.end_line();
+        }
+    }
+}
+
+impl Read for LogSink {
+    fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "can not read from a log sink",
+        ))
+    }
+}
+
+impl Seek for LogSink {
+    fn seek(&mut self, _pos: io::SeekFrom) -> io::Result<u64> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "can not seek in a log sink",
+        ))
+    }
+}
+
+impl Serialize for LogSink {
+    fn serialize<S: serde::Serializer>(&self, _serializer: S) -> Result<S::Ok, S::Error> {
+        Err(ser::Error::custom("a `LogSink` can not be serialized"))
+    }
+}
+
+impl<'de> Deserialize<'de> for LogSink {
+    fn deserialize<D: serde::Deserializer<'de>>(_deserializer: D) -> Result<Self, D::Error> {
+        Err(de::Error::custom("a `LogSink` can not be deserialized"))
+    }
+}
+
+#[typetag::serde]
+impl WasiFile for LogSink {
+    fn last_accessed(&self) -> u64 {
+        0
+    }
+    fn last_modified(&self) -> u64 {
+        0
+    }
+    fn created_time(&self) -> u64 {
+        0
+    }
+    fn size(&self) -> u64 {
+        0
+    }
+    fn set_len(&mut self, _len: u64) -> Result<(), WasiFsError> {
+        Err(WasiFsError::PermissionDenied)
+    }
+    fn unlink(&mut self) -> Result<(), WasiFsError> {
+        Ok(())
+    }
+    fn bytes_available(&self) -> Result<usize, WasiFsError> {
+        Ok(0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn capture(configure: impl FnOnce(LogSink) -> LogSink) -> (LogSink, Arc<Mutex<Vec<LogLine>>>) {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let sink_lines = lines.clone();
+        let sink = LogSink::new(LogStream::Stdout, move |line| {
+            sink_lines.lock().unwrap().push(line)
+        });
+        (configure(sink), lines)
+    }
+
+    fn contents(lines: &Mutex<Vec<LogLine>>) -> Vec<(String, bool)> {
+        lines
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|line| (line.line.clone(), line.truncated))
+            .collect()
+    }
+
+    #[test]
+    fn splits_lines() {
+        let (mut sink, lines) = capture(|sink| sink);
+        sink.write_all(b"hello\nwor").unwrap();
+        sink.write_all(b"ld\n\nlast").unwrap();
+        assert_eq!(
+            contents(&lines),
+            vec![
+                ("hello".to_string(), false),
+                ("world".to_string(), false),
+                ("".to_string(), false),
+            ]
+        );
+        drop(sink);
+        assert_eq!(contents(&lines)[3], ("last".to_string(), false));
+    }
+
+    #[test]
+    fn enforces_limits() {
+        let (mut sink, lines) = capture(|sink| sink.max_line_length(4).max_bytes(10));
+        sink.write_all(b"abcdef\nabc\nabcdef\nmore\n").unwrap();
+        assert_eq!(
+            contents(&lines),
+            vec![
+                ("abcd".to_string(), true),
+                ("abc".to_string(), false),
+                ("abc".to_string(), true),
+            ]
+        );
+    }
+}
@@ -0,0 +1,649 @@
+//! An in-memory [`VirtualFs`], giving WASI modules a synthetic filesystem
+//! without any access to the host disk.
+
+use crate::state::{
+    VirtualDirEntry, VirtualFs, VirtualMetadata, VirtualOpenOptions, WasiFile, WasiFsError,
+};
+use crate::syscalls::types::*;
+use serde::{de, ser, Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, Read, Seek, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+fn now() -> __wasi_timestamp_t {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|time| time.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Times {
+    accessed: __wasi_timestamp_t,
+    modified: __wasi_timestamp_t,
+    created: __wasi_timestamp_t,
+}
+
+impl Times {
+    fn new() -> Self {
+        let now = now();
+        Self {
+            accessed: now,
+            modified: now,
+            created: now,
+        }
+    }
+}
+
+/// The contents of a file, shared between the filesystem and the open handles.
+#[derive(Debug, Serialize, Deserialize)]
+struct FileData {
+    bytes: Vec<u8>,
+    times: Times,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum Node {
+    File(Arc<Mutex<FileData>>),
+    Dir {
+        entries: BTreeMap<String, Node>,
+        times: Times,
+    },
+}
+
+impl Node {
+    fn new_file() -> Self {
+        Node::File(Arc::new(Mutex::new(FileData {
+            bytes: Vec::new(),
+            times: Times::new(),
+        })))
+    }
+
+    fn new_dir() -> Self {
+        Node::Dir {
+            entries: BTreeMap::new(),
+            times: Times::new(),
+        }
+    }
+
+    fn filetype(&self) -> __wasi_filetype_t {
+        match self {
+            Node::File(_) => __WASI_FILETYPE_REGULAR_FILE,
+            Node::Dir { .. } => __WASI_FILETYPE_DIRECTORY,
+        }
+    }
+
+    fn metadata(&self) -> VirtualMetadata {
+        let (len, times) = match self {
+            Node::File(data) => {
+                let data = data.lock().unwrap();
+                (data.bytes.len() as u64, data.times)
+            }
+            Node::Dir { entries, times } => (entries.len() as u64, *times),
+        };
+        VirtualMetadata {
+            filetype: self.filetype(),
+            len,
+            accessed: times.accessed,
+            modified: times.modified,
+            created: times.created,
+        }
+    }
+
+    fn entries(&self) -> Result<&BTreeMap<String, Node>, WasiFsError> {
+        match self {
+            Node::Dir { entries, .. } => Ok(entries),
+            Node::File(_) => Err(WasiFsError::BaseNotDirectory),
+        }
+    }
+
+    fn entries_mut(&mut self) -> Result<&mut BTreeMap<String, Node>, WasiFsError> {
+        match self {
+            Node::Dir { entries, .. } => Ok(entries),
+            Node::File(_) => Err(WasiFsError::BaseNotDirectory),
+        }
+    }
+
+    fn lookup(&self, components: &[String]) -> Result<&Node, WasiFsError> {
+        components.iter().try_fold(self, |node, name| {
+            node.entries()?.get(name).ok_or(WasiFsError::EntityNotFound)
+        })
+    }
+
+    fn lookup_mut(&mut self, components: &[String]) -> Result<&mut Node, WasiFsError> {
+        components.iter().try_fold(self, |node, name| {
+            node.entries_mut()?
+                .get_mut(name)
+                .ok_or(WasiFsError::EntityNotFound)
+        })
+    }
+
+    /// Get the entries of the parent directory of `components` along with the
+    /// name of the last component.
+    fn parent_mut<'a>(
+        &mut self,
+        components: &'a [String],
+    ) -> Result<(&mut BTreeMap<String, Node>, &'a str), WasiFsError> {
+        let (name, parent) = components
+            .split_last()
+            .ok_or(WasiFsError::PermissionDenied)?;
+        Ok((self.lookup_mut(parent)?.entries_mut()?, name))
+    }
+}
+
+/// Split `path` into its normal components, resolving `.` and `..`.
+pub(super) fn components(path: &Path) -> Vec<String> {
+    let mut components = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => components.push(name.to_string_lossy().to_string()),
+            Component::ParentDir => {
+                components.pop();
+            }
+            Component::Prefix(_) | Component::RootDir | Component::CurDir => {}
+        }
+    }
+    components
+}
+
+/// A [`VirtualFs`] keeping all of its files and directories in memory.
+///
+/// `MemFs` is cheap to clone and all the clones share the same contents, so
+/// the host can keep a clone to populate the filesystem and inspect what the
+/// module wrote to it.
+///
+/// ```
+/// # use wasmer_wasi::{MemFs, WasiState, WasiStateCreationError};
+/// # fn main() -> Result<(), WasiStateCreationError> {
+/// let fs = MemFs::new();
+/// fs.create_dir_all("/app/data").unwrap();
+/// fs.write_file("/app/config.toml", b"verbose = true").unwrap();
+///
+/// let state = WasiState::new("program_name")
+///     .fs_backend(Box::new(fs.clone()))
+///     .map_dir("app", "/app")?
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Serializing a `MemFs`, for example with [`WasiState::freeze`], takes a
+/// snapshot of its contents. The files opened by the module can't be
+/// serialized.
+///
+/// [`WasiState::freeze`]: crate::WasiState::freeze
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemFs {
+    root: Arc<Mutex<Node>>,
+}
+
+impl Default for MemFs {
+    fn default() -> Self {
+        Self {
+            root: Arc::new(Mutex::new(Node::new_dir())),
+        }
+    }
+}
+
+impl MemFs {
+    /// Create an empty in-memory filesystem.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create the directory at `path` and all of its missing parents.
+    pub fn create_dir_all(&self, path: impl AsRef<Path>) -> Result<(), WasiFsError> {
+        let mut root = self.root.lock().unwrap();
+        let mut node = &mut *root;
+        for name in components(path.as_ref()) {
+            node = node
+                .entries_mut()?
+                .entry(name)
+                .or_insert_with(Node::new_dir);
+        }
+        node.entries_mut().map(|_| ())
+    }
+
+    /// Create or replace the file at `path` with `contents`.
+    pub fn write_file(&self, path: impl AsRef<Path>, contents: &[u8]) -> Result<(), WasiFsError> {
+        let mut file = self.open(
+            path.as_ref(),
+            &VirtualOpenOptions {
+                write: true,
+                create: true,
+                truncate: true,
+                ..Default::default()
+            },
+        )?;
+        file.write_all(contents).map_err(Into::into)
+    }
+
+    /// Read the contents of the file at `path`.
+    pub fn read_file(&self, path: impl AsRef<Path>) -> Result<Vec<u8>, WasiFsError> {
+        let root = self.root.lock().unwrap();
+        match root.lookup(&components(path.as_ref()))? {
+            Node::File(data) => Ok(data.lock().unwrap().bytes.clone()),
+            Node::Dir { .. } => Err(WasiFsError::NotAFile),
+        }
+    }
+}
+
+#[typetag::serde]
+impl VirtualFs for MemFs {
+    fn metadata(&self, path: &Path) -> Result<VirtualMetadata, WasiFsError> {
+        let root = self.root.lock().unwrap();
+        Ok(root.lookup(&components(path))?.metadata())
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<VirtualDirEntry>, WasiFsError> {
+        let root = self.root.lock().unwrap();
+        Ok(root
+            .lookup(&components(path))?
+            .entries()?
+            .iter()
+            .map(|(name, node)| VirtualDirEntry {
+                name: name.clone(),
+                filetype: node.filetype(),
+            })
+            .collect())
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<(), WasiFsError> {
+        let mut root = self.root.lock().unwrap();
+        let components = components(path);
+        let (entries, name) = root.parent_mut(&components)?;
+        if entries.contains_key(name) {
+            return Err(WasiFsError::AlreadyExists);
+        }
+        entries.insert(name.to_string(), Node::new_dir());
+        Ok(())
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<(), WasiFsError> {
+        let mut root = self.root.lock().unwrap();
+        let components = components(path);
+        let (entries, name) = root.parent_mut(&components)?;
+        match entries.get(name) {
+            Some(Node::Dir { entries: dir, .. }) if dir.is_empty() => {}
+            Some(Node::Dir { .. }) => return Err(WasiFsError::DirectoryNotEmpty),
+            Some(Node::File(_)) => return Err(WasiFsError::BaseNotDirectory),
+            None => return Err(WasiFsError::EntityNotFound),
+        }
+        entries.remove(name);
+        Ok(())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), WasiFsError> {
+        let mut root = self.root.lock().unwrap();
+        let from = components(from);
+        let to = components(to);
+        if from == to {
+            return Ok(());
+        }
+        // a directory can't be moved inside of itself
+        if to.starts_with(&from) {
+            return Err(WasiFsError::InvalidInput);
+        }
+
+        // validate everything before modifying the tree
+        let source_is_dir = match root.lookup(&from)? {
+            Node::Dir { .. } => true,
+            Node::File(_) => false,
+        };
+        let (target_name, target_parent) = to.split_last().ok_or(WasiFsError::PermissionDenied)?;
+        match root.lookup(target_parent)?.entries()?.get(target_name) {
+            Some(Node::Dir { entries, .. }) if source_is_dir && !entries.is_empty() => {
+                return Err(WasiFsError::DirectoryNotEmpty)
+            }
+            Some(Node::Dir { .. }) if !source_is_dir => return Err(WasiFsError::NotAFile),
+            Some(Node::File(_)) if source_is_dir => return Err(WasiFsError::BaseNotDirectory),
+            _ => {}
+        }
+
+        let (entries, name) = root.parent_mut(&from)?;
+        let node = entries.remove(name).unwrap();
+        let (entries, name) = root.parent_mut(&to)?;
+        entries.insert(name.to_string(), node);
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), WasiFsError> {
+        let mut root = self.root.lock().unwrap();
+        let components = components(path);
+        let (entries, name) = root.parent_mut(&components)?;
+        match entries.get(name) {
+            Some(Node::File(_)) => {}
+            Some(Node::Dir { .. }) => return Err(WasiFsError::NotAFile),
+            None => return Err(WasiFsError::EntityNotFound),
+        }
+        entries.remove(name);
+        Ok(())
+    }
+
+    fn open(
+        &self,
+        path: &Path,
+        options: &VirtualOpenOptions,
+    ) -> Result<Box<dyn WasiFile>, WasiFsError> {
+        let mut root = self.root.lock().unwrap();
+        let components = components(path);
+        let (entries, name) = root.parent_mut(&components)?;
+        let node = match entries.get(name) {
+            Some(_) if options.create_new => return Err(WasiFsError::AlreadyExists),
+            Some(node) => node,
+            None if options.create || options.create_new => {
+                entries.insert(name.to_string(), Node::new_file());
+                &entries[name]
+            }
+            None => return Err(WasiFsError::EntityNotFound),
+        };
+        let data = match node {
+            Node::File(data) => data.clone(),
+            Node::Dir { .. } => return Err(WasiFsError::NotAFile),
+        };
+        if options.truncate {
+            let mut data = data.lock().unwrap();
+            data.bytes.clear();
+            data.times.modified = now();
+        }
+
+        Ok(Box::new(MemFile {
+            fs: self.clone(),
+            path: Mutex::new(path.to_path_buf()),
+            data,
+            position: 0,
+            read: options.read,
+            write: options.write || options.append,
+            append: options.append,
+        }))
+    }
+}
+
+/// A file opened from a [`MemFs`].
+pub struct MemFile {
+    fs: MemFs,
+    path: Mutex<PathBuf>,
+    data: Arc<Mutex<FileData>>,
+    position: u64,
+    read: bool,
+    write: bool,
+    append: bool,
+}
+
+impl fmt::Debug for MemFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemFile")
+            .field("path", &self.path)
+            .field("position", &self.position)
+            .field("read", &self.read)
+            .field("write", &self.write)
+            .field("append", &self.append)
+            .finish()
+    }
+}
+
+impl Serialize for MemFile {
+    fn serialize<S: serde::Serializer>(&self, _serializer: S) -> Result<S::Ok, S::Error> {
+        Err(ser::Error::custom(
+            "files of a `MemFs` can not be serialized",
+        ))
+    }
+}
+
+impl<'de> Deserialize<'de> for MemFile {
+    fn deserialize<D: serde::Deserializer<'de>>(_deserializer: D) -> Result<Self, D::Error> {
+        Err(de::Error::custom(
+            "files of a `MemFs` can not be deserialized",
+        ))
+    }
+}
+
+impl Read for MemFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !self.read {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "file was not opened for reading",
+            ));
+        }
+        let mut data = self.data.lock().unwrap();
+        let start = (self.position as usize).min(data.bytes.len());
+        let amt = buf.len().min(data.bytes.len() - start);
+        buf[..amt].copy_from_slice(&data.bytes[start..start + amt]);
+        self.position += amt as u64;
+        data.times.accessed = now();
+        Ok(amt)
+    }
+}
+
+impl Write for MemFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.write {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "file was not opened for writing",
+            ));
+        }
+        let mut data = self.data.lock().unwrap();
+        if self.append {
+            self.position = data.bytes.len() as u64;
+        }
+        let start = self.position as usize;
+        let end = start + buf.len();
+        if data.bytes.len() < end {
+            data.bytes.resize(end, 0);
+        }
+        data.bytes[start..end].copy_from_slice(buf);
+        self.position = end as u64;
+        data.times.modified = now();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for MemFile {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            io::SeekFrom::Start(offset) => (0, offset as i64),
+            io::SeekFrom::End(offset) => (self.size(), offset),
+            io::SeekFrom::Current(offset) => (self.position, offset),
+        };
+        let position = (base as i64).checked_add(offset).filter(|p| *p >= 0);
+        match position {
+            Some(position) => {
+                self.position = position as u64;
+                Ok(self.position)
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )),
+        }
+    }
+}
+
+#[typetag::serde]
+impl WasiFile for MemFile {
+    fn last_accessed(&self) -> __wasi_timestamp_t {
+        self.data.lock().unwrap().times.accessed
+    }
+
+    fn set_last_accessed(&self, last_accessed: __wasi_timestamp_t) {
+        self.data.lock().unwrap().times.accessed = last_accessed;
+    }
+
+    fn last_modified(&self) -> __wasi_timestamp_t {
+        self.data.lock().unwrap().times.modified
+    }
+
+    fn set_last_modified(&self, last_modified: __wasi_timestamp_t) {
+        self.data.lock().unwrap().times.modified = last_modified;
+    }
+
+    fn created_time(&self) -> __wasi_timestamp_t {
+        self.data.lock().unwrap().times.created
+    }
+
+    fn set_created_time(&self, created_time: __wasi_timestamp_t) {
+        self.data.lock().unwrap().times.created = created_time;
+    }
+
+    fn size(&self) -> u64 {
+        self.data.lock().unwrap().bytes.len() as u64
+    }
+
+    fn set_len(&mut self, new_size: __wasi_filesize_t) -> Result<(), WasiFsError> {
+        let mut data = self.data.lock().unwrap();
+        data.bytes.resize(new_size as usize, 0);
+        data.times.modified = now();
+        Ok(())
+    }
+
+    fn unlink(&mut self) -> Result<(), WasiFsError> {
+        self.fs.remove_file(&self.path.lock().unwrap())
+    }
+
+    fn rename_file(&self, new_name: &Path) -> Result<(), WasiFsError> {
+        let mut path = self.path.lock().unwrap();
+        self.fs.rename(&path, new_name)?;
+        *path = new_name.to_path_buf();
+        Ok(())
+    }
+
+    fn bytes_available(&self) -> Result<usize, WasiFsError> {
+        Ok((self.size() as usize).saturating_sub(self.position as usize))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn read_to_end(file: &mut Box<dyn WasiFile>) -> Vec<u8> {
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents).unwrap();
+        contents
+    }
+
+    #[test]
+    fn files_and_directories() {
+        let fs = MemFs::new();
+        fs.create_dir_all("/a/b").unwrap();
+        fs.write_file("/a/b/file", b"hello").unwrap();
+        assert_eq!(fs.read_file("/a/./c/../b/file").unwrap(), b"hello");
+
+        let metadata = fs.metadata(Path::new("/a/b/file")).unwrap();
+        assert!(metadata.is_file());
+        assert_eq!(metadata.len, 5);
+        assert!(fs.metadata(Path::new("/a")).unwrap().is_dir());
+        assert_eq!(
+            fs.metadata(Path::new("/a/missing")),
+            Err(WasiFsError::EntityNotFound)
+        );
+
+        fs.create_dir(Path::new("/a/c")).unwrap();
+        assert_eq!(
+            fs.create_dir(Path::new("/a/c")),
+            Err(WasiFsError::AlreadyExists)
+        );
+        assert_eq!(
+            fs.read_dir(Path::new("/a")).unwrap(),
+            vec![
+                VirtualDirEntry {
+                    name: "b".to_string(),
+                    filetype: __WASI_FILETYPE_DIRECTORY,
+                },
+                VirtualDirEntry {
+                    name: "c".to_string(),
+                    filetype: __WASI_FILETYPE_DIRECTORY,
+                },
+            ]
+        );
+
+        assert_eq!(
+            fs.remove_dir(Path::new("/a/b")),
+            Err(WasiFsError::DirectoryNotEmpty)
+        );
+        fs.rename(Path::new("/a/b/file"), Path::new("/a/c/file"))
+            .unwrap();
+        fs.remove_dir(Path::new("/a/b")).unwrap();
+        assert_eq!(fs.read_file("/a/c/file").unwrap(), b"hello");
+        assert_eq!(
+            fs.rename(Path::new("/a"), Path::new("/a/c/a")),
+            Err(WasiFsError::InvalidInput)
+        );
+
+        fs.remove_file(Path::new("/a/c/file")).unwrap();
+        assert_eq!(fs.read_file("/a/c/file"), Err(WasiFsError::EntityNotFound));
+    }
+
+    #[test]
+    fn open_files() {
+        let fs = MemFs::new();
+        let read_write = VirtualOpenOptions {
+            read: true,
+            write: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            fs.open(Path::new("/file"), &read_write).unwrap_err(),
+            WasiFsError::EntityNotFound
+        );
+
+        let mut file = fs
+            .open(
+                Path::new("/file"),
+                &VirtualOpenOptions {
+                    create_new: true,
+                    ..read_write
+                },
+            )
+            .unwrap();
+        file.write_all(b"hello world").unwrap();
+        file.seek(io::SeekFrom::Start(6)).unwrap();
+        assert_eq!(read_to_end(&mut file), b"world");
+        file.seek(io::SeekFrom::End(-5)).unwrap();
+        file.write_all(b"there").unwrap();
+        assert_eq!(fs.read_file("/file").unwrap(), b"hello there");
+
+        let mut appending = fs
+            .open(
+                Path::new("/file"),
+                &VirtualOpenOptions {
+                    append: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        appending.write_all(b"!").unwrap();
+        file.seek(io::SeekFrom::Start(0)).unwrap();
+        assert_eq!(read_to_end(&mut file), b"hello there!");
+
+        // the contents of unlinked files live as long as their handles
+        file.unlink().unwrap();
+        assert_eq!(fs.read_file("/file"), Err(WasiFsError::EntityNotFound));
+        file.seek(io::SeekFrom::Start(0)).unwrap();
+        assert_eq!(read_to_end(&mut file), b"hello there!");
+
+        let mut truncated = fs
+            .open(
+                Path::new("/other"),
+                &VirtualOpenOptions {
+                    create: true,
+                    truncate: true,
+                    ..read_write
+                },
+            )
+            .unwrap();
+        truncated.write_all(b"data").unwrap();
+        truncated.rename_file(Path::new("/renamed")).unwrap();
+        truncated.unlink().unwrap();
+        assert!(fs.read_dir(Path::new("/")).unwrap().is_empty());
+    }
+}
@@ -16,11 +16,28 @@
 #![allow(clippy::cognitive_complexity, clippy::too_many_arguments)]
 
 mod builder;
+mod clock;
+mod log_sink;
+mod mem_fs;
+mod net;
+mod overlay_fs;
+mod pipe;
+mod random;
 mod types;
+mod virtual_fs;
 
 pub use self::builder::*;
+pub use self::clock::*;
+pub use self::log_sink::*;
+pub use self::mem_fs::*;
+pub use self::net::*;
+pub use self::overlay_fs::*;
+pub use self::pipe::*;
+pub use self::random::*;
 pub use self::types::*;
+pub use self::virtual_fs::*;
 use crate::syscalls::types::*;
+use crate::{SyscallPolicy, WasiTracer};
 use generational_arena::Arena;
 pub use generational_arena::Index as Inode;
 use serde::{Deserialize, Serialize};
@@ -28,10 +45,9 @@ use std::collections::HashMap;
 use std::{
     borrow::Borrow,
     cell::Cell,
-    fs,
     io::Write,
-    path::{Path, PathBuf},
-    time::SystemTime,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
 };
 use tracing::debug;
 
@@ -95,6 +111,10 @@ pub enum Kind {
     /// The root is immutable after creation; generally the Kind::Root
     /// branch of whatever code you're writing will be a simpler version of
     /// your Kind::Dir logic
+    ///
+    /// The virtual directories containing the directories mapped at nested
+    /// aliases, like `app` for `app/data`, are also `Root`s: they are
+    /// immutable and their parent is themselves
     Root {
         entries: HashMap<String, Inode>,
     },
@@ -109,7 +129,8 @@ pub enum Kind {
         base_po_dir: __wasi_fd_t,
         /// The path to the symlink from the `base_po_dir`
         path_to_symlink: PathBuf,
-        /// the value of the symlink as a relative path
+        /// the value of the symlink as a relative path, or as an absolute path
+        /// from the virtual root in a jail
         relative_path: PathBuf,
     },
     Buffer {
@@ -150,6 +171,43 @@ impl Fd {
     pub const CREATE: u16 = 16;
 }
 
+/// Limits on how much a WASI module can write to its filesystem, along with
+/// how much it has written so far.
+///
+/// Syscalls which would go over a limit fail with `__WASI_ENOSPC`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WasiFsQuota {
+    /// The maximum number of bytes which can be written to files, if any
+    pub max_bytes_written: Option<u64>,
+    /// The maximum number of files, directories and links which can be created, if any
+    pub max_files: Option<u64>,
+    /// The number of bytes written to files so far
+    pub bytes_written: u64,
+    /// The number of files, directories and links created so far
+    pub files_created: u64,
+}
+
+impl WasiFsQuota {
+    /// Account for `bytes` more bytes being written to files.
+    pub(crate) fn write_bytes(&mut self, bytes: u64) -> Result<(), __wasi_errno_t> {
+        let bytes_written = self.bytes_written.saturating_add(bytes);
+        if matches!(self.max_bytes_written, Some(max) if bytes_written > max) {
+            return Err(__WASI_ENOSPC);
+        }
+        self.bytes_written = bytes_written;
+        Ok(())
+    }
+
+    /// Account for one more file, directory or link being created.
+    pub(crate) fn create_file(&mut self) -> Result<(), __wasi_errno_t> {
+        if matches!(self.max_files, Some(max) if self.files_created >= max) {
+            return Err(__WASI_ENOSPC);
+        }
+        self.files_created += 1;
+        Ok(())
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 /// Warning, modifying these fields directly may cause invariants to break and
 /// should be considered unsafe.  These fields may be made private in a future release
@@ -163,6 +221,66 @@ pub struct WasiFs {
     inode_counter: Cell<u64>,
     /// for fds still open after the file has been deleted
     pub orphan_fds: HashMap<Inode, InodeVal>,
+    /// The filesystem in which the preopened directories are looked up
+    pub fs_backend: Box<dyn VirtualFs>,
+    /// The limits on what can be written to the filesystem
+    pub quota: WasiFsQuota,
+    /// The maximum number of open fds, including stdio and the preopened
+    /// directories, if any; opening more fails with `__WASI_EMFILE`
+    #[serde(default)]
+    pub max_open_fds: Option<u32>,
+    /// Whether the module is jailed in the virtual root: absolute paths are
+    /// resolved from it, and the host paths of the preopened directories are
+    /// not shown to the module
+    #[serde(default)]
+    pub jail: bool,
+    /// Which symlinks are followed when resolving paths
+    #[serde(default)]
+    pub symlink_policy: SymlinkPolicy,
+}
+
+/// Which symlinks are followed when resolving the paths given by a WASI
+/// module; following a symlink which isn't allowed fails with
+/// `__WASI_ENOTCAPABLE`.
+///
+/// Symlinks are always visible to the module, with `path_readlink` or by not
+/// following them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SymlinkPolicy {
+    /// Never follow symlinks
+    Deny,
+    /// Follow the symlinks whose target is in the preopened directory
+    /// containing them, without going through `..` of that directory or an
+    /// absolute path
+    FollowWithinPreopen,
+    /// Follow all symlinks, their target being resolved from the virtual
+    /// root when it's outside of the preopened directory containing them.
+    /// Outside of a jail, an absolute symlink is resolved from the preopened
+    /// directory containing its target on the host, if any
+    FollowAll,
+}
+
+// `#[default]` variants need a newer compiler than the one supported
+#[allow(clippy::derivable_impls)]
+impl Default for SymlinkPolicy {
+    fn default() -> Self {
+        SymlinkPolicy::FollowAll
+    }
+}
+
+/// An open fd of a WASI module, listed by [`WasiFs::open_fds`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpenFd {
+    pub fd: __wasi_fd_t,
+    /// The name of the file, or the alias of a preopened directory
+    pub name: String,
+    /// The path of the file or directory in the filesystem backend, if any
+    pub path: Option<PathBuf>,
+    pub filetype: __wasi_filetype_t,
+    pub rights: __wasi_rights_t,
+    pub rights_inheriting: __wasi_rights_t,
+    pub flags: __wasi_fdflags_t,
+    pub is_preopened: bool,
 }
 
 impl WasiFs {
@@ -176,7 +294,7 @@ impl WasiFs {
         preopened_dirs: &[PathBuf],
         mapped_dirs: &[(String, PathBuf)],
     ) -> Result<Self, String> {
-        let (mut wasi_fs, root_inode) = Self::new_init()?;
+        let (mut wasi_fs, root_inode) = Self::new_init(Box::new(HostFs))?;
 
         debug!("wasi::fs::preopen_dirs");
         for dir in preopened_dirs {
@@ -290,15 +408,30 @@ impl WasiFs {
     }
 
     /// Created for the builder API. like `new` but with more information
-    pub(crate) fn new_with_preopen(preopens: &[PreopenedDir]) -> Result<Self, String> {
-        let (mut wasi_fs, root_inode) = Self::new_init()?;
-
+    pub(crate) fn new_with_preopen(
+        preopens: &[PreopenedDir],
+        fs_backend: Box<dyn VirtualFs>,
+        jail: bool,
+    ) -> Result<Self, String> {
+        let (mut wasi_fs, root_inode) = Self::new_init(fs_backend)?;
+        wasi_fs.jail = jail;
+
+        let mut preopens = preopens.iter().collect::<Vec<_>>();
+        // the directories mapped at nested aliases go in the directories
+        // mapped at their prefixes, so these are preopened first
+        preopens.sort_by_key(|preopen| {
+            preopen
+                .alias
+                .as_ref()
+                .map_or(0, |alias| alias.matches('/').count())
+        });
         for PreopenedDir {
             path,
             alias,
             read,
             write,
             create,
+            delete,
         } in preopens
         {
             debug!(
@@ -306,7 +439,7 @@ impl WasiFs {
                 &path.to_string_lossy(),
                 &alias
             );
-            let cur_dir_metadata = path.metadata().map_err(|e| {
+            let cur_dir_metadata = wasi_fs.fs_backend.metadata(path).map_err(|e| {
                 format!(
                     "Could not get metadata for file {:?}: {}",
                     path,
@@ -314,9 +447,31 @@ impl WasiFs {
                 )
             })?;
 
+            let name = match alias {
+                Some(alias) => alias.clone(),
+                // a jailed module only sees the name of the directory
+                None if jail => path
+                    .file_name()
+                    .ok_or_else(|| {
+                        format!(
+                            "Preopened directory {:?} must have an alias in a jail",
+                            path
+                        )
+                    })?
+                    .to_string_lossy()
+                    .into_owned(),
+                None => path.to_string_lossy().into_owned(),
+            };
+            let (parent_inode, entry_name) = match alias {
+                Some(alias) if alias.contains('/') => {
+                    wasi_fs.create_virtual_dirs(root_inode, alias)
+                }
+                _ => (root_inode, name.clone()),
+            };
+
             let kind = if cur_dir_metadata.is_dir() {
                 Kind::Dir {
-                    parent: Some(root_inode),
+                    parent: Some(parent_inode),
                     path: path.clone(),
                     entries: Default::default(),
                 }
@@ -339,7 +494,6 @@ impl WasiFs {
                         | __WASI_RIGHT_PATH_FILESTAT_GET
                         | __WASI_RIGHT_FD_FILESTAT_GET
                         | __WASI_RIGHT_PATH_LINK_SOURCE
-                        | __WASI_RIGHT_PATH_RENAME_SOURCE
                         | __WASI_RIGHT_POLL_FD_READWRITE
                         | __WASI_RIGHT_SOCK_SHUTDOWN;
                 }
@@ -354,8 +508,6 @@ impl WasiFs {
                         | __WASI_RIGHT_PATH_FILESTAT_SET_TIMES
                         | __WASI_RIGHT_FD_FILESTAT_SET_SIZE
                         | __WASI_RIGHT_FD_FILESTAT_SET_TIMES
-                        | __WASI_RIGHT_PATH_REMOVE_DIRECTORY
-                        | __WASI_RIGHT_PATH_UNLINK_FILE
                         | __WASI_RIGHT_POLL_FD_READWRITE
                         | __WASI_RIGHT_SOCK_SHUTDOWN;
                 }
@@ -363,23 +515,27 @@ impl WasiFs {
                     rights |= __WASI_RIGHT_PATH_CREATE_DIRECTORY
                         | __WASI_RIGHT_PATH_CREATE_FILE
                         | __WASI_RIGHT_PATH_LINK_TARGET
+                        | __WASI_RIGHT_PATH_SYMLINK
                         | __WASI_RIGHT_PATH_OPEN
                         | __WASI_RIGHT_PATH_RENAME_TARGET;
                 }
+                if *delete {
+                    // renaming a file away removes it from the directory
+                    rights |= __WASI_RIGHT_PATH_REMOVE_DIRECTORY
+                        | __WASI_RIGHT_PATH_UNLINK_FILE
+                        | __WASI_RIGHT_PATH_RENAME_SOURCE;
+                }
 
                 rights
             };
-            let inode = if let Some(alias) = &alias {
-                wasi_fs.create_inode(kind, true, alias.clone())
-            } else {
-                wasi_fs.create_inode(kind, true, path.to_string_lossy().into_owned())
-            }
-            .map_err(|e| {
-                format!(
-                    "Failed to create inode for preopened dir: WASI error code: {}",
-                    e
-                )
-            })?;
+            let inode = wasi_fs
+                .create_inode(kind, true, name.clone())
+                .map_err(|e| {
+                    format!(
+                        "Failed to create inode for preopened dir: WASI error code: {}",
+                        e
+                    )
+                })?;
             let fd_flags = {
                 let mut fd_flags = 0;
                 if *read {
@@ -397,17 +553,14 @@ impl WasiFs {
             let fd = wasi_fs
                 .create_fd(rights, rights, 0, fd_flags, inode)
                 .map_err(|e| format!("Could not open fd for file {:?}: {}", path, e))?;
-            if let Kind::Root { entries } = &mut wasi_fs.inodes[root_inode].kind {
-                let key = if let Some(alias) = &alias {
-                    alias.clone()
-                } else {
-                    path.to_string_lossy().into_owned()
-                };
-                let existing_entry = entries.insert(key.clone(), inode);
-                if existing_entry.is_some() {
-                    return Err(format!("Found duplicate entry for alias `{}`", key));
+            match &mut wasi_fs.inodes[parent_inode].kind {
+                Kind::Dir { entries, .. } | Kind::Root { entries } => {
+                    let existing_entry = entries.insert(entry_name, inode);
+                    if existing_entry.is_some() {
+                        return Err(format!("Found duplicate entry for alias `{}`", name));
+                    }
                 }
-                assert!(existing_entry.is_none())
+                _ => unreachable!("Preopened directories are only mapped in directories"),
             }
             wasi_fs.preopen_fds.push(fd);
         }
@@ -415,9 +568,59 @@ impl WasiFs {
         Ok(wasi_fs)
     }
 
+    /// Returns the directory in which the directory mapped at the nested
+    /// `alias` goes and its name there, creating the virtual directories
+    /// leading to it that are not mapped.
+    fn create_virtual_dirs(&mut self, root_inode: Inode, alias: &str) -> (Inode, String) {
+        let mut components = alias.split('/').collect::<Vec<_>>();
+        let name = components.pop().unwrap_or_default().to_string();
+
+        let mut cur_inode = root_inode;
+        for component in components {
+            let existing_entry = match &self.inodes[cur_inode].kind {
+                Kind::Dir { entries, .. } | Kind::Root { entries } => {
+                    entries.get(component).copied()
+                }
+                _ => unreachable!("Preopened directories are only mapped in directories"),
+            };
+            cur_inode = match existing_entry {
+                Some(inode) => inode,
+                None => {
+                    let inode = self.create_inode_with_stat(
+                        Kind::Root {
+                            entries: HashMap::new(),
+                        },
+                        false,
+                        component.to_string(),
+                        __wasi_filestat_t {
+                            st_filetype: __WASI_FILETYPE_DIRECTORY,
+                            ..__wasi_filestat_t::default()
+                        },
+                    );
+                    if let Kind::Dir { entries, .. } | Kind::Root { entries } =
+                        &mut self.inodes[cur_inode].kind
+                    {
+                        entries.insert(component.to_string(), inode);
+                    }
+                    inode
+                }
+            };
+        }
+
+        (cur_inode, name)
+    }
+
+    /// Returns the virtual root, in which the preopened directories are.
+    fn root_inode(&self) -> Option<Inode> {
+        self.inodes
+            .iter()
+            .find(|(_, inode)| inode.is_preopened && matches!(inode.kind, Kind::Root { .. }))
+            .map(|(inode, _)| inode)
+    }
+
     /// Private helper function to init the filesystem, called in `new` and
     /// `new_with_preopen`
-    fn new_init() -> Result<(Self, Inode), String> {
+    fn new_init(fs_backend: Box<dyn VirtualFs>) -> Result<(Self, Inode), String> {
         debug!("Initializing WASI filesystem");
         let inodes = Arena::new();
         let mut wasi_fs = Self {
@@ -428,6 +631,11 @@ impl WasiFs {
             next_fd: Cell::new(3),
             inode_counter: Cell::new(1024),
             orphan_fds: HashMap::new(),
+            fs_backend,
+            quota: WasiFsQuota::default(),
+            max_open_fds: None,
+            jail: false,
+            symlink_policy: SymlinkPolicy::default(),
         };
         wasi_fs.create_stdin();
         wasi_fs.create_stdout();
@@ -561,9 +769,15 @@ impl WasiFs {
             let segment_name = c.as_os_str().to_string_lossy().to_string();
             match &self.inodes[cur_inode].kind {
                 Kind::Dir { ref entries, .. } | Kind::Root { ref entries } => {
-                    if let Some(_entry) = entries.get(&segment_name) {
-                        // TODO: this should be fixed
-                        return Err(WasiFsError::AlreadyExists);
+                    // like `create_dir_all`, the existing directories are reused
+                    if let Some(entry) = entries.get(&segment_name) {
+                        match self.inodes[*entry].kind {
+                            Kind::Dir { .. } | Kind::Root { .. } => {
+                                cur_inode = *entry;
+                                continue;
+                            }
+                            _ => return Err(WasiFsError::AlreadyExists),
+                        }
                     }
 
                     let kind = Kind::Dir {
@@ -725,12 +939,16 @@ impl WasiFs {
     /// this code.
     ///
     /// TODO: write more tests for this code
+    ///
+    /// When `confined`, the path can't lead out of `base`, like the targets
+    /// of symlinks with [`SymlinkPolicy::FollowWithinPreopen`].
     fn get_inode_at_path_inner(
         &mut self,
         base: __wasi_fd_t,
         path: &str,
         mut symlink_count: u32,
         follow_symlinks: bool,
+        confined: bool,
     ) -> Result<Inode, __wasi_errno_t> {
         if symlink_count > MAX_SYMLINKS {
             return Err(__WASI_EMLINK);
@@ -739,12 +957,22 @@ impl WasiFs {
         let base_dir = self.get_fd(base)?;
         let path: &Path = Path::new(path);
 
-        let mut cur_inode = base_dir.inode;
+        let base_inode = base_dir.inode;
+        let mut cur_inode = base_inode;
         let n_components = path.components().count();
         // TODO: rights checks
         'path_iter: for (i, component) in path.components().enumerate() {
             // used to terminate symlink resolution properly
             let last_component = i + 1 == n_components;
+            if let Component::RootDir | Component::Prefix(_) = component {
+                // absolute paths would escape the preopened directories, unless
+                // they are resolved from the virtual root as in a jail
+                if !self.jail || confined {
+                    return Err(__WASI_ENOTCAPABLE);
+                }
+                cur_inode = self.root_inode().ok_or(__WASI_ENOTCAPABLE)?;
+                continue 'path_iter;
+            }
             // for each component traverse file structure
             // loading inodes as necessary
             'symlink_resolution: while symlink_count < MAX_SYMLINKS {
@@ -758,6 +986,9 @@ impl WasiFs {
                     } => {
                         match component.as_os_str().to_string_lossy().borrow() {
                             ".." => {
+                                if confined && cur_inode == base_inode {
+                                    return Err(__WASI_ENOTCAPABLE);
+                                }
                                 if let Some(p) = parent {
                                     cur_inode = *p;
                                     continue 'path_iter;
@@ -780,13 +1011,16 @@ impl WasiFs {
                                 cd.push(component);
                                 cd
                             };
-                            let metadata = file.symlink_metadata().ok().ok_or(__WASI_EINVAL)?;
-                            let file_type = metadata.file_type();
+                            let metadata = self
+                                .fs_backend
+                                .symlink_metadata(&file)
+                                .ok()
+                                .ok_or(__WASI_EINVAL)?;
                             // we want to insert newly opened dirs and files, but not transient symlinks
                             // TODO: explain why (think about this deeply when well rested)
                             let mut should_insert = false;
 
-                            let kind = if file_type.is_dir() {
+                            let kind = if metadata.is_dir() {
                                 should_insert = true;
                                 // load DIR
                                 Kind::Dir {
@@ -794,7 +1028,7 @@ impl WasiFs {
                                     path: file.clone(),
                                     entries: Default::default(),
                                 }
-                            } else if file_type.is_file() {
+                            } else if metadata.is_file() {
                                 should_insert = true;
                                 // load file
                                 Kind::File {
@@ -802,15 +1036,13 @@ impl WasiFs {
                                     path: file.clone(),
                                     fd: None,
                                 }
-                            } else if file_type.is_symlink() {
-                                let link_value = file.read_link().ok().ok_or(__WASI_EIO)?;
+                            } else if metadata.is_symlink() {
+                                let link_value =
+                                    self.fs_backend.read_link(&file).ok().ok_or(__WASI_EIO)?;
                                 debug!("attempting to decompose path {:?}", link_value);
 
-                                let (pre_open_dir_fd, relative_path) = if link_value.is_relative() {
-                                    self.path_into_pre_open_and_relative_path(&file)?
-                                } else {
-                                    unimplemented!("Absolute symlinks are not yet supported");
-                                };
+                                let (pre_open_dir_fd, relative_path) =
+                                    self.path_into_pre_open_and_relative_path(&file)?;
                                 loop_for_symlink = true;
                                 symlink_count += 1;
                                 Kind::Symlink {
@@ -819,57 +1051,36 @@ impl WasiFs {
                                     relative_path: link_value,
                                 }
                             } else {
-                                #[cfg(unix)]
+                                // special files such as devices and sockets
+                                let kind = Kind::File {
+                                    handle: None,
+                                    path: file.clone(),
+                                    fd: None,
+                                };
+                                let new_inode = self.create_inode_with_stat(
+                                    kind,
+                                    false,
+                                    file.to_string_lossy().to_string(),
+                                    __wasi_filestat_t {
+                                        st_filetype: metadata.filetype,
+                                        ..__wasi_filestat_t::default()
+                                    },
+                                );
+                                if let Kind::Dir {
+                                    ref mut entries, ..
+                                } = &mut self.inodes[cur_inode].kind
                                 {
-                                    use std::os::unix::fs::FileTypeExt;
-                                    let file_type: __wasi_filetype_t = if file_type.is_char_device()
-                                    {
-                                        __WASI_FILETYPE_CHARACTER_DEVICE
-                                    } else if file_type.is_block_device() {
-                                        __WASI_FILETYPE_BLOCK_DEVICE
-                                    } else if file_type.is_fifo() {
-                                        // FIFO doesn't seem to fit any other type, so unknown
-                                        __WASI_FILETYPE_UNKNOWN
-                                    } else if file_type.is_socket() {
-                                        // TODO: how do we know if it's a `__WASI_FILETYPE_SOCKET_STREAM` or
-                                        // a `__WASI_FILETYPE_SOCKET_DGRAM`?
-                                        __WASI_FILETYPE_SOCKET_STREAM
-                                    } else {
-                                        unimplemented!("state::get_inode_at_path unknown file type: not file, directory, symlink, char device, block device, fifo, or socket");
-                                    };
-
-                                    let kind = Kind::File {
-                                        handle: None,
-                                        path: file.clone(),
-                                        fd: None,
-                                    };
-                                    let new_inode = self.create_inode_with_stat(
-                                        kind,
-                                        false,
-                                        file.to_string_lossy().to_string(),
-                                        __wasi_filestat_t {
-                                            st_filetype: file_type,
-                                            ..__wasi_filestat_t::default()
-                                        },
+                                    entries.insert(
+                                        component.as_os_str().to_string_lossy().to_string(),
+                                        new_inode,
+                                    );
+                                } else {
+                                    unreachable!(
+                                        "Attempted to insert special device into non-directory"
                                     );
-                                    if let Kind::Dir {
-                                        ref mut entries, ..
-                                    } = &mut self.inodes[cur_inode].kind
-                                    {
-                                        entries.insert(
-                                            component.as_os_str().to_string_lossy().to_string(),
-                                            new_inode,
-                                        );
-                                    } else {
-                                        unreachable!(
-                                            "Attempted to insert special device into non-directory"
-                                        );
-                                    }
-                                    // perhaps just continue with symlink resolution and return at the end
-                                    return Ok(new_inode);
                                 }
-                                #[cfg(not(unix))]
-                                unimplemented!("state::get_inode_at_path unknown file type: not file, directory, or symlink");
+                                // perhaps just continue with symlink resolution and return at the end
+                                return Ok(new_inode);
                             };
 
                             let new_inode =
@@ -918,18 +1129,35 @@ impl WasiFs {
                         path_to_symlink,
                         relative_path,
                     } => {
-                        let new_base_dir = *base_po_dir;
-                        // allocate to reborrow mutabily to recur
-                        let new_path = {
-                            /*if let Kind::Root { .. } = self.inodes[base_po_dir].kind {
-                                assert!(false, "symlinks should never be relative to the root");
-                            }*/
+                        let confined = match self.symlink_policy {
+                            SymlinkPolicy::Deny => return Err(__WASI_ENOTCAPABLE),
+                            SymlinkPolicy::FollowWithinPreopen => true,
+                            SymlinkPolicy::FollowAll => false,
+                        };
+                        let (new_base_dir, new_path) = if relative_path.is_absolute() && !self.jail
+                        {
+                            // outside of a jail, absolute symlinks point to the host: resolve
+                            // them from the preopened directory containing their target
+                            if confined {
+                                return Err(__WASI_ENOTCAPABLE);
+                            }
+                            let link_value = relative_path.clone();
+                            let (po_fd, rest) = self
+                                .path_into_pre_open_and_relative_path(&link_value)
+                                .map_err(|_| __WASI_ENOTCAPABLE)?;
+                            // the virtual root only contains the preopened directories
+                            if let Kind::Root { .. } = self.inodes[self.get_fd(po_fd)?.inode].kind {
+                                return Err(__WASI_ENOTCAPABLE);
+                            }
+                            (po_fd, rest.to_string_lossy().to_string())
+                        } else {
+                            // allocate to reborrow mutabily to recur
                             let mut base = path_to_symlink.clone();
                             // remove the symlink file itself from the path, leaving just the path from the base
                             // to the dir containing the symlink
                             base.pop();
                             base.push(relative_path);
-                            base.to_string_lossy().to_string()
+                            (*base_po_dir, base.to_string_lossy().to_string())
                         };
                         debug!("Following symlink recursively");
                         let symlink_inode = self.get_inode_at_path_inner(
@@ -937,6 +1165,7 @@ impl WasiFs {
                             &new_path,
                             symlink_count + 1,
                             follow_symlinks,
+                            confined,
                         )?;
                         cur_inode = symlink_inode;
                         // if we're at the very end and we found a file, then we're done
@@ -969,9 +1198,14 @@ impl WasiFs {
         &self,
         path: &Path,
     ) -> Result<(__wasi_fd_t, PathBuf), __wasi_errno_t> {
+        let mut found: Option<(__wasi_fd_t, &Path)> = None;
         // for each preopened directory
         for po_fd in &self.preopen_fds {
-            let po_inode = self.fd_map[po_fd].inode;
+            let po_inode = match self.fd_map.get(po_fd) {
+                Some(fd) => fd.inode,
+                // the preopened directory has been closed
+                None => continue,
+            };
             let po_path = match &self.inodes[po_inode].kind {
                 Kind::Dir { path, .. } => &**path,
                 Kind::Root { .. } => Path::new("/"),
@@ -981,12 +1215,18 @@ impl WasiFs {
             if let Ok(rest) = path.strip_prefix(po_path) {
                 // if any path meets this criteria
                 // (verify that all remaining components are not symlinks except for maybe last? (or do the more complex logic of resolving intermediary symlinks))
-                // return preopened dir and the rest of the path
-
-                return Ok((*po_fd, rest.to_owned()));
+                // keep the closest preopened dir, the root being the furthest
+                if found.map_or(true, |(_, found_rest)| {
+                    rest.components().count() < found_rest.components().count()
+                }) {
+                    found = Some((*po_fd, rest));
+                }
             }
         }
-        Err(__WASI_EINVAL) // this may not make sense
+        // return preopened dir and the rest of the path
+        found
+            .map(|(po_fd, rest)| (po_fd, rest.to_owned()))
+            .ok_or(__WASI_EINVAL) // this may not make sense
     }
 
     // if this is still dead code and the year is 2020 or later, please delete this function
@@ -1059,7 +1299,7 @@ impl WasiFs {
         path: &str,
         follow_symlinks: bool,
     ) -> Result<Inode, __wasi_errno_t> {
-        self.get_inode_at_path_inner(base, path, 0, follow_symlinks)
+        self.get_inode_at_path_inner(base, path, 0, follow_symlinks, false)
     }
 
     /// Returns the parent Dir or Root that the file at a given path is in and the file name
@@ -1085,6 +1325,65 @@ impl WasiFs {
             .map(|v| (v, new_entity_name))
     }
 
+    /// Returns the rights of the preopened directory that the directory
+    /// `inode` is in.
+    ///
+    /// Paths can go through the virtual root to another preopened directory
+    /// (for example `../other/file`), so the rights of the fd that a path is
+    /// relative to must be restricted by the rights of the directory that the
+    /// path ends up in.
+    pub(crate) fn preopen_rights(&self, inode: Inode) -> __wasi_rights_t {
+        let mut cur_inode = inode;
+        loop {
+            if self.inodes[cur_inode].is_preopened {
+                return self
+                    .preopen_fds
+                    .iter()
+                    .filter_map(|po_fd| self.fd_map.get(po_fd))
+                    .find(|po_fd| po_fd.inode == cur_inode)
+                    // the preopen has been closed, nothing is allowed anymore
+                    .map_or(0, |po_fd| po_fd.rights);
+            }
+            match &self.inodes[cur_inode].kind {
+                Kind::Dir {
+                    parent: Some(parent),
+                    ..
+                } => cur_inode = *parent,
+                _ => return 0,
+            }
+        }
+    }
+
+    /// Returns the rights of the preopened directory that the directory
+    /// containing the entry at `path` is in.
+    pub(crate) fn parent_preopen_rights(
+        &mut self,
+        base: __wasi_fd_t,
+        path: &Path,
+    ) -> Result<__wasi_rights_t, __wasi_errno_t> {
+        let parent_dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let mut parent_inode = self.get_inode_at_path(base, &parent_dir.to_string_lossy(), true)?;
+        // the last component of a path is not resolved when it's a symlink
+        for _ in 0..MAX_SYMLINKS {
+            let (base_po_dir, symlink_target) = match &self.inodes[parent_inode].kind {
+                Kind::Symlink {
+                    base_po_dir,
+                    path_to_symlink,
+                    relative_path,
+                } => {
+                    let mut target = path_to_symlink.clone();
+                    target.pop();
+                    target.push(relative_path);
+                    (*base_po_dir, target)
+                }
+                _ => return Ok(self.preopen_rights(parent_inode)),
+            };
+            parent_inode =
+                self.get_inode_at_path(base_po_dir, &symlink_target.to_string_lossy(), true)?;
+        }
+        Err(__WASI_EMLINK)
+    }
+
     pub fn get_fd(&self, fd: __wasi_fd_t) -> Result<&Fd, __wasi_errno_t> {
         self.fd_map.get(&fd).ok_or(__WASI_EBADF)
     }
@@ -1269,6 +1568,10 @@ impl WasiFs {
         open_flags: u16,
         inode: Inode,
     ) -> Result<__wasi_fd_t, __wasi_errno_t> {
+        if matches!(self.max_open_fds, Some(max) if self.fd_map.len() >= max as usize) {
+            debug!("wasi::fs: too many open fds");
+            return Err(__WASI_EMFILE);
+        }
         let idx = self.next_fd.get();
         self.next_fd.set(idx + 1);
         self.fd_map.insert(
@@ -1285,6 +1588,60 @@ impl WasiFs {
         Ok(idx)
     }
 
+    /// List the open fds, by increasing number.
+    pub fn open_fds(&self) -> Vec<OpenFd> {
+        let mut open_fds = self
+            .fd_map
+            .iter()
+            .filter_map(|(fd, fd_entry)| {
+                let inode = &self.inodes[fd_entry.inode];
+                if let Kind::File { handle: None, .. } = inode.kind {
+                    if *fd <= __WASI_STDERR_FILENO {
+                        return None;
+                    }
+                }
+                let path = match &inode.kind {
+                    Kind::File { path, .. } | Kind::Dir { path, .. } if path.as_os_str() != "" => {
+                        Some(path.clone())
+                    }
+                    _ => None,
+                };
+                Some(OpenFd {
+                    fd: *fd,
+                    name: inode.name.clone(),
+                    path,
+                    filetype: inode.stat.st_filetype,
+                    rights: fd_entry.rights,
+                    rights_inheriting: fd_entry.rights_inheriting,
+                    flags: fd_entry.flags,
+                    is_preopened: inode.is_preopened,
+                })
+            })
+            .collect::<Vec<_>>();
+        open_fds.sort_by_key(|open_fd| open_fd.fd);
+        open_fds
+    }
+
+    /// Create an fd for a socket, which is stored like a file.
+    pub(crate) fn create_socket_fd(
+        &mut self,
+        socket: Box<dyn WasiFile>,
+        rights: __wasi_rights_t,
+        flags: __wasi_fdflags_t,
+    ) -> Result<__wasi_fd_t, __wasi_errno_t> {
+        let stat = __wasi_filestat_t {
+            st_filetype: __WASI_FILETYPE_SOCKET_STREAM,
+            ..__wasi_filestat_t::default()
+        };
+        let kind = Kind::File {
+            handle: Some(socket),
+            path: PathBuf::new(),
+            fd: None,
+        };
+        let inode = self.create_inode_with_stat(kind, false, "socket".to_string(), stat);
+        self.create_fd(rights, rights, flags, 0, inode)
+    }
+
     /// Low level function to remove an inode, that is it deletes the WASI FS's
     /// knowledge of a file.
     ///
@@ -1395,9 +1752,9 @@ impl WasiFs {
                         ..__wasi_filestat_t::default()
                     })
                 }
-                None => path.metadata().ok()?,
+                None => self.fs_backend.metadata(path).ok()?,
             },
-            Kind::Dir { path, .. } => path.metadata().ok()?,
+            Kind::Dir { path, .. } => self.fs_backend.metadata(path).ok()?,
             Kind::Symlink {
                 base_po_dir,
                 path_to_symlink,
@@ -1407,7 +1764,7 @@ impl WasiFs {
                 let base_po_inode_v = &self.inodes[*base_po_inode];
                 match &base_po_inode_v.kind {
                     Kind::Root { .. } => {
-                        path_to_symlink.clone().symlink_metadata().ok()?
+                        self.fs_backend.symlink_metadata(path_to_symlink).ok()?
                     }
                     Kind::Dir { path, .. } => {
                         let mut real_path = path.clone();
@@ -1418,7 +1775,7 @@ impl WasiFs {
                         // TODO: adjust size of symlink, too
                         //      for all paths adjusted think about this
                         real_path.push(path_to_symlink);
-                        real_path.symlink_metadata().ok()?
+                        self.fs_backend.symlink_metadata(&real_path).ok()?
                     }
                     // if this triggers, there's a bug in the symlink code
                     _ => unreachable!("Symlink pointing to something that's not a directory as its base preopened directory"),
@@ -1427,26 +1784,11 @@ impl WasiFs {
             _ => return None,
         };
         Some(__wasi_filestat_t {
-            st_filetype: host_file_type_to_wasi_file_type(md.file_type()),
-            st_size: md.len(),
-            st_atim: md
-                .accessed()
-                .ok()?
-                .duration_since(SystemTime::UNIX_EPOCH)
-                .ok()?
-                .as_nanos() as u64,
-            st_mtim: md
-                .modified()
-                .ok()?
-                .duration_since(SystemTime::UNIX_EPOCH)
-                .ok()?
-                .as_nanos() as u64,
-            st_ctim: md
-                .created()
-                .ok()
-                .and_then(|ct| ct.duration_since(SystemTime::UNIX_EPOCH).ok())
-                .map(|ct| ct.as_nanos() as u64)
-                .unwrap_or(0),
+            st_filetype: md.filetype,
+            st_size: md.len,
+            st_atim: md.accessed,
+            st_mtim: md.modified,
+            st_ctim: md.created,
             ..__wasi_filestat_t::default()
         })
     }
@@ -1460,6 +1802,11 @@ impl WasiFs {
             Kind::File { ref mut handle, .. } => {
                 let mut empty_handle = None;
                 std::mem::swap(handle, &mut empty_handle);
+                // the stdio fds are kept, so that the host can still find
+                // out that they are closed
+                if fd > __WASI_STDERR_FILENO {
+                    self.fd_map.remove(&fd);
+                }
             }
             Kind::Dir { parent, path, .. } => {
                 debug!("Closing dir {:?}", &path);
@@ -1540,6 +1887,63 @@ pub struct WasiState {
     pub fs: WasiFs,
     pub args: Vec<Vec<u8>>,
     pub envs: Vec<Vec<u8>>,
+    /// The source of time of the `clock_*` syscalls.
+    ///
+    /// It is not serialized: an unfrozen `WasiState` uses a [`HostClock`].
+    #[serde(skip, default = "default_clock")]
+    pub clock: Box<dyn WasiClock>,
+    /// The source of the bytes of the `random_get` syscall.
+    ///
+    /// It is not serialized: an unfrozen `WasiState` uses a [`HostRandom`].
+    #[serde(skip, default = "default_random")]
+    pub random: Box<dyn WasiRandom>,
+    /// Whether `clock` is the [`HostClock`] used by default.
+    #[serde(skip, default = "default_true")]
+    pub(crate) uses_host_clock: bool,
+    /// Whether `random` is the [`HostRandom`] used by default.
+    #[serde(skip, default = "default_true")]
+    pub(crate) uses_host_random: bool,
+    /// The addresses the socket syscalls may use.
+    pub network: NetworkPolicy,
+    /// Called by the `proc_exit` syscall; it is not serialized.
+    #[serde(skip)]
+    pub(crate) on_exit: Option<ExitHook>,
+    /// Traces the syscalls of the module; it is not serialized.
+    #[serde(skip)]
+    pub(crate) tracer: Option<Arc<dyn WasiTracer>>,
+    /// Decides which syscalls the module can make; it is not serialized.
+    #[serde(skip)]
+    pub(crate) syscall_policy: Option<Arc<dyn SyscallPolicy>>,
+    /// The wasi-nn backends and graphs; they are not serialized.
+    #[cfg(feature = "wasi-nn")]
+    #[serde(skip)]
+    pub(crate) nn: crate::nn::WasiNn,
+    /// The wasi-crypto key store and objects; they are not serialized.
+    #[cfg(feature = "wasi-crypto")]
+    #[serde(skip)]
+    pub(crate) crypto: crate::crypto::WasiCrypto,
+}
+
+/// A callback given the exit code of a module calling `proc_exit`.
+#[derive(Clone)]
+pub(crate) struct ExitHook(pub(crate) Arc<dyn Fn(__wasi_exitcode_t) + Send + Sync>);
+
+impl std::fmt::Debug for ExitHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ExitHook")
+    }
+}
+
+fn default_clock() -> Box<dyn WasiClock> {
+    Box::new(HostClock)
+}
+
+fn default_random() -> Box<dyn WasiRandom> {
+    Box::new(HostRandom)
+}
+
+fn default_true() -> bool {
+    true
 }
 
 impl WasiState {
@@ -1559,17 +1963,21 @@ impl WasiState {
     pub fn unfreeze(bytes: &[u8]) -> Option<Self> {
         bincode::deserialize(bytes).ok()
     }
-}
 
-pub fn host_file_type_to_wasi_file_type(file_type: fs::FileType) -> __wasi_filetype_t {
-    // TODO: handle other file types
-    if file_type.is_dir() {
-        __WASI_FILETYPE_DIRECTORY
-    } else if file_type.is_file() {
-        __WASI_FILETYPE_REGULAR_FILE
-    } else if file_type.is_symlink() {
-        __WASI_FILETYPE_SYMBOLIC_LINK
-    } else {
-        __WASI_FILETYPE_UNKNOWN
+    /// Replace the sources reading the host with reproducible ones.
+    ///
+    /// A [`HostClock`] is replaced by a [`ManualClock`] frozen at 0 and a
+    /// [`HostRandom`] by a [`SeededRandom`] seeded with 0; a clock or random
+    /// source set on the [`WasiStateBuilder`] is kept. This is done for the
+    /// imports generated for a deterministic [`Store`](wasmer::Store).
+    pub fn make_deterministic(&mut self) {
+        if self.uses_host_clock {
+            self.clock = Box::new(ManualClock::new(0));
+            self.uses_host_clock = false;
+        }
+        if self.uses_host_random {
+            self.random = Box::new(SeededRandom::new(0));
+            self.uses_host_random = false;
+        }
     }
 }
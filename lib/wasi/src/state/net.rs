@@ -0,0 +1,266 @@
+//! TCP sockets for WASI modules, limited to the addresses allowed by the host.
+
+use crate::state::{WasiFile, WasiFsError, WasiStateCreationError};
+use serde::{de, ser, Deserialize, Serialize};
+use std::io::{self, Read, Seek, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+
+/// The hosts and ports a WASI module may connect to, listen on or resolve.
+///
+/// Nothing is allowed by default. Patterns have the form `host:port`, where:
+/// - `host` is a host name or an IP address (IPv6 addresses between
+///   brackets), `*` for any host, or `*.example.com` for the subdomains of
+///   `example.com`;
+/// - `port` is a port, a range of ports like `8000-8999`, or `*` for any port.
+///
+/// Host names are compared to the name given by the module, before it's
+/// resolved.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkPolicy {
+    rules: Vec<AddrPattern>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct AddrPattern {
+    host: HostPattern,
+    ports: (u16, u16),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+enum HostPattern {
+    Any,
+    /// The lowercase suffix of the subdomains, starting with a dot
+    Subdomains(String),
+    /// A lowercase host name or address
+    Exact(String),
+}
+
+impl NetworkPolicy {
+    /// Create a policy allowing nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a policy allowing every host and port.
+    pub fn allow_all() -> Self {
+        let mut policy = Self::new();
+        policy.rules.push(AddrPattern {
+            host: HostPattern::Any,
+            ports: (0, u16::MAX),
+        });
+        policy
+    }
+
+    /// Allow the hosts and ports matching `pattern`.
+    pub fn allow(&mut self, pattern: &str) -> Result<&mut Self, WasiStateCreationError> {
+        let invalid = || WasiStateCreationError::NetworkPatternFormatError(pattern.to_string());
+        let colon = pattern.rfind(':').ok_or_else(invalid)?;
+        let (host, port) = (&pattern[..colon], &pattern[colon + 1..]);
+
+        let host = match host {
+            "" => return Err(invalid()),
+            "*" => HostPattern::Any,
+            _ if host.starts_with("*.") => HostPattern::Subdomains(host[1..].to_lowercase()),
+            _ if host.contains('*') => return Err(invalid()),
+            _ => HostPattern::Exact(normalize_host(host).to_lowercase()),
+        };
+        let ports = match port {
+            "*" => (0, u16::MAX),
+            _ => {
+                let (start, end) = match port.find('-') {
+                    Some(dash) => (&port[..dash], &port[dash + 1..]),
+                    None => (port, port),
+                };
+                let start = start.parse::<u16>().map_err(|_| invalid())?;
+                let end = end.parse::<u16>().map_err(|_| invalid())?;
+                if start > end {
+                    return Err(invalid());
+                }
+                (start, end)
+            }
+        };
+
+        self.rules.push(AddrPattern { host, ports });
+        Ok(self)
+    }
+
+    /// Check whether `host` and `port` are allowed.
+    pub fn is_allowed(&self, host: &str, port: u16) -> bool {
+        let host = normalize_host(host).to_lowercase();
+        self.rules.iter().any(|rule| {
+            let host_matches = match &rule.host {
+                HostPattern::Any => true,
+                HostPattern::Subdomains(suffix) => host.ends_with(suffix.as_str()),
+                HostPattern::Exact(exact) => host == *exact,
+            };
+            host_matches && rule.ports.0 <= port && port <= rule.ports.1
+        })
+    }
+}
+
+/// Remove the brackets around an IPv6 address.
+fn normalize_host(host: &str) -> &str {
+    if host.starts_with('[') && host.ends_with(']') {
+        &host[1..host.len() - 1]
+    } else {
+        host
+    }
+}
+
+/// A connected TCP socket, which is a [`WasiFile`] so that `fd_read` and
+/// `fd_write` work on it.
+#[derive(Debug)]
+pub(crate) struct WasiTcpStream {
+    pub(crate) stream: TcpStream,
+}
+
+/// A listening TCP socket, from which connections are accepted with
+/// `sock_accept`.
+#[derive(Debug)]
+pub(crate) struct WasiTcpListener {
+    pub(crate) listener: TcpListener,
+}
+
+impl WasiTcpStream {
+    pub(crate) fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        self.stream.shutdown(how)
+    }
+}
+
+impl Read for WasiTcpStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.stream.read(buf)
+    }
+}
+
+impl Write for WasiTcpStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl Read for WasiTcpListener {
+    fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "can not read from a listening socket",
+        ))
+    }
+}
+
+impl Write for WasiTcpListener {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "can not write to a listening socket",
+        ))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+macro_rules! impl_socket {
+    ($socket:ident, $inner:ident, $name:literal) => {
+        impl Seek for $socket {
+            fn seek(&mut self, _pos: io::SeekFrom) -> io::Result<u64> {
+                Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "can not seek in a socket",
+                ))
+            }
+        }
+
+        impl Serialize for $socket {
+            fn serialize<S: serde::Serializer>(&self, _serializer: S) -> Result<S::Ok, S::Error> {
+                Err(ser::Error::custom(concat!(
+                    "a `",
+                    $name,
+                    "` can not be serialized"
+                )))
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $socket {
+            fn deserialize<D: serde::Deserializer<'de>>(
+                _deserializer: D,
+            ) -> Result<Self, D::Error> {
+                Err(de::Error::custom(concat!(
+                    "a `",
+                    $name,
+                    "` can not be deserialized"
+                )))
+            }
+        }
+
+        #[typetag::serde]
+        impl WasiFile for $socket {
+            fn last_accessed(&self) -> u64 {
+                0
+            }
+            fn last_modified(&self) -> u64 {
+                0
+            }
+            fn created_time(&self) -> u64 {
+                0
+            }
+            fn size(&self) -> u64 {
+                0
+            }
+            fn set_len(&mut self, _len: u64) -> Result<(), WasiFsError> {
+                Err(WasiFsError::PermissionDenied)
+            }
+            fn unlink(&mut self) -> Result<(), WasiFsError> {
+                Ok(())
+            }
+            fn bytes_available(&self) -> Result<usize, WasiFsError> {
+                Ok(0)
+            }
+
+            #[cfg(unix)]
+            fn get_raw_fd(&self) -> Option<i32> {
+                use std::os::unix::io::AsRawFd;
+                Some(self.$inner.as_raw_fd())
+            }
+        }
+    };
+}
+
+impl_socket!(WasiTcpStream, stream, "WasiTcpStream");
+impl_socket!(WasiTcpListener, listener, "WasiTcpListener");
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn network_policy_matches_patterns() {
+        let mut policy = NetworkPolicy::new();
+        assert!(!policy.is_allowed("example.com", 80));
+
+        policy
+            .allow("example.com:80")
+            .unwrap()
+            .allow("*.wasmer.io:8000-8999")
+            .unwrap()
+            .allow("[::1]:*")
+            .unwrap();
+        assert!(policy.is_allowed("Example.com", 80));
+        assert!(!policy.is_allowed("example.com", 443));
+        assert!(policy.is_allowed("registry.wasmer.io", 8080));
+        assert!(!policy.is_allowed("wasmer.io", 8080));
+        assert!(!policy.is_allowed("registry.wasmer.io", 9000));
+        assert!(policy.is_allowed("::1", 22));
+        assert!(policy.is_allowed("[::1]", 22));
+
+        assert!(NetworkPolicy::allow_all().is_allowed("anything", 1));
+        for invalid in &["example.com", "*:", "ex*ample.com:80", "a:9-8", "a:port"] {
+            assert!(policy.allow(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,521 @@
+//! A [`VirtualFs`] stacking a writable layer on top of a read-only one.
+
+use crate::state::{
+    mem_fs::components, VirtualDirEntry, VirtualFs, VirtualMetadata, VirtualOpenOptions, WasiFile,
+    WasiFsError,
+};
+use crate::syscalls::types::*;
+use serde::{de, ser, Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::io::{self, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// A union of two filesystems: the entries of `upper` shadow the entries of
+/// `lower`, and all the modifications are made in `upper`.
+///
+/// `lower` is never modified: files are copied to `upper` before being
+/// written to, and removed entries of `lower` are only hidden. This allows
+/// running an application image from `lower` while capturing all of its
+/// writes in `upper`, for example a [`MemFs`].
+///
+/// ```
+/// # use wasmer_wasi::{HostFs, MemFs, WasiState, WasiStateCreationError};
+/// # fn main() -> Result<(), WasiStateCreationError> {
+/// let writes = MemFs::new();
+/// let state = WasiState::new("program_name")
+///     .overlay_fs(Box::new(writes.clone()))
+///     .map_dir("app", ".")?
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`MemFs`]: crate::MemFs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OverlayFs {
+    layers: Arc<Mutex<Layers>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Layers {
+    lower: Box<dyn VirtualFs>,
+    upper: Box<dyn VirtualFs>,
+    /// Paths under which the entries of `lower` are hidden because they
+    /// were removed or replaced.
+    hidden: HashSet<PathBuf>,
+}
+
+/// Normalize `path` so that it can be compared with the hidden paths.
+fn normalize(path: &Path) -> PathBuf {
+    components(path).into_iter().collect()
+}
+
+impl Layers {
+    fn is_lower_visible(&self, path: &Path) -> bool {
+        !normalize(path)
+            .ancestors()
+            .any(|ancestor| self.hidden.contains(ancestor))
+    }
+
+    fn lower_metadata(&self, path: &Path) -> Result<VirtualMetadata, WasiFsError> {
+        if self.is_lower_visible(path) {
+            self.lower.metadata(path)
+        } else {
+            Err(WasiFsError::EntityNotFound)
+        }
+    }
+
+    fn hide_lower(&mut self, path: &Path) {
+        if self.lower_metadata(path).is_ok() {
+            self.hidden.insert(normalize(path));
+        }
+    }
+
+    fn metadata(&self, path: &Path) -> Result<VirtualMetadata, WasiFsError> {
+        self.upper
+            .metadata(path)
+            .or_else(|_| self.lower_metadata(path))
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<VirtualDirEntry>, WasiFsError> {
+        let upper = self.upper.read_dir(path);
+        let lower = if self.is_lower_visible(path) {
+            self.lower.read_dir(path)
+        } else {
+            Err(WasiFsError::EntityNotFound)
+        };
+        if let (Err(e), Err(_)) = (&upper, &lower) {
+            return Err(*e);
+        }
+
+        let mut entries = BTreeMap::new();
+        for entry in lower.unwrap_or_default() {
+            if self.is_lower_visible(&path.join(&entry.name)) {
+                entries.insert(entry.name.clone(), entry);
+            }
+        }
+        for entry in upper.unwrap_or_default() {
+            entries.insert(entry.name.clone(), entry);
+        }
+        Ok(entries.values().cloned().collect())
+    }
+
+    /// Create the parent directories of `path` in `upper`.
+    fn copy_up_parents(&self, path: &Path) -> Result<(), WasiFsError> {
+        let parent = path.parent().ok_or(WasiFsError::PermissionDenied)?;
+        if !self.metadata(parent)?.is_dir() {
+            return Err(WasiFsError::BaseNotDirectory);
+        }
+        let mut ancestors = parent.ancestors().collect::<Vec<_>>();
+        ancestors.reverse();
+        for ancestor in ancestors {
+            if components(ancestor).is_empty() || self.upper.metadata(ancestor).is_ok() {
+                continue;
+            }
+            self.upper.create_dir(ancestor)?;
+        }
+        Ok(())
+    }
+
+    /// Copy the file at `path` from `lower` to `upper`, if it isn't already there.
+    fn copy_up_file(&self, path: &Path) -> Result<(), WasiFsError> {
+        if self.upper.metadata(path).is_ok() {
+            return Ok(());
+        }
+        self.lower_metadata(path)?;
+        self.copy_up_parents(path)?;
+
+        let mut contents = Vec::new();
+        let read = VirtualOpenOptions {
+            read: true,
+            ..Default::default()
+        };
+        self.lower.open(path, &read)?.read_to_end(&mut contents)?;
+        let write = VirtualOpenOptions {
+            write: true,
+            create_new: true,
+            ..Default::default()
+        };
+        self.upper.open(path, &write)?.write_all(&contents)?;
+        Ok(())
+    }
+
+    /// Copy the directory at `path` and all of its contents from `lower` to `upper`.
+    fn copy_up_tree(&self, path: &Path) -> Result<(), WasiFsError> {
+        if self.upper.metadata(path).is_err() {
+            self.copy_up_parents(path)?;
+            self.upper.create_dir(path)?;
+        }
+        for entry in self.read_dir(path)? {
+            let entry_path = path.join(&entry.name);
+            match entry.filetype {
+                __WASI_FILETYPE_DIRECTORY => self.copy_up_tree(&entry_path)?,
+                __WASI_FILETYPE_REGULAR_FILE => self.copy_up_file(&entry_path)?,
+                // other kinds of entries can't be copied through a `VirtualFs`
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn create_dir(&mut self, path: &Path) -> Result<(), WasiFsError> {
+        if self.metadata(path).is_ok() {
+            return Err(WasiFsError::AlreadyExists);
+        }
+        self.copy_up_parents(path)?;
+        self.upper.create_dir(path)
+    }
+
+    fn remove_dir(&mut self, path: &Path) -> Result<(), WasiFsError> {
+        if !self.metadata(path)?.is_dir() {
+            return Err(WasiFsError::BaseNotDirectory);
+        }
+        if !self.read_dir(path)?.is_empty() {
+            return Err(WasiFsError::DirectoryNotEmpty);
+        }
+        if self.upper.metadata(path).is_ok() {
+            self.upper.remove_dir(path)?;
+        }
+        self.hide_lower(path);
+        Ok(())
+    }
+
+    fn remove_file(&mut self, path: &Path) -> Result<(), WasiFsError> {
+        if self.metadata(path)?.is_dir() {
+            return Err(WasiFsError::NotAFile);
+        }
+        if self.upper.metadata(path).is_ok() {
+            self.upper.remove_file(path)?;
+        }
+        self.hide_lower(path);
+        Ok(())
+    }
+
+    fn rename(&mut self, from: &Path, to: &Path) -> Result<(), WasiFsError> {
+        let source_is_dir = self.metadata(from)?.is_dir();
+        match self.metadata(to) {
+            Ok(target) if target.is_dir() && !source_is_dir => return Err(WasiFsError::NotAFile),
+            Ok(target) if !target.is_dir() && source_is_dir => {
+                return Err(WasiFsError::BaseNotDirectory)
+            }
+            Ok(target) if target.is_dir() && !self.read_dir(to)?.is_empty() => {
+                return Err(WasiFsError::DirectoryNotEmpty)
+            }
+            _ => {}
+        }
+
+        if source_is_dir {
+            self.copy_up_tree(from)?;
+        } else {
+            self.copy_up_file(from)?;
+        }
+        self.copy_up_parents(to)?;
+        self.upper.rename(from, to)?;
+        self.hide_lower(from);
+        self.hide_lower(to);
+        Ok(())
+    }
+
+    fn open(
+        &self,
+        path: &Path,
+        options: &VirtualOpenOptions,
+    ) -> Result<Box<dyn WasiFile>, WasiFsError> {
+        let modifies = options.write
+            || options.append
+            || options.truncate
+            || options.create
+            || options.create_new;
+        if !modifies {
+            if self.upper.metadata(path).is_ok() {
+                return self.upper.open(path, options);
+            }
+            self.lower_metadata(path)?;
+            return self.lower.open(path, options);
+        }
+
+        match self.metadata(path) {
+            Ok(_) if options.create_new => return Err(WasiFsError::AlreadyExists),
+            Ok(metadata) if metadata.is_dir() => return Err(WasiFsError::NotAFile),
+            Ok(_) => self.copy_up_file(path)?,
+            Err(_) if options.create || options.create_new => self.copy_up_parents(path)?,
+            Err(e) => return Err(e),
+        }
+        self.upper.open(path, options)
+    }
+}
+
+impl OverlayFs {
+    /// Stack `upper` on top of `lower`.
+    pub fn new(lower: Box<dyn VirtualFs>, upper: Box<dyn VirtualFs>) -> Self {
+        Self {
+            layers: Arc::new(Mutex::new(Layers {
+                lower,
+                upper,
+                hidden: HashSet::new(),
+            })),
+        }
+    }
+}
+
+#[typetag::serde]
+impl VirtualFs for OverlayFs {
+    fn metadata(&self, path: &Path) -> Result<VirtualMetadata, WasiFsError> {
+        self.layers.lock().unwrap().metadata(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<VirtualDirEntry>, WasiFsError> {
+        self.layers.lock().unwrap().read_dir(path)
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<(), WasiFsError> {
+        self.layers.lock().unwrap().create_dir(path)
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<(), WasiFsError> {
+        self.layers.lock().unwrap().remove_dir(path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), WasiFsError> {
+        self.layers.lock().unwrap().rename(from, to)
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), WasiFsError> {
+        self.layers.lock().unwrap().remove_file(path)
+    }
+
+    fn open(
+        &self,
+        path: &Path,
+        options: &VirtualOpenOptions,
+    ) -> Result<Box<dyn WasiFile>, WasiFsError> {
+        let inner = self.layers.lock().unwrap().open(path, options)?;
+        Ok(Box::new(OverlayFile {
+            fs: self.clone(),
+            path: Mutex::new(path.to_path_buf()),
+            inner,
+        }))
+    }
+}
+
+/// A file opened from an [`OverlayFs`].
+///
+/// It forwards everything to the file of the underlying layer, except for
+/// unlinking and renaming which must go through the overlay.
+pub struct OverlayFile {
+    fs: OverlayFs,
+    path: Mutex<PathBuf>,
+    inner: Box<dyn WasiFile>,
+}
+
+impl fmt::Debug for OverlayFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OverlayFile")
+            .field("path", &self.path)
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl Serialize for OverlayFile {
+    fn serialize<S: serde::Serializer>(&self, _serializer: S) -> Result<S::Ok, S::Error> {
+        Err(ser::Error::custom(
+            "files of an `OverlayFs` can not be serialized",
+        ))
+    }
+}
+
+impl<'de> Deserialize<'de> for OverlayFile {
+    fn deserialize<D: serde::Deserializer<'de>>(_deserializer: D) -> Result<Self, D::Error> {
+        Err(de::Error::custom(
+            "files of an `OverlayFs` can not be deserialized",
+        ))
+    }
+}
+
+impl Read for OverlayFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl Write for OverlayFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl Seek for OverlayFile {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+#[typetag::serde]
+impl WasiFile for OverlayFile {
+    fn last_accessed(&self) -> __wasi_timestamp_t {
+        self.inner.last_accessed()
+    }
+
+    fn set_last_accessed(&self, last_accessed: __wasi_timestamp_t) {
+        self.inner.set_last_accessed(last_accessed)
+    }
+
+    fn last_modified(&self) -> __wasi_timestamp_t {
+        self.inner.last_modified()
+    }
+
+    fn set_last_modified(&self, last_modified: __wasi_timestamp_t) {
+        self.inner.set_last_modified(last_modified)
+    }
+
+    fn created_time(&self) -> __wasi_timestamp_t {
+        self.inner.created_time()
+    }
+
+    fn set_created_time(&self, created_time: __wasi_timestamp_t) {
+        self.inner.set_created_time(created_time)
+    }
+
+    fn size(&self) -> u64 {
+        self.inner.size()
+    }
+
+    fn set_len(&mut self, new_size: __wasi_filesize_t) -> Result<(), WasiFsError> {
+        self.inner.set_len(new_size)
+    }
+
+    fn unlink(&mut self) -> Result<(), WasiFsError> {
+        self.fs.remove_file(&self.path.lock().unwrap())
+    }
+
+    fn sync_to_disk(&self) -> Result<(), WasiFsError> {
+        self.inner.sync_to_disk()
+    }
+
+    fn rename_file(&self, new_name: &Path) -> Result<(), WasiFsError> {
+        let mut path = self.path.lock().unwrap();
+        self.fs.rename(&path, new_name)?;
+        *path = new_name.to_path_buf();
+        Ok(())
+    }
+
+    fn bytes_available(&self) -> Result<usize, WasiFsError> {
+        self.inner.bytes_available()
+    }
+
+    fn get_raw_fd(&self) -> Option<i32> {
+        self.inner.get_raw_fd()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::state::MemFs;
+
+    fn names(fs: &dyn VirtualFs, path: &str) -> Vec<String> {
+        fs.read_dir(Path::new(path))
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect()
+    }
+
+    #[test]
+    fn writes_go_to_the_upper_layer() {
+        let lower = MemFs::new();
+        lower.create_dir_all("/app/data").unwrap();
+        lower.write_file("/app/main", b"main").unwrap();
+        lower.write_file("/app/data/db", b"db").unwrap();
+        let upper = MemFs::new();
+        let fs = OverlayFs::new(Box::new(lower.clone()), Box::new(upper.clone()));
+
+        // reading doesn't copy anything
+        let mut contents = Vec::new();
+        fs.open(
+            Path::new("/app/main"),
+            &VirtualOpenOptions {
+                read: true,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .read_to_end(&mut contents)
+        .unwrap();
+        assert_eq!(contents, b"main");
+        assert!(names(&upper, "/").is_empty());
+
+        // writing copies the file to the upper layer
+        let mut file = fs
+            .open(
+                Path::new("/app/data/db"),
+                &VirtualOpenOptions {
+                    append: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        file.write_all(b" updated").unwrap();
+        assert_eq!(upper.read_file("/app/data/db").unwrap(), b"db updated");
+        assert_eq!(lower.read_file("/app/data/db").unwrap(), b"db");
+
+        fs.create_dir(Path::new("/app/logs")).unwrap();
+        assert_eq!(names(&fs, "/app"), vec!["data", "logs", "main"]);
+        assert_eq!(names(&upper, "/app"), vec!["data", "logs"]);
+        assert_eq!(names(&lower, "/app"), vec!["data", "main"]);
+    }
+
+    #[test]
+    fn removals_hide_the_lower_layer() {
+        let lower = MemFs::new();
+        lower.create_dir_all("/app/data").unwrap();
+        lower.write_file("/app/main", b"main").unwrap();
+        lower.write_file("/app/data/db", b"db").unwrap();
+        let upper = MemFs::new();
+        let fs = OverlayFs::new(Box::new(lower.clone()), Box::new(upper.clone()));
+
+        assert_eq!(
+            fs.remove_dir(Path::new("/app/data")),
+            Err(WasiFsError::DirectoryNotEmpty)
+        );
+        fs.remove_file(Path::new("/app/data/db")).unwrap();
+        fs.remove_dir(Path::new("/app/data")).unwrap();
+        assert_eq!(names(&fs, "/app"), vec!["main"]);
+        assert_eq!(
+            fs.metadata(Path::new("/app/data/db")),
+            Err(WasiFsError::EntityNotFound)
+        );
+
+        // a directory created again doesn't show the removed entries
+        fs.create_dir(Path::new("/app/data")).unwrap();
+        assert!(names(&fs, "/app/data").is_empty());
+
+        fs.rename(Path::new("/app/main"), Path::new("/app/data/main"))
+            .unwrap();
+        assert_eq!(names(&fs, "/app"), vec!["data"]);
+        assert_eq!(upper.read_file("/app/data/main").unwrap(), b"main");
+
+        let mut file = fs
+            .open(
+                Path::new("/app/data/main"),
+                &VirtualOpenOptions {
+                    read: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        file.unlink().unwrap();
+        assert!(names(&fs, "/app/data").is_empty());
+
+        // the lower layer was never modified
+        assert_eq!(names(&lower, "/app"), vec!["data", "main"]);
+        assert_eq!(lower.read_file("/app/data/db").unwrap(), b"db");
+    }
+}
@@ -0,0 +1,329 @@
+//! Bounded pipes to stream the stdio of a WASI module while it runs.
+
+use crate::state::{PollEvent, PollEventBuilder, PollEventSet, WasiFile, WasiFsError};
+use serde::{de, ser, Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::{self, Read, Seek, Write};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+#[derive(Debug)]
+struct PipeBuffer {
+    bytes: VecDeque<u8>,
+    capacity: usize,
+    writer_closed: bool,
+    reader_closed: bool,
+}
+
+#[derive(Debug)]
+struct SharedPipe {
+    buffer: Mutex<PipeBuffer>,
+    /// Notified when bytes are written, or when an end is dropped.
+    readable: Condvar,
+    /// Notified when bytes are read, or when an end is dropped.
+    writable: Condvar,
+}
+
+impl PipeBuffer {
+    /// The events of `poll_oneoff` ready for the reader.
+    fn reader_events(&self) -> PollEventSet {
+        let mut events = PollEventBuilder::new();
+        if !self.bytes.is_empty() || self.writer_closed {
+            events = events.add(PollEvent::PollIn);
+        }
+        if self.writer_closed {
+            events = events.add(PollEvent::PollHangUp);
+        }
+        events.build()
+    }
+
+    /// The events of `poll_oneoff` ready for the writer.
+    fn writer_events(&self) -> PollEventSet {
+        let mut events = PollEventBuilder::new();
+        if self.bytes.len() < self.capacity || self.reader_closed {
+            events = events.add(PollEvent::PollOut);
+        }
+        if self.reader_closed {
+            events = events.add(PollEvent::PollHangUp);
+        }
+        events.build()
+    }
+}
+
+impl SharedPipe {
+    fn lock(&self) -> MutexGuard<PipeBuffer> {
+        self.buffer.lock().unwrap()
+    }
+}
+
+/// Create a pipe buffering at most `capacity` bytes.
+///
+/// Writing to a full pipe blocks until the bytes are read, so the host must
+/// read the output of a running module from another thread:
+///
+/// ```
+/// # use wasmer_wasi::{bounded_pipe, WasiState, WasiStateCreationError};
+/// # use std::io::Read;
+/// # fn main() -> Result<(), WasiStateCreationError> {
+/// let (stdout_writer, mut stdout_reader) = bounded_pipe(64 * 1024);
+/// let wasi_env = WasiState::new("program_name")
+///     .stdout(Box::new(stdout_writer))
+///     .finalize()?;
+/// let output = std::thread::spawn(move || {
+///     let mut output = String::new();
+///     // returns once the `WasiEnv` is dropped
+///     stdout_reader.read_to_string(&mut output).unwrap();
+///     output
+/// });
+/// // ... run the module, then drop it:
+/// drop(wasi_env);
+/// assert_eq!(output.join().unwrap(), "");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Both ends are [`WasiFile`]s: the writer can be the `stdout` or `stderr`
+/// of a module, and the reader its `stdin`.
+pub fn bounded_pipe(capacity: usize) -> (PipeWriter, PipeReader) {
+    let shared = Arc::new(SharedPipe {
+        buffer: Mutex::new(PipeBuffer {
+            bytes: VecDeque::new(),
+            capacity: capacity.max(1),
+            writer_closed: false,
+            reader_closed: false,
+        }),
+        readable: Condvar::new(),
+        writable: Condvar::new(),
+    });
+    (
+        PipeWriter {
+            shared: shared.clone(),
+        },
+        PipeReader { shared },
+    )
+}
+
+/// The writing end of a [`bounded_pipe`].
+#[derive(Debug)]
+pub struct PipeWriter {
+    shared: Arc<SharedPipe>,
+}
+
+/// The reading end of a [`bounded_pipe`].
+#[derive(Debug)]
+pub struct PipeReader {
+    shared: Arc<SharedPipe>,
+}
+
+impl PipeReader {
+    /// Read the bytes which are already in the pipe, without blocking.
+    ///
+    /// Returns an error of kind `WouldBlock` if the pipe is empty but the
+    /// writer still exists, and `Ok(0)` once the writer has been dropped and
+    /// everything has been read.
+    pub fn try_read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut buffer = self.shared.lock();
+        if buffer.bytes.is_empty() && !buffer.writer_closed && !buf.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::WouldBlock,
+                "the pipe is empty",
+            ));
+        }
+        Ok(self.drain_into(&mut buffer, buf))
+    }
+
+    fn drain_into(&self, buffer: &mut PipeBuffer, buf: &mut [u8]) -> usize {
+        let amt = buf.len().min(buffer.bytes.len());
+        for (dst, byte) in buf.iter_mut().zip(buffer.bytes.drain(..amt)) {
+            *dst = byte;
+        }
+        if amt > 0 {
+            self.shared.writable.notify_all();
+        }
+        amt
+    }
+}
+
+impl Read for PipeReader {
+    /// Read the bytes of the pipe, blocking until there are some or the
+    /// writer has been dropped.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut buffer = self.shared.lock();
+        while buffer.bytes.is_empty() && !buffer.writer_closed && !buf.is_empty() {
+            buffer = self.shared.readable.wait(buffer).unwrap();
+        }
+        Ok(self.drain_into(&mut buffer, buf))
+    }
+}
+
+impl Write for PipeWriter {
+    /// Write bytes to the pipe, blocking while it's full.
+    ///
+    /// Fails with a `BrokenPipe` error once the reader has been dropped.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut buffer = self.shared.lock();
+        loop {
+            if buffer.reader_closed {
+                return Err(io::Error::new(
+                    io::ErrorKind::BrokenPipe,
+                    "the reader of the pipe has been dropped",
+                ));
+            }
+            if buffer.bytes.len() < buffer.capacity || buf.is_empty() {
+                break;
+            }
+            buffer = self.shared.writable.wait(buffer).unwrap();
+        }
+        let amt = buf.len().min(buffer.capacity - buffer.bytes.len());
+        buffer.bytes.extend(&buf[..amt]);
+        self.shared.readable.notify_all();
+        Ok(amt)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for PipeWriter {
+    fn drop(&mut self) {
+        self.shared.lock().writer_closed = true;
+        self.shared.readable.notify_all();
+    }
+}
+
+impl Drop for PipeReader {
+    fn drop(&mut self) {
+        self.shared.lock().reader_closed = true;
+        self.shared.writable.notify_all();
+    }
+}
+
+impl Read for PipeWriter {
+    fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "can not read from the writing end of a pipe",
+        ))
+    }
+}
+
+impl Write for PipeReader {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "can not write to the reading end of a pipe",
+        ))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+macro_rules! impl_pipe_end {
+    ($end:ident, $name:literal, $events:ident) => {
+        impl Seek for $end {
+            fn seek(&mut self, _pos: io::SeekFrom) -> io::Result<u64> {
+                Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "can not seek in a pipe",
+                ))
+            }
+        }
+
+        impl Serialize for $end {
+            fn serialize<S: serde::Serializer>(&self, _serializer: S) -> Result<S::Ok, S::Error> {
+                Err(ser::Error::custom(concat!(
+                    "a `",
+                    $name,
+                    "` can not be serialized"
+                )))
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $end {
+            fn deserialize<D: serde::Deserializer<'de>>(
+                _deserializer: D,
+            ) -> Result<Self, D::Error> {
+                Err(de::Error::custom(concat!(
+                    "a `",
+                    $name,
+                    "` can not be deserialized"
+                )))
+            }
+        }
+
+        #[typetag::serde]
+        impl WasiFile for $end {
+            fn last_accessed(&self) -> u64 {
+                0
+            }
+            fn last_modified(&self) -> u64 {
+                0
+            }
+            fn created_time(&self) -> u64 {
+                0
+            }
+            fn size(&self) -> u64 {
+                self.shared.lock().bytes.len() as u64
+            }
+            fn set_len(&mut self, _len: u64) -> Result<(), WasiFsError> {
+                Err(WasiFsError::PermissionDenied)
+            }
+            fn unlink(&mut self) -> Result<(), WasiFsError> {
+                Ok(())
+            }
+            fn bytes_available(&self) -> Result<usize, WasiFsError> {
+                Ok(self.shared.lock().bytes.len())
+            }
+            fn poll_ready(&self) -> Result<PollEventSet, WasiFsError> {
+                Ok(self.shared.lock().$events())
+            }
+        }
+    };
+}
+
+impl_pipe_end!(PipeWriter, "PipeWriter", writer_events);
+impl_pipe_end!(PipeReader, "PipeReader", reader_events);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn try_read_does_not_block() {
+        let (mut writer, mut reader) = bounded_pipe(4);
+        let mut buf = [0; 8];
+        assert_eq!(
+            reader.try_read(&mut buf).unwrap_err().kind(),
+            io::ErrorKind::WouldBlock
+        );
+
+        assert_eq!(writer.write(b"hello").unwrap(), 4);
+        assert_eq!(reader.try_read(&mut buf).unwrap(), 4);
+        assert_eq!(&buf[..4], b"hell");
+
+        drop(writer);
+        assert_eq!(reader.try_read(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn writes_block_until_read() {
+        let (mut writer, mut reader) = bounded_pipe(2);
+        let handle = std::thread::spawn(move || writer.write_all(b"streamed"));
+        let mut output = String::new();
+        reader.read_to_string(&mut output).unwrap();
+        handle.join().unwrap().unwrap();
+        assert_eq!(output, "streamed");
+    }
+
+    #[test]
+    fn writes_fail_without_reader() {
+        let (mut writer, reader) = bounded_pipe(2);
+        drop(reader);
+        assert_eq!(
+            writer.write(b"lost").unwrap_err().kind(),
+            io::ErrorKind::BrokenPipe
+        );
+    }
+}
@@ -0,0 +1,76 @@
+//! The random sources read by the `random_get` syscall.
+
+use crate::syscalls::types::*;
+use std::fmt;
+
+/// A source of random bytes for a WASI module.
+///
+/// [`HostRandom`] is used by default; [`SeededRandom`] makes the bytes
+/// reproducible from one execution to the next.
+pub trait WasiRandom: fmt::Debug + Send + 'static {
+    /// Fill `buf` with random bytes.
+    fn fill(&mut self, buf: &mut [u8]) -> Result<(), __wasi_errno_t>;
+}
+
+/// The [`WasiRandom`] reading the random number generator of the host.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HostRandom;
+
+impl WasiRandom for HostRandom {
+    fn fill(&mut self, buf: &mut [u8]) -> Result<(), __wasi_errno_t> {
+        getrandom::getrandom(buf).map_err(|_| __WASI_EIO)
+    }
+}
+
+/// A [`WasiRandom`] generating the same bytes for the same seed, on every
+/// platform.
+///
+/// The bytes come from a SplitMix64 generator: they are fine for simulations
+/// and fuzzing, but must not be used for cryptography.
+#[derive(Debug, Clone)]
+pub struct SeededRandom {
+    state: u64,
+}
+
+impl SeededRandom {
+    /// Create a generator from `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+impl WasiRandom for SeededRandom {
+    fn fill(&mut self, buf: &mut [u8]) -> Result<(), __wasi_errno_t> {
+        for chunk in buf.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn seeded_random_is_reproducible() {
+        let mut first = [0; 20];
+        let mut second = [0; 20];
+        SeededRandom::new(42).fill(&mut first).unwrap();
+        SeededRandom::new(42).fill(&mut second).unwrap();
+        assert_eq!(first, second);
+
+        let mut other = [0; 20];
+        SeededRandom::new(43).fill(&mut other).unwrap();
+        assert_ne!(first, other);
+    }
+}
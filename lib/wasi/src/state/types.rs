@@ -10,7 +10,7 @@ use std::{
     fs,
     io::{self, Read, Seek, Write},
     path::PathBuf,
-    time::SystemTime,
+    time::{Duration, SystemTime},
 };
 use thiserror::Error;
 use tracing::debug;
@@ -69,6 +69,9 @@ pub enum WasiFsError {
     /// The requested file or directory could not be found
     #[error("entity not found")]
     EntityNotFound,
+    /// The directory could not be removed because it still has entries
+    #[error("directory not empty")]
+    DirectoryNotEmpty,
     /// The requested device couldn't be accessed
     #[error("can't access device")]
     NoDevice,
@@ -111,6 +114,7 @@ impl WasiFsError {
             __WASI_ENOTCONN => WasiFsError::NotConnected,
             __WASI_ENODEV => WasiFsError::NoDevice,
             __WASI_ENOENT => WasiFsError::EntityNotFound,
+            __WASI_ENOTEMPTY => WasiFsError::DirectoryNotEmpty,
             __WASI_EPERM => WasiFsError::PermissionDenied,
             __WASI_ETIMEDOUT => WasiFsError::TimedOut,
             __WASI_EPROTO => WasiFsError::UnexpectedEof,
@@ -139,6 +143,7 @@ impl WasiFsError {
             WasiFsError::NotAFile => __WASI_EINVAL,
             WasiFsError::NotConnected => __WASI_ENOTCONN,
             WasiFsError::EntityNotFound => __WASI_ENOENT,
+            WasiFsError::DirectoryNotEmpty => __WASI_ENOTEMPTY,
             WasiFsError::PermissionDenied => __WASI_EPERM,
             WasiFsError::TimedOut => __WASI_ETIMEDOUT,
             WasiFsError::UnexpectedEof => __WASI_EPROTO,
@@ -211,6 +216,17 @@ pub trait WasiFile: fmt::Debug + Send + Write + Read + Seek + 'static + Upcastab
     fn get_raw_fd(&self) -> Option<i32> {
         None
     }
+
+    /// The events of `poll_oneoff` that are ready, for files without a host
+    /// fd: `PollIn` if reading wouldn't block, `PollOut` if writing wouldn't
+    /// block, and `PollHangUp` if the other end is closed.
+    /// Default returns `PollIn` and `PollOut`, like a regular file.
+    fn poll_ready(&self) -> Result<PollEventSet, WasiFsError> {
+        Ok(PollEventBuilder::new()
+            .add(PollEvent::PollIn)
+            .add(PollEvent::PollOut)
+            .build())
+    }
 }
 
 // Implementation of `Upcastable` taken from https://users.rust-lang.org/t/why-does-downcasting-not-work-for-subtraits/33286/7 .
@@ -361,46 +377,56 @@ impl PollEventBuilder {
     }
 }
 
+/// Wait for the events of host fds, at most `timeout` (forever if `None`),
+/// returning the events seen for each fd.
+///
+/// An interrupted wait returns no events, so that the caller can check its
+/// deadlines again.
 #[cfg(unix)]
 pub(crate) fn poll(
-    selfs: &[&dyn WasiFile],
-    events: &[PollEventSet],
-    seen_events: &mut [PollEventSet],
-) -> Result<u32, WasiFsError> {
-    if !(selfs.len() == events.len() && events.len() == seen_events.len()) {
-        return Err(WasiFsError::InvalidInput);
-    }
-    let mut fds = selfs
+    fds: &[(i32, PollEventSet)],
+    timeout: Option<Duration>,
+) -> Result<Vec<PollEventSet>, WasiFsError> {
+    let mut pollfds = fds
         .iter()
-        .enumerate()
-        .filter_map(|(i, s)| s.get_raw_fd().map(|rfd| (i, rfd)))
-        .map(|(i, host_fd)| libc::pollfd {
-            fd: host_fd,
-            events: poll_event_set_to_platform_poll_events(events[i]),
+        .map(|(fd, events)| libc::pollfd {
+            fd: *fd,
+            events: poll_event_set_to_platform_poll_events(*events),
             revents: 0,
         })
         .collect::<Vec<_>>();
-    let result = unsafe { libc::poll(fds.as_mut_ptr(), selfs.len() as _, 1) };
-
+    let timeout = match timeout {
+        // round up, so that the deadline has passed when `poll` returns
+        Some(timeout) => {
+            let millis = (timeout.as_nanos() + 999_999) / 1_000_000;
+            millis.min(i32::MAX as u128) as i32
+        }
+        None => -1,
+    };
+    let result = unsafe { libc::poll(pollfds.as_mut_ptr(), pollfds.len() as _, timeout) };
     if result < 0 {
-        // TODO: check errno and return value
-        return Err(WasiFsError::IOError);
-    }
-    // convert result and write back values
-    for (i, fd) in fds.into_iter().enumerate() {
-        seen_events[i] = platform_poll_events_to_pollevent_set(fd.revents);
+        let err = io::Error::last_os_error();
+        if err.kind() == io::ErrorKind::Interrupted {
+            return Ok(vec![0; fds.len()]);
+        }
+        return Err(WasiFsError::from(err));
     }
-    // unwrap is safe because we check for negative values above
-    Ok(result.try_into().unwrap())
+    Ok(pollfds
+        .into_iter()
+        .map(|fd| platform_poll_events_to_pollevent_set(fd.revents))
+        .collect())
 }
 
+/// Files have no host fd to poll on these targets, so this only waits.
 #[cfg(not(unix))]
 pub(crate) fn poll(
-    _selfs: &[&dyn WasiFile],
-    _events: &[PollEventSet],
-    _seen_events: &mut [PollEventSet],
-) -> Result<(), WasiFsError> {
-    unimplemented!("HostFile::poll in WasiFile is not implemented for non-Unix-like targets yet");
+    fds: &[(i32, PollEventSet)],
+    timeout: Option<Duration>,
+) -> Result<Vec<PollEventSet>, WasiFsError> {
+    if let Some(timeout) = timeout {
+        std::thread::sleep(timeout);
+    }
+    Ok(vec![0; fds.len()])
 }
 
 pub trait WasiPath {}
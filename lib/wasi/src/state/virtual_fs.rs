@@ -0,0 +1,226 @@
+//! The filesystem backing the preopened directories of a [`WasiFs`].
+//!
+//! [`WasiFs`]: crate::WasiFs
+
+use crate::state::{HostFile, WasiFile, WasiFsError};
+use crate::syscalls::types::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Metadata about an entry of a [`VirtualFs`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VirtualMetadata {
+    /// The WASI type of the entry
+    pub filetype: __wasi_filetype_t,
+    /// The size of the entry in bytes
+    pub len: u64,
+    /// Last access time, in nanoseconds since the Unix epoch
+    pub accessed: __wasi_timestamp_t,
+    /// Last modification time, in nanoseconds since the Unix epoch
+    pub modified: __wasi_timestamp_t,
+    /// Creation time, in nanoseconds since the Unix epoch
+    pub created: __wasi_timestamp_t,
+}
+
+impl VirtualMetadata {
+    /// Whether the entry is a directory
+    pub fn is_dir(&self) -> bool {
+        self.filetype == __WASI_FILETYPE_DIRECTORY
+    }
+
+    /// Whether the entry is a regular file
+    pub fn is_file(&self) -> bool {
+        self.filetype == __WASI_FILETYPE_REGULAR_FILE
+    }
+
+    /// Whether the entry is a symbolic link
+    pub fn is_symlink(&self) -> bool {
+        self.filetype == __WASI_FILETYPE_SYMBOLIC_LINK
+    }
+}
+
+/// An entry of a directory of a [`VirtualFs`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VirtualDirEntry {
+    /// The name of the entry inside of its directory
+    pub name: String,
+    /// The WASI type of the entry
+    pub filetype: __wasi_filetype_t,
+}
+
+/// Options for [`VirtualFs::open`], mirroring `std::fs::OpenOptions`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VirtualOpenOptions {
+    /// Open the file for reading
+    pub read: bool,
+    /// Open the file for writing
+    pub write: bool,
+    /// Write at the end of the file, whatever the current position
+    pub append: bool,
+    /// Truncate the file to a length of 0 when it's opened
+    pub truncate: bool,
+    /// Create the file if it doesn't exist yet
+    pub create: bool,
+    /// Create the file, failing if it already exists
+    pub create_new: bool,
+}
+
+/// A filesystem that WASI can expose to a module.
+///
+/// All the paths given to a `VirtualFs` are the paths of the preopened
+/// directories joined with the paths the module asked for.
+///
+/// [`HostFs`] is used by default; [`MemFs`] keeps everything in memory so that
+/// the module doesn't get any access to the host disk.
+///
+/// [`MemFs`]: crate::MemFs
+#[typetag::serde(tag = "type")]
+pub trait VirtualFs: fmt::Debug + Send + 'static {
+    /// Get the metadata of the entry at `path`, following symlinks.
+    fn metadata(&self, path: &Path) -> Result<VirtualMetadata, WasiFsError>;
+
+    /// Get the metadata of the entry at `path`, without following symlinks.
+    fn symlink_metadata(&self, path: &Path) -> Result<VirtualMetadata, WasiFsError> {
+        self.metadata(path)
+    }
+
+    /// Read the value of the symlink at `path`.
+    fn read_link(&self, _path: &Path) -> Result<PathBuf, WasiFsError> {
+        Err(WasiFsError::InvalidInput)
+    }
+
+    /// List the entries of the directory at `path`.
+    fn read_dir(&self, path: &Path) -> Result<Vec<VirtualDirEntry>, WasiFsError>;
+
+    /// Create an empty directory at `path`.
+    fn create_dir(&self, path: &Path) -> Result<(), WasiFsError>;
+
+    /// Remove the empty directory at `path`.
+    fn remove_dir(&self, path: &Path) -> Result<(), WasiFsError>;
+
+    /// Move the entry at `from` to `to`, replacing `to` if it's a file.
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), WasiFsError>;
+
+    /// Remove the file at `path`.
+    fn remove_file(&self, path: &Path) -> Result<(), WasiFsError>;
+
+    /// Open the file at `path`.
+    fn open(
+        &self,
+        path: &Path,
+        options: &VirtualOpenOptions,
+    ) -> Result<Box<dyn WasiFile>, WasiFsError>;
+}
+
+/// The [`VirtualFs`] giving access to the filesystem of the host.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct HostFs;
+
+#[typetag::serde]
+impl VirtualFs for HostFs {
+    fn metadata(&self, path: &Path) -> Result<VirtualMetadata, WasiFsError> {
+        Ok(host_metadata(&path.metadata()?))
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> Result<VirtualMetadata, WasiFsError> {
+        Ok(host_metadata(&path.symlink_metadata()?))
+    }
+
+    fn read_link(&self, path: &Path) -> Result<PathBuf, WasiFsError> {
+        path.read_link().map_err(Into::into)
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<VirtualDirEntry>, WasiFsError> {
+        fs::read_dir(path)?
+            .map(|entry| {
+                let entry = entry?;
+                Ok(VirtualDirEntry {
+                    name: entry.file_name().to_string_lossy().to_string(),
+                    filetype: host_file_type_to_wasi_file_type(entry.file_type()?),
+                })
+            })
+            .collect()
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<(), WasiFsError> {
+        fs::create_dir(path).map_err(Into::into)
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<(), WasiFsError> {
+        fs::remove_dir(path).map_err(Into::into)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), WasiFsError> {
+        fs::rename(from, to).map_err(Into::into)
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), WasiFsError> {
+        fs::remove_file(path).map_err(Into::into)
+    }
+
+    fn open(
+        &self,
+        path: &Path,
+        options: &VirtualOpenOptions,
+    ) -> Result<Box<dyn WasiFile>, WasiFsError> {
+        let file = fs::OpenOptions::new()
+            .read(options.read)
+            .write(options.write)
+            .append(options.append)
+            .truncate(options.truncate)
+            .create(options.create)
+            .create_new(options.create_new)
+            .open(path)?;
+        Ok(Box::new(HostFile::new(
+            file,
+            path.to_path_buf(),
+            options.read,
+            options.write,
+            options.append,
+        )))
+    }
+}
+
+fn host_metadata(md: &fs::Metadata) -> VirtualMetadata {
+    let nanos = |time: std::io::Result<SystemTime>| {
+        time.ok()
+            .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map(|time| time.as_nanos() as u64)
+            .unwrap_or(0)
+    };
+    VirtualMetadata {
+        filetype: host_file_type_to_wasi_file_type(md.file_type()),
+        len: md.len(),
+        accessed: nanos(md.accessed()),
+        modified: nanos(md.modified()),
+        created: nanos(md.created()),
+    }
+}
+
+pub fn host_file_type_to_wasi_file_type(file_type: fs::FileType) -> __wasi_filetype_t {
+    if file_type.is_dir() {
+        return __WASI_FILETYPE_DIRECTORY;
+    } else if file_type.is_file() {
+        return __WASI_FILETYPE_REGULAR_FILE;
+    } else if file_type.is_symlink() {
+        return __WASI_FILETYPE_SYMBOLIC_LINK;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        if file_type.is_char_device() {
+            return __WASI_FILETYPE_CHARACTER_DEVICE;
+        } else if file_type.is_block_device() {
+            return __WASI_FILETYPE_BLOCK_DEVICE;
+        } else if file_type.is_socket() {
+            // TODO: how do we know if it's a `__WASI_FILETYPE_SOCKET_STREAM` or
+            // a `__WASI_FILETYPE_SOCKET_DGRAM`?
+            return __WASI_FILETYPE_SOCKET_STREAM;
+        }
+    }
+    // FIFO doesn't seem to fit any other type, so unknown
+    __WASI_FILETYPE_UNKNOWN
+}
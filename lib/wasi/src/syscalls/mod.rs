@@ -17,9 +17,9 @@ use self::types::*;
 use crate::{
     ptr::{Array, WasmPtr},
     state::{
-        self, host_file_type_to_wasi_file_type, iterate_poll_events, poll, Fd, HostFile, Inode,
-        InodeVal, Kind, PollEvent, PollEventBuilder, WasiFile, WasiFsError, WasiState,
-        MAX_SYMLINKS,
+        self, iterate_poll_events, poll, ExitHook, Fd, HostFile, Inode, InodeVal, Kind, PollEvent,
+        PollEventBuilder, PollEventSet, VirtualOpenOptions, WasiFile, WasiFsError, WasiState,
+        WasiTcpListener, WasiTcpStream, MAX_SYMLINKS,
     },
     WasiEnv, WasiError,
 };
@@ -27,6 +27,8 @@ use std::borrow::Borrow;
 use std::cell::Cell;
 use std::convert::{Infallible, TryInto};
 use std::io::{self, Read, Seek, Write};
+use std::net::{IpAddr, Shutdown, TcpListener, TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
 use tracing::{debug, trace};
 use wasmer::{Memory, RuntimeError, Value};
 
@@ -70,6 +72,14 @@ fn write_bytes<T: Write>(
     result
 }
 
+/// The total number of bytes in `iovs_arr_cell`.
+fn total_iovs_len(iovs_arr_cell: &[Cell<__wasi_ciovec_t>]) -> u64 {
+    iovs_arr_cell
+        .iter()
+        .map(|iov| iov.get().buf_len as u64)
+        .sum()
+}
+
 fn read_bytes<T: Read>(
     mut reader: T,
     memory: &Memory,
@@ -79,10 +89,13 @@ fn read_bytes<T: Read>(
 
     for iov in iovs_arr_cell {
         let iov_inner = iov.get();
-        let bytes = iov_inner.buf.deref(memory, 0, iov_inner.buf_len)?;
-        let mut raw_bytes: &mut [u8] =
-            unsafe { &mut *(bytes as *const [_] as *mut [_] as *mut [u8]) };
-        bytes_read += reader.read(raw_bytes).map_err(|_| __WASI_EIO)? as u32;
+        let cells = iov_inner.buf.deref(memory, 0, iov_inner.buf_len)?;
+        let mut buf = vec![0; cells.len()];
+        let amt = reader.read(&mut buf).map_err(|_| __WASI_EIO)?;
+        for (cell, byte) in cells.iter().zip(&buf[..amt]) {
+            cell.set(*byte);
+        }
+        bytes_read += amt as u32;
     }
     Ok(bytes_read)
 }
@@ -204,10 +217,11 @@ pub fn clock_res_get(
     resolution: WasmPtr<__wasi_timestamp_t>,
 ) -> __wasi_errno_t {
     debug!("wasi::clock_res_get");
-    let memory = env.memory();
+    let (memory, state) = env.get_memory_and_wasi_state(0);
 
     let out_addr = wasi_try!(resolution.deref(memory));
-    platform_clock_res_get(clock_id, out_addr)
+    out_addr.set(wasi_try!(state.clock.resolution(clock_id)));
+    __WASI_ESUCCESS
 }
 
 /// ### `clock_time_get()`
@@ -230,16 +244,12 @@ pub fn clock_time_get(
         "wasi::clock_time_get clock_id: {}, precision: {}",
         clock_id, precision
     );
-    let memory = env.memory();
+    let (memory, state) = env.get_memory_and_wasi_state(0);
 
     let out_addr = wasi_try!(time.deref(memory));
-    let result = platform_clock_time_get(clock_id, precision, out_addr);
-    debug!(
-        "time: {} => {}",
-        wasi_try!(time.deref(memory)).get(),
-        result
-    );
-    result
+    out_addr.set(wasi_try!(state.clock.time(clock_id, precision)));
+    debug!("time: {}", out_addr.get());
+    __WASI_ESUCCESS
 }
 
 /// ### `environ_get()`
@@ -342,15 +352,20 @@ pub fn fd_allocate(
     }
     let new_size = wasi_try!(offset.checked_add(len), __WASI_EINVAL);
 
-    match &mut state.fs.inodes[inode].kind {
+    let fs = &mut state.fs;
+    match &mut fs.inodes[inode].kind {
         Kind::File { handle, .. } => {
             if let Some(handle) = handle {
+                wasi_try!(fs.quota.write_bytes(new_size.saturating_sub(handle.size())));
                 wasi_try!(handle.set_len(new_size).map_err(WasiFsError::into_wasi_err));
             } else {
                 return __WASI_EBADF;
             }
         }
         Kind::Buffer { buffer } => {
+            wasi_try!(fs
+                .quota
+                .write_bytes(new_size.saturating_sub(buffer.len() as u64)));
             buffer.resize(new_size as usize, 0);
         }
         Kind::Symlink { .. } => return __WASI_EBADF,
@@ -537,15 +552,20 @@ pub fn fd_filestat_set_size(
         return __WASI_EACCES;
     }
 
-    match &mut state.fs.inodes[inode].kind {
+    let fs = &mut state.fs;
+    match &mut fs.inodes[inode].kind {
         Kind::File { handle, .. } => {
             if let Some(handle) = handle {
+                wasi_try!(fs.quota.write_bytes(st_size.saturating_sub(handle.size())));
                 wasi_try!(handle.set_len(st_size).map_err(WasiFsError::into_wasi_err));
             } else {
                 return __WASI_EBADF;
             }
         }
         Kind::Buffer { buffer } => {
+            wasi_try!(fs
+                .quota
+                .write_bytes(st_size.saturating_sub(buffer.len() as u64)));
             buffer.resize(st_size as usize, 0);
         }
         Kind::Symlink { .. } => return __WASI_EBADF,
@@ -830,6 +850,9 @@ pub fn fd_pwrite(
             }
 
             let inode_idx = fd_entry.inode;
+            if let Kind::File { .. } = state.fs.inodes[inode_idx].kind {
+                wasi_try!(state.fs.quota.write_bytes(total_iovs_len(iovs_arr_cell)));
+            }
             let inode = &mut state.fs.inodes[inode_idx];
 
             match &mut inode.kind {
@@ -980,17 +1003,17 @@ pub fn fd_readdir(
             // we need to support multiple calls,
             // simple and obviously correct implementation for now:
             // maintain consistent order via lexacographic sorting
-            let fs_info = wasi_try!(wasi_try!(std::fs::read_dir(path).map_err(|_| __WASI_EIO))
-                .collect::<Result<Vec<_>, _>>()
-                .map_err(|_| __WASI_EIO));
-            let mut entry_vec = wasi_try!(fs_info
+            let fs_info = wasi_try!(state.fs.fs_backend.read_dir(path).map_err(|_| __WASI_EIO));
+            let mut entry_vec = fs_info
                 .into_iter()
-                .map(|entry| Ok((
-                    entry.file_name().to_string_lossy().to_string(),
-                    host_file_type_to_wasi_file_type(entry.file_type().map_err(|_| __WASI_EIO)?),
-                    0, // TODO: inode
-                )))
-                .collect::<Result<Vec<(String, u8, u64)>, _>>());
+                .map(|entry| {
+                    (
+                        entry.name,
+                        entry.filetype,
+                        0, // TODO: inode
+                    )
+                })
+                .collect::<Vec<(String, u8, u64)>>();
             entry_vec.extend(
                 entries
                     .iter()
@@ -1084,6 +1107,12 @@ pub fn fd_renumber(env: &WasiEnv, from: __wasi_fd_t, to: __wasi_fd_t) -> __wasi_
 
     state.fs.fd_map.insert(to, new_fd_entry);
     state.fs.fd_map.remove(&from);
+    // the rights of a preopened directory are looked up through its fd
+    for po_fd in state.fs.preopen_fds.iter_mut() {
+        if *po_fd == from {
+            *po_fd = to;
+        }
+    }
     __WASI_ESUCCESS
 }
 
@@ -1284,6 +1313,13 @@ pub fn fd_write(
 
             let offset = fd_entry.offset as usize;
             let inode_idx = fd_entry.inode;
+            let inode = &state.fs.inodes[inode_idx];
+            // sockets are stored as files, but don't use the disk
+            if let Kind::File { .. } = inode.kind {
+                if inode.stat.st_filetype != __WASI_FILETYPE_SOCKET_STREAM {
+                    wasi_try!(state.fs.quota.write_bytes(total_iovs_len(iovs_arr_cell)));
+                }
+            }
             let inode = &mut state.fs.inodes[inode_idx];
 
             let bytes_written = match &mut inode.kind {
@@ -1392,10 +1428,17 @@ pub fn path_create_directory(
                     let mut adjusted_path = path.clone();
                     // TODO: double check this doesn't risk breaking the sandbox
                     adjusted_path.push(comp);
-                    if adjusted_path.exists() && !adjusted_path.is_dir() {
-                        return __WASI_ENOTDIR;
-                    } else if !adjusted_path.exists() {
-                        wasi_try!(std::fs::create_dir(&adjusted_path).ok(), __WASI_EIO);
+                    match state.fs.fs_backend.metadata(&adjusted_path) {
+                        Ok(metadata) if !metadata.is_dir() => return __WASI_ENOTDIR,
+                        Ok(_) => (),
+                        Err(_) => {
+                            wasi_try!(state.fs.quota.create_file());
+                            wasi_try!(state
+                                .fs
+                                .fs_backend
+                                .create_dir(&adjusted_path)
+                                .map_err(|_| __WASI_EIO))
+                        }
                     }
                     let kind = Kind::Dir {
                         parent: Some(cur_dir_inode),
@@ -1620,20 +1663,29 @@ pub fn path_link(
         wasi_try!(state
             .fs
             .get_parent_inode_at_path(new_fd, &target_path_arg, false));
+    if !has_rights(
+        wasi_try!(state.fs.parent_preopen_rights(new_fd, &target_path_arg)),
+        __WASI_RIGHT_PATH_LINK_TARGET,
+    ) {
+        return __WASI_EACCES;
+    }
 
     if state.fs.inodes[source_inode].stat.st_nlink == __wasi_linkcount_t::max_value() {
         return __WASI_EMLINK;
     }
-    match &mut state.fs.inodes[target_parent_inode].kind {
+    match &state.fs.inodes[target_parent_inode].kind {
         Kind::Dir { entries, .. } => {
             if entries.contains_key(&new_entry_name) {
                 return __WASI_EEXIST;
             }
-            entries.insert(new_entry_name, source_inode);
         }
         Kind::Root { .. } => return __WASI_EINVAL,
         Kind::File { .. } | Kind::Symlink { .. } | Kind::Buffer { .. } => return __WASI_ENOTDIR,
     }
+    wasi_try!(state.fs.quota.create_file());
+    if let Kind::Dir { entries, .. } = &mut state.fs.inodes[target_parent_inode].kind {
+        entries.insert(new_entry_name, source_inode);
+    }
     state.fs.inodes[source_inode].stat.st_nlink += 1;
 
     __WASI_ESUCCESS
@@ -1680,6 +1732,8 @@ pub fn path_open(
         debug!("  - will follow symlinks when opening path");
     }
     let (memory, mut state) = env.get_memory_and_wasi_state(0);
+    // borrow through the guard once so that the inodes and the backend can be borrowed separately
+    let state = &mut *state;
     /* TODO: find actual upper bound on name size (also this is a path, not a name :think-fish:) */
     if path_len > 1024 * 1024 {
         return __WASI_ENAMETOOLONG;
@@ -1694,6 +1748,7 @@ pub fn path_open(
     // - __WASI_O_TRUNC (truncate size to 0)
 
     let working_dir = wasi_try!(state.fs.get_fd(dirfd));
+    let working_dir_rights = working_dir.rights;
     let working_dir_rights_inheriting = working_dir.rights_inheriting;
 
     // ASSUMPTION: open rights apply recursively
@@ -1711,11 +1766,27 @@ pub fn path_open(
         dirflags & __WASI_LOOKUP_SYMLINK_FOLLOW != 0,
     );
 
+    // the path may lead to another preopened directory through the virtual root
+    let preopen_rights = match maybe_inode {
+        Ok(inode)
+            if matches!(
+                state.fs.inodes[inode].kind,
+                Kind::Dir { .. } | Kind::Root { .. }
+            ) =>
+        {
+            state.fs.preopen_rights(inode)
+        }
+        _ => state
+            .fs
+            .parent_preopen_rights(dirfd, &path_arg)
+            .unwrap_or(0),
+    };
+
     let mut open_flags = 0;
     // TODO: traverse rights of dirs properly
     // COMMENTED OUT: WASI isn't giving appropriate rights here when opening
     //              TODO: look into this; file a bug report if this is a bug
-    let adjusted_rights = /*fs_rights_base &*/ working_dir_rights_inheriting;
+    let adjusted_rights = /*fs_rights_base &*/ working_dir_rights_inheriting & preopen_rights;
     let inode = if let Ok(inode) = maybe_inode {
         // Happy path, we found the file we're trying to open
         match &mut state.fs.inodes[inode].kind {
@@ -1733,11 +1804,13 @@ pub fn path_open(
                 if o_flags & __WASI_O_DIRECTORY != 0 {
                     return __WASI_ENOTDIR;
                 }
-                if o_flags & __WASI_O_EXCL != 0 && path.exists() {
+                if o_flags & __WASI_O_EXCL != 0 && state.fs.fs_backend.metadata(path).is_ok() {
                     return __WASI_EEXIST;
                 }
-                let mut open_options = std::fs::OpenOptions::new();
                 let write_permission = adjusted_rights & __WASI_RIGHT_FD_WRITE != 0;
+                if o_flags & __WASI_O_TRUNC != 0 && !write_permission {
+                    return __WASI_EACCES;
+                }
                 // append, truncate, and create all require the permission to write
                 let (append_permission, truncate_permission, create_permission) =
                     if write_permission {
@@ -1749,13 +1822,15 @@ pub fn path_open(
                     } else {
                         (false, false, false)
                     };
-                let open_options = open_options
-                    .read(true)
+                let open_options = VirtualOpenOptions {
+                    read: true,
                     // TODO: ensure these rights are actually valid given parent, etc.
-                    .write(write_permission)
-                    .create(create_permission)
-                    .append(append_permission)
-                    .truncate(truncate_permission);
+                    write: write_permission,
+                    create: create_permission,
+                    append: append_permission,
+                    truncate: truncate_permission,
+                    ..VirtualOpenOptions::default()
+                };
                 open_flags |= Fd::READ;
                 if adjusted_rights & __WASI_RIGHT_FD_WRITE != 0 {
                     open_flags |= Fd::WRITE;
@@ -1766,18 +1841,16 @@ pub fn path_open(
                 if o_flags & __WASI_O_TRUNC != 0 {
                     open_flags |= Fd::TRUNCATE;
                 }
-                *handle = Some(Box::new(HostFile::new(
-                    wasi_try!(open_options.open(&path).map_err(|_| __WASI_EIO)),
-                    path.to_path_buf(),
-                    true,
-                    adjusted_rights & __WASI_RIGHT_FD_WRITE != 0,
-                    false,
-                )));
+                *handle = Some(wasi_try!(state
+                    .fs
+                    .fs_backend
+                    .open(path, &open_options)
+                    .map_err(|_| __WASI_EIO)));
             }
             Kind::Buffer { .. } => unimplemented!("wasi::path_open for Buffer type files"),
             Kind::Dir { .. } | Kind::Root { .. } => {
                 // TODO: adjust these to be correct
-                if o_flags & __WASI_O_EXCL != 0 && path_arg.exists() {
+                if o_flags & __WASI_O_EXCL != 0 && state.fs.fs_backend.metadata(&path_arg).is_ok() {
                     return __WASI_EEXIST;
                 }
             }
@@ -1799,6 +1872,12 @@ pub fn path_open(
             if o_flags & __WASI_O_DIRECTORY != 0 {
                 return __WASI_ENOTDIR;
             }
+            if !has_rights(
+                working_dir_rights & preopen_rights,
+                __WASI_RIGHT_PATH_CREATE_FILE,
+            ) {
+                return __WASI_EACCES;
+            }
             debug!("Creating file");
             // strip end file name
 
@@ -1816,29 +1895,29 @@ pub fn path_open(
                 Kind::Root { .. } => return __WASI_EACCES,
                 _ => return __WASI_EINVAL,
             };
+            wasi_try!(state.fs.quota.create_file());
             // once we got the data we need from the parent, we lookup the host file
             // todo: extra check that opening with write access is okay
             let handle = {
-                let mut open_options = std::fs::OpenOptions::new();
-                let open_options = open_options
-                    .read(true)
-                    .append(fs_flags & __WASI_FDFLAG_APPEND != 0)
+                let open_options = VirtualOpenOptions {
+                    read: true,
+                    append: fs_flags & __WASI_FDFLAG_APPEND != 0,
                     // TODO: ensure these rights are actually valid given parent, etc.
                     // write access is required for creating a file
-                    .write(true)
-                    .create_new(true);
+                    write: true,
+                    create_new: true,
+                    ..VirtualOpenOptions::default()
+                };
                 open_flags |= Fd::READ | Fd::WRITE | Fd::CREATE | Fd::TRUNCATE;
 
-                Some(Box::new(HostFile::new(
-                    wasi_try!(open_options.open(&new_file_host_path).map_err(|e| {
+                Some(wasi_try!(state
+                    .fs
+                    .fs_backend
+                    .open(&new_file_host_path, &open_options)
+                    .map_err(|e| {
                         debug!("Error opening file {}", e);
                         __WASI_EIO
-                    })),
-                    new_file_host_path.clone(),
-                    true,
-                    true,
-                    true,
-                )) as Box<dyn WasiFile>)
+                    })))
             };
 
             let new_inode = {
@@ -1870,9 +1949,10 @@ pub fn path_open(
 
     // TODO: check and reduce these
     // TODO: ensure a mutable fd to root can never be opened
+    // the opened fd can't pass on more rights than the directory it was opened from
     let out_fd = wasi_try!(state.fs.create_fd(
         adjusted_rights,
-        fs_rights_inheriting,
+        fs_rights_inheriting & working_dir_rights_inheriting & preopen_rights,
         fs_flags,
         open_flags,
         inode
@@ -1983,6 +2063,9 @@ pub fn path_remove_directory(
     let (memory, mut state) = env.get_memory_and_wasi_state(0);
 
     let base_dir = wasi_try!(state.fs.fd_map.get(&fd), __WASI_EBADF);
+    if !has_rights(base_dir.rights, __WASI_RIGHT_PATH_REMOVE_DIRECTORY) {
+        return __WASI_EACCES;
+    }
     let path_str = unsafe { get_input_str!(memory, path, path_len) };
 
     let inode = wasi_try!(state.fs.get_inode_at_path(fd, path_str, false));
@@ -1990,11 +2073,19 @@ pub fn path_remove_directory(
         wasi_try!(state
             .fs
             .get_parent_inode_at_path(fd, std::path::Path::new(path_str), false));
+    if !has_rights(
+        wasi_try!(state
+            .fs
+            .parent_preopen_rights(fd, std::path::Path::new(path_str))),
+        __WASI_RIGHT_PATH_REMOVE_DIRECTORY,
+    ) {
+        return __WASI_EACCES;
+    }
 
     let host_path_to_remove = match &state.fs.inodes[inode].kind {
         Kind::Dir { entries, path, .. } => {
             if !entries.is_empty()
-                || wasi_try!(std::fs::read_dir(path).ok(), __WASI_EIO).count() != 0
+                || !wasi_try!(state.fs.fs_backend.read_dir(path).ok(), __WASI_EIO).is_empty()
             {
                 return __WASI_ENOTEMPTY;
             }
@@ -2018,7 +2109,12 @@ pub fn path_remove_directory(
         ),
     }
 
-    if std::fs::remove_dir(path_str).is_err() {
+    if state
+        .fs
+        .fs_backend
+        .remove_dir(&host_path_to_remove)
+        .is_err()
+    {
         // reinsert to prevent FS from being in bad state
         if let Kind::Dir {
             ref mut entries, ..
@@ -2062,6 +2158,8 @@ pub fn path_rename(
         old_fd, new_fd
     );
     let (memory, mut state) = env.get_memory_and_wasi_state(0);
+    // borrow through the guard once so that the inodes and the backend can be borrowed separately
+    let state = &mut *state;
     let source_str = unsafe { get_input_str!(memory, old_path, old_path_len) };
     let source_path = std::path::Path::new(source_str);
     let target_str = unsafe { get_input_str!(memory, new_path, new_path_len) };
@@ -2083,6 +2181,15 @@ pub fn path_rename(
         wasi_try!(state.fs.get_parent_inode_at_path(old_fd, source_path, true));
     let (target_parent_inode, target_entry_name) =
         wasi_try!(state.fs.get_parent_inode_at_path(new_fd, target_path, true));
+    if !(has_rights(
+        wasi_try!(state.fs.parent_preopen_rights(old_fd, source_path)),
+        __WASI_RIGHT_PATH_RENAME_SOURCE,
+    ) && has_rights(
+        wasi_try!(state.fs.parent_preopen_rights(new_fd, target_path)),
+        __WASI_RIGHT_PATH_RENAME_TARGET,
+    )) {
+        return __WASI_EACCES;
+    }
 
     let host_adjusted_target_path = match &state.fs.inodes[target_parent_inode].kind {
         Kind::Dir { entries, path, .. } => {
@@ -2116,8 +2223,11 @@ pub fn path_rename(
                 h.rename_file(&host_adjusted_target_path)
                     .map_err(|e| e.into_wasi_err())
             } else {
-                let out =
-                    std::fs::rename(&path, &host_adjusted_target_path).map_err(|_| __WASI_EIO);
+                let out = state
+                    .fs
+                    .fs_backend
+                    .rename(path, &host_adjusted_target_path)
+                    .map_err(|_| __WASI_EIO);
                 *path = host_adjusted_target_path;
                 out
             };
@@ -2179,11 +2289,18 @@ pub fn path_symlink(
     // get the depth of the parent + 1 (UNDER INVESTIGATION HMMMMMMMM THINK FISH ^ THINK FISH)
     let old_path_path = std::path::Path::new(old_path_str);
     let (source_inode, _) = wasi_try!(state.fs.get_parent_inode_at_path(fd, old_path_path, true));
-    let depth = wasi_try!(state.fs.path_depth_from_fd(fd, source_inode)) - 1;
+    // the source may be directly inside the base directory, at depth 0
+    let depth = wasi_try!(state.fs.path_depth_from_fd(fd, source_inode)).saturating_sub(1);
 
     let new_path_path = std::path::Path::new(new_path_str);
     let (target_parent_inode, entry_name) =
         wasi_try!(state.fs.get_parent_inode_at_path(fd, new_path_path, true));
+    if !has_rights(
+        wasi_try!(state.fs.parent_preopen_rights(fd, new_path_path)),
+        __WASI_RIGHT_PATH_SYMLINK,
+    ) {
+        return __WASI_EACCES;
+    }
 
     // short circuit if anything is wrong, before we create an inode
     match &state.fs.inodes[target_parent_inode].kind {
@@ -2197,6 +2314,7 @@ pub fn path_symlink(
             unreachable!("get_parent_inode_at_path returned something other than a Dir or Root")
         }
     }
+    wasi_try!(state.fs.quota.create_file());
 
     let mut source_path = std::path::Path::new(old_path_str);
     let mut relative_path = std::path::PathBuf::new();
@@ -2246,6 +2364,8 @@ pub fn path_unlink_file(
 ) -> __wasi_errno_t {
     debug!("wasi::path_unlink_file");
     let (memory, mut state) = env.get_memory_and_wasi_state(0);
+    // borrow through the guard once so that the inodes and the backend can be borrowed separately
+    let state = &mut *state;
 
     let base_dir = wasi_try!(state.fs.fd_map.get(&fd).ok_or(__WASI_EBADF));
     if !has_rights(base_dir.rights, __WASI_RIGHT_PATH_UNLINK_FILE) {
@@ -2259,6 +2379,14 @@ pub fn path_unlink_file(
         wasi_try!(state
             .fs
             .get_parent_inode_at_path(fd, std::path::Path::new(path_str), false));
+    if !has_rights(
+        wasi_try!(state
+            .fs
+            .parent_preopen_rights(fd, std::path::Path::new(path_str))),
+        __WASI_RIGHT_PATH_UNLINK_FILE,
+    ) {
+        return __WASI_EACCES;
+    }
 
     let removed_inode = match &mut state.fs.inodes[parent_inode].kind {
         Kind::Dir {
@@ -2286,7 +2414,11 @@ pub fn path_unlink_file(
                     // File is closed
                     // problem with the abstraction, we can't call unlink because there's no handle
                     // TODO: replace this code
-                    wasi_try!(std::fs::remove_file(path).map_err(|_| __WASI_EIO));
+                    wasi_try!(state
+                        .fs
+                        .fs_backend
+                        .remove_file(path)
+                        .map_err(|_| __WASI_EIO));
                 }
             }
             Kind::Dir { .. } | Kind::Root { .. } => return __WASI_EISDIR,
@@ -2320,8 +2452,108 @@ pub fn path_unlink_file(
     __WASI_ESUCCESS
 }
 
+/// How often `poll_oneoff` checks the files without a host fd, like pipes.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The readiness of the file of an fd subscription of `poll_oneoff`.
+struct PollFd {
+    /// The host fd to poll, if any
+    raw_fd: Option<i32>,
+    /// The ready events, for files without a host fd
+    events: PollEventSet,
+    /// The bytes available to read
+    nbytes: usize,
+}
+
+fn poll_fd(
+    state: &WasiState,
+    fd: __wasi_fd_t,
+    right: __wasi_rights_t,
+) -> Result<PollFd, __wasi_errno_t> {
+    let file = match fd {
+        __WASI_STDIN_FILENO => state.fs.stdin().map_err(WasiFsError::into_wasi_err)?,
+        __WASI_STDOUT_FILENO => state.fs.stdout().map_err(WasiFsError::into_wasi_err)?,
+        __WASI_STDERR_FILENO => state.fs.stderr().map_err(WasiFsError::into_wasi_err)?,
+        _ => {
+            let fd_entry = state.fs.get_fd(fd)?;
+            if !has_rights(fd_entry.rights, right | __WASI_RIGHT_POLL_FD_READWRITE) {
+                return Err(__WASI_EACCES);
+            }
+            match &state.fs.inodes[fd_entry.inode].kind {
+                Kind::File { handle, .. } => handle,
+                Kind::Buffer { buffer } => {
+                    return Ok(PollFd {
+                        raw_fd: None,
+                        events: PollEventBuilder::new()
+                            .add(PollEvent::PollIn)
+                            .add(PollEvent::PollOut)
+                            .build(),
+                        nbytes: buffer.len(),
+                    })
+                }
+                Kind::Dir { .. } | Kind::Root { .. } | Kind::Symlink { .. } => {
+                    return Err(__WASI_EBADF)
+                }
+            }
+        }
+    };
+    let file = file.as_deref().ok_or(__WASI_EBADF)?;
+    let raw_fd = file.get_raw_fd();
+    let events = match raw_fd {
+        Some(_) => 0,
+        None => file.poll_ready().map_err(WasiFsError::into_wasi_err)?,
+    };
+    let nbytes = if right == __WASI_RIGHT_FD_READ {
+        file.bytes_available().unwrap_or(0)
+    } else {
+        0
+    };
+    Ok(PollFd {
+        raw_fd,
+        events,
+        nbytes,
+    })
+}
+
+/// Get how long to wait for a clock subscription of `poll_oneoff`.
+fn poll_clock_timeout(
+    state: &WasiState,
+    clock: &__wasi_subscription_clock_t,
+) -> Result<Duration, __wasi_errno_t> {
+    let now = state.clock.time(clock.clock_id, 1)?;
+    let timeout = if clock.flags & __WASI_SUBSCRIPTION_CLOCK_ABSTIME != 0 {
+        clock.timeout.saturating_sub(now)
+    } else {
+        clock.timeout
+    };
+    Ok(Duration::from_nanos(timeout))
+}
+
+fn poll_event(
+    subscription: &__wasi_subscription_t,
+    error: __wasi_errno_t,
+    nbytes: usize,
+    flags: __wasi_eventrwflags_t,
+) -> __wasi_event_t {
+    __wasi_event_t {
+        userdata: subscription.userdata,
+        error,
+        type_: subscription.type_,
+        u: __wasi_event_u {
+            fd_readwrite: __wasi_event_fd_readwrite_t {
+                nbytes: nbytes as u64,
+                flags,
+            },
+        },
+    }
+}
+
 /// ### `poll_oneoff()`
 /// Concurrently poll for a set of events
+///
+/// Blocks until at least one clock subscription has expired or one fd
+/// subscription is ready. Fd subscriptions that can't be polled are reported
+/// as events with an error.
 /// Inputs:
 /// - `const __wasi_subscription_t *in`
 ///     The events to subscribe to
@@ -2341,186 +2573,148 @@ pub fn poll_oneoff(
 ) -> __wasi_errno_t {
     debug!("wasi::poll_oneoff");
     debug!("  => nsubscriptions = {}", nsubscriptions);
-    let (memory, mut state) = env.get_memory_and_wasi_state(0);
+    let memory = env.memory();
 
     let subscription_array = wasi_try!(in_.deref(memory, 0, nsubscriptions));
     let event_array = wasi_try!(out_.deref(memory, 0, nsubscriptions));
-    let mut events_seen = 0;
     let out_ptr = wasi_try!(nevents.deref(memory));
+    if nsubscriptions == 0 {
+        return __WASI_EINVAL;
+    }
 
-    let mut fds = vec![];
-    let mut clock_subs = vec![];
-    let mut in_events = vec![];
-    let mut total_ns_slept = 0;
-
-    for sub in subscription_array.iter() {
-        let s: WasiSubscription = wasi_try!(sub.get().try_into());
-        let mut peb = PollEventBuilder::new();
-        let mut ns_to_sleep = 0;
-
-        let fd = match s.event_type {
-            EventType::Read(__wasi_subscription_fs_readwrite_t { fd }) => {
-                match fd {
-                    __WASI_STDIN_FILENO | __WASI_STDOUT_FILENO | __WASI_STDERR_FILENO => (),
-                    _ => {
-                        let fd_entry = wasi_try!(state.fs.get_fd(fd));
-                        if !has_rights(fd_entry.rights, __WASI_RIGHT_FD_READ) {
-                            return __WASI_EACCES;
-                        }
-                    }
+    let subscriptions = subscription_array
+        .iter()
+        .map(Cell::get)
+        .collect::<Vec<__wasi_subscription_t>>();
+    let mut parsed = Vec::with_capacity(subscriptions.len());
+    for subscription in subscriptions.iter() {
+        let s: WasiSubscription = wasi_try!((*subscription).try_into());
+        parsed.push(s.event_type);
+    }
+
+    // the events, with the index of their subscription
+    let mut events = vec![];
+    let mut deadlines = vec![];
+    let start = Instant::now();
+    {
+        let state = env.state();
+        for (i, event_type) in parsed.iter().enumerate() {
+            if let EventType::Clock(clock) = event_type {
+                match poll_clock_timeout(&state, clock) {
+                    Ok(timeout) => deadlines.push((i, start + timeout)),
+                    Err(errno) => events.push((i, poll_event(&subscriptions[i], errno, 0, 0))),
                 }
-                in_events.push(peb.add(PollEvent::PollIn).build());
-                Some(fd)
             }
-            EventType::Write(__wasi_subscription_fs_readwrite_t { fd }) => {
-                match fd {
-                    __WASI_STDIN_FILENO | __WASI_STDOUT_FILENO | __WASI_STDERR_FILENO => (),
-                    _ => {
-                        let fd_entry = wasi_try!(state.fs.get_fd(fd));
-
-                        if !has_rights(fd_entry.rights, __WASI_RIGHT_FD_WRITE) {
-                            return __WASI_EACCES;
+        }
+    }
+
+    loop {
+        // check the readiness of the fds with the state locked, and wait
+        // with it unlocked, so that other threads can use it
+        let mut host_fds = vec![];
+        let mut waits_for_files = false;
+        {
+            let state = env.state();
+            let mut checked = vec![];
+            for (i, event_type) in parsed.iter().enumerate() {
+                let (fd, right, wanted) = match event_type {
+                    EventType::Read(__wasi_subscription_fs_readwrite_t { fd }) => {
+                        (*fd, __WASI_RIGHT_FD_READ, PollEvent::PollIn)
+                    }
+                    EventType::Write(__wasi_subscription_fs_readwrite_t { fd }) => {
+                        (*fd, __WASI_RIGHT_FD_WRITE, PollEvent::PollOut)
+                    }
+                    EventType::Clock(_) => continue,
+                };
+                match poll_fd(&state, fd, right) {
+                    Ok(readiness) => {
+                        let wanted = PollEventBuilder::new().add(wanted).build();
+                        if let Some(raw_fd) = readiness.raw_fd {
+                            host_fds.push((raw_fd, wanted));
                         }
+                        checked.push((i, readiness));
                     }
+                    Err(errno) => events.push((i, poll_event(&subscriptions[i], errno, 0, 0))),
                 }
-                in_events.push(peb.add(PollEvent::PollOut).build());
-                Some(fd)
             }
-            EventType::Clock(clock_info) => {
-                if clock_info.clock_id == __WASI_CLOCK_REALTIME {
-                    // this is a hack
-                    // TODO: do this properly
-                    ns_to_sleep = clock_info.timeout;
-                    clock_subs.push(clock_info);
-                    None
-                } else {
-                    unimplemented!("Polling not implemented for clocks yet");
-                }
-            }
-        };
 
-        if let Some(fd) = fd {
-            let wasi_file_ref: &dyn WasiFile = match fd {
-                __WASI_STDERR_FILENO => wasi_try!(
-                    wasi_try!(state.fs.stderr().map_err(WasiFsError::into_wasi_err)).as_ref(),
-                    __WASI_EBADF
-                )
-                .as_ref(),
-                __WASI_STDIN_FILENO => wasi_try!(
-                    wasi_try!(state.fs.stdin().map_err(WasiFsError::into_wasi_err)).as_ref(),
-                    __WASI_EBADF
-                )
-                .as_ref(),
-                __WASI_STDOUT_FILENO => wasi_try!(
-                    wasi_try!(state.fs.stdout().map_err(WasiFsError::into_wasi_err)).as_ref(),
-                    __WASI_EBADF
-                )
-                .as_ref(),
-                _ => {
-                    let fd_entry = wasi_try!(state.fs.get_fd(fd));
-                    let inode = fd_entry.inode;
-                    if !has_rights(fd_entry.rights, __WASI_RIGHT_POLL_FD_READWRITE) {
-                        return __WASI_EACCES;
-                    }
-
-                    match &state.fs.inodes[inode].kind {
-                        Kind::File { handle, .. } => {
-                            if let Some(h) = handle {
-                                h.as_ref()
-                            } else {
-                                return __WASI_EBADF;
-                            }
+            let host_events =
+                wasi_try!(poll(&host_fds, Some(Duration::from_secs(0)))
+                    .map_err(WasiFsError::into_wasi_err));
+            let mut host_events = host_events.into_iter();
+            for (i, poll_fd) in checked {
+                let seen = match poll_fd.raw_fd {
+                    Some(_) => host_events.next().unwrap_or(0),
+                    None => poll_fd.events,
+                };
+                let mut ready = false;
+                let mut error = __WASI_ESUCCESS;
+                let mut flags = 0;
+                for event in iterate_poll_events(seen) {
+                    match (event, subscriptions[i].type_) {
+                        (PollEvent::PollIn, __WASI_EVENTTYPE_FD_READ)
+                        | (PollEvent::PollOut, __WASI_EVENTTYPE_FD_WRITE) => ready = true,
+                        (PollEvent::PollIn, _) | (PollEvent::PollOut, _) => (),
+                        (PollEvent::PollHangUp, _) => {
+                            ready = true;
+                            flags = __WASI_EVENT_FD_READWRITE_HANGUP;
+                        }
+                        (PollEvent::PollError, _) => {
+                            ready = true;
+                            error = __WASI_EIO;
                         }
-                        Kind::Dir { .. }
-                        | Kind::Root { .. }
-                        | Kind::Buffer { .. }
-                        | Kind::Symlink { .. } => {
-                            unimplemented!("polling read on non-files not yet supported")
+                        (PollEvent::PollInvalid, _) => {
+                            ready = true;
+                            error = __WASI_EBADF;
                         }
                     }
                 }
-            };
-            fds.push(wasi_file_ref);
-        } else {
-            let remaining_ns = ns_to_sleep as i64 - total_ns_slept as i64;
-            if remaining_ns > 0 {
-                debug!("Sleeping for {} nanoseconds", remaining_ns);
-                let duration = std::time::Duration::from_nanos(remaining_ns as u64);
-                std::thread::sleep(duration);
-                total_ns_slept += remaining_ns;
+                if ready {
+                    let event = poll_event(&subscriptions[i], error, poll_fd.nbytes, flags);
+                    events.push((i, event));
+                } else if poll_fd.raw_fd.is_none() {
+                    waits_for_files = true;
+                }
             }
         }
-    }
-    let mut seen_events = vec![Default::default(); in_events.len()];
-    wasi_try!(poll(
-        fds.as_slice(),
-        in_events.as_slice(),
-        seen_events.as_mut_slice()
-    )
-    .map_err(|e| e.into_wasi_err()));
-
-    for (i, seen_event) in seen_events.into_iter().enumerate() {
-        let mut flags = 0;
-        let mut error = __WASI_EAGAIN;
-        let mut bytes_available = 0;
-        let event_iter = iterate_poll_events(seen_event);
-        for event in event_iter {
-            match event {
-                PollEvent::PollError => error = __WASI_EIO,
-                PollEvent::PollHangUp => flags = __WASI_EVENT_FD_READWRITE_HANGUP,
-                PollEvent::PollInvalid => error = __WASI_EINVAL,
-                PollEvent::PollIn => {
-                    bytes_available =
-                        wasi_try!(fds[i].bytes_available().map_err(|e| e.into_wasi_err()));
-                    error = __WASI_ESUCCESS;
-                }
-                PollEvent::PollOut => {
-                    bytes_available =
-                        wasi_try!(fds[i].bytes_available().map_err(|e| e.into_wasi_err()));
-                    error = __WASI_ESUCCESS;
-                }
+
+        let now = Instant::now();
+        for &(i, deadline) in deadlines.iter() {
+            if deadline <= now {
+                events.push((i, poll_event(&subscriptions[i], __WASI_ESUCCESS, 0, 0)));
             }
         }
-        let event = __wasi_event_t {
-            userdata: subscription_array[i].get().userdata,
-            error,
-            type_: subscription_array[i].get().type_,
-            u: unsafe {
-                __wasi_event_u {
-                    fd_readwrite: __wasi_event_fd_readwrite_t {
-                        nbytes: bytes_available as u64,
-                        flags,
-                    },
-                }
-            },
-        };
-        event_array[events_seen].set(event);
-        events_seen += 1;
-    }
-    for clock_info in clock_subs {
-        let event = __wasi_event_t {
-            // TOOD: review userdata value
-            userdata: 0,
-            error: __WASI_ESUCCESS,
-            type_: __WASI_EVENTTYPE_CLOCK,
-            u: unsafe {
-                __wasi_event_u {
-                    fd_readwrite: __wasi_event_fd_readwrite_t {
-                        nbytes: 0,
-                        flags: 0,
-                    },
-                }
-            },
-        };
-        event_array[events_seen].set(event);
-        events_seen += 1;
+        if !events.is_empty() {
+            break;
+        }
+
+        let mut timeout = deadlines
+            .iter()
+            .map(|&(_, deadline)| deadline.saturating_duration_since(now))
+            .min();
+        if waits_for_files {
+            timeout = Some(timeout.map_or(POLL_INTERVAL, |timeout| timeout.min(POLL_INTERVAL)));
+        }
+        // the events are checked again with the state locked, so an fd
+        // closed in the meantime only causes a spurious wake up
+        wasi_try!(poll(&host_fds, timeout).map_err(WasiFsError::into_wasi_err));
     }
-    out_ptr.set(events_seen as u32);
+
+    events.sort_by_key(|&(i, _)| i);
+    for (cell, (_, event)) in event_array.iter().zip(events.iter()) {
+        cell.set(*event);
+    }
+    out_ptr.set(events.len() as u32);
     __WASI_ESUCCESS
 }
 
 pub fn proc_exit(env: &WasiEnv, code: __wasi_exitcode_t) {
     debug!("wasi::proc_exit, {}", code);
+    // the state must not be locked while the hook runs
+    let on_exit = env.state().on_exit.clone();
+    if let Some(ExitHook(on_exit)) = on_exit {
+        on_exit(code);
+    }
     RuntimeError::raise(Box::new(WasiError::Exit(code)));
     unreachable!();
 }
@@ -2539,18 +2733,16 @@ pub fn proc_raise(env: &WasiEnv, sig: __wasi_signal_t) -> __wasi_errno_t {
 ///     The number of bytes that will be written
 pub fn random_get(env: &WasiEnv, buf: WasmPtr<u8, Array>, buf_len: u32) -> __wasi_errno_t {
     debug!("wasi::random_get buf_len: {}", buf_len);
-    let memory = env.memory();
+    let (memory, mut state) = env.get_memory_and_wasi_state(0);
 
     let buf = wasi_try!(buf.deref(memory, 0, buf_len));
 
-    let res = unsafe {
-        let u8_buffer = &mut *(buf as *const [_] as *mut [_] as *mut [u8]);
-        getrandom::getrandom(u8_buffer)
-    };
-    match res {
-        Ok(()) => __WASI_ESUCCESS,
-        Err(_) => __WASI_EIO,
+    let mut u8_buffer = vec![0; buf.len()];
+    wasi_try!(state.random.fill(&mut u8_buffer));
+    for (cell, byte) in buf.iter().zip(&u8_buffer) {
+        cell.set(*byte);
     }
+    __WASI_ESUCCESS
 }
 
 /// ### `sched_yield()`
@@ -2561,6 +2753,58 @@ pub fn sched_yield(env: &WasiEnv) -> __wasi_errno_t {
     __WASI_ESUCCESS
 }
 
+/// The rights of the fds of sockets.
+const SOCKET_RIGHTS: __wasi_rights_t = __WASI_RIGHT_FD_READ
+    | __WASI_RIGHT_FD_WRITE
+    | __WASI_RIGHT_FD_FDSTAT_SET_FLAGS
+    | __WASI_RIGHT_FD_FILESTAT_GET
+    | __WASI_RIGHT_POLL_FD_READWRITE
+    | __WASI_RIGHT_SOCK_SHUTDOWN;
+
+/// Get the socket of type `T` behind `sock`, if the fd has the `rights`.
+fn get_socket<T: WasiFile>(
+    state: &mut WasiState,
+    sock: __wasi_fd_t,
+    rights: __wasi_rights_t,
+) -> Result<&mut T, __wasi_errno_t> {
+    let fd_entry = state.fs.fd_map.get(&sock).ok_or(__WASI_EBADF)?;
+    if !has_rights(fd_entry.rights, rights) {
+        return Err(__WASI_EACCES);
+    }
+    match &mut state.fs.inodes[fd_entry.inode].kind {
+        Kind::File {
+            handle: Some(handle),
+            ..
+        } => handle.downcast_mut::<T>().ok_or(__WASI_ENOTSOCK),
+        _ => Err(__WASI_ENOTSOCK),
+    }
+}
+
+fn io_err_into_wasi_err(err: io::Error) -> __wasi_errno_t {
+    WasiFsError::from(err).into_wasi_err()
+}
+
+/// Read the port given to a socket syscall.
+fn socket_port(port: u32) -> Result<u16, __wasi_errno_t> {
+    port.try_into().map_err(|_| __WASI_EINVAL)
+}
+
+/// ### `sock_recv()`
+/// Receive a message from a socket.
+/// Inputs:
+/// - `__wasi_fd_t sock`
+///     The socket to receive from
+/// - `__wasi_iovec_t *ri_data`
+///     The buffers where the message is stored
+/// - `u32 ri_data_len`
+///     The number of buffers
+/// - `__wasi_riflags_t ri_flags`
+///     `__WASI_SOCK_RECV_PEEK` to keep the message in the socket
+/// Output:
+/// - `u32 *ro_datalen`
+///     The number of bytes received
+/// - `__wasi_roflags_t *ro_flags`
+///     Always 0
 pub fn sock_recv(
     env: &WasiEnv,
     sock: __wasi_fd_t,
@@ -2570,9 +2814,64 @@ pub fn sock_recv(
     ro_datalen: WasmPtr<u32>,
     ro_flags: WasmPtr<__wasi_roflags_t>,
 ) -> __wasi_errno_t {
-    debug!("wasi::sock_recv");
-    unimplemented!("wasi::sock_recv")
+    debug!("wasi::sock_recv: sock={}", sock);
+    let (memory, mut state) = env.get_memory_and_wasi_state(0);
+    let iovs_arr_cell = wasi_try!(ri_data.deref(memory, 0, ri_data_len));
+    let ro_datalen_cell = wasi_try!(ro_datalen.deref(memory));
+    let ro_flags_cell = wasi_try!(ro_flags.deref(memory));
+    let socket = wasi_try!(get_socket::<WasiTcpStream>(
+        &mut state,
+        sock,
+        __WASI_RIGHT_FD_READ
+    ));
+    // Don't block the other WASI calls while waiting for the data.
+    let mut stream = wasi_try!(socket.stream.try_clone().map_err(io_err_into_wasi_err));
+    drop(state);
+
+    let peek = ri_flags & __WASI_SOCK_RECV_PEEK != 0;
+    let mut bytes_read = 0;
+    for iov in iovs_arr_cell {
+        let iov_inner = iov.get();
+        let cells = wasi_try!(iov_inner.buf.deref(memory, 0, iov_inner.buf_len));
+        let mut buf = vec![0; cells.len()];
+        let amt = match if peek {
+            stream.peek(&mut buf)
+        } else {
+            stream.read(&mut buf)
+        } {
+            Ok(amt) => amt,
+            // return what was already received
+            Err(_) if bytes_read > 0 => break,
+            Err(err) => return io_err_into_wasi_err(err),
+        };
+        for (cell, byte) in cells.iter().zip(&buf[..amt]) {
+            cell.set(*byte);
+        }
+        bytes_read += amt as u32;
+        if peek || amt < buf.len() {
+            break;
+        }
+    }
+
+    ro_datalen_cell.set(bytes_read);
+    ro_flags_cell.set(0);
+    __WASI_ESUCCESS
 }
+
+/// ### `sock_send()`
+/// Send a message on a socket.
+/// Inputs:
+/// - `__wasi_fd_t sock`
+///     The socket to send on
+/// - `__wasi_ciovec_t *si_data`
+///     The buffers of the message
+/// - `u32 si_data_len`
+///     The number of buffers
+/// - `__wasi_siflags_t si_flags`
+///     Unused
+/// Output:
+/// - `u32 *so_datalen`
+///     The number of bytes sent
 pub fn sock_send(
     env: &WasiEnv,
     sock: __wasi_fd_t,
@@ -2581,10 +2880,272 @@ pub fn sock_send(
     si_flags: __wasi_siflags_t,
     so_datalen: WasmPtr<u32>,
 ) -> __wasi_errno_t {
-    debug!("wasi::sock_send");
-    unimplemented!("wasi::sock_send")
+    debug!("wasi::sock_send: sock={}", sock);
+    let (memory, mut state) = env.get_memory_and_wasi_state(0);
+    let iovs_arr_cell = wasi_try!(si_data.deref(memory, 0, si_data_len));
+    let so_datalen_cell = wasi_try!(so_datalen.deref(memory));
+    let socket = wasi_try!(get_socket::<WasiTcpStream>(
+        &mut state,
+        sock,
+        __WASI_RIGHT_FD_WRITE
+    ));
+    // Don't block the other WASI calls while the data is sent.
+    let mut stream = wasi_try!(socket.stream.try_clone().map_err(io_err_into_wasi_err));
+    drop(state);
+
+    let mut bytes_written = 0;
+    for iov in iovs_arr_cell {
+        let iov_inner = iov.get();
+        let bytes = wasi_try!(iov_inner.buf.deref(memory, 0, iov_inner.buf_len));
+        let bytes = bytes.iter().map(|b_cell| b_cell.get()).collect::<Vec<u8>>();
+        let amt = match stream.write(&bytes) {
+            Ok(amt) => amt,
+            // return what was already sent
+            Err(_) if bytes_written > 0 => break,
+            Err(err) => return io_err_into_wasi_err(err),
+        };
+        bytes_written += amt as u32;
+        if amt < bytes.len() {
+            break;
+        }
+    }
+
+    so_datalen_cell.set(bytes_written);
+    __WASI_ESUCCESS
 }
+
+/// ### `sock_shutdown()`
+/// Shut down the receiving and/or sending part of a socket.
+/// Inputs:
+/// - `__wasi_fd_t sock`
+///     The socket to shut down
+/// - `__wasi_sdflags_t how`
+///     `__WASI_SHUT_RD` and/or `__WASI_SHUT_WR`
 pub fn sock_shutdown(env: &WasiEnv, sock: __wasi_fd_t, how: __wasi_sdflags_t) -> __wasi_errno_t {
-    debug!("wasi::sock_shutdown");
-    unimplemented!("wasi::sock_shutdown")
+    debug!("wasi::sock_shutdown: sock={}", sock);
+    let mut state = env.state();
+    let how = match how {
+        __WASI_SHUT_RD => Shutdown::Read,
+        __WASI_SHUT_WR => Shutdown::Write,
+        _ if how == __WASI_SHUT_RD | __WASI_SHUT_WR => Shutdown::Both,
+        _ => return __WASI_EINVAL,
+    };
+    let socket = wasi_try!(get_socket::<WasiTcpStream>(
+        &mut state,
+        sock,
+        __WASI_RIGHT_SOCK_SHUTDOWN
+    ));
+    wasi_try!(socket.shutdown(how).map_err(io_err_into_wasi_err));
+    __WASI_ESUCCESS
+}
+
+/// ### `sock_accept()`
+/// Accept a connection on a listening socket.
+/// Inputs:
+/// - `__wasi_fd_t sock`
+///     The listening socket
+/// - `__wasi_fdflags_t flags`
+///     The flags of the new fd; only `__WASI_FDFLAG_NONBLOCK` is supported
+/// Output:
+/// - `__wasi_fd_t *ro_fd`
+///     The fd of the connection
+pub fn sock_accept(
+    env: &WasiEnv,
+    sock: __wasi_fd_t,
+    flags: __wasi_fdflags_t,
+    ro_fd: WasmPtr<__wasi_fd_t>,
+) -> __wasi_errno_t {
+    debug!("wasi::sock_accept: sock={}", sock);
+    let (memory, mut state) = env.get_memory_and_wasi_state(0);
+    let ro_fd_cell = wasi_try!(ro_fd.deref(memory));
+    if flags & !__WASI_FDFLAG_NONBLOCK != 0 {
+        return __WASI_EINVAL;
+    }
+    let listener = wasi_try!(get_socket::<WasiTcpListener>(
+        &mut state,
+        sock,
+        __WASI_RIGHT_FD_READ
+    ));
+    // Don't block the other WASI calls while waiting for a connection.
+    let listener = wasi_try!(listener.listener.try_clone().map_err(io_err_into_wasi_err));
+    drop(state);
+
+    let (stream, _) = wasi_try!(listener.accept().map_err(io_err_into_wasi_err));
+    wasi_try!(stream
+        .set_nonblocking(flags & __WASI_FDFLAG_NONBLOCK != 0)
+        .map_err(io_err_into_wasi_err));
+    let mut state = env.state();
+    let fd = wasi_try!(state.fs.create_socket_fd(
+        Box::new(WasiTcpStream { stream }),
+        SOCKET_RIGHTS,
+        flags
+    ));
+
+    ro_fd_cell.set(fd);
+    __WASI_ESUCCESS
+}
+
+/// ### `sock_connect()`
+/// Open a TCP connection to a host allowed by the [`NetworkPolicy`] of the
+/// module.  This is an extension of WASI.
+/// Inputs:
+/// - `const char *host`
+///     The name or the address of the host
+/// - `u32 host_len`
+///     The length of `host`
+/// - `u32 port`
+///     The port to connect to
+/// - `__wasi_fdflags_t flags`
+///     The flags of the new fd; only `__WASI_FDFLAG_NONBLOCK` is supported
+/// Output:
+/// - `__wasi_fd_t *ro_fd`
+///     The fd of the connection
+///
+/// [`NetworkPolicy`]: crate::NetworkPolicy
+pub fn sock_connect(
+    env: &WasiEnv,
+    host: WasmPtr<u8, Array>,
+    host_len: u32,
+    port: u32,
+    flags: __wasi_fdflags_t,
+    ro_fd: WasmPtr<__wasi_fd_t>,
+) -> __wasi_errno_t {
+    debug!("wasi::sock_connect");
+    let (memory, mut state) = env.get_memory_and_wasi_state(0);
+    let host = unsafe { get_input_str!(memory, host, host_len) };
+    let port = wasi_try!(socket_port(port));
+    let ro_fd_cell = wasi_try!(ro_fd.deref(memory));
+    if flags & !__WASI_FDFLAG_NONBLOCK != 0 {
+        return __WASI_EINVAL;
+    }
+    if !state.network.is_allowed(host, port) {
+        debug!("=> connection to {}:{} denied", host, port);
+        return __WASI_ENOTCAPABLE;
+    }
+    // Don't block the other WASI calls while connecting.
+    drop(state);
+
+    let stream = wasi_try!(TcpStream::connect((host, port)).map_err(io_err_into_wasi_err));
+    wasi_try!(stream
+        .set_nonblocking(flags & __WASI_FDFLAG_NONBLOCK != 0)
+        .map_err(io_err_into_wasi_err));
+    let mut state = env.state();
+    let fd = wasi_try!(state.fs.create_socket_fd(
+        Box::new(WasiTcpStream { stream }),
+        SOCKET_RIGHTS,
+        flags
+    ));
+
+    ro_fd_cell.set(fd);
+    __WASI_ESUCCESS
+}
+
+/// ### `sock_listen()`
+/// Open a TCP socket listening on an address allowed by the
+/// [`NetworkPolicy`] of the module.  This is an extension of WASI.
+/// Inputs:
+/// - `const char *host`
+///     The address to listen on
+/// - `u32 host_len`
+///     The length of `host`
+/// - `u32 port`
+///     The port to listen on
+/// - `__wasi_fdflags_t flags`
+///     The flags of the new fd; with `__WASI_FDFLAG_NONBLOCK`, `sock_accept`
+///     fails with `__WASI_EAGAIN` instead of waiting for a connection
+/// Output:
+/// - `__wasi_fd_t *ro_fd`
+///     The fd of the listening socket
+///
+/// [`NetworkPolicy`]: crate::NetworkPolicy
+pub fn sock_listen(
+    env: &WasiEnv,
+    host: WasmPtr<u8, Array>,
+    host_len: u32,
+    port: u32,
+    flags: __wasi_fdflags_t,
+    ro_fd: WasmPtr<__wasi_fd_t>,
+) -> __wasi_errno_t {
+    debug!("wasi::sock_listen");
+    let (memory, mut state) = env.get_memory_and_wasi_state(0);
+    let host = unsafe { get_input_str!(memory, host, host_len) };
+    let port = wasi_try!(socket_port(port));
+    let ro_fd_cell = wasi_try!(ro_fd.deref(memory));
+    if flags & !__WASI_FDFLAG_NONBLOCK != 0 {
+        return __WASI_EINVAL;
+    }
+    if !state.network.is_allowed(host, port) {
+        debug!("=> listening on {}:{} denied", host, port);
+        return __WASI_ENOTCAPABLE;
+    }
+
+    let listener = wasi_try!(TcpListener::bind((host, port)).map_err(io_err_into_wasi_err));
+    wasi_try!(listener
+        .set_nonblocking(flags & __WASI_FDFLAG_NONBLOCK != 0)
+        .map_err(io_err_into_wasi_err));
+    let fd = wasi_try!(state.fs.create_socket_fd(
+        Box::new(WasiTcpListener { listener }),
+        SOCKET_RIGHTS,
+        flags
+    ));
+
+    ro_fd_cell.set(fd);
+    __WASI_ESUCCESS
+}
+
+/// ### `sock_addr_resolve()`
+/// Resolve the addresses of a host allowed by the [`NetworkPolicy`] of the
+/// module.  This is an extension of WASI.
+/// Inputs:
+/// - `const char *host`
+///     The name of the host
+/// - `u32 host_len`
+///     The length of `host`
+/// - `u32 port`
+///     The port which will be used
+/// - `u8 *addrs`
+///     Where the addresses are written, as 16 byte IPv6 addresses; IPv4
+///     addresses are mapped to IPv6
+/// - `u32 naddrs`
+///     The maximum number of addresses to write
+/// Output:
+/// - `u32 *ro_naddrs`
+///     The number of addresses of the host, which can be more than `naddrs`
+///
+/// [`NetworkPolicy`]: crate::NetworkPolicy
+pub fn sock_addr_resolve(
+    env: &WasiEnv,
+    host: WasmPtr<u8, Array>,
+    host_len: u32,
+    port: u32,
+    addrs: WasmPtr<u8, Array>,
+    naddrs: u32,
+    ro_naddrs: WasmPtr<u32>,
+) -> __wasi_errno_t {
+    debug!("wasi::sock_addr_resolve");
+    let (memory, state) = env.get_memory_and_wasi_state(0);
+    let host = unsafe { get_input_str!(memory, host, host_len) };
+    let port = wasi_try!(socket_port(port));
+    let ro_naddrs_cell = wasi_try!(ro_naddrs.deref(memory));
+    if !state.network.is_allowed(host, port) {
+        debug!("=> resolution of {}:{} denied", host, port);
+        return __WASI_ENOTCAPABLE;
+    }
+
+    let resolved =
+        wasi_try!((host, port).to_socket_addrs().map_err(io_err_into_wasi_err)).collect::<Vec<_>>();
+    let written = resolved.len().min(naddrs as usize);
+    let addrs_cells = wasi_try!(addrs.deref(memory, 0, written as u32 * 16));
+    for (addr, cells) in resolved.iter().zip(addrs_cells.chunks(16)) {
+        let octets = match addr.ip() {
+            IpAddr::V4(ip) => ip.to_ipv6_mapped().octets(),
+            IpAddr::V6(ip) => ip.octets(),
+        };
+        for (cell, byte) in cells.iter().zip(octets.iter()) {
+            cell.set(*byte);
+        }
+    }
+
+    ro_naddrs_cell.set(resolved.len() as u32);
+    __WASI_ESUCCESS
 }
@@ -0,0 +1,142 @@
+//! Support of the wasi-threads proposal.
+//!
+//! The `wasi.thread-spawn` import creates a new instance of the module on a
+//! new host thread, importing the same shared memory, and calls its
+//! `wasi_thread_start` export.
+
+use crate::syscalls::types::*;
+use crate::{generate_import_object_from_env, WasiEnv, WasiError, WasiVersion};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use tracing::debug;
+use wasmer::{Exports, ExternType, Function, ImportObject, Instance, Memory, Module, RuntimeError};
+
+/// The largest thread id allowed by wasi-threads.
+const MAX_TID: u32 = 0x1FFF_FFFF;
+
+type ThreadHandle = JoinHandle<Result<(), RuntimeError>>;
+
+/// The threads of a module using wasi-threads, shared by all its instances.
+#[derive(Debug, Clone)]
+pub(crate) struct WasiThreads {
+    module: Module,
+    version: WasiVersion,
+    /// The namespace and the name of the memory import
+    memory_import: (String, String),
+    memory: Memory,
+    next_tid: Arc<AtomicU32>,
+    handles: Arc<Mutex<Vec<ThreadHandle>>>,
+}
+
+impl WasiThreads {
+    /// Create the memory shared by the threads of `module`.
+    pub(crate) fn new(module: &Module, version: WasiVersion) -> Result<Self, WasiError> {
+        let invalid = |reason: &str| WasiError::InvalidThreadsModule(reason.to_string());
+        let import = module
+            .imports()
+            .find(|import| matches!(import.ty(), ExternType::Memory(_)))
+            .ok_or_else(|| invalid("the module doesn't import its memory"))?;
+        let memory_type = match import.ty() {
+            ExternType::Memory(memory_type) if memory_type.shared => *memory_type,
+            _ => return Err(invalid("the memory of the module isn't shared")),
+        };
+        let memory = Memory::new(module.store(), memory_type)
+            .map_err(|err| WasiError::InvalidThreadsModule(err.to_string()))?;
+
+        Ok(Self {
+            module: module.clone(),
+            version,
+            memory_import: (import.module().to_string(), import.name().to_string()),
+            memory,
+            next_tid: Arc::new(AtomicU32::new(1)),
+            handles: Arc::new(Mutex::new(Vec::new())),
+        })
+    }
+
+    /// Create the imports of an instance of the module: the WASI imports,
+    /// `wasi.thread-spawn` and the shared memory.
+    pub(crate) fn import_object(&self, env: &WasiEnv) -> ImportObject {
+        let store = self.module.store();
+        let mut import_object = generate_import_object_from_env(store, env.clone(), self.version);
+
+        let mut wasi = Exports::new();
+        wasi.insert(
+            "thread-spawn",
+            Function::new_native_with_env(store, env.clone(), thread_spawn),
+        );
+        import_object.register("wasi", wasi);
+
+        let (namespace, name) = &self.memory_import;
+        let mut memory = Exports::new();
+        memory.insert(name.as_str(), self.memory.clone());
+        import_object.register(namespace.as_str(), memory);
+
+        import_object
+    }
+
+    /// Start a thread calling `wasi_thread_start` with `start_arg`.
+    fn spawn(&self, env: &WasiEnv, start_arg: u32) -> Result<u32, __wasi_errno_t> {
+        let tid = self.next_tid.fetch_add(1, Ordering::SeqCst);
+        if tid > MAX_TID {
+            return Err(__WASI_EAGAIN);
+        }
+
+        let threads = self.clone();
+        let env = env.clone();
+        let handle = std::thread::Builder::new()
+            .name(format!("wasi-thread-{}", tid))
+            .spawn(move || {
+                let import_object = threads.import_object(&env);
+                let instance = Instance::new(&threads.module, &import_object)
+                    .map_err(|err| RuntimeError::new(err.to_string()))?;
+                let start = instance
+                    .exports
+                    .get_native_function::<(i32, i32), ()>("wasi_thread_start")
+                    .map_err(|err| RuntimeError::new(err.to_string()))?;
+                start.call(tid as i32, start_arg as i32)
+            })
+            .map_err(|_| __WASI_EAGAIN)?;
+        self.handles.lock().unwrap().push(handle);
+
+        Ok(tid)
+    }
+
+    /// Wait for the threads started so far, and for the threads they start.
+    pub(crate) fn join(&self) -> Result<(), RuntimeError> {
+        let mut result = Ok(());
+        loop {
+            let handles = std::mem::take(&mut *self.handles.lock().unwrap());
+            if handles.is_empty() {
+                return result;
+            }
+            for handle in handles {
+                let thread_result = handle
+                    .join()
+                    .unwrap_or_else(|_| Err(RuntimeError::new("a WASI thread panicked")));
+                if result.is_ok() {
+                    result = thread_result;
+                }
+            }
+        }
+    }
+}
+
+/// ### `thread-spawn()`
+/// Start a new thread running `wasi_thread_start(tid, start_arg)`.
+/// Inputs:
+/// - `u32 start_arg`
+///     The argument given to `wasi_thread_start`
+/// Output:
+/// - The id of the new thread, or a negative errno
+fn thread_spawn(env: &WasiEnv, start_arg: u32) -> i32 {
+    debug!("wasi::thread_spawn: start_arg={}", start_arg);
+    let result = match &env.threads {
+        Some(threads) => threads.spawn(env, start_arg),
+        None => Err(__WASI_ENOTSUP),
+    };
+    match result {
+        Ok(tid) => tid as i32,
+        Err(errno) => -(errno as i32),
+    }
+}
@@ -0,0 +1,312 @@
+//! Tracing of the WASI syscalls made by a module, like `strace`, and the
+//! host functions of the syscalls, which are traced and checked against a
+//! [`SyscallPolicy`].
+
+use crate::policy::SyscallPolicy;
+use crate::syscalls::types::{__wasi_errno_t, __WASI_ENOTCAPABLE};
+use crate::WasiEnv;
+use std::fmt;
+use std::sync::Arc;
+use wasmer::internals::WithEnv;
+use wasmer::{
+    FromToNativeWasmType, Function, FunctionType, HostFunction, Store, Type, Val, WasmTypeList,
+};
+
+/// An observer of the WASI syscalls made by a module, given to
+/// [`WasiStateBuilder::tracer`](crate::WasiStateBuilder::tracer).
+///
+/// The syscalls of `wasi_unstable` and `wasi_snapshot_preview1` are traced,
+/// with their arguments as given by the module: pointers are offsets into its
+/// memory.
+pub trait WasiTracer: fmt::Debug + Send + Sync + 'static {
+    /// Called before the syscall `name`.
+    fn before_call(&self, _name: &str, _args: &[Val]) {}
+
+    /// Called after the syscall `name` returned, with its errno if it returns
+    /// one.
+    ///
+    /// It isn't called when the syscall doesn't return, like `proc_exit`.
+    fn after_call(&self, name: &str, args: &[Val], errno: Option<__wasi_errno_t>);
+}
+
+/// A [`WasiTracer`] printing a line like `strace` to stderr for each syscall,
+/// like `fd_write(1, 1024, 1, 1016) = 0 (ESUCCESS)`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StraceTracer;
+
+impl StraceTracer {
+    /// Create a tracer printing to stderr.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl WasiTracer for StraceTracer {
+    fn before_call(&self, name: &str, args: &[Val]) {
+        // the module doesn't come back from it
+        if name == "proc_exit" {
+            eprintln!("{}({}) = ?", name, format_args(args));
+        }
+    }
+
+    fn after_call(&self, name: &str, args: &[Val], errno: Option<__wasi_errno_t>) {
+        match errno {
+            Some(errno) => eprintln!(
+                "{}({}) = {} ({})",
+                name,
+                format_args(args),
+                errno,
+                errno_name(errno)
+            ),
+            None => eprintln!("{}({})", name, format_args(args)),
+        }
+    }
+}
+
+fn format_args(args: &[Val]) -> String {
+    args.iter()
+        .map(|arg| match arg {
+            Val::I32(value) => value.to_string(),
+            Val::I64(value) => value.to_string(),
+            Val::F32(value) => value.to_string(),
+            Val::F64(value) => value.to_string(),
+            other => format!("{:?}", other),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+const ERRNO_NAMES: [&str; 77] = [
+    "ESUCCESS",
+    "E2BIG",
+    "EACCES",
+    "EADDRINUSE",
+    "EADDRNOTAVAIL",
+    "EAFNOSUPPORT",
+    "EAGAIN",
+    "EALREADY",
+    "EBADF",
+    "EBADMSG",
+    "EBUSY",
+    "ECANCELED",
+    "ECHILD",
+    "ECONNABORTED",
+    "ECONNREFUSED",
+    "ECONNRESET",
+    "EDEADLK",
+    "EDESTADDRREQ",
+    "EDOM",
+    "EDQUOT",
+    "EEXIST",
+    "EFAULT",
+    "EFBIG",
+    "EHOSTUNREACH",
+    "EIDRM",
+    "EILSEQ",
+    "EINPROGRESS",
+    "EINTR",
+    "EINVAL",
+    "EIO",
+    "EISCONN",
+    "EISDIR",
+    "ELOOP",
+    "EMFILE",
+    "EMLINK",
+    "EMSGSIZE",
+    "EMULTIHOP",
+    "ENAMETOOLONG",
+    "ENETDOWN",
+    "ENETRESET",
+    "ENETUNREACH",
+    "ENFILE",
+    "ENOBUFS",
+    "ENODEV",
+    "ENOENT",
+    "ENOEXEC",
+    "ENOLCK",
+    "ENOLINK",
+    "ENOMEM",
+    "ENOMSG",
+    "ENOPROTOOPT",
+    "ENOSPC",
+    "ENOSYS",
+    "ENOTCONN",
+    "ENOTDIR",
+    "ENOTEMPTY",
+    "ENOTRECOVERABLE",
+    "ENOTSOCK",
+    "ENOTSUP",
+    "ENOTTY",
+    "ENXIO",
+    "EOVERFLOW",
+    "EOWNERDEAD",
+    "EPERM",
+    "EPIPE",
+    "EPROTO",
+    "EPROTONOSUPPORT",
+    "EPROTOTYPE",
+    "ERANGE",
+    "EROFS",
+    "ESPIPE",
+    "ESRCH",
+    "ESTALE",
+    "ETIMEDOUT",
+    "ETXTBSY",
+    "EXDEV",
+    "ENOTCAPABLE",
+];
+
+/// The name of a WASI errno, like `EBADF`.
+pub fn errno_name(errno: __wasi_errno_t) -> &'static str {
+    ERRNO_NAMES
+        .get(errno as usize)
+        .copied()
+        .unwrap_or("EUNKNOWN")
+}
+
+/// A syscall called with the arguments of a dynamic function.
+///
+/// `Args` is a tuple of the types of the parameters, for the implementations
+/// for each number of parameters to not overlap.
+pub(crate) trait Syscall<Args, Rets>: Send + Sync + 'static {
+    /// The types of the parameters of the syscall.
+    fn params() -> Vec<Type>;
+
+    fn call(&self, env: &WasiEnv, args: &[Val]) -> Rets;
+}
+
+fn to_binary(val: &Val) -> i128 {
+    match val {
+        Val::I32(value) => *value as i128,
+        Val::I64(value) => *value as i128,
+        Val::F32(value) => value.to_bits() as i128,
+        Val::F64(value) => value.to_bits() as i128,
+        other => unreachable!("WASI syscalls don't take {:?}", other),
+    }
+}
+
+fn from_binary(binary: i128, ty: Type) -> Val {
+    match ty {
+        Type::I32 => Val::I32(binary as i32),
+        Type::I64 => Val::I64(binary as i64),
+        Type::F32 => Val::F32(f32::from_bits(binary as u32)),
+        Type::F64 => Val::F64(f64::from_bits(binary as u64)),
+        other => unreachable!("WASI syscalls don't return {:?}", other),
+    }
+}
+
+macro_rules! impl_syscall {
+    ( $( $x:ident ),* ) => {
+        #[allow(non_snake_case)]
+        impl<Func, Rets, $( $x ),*> Syscall<( $( $x, )* ), Rets> for Func
+        where
+            Func: Fn(&WasiEnv, $( $x ),*) -> Rets + Send + Sync + 'static,
+            Rets: WasmTypeList,
+            $( $x: FromToNativeWasmType + WasmTypeList, )*
+        {
+            fn params() -> Vec<Type> {
+                let params: Vec<&[Type]> = vec![$( $x::wasm_types() ),*];
+                params.concat()
+            }
+
+            #[allow(unused_variables, unused_mut)]
+            fn call(&self, env: &WasiEnv, args: &[Val]) -> Rets {
+                let mut args = args.iter();
+                $(
+                    let $x = $x::from_slice(&[to_binary(args.next().unwrap())]).unwrap();
+                )*
+                (self)(env, $( $x ),*)
+            }
+        }
+    };
+}
+
+impl_syscall!();
+impl_syscall!(A1);
+impl_syscall!(A1, A2);
+impl_syscall!(A1, A2, A3);
+impl_syscall!(A1, A2, A3, A4);
+impl_syscall!(A1, A2, A3, A4, A5);
+impl_syscall!(A1, A2, A3, A4, A5, A6);
+impl_syscall!(A1, A2, A3, A4, A5, A6, A7);
+impl_syscall!(A1, A2, A3, A4, A5, A6, A7, A8);
+impl_syscall!(A1, A2, A3, A4, A5, A6, A7, A8, A9);
+
+/// Creates the host functions of the WASI syscalls, which are traced when
+/// the module has a [`WasiTracer`] and checked when it has a
+/// [`SyscallPolicy`].
+pub(crate) struct Syscalls<'a> {
+    store: &'a Store,
+    env: WasiEnv,
+    tracer: Option<Arc<dyn WasiTracer>>,
+    policy: Option<Arc<dyn SyscallPolicy>>,
+}
+
+impl<'a> Syscalls<'a> {
+    pub(crate) fn new(store: &'a Store, env: WasiEnv) -> Self {
+        let (tracer, policy) = {
+            let state = env.state();
+            (state.tracer.clone(), state.syscall_policy.clone())
+        };
+        Self {
+            store,
+            env,
+            tracer,
+            policy,
+        }
+    }
+
+    pub(crate) fn env(&self) -> &WasiEnv {
+        &self.env
+    }
+
+    /// Whether the syscalls are dynamic functions, to trace or check them.
+    pub(crate) fn is_dynamic(&self) -> bool {
+        self.tracer.is_some() || self.policy.is_some()
+    }
+
+    /// The host function of the syscall `name`.
+    pub(crate) fn function<F, Args, SyscallArgs, Rets>(
+        &self,
+        name: &'static str,
+        syscall: F,
+    ) -> Function
+    where
+        F: HostFunction<Args, Rets, WithEnv, WasiEnv> + Syscall<SyscallArgs, Rets>,
+        Args: WasmTypeList,
+        Rets: WasmTypeList,
+    {
+        if !self.is_dynamic() {
+            return Function::new_native_with_env(self.store, self.env.clone(), syscall);
+        }
+        let (tracer, policy) = (self.tracer.clone(), self.policy.clone());
+        // the syscalls without an errno can't be denied
+        let returns_errno = Rets::wasm_types() == [Type::I32];
+        let ty = FunctionType::new(F::params(), Rets::wasm_types());
+        Function::new_with_env(self.store, ty, self.env.clone(), move |env, args| {
+            if let Some(tracer) = &tracer {
+                tracer.before_call(name, args);
+            }
+            let denied =
+                returns_errno && matches!(&policy, Some(policy) if !policy.allows(name, args));
+            let rets = if denied {
+                vec![Val::I32(__WASI_ENOTCAPABLE as i32)]
+            } else {
+                let mut rets = syscall.call(env, args).into_array();
+                rets.as_mut()
+                    .iter()
+                    .zip(Rets::wasm_types())
+                    .map(|(binary, ty)| from_binary(*binary, *ty))
+                    .collect::<Vec<_>>()
+            };
+            if let Some(tracer) = &tracer {
+                let errno = match rets.first() {
+                    Some(Val::I32(errno)) => Some(*errno as __wasi_errno_t),
+                    _ => None,
+                };
+                tracer.after_call(name, args, errno);
+            }
+            Ok(rets)
+        })
+    }
+}
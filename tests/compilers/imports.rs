@@ -390,3 +390,48 @@ fn multi_use_host_fn_manages_memory_correctly() -> Result<()> {
     drop(instance2);
     Ok(())
 }
+
+#[cfg(feature = "test-jit")]
+#[test]
+fn dynamic_function_v128() -> Result<()> {
+    use crate::utils::get_compiler;
+    use wasmer_engine_jit::JIT;
+
+    let mut features = Features::new();
+    features.simd(true);
+    let store = Store::new(&JIT::new(get_compiler(false)).features(features).engine());
+    let module = Module::new(
+        &store,
+        r#"
+        (module
+          (import "host" "swap" (func $swap (param v128) (result v128)))
+          (func (export "run") (param v128 i32) (result v128)
+            (i32x4.add (call $swap (local.get 0)) (i32x4.splat (local.get 1)))))
+        "#,
+    )?;
+    let swap = Function::new(
+        &store,
+        &FunctionType::new(vec![Type::V128], vec![Type::V128]),
+        |args| {
+            let value = args[0].unwrap_v128();
+            Ok(vec![Value::V128(value.rotate_left(64))])
+        },
+    );
+    let instance = Instance::new(
+        &module,
+        &imports! {
+            "host" => {
+                "swap" => swap,
+            }
+        },
+    )?;
+
+    let run = instance.exports.get_function("run")?;
+    let result = run.call(&[
+        Value::V128(0x1_0000_0002 << 64 | 0x3_0000_0004),
+        Value::I32(1),
+    ])?;
+    assert_eq!(result[0], Value::V128(0x4_0000_0005 << 64 | 0x2_0000_0003));
+
+    Ok(())
+}
@@ -77,6 +77,46 @@ fn native_function_works_for_wasm() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn native_function_works_for_table_funcref() -> Result<()> {
+    let store = get_store(false);
+    let module = Module::new(
+        &store,
+        r#"
+        (module
+          (table (export "table") 2 funcref)
+          (func $double (param i32) (result i32)
+            (i32.mul (local.get 0) (i32.const 2)))
+          (func $fail (param i32) (result i32)
+            unreachable)
+          (elem (i32.const 0) $double $fail))
+        "#,
+    )?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let table = instance.exports.get_table("table")?;
+
+    let double = match table.get(0) {
+        Some(Value::FuncRef(function)) => function,
+        _ => panic!("expected a funcref"),
+    };
+    assert!(double.native::<i64, i32>().is_err());
+    assert!(double.native::<i32, ()>().is_err());
+    let double: NativeFunc<i32, i32> = double.native()?;
+    assert_eq!(double.call(21)?, 42);
+    assert_eq!(
+        double.call_dyn(&[Value::I32(4)])?.to_vec(),
+        vec![Value::I32(8)]
+    );
+
+    let fail: NativeFunc<i32, i32> = match table.get(1) {
+        Some(Value::FuncRef(function)) => function.native()?,
+        _ => panic!("expected a funcref"),
+    };
+    assert!(fail.call(1).is_err());
+
+    Ok(())
+}
+
 #[test]
 #[should_panic(
     expected = "Closures (functions with captured environments) are currently unsupported with native functions. See: https://github.com/wasmerio/wasmer/issues/1840"
@@ -448,3 +488,126 @@ fn dynamic_host_function_with_env() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+#[cfg_attr(all(target_os = "macos", target_arch = "aarch64"), ignore)]
+fn native_function_works_with_26_params() -> Result<()> {
+    let store = get_store(false);
+    let params = vec!["i32"; 26].join(" ");
+    let gets = (0..26)
+        .map(|i| format!("(local.get {})", i))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let wat = format!(
+        r#"(module
+        (func $sum (import "env" "sum") (param {params}) (result i32))
+        (func (export "sum") (param {params}) (result i32)
+           (call $sum {gets})))"#,
+        params = params,
+        gets = gets
+    );
+    let module = Module::new(&store, wat)?;
+
+    #[allow(clippy::too_many_arguments)]
+    fn sum(
+        a1: i32,
+        a2: i32,
+        a3: i32,
+        a4: i32,
+        a5: i32,
+        a6: i32,
+        a7: i32,
+        a8: i32,
+        a9: i32,
+        a10: i32,
+        a11: i32,
+        a12: i32,
+        a13: i32,
+        a14: i32,
+        a15: i32,
+        a16: i32,
+        a17: i32,
+        a18: i32,
+        a19: i32,
+        a20: i32,
+        a21: i32,
+        a22: i32,
+        a23: i32,
+        a24: i32,
+        a25: i32,
+        a26: i32,
+    ) -> i32 {
+        a1 + a2
+            + a3
+            + a4
+            + a5
+            + a6
+            + a7
+            + a8
+            + a9
+            + a10
+            + a11
+            + a12
+            + a13
+            + a14
+            + a15
+            + a16
+            + a17
+            + a18
+            + a19
+            + a20
+            + a21
+            + a22
+            + a23
+            + a24
+            + a25
+            + a26
+    }
+
+    let import_object = imports! {
+        "env" => {
+            "sum" => Function::new_native(&store, sum),
+        },
+    };
+    let instance = Instance::new(&module, &import_object)?;
+    let f: NativeFunc<
+        (
+            i32,
+            i32,
+            i32,
+            i32,
+            i32,
+            i32,
+            i32,
+            i32,
+            i32,
+            i32,
+            i32,
+            i32,
+            i32,
+            i32,
+            i32,
+            i32,
+            i32,
+            i32,
+            i32,
+            i32,
+            i32,
+            i32,
+            i32,
+            i32,
+            i32,
+            i32,
+        ),
+        i32,
+    > = instance.exports.get_native_function("sum")?;
+    assert_eq!(
+        f.call(
+            1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24,
+            25, 26
+        )?,
+        351
+    );
+
+    Ok(())
+}
@@ -1,6 +1,9 @@
-use crate::utils::{get_headless_store, get_store};
+use crate::utils::{get_headless_store, get_store, get_store_with_middlewares};
 use anyhow::Result;
+use std::sync::Arc;
+use wasmer::wasmparser::Operator;
 use wasmer::*;
+use wasmer_middlewares::Metering;
 
 #[test]
 fn test_serialize() -> Result<()> {
@@ -75,3 +78,39 @@ fn test_deserialize() -> Result<()> {
     assert_eq!(result.to_vec(), vec![Value::I64(1500)]);
     Ok(())
 }
+
+#[test]
+fn test_deserialize_with_another_compiler_config() -> Result<()> {
+    let store = get_store(false);
+    let module = Module::new(&store, r#"(module (func (export "run")))"#)?;
+    let serialized_bytes = module.serialize()?;
+
+    // The headless engines share the fingerprint of the compiling ones, so
+    // that they find their artifacts in the caches.
+    let headless_store = get_headless_store();
+    assert_eq!(
+        store.engine().fingerprint(),
+        headless_store.engine().fingerprint()
+    );
+    unsafe { Module::deserialize(&headless_store, &serialized_bytes)? };
+    unsafe { Module::deserialize(&get_store(false), &serialized_bytes)? };
+
+    // Compiling engines refuse artifacts compiled with another
+    // configuration.
+    let deterministic_store = get_store(true);
+    assert!(matches!(
+        unsafe { Module::deserialize(&deterministic_store, &serialized_bytes) },
+        Err(DeserializeError::Incompatible(_))
+    ));
+    fn cost_always_one(_: &Operator) -> u64 {
+        1
+    }
+    let metering = Arc::new(Metering::new(10, cost_always_one));
+    let metered_store =
+        get_store_with_middlewares(std::iter::once(metering as Arc<dyn ModuleMiddleware>));
+    assert!(matches!(
+        unsafe { Module::deserialize(&metered_store, &serialized_bytes) },
+        Err(DeserializeError::Incompatible(_))
+    ));
+    Ok(())
+}
@@ -1,6 +1,7 @@
 use crate::utils::get_store;
 use anyhow::Result;
 use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
 use wasmer::*;
 
 #[test]
@@ -70,9 +71,16 @@ fn test_trap_trace() -> Result<()> {
     assert_eq!(trace[0].module_name(), "hello_mod");
     assert_eq!(trace[0].func_index(), 1);
     assert_eq!(trace[0].function_name(), Some("hello"));
+    assert_eq!(
+        trace[0].qualified_function_name().as_deref(),
+        Some("hello_mod::hello")
+    );
     assert_eq!(trace[1].module_name(), "hello_mod");
     assert_eq!(trace[1].func_index(), 0);
     assert_eq!(trace[1].function_name(), None);
+    assert_eq!(trace[1].qualified_function_name(), None);
+    assert!(trace[0].is_trapping_frame());
+    assert!(!trace[1].is_trapping_frame());
     assert!(
         e.message().contains("unreachable"),
         "wrong message: {}",
@@ -82,6 +90,90 @@ fn test_trap_trace() -> Result<()> {
     Ok(())
 }
 
+#[cfg(feature = "test-jit")]
+#[test]
+fn test_trap_trace_source_locations() -> Result<()> {
+    use crate::utils::get_compiler;
+    use gimli::write::{Address, DwarfUnit, EndianVec, LineProgram, LineString, Sections};
+    use gimli::{Encoding, Format, LineEncoding, LittleEndian};
+    use wasmer_engine_jit::JIT;
+
+    let encoding = Encoding {
+        format: Format::Dwarf32,
+        version: 4,
+        address_size: 4,
+    };
+    let mut program = LineProgram::new(
+        encoding,
+        LineEncoding::default(),
+        LineString::String(b"/src".to_vec()),
+        LineString::String(b"main.c".to_vec()),
+        None,
+    );
+    let directory = program.add_directory(LineString::String(b"/src".to_vec()));
+    let file = program.add_file(LineString::String(b"main.c".to_vec()), directory, None);
+    // Addresses are relative to the contents of the code section: the
+    // function count, the body size and the locals come before the
+    // `unreachable` instruction at offset 3.
+    program.begin_sequence(Some(Address::Constant(0)));
+    program.row().file = file;
+    program.row().line = 1;
+    program.row().column = 1;
+    program.generate_row();
+    program.row().address_offset = 3;
+    program.row().line = 2;
+    program.row().column = 5;
+    program.generate_row();
+    program.end_sequence(5);
+    let mut dwarf = DwarfUnit::new(encoding);
+    dwarf.unit.line_program = program;
+    let mut sections = Sections::new(EndianVec::new(LittleEndian));
+    dwarf.write(&mut sections)?;
+
+    let mut wasm =
+        wat2wasm(br#"(module $debug_mod (func (export "run") (unreachable)))"#)?.into_owned();
+    sections.for_each(|id, data| -> Result<()> {
+        let data = data.slice();
+        if !data.is_empty() {
+            let name = id.name().as_bytes();
+            wasm.push(0);
+            wasm.push((1 + name.len() + data.len()) as u8);
+            wasm.push(name.len() as u8);
+            wasm.extend_from_slice(name);
+            wasm.extend_from_slice(data);
+        }
+        Ok(())
+    })?;
+
+    let store = Store::new(&JIT::new(get_compiler(false)).debug_info(true).engine());
+    let module = Module::new(&store, &wasm)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let run_func = instance.exports.get_function("run")?;
+    let e = run_func.call(&[]).err().expect("error calling function");
+
+    let location = e.trace()[0]
+        .source_location()
+        .expect("expected a source location");
+    assert!(location.file().ends_with("main.c"), "{}", location.file());
+    assert_eq!(location.line(), 2);
+    assert_eq!(location.column(), Some(5));
+    assert!(
+        e.to_string().contains(&format!(" at {}", location)),
+        "{}",
+        e
+    );
+
+    // Without debug info, the DWARF sections are ignored.
+    let store = get_store(false);
+    let module = Module::new(&store, &wasm)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let run_func = instance.exports.get_function("run")?;
+    let e = run_func.call(&[]).err().expect("error calling function");
+    assert!(e.trace()[0].source_location().is_none());
+
+    Ok(())
+}
+
 #[test]
 fn test_trap_trace_cb() -> Result<()> {
     let store = get_store(false);
@@ -120,11 +212,66 @@ fn test_trap_trace_cb() -> Result<()> {
     // assert_eq!(trace[0].func_index(), 2);
     // assert_eq!(trace[1].module_name(), "hello_mod");
     // assert_eq!(trace[1].func_index(), 1);
+    assert!(trace.iter().all(|frame| !frame.is_trapping_frame()));
     assert_eq!(e.message(), "cb throw");
 
     Ok(())
 }
 
+#[test]
+#[cfg_attr(
+    any(
+        feature = "test-singlepass",
+        feature = "test-llvm",
+        feature = "test-native",
+        target_arch = "aarch64",
+        target_env = "musl",
+    ),
+    ignore
+)]
+fn test_current_backtrace_in_host_function() -> Result<()> {
+    let store = get_store(false);
+    let wat = r#"
+        (module $hello_mod
+            (import "" "log" (func $log))
+            (func (export "run") (call $hello))
+            (func $hello (call $log))
+        )
+    "#;
+
+    #[derive(WasmerEnv, Clone, Default)]
+    struct Env {
+        frames: Arc<Mutex<Vec<FrameInfo>>>,
+    }
+
+    fn log(env: &Env) {
+        *env.frames.lock().unwrap() = current_backtrace();
+    }
+
+    let env = Env::default();
+    let log_func = Function::new_native_with_env(&store, env.clone(), log);
+
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(
+        &module,
+        &imports! {
+            "" => {
+                "log" => log_func,
+            }
+        },
+    )?;
+    instance.exports.get_function("run")?.call(&[])?;
+
+    let frames = env.frames.lock().unwrap();
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0].function_name(), Some("hello"));
+    assert_eq!(frames[0].func_index(), 2);
+    assert_eq!(frames[1].func_index(), 1);
+    assert!(frames.iter().all(|frame| !frame.is_trapping_frame()));
+
+    Ok(())
+}
+
 #[test]
 #[cfg_attr(
     any(
@@ -198,9 +345,9 @@ fn trap_display_pretty() -> Result<()> {
         e.to_string(),
         "\
 RuntimeError: unreachable
-    at die (m[0]:0x23)
+    at m::die (m[0]:0x23)
     at <unnamed> (m[1]:0x27)
-    at foo (m[2]:0x2c)
+    at m::foo (m[2]:0x2c)
     at <unnamed> (m[3]:0x31)"
     );
     Ok(())
@@ -258,11 +405,11 @@ fn trap_display_multi_module() -> Result<()> {
         e.to_string(),
         "\
 RuntimeError: unreachable
-    at die (a[0]:0x23)
+    at a::die (a[0]:0x23)
     at <unnamed> (a[1]:0x27)
-    at foo (a[2]:0x2c)
+    at a::foo (a[2]:0x2c)
     at <unnamed> (a[3]:0x31)
-    at middle (b[1]:0x29)
+    at b::middle (b[1]:0x29)
     at <unnamed> (b[2]:0x2e)"
     );
     Ok(())
@@ -393,6 +540,74 @@ fn rust_panic_start_function() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn downcast_host_errors() -> Result<()> {
+    #[derive(Debug, PartialEq)]
+    struct Rejected(u32);
+
+    impl std::fmt::Display for Rejected {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "rejected with code {}", self.0)
+        }
+    }
+
+    impl std::error::Error for Rejected {}
+
+    let store = get_store(false);
+    let wat = r#"
+        (module
+            (import "" "native" (func $native))
+            (import "" "dynamic" (func $dynamic))
+            (func (export "native") (call $native))
+            (func (export "dynamic") (call $dynamic))
+            (func (export "trap") unreachable)
+        )
+    "#;
+    let module = Module::new(&store, wat)?;
+    let dynamic = Function::new(&store, &FunctionType::new(vec![], vec![]), |_| {
+        Err(RuntimeError::user(Box::new(Rejected(2))))
+    });
+    let native = Function::new_native(&store, || -> Result<(), Rejected> { Err(Rejected(1)) });
+    let instance = Instance::new(
+        &module,
+        &imports! {
+            "" => {
+                "native" => native,
+                "dynamic" => dynamic,
+            }
+        },
+    )?;
+
+    let err = instance
+        .exports
+        .get_function("native")?
+        .call(&[])
+        .unwrap_err();
+    assert!(err.is::<Rejected>());
+    assert_eq!(err.downcast_ref::<Rejected>(), Some(&Rejected(1)));
+    assert_eq!(err.downcast::<Rejected>().ok(), Some(Rejected(1)));
+
+    let err = instance
+        .exports
+        .get_function("dynamic")?
+        .call(&[])
+        .unwrap_err();
+    let cloned = err.clone();
+    assert_eq!(cloned.downcast_ref::<Rejected>(), Some(&Rejected(2)));
+    assert_eq!(err.message(), "rejected with code 2");
+
+    let err = instance
+        .exports
+        .get_function("trap")?
+        .call(&[])
+        .unwrap_err();
+    assert!(!err.is::<Rejected>());
+    assert_eq!(err.downcast_ref::<Rejected>(), None);
+    assert!(err.downcast::<Rejected>().is_err());
+
+    Ok(())
+}
+
 #[test]
 fn mismatched_arguments() -> Result<()> {
     let store = get_store(false);
@@ -456,7 +671,7 @@ fn call_signature_mismatch() -> Result<()> {
         format!("{}", err),
         "\
 RuntimeError: indirect call type mismatch
-    at foo (a[0]:0x30)\
+    at a::foo (a[0]:0x30)\
 "
     );
     Ok(())
@@ -494,10 +709,10 @@ fn start_trap_pretty() -> Result<()> {
         format!("{}", err),
         "\
 RuntimeError: unreachable
-    at die (m[0]:0x1d)
+    at m::die (m[0]:0x1d)
     at <unnamed> (m[1]:0x21)
-    at foo (m[2]:0x26)
-    at start (m[3]:0x2b)\
+    at m::foo (m[2]:0x26)
+    at m::start (m[3]:0x2b)\
 "
     );
     Ok(())
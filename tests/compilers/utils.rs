@@ -64,6 +64,20 @@ pub fn get_store_with_middlewares<I: Iterator<Item = Arc<dyn ModuleMiddleware>>>
     Store::new(&engine)
 }
 
+pub fn get_deterministic_store_with_middlewares<I: Iterator<Item = Arc<dyn ModuleMiddleware>>>(
+    middlewares: I,
+) -> Store {
+    let mut compiler_config = get_compiler(true);
+    for x in middlewares {
+        compiler_config.push_middleware(x);
+    }
+    #[cfg(feature = "test-jit")]
+    let engine = JIT::new(compiler_config).engine();
+    #[cfg(feature = "test-native")]
+    let engine = Native::new(compiler_config).engine();
+    Store::new_deterministic(&engine)
+}
+
 #[cfg(feature = "test-jit")]
 pub fn get_headless_store() -> Store {
     Store::new(&JIT::headless().engine())
@@ -1,8 +1,14 @@
 #![cfg(all(feature = "compiler", feature = "engine"))]
 
-use crate::utils::get_store;
+use crate::utils::{get_deterministic_store_with_middlewares, get_store};
 use std::fs::File;
 use std::io::Read;
+use std::sync::Arc;
+use wasmer::wasmparser::Operator;
+use wasmer::{Instance, Module, ModuleMiddleware};
+use wasmer_middlewares::metering::{get_remaining_points, MeteringPoints};
+use wasmer_middlewares::Metering;
+use wasmer_wasi::WasiState;
 use wasmer_wast::WasiTest;
 
 // The generated tests (from build.rs) look like:
@@ -38,3 +44,1266 @@ pub fn run_wasi(wast_path: &str, base_dir: &str, compiler: &str) -> anyhow::Resu
 
     Ok(())
 }
+
+#[test]
+fn wasi_mem_fs() -> anyhow::Result<()> {
+    use wasmer::{Instance, Module};
+    use wasmer_wasi::{generate_import_object_from_env, MemFs, WasiState, WasiVersion};
+
+    let store = get_store(false);
+    let wat = r#"
+        (module
+          (import "wasi_snapshot_preview1" "path_open"
+            (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "fd_write"
+            (func $fd_write (param i32 i32 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "fd_close"
+            (func $fd_close (param i32) (result i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 0) "out.txt")
+          (data (i32.const 16) "hello")
+          (data (i32.const 32) "\10\00\00\00\05\00\00\00")
+          (func (export "_start")
+            ;; open `out.txt` in the first preopened directory, creating it
+            (if (call $path_open (i32.const 4) (i32.const 0) (i32.const 0) (i32.const 7)
+                  (i32.const 1) (i64.const -1) (i64.const -1) (i32.const 0) (i32.const 48))
+              (then unreachable))
+            (if (call $fd_write (i32.load (i32.const 48)) (i32.const 32) (i32.const 1) (i32.const 52))
+              (then unreachable))
+            (if (call $fd_close (i32.load (i32.const 48)))
+              (then unreachable))))
+    "#;
+    let module = Module::new(&store, wat)?;
+
+    let fs = MemFs::new();
+    fs.create_dir_all("/app")?;
+    let wasi_env = WasiState::new("mem_fs")
+        .fs_backend(Box::new(fs.clone()))
+        .map_dir("app", "/app")?
+        .finalize()?;
+    let import_object = generate_import_object_from_env(&store, wasi_env, WasiVersion::Snapshot1);
+    let instance = Instance::new(&module, &import_object)?;
+    instance.exports.get_function("_start")?.call(&[])?;
+
+    assert_eq!(fs.read_file("/app/out.txt")?, b"hello");
+    Ok(())
+}
+
+#[test]
+fn wasi_max_open_fds() -> anyhow::Result<()> {
+    use wasmer::{Instance, Module, Val};
+    use wasmer_wasi::types::{__WASI_EMFILE, __WASI_ESUCCESS};
+    use wasmer_wasi::{generate_import_object_from_env, MemFs, WasiState, WasiVersion};
+
+    let store = get_store(false);
+    let wat = r#"
+        (module
+          (import "wasi_snapshot_preview1" "path_open"
+            (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "fd_close"
+            (func $fd_close (param i32) (result i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 0) "out.txt")
+          (func (export "open") (result i32)
+            (call $path_open (i32.const 4) (i32.const 0) (i32.const 0) (i32.const 7)
+              (i32.const 1) (i64.const -1) (i64.const -1) (i32.const 0) (i32.const 48)))
+          (func (export "close") (result i32)
+            (call $fd_close (i32.load (i32.const 48)))))
+    "#;
+    let module = Module::new(&store, wat)?;
+
+    let fs = MemFs::new();
+    fs.create_dir_all("/app")?;
+    let wasi_env = WasiState::new("max_open_fds")
+        .fs_backend(Box::new(fs))
+        .map_dir("app", "/app")?
+        .max_open_fds(6)
+        .finalize()?;
+    let fds_before = wasi_env.open_fds().len();
+    let import_object =
+        generate_import_object_from_env(&store, wasi_env.clone(), WasiVersion::Snapshot1);
+    let instance = Instance::new(&module, &import_object)?;
+    let open = instance.exports.get_function("open")?;
+    let close = instance.exports.get_function("close")?;
+
+    assert_eq!(*open.call(&[])?, [Val::I32(__WASI_ESUCCESS as i32)]);
+    let open_fds = wasi_env.open_fds();
+    assert_eq!(open_fds.len(), fds_before + 1);
+    let opened = open_fds.last().unwrap();
+    assert_eq!(opened.name, "out.txt");
+    assert!(!opened.is_preopened);
+
+    assert_eq!(*open.call(&[])?, [Val::I32(__WASI_EMFILE as i32)]);
+    assert_eq!(*close.call(&[])?, [Val::I32(__WASI_ESUCCESS as i32)]);
+    assert_eq!(wasi_env.open_fds().len(), fds_before);
+    assert_eq!(*open.call(&[])?, [Val::I32(__WASI_ESUCCESS as i32)]);
+    Ok(())
+}
+
+#[test]
+fn wasi_jail() -> anyhow::Result<()> {
+    use wasmer::{Instance, Module, Val};
+    use wasmer_wasi::types::__WASI_ESUCCESS;
+    use wasmer_wasi::{generate_import_object_from_env, MemFs, WasiState, WasiVersion};
+
+    let store = get_store(false);
+    let wat = r#"
+        (module
+          (import "wasi_snapshot_preview1" "path_open"
+            (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "fd_prestat_dir_name"
+            (func $fd_prestat_dir_name (param i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 0) "/app/config/out.txt")
+          (data (i32.const 32) "../app/config/up.txt")
+          (func (export "open") (param i32 i32) (result i32)
+            ;; open a path in the first preopened directory, creating it
+            (call $path_open (i32.const 4) (i32.const 0) (local.get 0) (local.get 1)
+              (i32.const 1) (i64.const -1) (i64.const -1) (i32.const 0) (i32.const 96)))
+          (func (export "prestat_dir_name") (param i32) (result i32)
+            (call $fd_prestat_dir_name (local.get 0) (i32.const 64) (i32.const 32))))
+    "#;
+    let module = Module::new(&store, wat)?;
+
+    let fs = MemFs::new();
+    fs.create_dir_all("/srv/tenant/data")?;
+    fs.create_dir_all("/srv/config")?;
+    let instantiate = |jail: bool| -> anyhow::Result<Instance> {
+        let wasi_env = WasiState::new("jail")
+            .fs_backend(Box::new(fs.clone()))
+            .preopen_dir("/srv/tenant/data")?
+            .map_dir("app/config", "/srv/config")?
+            .jail(jail)
+            .finalize()?;
+        let import_object =
+            generate_import_object_from_env(&store, wasi_env, WasiVersion::Snapshot1);
+        Ok(Instance::new(&module, &import_object)?)
+    };
+
+    // absolute paths can't escape the preopened directories without a jail
+    let instance = instantiate(false)?;
+    let open = instance.exports.get_function("open")?;
+    assert_ne!(
+        *open.call(&[Val::I32(0), Val::I32(19)])?,
+        [Val::I32(__WASI_ESUCCESS as i32)]
+    );
+    assert!(fs.read_file("/srv/config/out.txt").is_err());
+
+    let instance = instantiate(true)?;
+    let open = instance.exports.get_function("open")?;
+    let prestat_dir_name = instance.exports.get_function("prestat_dir_name")?;
+    let memory = instance.exports.get_memory("memory")?;
+    let dir_name = |fd: i32| -> anyhow::Result<String> {
+        assert_eq!(
+            *prestat_dir_name.call(&[Val::I32(fd)])?,
+            [Val::I32(__WASI_ESUCCESS as i32)]
+        );
+        let name = memory.view::<u8>()[64..96]
+            .iter()
+            .map(|c| c.get())
+            .take_while(|c| *c != 0)
+            .collect();
+        Ok(String::from_utf8(name)?)
+    };
+    assert_eq!(dir_name(4)?, "data");
+    assert_eq!(dir_name(5)?, "app/config");
+
+    assert_eq!(
+        *open.call(&[Val::I32(0), Val::I32(19)])?,
+        [Val::I32(__WASI_ESUCCESS as i32)]
+    );
+    assert_eq!(
+        *open.call(&[Val::I32(32), Val::I32(20)])?,
+        [Val::I32(__WASI_ESUCCESS as i32)]
+    );
+    assert!(fs.read_file("/srv/config/out.txt").is_ok());
+    assert!(fs.read_file("/srv/config/up.txt").is_ok());
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn wasi_symlink_policy() -> anyhow::Result<()> {
+    use std::os::unix::fs::symlink;
+    use wasmer::{Instance, Module, Val};
+    use wasmer_wasi::types::{__WASI_ENOTCAPABLE, __WASI_ESUCCESS};
+    use wasmer_wasi::{generate_import_object_from_env, SymlinkPolicy, WasiState, WasiVersion};
+
+    let store = get_store(false);
+    let wat = r#"
+        (module
+          (import "wasi_snapshot_preview1" "path_open"
+            (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 0) "inside")
+          (data (i32.const 16) "outside")
+          (data (i32.const 32) "absolute")
+          (data (i32.const 48) "escape")
+          (func (export "open") (param i32 i32) (result i32)
+            ;; open a path in the first preopened directory, following symlinks
+            (call $path_open (i32.const 4) (i32.const 1) (local.get 0) (local.get 1)
+              (i32.const 0) (i64.const -1) (i64.const -1) (i32.const 0) (i32.const 96))))
+    "#;
+    let module = Module::new(&store, wat)?;
+
+    let dir = tempfile::tempdir()?;
+    let app = dir.path().join("app");
+    let other = dir.path().join("other");
+    std::fs::create_dir(&app)?;
+    std::fs::create_dir(&other)?;
+    std::fs::write(app.join("data.txt"), b"data")?;
+    std::fs::write(other.join("secret.txt"), b"secret")?;
+    symlink("data.txt", app.join("inside"))?;
+    symlink("../other/secret.txt", app.join("outside"))?;
+    // absolute symlinks to the host, inside and outside of the preopened directories
+    std::fs::write(dir.path().join("hidden.txt"), b"hidden")?;
+    symlink(other.join("secret.txt"), app.join("absolute"))?;
+    symlink(dir.path().join("hidden.txt"), app.join("escape"))?;
+
+    let open = |policy: SymlinkPolicy, path: (i32, i32)| -> anyhow::Result<u16> {
+        let wasi_env = WasiState::new("symlink_policy")
+            .map_dir("app", &app)?
+            .map_dir("other", &other)?
+            .symlink_policy(policy)
+            .finalize()?;
+        let import_object =
+            generate_import_object_from_env(&store, wasi_env, WasiVersion::Snapshot1);
+        let instance = Instance::new(&module, &import_object)?;
+        let result = instance
+            .exports
+            .get_function("open")?
+            .call(&[Val::I32(path.0), Val::I32(path.1)])?;
+        Ok(result[0].unwrap_i32() as u16)
+    };
+    let inside = (0, 6);
+    let outside = (16, 7);
+    let absolute = (32, 8);
+    let escape = (48, 6);
+
+    assert_eq!(open(SymlinkPolicy::FollowAll, inside)?, __WASI_ESUCCESS);
+    assert_eq!(open(SymlinkPolicy::FollowAll, outside)?, __WASI_ESUCCESS);
+    assert_eq!(
+        open(SymlinkPolicy::FollowWithinPreopen, inside)?,
+        __WASI_ESUCCESS
+    );
+    assert_eq!(
+        open(SymlinkPolicy::FollowWithinPreopen, outside)?,
+        __WASI_ENOTCAPABLE
+    );
+    assert_eq!(open(SymlinkPolicy::Deny, inside)?, __WASI_ENOTCAPABLE);
+
+    assert_eq!(open(SymlinkPolicy::FollowAll, absolute)?, __WASI_ESUCCESS);
+    assert_eq!(open(SymlinkPolicy::FollowAll, escape)?, __WASI_ENOTCAPABLE);
+    assert_eq!(
+        open(SymlinkPolicy::FollowWithinPreopen, absolute)?,
+        __WASI_ENOTCAPABLE
+    );
+    assert_eq!(open(SymlinkPolicy::Deny, absolute)?, __WASI_ENOTCAPABLE);
+    Ok(())
+}
+
+#[test]
+fn wasi_tracer() -> anyhow::Result<()> {
+    use std::sync::{Arc, Mutex};
+    use wasmer::{Instance, Module, Val};
+    use wasmer_wasi::types::{__wasi_errno_t, __WASI_EBADF, __WASI_ESUCCESS};
+    use wasmer_wasi::{generate_import_object_from_env, WasiState, WasiTracer, WasiVersion};
+
+    #[derive(Debug, Default, Clone)]
+    struct Recorder(Arc<Mutex<Vec<(String, Vec<i64>, Option<__wasi_errno_t>)>>>);
+
+    impl WasiTracer for Recorder {
+        fn after_call(&self, name: &str, args: &[Val], errno: Option<__wasi_errno_t>) {
+            let args = args
+                .iter()
+                .map(|arg| arg.i64().unwrap_or_else(|| arg.unwrap_i32().into()));
+            self.0
+                .lock()
+                .unwrap()
+                .push((name.to_string(), args.collect(), errno));
+        }
+    }
+
+    let store = get_store(false);
+    let wat = r#"
+        (module
+          (import "wasi_snapshot_preview1" "args_sizes_get"
+            (func $args_sizes_get (param i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "path_open"
+            (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (func (export "_start")
+            (if (call $args_sizes_get (i32.const 0) (i32.const 4))
+              (then unreachable))
+            (if (i32.ne (call $path_open (i32.const 99) (i32.const 0) (i32.const 16) (i32.const 1)
+                  (i32.const 0) (i64.const 2) (i64.const 0) (i32.const 0) (i32.const 8))
+                  (i32.const 8))
+              (then unreachable))))
+    "#;
+    let module = Module::new(&store, wat)?;
+
+    let recorder = Recorder::default();
+    let wasi_env = WasiState::new("tracer")
+        .tracer(Box::new(recorder.clone()))
+        .finalize()?;
+    let import_object = generate_import_object_from_env(&store, wasi_env, WasiVersion::Snapshot1);
+    let instance = Instance::new(&module, &import_object)?;
+    instance.exports.get_function("_start")?.call(&[])?;
+
+    let calls = recorder.0.lock().unwrap();
+    assert_eq!(
+        *calls,
+        vec![
+            (
+                "args_sizes_get".to_string(),
+                vec![0, 4],
+                Some(__WASI_ESUCCESS)
+            ),
+            (
+                "path_open".to_string(),
+                vec![99, 0, 16, 1, 0, 2, 0, 0, 8],
+                Some(__WASI_EBADF)
+            ),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn wasi_syscall_policy() -> anyhow::Result<()> {
+    use wasmer::{Instance, Module, Val};
+    use wasmer_wasi::types::{__WASI_ENOTCAPABLE, __WASI_ESUCCESS, __WASI_O_CREAT};
+    use wasmer_wasi::{generate_import_object_from_env, MemFs, WasiState, WasiVersion};
+
+    let store = get_store(false);
+    let wat = r#"
+        (module
+          (import "wasi_snapshot_preview1" "path_open"
+            (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 0) "out.txt")
+          (func (export "open") (param i32) (result i32)
+            (call $path_open (i32.const 4) (i32.const 0) (i32.const 0) (i32.const 7)
+              (local.get 0) (i64.const -1) (i64.const -1) (i32.const 0) (i32.const 48))))
+    "#;
+    let module = Module::new(&store, wat)?;
+
+    let fs = MemFs::new();
+    fs.create_dir_all("/app")?;
+    let wasi_env = WasiState::new("syscall_policy")
+        .fs_backend(Box::new(fs.clone()))
+        .map_dir("app", "/app")?
+        // the module can't create files
+        .syscall_policy(|name: &str, args: &[Val]| match (name, args.get(4)) {
+            ("path_open", Some(Val::I32(o_flags))) => *o_flags as u16 & __WASI_O_CREAT == 0,
+            _ => true,
+        })
+        .finalize()?;
+    let import_object = generate_import_object_from_env(&store, wasi_env, WasiVersion::Snapshot1);
+    let instance = Instance::new(&module, &import_object)?;
+    let open = instance.exports.get_function("open")?;
+
+    assert_eq!(
+        *open.call(&[Val::I32(__WASI_O_CREAT as i32)])?,
+        [Val::I32(__WASI_ENOTCAPABLE as i32)]
+    );
+    assert!(fs.read_file("/app/out.txt").is_err());
+
+    fs.write_file("/app/out.txt", b"hello")?;
+    assert_eq!(
+        *open.call(&[Val::I32(0)])?,
+        [Val::I32(__WASI_ESUCCESS as i32)]
+    );
+    Ok(())
+}
+
+#[test]
+fn wasi_read_only_preopen() -> anyhow::Result<()> {
+    use wasmer::{Instance, Module, Val};
+    use wasmer_wasi::types::{__WASI_EACCES, __WASI_ESUCCESS};
+    use wasmer_wasi::{generate_import_object_from_env, MemFs, WasiState, WasiVersion};
+
+    let store = get_store(false);
+    let wat = r#"
+        (module
+          (import "wasi_snapshot_preview1" "path_open"
+            (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "path_unlink_file"
+            (func $path_unlink_file (param i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 0) "config")
+          (data (i32.const 16) "../etc/config")
+          (data (i32.const 32) "new")
+          ;; the read-only directory is fd 4, the writable one is fd 5
+          (func (export "read_config") (result i32)
+            (call $path_open (i32.const 4) (i32.const 0) (i32.const 0) (i32.const 6)
+              (i32.const 0) (i64.const -1) (i64.const -1) (i32.const 0) (i32.const 64)))
+          (func (export "truncate_config") (result i32)
+            (call $path_open (i32.const 4) (i32.const 0) (i32.const 0) (i32.const 6)
+              (i32.const 8) (i64.const -1) (i64.const -1) (i32.const 0) (i32.const 64)))
+          (func (export "truncate_config_from_tmp") (result i32)
+            (call $path_open (i32.const 5) (i32.const 0) (i32.const 16) (i32.const 13)
+              (i32.const 8) (i64.const -1) (i64.const -1) (i32.const 0) (i32.const 64)))
+          (func (export "unlink_config") (result i32)
+            (call $path_unlink_file (i32.const 4) (i32.const 0) (i32.const 6)))
+          (func (export "unlink_config_from_tmp") (result i32)
+            (call $path_unlink_file (i32.const 5) (i32.const 16) (i32.const 13)))
+          (func (export "create_in_etc") (result i32)
+            (call $path_open (i32.const 4) (i32.const 0) (i32.const 32) (i32.const 3)
+              (i32.const 1) (i64.const -1) (i64.const -1) (i32.const 0) (i32.const 64)))
+          (func (export "create_in_tmp") (result i32)
+            (call $path_open (i32.const 5) (i32.const 0) (i32.const 32) (i32.const 3)
+              (i32.const 1) (i64.const -1) (i64.const -1) (i32.const 0) (i32.const 64))))
+    "#;
+    let module = Module::new(&store, wat)?;
+
+    let fs = MemFs::new();
+    fs.create_dir_all("/etc")?;
+    fs.create_dir_all("/tmp")?;
+    fs.write_file("/etc/config", b"answer = 42")?;
+    let wasi_env = WasiState::new("read_only")
+        .fs_backend(Box::new(fs.clone()))
+        .preopen(|p| p.directory("/etc").alias("etc").read(true))?
+        .preopen(|p| {
+            p.directory("/tmp")
+                .alias("tmp")
+                .read(true)
+                .create(true)
+                .delete(true)
+        })?
+        .finalize()?;
+    let import_object = generate_import_object_from_env(&store, wasi_env, WasiVersion::Snapshot1);
+    let instance = Instance::new(&module, &import_object)?;
+    let call = |name: &str| -> anyhow::Result<i32> {
+        match &*instance.exports.get_function(name)?.call(&[])? {
+            [Val::I32(errno)] => Ok(*errno),
+            results => panic!("unexpected results {:?}", results),
+        }
+    };
+
+    assert_eq!(call("read_config")?, __WASI_ESUCCESS as i32);
+    assert_eq!(call("truncate_config")?, __WASI_EACCES as i32);
+    assert_eq!(call("truncate_config_from_tmp")?, __WASI_EACCES as i32);
+    assert_eq!(call("unlink_config")?, __WASI_EACCES as i32);
+    assert_eq!(call("unlink_config_from_tmp")?, __WASI_EACCES as i32);
+    assert_eq!(call("create_in_etc")?, __WASI_EACCES as i32);
+    assert_eq!(call("create_in_tmp")?, __WASI_ESUCCESS as i32);
+
+    assert_eq!(fs.read_file("/etc/config")?, b"answer = 42");
+    assert!(fs.read_file("/etc/new").is_err());
+    assert!(fs.read_file("/tmp/new").is_ok());
+    Ok(())
+}
+
+#[test]
+fn wasi_fs_quota() -> anyhow::Result<()> {
+    use wasmer::{Instance, Module, Val};
+    use wasmer_wasi::types::{__WASI_ENOSPC, __WASI_ESUCCESS};
+    use wasmer_wasi::{generate_import_object_from_env, MemFs, WasiState, WasiVersion};
+
+    let store = get_store(false);
+    let wat = r#"
+        (module
+          (import "wasi_snapshot_preview1" "path_open"
+            (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "path_create_directory"
+            (func $path_create_directory (param i32 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "fd_write"
+            (func $fd_write (param i32 i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 0) "a")
+          (data (i32.const 8) "b")
+          (data (i32.const 16) "hello")
+          (data (i32.const 32) "\10\00\00\00\05\00\00\00")
+          (func (export "create_a") (result i32)
+            (call $path_open (i32.const 4) (i32.const 0) (i32.const 0) (i32.const 1)
+              (i32.const 1) (i64.const -1) (i64.const -1) (i32.const 0) (i32.const 64)))
+          (func (export "create_b") (result i32)
+            (call $path_open (i32.const 4) (i32.const 0) (i32.const 8) (i32.const 1)
+              (i32.const 1) (i64.const -1) (i64.const -1) (i32.const 0) (i32.const 68)))
+          (func (export "create_dir") (result i32)
+            (call $path_create_directory (i32.const 4) (i32.const 8) (i32.const 1)))
+          (func (export "write_a") (result i32)
+            (call $fd_write (i32.load (i32.const 64)) (i32.const 32) (i32.const 1) (i32.const 72))))
+    "#;
+    let module = Module::new(&store, wat)?;
+
+    let fs = MemFs::new();
+    fs.create_dir_all("/data")?;
+    let wasi_env = WasiState::new("quota")
+        .fs_backend(Box::new(fs.clone()))
+        .map_dir("data", "/data")?
+        .max_bytes_written(8)
+        .max_files(1)
+        .finalize()?;
+    let import_object = generate_import_object_from_env(&store, wasi_env, WasiVersion::Snapshot1);
+    let instance = Instance::new(&module, &import_object)?;
+    let call = |name: &str| -> anyhow::Result<i32> {
+        match &*instance.exports.get_function(name)?.call(&[])? {
+            [Val::I32(errno)] => Ok(*errno),
+            results => panic!("unexpected results {:?}", results),
+        }
+    };
+
+    assert_eq!(call("create_a")?, __WASI_ESUCCESS as i32);
+    assert_eq!(call("create_b")?, __WASI_ENOSPC as i32);
+    assert_eq!(call("create_dir")?, __WASI_ENOSPC as i32);
+    assert_eq!(call("write_a")?, __WASI_ESUCCESS as i32);
+    assert_eq!(call("write_a")?, __WASI_ENOSPC as i32);
+
+    assert_eq!(fs.read_file("/data/a")?, b"hello");
+    assert!(fs.read_file("/data/b").is_err());
+    Ok(())
+}
+
+#[test]
+fn wasi_fs_quota_file_growth() -> anyhow::Result<()> {
+    use wasmer::{Instance, Module, Val};
+    use wasmer_wasi::types::{__WASI_ENOSPC, __WASI_ESUCCESS};
+    use wasmer_wasi::{generate_import_object_from_env, MemFs, WasiState, WasiVersion};
+
+    let store = get_store(false);
+    let wat = r#"
+        (module
+          (import "wasi_snapshot_preview1" "path_open"
+            (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "fd_allocate"
+            (func $fd_allocate (param i32 i64 i64) (result i32)))
+          (import "wasi_snapshot_preview1" "fd_filestat_set_size"
+            (func $fd_filestat_set_size (param i32 i64) (result i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 0) "a")
+          (func (export "create_a") (result i32)
+            (call $path_open (i32.const 4) (i32.const 0) (i32.const 0) (i32.const 1)
+              (i32.const 1) (i64.const -1) (i64.const -1) (i32.const 0) (i32.const 64)))
+          (func (export "allocate") (param i64) (result i32)
+            (call $fd_allocate (i32.load (i32.const 64)) (i64.const 0) (local.get 0)))
+          (func (export "set_size") (param i64) (result i32)
+            (call $fd_filestat_set_size (i32.load (i32.const 64)) (local.get 0))))
+    "#;
+    let module = Module::new(&store, wat)?;
+
+    let fs = MemFs::new();
+    fs.create_dir_all("/data")?;
+    let wasi_env = WasiState::new("quota")
+        .fs_backend(Box::new(fs.clone()))
+        .map_dir("data", "/data")?
+        .max_bytes_written(8)
+        .finalize()?;
+    let import_object = generate_import_object_from_env(&store, wasi_env, WasiVersion::Snapshot1);
+    let instance = Instance::new(&module, &import_object)?;
+    let call = |name: &str, params: &[Val]| -> anyhow::Result<i32> {
+        match &*instance.exports.get_function(name)?.call(params)? {
+            [Val::I32(errno)] => Ok(*errno),
+            results => panic!("unexpected results {:?}", results),
+        }
+    };
+
+    assert_eq!(call("create_a", &[])?, __WASI_ESUCCESS as i32);
+    assert_eq!(call("allocate", &[Val::I64(6)])?, __WASI_ESUCCESS as i32);
+    assert_eq!(call("allocate", &[Val::I64(9)])?, __WASI_ENOSPC as i32);
+    assert_eq!(
+        call("set_size", &[Val::I64(1 << 40)])?,
+        __WASI_ENOSPC as i32
+    );
+    // Shrinking a file is free, and growing it back is charged again.
+    assert_eq!(call("set_size", &[Val::I64(4)])?, __WASI_ESUCCESS as i32);
+    assert_eq!(call("set_size", &[Val::I64(6)])?, __WASI_ESUCCESS as i32);
+    assert_eq!(call("allocate", &[Val::I64(7)])?, __WASI_ENOSPC as i32);
+
+    assert_eq!(fs.read_file("/data/a")?.len(), 6);
+    Ok(())
+}
+
+#[test]
+fn wasi_fs_quota_links() -> anyhow::Result<()> {
+    use wasmer::{Instance, Module, Val};
+    use wasmer_wasi::types::{__WASI_ENOSPC, __WASI_ESUCCESS};
+    use wasmer_wasi::{generate_import_object_from_env, MemFs, WasiState, WasiVersion};
+
+    let store = get_store(false);
+    let wat = r#"
+        (module
+          (import "wasi_snapshot_preview1" "path_open"
+            (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "path_link"
+            (func $path_link (param i32 i32 i32 i32 i32 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "path_symlink"
+            (func $path_symlink (param i32 i32 i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 0) "abcde")
+          (func (export "create_a") (result i32)
+            (call $path_open (i32.const 4) (i32.const 0) (i32.const 0) (i32.const 1)
+              (i32.const 1) (i64.const -1) (i64.const -1) (i32.const 0) (i32.const 64)))
+          (func (export "link") (param i32) (result i32)
+            (call $path_link (i32.const 4) (i32.const 0) (i32.const 0) (i32.const 1)
+              (i32.const 4) (local.get 0) (i32.const 1)))
+          (func (export "symlink") (param i32) (result i32)
+            (call $path_symlink (i32.const 0) (i32.const 1)
+              (i32.const 4) (local.get 0) (i32.const 1))))
+    "#;
+    let module = Module::new(&store, wat)?;
+
+    let fs = MemFs::new();
+    fs.create_dir_all("/data")?;
+    let wasi_env = WasiState::new("quota")
+        .fs_backend(Box::new(fs.clone()))
+        .map_dir("data", "/data")?
+        .max_files(3)
+        .finalize()?;
+    let import_object = generate_import_object_from_env(&store, wasi_env, WasiVersion::Snapshot1);
+    let instance = Instance::new(&module, &import_object)?;
+    let call = |name: &str, params: &[Val]| -> anyhow::Result<i32> {
+        match &*instance.exports.get_function(name)?.call(params)? {
+            [Val::I32(errno)] => Ok(*errno),
+            results => panic!("unexpected results {:?}", results),
+        }
+    };
+
+    // The names of the new entries are the characters at these offsets.
+    let (b, c, d, e) = (Val::I32(1), Val::I32(2), Val::I32(3), Val::I32(4));
+    assert_eq!(call("create_a", &[])?, __WASI_ESUCCESS as i32);
+    assert_eq!(call("link", &[b])?, __WASI_ESUCCESS as i32);
+    assert_eq!(call("symlink", &[c])?, __WASI_ESUCCESS as i32);
+    assert_eq!(call("link", &[d])?, __WASI_ENOSPC as i32);
+    assert_eq!(call("symlink", &[e])?, __WASI_ENOSPC as i32);
+    Ok(())
+}
+
+#[test]
+fn wasi_exit_code() -> anyhow::Result<()> {
+    use std::sync::{Arc, Mutex};
+    use wasmer::{Instance, Module};
+    use wasmer_wasi::{generate_import_object_from_env, WasiError, WasiState, WasiVersion};
+
+    let store = get_store(false);
+    let wat = r#"
+        (module
+          (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+          (memory (export "memory") 1)
+          (func (export "_start")
+            (call $proc_exit (i32.const 3)))
+          (func (export "crash")
+            unreachable))
+    "#;
+    let module = Module::new(&store, wat)?;
+
+    let exit_codes = Arc::new(Mutex::new(Vec::new()));
+    let hook_exit_codes = exit_codes.clone();
+    let wasi_env = WasiState::new("exit")
+        .on_exit(move |code| hook_exit_codes.lock().unwrap().push(code))
+        .finalize()?;
+    let import_object = generate_import_object_from_env(&store, wasi_env, WasiVersion::Snapshot1);
+    let instance = Instance::new(&module, &import_object)?;
+
+    let error = instance
+        .exports
+        .get_function("_start")?
+        .call(&[])
+        .unwrap_err();
+    assert_eq!(WasiError::exit_code(&error), Some(3));
+    let error = instance
+        .exports
+        .get_function("crash")?
+        .call(&[])
+        .unwrap_err();
+    assert_eq!(WasiError::exit_code(&error), None);
+    assert_eq!(*exit_codes.lock().unwrap(), vec![3]);
+    Ok(())
+}
+
+#[test]
+fn wasi_update_env_and_args() -> anyhow::Result<()> {
+    use wasmer::{Instance, Module, Val};
+    use wasmer_wasi::{generate_import_object_from_env, WasiState, WasiVersion};
+
+    let store = get_store(false);
+    let wat = r#"
+        (module
+          (import "wasi_snapshot_preview1" "args_sizes_get"
+            (func $args_sizes_get (param i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "environ_sizes_get"
+            (func $environ_sizes_get (param i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          ;; the number of arguments and their total size
+          (func (export "args") (result i32 i32)
+            (drop (call $args_sizes_get (i32.const 0) (i32.const 4)))
+            (i32.load (i32.const 0))
+            (i32.load (i32.const 4)))
+          ;; the number of environment variables and their total size
+          (func (export "envs") (result i32 i32)
+            (drop (call $environ_sizes_get (i32.const 0) (i32.const 4)))
+            (i32.load (i32.const 0))
+            (i32.load (i32.const 4))))
+    "#;
+    let module = Module::new(&store, wat)?;
+
+    let wasi_env = WasiState::new("prog")
+        .env("A", "1")
+        .arg("first")
+        .finalize()?;
+    let import_object =
+        generate_import_object_from_env(&store, wasi_env.clone(), WasiVersion::Snapshot1);
+    let instance = Instance::new(&module, &import_object)?;
+    let call = |name: &str| -> anyhow::Result<(i32, i32)> {
+        match &*instance.exports.get_function(name)?.call(&[])? {
+            [Val::I32(count), Val::I32(size)] => Ok((*count, *size)),
+            results => panic!("unexpected results {:?}", results),
+        }
+    };
+
+    assert_eq!(call("args")?, (2, 11));
+    assert_eq!(call("envs")?, (1, 4));
+
+    wasi_env.set_env("A", "123")?;
+    wasi_env.set_env("LONGER", "x")?;
+    wasi_env.set_args(&["a", "b", "c"])?;
+    assert_eq!(call("envs")?, (2, 15));
+    assert_eq!(call("args")?, (4, 11));
+
+    assert!(wasi_env.remove_env("A"));
+    assert!(!wasi_env.remove_env("A"));
+    assert_eq!(call("envs")?, (1, 9));
+
+    assert!(wasi_env.set_env("B=", "2").is_err());
+    assert!(wasi_env.set_args(&["nul\0"]).is_err());
+    assert_eq!(call("args")?, (4, 11));
+    Ok(())
+}
+
+#[test]
+fn wasi_poll_oneoff() -> anyhow::Result<()> {
+    use std::io::Write;
+    use std::time::{Duration, Instant};
+    use wasmer::{Instance, Module, Val};
+    use wasmer_wasi::types::__WASI_ESUCCESS;
+    use wasmer_wasi::{bounded_pipe, generate_import_object_from_env, WasiState, WasiVersion};
+
+    let store = get_store(false);
+    let wat = r#"
+        (module
+          (import "wasi_snapshot_preview1" "poll_oneoff"
+            (func $poll_oneoff (param i32 i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          ;; a relative timer of 30ms on the monotonic clock, with userdata 1
+          (data (i32.const 0) "\01\00\00\00\00\00\00\00\00")
+          (data (i32.const 16) "\01\00\00\00\00\00\00\00\80\c3\c9\01")
+          ;; a read of stdin, with userdata 2
+          (data (i32.const 48) "\02\00\00\00\00\00\00\00\01")
+          (func (export "poll") (result i32)
+            (call $poll_oneoff (i32.const 0) (i32.const 128) (i32.const 2) (i32.const 256))))
+    "#;
+    let module = Module::new(&store, wat)?;
+
+    let (mut stdin, stdin_reader) = bounded_pipe(16);
+    let wasi_env = WasiState::new("poll_oneoff")
+        .stdin(Box::new(stdin_reader))
+        .finalize()?;
+    let import_object = generate_import_object_from_env(&store, wasi_env, WasiVersion::Snapshot1);
+    let instance = Instance::new(&module, &import_object)?;
+    let poll = instance.exports.get_function("poll")?;
+    let memory = instance.exports.get_memory("memory")?;
+    let read_u64 = |offset: usize| {
+        let view = memory.view::<u8>();
+        let mut bytes = [0; 8];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = view[offset + i].get();
+        }
+        u64::from_le_bytes(bytes)
+    };
+
+    // nothing to read: the timer expires
+    let start = Instant::now();
+    assert_eq!(*poll.call(&[])?, [Val::I32(__WASI_ESUCCESS as i32)]);
+    assert!(start.elapsed() >= Duration::from_millis(30));
+    assert_eq!(read_u64(256) as u32, 1);
+    assert_eq!(read_u64(128), 1);
+
+    // stdin is ready before the timer expires
+    stdin.write_all(b"hi")?;
+    assert_eq!(*poll.call(&[])?, [Val::I32(__WASI_ESUCCESS as i32)]);
+    assert_eq!(read_u64(256) as u32, 1);
+    assert_eq!(read_u64(128), 2);
+    assert_eq!(read_u64(128 + 16), 2);
+    Ok(())
+}
+
+#[test]
+fn wasi_sockets() -> anyhow::Result<()> {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use wasmer::{Instance, Module, Val};
+    use wasmer_wasi::types::{__WASI_ENOTCAPABLE, __WASI_ESUCCESS};
+    use wasmer_wasi::{generate_import_object_from_env, WasiState, WasiVersion};
+
+    let store = get_store(false);
+    let wat = r#"
+        (module
+          (import "wasi_snapshot_preview1" "sock_connect"
+            (func $sock_connect (param i32 i32 i32 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "sock_send"
+            (func $sock_send (param i32 i32 i32 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "sock_recv"
+            (func $sock_recv (param i32 i32 i32 i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 0) "127.0.0.1")
+          (data (i32.const 16) "localhost")
+          (data (i32.const 32) "ping")
+          (data (i32.const 48) "\20\00\00\00\04\00\00\00")
+          (data (i32.const 80) "\40\00\00\00\10\00\00\00")
+          (func (export "connect") (param $host i32) (param $port i32) (result i32)
+            (call $sock_connect (local.get $host) (i32.const 9) (local.get $port) (i32.const 0)
+              (i32.const 96)))
+          (func (export "send") (result i32)
+            (call $sock_send (i32.load (i32.const 96)) (i32.const 48) (i32.const 1) (i32.const 0)
+              (i32.const 100)))
+          (func (export "recv") (result i32)
+            (call $sock_recv (i32.load (i32.const 96)) (i32.const 80) (i32.const 1) (i32.const 0)
+              (i32.const 100) (i32.const 104))))
+    "#;
+    let module = Module::new(&store, wat)?;
+
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let port = listener.local_addr()?.port();
+    let server = std::thread::spawn(move || -> std::io::Result<Vec<u8>> {
+        let (mut stream, _) = listener.accept()?;
+        let mut request = vec![0; 4];
+        stream.read_exact(&mut request)?;
+        stream.write_all(b"pong")?;
+        Ok(request)
+    });
+
+    let wasi_env = WasiState::new("sockets")
+        .allow_network(&format!("127.0.0.1:{}", port))?
+        .finalize()?;
+    let import_object = generate_import_object_from_env(&store, wasi_env, WasiVersion::Snapshot1);
+    let instance = Instance::new(&module, &import_object)?;
+    let call = |name: &str, params: &[Val]| -> anyhow::Result<i32> {
+        match &*instance.exports.get_function(name)?.call(params)? {
+            [Val::I32(errno)] => Ok(*errno),
+            results => panic!("unexpected results {:?}", results),
+        }
+    };
+
+    let (ip, localhost) = (Val::I32(0), Val::I32(16));
+    assert_eq!(
+        call("connect", &[localhost, Val::I32(port as i32)])?,
+        __WASI_ENOTCAPABLE as i32
+    );
+    assert_eq!(
+        call("connect", &[ip.clone(), Val::I32(port as i32 + 1)])?,
+        __WASI_ENOTCAPABLE as i32
+    );
+    assert_eq!(
+        call("connect", &[ip, Val::I32(port as i32)])?,
+        __WASI_ESUCCESS as i32
+    );
+    assert_eq!(call("send", &[])?, __WASI_ESUCCESS as i32);
+    assert_eq!(server.join().unwrap()?, b"ping");
+    assert_eq!(call("recv", &[])?, __WASI_ESUCCESS as i32);
+
+    let memory = instance.exports.get_memory("memory")?;
+    let view = memory.view::<u8>();
+    let received = view[64..68].iter().map(|b| b.get()).collect::<Vec<u8>>();
+    assert_eq!(received, b"pong");
+    assert_eq!(view[100].get(), 4);
+    Ok(())
+}
+
+#[cfg(feature = "test-jit")]
+#[test]
+fn wasi_threads() -> anyhow::Result<()> {
+    use crate::utils::get_compiler;
+    use wasmer::{Features, Instance, Module, Store};
+    use wasmer_engine_jit::JIT;
+    use wasmer_wasi::WasiState;
+
+    let mut features = Features::new();
+    features.threads(true);
+    let store = Store::new(&JIT::new(get_compiler(false)).features(features).engine());
+    let wat = r#"
+        (module
+          (import "env" "memory" (memory 1 1 shared))
+          (import "wasi" "thread-spawn" (func $thread_spawn (param i32) (result i32)))
+          (import "wasi_snapshot_preview1" "sched_yield" (func $sched_yield (result i32)))
+          (export "memory" (memory 0))
+          ;; spawn 3 threads, and keep their ids at 0, 4 and 8
+          (func (export "_start")
+            (i32.store (i32.const 0) (call $thread_spawn (i32.const 100)))
+            (i32.store (i32.const 4) (call $thread_spawn (i32.const 104)))
+            (i32.store (i32.const 8) (call $thread_spawn (i32.const 108))))
+          ;; each thread writes its id at its start argument
+          (func (export "wasi_thread_start") (param $tid i32) (param $start_arg i32)
+            (i32.store (local.get $start_arg) (local.get $tid))))
+    "#;
+    let module = Module::new(&store, wat)?;
+
+    let mut wasi_env = WasiState::new("threads").finalize()?;
+    let import_object = wasi_env.import_object_with_threads(&module)?;
+    let instance = Instance::new(&module, &import_object)?;
+    instance.exports.get_function("_start")?.call(&[])?;
+    wasi_env.join_threads()?;
+
+    let memory = instance.exports.get_memory("memory")?;
+    let read = |offset: u64| -> anyhow::Result<u32> {
+        let mut bytes = [0; 4];
+        memory.read(offset, &mut bytes)?;
+        Ok(u32::from_le_bytes(bytes))
+    };
+    let tids = [read(0)?, read(4)?, read(8)?];
+    assert_eq!(tids, [1, 2, 3]);
+    assert_eq!([read(100)?, read(104)?, read(108)?], tids);
+
+    let unshared = Module::new(
+        &store,
+        r#"(module
+             (import "env" "memory" (memory 1))
+             (import "wasi_snapshot_preview1" "sched_yield" (func (result i32))))"#,
+    )?;
+    assert!(wasi_env.import_object_with_threads(&unshared).is_err());
+    Ok(())
+}
+
+fn run_deterministic_wasi() -> anyhow::Result<(Vec<u8>, MeteringPoints)> {
+    let store = get_deterministic_store_with_middlewares(std::iter::once(Arc::new(Metering::new(
+        1_000_000,
+        |_: &Operator| 1,
+    ))
+        as Arc<dyn ModuleMiddleware>));
+    let wat = r#"(module
+        (import "wasi_snapshot_preview1" "clock_time_get"
+            (func $clock_time_get (param i32 i64 i32) (result i32)))
+        (import "wasi_snapshot_preview1" "random_get"
+            (func $random_get (param i32 i32) (result i32)))
+        (memory (export "memory") 1)
+        (func (export "run")
+            (drop (call $clock_time_get (i32.const 0) (i64.const 1) (i32.const 0)))
+            (drop (call $clock_time_get (i32.const 1) (i64.const 1) (i32.const 8)))
+            (drop (call $random_get (i32.const 16) (i32.const 32)))
+        )
+)"#;
+    let module = Module::new(&store, wat)?;
+    let mut wasi_env = WasiState::new("deterministic").finalize()?;
+    let import_object = wasi_env.import_object(&module)?;
+    let instance = Instance::new(&module, &import_object)?;
+
+    instance.exports.get_function("run")?.call(&[])?;
+
+    let memory = instance.exports.get_memory("memory")?;
+    let output = unsafe { memory.data_unchecked()[..48].to_vec() };
+    Ok((output, get_remaining_points(&instance)))
+}
+
+#[test]
+fn deterministic_wasi_runs_are_identical() -> anyhow::Result<()> {
+    let (first_output, first_points) = run_deterministic_wasi()?;
+    let (second_output, second_points) = run_deterministic_wasi()?;
+
+    // The clocks are frozen at 0 and the random bytes are seeded.
+    assert_eq!(&first_output[..16], &[0; 16]);
+    assert_ne!(&first_output[16..], &[0; 32]);
+    assert_eq!(first_output, second_output);
+    assert_eq!(first_points, MeteringPoints::Remaining(999_985));
+    assert_eq!(first_points, second_points);
+
+    Ok(())
+}
+
+/// Instantiate a module re-exporting the wasi-crypto imports, to call them
+/// with the memory layouts set up by the tests.
+#[cfg(feature = "wasi-crypto")]
+fn instantiate_wasi_crypto() -> anyhow::Result<Instance> {
+    use wasmer_wasi::{generate_import_object_from_env, WasiVersion};
+
+    let store = get_store(false);
+    let wat = r#"
+        (module
+          (import "wasi_ephemeral_crypto_common" "array_output_len"
+            (func $array_output_len (param i32 i32) (result i32)))
+          (import "wasi_ephemeral_crypto_common" "array_output_pull"
+            (func $array_output_pull (param i32 i32 i32 i32) (result i32)))
+          (import "wasi_ephemeral_crypto_common" "secrets_manager_open"
+            (func $secrets_manager_open (param i32 i32) (result i32)))
+          (import "wasi_ephemeral_crypto_asymmetric_common" "keypair_generate"
+            (func $keypair_generate (param i32 i32 i32 i32 i32) (result i32)))
+          (import "wasi_ephemeral_crypto_asymmetric_common" "keypair_store"
+            (func $keypair_store (param i32 i32 i32 i32) (result i32)))
+          (import "wasi_ephemeral_crypto_asymmetric_common" "keypair_id"
+            (func $keypair_id (param i32 i32 i32 i32 i32) (result i32)))
+          (import "wasi_ephemeral_crypto_asymmetric_common" "keypair_from_id"
+            (func $keypair_from_id (param i32 i32 i32 i64 i32) (result i32)))
+          (import "wasi_ephemeral_crypto_asymmetric_common" "keypair_publickey"
+            (func $keypair_publickey (param i32 i32) (result i32)))
+          (import "wasi_ephemeral_crypto_asymmetric_common" "keypair_export"
+            (func $keypair_export (param i32 i32 i32) (result i32)))
+          (import "wasi_ephemeral_crypto_signatures" "signature_export"
+            (func $signature_export (param i32 i32 i32) (result i32)))
+          (import "wasi_ephemeral_crypto_signatures" "signature_import"
+            (func $signature_import (param i32 i32 i32 i32 i32 i32) (result i32)))
+          (import "wasi_ephemeral_crypto_signatures" "signature_state_open"
+            (func $signature_state_open (param i32 i32) (result i32)))
+          (import "wasi_ephemeral_crypto_signatures" "signature_state_update"
+            (func $signature_state_update (param i32 i32 i32) (result i32)))
+          (import "wasi_ephemeral_crypto_signatures" "signature_state_sign"
+            (func $signature_state_sign (param i32 i32) (result i32)))
+          (import "wasi_ephemeral_crypto_signatures" "signature_verification_state_open"
+            (func $signature_verification_state_open (param i32 i32) (result i32)))
+          (import "wasi_ephemeral_crypto_signatures" "signature_verification_state_update"
+            (func $signature_verification_state_update (param i32 i32 i32) (result i32)))
+          (import "wasi_ephemeral_crypto_signatures" "signature_verification_state_verify"
+            (func $signature_verification_state_verify (param i32 i32) (result i32)))
+          (import "wasi_ephemeral_crypto_symmetric" "symmetric_state_open"
+            (func $symmetric_state_open (param i32 i32 i32 i32 i32) (result i32)))
+          (import "wasi_ephemeral_crypto_symmetric" "symmetric_state_absorb"
+            (func $symmetric_state_absorb (param i32 i32 i32) (result i32)))
+          (import "wasi_ephemeral_crypto_symmetric" "symmetric_state_squeeze"
+            (func $symmetric_state_squeeze (param i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          ;; an optional handle set to `none`
+          (data (i32.const 0) "\01")
+          (data (i32.const 16) "Ed25519")
+          (data (i32.const 32) "SHA-256")
+          (data (i32.const 48) "message")
+          (export "array_output_len" (func $array_output_len))
+          (export "array_output_pull" (func $array_output_pull))
+          (export "secrets_manager_open" (func $secrets_manager_open))
+          (export "keypair_generate" (func $keypair_generate))
+          (export "keypair_store" (func $keypair_store))
+          (export "keypair_id" (func $keypair_id))
+          (export "keypair_from_id" (func $keypair_from_id))
+          (export "keypair_publickey" (func $keypair_publickey))
+          (export "keypair_export" (func $keypair_export))
+          (export "signature_export" (func $signature_export))
+          (export "signature_import" (func $signature_import))
+          (export "signature_state_open" (func $signature_state_open))
+          (export "signature_state_update" (func $signature_state_update))
+          (export "signature_state_sign" (func $signature_state_sign))
+          (export "signature_verification_state_open" (func $signature_verification_state_open))
+          (export "signature_verification_state_update" (func $signature_verification_state_update))
+          (export "signature_verification_state_verify" (func $signature_verification_state_verify))
+          (export "symmetric_state_open" (func $symmetric_state_open))
+          (export "symmetric_state_absorb" (func $symmetric_state_absorb))
+          (export "symmetric_state_squeeze" (func $symmetric_state_squeeze)))
+    "#;
+    let module = Module::new(&store, wat)?;
+    let wasi_env = WasiState::new("crypto").finalize()?;
+    let import_object = generate_import_object_from_env(&store, wasi_env, WasiVersion::Snapshot1);
+    Ok(Instance::new(&module, &import_object)?)
+}
+
+/// Call a wasi-crypto import re-exported by `instance` with `i32` params,
+/// returning its errno.
+#[cfg(feature = "wasi-crypto")]
+fn call_wasi_crypto(instance: &Instance, name: &str, params: &[i32]) -> anyhow::Result<u16> {
+    use wasmer::Val;
+
+    let params = params
+        .iter()
+        .map(|param| Val::I32(*param))
+        .collect::<Vec<_>>();
+    let results = instance.exports.get_function(name)?.call(&params)?;
+    Ok(results[0].unwrap_i32() as u16)
+}
+
+#[cfg(feature = "wasi-crypto")]
+#[test]
+fn wasi_crypto_hash() -> anyhow::Result<()> {
+    use wasmer_wasi::crypto::{__WASI_CRYPTO_EINVALID_LENGTH, __WASI_CRYPTO_ESUCCESS};
+
+    let instance = instantiate_wasi_crypto()?;
+    let memory = instance.exports.get_memory("memory")?;
+    let call = |name: &str, params: &[i32]| call_wasi_crypto(&instance, name, params);
+    let read_u32 = |offset: usize| -> u32 {
+        let view = memory.view::<u8>();
+        let mut bytes = [0; 4];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = view[offset + i].get();
+        }
+        u32::from_le_bytes(bytes)
+    };
+
+    // SHA-256("message"), absorbed in two parts
+    assert_eq!(
+        call("symmetric_state_open", &[32, 7, 0, 0, 64])?,
+        __WASI_CRYPTO_ESUCCESS
+    );
+    let state = read_u32(64) as i32;
+    assert_eq!(
+        call("symmetric_state_absorb", &[state, 48, 3])?,
+        __WASI_CRYPTO_ESUCCESS
+    );
+    assert_eq!(
+        call("symmetric_state_absorb", &[state, 51, 4])?,
+        __WASI_CRYPTO_ESUCCESS
+    );
+    assert_eq!(
+        call("symmetric_state_squeeze", &[state, 128, 32])?,
+        __WASI_CRYPTO_ESUCCESS
+    );
+    let hash = memory.view::<u8>()[128..160]
+        .iter()
+        .map(|c| c.get())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        hash,
+        [
+            0xab, 0x53, 0x0a, 0x13, 0xe4, 0x59, 0x14, 0x98, 0x2b, 0x79, 0xf9, 0xb7, 0xe3, 0xfb,
+            0xa9, 0x94, 0xcf, 0xd1, 0xf3, 0xfb, 0x22, 0xf7, 0x1c, 0xea, 0x1a, 0xfb, 0xf0, 0x2b,
+            0x46, 0x0c, 0x6d, 0x1d
+        ]
+    );
+    // a SHA-256 hash is only 32 bytes long
+    assert_eq!(
+        call("symmetric_state_squeeze", &[state, 128, 33])?,
+        __WASI_CRYPTO_EINVALID_LENGTH
+    );
+    Ok(())
+}
+
+#[cfg(feature = "wasi-crypto")]
+#[test]
+fn wasi_crypto_sign_and_verify() -> anyhow::Result<()> {
+    use wasmer::Val;
+    use wasmer_wasi::crypto::{
+        __WASI_CRYPTO_ALGORITHM_TYPE_SIGNATURES, __WASI_CRYPTO_EPROHIBITED_OPERATION,
+        __WASI_CRYPTO_ESUCCESS, __WASI_CRYPTO_EVERIFICATION_FAILED,
+        __WASI_CRYPTO_KEYPAIR_ENCODING_PKCS8, __WASI_CRYPTO_SIGNATURE_ENCODING_RAW,
+    };
+
+    let instance = instantiate_wasi_crypto()?;
+    let memory = instance.exports.get_memory("memory")?;
+    let call = |name: &str, params: &[i32]| call_wasi_crypto(&instance, name, params);
+    let read_u32 = |offset: usize| -> u32 {
+        let view = memory.view::<u8>();
+        let mut bytes = [0; 4];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = view[offset + i].get();
+        }
+        u32::from_le_bytes(bytes)
+    };
+    let signatures = i32::from(__WASI_CRYPTO_ALGORITHM_TYPE_SIGNATURES);
+    let raw = i32::from(__WASI_CRYPTO_SIGNATURE_ENCODING_RAW);
+
+    // sign the message with a key pair, returning the signature handle
+    let sign = |keypair: i32, message_len: i32| -> anyhow::Result<i32> {
+        assert_eq!(
+            call("signature_state_open", &[keypair, 64])?,
+            __WASI_CRYPTO_ESUCCESS
+        );
+        let state = read_u32(64) as i32;
+        assert_eq!(
+            call("signature_state_update", &[state, 48, message_len])?,
+            __WASI_CRYPTO_ESUCCESS
+        );
+        assert_eq!(
+            call("signature_state_sign", &[state, 64])?,
+            __WASI_CRYPTO_ESUCCESS
+        );
+        Ok(read_u32(64) as i32)
+    };
+    // verify the signature of the message with a public key, returning the errno
+    let verify = |publickey: i32, message_len: i32, signature: i32| -> anyhow::Result<u16> {
+        assert_eq!(
+            call("signature_verification_state_open", &[publickey, 64])?,
+            __WASI_CRYPTO_ESUCCESS
+        );
+        let state = read_u32(64) as i32;
+        assert_eq!(
+            call(
+                "signature_verification_state_update",
+                &[state, 48, message_len]
+            )?,
+            __WASI_CRYPTO_ESUCCESS
+        );
+        call("signature_verification_state_verify", &[state, signature])
+    };
+
+    assert_eq!(
+        call("keypair_generate", &[signatures, 16, 7, 0, 64])?,
+        __WASI_CRYPTO_ESUCCESS
+    );
+    let keypair = read_u32(64) as i32;
+    assert_eq!(
+        call("keypair_publickey", &[keypair, 64])?,
+        __WASI_CRYPTO_ESUCCESS
+    );
+    let publickey = read_u32(64) as i32;
+
+    let signature = sign(keypair, 7)?;
+    assert_eq!(verify(publickey, 7, signature)?, __WASI_CRYPTO_ESUCCESS);
+    assert_eq!(
+        verify(publickey, 6, signature)?,
+        __WASI_CRYPTO_EVERIFICATION_FAILED
+    );
+
+    // export the raw signature and import it back
+    assert_eq!(
+        call("signature_export", &[signature, raw, 64])?,
+        __WASI_CRYPTO_ESUCCESS
+    );
+    let array_output = read_u32(64) as i32;
+    assert_eq!(
+        call("array_output_len", &[array_output, 64])?,
+        __WASI_CRYPTO_ESUCCESS
+    );
+    assert_eq!(read_u32(64), 64);
+    assert_eq!(
+        call("array_output_pull", &[array_output, 256, 64, 64])?,
+        __WASI_CRYPTO_ESUCCESS
+    );
+    assert_eq!(
+        call("signature_import", &[16, 7, 256, 64, raw, 64])?,
+        __WASI_CRYPTO_ESUCCESS
+    );
+    let imported_signature = read_u32(64) as i32;
+    assert_eq!(
+        verify(publickey, 7, imported_signature)?,
+        __WASI_CRYPTO_ESUCCESS
+    );
+
+    // store the key pair, and sign with it from its id
+    assert_eq!(
+        call("secrets_manager_open", &[0, 64])?,
+        __WASI_CRYPTO_ESUCCESS
+    );
+    let secrets_manager = read_u32(64) as i32;
+    assert_eq!(
+        call("keypair_store", &[secrets_manager, keypair, 512, 64])?,
+        __WASI_CRYPTO_ESUCCESS
+    );
+    assert_eq!(
+        call("keypair_id", &[keypair, 512, 64, 64, 72])?,
+        __WASI_CRYPTO_ESUCCESS
+    );
+    let keypair_id_len = read_u32(64) as i32;
+    let keypair_from_id = instance.exports.get_function("keypair_from_id")?.call(&[
+        Val::I32(secrets_manager),
+        Val::I32(512),
+        Val::I32(keypair_id_len),
+        Val::I64(0),
+        Val::I32(64),
+    ])?;
+    assert_eq!(
+        keypair_from_id[0].unwrap_i32() as u16,
+        __WASI_CRYPTO_ESUCCESS
+    );
+    let stored_keypair = read_u32(64) as i32;
+    let stored_signature = sign(stored_keypair, 7)?;
+    assert_eq!(
+        verify(publickey, 7, stored_signature)?,
+        __WASI_CRYPTO_ESUCCESS
+    );
+    // the secret key of a stored key pair is never given to the module
+    assert_eq!(
+        call(
+            "keypair_export",
+            &[
+                stored_keypair,
+                i32::from(__WASI_CRYPTO_KEYPAIR_ENCODING_PKCS8),
+                64
+            ]
+        )?,
+        __WASI_CRYPTO_EPROHIBITED_OPERATION
+    );
+    Ok(())
+}